@@ -0,0 +1,28 @@
+//! Build script - embeds the git commit shown by `/status`.
+//!
+//! Uses `GIT_COMMIT` from the environment when set (e.g. in CI or cross builds without
+//! git), otherwise asks git for the short hash of `HEAD`. If neither is available the
+//! commit is reported as unknown.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let commit = std::env::var("GIT_COMMIT").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|hash| hash.trim().to_string())
+            .filter(|hash| !hash.is_empty())
+    });
+
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=GIT_COMMIT={commit}");
+    }
+}
@@ -0,0 +1,195 @@
+//! HTTP endpoint for spends from external sources (`api` feature).
+//!
+//! `POST /api/transactions` takes a JSON [`ExternalSpend`] with an
+//! `Authorization: Bearer <API_TOKEN>` header and records it through
+//! [`ingest::record_external_spend`]. Each recorded spend is noted in its envelope's
+//! thread and, if `API_RELAY_CHANNEL_ID` is set, confirmed in that channel. See
+//! [`crate::config::api`] for the settings.
+
+use crate::{
+    bot::notifications,
+    config::{api::ApiConfig, users},
+    core::{
+        ingest::{self, ExternalSpend, RecordedSpend},
+        setup,
+    },
+    errors::{Error, Result},
+};
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    routing::post,
+};
+use poise::serenity_prelude as serenity;
+use sea_orm::DatabaseConnection;
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+/// State shared by the endpoint's requests.
+struct ApiState {
+    config: ApiConfig,
+    db: DatabaseConnection,
+    http: Arc<serenity::Http>,
+}
+
+/// Serves the endpoint until the listener fails.
+///
+/// # Errors
+/// Returns an error if the address can't be bound or the server stops with an error.
+pub async fn serve(
+    config: ApiConfig,
+    db: DatabaseConnection,
+    http: Arc<serenity::Http>,
+) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(config.bind)
+        .await
+        .map_err(|e| Error::Config {
+            message: format!("Failed to bind API endpoint to {}: {e}", config.bind),
+        })?;
+    tracing::info!(
+        "Accepting transactions at http://{}/api/transactions",
+        config.bind
+    );
+
+    let state = Arc::new(ApiState { config, db, http });
+    let app = Router::new()
+        .route("/api/transactions", post(create_transaction))
+        .with_state(state);
+    axum::serve(listener, app).await.map_err(|e| Error::Config {
+        message: format!("API endpoint error: {e}"),
+    })
+}
+
+/// Handles `POST /api/transactions`.
+async fn create_transaction(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Json(spend): Json<ExternalSpend>,
+) -> (StatusCode, Json<Value>) {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if !ingest::token_matches(&state.config.token, token.trim()) {
+        return error_response(StatusCode::UNAUTHORIZED, "Invalid or missing token");
+    }
+
+    let Some(user_id) = resolve_user(
+        spend
+            .user
+            .as_deref()
+            .or(state.config.default_user.as_deref()),
+    ) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Unknown user; set `user` to a nickname or Discord user ID",
+        );
+    };
+
+    match ingest::record_external_spend(&state.db, &spend, &user_id).await {
+        Ok(recorded) => {
+            tracing::info!(
+                "API spend: user {} spent {:.2} from '{}' (transaction {})",
+                user_id,
+                recorded.transaction.amount.abs(),
+                recorded.envelope.name,
+                recorded.transaction.id
+            );
+            relay(&state, &recorded).await;
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "transaction_id": recorded.transaction.id,
+                    "envelope": recorded.envelope.name,
+                    "amount": recorded.transaction.amount.abs(),
+                    "description": recorded.transaction.description,
+                    "balance": recorded.envelope.balance,
+                })),
+            )
+        }
+        Err(e) => {
+            let status = status_for(&e);
+            if status == StatusCode::INTERNAL_SERVER_ERROR {
+                tracing::error!("Failed to record API spend: {e}");
+                return error_response(status, "Failed to record the spend");
+            }
+            error_response(status, &e.to_string())
+        }
+    }
+}
+
+/// Posts the spend note to the envelope's thread and the confirmation to the relay
+/// channel.
+///
+/// Failures are logged rather than returned, since the spend is already recorded.
+async fn relay(state: &ApiState, recorded: &RecordedSpend) {
+    notifications::post_spend_notes_in(
+        &state.http,
+        &state.db,
+        std::slice::from_ref(&recorded.transaction),
+    )
+    .await;
+
+    let Some(channel_id) = state.config.relay_channel_id else {
+        return;
+    };
+    let spend = &recorded.transaction;
+    let currency = match setup::get_currency_format(&state.db).await {
+        Ok(currency) => currency,
+        Err(e) => {
+            tracing::warn!("Failed to read the currency: {e}");
+            setup::Currency::default()
+        }
+    };
+    let content = format!(
+        "📲 <@{}> Spent {} from envelope '{}' - {} (Transaction ID: {}). Balance: {}",
+        spend.user_id,
+        currency.format(spend.amount.abs()),
+        recorded.envelope.name,
+        spend.description,
+        spend.id,
+        currency.format(recorded.envelope.balance)
+    );
+    let message = serenity::CreateMessage::new()
+        .content(content)
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+    if let Err(e) = serenity::ChannelId::new(channel_id)
+        .send_message(&state.http, message)
+        .await
+    {
+        tracing::warn!("Failed to relay API spend {}: {e}", spend.id);
+    }
+}
+
+/// Resolves a nickname or numeric Discord user ID.
+fn resolve_user(user: Option<&str>) -> Option<String> {
+    let user = user?.trim();
+    users::resolve_nickname(user).or_else(|| {
+        user.parse::<u64>()
+            .is_ok_and(|id| id > 0)
+            .then(|| user.to_string())
+    })
+}
+
+/// The HTTP status for an error from [`ingest::record_external_spend`].
+const fn status_for(error: &Error) -> StatusCode {
+    match error {
+        Error::InvalidAmount { .. } | Error::Config { .. } => StatusCode::BAD_REQUEST,
+        Error::EnvelopeNotFound { .. } => StatusCode::NOT_FOUND,
+        Error::SpendNotPermitted { .. } => StatusCode::FORBIDDEN,
+        Error::InsufficientFunds { .. }
+        | Error::BelowMinimumBalance { .. }
+        | Error::EnvelopeFrozen { .. }
+        | Error::ApprovalRequired { .. }
+        | Error::DailyLimitExceeded { .. }
+        | Error::CategoryCapExceeded { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// A JSON error body with `status`.
+fn error_response(status: StatusCode, message: &str) -> (StatusCode, Json<Value>) {
+    (status, Json(json!({ "error": message })))
+}
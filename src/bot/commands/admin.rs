@@ -0,0 +1,657 @@
+//! Admin Discord commands - maintenance operations restricted to server administrators.
+//!
+//! This module contains commands that change bot-wide configuration at runtime, such as
+//! re-applying config.toml without restarting the bot, choosing the day budget months
+//! start on, how long transactions are kept, or the report's pace thresholds, `/config`
+//! for the runtime settings in one place, `/alias` for prefix command aliases,
+//! diagnostics for remote installs, `/reconcile` for balances that drifted from their
+//! history, and `/audit` for records that reference missing data.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::{
+            self, BotData,
+            handlers::{self, autocomplete},
+        },
+        config,
+        core::{
+            alias, audit, calendar, config_sync, cooldown, diagnostics, envelope, reconcile,
+            report, retention, settings, setup,
+        },
+        errors::{Error, Result},
+    };
+    use std::fmt::Write;
+
+    /// Most discrepancies listed by `/reconcile`, keeping the reply under Discord's limit.
+    const MAX_LISTED_DISCREPANCIES: usize = 15;
+
+    /// Re-reads config.toml and applies envelope additions and setting changes.
+    ///
+    /// Shared envelopes missing from the database are created, and existing envelopes
+    /// have their category, allocation, rollover, and sinking-fund settings updated to
+    /// match the file. Nicknames saved through `/setup` are reloaded as well.
+    #[poise::command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
+    pub async fn reload_config(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+
+        ctx.defer().await?;
+
+        let config = match config::envelopes::load_default_config() {
+            Ok(config) => config,
+            Err(e) => {
+                ctx.say(format!("❌ Could not load config.toml: {e}"))
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let result = config_sync::sync_envelopes(db, &config).await?;
+
+        // User settings stored in the database
+        let nicknames = setup::get_saved_nicknames(db).await?;
+        config::users::register_nicknames(nicknames);
+
+        let summary = config_sync::format_sync_summary(&result)?;
+        ctx.say(format!("🔄 **Config Reloaded**\n\n```\n{summary}\n```"))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Shows bot health: version, uptime, database stats, and background job runs.
+    ///
+    /// Includes the database file size, row counts per table, the last monthly update,
+    /// and when each background job last ran. The reply is only visible to the caller.
+    #[poise::command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
+    pub async fn status(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let database_url = config::database::get_database_url()?;
+
+        let report = diagnostics::collect(
+            db,
+            &database_url,
+            ctx.data().started_at,
+            chrono::Utc::now(),
+            ctx.data().cache.stats(),
+        )
+        .await?;
+        let summary = diagnostics::format_diagnostics(&report)?;
+
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!("🩺 **EnvelopeBuddy Status**\n\n{summary}"))
+                .ephemeral(true),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Recomputes every envelope's balance from its history and fixes the drifted ones.
+    ///
+    /// Balances are worked forward from each envelope's latest monthly statement and the
+    /// transactions since. Discrepancies are listed first, and nothing changes until the
+    /// caller presses "Fix balances". Attempts are rate limited per user with a counter
+    /// that survives restarts.
+    #[poise::command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
+    pub async fn reconcile(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+
+        if let Some(cooldown_duration) = ctx.data().cooldowns.reconcile {
+            let author_id = ctx.author().id.to_string();
+            if let cooldown::AttemptOutcome::Limited {
+                remaining,
+                attempts,
+            } = cooldown::record_attempt(db, "reconcile", &author_id, cooldown_duration).await?
+            {
+                tracing::warn!(
+                    "User {author_id} hit the /reconcile cooldown ({attempts} attempts)"
+                );
+                ctx.send(
+                    poise::CreateReply::default()
+                        .content(bot::cooldown_message("reconcile", remaining))
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        ctx.defer_ephemeral().await?;
+
+        let report = reconcile::check_balances(db).await?;
+        let skipped_note = if report.skipped.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "\nSkipped (no statement yet): {}",
+                report.skipped.join(", ")
+            )
+        };
+        if report.discrepancies.is_empty() {
+            ctx.say(format!(
+                "✅ All {} checked balances match their history.{skipped_note}",
+                report.checked
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let mut summary = format!(
+            "⚖️ **{} of {} balances drifted:**\n",
+            report.discrepancies.len(),
+            report.checked
+        );
+        let currency = setup::get_currency_format(db).await?;
+        for discrepancy in report.discrepancies.iter().take(MAX_LISTED_DISCREPANCIES) {
+            writeln!(
+                &mut summary,
+                "• **{}**: stored {}, expected {} ({}; from {} + {} transaction(s))",
+                discrepancy.envelope.name,
+                currency.format(discrepancy.envelope.balance),
+                currency.format(discrepancy.expected),
+                currency.format_signed(discrepancy.difference()),
+                discrepancy.statement_month,
+                discrepancy.transaction_count
+            )?;
+        }
+        if let Some(more) = report
+            .discrepancies
+            .len()
+            .checked_sub(MAX_LISTED_DISCREPANCIES)
+            .filter(|more| *more > 0)
+        {
+            writeln!(&mut summary, "…and {more} more")?;
+        }
+        summary.push_str(&skipped_note);
+
+        if !handlers::reconcile::confirm_fix(ctx, &summary).await? {
+            return Ok(());
+        }
+
+        // Recomputed again inside the fix, so balances that changed meanwhile are right
+        let fixed = reconcile::fix_balances(db).await?;
+        ctx.say(format!(
+            "✅ Fixed {} balance(s) to match their history.",
+            fixed.discrepancies.len()
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Checks the database for orphaned records and drifted balances.
+    ///
+    /// Lists transactions whose envelope is gone, products linked to deleted envelopes,
+    /// individual templates holding money, and balances that drifted from their history.
+    /// With `fix`, products linked to deleted envelopes are retired; the other findings
+    /// need an admin's judgement and are only reported.
+    #[poise::command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
+    pub async fn audit(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Retire products linked to deleted envelopes"] fix: Option<bool>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        ctx.defer_ephemeral().await?;
+
+        let report = audit::run_audit(db).await?;
+        let mut response = format!(
+            "🔍 **Integrity Audit**\n\n{}",
+            audit::format_report(&report, setup::get_currency_format(db).await?)?
+        );
+        if fix.unwrap_or(false) {
+            let retired = audit::fix_safe_findings(db, &report).await?;
+            write!(&mut response, "\n\n🧹 Retired {retired} product(s).")?;
+        } else if !report.orphaned_products.is_empty() {
+            response.push_str("\n\nRun `/audit fix:true` to retire the products.");
+        }
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Shows or sets the day of the month budget months start on (e.g. payday).
+    ///
+    /// Affects when the monthly update runs, the "this month" and "last month" report
+    /// periods, and the pace shown in `/report`. Days run from 1 to 28 so every month
+    /// contains the start day.
+    #[poise::command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
+    pub async fn budget_cycle(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Day of the month the budget month starts on (1-28)"]
+        #[min = 1]
+        #[max = 28]
+        start_day: Option<u32>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let today = calendar::today(db).await?;
+
+        if let Some(day) = start_day {
+            match calendar::set_cycle_start_day(db, day).await {
+                Ok(()) => {}
+                Err(Error::Config { message }) => {
+                    ctx.say(&format!("❌ {message}")).await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let cycle = calendar::current_cycle(db, today).await?;
+        let verb = if start_day.is_some() {
+            "✅ Budget months now start"
+        } else {
+            "📅 Budget months start"
+        };
+        ctx.say(&format!(
+            "{verb} on day {} of the month. The current budget month runs {} to {} (day {} of {}).",
+            cycle.start.format("%-d"),
+            cycle.start.format("%Y-%m-%d"),
+            cycle.end.format("%Y-%m-%d"),
+            cycle.day_number(today),
+            cycle.days()
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Shows or sets how many months of raw transactions are kept.
+    ///
+    /// At each monthly update, transactions older than the current budget month plus
+    /// `months` previous months are summarized into monthly statements (see `/statement`)
+    /// and deleted. Without a retention period, history is kept forever.
+    #[poise::command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
+    pub async fn retention(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Closed budget months of transactions to keep (0 keeps everything)"]
+        #[min = 0]
+        #[max = 120]
+        months: Option<u32>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        if let Some(months) = months {
+            match retention::set_retention_months(db, Some(months).filter(|m| *m > 0)).await {
+                Ok(()) => {}
+                Err(Error::Config { message }) => {
+                    ctx.say(&format!("❌ {message}")).await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let prefix = if months.is_some() { "✅" } else { "🗄️" };
+        let message = match retention::get_retention_months(db).await? {
+            Some(months) => {
+                let today = calendar::today(db).await?;
+                let cycle = calendar::current_cycle(db, today).await?;
+                format!(
+                    "{prefix} Keeping {months} month(s) of transactions besides the current one. \
+                     At the next monthly update, transactions before {} will be summarized \
+                     into statements and deleted.",
+                    retention::retention_cutoff(&cycle.next(), months).format("%Y-%m-%d")
+                )
+            }
+            None => format!("{prefix} Transactions are kept forever."),
+        };
+        ctx.say(message).await?;
+
+        Ok(())
+    }
+
+    /// Shows or sets the thresholds for the 🟢/🟡/🔴 pace status in `/report`.
+    ///
+    /// Thresholds are percentage points over the expected pace: with the defaults (0 and
+    /// 20), an envelope is 🟢 up to the expected pace, 🟡 up to 20 points over it, and 🔴
+    /// beyond. Naming an envelope overrides the thresholds for that envelope only, and
+    /// `never_warn:true` keeps it 🟢 however it's spent (for bills like rent paid in one
+    /// go); `reset:true` goes back to the defaults (or the household thresholds).
+    #[poise::command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
+    pub async fn pace_thresholds(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Points over the expected pace still shown as 🟢"] on_track: Option<f64>,
+        #[description = "Points over the expected pace still shown as 🟡"] slightly_over: Option<
+            f64,
+        >,
+        #[description = "Envelope to override (leave empty for the whole household)"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope_name: Option<String>,
+        #[description = "Never warn about this envelope's pace (needs an envelope)"]
+        never_warn: Option<bool>,
+        #[description = "Remove the saved thresholds"] reset: Option<bool>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        if never_warn == Some(true) && envelope_name.is_none() {
+            ctx.say("❌ `never_warn` applies to one envelope; name it with `envelope_name`.")
+                .await?;
+            return Ok(());
+        }
+
+        // Try to find the envelope - first check user's individual envelope, then shared
+        let envelope = match envelope_name.as_deref() {
+            Some(name) => {
+                let Some(envelope) = envelope::find_envelope_for_user(db, name, &author_id).await?
+                else {
+                    ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
+                    return Ok(());
+                };
+                Some(envelope)
+            }
+            None => None,
+        };
+        let envelope_id = envelope.as_ref().map(|env| env.id);
+
+        let settings = report::get_pace_settings(db).await?;
+        let current = envelope_id
+            .map(|id| settings.for_envelope(id))
+            .filter(|thresholds| !thresholds.is_never())
+            .unwrap_or(settings.household);
+        let updated = if reset == Some(true) {
+            report::set_pace_thresholds(db, envelope_id, None).await?;
+            true
+        } else if never_warn == Some(true) {
+            let never = Some(report::PaceThresholds::NEVER);
+            report::set_pace_thresholds(db, envelope_id, never).await?;
+            true
+        } else if on_track.is_some() || slightly_over.is_some() {
+            let thresholds = match report::PaceThresholds::new(
+                on_track.unwrap_or(current.on_track),
+                slightly_over.unwrap_or(current.slightly_over),
+            ) {
+                Ok(thresholds) => thresholds,
+                Err(Error::Config { message }) => {
+                    ctx.say(&format!("❌ {message}")).await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            report::set_pace_thresholds(db, envelope_id, Some(thresholds)).await?;
+            true
+        } else {
+            false
+        };
+
+        let settings = report::get_pace_settings(db).await?;
+        let (scope, thresholds) = envelope.as_ref().map_or_else(
+            || ("the household".to_string(), settings.household),
+            |env| (format!("'{}'", env.name), settings.for_envelope(env.id)),
+        );
+        let prefix = if updated { "✅" } else { "🚦" };
+        if thresholds.is_never() {
+            ctx.say(&format!(
+                "{prefix} {scope} never warns about its pace: it always shows 🟢."
+            ))
+            .await?;
+            return Ok(());
+        }
+        ctx.say(&format!(
+            "{prefix} Pace thresholds for {scope}: 🟢 up to +{}%, 🟡 up to +{}%, 🔴 beyond \
+             (points over the expected pace).",
+            thresholds.on_track, thresholds.slightly_over
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Parent command for viewing and changing runtime settings.
+    #[poise::command(
+        slash_command,
+        rename = "config",
+        guild_only,
+        required_permissions = "ADMINISTRATOR",
+        subcommands("config_get", "config_set")
+    )]
+    pub async fn bot_config(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Config command. Available subcommands:\n\
+            `/config get [key]` - Show one setting, or all of them\n\
+            `/config set <key> <value>` - Change a setting";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Shows a runtime setting, or all of them.
+    #[poise::command(
+        slash_command,
+        rename = "get",
+        guild_only,
+        required_permissions = "ADMINISTRATOR"
+    )]
+    pub async fn config_get(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Setting to show (leave empty for all)"]
+        #[autocomplete = "autocomplete::autocomplete_setting_key"]
+        key: Option<String>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let selected = match key.as_deref() {
+            Some(key) => {
+                let Some(setting) = settings::Setting::from_key(key) else {
+                    ctx.say(&unknown_setting(key)).await?;
+                    return Ok(());
+                };
+                vec![setting]
+            }
+            None => settings::Setting::ALL.to_vec(),
+        };
+
+        let mut response = String::from("⚙️ **Settings**\n");
+        for setting in selected {
+            let value = settings::get_setting(db, setting)
+                .await?
+                .unwrap_or_else(|| "not set".to_string());
+            writeln!(
+                &mut response,
+                "• `{}` = **{value}** ({})",
+                setting.key(),
+                setting.expected()
+            )?;
+        }
+        ctx.say(response).await?;
+
+        Ok(())
+    }
+
+    /// Changes a runtime setting, checking the value first.
+    #[poise::command(
+        slash_command,
+        rename = "set",
+        guild_only,
+        required_permissions = "ADMINISTRATOR"
+    )]
+    pub async fn config_set(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Setting to change"]
+        #[autocomplete = "autocomplete::autocomplete_setting_key"]
+        key: String,
+        #[description = "New value"] value: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let Some(setting) = settings::Setting::from_key(&key) else {
+            ctx.say(&unknown_setting(&key)).await?;
+            return Ok(());
+        };
+
+        match settings::set_setting(db, setting, &value).await {
+            Ok(()) => {}
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        }
+
+        let value = settings::get_setting(db, setting).await?;
+        let mut reply = format!(
+            "✅ `{}` is now **{}**.",
+            setting.key(),
+            value.as_deref().unwrap_or("not set")
+        );
+        // The Server Members intent is only requested at startup when a role is set
+        if setting == settings::Setting::BudgetRole && value.is_some() && !ctx.data().members_intent
+        {
+            reply.push_str(
+                "\n⚠️ Restart the bot to start enrolling members given the role; it needs the \
+                 Server Members Intent enabled in the Discord Developer Portal.",
+            );
+        }
+        ctx.say(&reply).await?;
+
+        Ok(())
+    }
+
+    /// Error message for a key that isn't a setting, listing the valid keys.
+    fn unknown_setting(key: &str) -> String {
+        let keys: Vec<&str> = settings::Setting::ALL
+            .iter()
+            .map(|setting| setting.key())
+            .collect();
+        format!("❌ Unknown setting '{key}'. Settings: {}", keys.join(", "))
+    }
+
+    /// Parent command for the server's prefix command aliases.
+    #[poise::command(
+        slash_command,
+        guild_only,
+        subcommands("alias_set", "alias_remove", "alias_list")
+    )]
+    pub async fn alias(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Alias command. Available subcommands:\n\
+            `/alias set <alias> <command>` - Add or change an alias (admin)\n\
+            `/alias remove <alias>` - Remove an alias (admin)\n\
+            `/alias list` - Show the aliases this server can use";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Adds or changes a prefix command alias for this server.
+    #[poise::command(
+        slash_command,
+        rename = "set",
+        guild_only,
+        required_permissions = "ADMINISTRATOR"
+    )]
+    pub async fn alias_set(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Short name to type after the prefix (e.g. cof)"] alias: String,
+        #[description = "Command it runs (e.g. favorite_use)"] command: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let Some(guild_id) = ctx.guild_id() else {
+            return Ok(());
+        };
+        let commands = &ctx.framework().options().commands;
+
+        let target = command.trim().trim_start_matches('/').to_lowercase();
+        let Some((target_command, _, _)) =
+            poise::find_command(commands, &target, true, &mut Vec::new())
+        else {
+            ctx.say(&format!("❌ There is no `{target}` command."))
+                .await?;
+            return Ok(());
+        };
+        if target_command.prefix_action.is_none() {
+            ctx.say(&format!(
+                "❌ `{}` is slash-only and can't be run with a prefix alias.",
+                target_command.qualified_name
+            ))
+            .await?;
+            return Ok(());
+        }
+        if poise::find_command(commands, alias.trim(), true, &mut Vec::new()).is_some() {
+            ctx.say(&format!("❌ `{}` is already a command name.", alias.trim()))
+                .await?;
+            return Ok(());
+        }
+
+        let target = target_command.qualified_name.clone();
+        let alias = match alias::set_alias(db, &guild_id.to_string(), &alias, &target).await {
+            Ok(alias) => alias,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let prefix = command_prefix(ctx);
+        ctx.say(&format!(
+            "✅ `{prefix}{alias}` now runs `{prefix}{target}`."
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes a prefix command alias from this server.
+    #[poise::command(
+        slash_command,
+        rename = "remove",
+        guild_only,
+        required_permissions = "ADMINISTRATOR"
+    )]
+    pub async fn alias_remove(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Alias to remove"] alias: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let Some(guild_id) = ctx.guild_id() else {
+            return Ok(());
+        };
+
+        if alias::remove_alias(db, &guild_id.to_string(), &alias).await? {
+            ctx.say(&format!("✅ Removed alias `{}`.", alias.trim()))
+                .await?;
+        } else {
+            ctx.say(&format!("❌ There is no `{}` alias.", alias.trim()))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists the built-in and server-specific prefix command aliases.
+    #[poise::command(slash_command, rename = "list", guild_only)]
+    pub async fn alias_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let Some(guild_id) = ctx.guild_id() else {
+            return Ok(());
+        };
+        let prefix = command_prefix(ctx);
+
+        let mut response = String::from("⌨️ **Command aliases**\n");
+        for (alias, command) in alias::BUILTIN_ALIASES {
+            writeln!(
+                &mut response,
+                "• `{prefix}{alias}` → `{prefix}{command}` (built-in)"
+            )?;
+        }
+        for (alias, command) in alias::get_aliases(db, &guild_id.to_string()).await? {
+            writeln!(&mut response, "• `{prefix}{alias}` → `{prefix}{command}`")?;
+        }
+
+        ctx.say(&response).await?;
+        Ok(())
+    }
+
+    /// The prefix text commands are typed with.
+    fn command_prefix(ctx: poise::Context<'_, BotData, Error>) -> String {
+        ctx.framework()
+            .options()
+            .prefix_options
+            .prefix
+            .clone()
+            .unwrap_or_default()
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
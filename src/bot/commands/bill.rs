@@ -0,0 +1,160 @@
+//! Bill Discord commands - `/bill` subcommands for recurring monthly bills.
+//!
+//! `/bill add` registers a bill due on the same day every month. The background scheduler
+//! posts a reminder a few days before each due date, and a spend from the bill's envelope
+//! that matches it marks the due date paid.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::{BotData, handlers::autocomplete},
+        core::{bill, calendar, envelope, setup},
+        errors::{Error, Result},
+    };
+    use std::fmt::Write;
+
+    /// Parent command for recurring bills.
+    #[poise::command(slash_command, subcommands("bill_add", "bill_list", "bill_remove"))]
+    pub async fn bill(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Bill command. Available subcommands:\n\
+            `/bill add` - Add a monthly bill with reminders before it's due\n\
+            `/bill list` - Show bills, their next due dates, and whether they're paid\n\
+            `/bill remove` - Remove a bill";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Adds a recurring monthly bill paid from an envelope.
+    ///
+    /// A reminder is posted `remind_days` before each due date. With `event`, each
+    /// reminder also creates a Discord scheduled event on the due date.
+    #[poise::command(slash_command, rename = "add")]
+    #[allow(clippy::too_many_arguments)] // Each slash command option is a function parameter
+    pub async fn bill_add(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the bill (e.g. 'Rent')"] name: String,
+        #[description = "Amount due each month"] amount: f64,
+        #[description = "Day of the month it's due (1-31; short months use their last day)"]
+        due_day: i32,
+        #[description = "Envelope the bill is paid from"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope: String,
+        #[description = "Days before the due date to post a reminder (default: 3)"]
+        remind_days: Option<i32>,
+        #[description = "Also create a Discord scheduled event on each due date"] event: Option<
+            bool,
+        >,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+
+        let Some(found) = envelope::find_envelope_for_user(db, &envelope, &author_id).await? else {
+            ctx.say(&format!(
+                "❌ Envelope '{envelope}' not found. Use `/envelopes` to see available envelopes.",
+            ))
+            .await?;
+            return Ok(());
+        };
+
+        let create_event = event.unwrap_or(false);
+        let new_bill = bill::NewBill {
+            name,
+            amount,
+            due_day,
+            envelope_id: found.id,
+            user_id: author_id,
+            channel_id: Some(ctx.channel_id().to_string()),
+            guild_id: ctx.guild_id().map(|id| id.to_string()),
+            remind_days: remind_days.unwrap_or(bill::DEFAULT_REMIND_DAYS),
+            create_event,
+        };
+        match bill::add_bill(db, new_bill).await {
+            Ok(added) => {
+                let today = calendar::today(db).await?;
+                let event_note = match (create_event, added.guild_id.is_some()) {
+                    (true, true) => " and a scheduled event",
+                    (true, false) => " (scheduled events need a server channel)",
+                    _ => "",
+                };
+                ctx.say(&format!(
+                    "🧾 Added bill **{}**: {} from '{}' on day {} of each month \
+                     (next due {}). You'll get a reminder {} day(s) before{event_note}.",
+                    added.name,
+                    setup::get_currency_format(db).await?.format(added.amount),
+                    found.name,
+                    added.due_day,
+                    bill::due_date_on_or_after(added.due_day, today),
+                    added.remind_days
+                ))
+                .await?;
+            }
+            Err(Error::InvalidAmount { .. }) => {
+                ctx.say("❌ Invalid amount: must be a number greater than zero")
+                    .await?;
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    /// Lists bills with their next due dates and payment status.
+    #[poise::command(slash_command, rename = "list")]
+    pub async fn bill_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let bills = bill::get_bills(db).await?;
+
+        if bills.is_empty() {
+            ctx.say("ℹ️ No bills yet. Add one with `/bill add`.")
+                .await?;
+            return Ok(());
+        }
+
+        let today = calendar::today(db).await?;
+        let currency = setup::get_currency_format(db).await?;
+        let mut response = String::from("🧾 **Bills:**\n");
+        for bill in &bills {
+            let envelope_name = envelope::get_envelope_by_id(db, bill.envelope_id)
+                .await?
+                .map_or_else(|| "unknown".to_string(), |env| env.name);
+            let due_on = bill::due_date_on_or_after(bill.due_day, today);
+            let status = if bill.paid_for.is_some_and(|paid| paid >= due_on) {
+                "✅ paid"
+            } else {
+                "⏳ unpaid"
+            };
+            writeln!(
+                &mut response,
+                "• **{}** - {} from '{envelope_name}', next due {due_on} ({status})",
+                bill.name,
+                currency.format(bill.amount)
+            )?;
+        }
+
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Removes a bill.
+    #[poise::command(slash_command, rename = "remove")]
+    pub async fn bill_remove(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the bill"] name: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        if bill::remove_bill(db, &name).await? {
+            ctx.say(&format!("🗑️ Removed bill '{name}'.")).await?;
+        } else {
+            ctx.say(&format!("❌ Bill '{name}' not found.")).await?;
+        }
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
@@ -0,0 +1,227 @@
+//! Bundle Discord commands - `/bundle` subcommands for managing product bundles.
+//!
+//! A bundle groups products with quantities (e.g. "Taco Night" = 1x tortillas + 2x
+//! ground beef + 1x salsa). Bundles are used with `/use_product bundle:<name>`, which
+//! logs every component in one go.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::{BotData, handlers::autocomplete},
+        core::{bundle, product, report, setup},
+        errors::{Error, Result},
+    };
+    use poise::serenity_prelude as serenity;
+    use std::fmt::Write;
+
+    /// Parent command for managing product bundles.
+    #[poise::command(
+        slash_command,
+        subcommands(
+            "bundle_create",
+            "bundle_add",
+            "bundle_remove",
+            "bundle_list",
+            "bundle_delete"
+        )
+    )]
+    pub async fn bundle(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Bundle command. Available subcommands:\n\
+            `/bundle create` - Create an empty bundle\n\
+            `/bundle add` - Add a product to a bundle, or change its quantity\n\
+            `/bundle remove` - Remove a product from a bundle\n\
+            `/bundle list` - Show all bundles and their products\n\
+            `/bundle delete` - Delete a bundle\n\
+            Use a bundle with `/use_product bundle:<name>`.";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Creates an empty bundle; add products to it with `/bundle add`.
+    #[poise::command(slash_command, rename = "create")]
+    pub async fn bundle_create(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the bundle (e.g., Taco Night)"] name: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        match bundle::create_bundle(db, &name).await {
+            Ok(created) => {
+                ctx.say(&format!(
+                    "✅ Bundle '{}' created. Add products with `/bundle add`.",
+                    created.name
+                ))
+                .await?;
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    /// Adds a product to a bundle, or changes its quantity if it's already there.
+    #[poise::command(slash_command, rename = "add")]
+    pub async fn bundle_add(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the bundle"]
+        #[autocomplete = "autocomplete::autocomplete_bundle_name"]
+        bundle_name: String,
+        #[description = "Product to include"]
+        #[autocomplete = "autocomplete::autocomplete_product_name"]
+        product_name: String,
+        #[description = "Units of the product per use (defaults to 1)"] quantity: Option<i64>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let quantity = quantity.unwrap_or(1);
+
+        let Some(found) = bundle::get_bundle_by_name(db, &bundle_name).await? else {
+            ctx.say(&format!("❌ Bundle '{bundle_name}' not found."))
+                .await?;
+            return Ok(());
+        };
+        let Some(prod) = product::get_product_by_name(db, &product_name).await? else {
+            ctx.say(&format!("❌ Product '{product_name}' not found."))
+                .await?;
+            return Ok(());
+        };
+
+        match bundle::set_bundle_item(db, found.id, prod.id, quantity).await {
+            Ok(_) => {
+                // Cast is safe: for quantities < 2^53, no precision loss occurs in f64
+                #[allow(clippy::cast_precision_loss)]
+                let cost = prod.price * quantity as f64;
+                ctx.say(&format!(
+                    "✅ Bundle '{}' now uses {} x {} ({}).",
+                    found.name,
+                    quantity,
+                    prod.name,
+                    setup::get_currency_format(db).await?.format(cost)
+                ))
+                .await?;
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    /// Removes a product from a bundle.
+    #[poise::command(slash_command, rename = "remove")]
+    pub async fn bundle_remove(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the bundle"]
+        #[autocomplete = "autocomplete::autocomplete_bundle_name"]
+        bundle_name: String,
+        #[description = "Product to remove"]
+        #[autocomplete = "autocomplete::autocomplete_product_name"]
+        product_name: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let Some(found) = bundle::get_bundle_by_name(db, &bundle_name).await? else {
+            ctx.say(&format!("❌ Bundle '{bundle_name}' not found."))
+                .await?;
+            return Ok(());
+        };
+
+        // Match by name among the bundle's items so deleted products can still be removed
+        let components = bundle::get_bundle_components(db, found.id).await?;
+        let Some((_, prod)) = components
+            .iter()
+            .find(|(_, prod)| prod.name.eq_ignore_ascii_case(&product_name))
+        else {
+            ctx.say(&format!(
+                "❌ Bundle '{}' doesn't include '{product_name}'.",
+                found.name
+            ))
+            .await?;
+            return Ok(());
+        };
+
+        bundle::remove_bundle_item(db, found.id, prod.id).await?;
+        ctx.say(&format!(
+            "✅ Removed '{}' from bundle '{}'.",
+            prod.name, found.name
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Lists all bundles with their products and total cost.
+    #[poise::command(slash_command, rename = "list")]
+    pub async fn bundle_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let bundles = bundle::get_all_bundles(db).await?;
+
+        if bundles.is_empty() {
+            ctx.say("No bundles have been defined yet. Use `/bundle create` to make one!")
+                .await?;
+            return Ok(());
+        }
+
+        let currency = setup::get_currency_format(db).await?;
+        let mut embed_fields = Vec::new();
+        for found in bundles {
+            let components = bundle::get_bundle_components(db, found.id).await?;
+            let mut contents = String::new();
+            for (item, prod) in &components {
+                let note = if prod.is_deleted { " (deleted)" } else { "" };
+                writeln!(&mut contents, "{} x {}{note}", item.quantity, prod.name)?;
+            }
+            if contents.is_empty() {
+                contents.push_str("No products yet");
+            }
+
+            let field_name = format!(
+                "{} ({})",
+                found.name,
+                currency.format(bundle::bundle_cost(&components))
+            );
+            embed_fields.push((field_name, contents, false));
+        }
+
+        let list_embed = serenity::CreateEmbed::default()
+            .title("**Bundle List**")
+            .color(
+                report::get_theme(db)
+                    .await?
+                    .color(report::EmbedColor::Accent),
+            )
+            .fields(embed_fields);
+
+        ctx.send(poise::CreateReply::default().embed(list_embed))
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes a bundle. Spends already logged with it are kept.
+    #[poise::command(slash_command, rename = "delete")]
+    pub async fn bundle_delete(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the bundle to delete"]
+        #[autocomplete = "autocomplete::autocomplete_bundle_name"]
+        name: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let Some(found) = bundle::get_bundle_by_name(db, &name).await? else {
+            ctx.say(&format!("❌ Bundle '{name}' not found.")).await?;
+            return Ok(());
+        };
+
+        bundle::delete_bundle(db, found.id).await?;
+        ctx.say(&format!("✅ Bundle '{}' has been deleted.", found.name))
+            .await?;
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
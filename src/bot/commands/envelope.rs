@@ -7,153 +7,262 @@
 mod inner {
     #![allow(missing_docs)]
 
+    #[cfg(feature = "charts")]
+    use crate::core::charts;
+    #[cfg(feature = "pdf")]
+    use crate::core::pdf;
     use crate::{
-        bot::{BotData, handlers::autocomplete},
+        bot::{
+            self, BotData,
+            handlers::{alerts, allocation_editor, autocomplete, envelope_pages, monthly_review},
+            notifications, permissions, remember_undo, render,
+        },
         config,
-        core::{envelope, monthly, report},
+        core::{
+            allocation,
+            cache::{self, CachedTable},
+            calendar, category_cap, config_sync, cooldown, envelope, envelope_permission, monthly,
+            notify::{self, Notification},
+            product, report, setup, snapshot, statement, table, transaction,
+            undo::UndoAction,
+        },
         errors::{Error, Result},
     };
-    use chrono::Datelike;
+    use poise::serenity_prelude as serenity;
     use sea_orm::ActiveModelTrait;
     use std::fmt::Write;
 
+    /// Parent command for envelope reports.
+    ///
+    /// Invoked without a subcommand as a prefix command (`!report`), shows the summary for
+    /// the current month like `/report summary`.
+    #[poise::command(
+        slash_command,
+        prefix_command,
+        subcommands(
+            "report_summary",
+            "report_trend",
+            "report_category",
+            "report_merchants",
+            "report_stale",
+            "report_variance"
+        )
+    )]
+    pub async fn report(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        summary_report(ctx, None, None, None).await
+    }
+
     /// Shows a comprehensive financial report of all active envelopes.
     ///
     /// This command generates a detailed report showing current balances, allocations,
     /// and spending progress for all envelopes in the system. The report includes
     /// visual progress indicators and recent transaction information.
-    #[allow(clippy::too_many_lines)] // Complex reporting logic with status calculations
-    #[poise::command(slash_command, prefix_command)]
-    pub async fn report(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
-        use poise::serenity_prelude as serenity;
+    ///
+    /// With `period`, `from`, or `to`, reports spending over that date window instead.
+    #[poise::command(slash_command, prefix_command, rename = "summary")]
+    pub async fn report_summary(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Report over a preset window instead of the current month"] period: Option<
+            ReportPeriodChoice,
+        >,
+        #[description = "Start date (YYYY-MM-DD, defaults to the start of this budget month)"]
+        from: Option<String>,
+        #[description = "End date (YYYY-MM-DD, defaults to today)"] to: Option<String>,
+    ) -> Result<()> {
+        summary_report(ctx, period, from.as_deref(), to.as_deref()).await
+    }
 
+    /// Replies with the current month's report, or the spending over a date window when
+    /// one is asked for.
+    async fn summary_report(
+        ctx: poise::Context<'_, BotData, Error>,
+        period: Option<ReportPeriodChoice>,
+        from: Option<&str>,
+        to: Option<&str>,
+    ) -> Result<()> {
         let db = &ctx.data().database;
+        let cycle_start_day = calendar::get_cycle_start_day(db).await?;
+
+        let today = calendar::today(db).await?;
+        let range = requested_range(period, from, to, today, cycle_start_day);
+        match range {
+            Some(Ok(range)) => return range_report(ctx, range, today, cycle_start_day).await,
+            Some(Err(Error::Config { message })) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Some(Err(e)) => return Err(e),
+            None => {}
+        }
 
-        // Get all active envelopes
-        let envelopes = envelope::get_all_active_envelopes(db).await?;
-
-        if envelopes.is_empty() {
+        // All figures come from the core model; this command only labels and sends them
+        let model = report::build_report_model(db, &ctx.author().id.to_string(), today).await?;
+        if model.envelopes.is_empty() {
             ctx.say("📊 No envelopes found. Create one with `/create_envelope` to get started!")
                 .await?;
             return Ok(());
         }
-
-        // Get current date info for embed description
-        let now = chrono::Local::now();
-
-        // Calculate days in current month
-        // Note: from_ymd_opt only returns None for invalid dates (e.g., Feb 30).
-        // Since we're using month=1-12 from DateTime and day=1, these are always valid.
-        #[allow(clippy::expect_used)] // First day of any valid month/year is always valid
-        let next_month_first = if now.month() == 12 {
-            chrono::NaiveDate::from_ymd_opt(now.year() + 1, 1, 1)
-        } else {
-            chrono::NaiveDate::from_ymd_opt(now.year(), now.month() + 1, 1)
+        let mut labels = Vec::with_capacity(model.envelopes.len());
+        for line in &model.envelopes {
+            let label = match line {
+                report::EnvelopeLine::Visible(figures) => {
+                    let env = &figures.envelope;
+                    envelope_label(ctx, &env.name, env.is_individual, env.user_id.as_deref()).await
+                }
+                report::EnvelopeLine::Hidden(hidden) => {
+                    envelope_label(ctx, &hidden.name, true, hidden.user_id.as_deref()).await
+                }
+            };
+            labels.push(label);
         }
-        .expect("First day of next month is always valid");
+        let theme = report::get_theme(db).await?;
+        let report_embed = render::report_embed(&model, &labels, theme)?;
+
+        #[allow(unused_mut)] // Only reassigned when the chart is attached
+        let mut reply = poise::CreateReply::default();
+
+        // Attach a spend vs allocation chart; the text report is still sent if rendering fails
+        #[cfg(feature = "charts")]
+        let report_embed = match charts::render_spend_vs_allocation(
+            &render::chart_bars(&model, &labels),
+            model.currency,
+        ) {
+            Ok(png) => {
+                reply = reply.attachment(serenity::CreateAttachment::bytes(
+                    png,
+                    charts::REPORT_CHART_FILENAME,
+                ));
+                report_embed.image(format!("attachment://{}", charts::REPORT_CHART_FILENAME))
+            }
+            Err(e) => {
+                tracing::warn!("Failed to render report chart: {e}");
+                report_embed
+            }
+        };
+
+        ctx.send(reply.embed(report_embed)).await?;
 
-        #[allow(clippy::expect_used)] // First day of current month from DateTime is always valid
-        let current_month_first = chrono::NaiveDate::from_ymd_opt(now.year(), now.month(), 1)
-            .expect("First day of current month is always valid");
+        Ok(())
+    }
 
-        let days_in_month = next_month_first
-            .signed_duration_since(current_month_first)
-            .num_days();
+    /// Preset windows for `/report period`.
+    #[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+    pub enum ReportPeriodChoice {
+        #[name = "this_month"]
+        ThisMonth,
+        #[name = "last_month"]
+        LastMonth,
+        #[name = "ytd"]
+        YearToDate,
+    }
 
-        let current_day = i64::from(now.day());
+    impl From<ReportPeriodChoice> for report::ReportPeriod {
+        fn from(choice: ReportPeriodChoice) -> Self {
+            match choice {
+                ReportPeriodChoice::ThisMonth => Self::ThisMonth,
+                ReportPeriodChoice::LastMonth => Self::LastMonth,
+                ReportPeriodChoice::YearToDate => Self::YearToDate,
+            }
+        }
+    }
 
-        // Build embed fields - one field per envelope
-        let mut embed_fields = Vec::new();
+    /// Replies with spending over a date window, paced against the prorated allocation.
+    async fn range_report(
+        ctx: poise::Context<'_, BotData, Error>,
+        range: report::ReportRange,
+        today: chrono::NaiveDate,
+        cycle_start_day: u32,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let envelopes = report::get_envelope_views(db, &ctx.author().id.to_string()).await?;
+        if envelopes.is_empty() {
+            ctx.say("📊 No envelopes found. Create one with `/create_envelope` to get started!")
+                .await?;
+            return Ok(());
+        }
 
-        for env in &envelopes {
-            let progress = report::calculate_progress(env.balance, env.allocation);
-            let progress_bar = report::format_progress_bar(progress, Some(10));
-
-            // Calculate expected pace based on day of month
-            #[allow(clippy::cast_precision_loss)]
-            // Days in month is small, precision loss negligible
-            let expected_percent = (current_day as f64 / days_in_month as f64) * 100.0;
-            let spent_amount = env.allocation - env.balance;
-            let spent_percent = if env.allocation > 0.0 {
-                (spent_amount / env.allocation) * 100.0
-            } else {
-                0.0
-            };
+        let spending = report::get_spending_in_range(db, &range).await?;
+        let interest = report::get_interest_in_range(db, &range).await?;
+        let pace = report::get_pace_settings(db).await?;
+        let theme = report::get_theme(db).await?;
+        let currency = setup::get_currency_format(db).await?;
+        let days = range.days();
+        let elapsed = range.elapsed_days(today);
+        // Day counts are small, precision loss negligible
+        #[allow(clippy::cast_precision_loss)]
+        let elapsed_fraction = elapsed as f64 / days as f64;
+        let expected_percent = elapsed_fraction * 100.0;
 
-            // Status indicator: green if on track, yellow if slightly over, red if significantly over
-            let status_emoji = if spent_percent <= expected_percent {
-                "🟢" // On track or under budget
-            } else if spent_percent <= expected_percent + 20.0 {
-                "🟡" // Slightly over pace
-            } else {
-                "🔴" // Significantly over pace
+        let mut embed_fields = Vec::new();
+        for view in &envelopes {
+            let env = match view {
+                report::EnvelopeView::Visible(env) => env,
+                report::EnvelopeView::Hidden(hidden) => {
+                    let field_name =
+                        envelope_label(ctx, &hidden.name, true, hidden.user_id.as_deref()).await;
+                    embed_fields.push((
+                        field_name,
+                        format!("🔒 *{}*", report::HIDDEN_LABEL),
+                        false,
+                    ));
+                    continue;
+                }
             };
 
-            // Build field name: "name (User)" or "name (Shared)"
-            let field_name = if env.is_individual {
-                if let Some(ref uid) = env.user_id {
-                    // First try to get nickname from .env config
-                    let user_name = if let Some(nickname) = config::users::get_nickname(uid) {
-                        nickname
-                    } else {
-                        // Fallback to Discord username
-                        if let Ok(user_id_val) = uid.parse::<u64>() {
-                            let user_id = serenity::UserId::new(user_id_val);
-                            if let Ok(user) = user_id.to_user(ctx.serenity_context()).await {
-                                user.name
-                            } else {
-                                format!("User {uid}")
-                            }
-                        } else {
-                            format!("User {uid}")
-                        }
-                    };
-                    format!("{} ({})", env.name, user_name)
-                } else {
-                    format!("{} (Individual)", env.name)
-                }
+            let budget = report::prorated_allocation(env.allocation, &range, cycle_start_day);
+            let spent_amount = spending.get(&env.id).copied().unwrap_or(0.0);
+            let spent_percent = if budget > 0.0 {
+                (spent_amount / budget) * 100.0
             } else {
-                format!("{} (Shared)", env.name)
+                0.0
             };
+            let progress = report::calculate_progress(budget - spent_amount, budget);
 
+            let field_name =
+                envelope_label(ctx, &env.name, env.is_individual, env.user_id.as_deref()).await;
             let mut field_value = String::new();
             writeln!(
                 &mut field_value,
-                "**Balance:** ${:.2} / ${:.2}",
-                env.balance, env.allocation
+                "**Spent:** {} / {} ({spent_percent:.1}%)",
+                currency.format(spent_amount),
+                currency.format(budget)
             )?;
             writeln!(
                 &mut field_value,
-                "**Spent:** ${:.2} ({:.1}%)",
-                spent_amount.abs(),
-                spent_percent.abs()
+                "**Expected Pace:** {} ({expected_percent:.1}%)",
+                currency.format(budget * elapsed_fraction)
             )?;
-            #[allow(clippy::cast_precision_loss)]
-            // Days in month is small, precision loss negligible
-            let expected_spent = env.allocation * (current_day as f64 / days_in_month as f64);
             writeln!(
                 &mut field_value,
-                "**Expected Pace:** ${expected_spent:.2} ({expected_percent:.1}%)"
+                "**Progress:** {} {progress:.1}%",
+                theme.progress_bar(progress, Some(10))
             )?;
-            write!(
+            writeln!(
                 &mut field_value,
-                "**Progress:** {progress_bar} {progress:.1}%"
+                "**Status:** {}",
+                theme.status(
+                    pace.for_envelope(env.id)
+                        .classify(spent_percent, expected_percent)
+                )
             )?;
-            writeln!(&mut field_value, "\n**Status:** {status_emoji}")?;
-
-            embed_fields.push((field_name, field_value, false)); // false = not inline
+            if let Some(earned) = interest.get(&env.id) {
+                writeln!(
+                    &mut field_value,
+                    "**Interest Earned:** {}",
+                    currency.format(*earned)
+                )?;
+            }
+            embed_fields.push((field_name, field_value, false));
         }
 
-        // Create embed
         let report_embed = serenity::CreateEmbed::default()
-            .title("📊 Full Envelope Report")
+            .title("📊 Envelope Report")
             .description(format!(
-                "As of: {} (Day {}/{} of month)",
-                now.format("%Y-%m-%d"),
-                now.day(),
-                days_in_month
+                "{} to {} (Day {elapsed}/{days}). Budgets are prorated monthly allocations.",
+                range.from.format("%Y-%m-%d"),
+                range.to.format("%Y-%m-%d")
             ))
-            .color(0x0034_98DB) // Blue color
+            .color(theme.color(report::EmbedColor::Report))
             .fields(embed_fields)
             .footer(serenity::CreateEmbedFooter::new(format!(
                 "EnvelopeBuddy v0.2.0 | {} envelope{}",
@@ -163,180 +272,961 @@ mod inner {
 
         ctx.send(poise::CreateReply::default().embed(report_embed))
             .await?;
-
         Ok(())
     }
 
-    /// Runs the monthly update process for all envelopes.
-    ///
-    /// This command processes monthly updates for all active envelopes:
-    /// - Rollover envelopes: adds allocation to existing balance
-    /// - Non-rollover envelopes: resets balance to allocation amount
-    /// The command prevents duplicate updates within the same month.
-    #[poise::command(slash_command, prefix_command)]
-    pub async fn update(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+    /// Shows an envelope's balance over the last 90 days from the nightly snapshots.
+    #[poise::command(slash_command, prefix_command, rename = "trend")]
+    pub async fn report_trend(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Envelope to show the balance trend of"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        #[rename = "envelope"]
+        envelope_name: String,
+    ) -> Result<()> {
         let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
 
-        // Acknowledge command quickly
-        ctx.defer().await?;
+        let Some(envelope) =
+            envelope::find_envelope_for_user(db, &envelope_name, &author_id).await?
+        else {
+            ctx.say(&format!("❌ Envelope '{envelope_name}' not found."))
+                .await?;
+            return Ok(());
+        };
 
-        // Process monthly updates
-        match monthly::process_monthly_updates(db).await? {
-            Some(result) => {
-                let summary = monthly::format_monthly_update_summary(&result)?;
-                ctx.say(format!(
-                    "✅ **Monthly Update Complete!**\n\n```\n{summary}\n```",
-                ))
+        let today = calendar::today(db).await?;
+        let snapshots = snapshot::get_trend(db, envelope.id, today, snapshot::TREND_DAYS).await?;
+        let currency = setup::get_currency_format(db).await?;
+        ctx.say(snapshot::format_trend(
+            &envelope.name,
+            &snapshots,
+            currency,
+        )?)
+        .await?;
+        Ok(())
+    }
+
+    /// The date window asked for with `period`, `from`, or `to`, or `None` for the
+    /// regular current-month report.
+    fn requested_range(
+        period: Option<ReportPeriodChoice>,
+        from: Option<&str>,
+        to: Option<&str>,
+        today: chrono::NaiveDate,
+        cycle_start_day: u32,
+    ) -> Option<Result<report::ReportRange>> {
+        match (period, from, to) {
+            (Some(period), _, _) => Some(Ok(report::ReportRange::for_period(
+                period.into(),
+                today,
+                cycle_start_day,
+            ))),
+            (None, None, None) => None,
+            (None, _, _) => Some(report::ReportRange::parse(from, to, today, cycle_start_day)),
+        }
+    }
+
+    /// Lists this budget month's spending per category, with any category caps.
+    #[poise::command(slash_command, prefix_command, rename = "category")]
+    pub async fn report_category(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let today = calendar::today(db).await?;
+        let statuses = category_cap::get_category_statuses(db, today).await?;
+        if statuses.is_empty() {
+            ctx.say("📊 No envelopes found. Create one with `/create_envelope` to get started!")
                 .await?;
-            }
-            None => {
-                ctx.say("ℹ️ Monthly update already performed this month. No updates needed.")
-                    .await?;
-            }
+            return Ok(());
         }
 
+        let cycle = calendar::current_cycle(db, today).await?;
+        let currency = setup::get_currency_format(db).await?;
+        let mut response = format!(
+            "🧢 **Spending by Category** ({} - {})\n",
+            cycle.start.format("%b %d"),
+            cycle.end.format("%b %d")
+        );
+        for status in &statuses {
+            write!(
+                &mut response,
+                "\n**{}:** {} spent of {} allocated",
+                status.category,
+                currency.format(status.spent),
+                currency.format(status.allocation)
+            )?;
+            if let Some(cap) = &status.cap {
+                write!(
+                    &mut response,
+                    " | {} cap {} ({:.1}% used)",
+                    if cap.hard { "Hard" } else { "Soft" },
+                    currency.format(cap.cap),
+                    status.spent / cap.cap * 100.0
+                )?;
+                if status.is_over_cap() {
+                    write!(&mut response, " ⚠️")?;
+                }
+            }
+        }
+        ctx.say(response).await?;
         Ok(())
     }
 
-    /// Shows detailed information about a specific envelope.
+    /// Shows budget vs actual spending per envelope for a budget month.
     ///
-    /// This command displays comprehensive information about an envelope including
-    /// its balance, allocation, category, rollover setting, and recent transactions.
-    #[poise::command(slash_command, prefix_command)]
-    pub async fn envelope_info(
+    /// Defaults to this month; the table is sent as a file if it is too long for one
+    /// message.
+    #[poise::command(slash_command, prefix_command, rename = "variance")]
+    pub async fn report_variance(
         ctx: poise::Context<'_, BotData, Error>,
-        #[description = "Name of the envelope"]
-        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
-        envelope_name: String,
-        #[description = "User ID (for individual envelopes)"] user: Option<String>,
+        #[description = "Budget month (YYYY-MM, defaults to this month)"]
+        #[autocomplete = "autocomplete::autocomplete_statement_month"]
+        month: Option<String>,
     ) -> Result<()> {
-        let db = &ctx.data().database;
-        let user_id = user.unwrap_or_else(|| ctx.author().id.to_string());
+        // Discord's message limit, less room for the heading and code fences
+        const MAX_INLINE_TABLE: usize = 1800;
 
-        // Try to find the envelope - first check user's individual envelope, then shared
-        let envelope = if let Some(env) =
-            envelope::get_envelope_by_name_and_user(db, &envelope_name, &user_id).await?
+        let db = &ctx.data().database;
+        let today = calendar::today(db).await?;
+        let variance = match report::variance_report(
+            db,
+            month.as_deref(),
+            &ctx.author().id.to_string(),
+            today,
+        )
+        .await
         {
-            Some(env)
+            Ok(variance) => variance,
+            Err(Error::Config { message }) => {
+                ctx.say(format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        if variance.lines.is_empty() {
+            ctx.say(format!("📐 No budget data for {}.", variance.month))
+                .await?;
+            return Ok(());
+        }
+
+        let mut heading = format!("📐 **Budget vs Actual** ({}", variance.month);
+        if let Some((start, end)) = variance.period {
+            write!(
+                &mut heading,
+                ", {} - {}",
+                start.format("%b %d"),
+                end.format("%b %d")
+            )?;
+        }
+        heading.push(')');
+        if variance.in_progress {
+            heading.push_str(" - month in progress");
+        }
+        if variance.hidden > 0 {
+            write!(
+                &mut heading,
+                "\n🔒 {} private envelope(s) not shown",
+                variance.hidden
+            )?;
+        }
+
+        let table = report::format_variance_table(&variance);
+        let reply = if table.len() > MAX_INLINE_TABLE {
+            poise::CreateReply::default().content(heading).attachment(
+                serenity::CreateAttachment::bytes(
+                    table.into_bytes(),
+                    format!("variance-{}.txt", variance.month),
+                ),
+            )
         } else {
-            envelope::get_shared_envelope_by_name(db, &envelope_name).await?
+            poise::CreateReply::default().content(format!("{heading}\n```\n{table}\n```"))
         };
+        ctx.send(reply).await?;
+        Ok(())
+    }
+
+    /// Lists the merchants with the most spending this budget month.
+    #[poise::command(slash_command, prefix_command, rename = "merchants")]
+    pub async fn report_merchants(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        const TOP_MERCHANTS: usize = 10;
+
+        let db = &ctx.data().database;
+        let today = calendar::today(db).await?;
+        let cycle = calendar::current_cycle(db, today).await?;
+        let (start, end) = calendar::utc_bounds(db, cycle.start, cycle.end).await?;
+        let merchants = transaction::get_top_merchants(
+            db,
+            start,
+            end,
+            &ctx.author().id.to_string(),
+            TOP_MERCHANTS,
+        )
+        .await?;
+
+        if merchants.is_empty() {
+            ctx.say("🏪 No spending with a merchant this month yet.")
+                .await?;
+            return Ok(());
+        }
 
-        let Some(envelope) = envelope else {
+        let currency = setup::get_currency_format(db).await?;
+        let mut response = format!(
+            "🏪 **Top Merchants** ({} - {})\n",
+            cycle.start.format("%b %d"),
+            cycle.end.format("%b %d")
+        );
+        for (rank, merchant) in merchants.iter().enumerate() {
+            write!(
+                &mut response,
+                "\n{}. **{}** - {} ({} transaction(s))",
+                rank + 1,
+                merchant.merchant,
+                currency.format(merchant.spent),
+                merchant.transaction_count
+            )?;
+        }
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Lists the envelopes that have had no transactions in `months` months, suggesting
+    /// they be archived.
+    #[poise::command(slash_command, prefix_command, rename = "stale")]
+    pub async fn report_stale(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "List envelopes with no transactions in this many months"]
+        #[min = 1]
+        #[max = 120]
+        months: u32,
+    ) -> Result<()> {
+        const MAX_LISTED: usize = 25;
+
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let today = calendar::today(db).await?;
+        let stale: Vec<report::StaleEnvelope> = report::get_stale_envelopes(db, months, today)
+            .await?
+            .into_iter()
+            .filter(|stale| report::can_view(&stale.envelope, &author_id))
+            .collect();
+
+        if stale.is_empty() {
             ctx.say(&format!(
-                "❌ Envelope '{envelope_name}' not found. Use `/envelopes` to see all available envelopes.",
+                "✨ Every envelope has had a transaction in the last {months} month(s)."
             ))
             .await?;
             return Ok(());
-        };
-
-        // Generate envelope report
-        let envelope_report = report::generate_envelope_report(db, envelope.id, Some(5)).await?;
+        }
 
-        // Build response
-        let mut response = format!("📋 **Envelope: {}**\n\n", envelope.name);
-        writeln!(&mut response, "💰 Balance: ${:.2}", envelope_report.balance)?;
-        writeln!(
-            &mut response,
-            "📅 Monthly Allocation: ${:.2}",
-            envelope_report.allocation
-        )?;
-        writeln!(&mut response, "📊 Category: {}\n", envelope.category)?;
-        writeln!(
-            &mut response,
-            "👥 Type: {}",
-            if envelope.is_individual {
-                "Individual"
-            } else {
-                "Shared"
-            }
-        )?;
-        writeln!(
+        let currency = setup::get_currency_format(db).await?;
+        let mut response =
+            format!("🕸️ **Stale Envelopes** (no transactions in {months} month(s))\n");
+        for entry in stale.iter().take(MAX_LISTED) {
+            let env = &entry.envelope;
+            let label =
+                envelope_label(ctx, &env.name, env.is_individual, env.user_id.as_deref()).await;
+            let last = entry.last_activity.map_or_else(
+                || "never used".to_string(),
+                |last| format!("last used <t:{}:R>", last.timestamp()),
+            );
+            write!(
+                &mut response,
+                "\n• **{label}** - {last} | Balance: {} | Allocation: {}",
+                currency.format(env.balance),
+                currency.format(env.allocation)
+            )?;
+        }
+        if stale.len() > MAX_LISTED {
+            write!(&mut response, "\n…and {} more", stale.len() - MAX_LISTED)?;
+        }
+        write!(
             &mut response,
-            "🔄 Rollover: {}",
-            if envelope.rollover {
-                "Enabled"
-            } else {
-                "Disabled"
-            }
+            "\n\nConsider archiving them with `/delete_envelope` (history is kept and the \
+             envelope can be re-enabled) or folding them into another with `/envelope merge`."
         )?;
-        writeln!(&mut response)?;
+        ctx.say(response).await?;
+        Ok(())
+    }
 
-        let progress_bar = report::format_progress_bar(envelope_report.progress_percent, Some(15));
-        writeln!(
-            &mut response,
-            "**Progress:** {} {:.1}%",
-            progress_bar, envelope_report.progress_percent
-        )?;
-        writeln!(
-            &mut response,
-            "Spent: ${:.2} | Remaining: ${:.2}",
-            envelope_report.amount_spent, envelope_report.amount_remaining
-        )?;
-        writeln!(&mut response)?;
+    /// Builds the display label for an envelope: "name (User)" or "name (Shared)".
+    async fn envelope_label(
+        ctx: poise::Context<'_, BotData, Error>,
+        name: &str,
+        is_individual: bool,
+        user_id: Option<&str>,
+    ) -> String {
+        if !is_individual {
+            return format!("{name} (Shared)");
+        }
+        let Some(uid) = user_id else {
+            return format!("{name} (Individual)");
+        };
 
-        if envelope_report.recent_transactions.is_empty() {
-            response.push_str("_No recent transactions_\n");
+        // First try to get nickname from .env config
+        let user_name = if let Some(nickname) = config::users::get_nickname(uid) {
+            nickname
         } else {
-            response.push_str("**Recent Transactions:**\n");
-            for txn in envelope_report.recent_transactions {
-                let summary = report::format_transaction_summary(&txn);
-                writeln!(&mut response, "• {summary}")?;
+            // Fallback to Discord username
+            if let Ok(user_id_val) = uid.parse::<u64>() {
+                let user_id = serenity::UserId::new(user_id_val);
+                if let Ok(user) = user_id.to_user(ctx.serenity_context()).await {
+                    user.name
+                } else {
+                    format!("User {uid}")
+                }
+            } else {
+                format!("User {uid}")
             }
-        }
+        };
+        format!("{name} ({user_name})")
+    }
 
-        ctx.say(response).await?;
+    /// Parent command for archived monthly statements.
+    #[poise::command(
+        slash_command,
+        prefix_command,
+        subcommands("statement_show", "statement_pdf")
+    )]
+    pub async fn statement(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Statement command. Available subcommands:\n\
+            `/statement show [month]` - Show a past month's statement, or list the months available\n\
+            `/statement pdf <month>` - Download a past month's statement as a PDF";
+
+        ctx.say(help_text).await?;
         Ok(())
     }
 
-    /// Lists all active envelopes in the system.
+    /// Shows the archived statement for a past budget month.
     ///
-    /// This command shows a summary of all available envelopes with their
-    /// balances and allocations, helping users see what envelopes exist.
-    #[poise::command(slash_command, prefix_command)]
-    pub async fn envelopes(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+    /// Statements are stored at each monthly update with every envelope's opening and
+    /// closing balance, spending, and transaction count, so they remain available after
+    /// the month's transactions are pruned. Without `month`, lists the available months.
+    #[poise::command(slash_command, prefix_command, rename = "show")]
+    pub async fn statement_show(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Budget month (YYYY-MM)"]
+        #[autocomplete = "autocomplete::autocomplete_statement_month"]
+        month: Option<String>,
+    ) -> Result<()> {
         let db = &ctx.data().database;
+        let Some(month) = month else {
+            let months = statement::get_statement_months(db).await?;
+            if months.is_empty() {
+                ctx.say(
+                    "🧾 No statements yet. One is stored for each month at the monthly update.",
+                )
+                .await?;
+            } else {
+                ctx.say(format!(
+                    "🧾 Statements are available for: {}",
+                    months.join(", ")
+                ))
+                .await?;
+            }
+            return Ok(());
+        };
+        let month = match statement::parse_month(&month) {
+            Ok(month) => month,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
 
-        let all_envelopes = envelope::get_all_active_envelopes(db).await?;
+        let views = statement::get_statements(db, &month, &ctx.author().id.to_string()).await?;
+        let currency = setup::get_currency_format(db).await?;
+        // Balance totals are only shown when every statement has its balances
+        let totals = statement::StatementTotals::from_views(&views);
+        let mut embed_fields = Vec::new();
+        for view in views {
+            match view {
+                statement::StatementView::Visible(s) => {
+                    embed_fields.push((
+                        s.envelope_name,
+                        format!(
+                            "**Opened:** {}\n**Closed:** {}\n**Spent:** {}\n**Transactions:** {}",
+                            statement_balance(s.open_balance, currency),
+                            statement_balance(s.close_balance, currency),
+                            currency.format(s.total_spent),
+                            s.transaction_count
+                        ),
+                        true,
+                    ));
+                }
+                statement::StatementView::Hidden { envelope_name } => {
+                    embed_fields.push((
+                        envelope_name,
+                        format!("🔒 *{}*", report::HIDDEN_LABEL),
+                        true,
+                    ));
+                }
+            }
+        }
 
-        if all_envelopes.is_empty() {
-            ctx.say("📂 No envelopes found. Create one with `/create_envelope` to get started!")
+        if embed_fields.is_empty() {
+            ctx.say(format!("🧾 No statement found for {month}."))
                 .await?;
             return Ok(());
         }
 
-        let mut response = String::from("📂 **All Envelopes**\n\n");
-
-        for env in all_envelopes {
-            let type_indicator = if env.is_individual { "👤" } else { "👥" };
+        let mut description = String::new();
+        if let Some((start, end)) = totals.period {
             writeln!(
-                &mut response,
-                "{} **{}** - ${:.2} / ${:.2} ({})",
-                type_indicator, env.name, env.balance, env.allocation, env.category
+                &mut description,
+                "{} to {}",
+                start.format("%Y-%m-%d"),
+                end.format("%Y-%m-%d")
             )?;
         }
+        write!(
+            &mut description,
+            "**Opened:** {} | **Closed:** {} | **Spent:** {} | **Transactions:** {}",
+            statement_balance(totals.open_balance, currency),
+            statement_balance(totals.close_balance, currency),
+            currency.format(totals.total_spent),
+            totals.transaction_count
+        )?;
 
-        ctx.say(response).await?;
+        let statement_embed = serenity::CreateEmbed::default()
+            .title(format!("🧾 Statement for {month}"))
+            .description(description)
+            .color(
+                report::get_theme(db)
+                    .await?
+                    .color(report::EmbedColor::Report),
+            )
+            .fields(embed_fields);
+        ctx.send(poise::CreateReply::default().embed(statement_embed))
+            .await?;
         Ok(())
     }
 
-    /// Creates a new envelope for budget tracking.
+    /// Attaches a past budget month's statement as a PDF document.
     ///
-    /// This command creates a new envelope with the specified name, category, and allocation.
-    /// Envelopes can be either shared (accessible by all users) or individual (user-specific).
-    #[poise::command(slash_command, prefix_command)]
-    pub async fn create_envelope(
+    /// The PDF has the household summary, a table of envelopes, and the month's largest
+    /// spends. Only available when the bot is built with the `pdf` feature.
+    #[poise::command(slash_command, prefix_command, rename = "pdf")]
+    pub async fn statement_pdf(
         ctx: poise::Context<'_, BotData, Error>,
-        #[description = "Name of the envelope"] name: String,
-        #[description = "Budget category (e.g., 'groceries', 'entertainment')"] category: String,
-        #[description = "Monthly allocation amount"] allocation: f64,
-        #[description = "Is this an individual envelope? (default: false)"] is_individual: Option<
-            bool,
-        >,
-        #[description = "Enable rollover? (default: false)"] rollover: Option<bool>,
+        #[description = "Budget month (YYYY-MM)"]
+        #[autocomplete = "autocomplete::autocomplete_statement_month"]
+        month: String,
     ) -> Result<()> {
-        let db = &ctx.data().database;
-
+        let month = match statement::parse_month(&month) {
+            Ok(month) => month,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        send_statement_pdf(ctx, &month).await
+    }
+
+    /// Renders a month's PDF statement and attaches it to the reply.
+    #[cfg(feature = "pdf")]
+    async fn send_statement_pdf(
+        ctx: poise::Context<'_, BotData, Error>,
+        month: &str,
+    ) -> Result<()> {
+        ctx.defer().await?;
+        let db = &ctx.data().database;
+        let Some(document) = pdf::build_statement(db, month, &ctx.author().id.to_string()).await?
+        else {
+            ctx.say(format!("🧾 No statement found for {month}."))
+                .await?;
+            return Ok(());
+        };
+        let bytes = pdf::render_statement(&document)?;
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!("🧾 Statement for {month}"))
+                .attachment(serenity::CreateAttachment::bytes(
+                    bytes,
+                    pdf::statement_filename(month),
+                )),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Explains that PDF statements need the `pdf` feature.
+    #[cfg(not(feature = "pdf"))]
+    async fn send_statement_pdf(
+        ctx: poise::Context<'_, BotData, Error>,
+        month: &str,
+    ) -> Result<()> {
+        ctx.say(format!(
+            "❌ PDF statements aren't available in this build. Use `/statement show month:{month}` instead."
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Formats a statement balance, which is missing for months aggregated from history.
+    fn statement_balance(balance: Option<f64>, currency: setup::Currency) -> String {
+        balance.map_or_else(|| "n/a".to_string(), |balance| currency.format(balance))
+    }
+
+    /// Shows what an envelope's balance was at the end of a past day.
+    ///
+    /// The balance is worked forward from the statement of the budget month containing
+    /// the day, or back from the current balance if that month hasn't been closed yet,
+    /// which helps track down balance discrepancies.
+    #[poise::command(slash_command, prefix_command)]
+    pub async fn balance_at(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope_name: String,
+        #[description = "Day to show the closing balance for (YYYY-MM-DD)"] date: String,
+        #[description = "Member whose individual envelope to use (admins only for others)"]
+        user: Option<serenity::User>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let Some(user_id) = permissions::target_user(ctx, user.as_ref()).await? else {
+            return Ok(());
+        };
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &envelope_name, &user_id).await?
+        else {
+            ctx.say(&format!("❌ Envelope '{envelope_name}' not found."))
+                .await?;
+            return Ok(());
+        };
+        if !report::can_view(&envelope, &author_id) {
+            ctx.say(&format!(
+                "🔒 Envelope '{}' is private to its owner.",
+                envelope.name
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let reconstruction = match report::parse_date(&date) {
+            Ok(date) => report::reconstruct_balance(db, envelope.id, date).await,
+            Err(e) => Err(e),
+        };
+        let reconstruction = match reconstruction {
+            Ok(reconstruction) => reconstruction,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let currency = setup::get_currency_format(db).await?;
+        let source = reconstruction.statement_month.as_ref().map_or_else(
+            || {
+                format!(
+                    "current balance {} less {} later transaction(s)",
+                    currency.format(envelope.balance),
+                    reconstruction.transaction_count
+                )
+            },
+            |month| {
+                format!(
+                    "{month} opening balance plus {} transaction(s)",
+                    reconstruction.transaction_count
+                )
+            },
+        );
+        ctx.say(&format!(
+            "🕰️ '{}' had **{}** at the end of {}, from the {source}.",
+            envelope.name,
+            currency.format(reconstruction.balance),
+            date.trim()
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Shows your own allowances and how much you saved compared to last month.
+    ///
+    /// Lists the caller's individual envelopes with their balances and how much of each
+    /// allocation was left unspent this month and last month. Only the caller's own
+    /// envelopes are ever shown, and the reply is only visible to them.
+    #[poise::command(slash_command, prefix_command)]
+    pub async fn allowance(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let today = calendar::today(db).await?;
+        let personal = report::personal_report(db, &ctx.author().id.to_string(), today).await?;
+
+        if personal.envelopes.is_empty() {
+            ctx.send(
+                poise::CreateReply::default()
+                    .content("👛 You don't have any individual envelopes (allowances).")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let currency = setup::get_currency_format(db).await?;
+        let fields: Vec<(String, String, bool)> = personal
+            .envelopes
+            .iter()
+            .map(|env| {
+                (
+                    env.envelope.name.clone(),
+                    format!(
+                        "**Balance:** {} / {}\n**Saved this month:** {}\n\
+                         **Saved last month:** {}\n**Change:** {}",
+                        currency.format(env.envelope.balance),
+                        currency.format(env.envelope.allocation),
+                        currency.format(env.saved_this_month()),
+                        currency.format(env.saved_last_month()),
+                        savings_change(env.savings_change(), currency)
+                    ),
+                    true,
+                )
+            })
+            .collect();
+        let saved_this_month = personal.total_saved_this_month();
+        let saved_last_month = personal.total_saved_last_month();
+        let description = format!(
+            "{} to {}\n**Remaining:** {} | **Saved:** {} (last month {}, {})",
+            personal.this_month.from.format("%Y-%m-%d"),
+            personal.this_month.to.format("%Y-%m-%d"),
+            currency.format(personal.total_balance()),
+            currency.format(saved_this_month),
+            currency.format(saved_last_month),
+            savings_change(saved_this_month - saved_last_month, currency)
+        );
+
+        let embed = serenity::CreateEmbed::default()
+            .title("👛 Your allowances")
+            .description(description)
+            .color(
+                report::get_theme(db)
+                    .await?
+                    .color(report::EmbedColor::Positive),
+            )
+            .fields(fields);
+        ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Formats a month-over-month savings change with an arrow.
+    fn savings_change(change: f64, currency: setup::Currency) -> String {
+        if change > 0.0 {
+            format!("📈 {}", currency.format_signed(change))
+        } else if change < 0.0 {
+            format!("📉 {}", currency.format_signed(change))
+        } else {
+            format!("➖ {}", currency.format(0.0))
+        }
+    }
+
+    /// Runs the monthly update process for all envelopes.
+    ///
+    /// This command processes monthly updates for all active envelopes:
+    /// - Rollover envelopes: adds allocation to existing balance
+    /// - Non-rollover envelopes: resets balance to allocation amount
+    /// The command prevents duplicate updates within the same month, and attempts are
+    /// rate limited per user with a counter that survives restarts. Afterwards it posts a
+    /// review of envelopes that overspent or under-spent, with buttons to adjust their
+    /// allocations.
+    #[poise::command(slash_command, prefix_command)]
+    pub async fn update(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+
+        if let Some(cooldown_duration) = ctx.data().cooldowns.update {
+            let author_id = ctx.author().id.to_string();
+            if let cooldown::AttemptOutcome::Limited {
+                remaining,
+                attempts,
+            } = cooldown::record_attempt(db, "update", &author_id, cooldown_duration).await?
+            {
+                tracing::warn!("User {author_id} hit the /update cooldown ({attempts} attempts)");
+                ctx.send(
+                    poise::CreateReply::default()
+                        .content(bot::cooldown_message("update", remaining))
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        // Acknowledge command quickly
+        ctx.defer().await?;
+
+        // Process monthly updates
+        match monthly::process_monthly_updates(db).await? {
+            Some(result) => {
+                let summary = monthly::format_monthly_update_summary(
+                    &result,
+                    setup::get_currency_format(db).await?,
+                )?;
+                ctx.say(format!(
+                    "✅ **Monthly Update Complete!**\n\n```\n{summary}\n```",
+                ))
+                .await?;
+                notify::send_external(Notification::household(
+                    format!("Monthly update for {}", result.closed_month),
+                    summary,
+                ));
+
+                // Envelopes reset below their threshold alert for the new month
+                let envelope_ids: Vec<i64> = envelope::get_all_active_envelopes(db)
+                    .await?
+                    .iter()
+                    .map(|env| env.id)
+                    .collect();
+                alerts::after_balance_change(ctx, &envelope_ids).await;
+
+                // Offer to adjust allocations that didn't fit the closed month
+                monthly_review::post_review(ctx, &result.closed_month).await?;
+            }
+            None => {
+                ctx.say("ℹ️ Monthly update already performed this month. No updates needed.")
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Shows detailed information about a specific envelope.
+    ///
+    /// This command displays comprehensive information about an envelope including
+    /// its balance, allocation, category, rollover setting, and recent transactions.
+    /// Buttons page through older transactions and a menu jumps to a month.
+    #[allow(clippy::too_many_lines)] // Formats every envelope detail inline
+    #[poise::command(slash_command, prefix_command)]
+    pub async fn envelope_info(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope_name: String,
+        #[description = "Member whose individual envelope to use (admins only for others)"]
+        user: Option<serenity::User>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let Some(user_id) = permissions::target_user(ctx, user.as_ref()).await? else {
+            return Ok(());
+        };
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &envelope_name, &user_id).await?
+        else {
+            ctx.say(&format!(
+                "❌ Envelope '{envelope_name}' not found. Use `/envelopes` to see all available envelopes.",
+            ))
+            .await?;
+            return Ok(());
+        };
+
+        // Generate envelope report (private envelopes are only shown to their owner);
+        // transactions are paged separately below
+        let envelope_report = match report::generate_envelope_report_for_viewer(
+            db,
+            envelope.id,
+            Some(0),
+            &ctx.author().id.to_string(),
+        )
+        .await
+        {
+            Ok(envelope_report) => envelope_report,
+            Err(Error::EnvelopeHidden { name }) => {
+                ctx.say(&format!(
+                    "🔒 Envelope '{name}' is {}.",
+                    report::HIDDEN_LABEL
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Build response
+        let currency = setup::get_currency_format(db).await?;
+        let mut response = format!("📋 **Envelope: {}**\n\n", envelope.name);
+        writeln!(
+            &mut response,
+            "💰 Balance: {}",
+            currency.format(envelope_report.balance)
+        )?;
+        writeln!(
+            &mut response,
+            "📅 Monthly Allocation: {}",
+            currency.format(envelope_report.allocation)
+        )?;
+        writeln!(&mut response, "📊 Category: {}\n", envelope.category)?;
+        writeln!(
+            &mut response,
+            "👥 Type: {}",
+            if envelope.is_individual {
+                "Individual"
+            } else {
+                "Shared"
+            }
+        )?;
+        writeln!(
+            &mut response,
+            "🔄 Rollover: {}",
+            if envelope.rollover {
+                "Enabled"
+            } else {
+                "Disabled"
+            }
+        )?;
+        if envelope.contribution > 0.0 {
+            writeln!(
+                &mut response,
+                "🏦 Monthly Contribution: {}",
+                currency.format(envelope.contribution)
+            )?;
+        }
+        if let Some(goal) = envelope.goal {
+            writeln!(
+                &mut response,
+                "🎯 Goal: {}",
+                report::format_goal_progress(envelope.balance, goal, currency)
+            )?;
+        }
+        if let Some(threshold) = envelope.alert_below {
+            writeln!(
+                &mut response,
+                "🔔 Alert Below: {}",
+                currency.format(threshold)
+            )?;
+        }
+        if let Some(limit) = envelope.daily_limit {
+            writeln!(&mut response, "🚦 Daily Limit: {}", currency.format(limit))?;
+        }
+        if let Some(floor) = envelope.min_balance {
+            writeln!(
+                &mut response,
+                "🛡️ Minimum Balance: {} ({} spendable)",
+                currency.format(floor),
+                currency.format((envelope.balance - floor).max(0.0))
+            )?;
+        }
+        if let Some(rate) = envelope.interest_rate.filter(|_| envelope.rollover) {
+            writeln!(&mut response, "💹 Interest: {rate}% monthly")?;
+        }
+        if envelope::is_frozen_on(&envelope, calendar::today(db).await?) {
+            match envelope.frozen_until {
+                Some(until) => writeln!(&mut response, "🧊 Frozen through {until}")?,
+                None => writeln!(&mut response, "🧊 Frozen until unfrozen")?,
+            }
+        }
+        writeln!(&mut response)?;
+
+        let progress_bar = report::get_theme(db)
+            .await?
+            .progress_bar(envelope_report.progress_percent, Some(15));
+        writeln!(
+            &mut response,
+            "**Progress:** {} {:.1}%",
+            progress_bar, envelope_report.progress_percent
+        )?;
+        writeln!(
+            &mut response,
+            "Spent: {} | Remaining: {}",
+            currency.format(envelope_report.amount_spent),
+            currency.format(envelope_report.amount_remaining)
+        )?;
+        writeln!(&mut response)?;
+
+        let key = ctx.id();
+        let state = envelope_pages::PageState {
+            envelope_id: envelope.id,
+            viewer_id: ctx.author().id,
+            header: response,
+            month: None,
+            page: 0,
+            months: transaction::get_transaction_months(db, envelope.id).await?,
+        };
+        let (content, components, state) = envelope_pages::render(ctx.data(), key, state).await?;
+        ctx.data().envelope_pages.insert(key, state);
+
+        ctx.send(
+            poise::CreateReply::default()
+                .content(content)
+                .components(components),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Lists all active envelopes in the system.
+    ///
+    /// This command shows a summary of all available envelopes with their
+    /// balances and allocations, helping users see what envelopes exist.
+    #[poise::command(slash_command, prefix_command)]
+    pub async fn envelopes(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let all_envelopes = report::get_envelope_views(db, &ctx.author().id.to_string()).await?;
+
+        if all_envelopes.is_empty() {
+            ctx.say("📂 No envelopes found. Create one with `/create_envelope` to get started!")
+                .await?;
+            return Ok(());
+        }
+
+        let currency = setup::get_currency_format(db).await?;
+        let mut response = String::from("📂 **All Envelopes**\n\n");
+
+        for view in all_envelopes {
+            match view {
+                report::EnvelopeView::Visible(env) => {
+                    let type_indicator = if env.is_individual { "👤" } else { "👥" };
+                    let lock = if env.is_private { " 🔒" } else { "" };
+                    writeln!(
+                        &mut response,
+                        "{} **{}**{} - {} / {} ({})",
+                        type_indicator,
+                        env.name,
+                        lock,
+                        currency.format(env.balance),
+                        currency.format(env.allocation),
+                        env.category
+                    )?;
+                }
+                report::EnvelopeView::Hidden(hidden) => {
+                    writeln!(
+                        &mut response,
+                        "🔒 **{}** - {} ({})",
+                        hidden.name,
+                        report::HIDDEN_LABEL,
+                        hidden.category
+                    )?;
+                }
+            }
+        }
+
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Creates a new envelope for budget tracking.
+    ///
+    /// This command creates a new envelope with the specified name, category, and allocation.
+    /// Envelopes can be either shared (accessible by all users) or individual (user-specific).
+    #[allow(clippy::too_many_arguments)] // Each slash command option is a function parameter
+    #[poise::command(slash_command, prefix_command)]
+    pub async fn create_envelope(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the envelope"] name: String,
+        #[description = "Budget category (e.g., 'groceries', 'entertainment')"] category: String,
+        #[description = "Monthly allocation amount"] allocation: f64,
+        #[description = "Is this an individual envelope? (default: false)"] is_individual: Option<
+            bool,
+        >,
+        #[description = "Enable rollover? (default: false)"] rollover: Option<bool>,
+        #[description = "Sinking-fund contribution added each month (optional)"]
+        contribution: Option<f64>,
+        #[description = "Savings goal for a sinking fund (optional)"] goal: Option<f64>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
         if allocation.is_nan() || allocation.is_infinite() {
             ctx.say("❌ Invalid allocation: must be a valid number")
                 .await?;
@@ -348,180 +1238,1507 @@ mod inner {
             return Ok(());
         }
 
-        let user_id = if is_individual.unwrap_or(false) {
-            Some(ctx.author().id.to_string())
-        } else {
-            None
+        // Reject bad sinking-fund settings before anything is created
+        if let Err(e) = envelope::validate_sinking_fund(
+            contribution.unwrap_or(0.0),
+            goal,
+            rollover.unwrap_or(false),
+        ) {
+            return reply_sinking_fund_error(ctx, e).await;
+        }
+
+        let user_id = if is_individual.unwrap_or(false) {
+            Some(ctx.author().id.to_string())
+        } else {
+            None
+        };
+
+        // Check if envelope already exists
+        let existing = if let Some(uid) = &user_id {
+            envelope::get_envelope_by_name_and_user(db, &name, uid).await?
+        } else {
+            envelope::get_shared_envelope_by_name(db, &name).await?
+        };
+
+        if existing.is_some() {
+            ctx.say(&format!(
+                "❌ Envelope '{name}' already exists. Use a different name or delete the existing envelope first.",
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        // Create the envelope
+        let new_envelope = match envelope::create_envelope(
+            db,
+            name.clone(),
+            user_id,
+            category.clone(),
+            allocation,
+            is_individual.unwrap_or(false),
+            rollover.unwrap_or(false),
+        )
+        .await
+        {
+            Ok(created) => created,
+            Err(Error::EnvelopeAlreadyExists { name }) => {
+                ctx.say(&format!(
+                    "❌ Envelope '{name}' already exists. Use a different name or delete the existing envelope first.",
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let name = new_envelope.name.clone();
+
+        // Configure sinking-fund behavior if requested
+        let new_envelope = if contribution.is_some() || goal.is_some() {
+            envelope::set_sinking_fund(db, new_envelope.id, contribution.unwrap_or(0.0), goal)
+                .await?
+        } else {
+            new_envelope
+        };
+
+        let type_str = if new_envelope.is_individual {
+            "individual"
+        } else {
+            "shared"
+        };
+        let rollover_str = if new_envelope.rollover {
+            "with rollover enabled"
+        } else {
+            "without rollover"
+        };
+
+        let currency = setup::get_currency_format(db).await?;
+        let mut message = format!(
+            "✅ Created {type_str} envelope **{name}** in category '{category}' with {} monthly allocation {rollover_str}!",
+            currency.format(allocation)
+        );
+        if new_envelope.contribution > 0.0 {
+            write!(
+                &mut message,
+                "\n🏦 Sinking fund: {} contributed each month",
+                currency.format(new_envelope.contribution)
+            )?;
+        }
+        if let Some(goal_amount) = new_envelope.goal {
+            write!(&mut message, "\n🎯 Goal: {}", currency.format(goal_amount))?;
+        }
+        ctx.say(&message).await?;
+
+        Ok(())
+    }
+
+    /// Deletes an envelope from the system.
+    ///
+    /// This command performs a soft delete on the envelope, marking it as deleted
+    /// while preserving historical transaction data. The envelope will no longer
+    /// appear in reports or be available for new transactions.
+    #[poise::command(slash_command, prefix_command)]
+    pub async fn delete_envelope(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the envelope to delete"] name: String,
+        #[description = "Member whose individual envelope to use (admins only for others)"]
+        user: Option<serenity::User>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let Some(user_id) = permissions::target_user(ctx, user.as_ref()).await? else {
+            return Ok(());
+        };
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &name, &user_id).await? else {
+            ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
+            return Ok(());
+        };
+
+        // Perform soft delete
+        let mut active_model: crate::entities::envelope::ActiveModel = envelope.clone().into();
+        active_model.is_deleted = sea_orm::ActiveValue::Set(true);
+        active_model.update(db).await?;
+        cache::invalidate(CachedTable::Envelopes);
+        remember_undo(
+            ctx,
+            UndoAction::EnvelopeDelete {
+                envelope_id: envelope.id,
+            },
+            &format!("Deleted envelope '{}'", envelope.name),
+        )
+        .await;
+
+        ctx.say(&format!(
+            "✅ Deleted envelope **{name}**. Historical transaction data has been preserved.",
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Updates an envelope's allocation or settings.
+    ///
+    /// This command allows modifying an existing envelope's monthly allocation,
+    /// rollover setting, or category without creating a new envelope.
+    // Slash-only: poise expands prefix parsing for every combination of optional
+    // arguments, which stops compiling at this many options
+    #[allow(clippy::too_many_arguments)] // Each slash command option is a function parameter
+    #[allow(clippy::too_many_lines)] // One validation/update branch per optional field
+    #[poise::command(slash_command)]
+    pub async fn update_envelope(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the envelope to update"] name: String,
+        #[description = "New monthly allocation (optional)"] allocation: Option<f64>,
+        #[description = "Enable/disable rollover (optional)"] rollover: Option<bool>,
+        #[description = "New category (optional)"] category: Option<String>,
+        #[description = "Member whose individual envelope to use (admins only for others)"]
+        user: Option<serenity::User>,
+        #[description = "Monthly sinking-fund contribution, 0 to disable (optional)"]
+        contribution: Option<f64>,
+        #[description = "Savings goal for a sinking fund, 0 to clear (optional)"] goal: Option<f64>,
+        #[description = "Hide balance and transactions from other members (optional)"]
+        private: Option<bool>,
+        #[description = "Warn when the balance drops below this amount, 0 to disable (optional)"]
+        alert_below: Option<f64>,
+        #[description = "Most that may be spent per day without an override, 0 to disable (optional)"]
+        daily_limit: Option<f64>,
+        #[description = "Role or member to ping when overspent, e.g. @Parents; `none` to clear (optional)"]
+        ping: Option<String>,
+        #[description = "Balance spends may not go below, 0 to disable (optional)"]
+        min_balance: Option<f64>,
+        #[description = "Monthly interest in percent paid on a rollover balance, 0 to disable (optional)"]
+        interest_rate: Option<f64>,
+        #[description = "List this envelope first in /report and /envelopes (optional)"]
+        pinned: Option<bool>,
+        #[description = "Position in /report and /envelopes, lowest first; 0 to clear (optional)"]
+        #[min = 0]
+        sort_order: Option<i32>,
+        #[description = "Description for spends recorded without one; `none` to clear (optional)"]
+        default_description: Option<String>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let Some(user_id) = permissions::target_user(ctx, user.as_ref()).await? else {
+            return Ok(());
+        };
+        let currency = setup::get_currency_format(db).await?;
+
+        if allocation.is_none()
+            && rollover.is_none()
+            && category.is_none()
+            && contribution.is_none()
+            && goal.is_none()
+            && private.is_none()
+            && alert_below.is_none()
+            && daily_limit.is_none()
+            && ping.is_none()
+            && min_balance.is_none()
+            && interest_rate.is_none()
+            && pinned.is_none()
+            && sort_order.is_none()
+            && default_description.is_none()
+        {
+            ctx.say(
+                "❌ Please specify at least one field to update (allocation, rollover, category, contribution, goal, private, alert_below, daily_limit, ping, min_balance, interest_rate, pinned, sort_order, or default_description).",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        if !check_allocation(ctx, allocation).await? {
+            return Ok(());
+        }
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &name, &user_id).await? else {
+            ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
+            return Ok(());
+        };
+
+        let before = envelope.clone();
+        let mut changes = Vec::new();
+
+        // Sinking-fund settings are checked against the rollover setting this update leaves
+        // behind, and applied once it is saved
+        let (new_contribution, new_goal) = sinking_fund_settings(&envelope, contribution, goal);
+        if let Err(e) = envelope::validate_sinking_fund(
+            new_contribution,
+            new_goal,
+            rollover.unwrap_or(envelope.rollover),
+        ) {
+            return reply_sinking_fund_error(ctx, e).await;
+        }
+
+        let Some(envelope) =
+            update_privacy(ctx, envelope, private, &author_id, &mut changes).await?
+        else {
+            return Ok(());
+        };
+
+        // A threshold of zero turns low-balance alerts off
+        let envelope = if let Some(threshold) = alert_below {
+            let new_threshold = (threshold != 0.0).then_some(threshold);
+            match envelope::set_alert_threshold(db, envelope.id, new_threshold).await {
+                Ok(updated) => {
+                    changes.push(new_threshold.map_or_else(
+                        || "low-balance alert to off".to_string(),
+                        |t| format!("low-balance alert to below {}", currency.format(t)),
+                    ));
+                    updated
+                }
+                Err(Error::InvalidAmount { amount }) => {
+                    ctx.say(&format!(
+                        "❌ Invalid alert threshold: {}. Thresholds must be non-negative.",
+                        currency.format(amount)
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        } else {
+            envelope
+        };
+
+        let envelope = if let Some(limit) = daily_limit {
+            let Some(updated) = update_daily_limit(ctx, &envelope, limit, &mut changes).await?
+            else {
+                return Ok(());
+            };
+            updated
+        } else {
+            envelope
+        };
+
+        let Some(envelope) =
+            update_ping_mention(ctx, envelope, ping.as_deref(), &mut changes).await?
+        else {
+            return Ok(());
+        };
+        let Some(envelope) = update_min_balance(ctx, envelope, min_balance, &mut changes).await?
+        else {
+            return Ok(());
+        };
+        let envelope =
+            update_display_order(ctx, envelope, pinned, sort_order, &mut changes).await?;
+        let envelope = if let Some(text) = default_description {
+            let cleared = text.trim().eq_ignore_ascii_case("none");
+            let updated =
+                envelope::set_default_description(db, envelope.id, (!cleared).then_some(&text))
+                    .await?;
+            changes.push(updated.default_description.as_deref().map_or_else(
+                || "default description to off".to_string(),
+                |desc| format!("default description to '{desc}'"),
+            ));
+            updated
+        } else {
+            envelope
+        };
+
+        // Interest is checked against the rollover setting this update leaves behind
+        if !check_interest_rate(ctx, &envelope, interest_rate, rollover).await? {
+            return Ok(());
+        }
+
+        // Update the envelope
+        let mut active_model: crate::entities::envelope::ActiveModel = envelope.into();
+
+        if let Some(alloc) = allocation {
+            active_model.allocation = sea_orm::ActiveValue::Set(alloc);
+            changes.push(format!("allocation to {}", currency.format(alloc)));
+        }
+        if let Some(roll) = rollover {
+            active_model.rollover = sea_orm::ActiveValue::Set(roll);
+            changes.push(format!(
+                "rollover to {}",
+                if roll { "enabled" } else { "disabled" }
+            ));
+        }
+        if let Some(ref cat) = category {
+            active_model.category = sea_orm::ActiveValue::Set(cat.clone());
+            changes.push(format!("category to '{cat}'"));
+        }
+
+        let updated = active_model.update(db).await?;
+        cache::invalidate(CachedTable::Envelopes);
+
+        let Some(updated) =
+            update_sinking_fund(ctx, updated, contribution, goal, &mut changes).await?
+        else {
+            return Ok(());
+        };
+        apply_interest_rate(ctx, updated.id, interest_rate, &mut changes).await?;
+        let after = envelope::get_envelope_by_id(db, updated.id)
+            .await?
+            .unwrap_or(updated);
+        if let Some(action) = UndoAction::envelope_edit(&before, &after)? {
+            remember_undo(
+                ctx,
+                action,
+                &format!("Updated envelope '{}': {}", after.name, changes.join(", ")),
+            )
+            .await;
+        }
+
+        // The changes may echo a linked mention; show it without pinging anyone
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!(
+                    "✅ Updated envelope **{}**: {}",
+                    name,
+                    changes.join(", ")
+                ))
+                .allowed_mentions(poise::serenity_prelude::CreateAllowedMentions::new()),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Checks `/update_envelope allocation`, replying with an error and returning `false`
+    /// if it isn't a valid non-negative amount.
+    async fn check_allocation(
+        ctx: poise::Context<'_, BotData, Error>,
+        allocation: Option<f64>,
+    ) -> Result<bool> {
+        let Some(alloc) = allocation else {
+            return Ok(true);
+        };
+        if alloc.is_nan() || alloc.is_infinite() {
+            ctx.say("❌ Invalid allocation: must be a valid number")
+                .await?;
+            return Ok(false);
+        }
+        if alloc < 0.0 {
+            ctx.say("❌ Allocation must be a non-negative number.")
+                .await?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Checks `/update_envelope interest_rate` before anything is changed, against the
+    /// rollover setting the update leaves behind. Replies with an error and returns
+    /// `false` if the rate is invalid or the envelope won't roll over.
+    async fn check_interest_rate(
+        ctx: poise::Context<'_, BotData, Error>,
+        envelope: &crate::entities::envelope::Model,
+        interest_rate: Option<f64>,
+        rollover: Option<bool>,
+    ) -> Result<bool> {
+        let Some(rate) = interest_rate.filter(|rate| *rate != 0.0) else {
+            return Ok(true);
+        };
+        if !rate.is_finite() || !(0.0..=100.0).contains(&rate) {
+            ctx.say("❌ Interest rate must be a percentage between 0 and 100.")
+                .await?;
+            return Ok(false);
+        }
+        if !rollover.unwrap_or(envelope.rollover) {
+            ctx.say(&format!(
+                "❌ Envelope '{}' doesn't roll over, so it can't earn interest.",
+                envelope.name
+            ))
+            .await?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Applies `/update_envelope interest_rate` once [`check_interest_rate`] has passed,
+    /// where zero turns interest off.
+    async fn apply_interest_rate(
+        ctx: poise::Context<'_, BotData, Error>,
+        envelope_id: i64,
+        interest_rate: Option<f64>,
+        changes: &mut Vec<String>,
+    ) -> Result<()> {
+        let Some(rate) = interest_rate else {
+            return Ok(());
+        };
+        let new_rate = (rate != 0.0).then_some(rate);
+        envelope::set_interest_rate(&ctx.data().database, envelope_id, new_rate).await?;
+        changes.push(new_rate.map_or_else(
+            || "interest to off".to_string(),
+            |r| format!("interest to {r}% monthly"),
+        ));
+        Ok(())
+    }
+
+    /// Applies a new daily limit from `/update_envelope`, where zero turns the limit off.
+    ///
+    /// Returns `None` after telling the user if the limit is invalid.
+    async fn update_daily_limit(
+        ctx: poise::Context<'_, BotData, Error>,
+        envelope: &crate::entities::envelope::Model,
+        limit: f64,
+        changes: &mut Vec<String>,
+    ) -> Result<Option<crate::entities::envelope::Model>> {
+        let new_limit = (limit != 0.0).then_some(limit);
+        let currency = setup::get_currency_format(&ctx.data().database).await?;
+        match envelope::set_daily_limit(&ctx.data().database, envelope.id, new_limit).await {
+            Ok(updated) => {
+                changes.push(new_limit.map_or_else(
+                    || "daily limit to off".to_string(),
+                    |l| format!("daily limit to {}", currency.format(l)),
+                ));
+                Ok(Some(updated))
+            }
+            Err(Error::InvalidAmount { amount }) => {
+                ctx.say(&format!(
+                    "❌ Invalid daily limit: {}. Limits must be positive.",
+                    currency.format(amount)
+                ))
+                .await?;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The contribution and goal an envelope has after `/update_envelope contribution` and
+    /// `goal`, where a goal of zero clears it.
+    fn sinking_fund_settings(
+        envelope: &crate::entities::envelope::Model,
+        contribution: Option<f64>,
+        goal: Option<f64>,
+    ) -> (f64, Option<f64>) {
+        let new_goal = goal.map_or(envelope.goal, |goal_amount| {
+            (goal_amount != 0.0).then_some(goal_amount)
+        });
+        (contribution.unwrap_or(envelope.contribution), new_goal)
+    }
+
+    /// Replies with why sinking-fund settings were rejected, passing on other errors.
+    async fn reply_sinking_fund_error(
+        ctx: poise::Context<'_, BotData, Error>,
+        e: Error,
+    ) -> Result<()> {
+        match e {
+            Error::InvalidAmount { amount } => {
+                let currency = setup::get_currency_format(&ctx.data().database).await?;
+                ctx.say(&format!(
+                    "❌ Invalid sinking-fund amount: {}. Contributions must be non-negative and goals positive.",
+                    currency.format(amount)
+                ))
+                .await?;
+                Ok(())
+            }
+            Error::Config { message } => {
+                ctx.say(&format!("❌ {message}")).await?;
+                Ok(())
+            }
+            e => Err(e),
+        }
+    }
+
+    /// Applies `/update_envelope contribution` and `goal` (see [`sinking_fund_settings`]).
+    /// Replies with the validation error and returns `None` for invalid settings.
+    async fn update_sinking_fund(
+        ctx: poise::Context<'_, BotData, Error>,
+        envelope: crate::entities::envelope::Model,
+        contribution: Option<f64>,
+        goal: Option<f64>,
+        changes: &mut Vec<String>,
+    ) -> Result<Option<crate::entities::envelope::Model>> {
+        if contribution.is_none() && goal.is_none() {
+            return Ok(Some(envelope));
+        }
+        let (new_contribution, new_goal) = sinking_fund_settings(&envelope, contribution, goal);
+        match envelope::set_sinking_fund(
+            &ctx.data().database,
+            envelope.id,
+            new_contribution,
+            new_goal,
+        )
+        .await
+        {
+            Ok(updated) => {
+                let currency = setup::get_currency_format(&ctx.data().database).await?;
+                if let Some(contrib) = contribution {
+                    changes.push(format!("contribution to {}", currency.format(contrib)));
+                }
+                if goal.is_some() {
+                    changes.push(new_goal.map_or_else(
+                        || "goal to none".to_string(),
+                        |goal_amount| format!("goal to {}", currency.format(goal_amount)),
+                    ));
+                }
+                Ok(Some(updated))
+            }
+            Err(e) => {
+                reply_sinking_fund_error(ctx, e).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Applies `/update_envelope private`, which only the owner of an individual envelope
+    /// may change. Replies with the refusal and returns `None` for anyone else.
+    async fn update_privacy(
+        ctx: poise::Context<'_, BotData, Error>,
+        envelope: crate::entities::envelope::Model,
+        private: Option<bool>,
+        author_id: &str,
+        changes: &mut Vec<String>,
+    ) -> Result<Option<crate::entities::envelope::Model>> {
+        let Some(is_private) = private else {
+            return Ok(Some(envelope));
+        };
+        if envelope.user_id.as_deref() != Some(author_id) {
+            ctx.say("❌ Only the owner of an individual envelope can change its privacy.")
+                .await?;
+            return Ok(None);
+        }
+        let updated = envelope::set_private(&ctx.data().database, envelope.id, is_private).await?;
+        changes.push(format!(
+            "visibility to {}",
+            if is_private { "private" } else { "household" }
+        ));
+        Ok(Some(updated))
+    }
+
+    /// Applies `/update_envelope pinned` and `sort_order`, where a sort order of zero
+    /// clears the position.
+    async fn update_display_order(
+        ctx: poise::Context<'_, BotData, Error>,
+        envelope: crate::entities::envelope::Model,
+        pinned: Option<bool>,
+        sort_order: Option<i32>,
+        changes: &mut Vec<String>,
+    ) -> Result<crate::entities::envelope::Model> {
+        if pinned.is_none() && sort_order.is_none() {
+            return Ok(envelope);
+        }
+        let new_pinned = pinned.unwrap_or(envelope.pinned);
+        let new_order =
+            sort_order.map_or(envelope.sort_order, |order| (order > 0).then_some(order));
+        let updated =
+            envelope::set_display_order(&ctx.data().database, envelope.id, new_pinned, new_order)
+                .await?;
+        if let Some(pinned) = pinned {
+            changes.push(if pinned { "pinned" } else { "unpinned" }.to_string());
+        }
+        if sort_order.is_some() {
+            changes.push(new_order.map_or_else(
+                || "sort order to off".to_string(),
+                |order| format!("sort order to {order}"),
+            ));
+        }
+        Ok(updated)
+    }
+
+    /// Applies `/update_envelope min_balance`, where zero removes the floor. Replies with
+    /// an error and returns `None` if the floor is invalid.
+    async fn update_min_balance(
+        ctx: poise::Context<'_, BotData, Error>,
+        envelope: crate::entities::envelope::Model,
+        floor: Option<f64>,
+        changes: &mut Vec<String>,
+    ) -> Result<Option<crate::entities::envelope::Model>> {
+        let Some(floor) = floor else {
+            return Ok(Some(envelope));
+        };
+        let new_floor = (floor != 0.0).then_some(floor);
+        let currency = setup::get_currency_format(&ctx.data().database).await?;
+        match envelope::set_min_balance(&ctx.data().database, envelope.id, new_floor).await {
+            Ok(updated) => {
+                changes.push(new_floor.map_or_else(
+                    || "minimum balance to off".to_string(),
+                    |f| format!("minimum balance to {}", currency.format(f)),
+                ));
+                Ok(Some(updated))
+            }
+            Err(Error::InvalidAmount { amount }) => {
+                ctx.say(&format!(
+                    "❌ Invalid minimum balance: {}. The floor must be non-negative.",
+                    currency.format(amount)
+                ))
+                .await?;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Applies `/update_envelope ping`, replying with an error and returning `None` if
+    /// the mention is invalid. `none` unlinks the ping; no mention leaves it unchanged.
+    async fn update_ping_mention(
+        ctx: poise::Context<'_, BotData, Error>,
+        envelope: crate::entities::envelope::Model,
+        mention: Option<&str>,
+        changes: &mut Vec<String>,
+    ) -> Result<Option<crate::entities::envelope::Model>> {
+        let Some(mention) = mention else {
+            return Ok(Some(envelope));
+        };
+        let new_mention = (!mention.trim().eq_ignore_ascii_case("none")).then_some(mention);
+        match envelope::set_ping_mention(&ctx.data().database, envelope.id, new_mention).await {
+            Ok(updated) => {
+                changes.push(updated.ping_mention.as_deref().map_or_else(
+                    || "overspend ping to off".to_string(),
+                    |m| format!("overspend ping to {m}"),
+                ));
+                Ok(Some(updated))
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Parent command for editing many envelope allocations at once.
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "ADMINISTRATOR",
+        subcommands("allocations_edit")
+    )]
+    pub async fn allocations(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Allocation command. Available subcommands:\n\
+            `/allocations edit` - Edit several envelopes' monthly allocations and apply them together";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Edits the monthly allocations of several envelopes and applies them together.
+    ///
+    /// Lists the envelopes (optionally one category's) with buttons that open modals for
+    /// entering new allocations. Nothing is saved until Apply, which writes every change
+    /// in one transaction and shows the before/after diff.
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "ADMINISTRATOR",
+        rename = "edit"
+    )]
+    pub async fn allocations_edit(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Only list envelopes in this category"]
+        #[autocomplete = "autocomplete::autocomplete_category"]
+        category: Option<String>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let envelopes: Vec<_> = envelope::get_all_active_envelopes(db)
+            .await?
+            .into_iter()
+            .filter(|env| {
+                category
+                    .as_deref()
+                    .is_none_or(|c| env.category.eq_ignore_ascii_case(c.trim()))
+            })
+            .collect();
+
+        if envelopes.is_empty() {
+            ctx.say("❌ No envelopes to edit.").await?;
+            return Ok(());
+        }
+        if envelopes.len() > allocation_editor::MAX_ENVELOPES {
+            ctx.say(format!(
+                "❌ There are {} envelopes; the editor lists at most {}. Pick a `category` to narrow the list.",
+                envelopes.len(),
+                allocation_editor::MAX_ENVELOPES
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        allocation_editor::edit_allocations(ctx, envelopes).await
+    }
+
+    /// Parent command for envelope maintenance operations.
+    #[poise::command(
+        slash_command,
+        rename = "envelope",
+        subcommands(
+            "envelope_rename",
+            "envelope_clone",
+            "envelope_merge",
+            "envelope_thread",
+            "envelope_weekly",
+            "envelope_rollover",
+            "envelope_freeze",
+            "envelope_unfreeze",
+            "envelope_spenders",
+            "envelope_fund_after",
+            "envelope_export",
+            "envelope_import"
+        )
+    )]
+    pub async fn envelope_manage(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Envelope maintenance command. Available subcommands:\n\
+            `/envelope rename` - Rename an envelope, keeping its history and product links\n\
+            `/envelope clone` - Copy an envelope's settings and products to a new envelope\n\
+            `/envelope merge` - Merge a duplicate envelope's transactions, products, and balance into another\n\
+            `/envelope thread` - Post a note in a Discord thread for each spend from an envelope\n\
+            `/envelope weekly` - Break an envelope's spending down by week in `/report`\n\
+            `/envelope rollover` - Show how much of each rollover envelope's balance was carried over\n\
+            `/envelope freeze` - Block new spends from an envelope, optionally until a date\n\
+            `/envelope unfreeze` - Allow spends from a frozen envelope again\n\
+            `/envelope spenders` - Show or limit who may spend from a shared envelope\n\
+            `/envelope fund_after` - Show or set the envelope that must be full before another is funded\n\
+            `/envelope export` - Download the current envelopes as a config.toml file\n\
+            `/envelope import` - Apply a config.toml file to the envelopes (admin only)";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Renames an envelope without losing its transactions or product links.
+    ///
+    /// Individual envelopes are renamed for every household member so products keep
+    /// resolving to each member's copy.
+    #[poise::command(slash_command, rename = "rename")]
+    pub async fn envelope_rename(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Current name of the envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        old: String,
+        #[description = "New name for the envelope"] new: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let user_id = ctx.author().id.to_string();
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &old, &user_id).await? else {
+            ctx.say(&format!("❌ Envelope '{old}' not found.")).await?;
+            return Ok(());
+        };
+
+        let renamed = match envelope::rename_envelope(db, envelope.id, &new).await {
+            Ok(renamed) => renamed,
+            Err(Error::EnvelopeAlreadyExists { name }) => {
+                ctx.say(&format!(
+                    "❌ An envelope named '{name}' already exists (possibly deleted). Choose a different name.",
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let new_name = renamed
+            .first()
+            .map_or_else(|| new.trim().to_string(), |env| env.name.clone());
+        let mut response = format!("✅ Renamed envelope **{old}** to **{new_name}**.");
+        if renamed.len() > 1 {
+            write!(&mut response, " Updated {} members' copies.", renamed.len())?;
+        }
+
+        let ids: Vec<i64> = renamed.iter().map(|env| env.id).collect();
+        let linked = product::get_products_for_envelopes(db, &ids).await?;
+        if !linked.is_empty() {
+            let names: Vec<&str> = linked.iter().map(|p| p.name.as_str()).collect();
+            write!(
+                &mut response,
+                "\n🔗 Linked products now show **{new_name}**: {}",
+                names.join(", ")
+            )?;
+        }
+
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Creates a new envelope with the settings and products of an existing one.
+    ///
+    /// Copies the category, rollover, thresholds, and linked products, which saves time
+    /// when creating seasonal variants such as "Vacation 2025". Product copies are named
+    /// after the new envelope, e.g. "Hotel (Vacation 2025)".
+    #[poise::command(slash_command, rename = "clone")]
+    pub async fn envelope_clone(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Envelope to copy"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        source: String,
+        #[description = "Name for the new envelope"] new_name: String,
+        #[description = "Monthly allocation (defaults to the source's)"] allocation: Option<f64>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let user_id = ctx.author().id.to_string();
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &source, &user_id).await? else {
+            ctx.say(&format!("❌ Envelope '{source}' not found."))
+                .await?;
+            return Ok(());
+        };
+
+        let currency = setup::get_currency_format(db).await?;
+        let cloned = match envelope::clone_envelope(db, envelope.id, &new_name, allocation).await {
+            Ok(cloned) => cloned,
+            Err(Error::EnvelopeAlreadyExists { name }) => {
+                ctx.say(&format!(
+                    "❌ An envelope named '{name}' already exists (possibly deleted). Choose a different name.",
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(Error::InvalidAmount { amount }) => {
+                ctx.say(&format!(
+                    "❌ Invalid allocation {}: must be zero or more.",
+                    currency.format(amount)
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let Some(copy) = cloned.envelopes.first() else {
+            return Ok(());
+        };
+        let mut response = format!(
+            "✅ Cloned **{}** to **{}** ({}/month).",
+            envelope.name,
+            copy.name,
+            currency.format(copy.allocation)
+        );
+        if cloned.envelopes.len() > 1 {
+            write!(
+                &mut response,
+                " Created {} members' copies.",
+                cloned.envelopes.len()
+            )?;
+        }
+        if !cloned.products.is_empty() {
+            let names: Vec<&str> = cloned
+                .products
+                .iter()
+                .map(|product| product.name.as_str())
+                .collect();
+            write!(&mut response, "\nCopied products: {}", names.join(", "))?;
+        }
+
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Merges a duplicate envelope into another one.
+    ///
+    /// Moves the source's transactions, product links, and balance to the target and
+    /// soft-deletes the source, all at once. Both envelopes must be shared, or both the
+    /// author's own. Past monthly statements stay with the source.
+    #[poise::command(slash_command, rename = "merge")]
+    pub async fn envelope_merge(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Duplicate envelope to merge away"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        source: String,
+        #[description = "Envelope to keep"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        target: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let user_id = ctx.author().id.to_string();
+
+        let mut found = Vec::with_capacity(2);
+        for name in [&source, &target] {
+            let Some(envelope) = envelope::find_envelope_for_user(db, name, &user_id).await? else {
+                ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
+                return Ok(());
+            };
+            found.push(envelope);
+        }
+        let (Some(target_env), Some(source_env)) = (found.pop(), found.pop()) else {
+            return Ok(());
+        };
+
+        let merged = match envelope::merge_envelopes(db, source_env.id, target_env.id).await {
+            Ok(merged) => merged,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
         };
 
-        // Check if envelope already exists
-        let existing = if let Some(uid) = &user_id {
-            envelope::get_envelope_by_name_and_user(db, &name, uid).await?
-        } else {
-            envelope::get_shared_envelope_by_name(db, &name).await?
+        tracing::info!(
+            "Envelope merge: user {} merged '{}' into '{}' ({} transactions, {:.2})",
+            user_id,
+            source_env.name,
+            merged.target.name,
+            merged.transactions,
+            merged.balance
+        );
+        let currency = setup::get_currency_format(db).await?;
+        let response = format!(
+            "✅ Merged **{}** into **{}**: moved {} transaction(s), {} product link(s), and \
+             {}. **{}** now has {}; **{}** was deleted.\n\
+             Past monthly statements stay with **{}**, so they won't match **{}**'s \
+             transaction history.",
+            source_env.name,
+            merged.target.name,
+            merged.transactions,
+            merged.products,
+            currency.format(merged.balance),
+            merged.target.name,
+            currency.format(merged.target.balance),
+            source_env.name,
+            source_env.name,
+            merged.target.name
+        );
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Links an envelope to a Discord thread where a note is posted for each spend.
+    ///
+    /// Without `thread`, a new thread is created in this channel. Private envelopes can't
+    /// be linked, since threads are visible to the whole server.
+    #[poise::command(slash_command, rename = "thread", guild_only)]
+    pub async fn envelope_thread(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        name: String,
+        #[description = "Existing thread or channel to use (leave empty to create a thread)"]
+        #[channel_types("PublicThread", "PrivateThread", "Text")]
+        thread: Option<poise::serenity_prelude::ChannelId>,
+        #[description = "Stop posting spend notes for this envelope"] unlink: Option<bool>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let user_id = ctx.author().id.to_string();
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &name, &user_id).await? else {
+            ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
+            return Ok(());
         };
 
-        if existing.is_some() {
+        if unlink == Some(true) {
+            envelope::set_thread(db, envelope.id, None).await?;
             ctx.say(&format!(
-                "❌ Envelope '{name}' already exists. Use a different name or delete the existing envelope first.",
+                "✅ Spend notes for **{}** are no longer posted.",
+                envelope.name
             ))
             .await?;
             return Ok(());
         }
+        if envelope.is_private {
+            ctx.say("❌ Private envelopes can't post spend notes to a thread.")
+                .await?;
+            return Ok(());
+        }
 
-        // Create the envelope
-        let new_envelope = envelope::create_envelope(
-            db,
-            name.clone(),
-            user_id,
-            category.clone(),
-            allocation,
-            is_individual.unwrap_or(false),
-            rollover.unwrap_or(false),
-        )
-        .await?;
-
-        let type_str = if new_envelope.is_individual {
-            "individual"
+        let thread_id = if let Some(thread_id) = thread {
+            thread_id
         } else {
-            "shared"
+            if let Some(existing) = &envelope.thread_id {
+                ctx.say(&format!(
+                    "🧵 **{}** already posts spend notes in <#{existing}>. Pass `thread:` to \
+                     move them, or `unlink:true` to stop.",
+                    envelope.name
+                ))
+                .await?;
+                return Ok(());
+            }
+            match notifications::create_envelope_thread(
+                ctx.http(),
+                ctx.channel_id(),
+                &envelope.name,
+            )
+            .await
+            {
+                Ok(thread_id) => thread_id,
+                Err(e) => {
+                    tracing::warn!("Failed to create thread for envelope {}: {e}", envelope.id);
+                    ctx.say(
+                        "❌ Couldn't create a thread here. Check that I can create threads \
+                         in this channel, or pass an existing `thread:`.",
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            }
         };
-        let rollover_str = if new_envelope.rollover {
-            "with rollover enabled"
-        } else {
-            "without rollover"
+
+        envelope::set_thread(db, envelope.id, Some(thread_id.to_string())).await?;
+        ctx.say(&format!(
+            "✅ Spends from **{}** will be noted in <#{thread_id}>.",
+            envelope.name
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Turns weekly mode on or off for an envelope.
+    ///
+    /// In weekly mode, `/report` also lists the envelope's spending for each week of the
+    /// budget month so far, compared to the week's share of the monthly allocation.
+    #[poise::command(slash_command, rename = "weekly")]
+    pub async fn envelope_weekly(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        name: String,
+        #[description = "Show weekly spending (default: true)"] enabled: Option<bool>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let user_id = ctx.author().id.to_string();
+        let enabled = enabled.unwrap_or(true);
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &name, &user_id).await? else {
+            ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
+            return Ok(());
         };
 
+        let updated = envelope::set_weekly(db, envelope.id, enabled).await?;
         ctx.say(&format!(
-            "✅ Created {type_str} envelope **{name}** in category '{category}' with ${allocation:.2} monthly allocation {rollover_str}!"
+            "✅ Weekly spending for **{}** is now {} in `/report`.",
+            updated.name,
+            if enabled { "shown" } else { "hidden" }
         ))
         .await?;
+        Ok(())
+    }
+
+    /// Shows how much of each rollover envelope's balance was carried over.
+    ///
+    /// Each balance is split into the surplus carried over from earlier months, this
+    /// month's funding, and this month's transactions, with a sparkline of the surplus
+    /// its recent closed months ended with.
+    #[poise::command(slash_command, rename = "rollover")]
+    pub async fn envelope_rollover(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        const HISTORY_MONTHS: usize = 12;
+
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let today = calendar::today(db).await?;
+        let accumulation: Vec<report::RolloverAccumulation> =
+            report::rollover_accumulation(db, today)
+                .await?
+                .into_iter()
+                .filter(|entry| report::can_view(&entry.envelope, &author_id))
+                .collect();
+
+        if accumulation.is_empty() {
+            ctx.say("🔁 No rollover envelopes. Enable rollover with `/update_envelope`.")
+                .await?;
+            return Ok(());
+        }
 
+        let currency = setup::get_currency_format(db).await?;
+        let mut response = "🔁 **Rollover Accumulation**\n".to_string();
+        for entry in &accumulation {
+            let env = &entry.envelope;
+            let label =
+                envelope_label(ctx, &env.name, env.is_individual, env.user_id.as_deref()).await;
+            write!(
+                &mut response,
+                "\n• **{label}** - Balance: {} = {} carried over + {} this month's funding \
+                 {} in transactions",
+                currency.format(env.balance),
+                currency.format(entry.carried_over),
+                currency.format(entry.funded),
+                report::format_transaction_amount(entry.net_this_month, currency)
+            )?;
+            let recent = &entry.history[entry.history.len().saturating_sub(HISTORY_MONTHS)..];
+            if let (Some((first_month, _)), Some((last_month, last_close))) =
+                (recent.first(), recent.last())
+            {
+                let closes: Vec<f64> = recent.iter().map(|(_, close)| *close).collect();
+                write!(
+                    &mut response,
+                    "\n  Surplus at month end: {} ({first_month} to {last_month}, now {})",
+                    snapshot::sparkline(&closes),
+                    currency.format(*last_close)
+                )?;
+            }
+        }
+        ctx.say(response).await?;
         Ok(())
     }
 
-    /// Deletes an envelope from the system.
+    /// Blocks new spends from an envelope until it is unfrozen or `until_date` passes.
     ///
-    /// This command performs a soft delete on the envelope, marking it as deleted
-    /// while preserving historical transaction data. The envelope will no longer
-    /// appear in reports or be available for new transactions.
-    #[poise::command(slash_command, prefix_command)]
-    pub async fn delete_envelope(
+    /// Adding funds and the monthly update still work while frozen.
+    #[poise::command(slash_command, rename = "freeze")]
+    pub async fn envelope_freeze(
         ctx: poise::Context<'_, BotData, Error>,
-        #[description = "Name of the envelope to delete"] name: String,
-        #[description = "User ID (for individual envelopes)"] user: Option<String>,
+        #[description = "Name of the envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        name: String,
+        #[description = "Last day of the freeze (YYYY-MM-DD); omit to freeze until unfrozen"]
+        until_date: Option<String>,
     ) -> Result<()> {
         let db = &ctx.data().database;
-        let user_id = user.unwrap_or_else(|| ctx.author().id.to_string());
+        let user_id = ctx.author().id.to_string();
 
-        // Try to find the envelope - first check user's individual envelope, then shared
-        let envelope = if let Some(env) =
-            envelope::get_envelope_by_name_and_user(db, &name, &user_id).await?
+        let until = match until_date.as_deref().map(report::parse_date).transpose() {
+            Ok(until) => until,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(until) = until
+            && until < calendar::today(db).await?
         {
-            Some(env)
-        } else {
-            envelope::get_shared_envelope_by_name(db, &name).await?
+            ctx.say(&format!("❌ {until} has already passed.")).await?;
+            return Ok(());
+        }
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &name, &user_id).await? else {
+            ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
+            return Ok(());
         };
 
-        let Some(envelope) = envelope else {
+        let frozen = envelope::set_frozen(db, envelope.id, true, until).await?;
+        let duration = frozen.frozen_until.map_or_else(
+            || "until unfrozen".to_string(),
+            |until| format!("through {until}"),
+        );
+        ctx.say(&format!(
+            "🧊 **{}** is frozen {duration}. New spends from it will be blocked; \
+             use `/envelope unfreeze` to lift the freeze early.",
+            frozen.name
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Lifts a freeze so spends from the envelope are allowed again.
+    #[poise::command(slash_command, rename = "unfreeze")]
+    pub async fn envelope_unfreeze(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        name: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let user_id = ctx.author().id.to_string();
+
+        let Some(envelope) = envelope::find_envelope_for_user(db, &name, &user_id).await? else {
             ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
             return Ok(());
         };
 
-        // Perform soft delete
-        let mut active_model: crate::entities::envelope::ActiveModel = envelope.clone().into();
-        active_model.is_deleted = sea_orm::ActiveValue::Set(true);
-        active_model.update(db).await?;
+        if !envelope.frozen {
+            ctx.say(&format!("ℹ️ **{}** isn't frozen.", envelope.name))
+                .await?;
+            return Ok(());
+        }
 
+        let unfrozen = envelope::set_frozen(db, envelope.id, false, None).await?;
         ctx.say(&format!(
-            "✅ Deleted envelope **{name}**. Historical transaction data has been preserved.",
+            "✅ **{}** is unfrozen; spends are allowed again.",
+            unfrozen.name
         ))
         .await?;
-
         Ok(())
     }
 
-    /// Updates an envelope's allocation or settings.
+    /// Shows or limits which members may spend from a shared envelope.
     ///
-    /// This command allows modifying an existing envelope's monthly allocation,
-    /// rollover setting, or category without creating a new envelope.
-    #[poise::command(slash_command, prefix_command)]
-    pub async fn update_envelope(
+    /// Without options, lists the envelope's spenders. Admins can `add` or `remove` a
+    /// spender, or `clear` them all to let every member spend from it again.
+    #[poise::command(slash_command, rename = "spenders", guild_only)]
+    pub async fn envelope_spenders(
         ctx: poise::Context<'_, BotData, Error>,
-        #[description = "Name of the envelope to update"] name: String,
-        #[description = "New monthly allocation (optional)"] allocation: Option<f64>,
-        #[description = "Enable/disable rollover (optional)"] rollover: Option<bool>,
-        #[description = "New category (optional)"] category: Option<String>,
-        #[description = "User ID (for individual envelopes)"] user: Option<String>,
+        #[description = "Name of the shared envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        name: String,
+        #[description = "Member to allow to spend from it (admins only)"] add: Option<
+            serenity::User,
+        >,
+        #[description = "Member to stop from spending from it (admins only)"] remove: Option<
+            serenity::User,
+        >,
+        #[description = "Let every member spend from it again (admins only)"] clear: Option<bool>,
     ) -> Result<()> {
         let db = &ctx.data().database;
-        let user_id = user.unwrap_or_else(|| ctx.author().id.to_string());
+        let author_id = ctx.author().id.to_string();
 
-        if allocation.is_none() && rollover.is_none() && category.is_none() {
-            ctx.say(
-                "❌ Please specify at least one field to update (allocation, rollover, or category).",
-            )
+        let Some(envelope) = envelope::get_shared_envelope_by_name(db, &name).await? else {
+            ctx.say(&format!(
+                "❌ Shared envelope '{name}' not found. Spend permissions only apply to shared envelopes.",
+            ))
             .await?;
             return Ok(());
+        };
+
+        let clear = clear.unwrap_or(false);
+        let changing = add.is_some() || remove.is_some() || clear;
+        if changing && !permissions::is_admin(ctx).await {
+            ctx.say("❌ Only admins can change who may spend from an envelope.")
+                .await?;
+            return Ok(());
         }
 
-        if let Some(alloc) = allocation {
-            if alloc.is_nan() || alloc.is_infinite() {
-                ctx.say("❌ Invalid allocation: must be a valid number")
-                    .await?;
-                return Ok(());
+        let mut response = String::new();
+        if clear {
+            let removed = envelope_permission::clear_spenders(db, envelope.id).await?;
+            writeln!(&mut response, "✅ Removed {removed} spender(s).")?;
+        }
+        if let Some(user) = &add {
+            if envelope_permission::grant_spender(db, &envelope, &user.id.to_string(), &author_id)
+                .await?
+            {
+                writeln!(&mut response, "✅ <@{}> may now spend from it.", user.id)?;
+            } else {
+                writeln!(
+                    &mut response,
+                    "ℹ️ <@{}> could already spend from it.",
+                    user.id
+                )?;
             }
-            if alloc < 0.0 {
-                ctx.say("❌ Allocation must be a non-negative number.")
-                    .await?;
-                return Ok(());
+        }
+        if let Some(user) = &remove {
+            if envelope_permission::revoke_spender(db, envelope.id, &user.id.to_string()).await? {
+                writeln!(
+                    &mut response,
+                    "✅ <@{}> may no longer spend from it.",
+                    user.id
+                )?;
+            } else {
+                writeln!(
+                    &mut response,
+                    "ℹ️ <@{}> wasn't one of its spenders.",
+                    user.id
+                )?;
             }
         }
 
-        // Try to find the envelope - first check user's individual envelope, then shared
-        let envelope = if let Some(env) =
-            envelope::get_envelope_by_name_and_user(db, &name, &user_id).await?
-        {
-            Some(env)
+        let spenders = envelope_permission::get_spenders(db, envelope.id).await?;
+        if spenders.is_empty() {
+            write!(
+                &mut response,
+                "👥 Every member may spend from **{}**.",
+                envelope.name
+            )?;
         } else {
-            envelope::get_shared_envelope_by_name(db, &name).await?
-        };
+            write!(
+                &mut response,
+                "🔒 Only {} may spend from **{}**; everyone can still see it and add funds.",
+                envelope_permission::format_spenders(&spenders),
+                envelope.name
+            )?;
+        }
+        ctx.send(
+            poise::CreateReply::default()
+                .content(response)
+                .allowed_mentions(serenity::CreateAllowedMentions::new()),
+        )
+        .await?;
+        Ok(())
+    }
 
-        let Some(envelope) = envelope else {
-            ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
+    /// Shows or sets the funding rule of a shared envelope.
+    ///
+    /// With `requires`, the envelope is funded by `/addfunds distribute` and the monthly
+    /// update only once `requires` is full (at its goal, or its allocation without one);
+    /// until then its funding goes to `requires`. Admins can set or `clear` the rule.
+    #[poise::command(slash_command, rename = "fund_after", guild_only)]
+    pub async fn envelope_fund_after(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the shared envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        name: String,
+        #[description = "Shared envelope that must be full first (admins only)"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        requires: Option<String>,
+        #[description = "Fund the envelope regardless of others again (admins only)"] clear: Option<
+            bool,
+        >,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let Some(envelope) = envelope::get_shared_envelope_by_name(db, &name).await? else {
+            ctx.say(&format!(
+                "❌ Shared envelope '{name}' not found. Funding rules only apply to shared envelopes.",
+            ))
+            .await?;
             return Ok(());
         };
 
-        // Update the envelope
-        let mut active_model: crate::entities::envelope::ActiveModel = envelope.into();
-
-        let mut changes = Vec::new();
+        let clear = clear.unwrap_or(false);
+        if (requires.is_some() || clear) && !permissions::is_admin(ctx).await {
+            ctx.say("❌ Only admins can change funding rules.").await?;
+            return Ok(());
+        }
 
-        if let Some(alloc) = allocation {
-            active_model.allocation = sea_orm::ActiveValue::Set(alloc);
-            changes.push(format!("allocation to ${alloc:.2}"));
+        if clear {
+            allocation::set_funding_rule(db, envelope.id, None).await?;
+            ctx.say(&format!(
+                "✅ **{}** is funded regardless of other envelopes again.",
+                envelope.name
+            ))
+            .await?;
+            return Ok(());
         }
-        if let Some(roll) = rollover {
-            active_model.rollover = sea_orm::ActiveValue::Set(roll);
-            changes.push(format!(
-                "rollover to {}",
-                if roll { "enabled" } else { "disabled" }
-            ));
+
+        if let Some(requires) = requires {
+            let Some(prerequisite) = envelope::get_shared_envelope_by_name(db, &requires).await?
+            else {
+                ctx.say(&format!("❌ Shared envelope '{requires}' not found."))
+                    .await?;
+                return Ok(());
+            };
+            match allocation::set_funding_rule(db, envelope.id, Some(prerequisite.id)).await {
+                Ok(()) => {}
+                Err(Error::Config { message }) => {
+                    ctx.say(&format!("❌ {message}")).await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+            ctx.say(&format!(
+                "✅ **{}** will only be funded once **{}** is full ({}); until then its \
+                 funding goes to **{}**.",
+                envelope.name,
+                prerequisite.name,
+                setup::get_currency_format(db)
+                    .await?
+                    .format(allocation::funding_target(&prerequisite)),
+                prerequisite.name
+            ))
+            .await?;
+            return Ok(());
         }
-        if let Some(ref cat) = category {
-            active_model.category = sea_orm::ActiveValue::Set(cat.clone());
-            changes.push(format!("category to '{cat}'"));
+
+        let rules = allocation::get_funding_rules(db).await?;
+        let prerequisite = match rules.get(&envelope.id) {
+            Some(&requires_id) => envelope::get_envelope_by_id(db, requires_id)
+                .await?
+                .filter(|env| !env.is_deleted),
+            None => None,
+        };
+        let currency = setup::get_currency_format(db).await?;
+        let response = prerequisite.map_or_else(
+            || {
+                format!(
+                    "💰 **{}** is funded regardless of other envelopes.",
+                    envelope.name
+                )
+            },
+            |prerequisite| {
+                format!(
+                    "💰 **{}** is only funded once **{}** is full: {} of {}{}",
+                    envelope.name,
+                    prerequisite.name,
+                    currency.format(prerequisite.balance),
+                    currency.format(allocation::funding_target(&prerequisite)),
+                    if allocation::is_full(&prerequisite) {
+                        " ✅"
+                    } else {
+                        ""
+                    }
+                )
+            },
+        );
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Largest config.toml attachment `/envelope import` accepts, in bytes.
+    const MAX_CONFIG_IMPORT_SIZE: u32 = 256 * 1024;
+
+    /// Exports the current envelopes as a config.toml file.
+    ///
+    /// The first envelopes are previewed as a table in the reply. The file has the same format as config.toml, so it can seed another bot or be
+    /// applied again with `/envelope import`.
+    #[poise::command(slash_command, rename = "export")]
+    pub async fn envelope_export(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let snapshot = config_sync::export_envelopes(db).await?;
+        let text = config::envelopes::format_config(&snapshot)?;
+        let preview = config_sync::envelope_table(&snapshot).preview(table::PREVIEW_ROWS);
+        ctx.send(
+            poise::CreateReply::default()
+                .content(format!(
+                    "📤 Exported {} envelope(s) in config.toml format.\n```\n{preview}\n```",
+                    snapshot.envelopes.len()
+                ))
+                .attachment(serenity::CreateAttachment::bytes(
+                    text.into_bytes(),
+                    "config.toml",
+                )),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Applies a config.toml file to the envelopes, like `/reload_config` does for the
+    /// file on disk.
+    ///
+    /// The file's first envelopes are shown as a table before anything changes. Shared
+    /// envelopes missing from the database are created, and existing envelopes have their
+    /// category, allocation, rollover, and sinking-fund settings updated. Nothing is
+    /// deleted and balances are never touched.
+    #[poise::command(
+        slash_command,
+        rename = "import",
+        guild_only,
+        required_permissions = "ADMINISTRATOR"
+    )]
+    pub async fn envelope_import(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "config.toml file, e.g. from /envelope export"] file: serenity::Attachment,
+    ) -> Result<()> {
+        if file.size > MAX_CONFIG_IMPORT_SIZE {
+            ctx.say(&format!(
+                "❌ '{}' is too large to import (limit {} KB).",
+                file.filename,
+                MAX_CONFIG_IMPORT_SIZE / 1024
+            ))
+            .await?;
+            return Ok(());
         }
 
-        let _updated = active_model.update(db).await?;
+        let db = &ctx.data().database;
+        ctx.defer().await?;
 
-        ctx.say(&format!(
-            "✅ Updated envelope **{}**: {}",
-            name,
-            changes.join(", ")
+        let bytes = file.download().await?;
+        let Ok(content) = String::from_utf8(bytes) else {
+            ctx.say(&format!("❌ '{}' is not a UTF-8 text file.", file.filename))
+                .await?;
+            return Ok(());
+        };
+        let imported = match config::envelopes::parse_config(&content) {
+            Ok(imported) => imported,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Show what the file holds before it is applied
+        let preview = config_sync::envelope_table(&imported).preview(table::PREVIEW_ROWS);
+        ctx.say(format!(
+            "📄 '{}' has {} envelope(s):\n```\n{preview}\n```",
+            file.filename,
+            imported.envelopes.len()
         ))
         .await?;
 
+        let result = match config_sync::sync_envelopes(db, &imported).await {
+            Ok(result) => result,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let summary = config_sync::format_sync_summary(&result)?;
+        ctx.say(format!(
+            "📥 **Imported '{}'**\n\n```\n{summary}\n```",
+            file.filename
+        ))
+        .await?;
         Ok(())
     }
 }
@@ -0,0 +1,203 @@
+//! Favorite Discord commands - `/favorite` management and the `/f` quick spend.
+//!
+//! Members save spends they repeat often (parking, coffee) with `/favorite add`, then
+//! log them again with `/f <label>`. Favorites are private to the member who saved them.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::{
+            BotData, delivery,
+            handlers::{alerts, autocomplete},
+            notifications,
+        },
+        core::{envelope, favorite, setup},
+        errors::{Error, Result},
+    };
+    use std::fmt::Write;
+
+    /// Parent command for managing your saved spends.
+    #[poise::command(
+        slash_command,
+        subcommands("favorite_add", "favorite_list", "favorite_remove")
+    )]
+    pub async fn favorite(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Favorite command. Available subcommands:\n\
+            `/favorite add` - Save a spend you repeat often\n\
+            `/favorite list` - Show your favorites\n\
+            `/favorite remove` - Delete a favorite\n\
+            Log a favorite with `/f <label>`.";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Saves a spend you repeat often so `/f <name>` logs it in one step.
+    ///
+    /// Saving a favorite with an existing name replaces it.
+    #[poise::command(slash_command, rename = "add")]
+    pub async fn favorite_add(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Short label for the favorite (e.g., parking)"] name: String,
+        #[description = "Envelope the spend comes from"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope: String,
+        #[description = "Amount to spend"] amount: f64,
+        #[description = "Description recorded on the spend (defaults to the name)"]
+        description: Option<String>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+
+        let Some(found) = envelope::find_envelope_for_user(db, &envelope, &author_id).await? else {
+            ctx.say(&format!(
+                "❌ Envelope '{envelope}' not found. Use `/envelopes` to see available envelopes.",
+            ))
+            .await?;
+            return Ok(());
+        };
+
+        let description = description.unwrap_or_else(|| name.trim().to_string());
+        match favorite::save_favorite(db, &author_id, &name, found.id, amount, description).await {
+            Ok(saved) => {
+                ctx.say(&format!(
+                    "⭐ Saved favorite '{}': {} from '{}' - {}. Use it with `/f {}`.",
+                    saved.label,
+                    setup::get_currency_format(db).await?.format(saved.amount),
+                    found.name,
+                    saved.description,
+                    saved.label
+                ))
+                .await?;
+            }
+            Err(Error::InvalidAmount { .. }) => {
+                ctx.say("❌ Invalid amount: must be a number greater than zero")
+                    .await?;
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    /// Lists your saved favorites.
+    #[poise::command(slash_command, rename = "list")]
+    pub async fn favorite_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let favorites = favorite::get_favorites_for_user(db, &ctx.author().id.to_string()).await?;
+
+        if favorites.is_empty() {
+            ctx.say("ℹ️ You have no favorites yet. Save one with `/favorite add`.")
+                .await?;
+            return Ok(());
+        }
+
+        let currency = setup::get_currency_format(db).await?;
+        let mut response = String::from("⭐ **Your favorites:**\n");
+        for fav in &favorites {
+            let envelope_name = envelope::get_envelope_by_id(db, fav.envelope_id)
+                .await?
+                .map_or_else(|| "unknown".to_string(), |env| env.name);
+            writeln!(
+                &mut response,
+                "• `{}` - {} from '{envelope_name}' - {}",
+                fav.label,
+                currency.format(fav.amount),
+                fav.description
+            )?;
+        }
+
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Deletes one of your favorites.
+    #[poise::command(slash_command, rename = "remove")]
+    pub async fn favorite_remove(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Label of the favorite to delete"]
+        #[autocomplete = "autocomplete::autocomplete_favorite_label"]
+        name: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        if favorite::delete_favorite(db, &ctx.author().id.to_string(), &name).await? {
+            ctx.say(&format!("✅ Favorite '{name}' has been deleted."))
+                .await?;
+        } else {
+            ctx.say(&format!("❌ You have no favorite named '{name}'."))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Logs one of your favorite spends.
+    #[poise::command(slash_command, rename = "f")]
+    pub async fn favorite_use(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Label of the favorite to log"]
+        #[autocomplete = "autocomplete::autocomplete_favorite_label"]
+        label: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let Some(fav) = favorite::get_favorite(db, &ctx.author().id.to_string(), &label).await?
+        else {
+            ctx.say(&format!(
+                "❌ You have no favorite named '{label}'. Save one with `/favorite add`."
+            ))
+            .await?;
+            return Ok(());
+        };
+
+        let currency = setup::get_currency_format(db).await?;
+        let reply = delivery::prepare(ctx).await?;
+        let spend = match favorite::use_favorite(db, &fav, Some(ctx.id().to_string())).await {
+            Ok(spend) => spend,
+            Err(Error::InsufficientFunds { current, required }) => {
+                ctx.say(&format!(
+                    "❌ Not enough funds: the envelope has {}, the spend needs {}.",
+                    currency.format(current),
+                    currency.format(required)
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(Error::EnvelopeNotFound { .. }) => {
+                ctx.say(&format!(
+                    "❌ The envelope for favorite '{}' no longer exists. Save it again with `/favorite add`.",
+                    fav.label
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let envelope_name = envelope::get_envelope_by_id(db, fav.envelope_id)
+            .await?
+            .map_or_else(|| "unknown".to_string(), |env| env.name);
+        delivery::confirm(
+            ctx,
+            reply,
+            format!(
+                "✅ Spent {} from envelope '{envelope_name}' - {} (Transaction ID: {})",
+                currency.format(spend.amount.abs()),
+                spend.description,
+                spend.id
+            ),
+        )
+        .await;
+        alerts::after_balance_change(ctx, &[fav.envelope_id]).await;
+        notifications::post_spend_notes(ctx, &[spend]).await;
+
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
@@ -29,14 +29,74 @@ mod inner {
         let help_text = "**EnvelopeBuddy Help**\n\
         Here is a summary of all available commands for EnvelopeBuddy.\n\n\
         **Action Commands**\n\
-        • `/spend <envelope> <amount> [user] [desc]` - Records an expense from an envelope.\n\
-        • `/addfunds <envelope> <amount> [user] [desc]` - Adds funds to an envelope.\n\
-        • `/use_product <product> [quantity]` - Logs an expense using a predefined product.\n\
-        • `/report` - Shows a full financial report of all envelopes.\n\n\
+        • `/spend <envelope> <amount> [user] [desc] [currency] [override] [pending] [merchant] [quantity] [unit] [location]` - Records an expense from an envelope; the amount can be math like `45/3`. Large spends wait for another member's approval.\n\
+        • `/again [amount]` - Repeats your last spend after confirming the amount and description in a form.\n\
+        • *Apps → Log as expense* (right-click a message) - Logs the amount in a message, such as a bank alert, as a spend.\n\
+        • `/addfunds <amount> <envelope> [user] [desc]` - Adds funds to an envelope.\n\
+        • `/addfunds <amount> distribute:<by_allocation|by_plan> [user] [desc]` - Spreads a deposit across envelopes.\n\
+        • `/distribution_plan [plan]` - Shows or sets the percentage plan, e.g. `Groceries:40, Rent:50, Fun:10`.\n\
+        • `/f <label>` - Logs one of your saved favorite spends.\n\
+        • `/refund <transaction> [amount]` - Refunds part or all of a previous spend.\n\
+        • `/transaction clear <id> [amount]` - Clears a pending spend with its posted amount.\n\
+        • `/activity [count] [user] [type]` - Shows the latest transactions across all envelopes.\n\
+        • `/transaction pending` - Lists spends that are still pending.\n\
+        • `/transaction purge [envelope] [from] [to] [type] [user]` - Deletes matching transactions after a preview (admins).\n\
+        • `/undo list`, `/undo last`, `/undo entry <id>` - Lists or reverses your last 10 changes, in any order.\n\
+        • `/use_product <product> [quantity] [envelope]` - Logs an expense using a predefined product.\n\
+        • `/use_product bundle:<name>` - Logs every product in a bundle at once.\n\
+        • `/return_product <product> [quantity]` - Returns units of a product you used, crediting its envelope.\n\
+        • `/report summary [period] [from] [to]` - Shows a household summary and a full report of all envelopes, optionally over a date range.\n\
+        • `/report trend <envelope>` - Shows an envelope's balance over the last 90 days.\n\
+        • `/report category` / `/report merchants` - Shows this month's spending per category or by merchant.\n\
+        • `/report stale <months>` - Lists envelopes unused for that many months.\n\
+        • `/report variance [month]` - Shows a budget vs actual table for a month.\n\
+        • `/statement show [month]` / `/statement pdf <month>` - Shows a past month's statement, or attaches it as a PDF.\n\
+        • `/balance_at <envelope> <date> [user]` - Shows an envelope's balance at the end of a past day.\n\
+        • `/allowance` - Shows your own allowances and how much you saved compared to last month.\n\
+        • `/iou add <user> <amount> [desc] [transaction]` - Records that a member owes you money.\n\
+        • `/iou settle <user>` - Marks everything owed between you and a member as paid.\n\
+        • `/wishlist propose <envelope> <amount> <desc> [approver]` - Asks your partner to approve a purchase.\n\
+        • `/wishlist list` - Shows proposals waiting for a response.\n\
+        • `/list add [envelope] [item] [product] [quantity]` - Adds something to buy to an envelope's shopping list.\n\
+        • `/list show <envelope>` - Shows a shopping list with menus to check items off, optionally logging the spend.\n\
+        • `/list clear <envelope>` - Removes checked-off items from a shopping list.\n\
+        • `/schedule spend <envelope> <amount> <date> <desc>` - Schedules a spend for a future date.\n\n\
         **Management Commands**\n\
         • `/manage envelope <subcommand>` - Manage envelopes (create, delete, edit, list).\n\
-        • `/manage product <subcommand>` - Manage products (add, delete, update, list).\n\n\
+        • `/manage product <subcommand>` - Manage products (add, delete, update, list, import, describe).\n\
+        • `/favorite <subcommand>` - Manage your favorite spends (add, list, remove).\n\
+        • `/schedule <subcommand>` - Manage your scheduled spends (spend, list, cancel).\n\
+        • `/bill <subcommand>` - Manage recurring bills and their reminders (add, list, remove).\n\
+        • `/bundle <subcommand>` - Manage product bundles (create, add, remove, list, delete).\n\
+        • `/envelope rename <old> <new>` - Renames an envelope, keeping its history.\n\
+        • `/envelope clone <source> <new_name> [allocation]` - Copies an envelope's settings and products to a new one.\n\
+        • `/envelope merge <source> <target>` - Merges a duplicate envelope's transactions, products, and balance into another.\n\
+        • `/envelope thread <envelope> [thread] [unlink]` - Posts a note in a thread for each spend from an envelope.\n\
+        • `/envelope weekly <envelope> [enabled]` - Shows an envelope's spending by week in `/report`.\n\
+        • `/envelope rollover` - Shows how much of each rollover envelope's balance was carried over from earlier months.\n\
+        • `/envelope freeze <envelope> [until_date]` - Blocks new spends from an envelope until unfrozen or the date passes.\n\
+        • `/envelope unfreeze <envelope>` - Allows spends from a frozen envelope again.\n\
+        • `/envelope spenders <envelope> [add] [remove] [clear]` - Shows or limits who may spend from a shared envelope.\n\
+        • `/envelope fund_after <envelope> [requires] [clear]` - Shows or sets the envelope that must be full before a shared envelope is funded.\n\
+        • `/envelope export` / `/envelope import <file>` - Downloads the envelopes as config.toml, or applies such a file (import is admin-only).\n\
+        • `/preferences view` / `/preferences set <key> <value>` - Shows or changes your personal preferences.\n\
+        • `/verbosity [mode]` - Shows or sets whether your confirmations are compact one-liners or detailed embeds.\n\
+        • `/daily_limit [amount]` - Shows or sets your daily spending limit (0 disables).\n\
+        • `/category_cap <category> [amount] [hard]` - Shows or sets a category's monthly spending cap (0 removes it).\n\n\
         **Utility Commands**\n\
+        • `/setup` - Walks through first-time configuration (admins).\n\
+        • `/retention [months]` - Shows or sets how many months of transactions are kept (admins).\n\
+        • `/pace_thresholds [on_track] [slightly_over] [envelope] [never_warn] [reset]` - Shows or sets the report's 🟢/🟡/🔴 thresholds (admins).\n\
+        • `/config get [key]` / `/config set <key> <value>` - Shows or changes runtime settings (admins).\n\
+        • `/alias <subcommand>` - Manage prefix aliases; `!s`, `!p`, and `!r` run spend, use_product, and report (set and remove are admin-only).\n\
+        • `/reload_config` - Applies changes from config.toml without a restart (admins).\n\
+        • `/budget_cycle [start_day]` - Shows or sets the day budget months start on (admins).\n\
+        • `/reconcile` - Finds balances that drifted from their history and fixes them (admins).\n\
+        • `/audit [fix]` - Checks for orphaned records and drifted balances (admins).\n\
+        • `/status` - Shows uptime, database stats, and background job runs (admins).\n\
+        • `/share <subcommand>` - Manage read-only share links for viewers outside the server (admins).\n\
+        • `/share_status <token>` - Shows envelope balances using a share token.\n\
+        • `/stats usage` - Shows this month's most-used commands and most active members.\n\
         • `/update` - Runs the monthly rollover/reset process.\n\
         • `/ping` - Checks if the bot is responsive.\n\
         • `/help` - Shows this help message.\n\n\
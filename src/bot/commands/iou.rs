@@ -0,0 +1,127 @@
+//! IOU Discord commands - `/iou add` and `/iou settle`.
+//!
+//! Tracks money household members owe each other when one partner covers an expense
+//! for the other. Net balances between members are also shown at the end of `/report`.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::BotData,
+        config::users,
+        core::{iou, setup},
+        errors::{Error, Result},
+    };
+    use poise::serenity_prelude as serenity;
+
+    /// Parent command for tracking money owed between household members.
+    #[poise::command(slash_command, subcommands("iou_add", "iou_settle"))]
+    pub async fn iou(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "IOU command. Available subcommands:\n\
+            `/iou add` - Record that another member owes you money\n\
+            `/iou settle` - Mark everything between you and another member as paid";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Records that another member owes you money, e.g. after you covered their expense.
+    ///
+    /// Optionally links the IOU to the transaction that generated the debt.
+    #[poise::command(slash_command, rename = "add")]
+    pub async fn iou_add(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Member who owes you"] user: serenity::User,
+        #[description = "Amount owed"] amount: f64,
+        #[description = "What the money was for (optional)"] description: Option<String>,
+        #[description = "ID of the transaction that generated the debt (optional)"]
+        transaction_id: Option<i64>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let debtor_id = user.id.to_string();
+        let creditor_id = ctx.author().id.to_string();
+        let desc = description.unwrap_or_else(|| "IOU".to_string());
+
+        match iou::add_iou(
+            db,
+            &creditor_id,
+            &debtor_id,
+            amount,
+            desc.clone(),
+            transaction_id,
+        )
+        .await
+        {
+            Ok(record) => {
+                let link = record
+                    .transaction_id
+                    .map_or_else(String::new, |id| format!(" (Transaction ID: {id})"));
+                ctx.say(&format!(
+                    "✅ Recorded: {} owes {} {} - {desc}{link}",
+                    users::get_user_display_name(&debtor_id),
+                    users::get_user_display_name(&creditor_id),
+                    setup::get_currency_format(db).await?.format(record.amount)
+                ))
+                .await?;
+            }
+            Err(Error::InvalidAmount { .. }) => {
+                ctx.say("❌ Invalid amount: must be a number greater than zero")
+                    .await?;
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+            }
+            Err(Error::TransactionNotFound { id }) => {
+                ctx.say(&format!("❌ Transaction {id} not found.")).await?;
+            }
+            Err(e) => return Err(e),
+        }
+
+        Ok(())
+    }
+
+    /// Marks every outstanding IOU between you and another member as paid.
+    #[poise::command(slash_command, rename = "settle")]
+    pub async fn iou_settle(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Member to settle up with"] user: serenity::User,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let other_id = user.id.to_string();
+        let other_name = users::get_user_display_name(&other_id);
+
+        let settled = iou::settle_ious(db, &author_id, &other_id).await?;
+        if settled.is_empty() {
+            ctx.say(&format!(
+                "ℹ️ Nothing outstanding between you and {other_name}."
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let currency = setup::get_currency_format(db).await?;
+        let summary = iou::net_balances(&settled).first().map_or_else(
+            || "the balance was already even".to_string(),
+            |net| {
+                format!(
+                    "{} paid {} {}",
+                    users::get_user_display_name(&net.debtor_id),
+                    users::get_user_display_name(&net.creditor_id),
+                    currency.format(net.amount)
+                )
+            },
+        );
+        ctx.say(&format!(
+            "✅ Settled {} IOU(s) with {other_name}: {summary}.",
+            settled.len()
+        ))
+        .await?;
+
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
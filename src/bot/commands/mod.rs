@@ -3,20 +3,72 @@
 // Clippy incorrectly treats separate module doc comments as one paragraph
 #![allow(clippy::too_long_first_doc_paragraph)]
 
+/// Admin commands
+pub mod admin;
+
+/// Recurring bill commands
+pub mod bill;
+
+/// Product bundle commands
+pub mod bundle;
+
 /// Envelope commands
 pub mod envelope;
 
+/// Favorite (saved spend) commands
+pub mod favorite;
+
 /// General commands
 pub mod general;
 
+/// IOU (money owed between members) commands
+pub mod iou;
+
+/// Per-member preference commands
+pub mod preferences;
+
 /// Product commands
 pub mod product;
 
+/// Scheduled (future one-off) spend commands
+pub mod schedule;
+
+/// Setup (onboarding) commands
+pub mod setup;
+
+/// Shopping list commands
+pub mod shopping_list;
+
+/// Read-only share link commands
+pub mod share;
+
+/// Usage statistics commands
+pub mod stats;
+
 /// Transaction commands
 pub mod transaction;
 
+/// Undo history commands
+pub mod undo;
+
+/// Wishlist (partner-approved purchase) commands
+pub mod wishlist;
+
 // Export commands
+pub use admin::*;
+pub use bill::*;
+pub use bundle::*;
 pub use envelope::*;
+pub use favorite::*;
 pub use general::*;
+pub use iou::*;
+pub use preferences::*;
 pub use product::*;
+pub use schedule::*;
+pub use setup::*;
+pub use share::*;
+pub use shopping_list::*;
+pub use stats::*;
 pub use transaction::*;
+pub use undo::*;
+pub use wishlist::*;
@@ -0,0 +1,138 @@
+//! Preference Discord commands - `/preferences` and `/verbosity`.
+//!
+//! `/preferences` lists and changes every personal preference (see
+//! [`crate::core::preferences::Preference`]). `/verbosity` is the shortcut for choosing
+//! how your own confirmations look: compact one-line text, or detailed embeds with a
+//! balance mini-report (see [`crate::bot::responses`]).
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::{BotData, handlers::autocomplete},
+        core::preferences::{self, Preference, Verbosity},
+        errors::{Error, Result},
+    };
+    use std::fmt::Write;
+
+    /// Parent command for your personal preferences.
+    #[poise::command(slash_command, subcommands("preferences_view", "preferences_set"))]
+    pub async fn preferences(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Preferences command. Available subcommands:\n\
+            `/preferences view` - Show your preferences\n\
+            `/preferences set <key> <value>` - Change one of your preferences";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Shows your personal preferences and the values each accepts.
+    #[poise::command(slash_command, rename = "view")]
+    pub async fn preferences_view(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+
+        let mut response = String::from("🗒️ **Your preferences**\n");
+        for preference in Preference::ALL {
+            let value = preferences::get_preference(db, &author_id, preference).await?;
+            writeln!(
+                &mut response,
+                "• `{}` = **{value}** ({})",
+                preference.key(),
+                preference.expected()
+            )?;
+        }
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Changes one of your personal preferences, checking the value first.
+    #[poise::command(slash_command, rename = "set")]
+    pub async fn preferences_set(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Preference to change"]
+        #[autocomplete = "autocomplete::autocomplete_preference_key"]
+        key: String,
+        #[description = "New value"] value: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+
+        let Some(preference) = Preference::from_key(&key) else {
+            let keys: Vec<&str> = Preference::ALL.iter().map(|p| p.key()).collect();
+            ctx.say(&format!(
+                "❌ Unknown preference '{key}'. Preferences: {}",
+                keys.join(", ")
+            ))
+            .await?;
+            return Ok(());
+        };
+        match preferences::set_preference(db, &author_id, preference, &value).await {
+            Ok(()) => {}
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        }
+
+        let value = preferences::get_preference(db, &author_id, preference).await?;
+        ctx.say(&format!(
+            "✅ Your `{}` is now **{value}**.",
+            preference.key()
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// How much detail confirmations show, as offered by `/verbosity`.
+    #[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+    pub enum VerbosityChoice {
+        #[name = "compact"]
+        Compact,
+        #[name = "detailed"]
+        Detailed,
+    }
+
+    impl From<VerbosityChoice> for Verbosity {
+        fn from(choice: VerbosityChoice) -> Self {
+            match choice {
+                VerbosityChoice::Compact => Self::Compact,
+                VerbosityChoice::Detailed => Self::Detailed,
+            }
+        }
+    }
+
+    /// Shows or sets how much detail your spend, deposit, and product confirmations show.
+    ///
+    /// Compact confirmations are one line, which fits in a mobile notification; detailed
+    /// ones are embeds with the envelope's balance.
+    #[poise::command(slash_command)]
+    pub async fn verbosity(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "compact (one line) or detailed (embed)"] mode: Option<VerbosityChoice>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+
+        if let Some(mode) = mode {
+            preferences::set_verbosity(db, &author_id, mode.into()).await?;
+        }
+
+        let current = preferences::get_verbosity(db, &author_id).await?;
+        let description = match current {
+            Verbosity::Compact => "one-line confirmations",
+            Verbosity::Detailed => "detailed confirmations with your envelope's balance",
+        };
+        let prefix = if mode.is_some() { "✅ Updated. " } else { "" };
+        ctx.say(format!(
+            "{prefix}🗒️ Your verbosity is **{current}** ({description})."
+        ))
+        .await?;
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
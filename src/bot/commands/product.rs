@@ -1,4 +1,4 @@
-//! Product Discord commands - `product_manage` and `use_product`.
+//! Product Discord commands - `product_manage`, `use_product`, and `return_product`.
 //!
 //! This module contains commands for managing predefined products and using them
 //! to quickly log expenses.
@@ -8,8 +8,13 @@ mod inner {
     #![allow(missing_docs)]
 
     use crate::{
-        bot::{BotData, handlers::autocomplete},
-        core::{envelope, product, transaction},
+        bot::{
+            BotData, delivery,
+            handlers::{alerts, autocomplete, daily_limit, name_suggestions, product_pages},
+            notifications, permissions, remember_transaction, responses,
+        },
+        core::{bundle, calendar, envelope, product, setup, table, transaction},
+        entities::TransactionType,
         errors::{Error, Result},
     };
     use poise::serenity_prelude as serenity;
@@ -21,14 +26,25 @@ mod inner {
     /// listing, updating, and deleting products.
     #[poise::command(
         slash_command,
-        subcommands("product_add", "product_list", "product_update", "product_delete")
+        subcommands(
+            "product_add",
+            "product_list",
+            "product_update",
+            "product_tag",
+            "product_describe",
+            "product_delete",
+            "product_import"
+        )
     )]
     pub async fn product_manage(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
         let help_text = "Product management command. Available subcommands:\n\
             `/product_manage add` - Add a new product\n\
             `/product_manage list` - List all products\n\
             `/product_manage update` - Update a product's price\n\
-            `/product_manage delete` - Delete a product";
+            `/product_manage tag` - Set or clear a product's category\n\
+            `/product_manage describe` - Set or clear a product's default description\n\
+            `/product_manage delete` - Delete a product\n\
+            `/product_manage import` - Update prices from a CSV/JSON file";
 
         ctx.say(help_text).await?;
         Ok(())
@@ -49,6 +65,9 @@ mod inner {
         #[description = "Quantity for the total price (e.g., 12). Defaults to 1."] quantity: Option<
             f64,
         >,
+        #[description = "Category tag for filtering the product list (e.g., 'snacks')"]
+        #[autocomplete = "autocomplete::autocomplete_product_category"]
+        category: Option<String>,
     ) -> Result<()> {
         let author_id_str = ctx.author().id.to_string();
         let qty_value = quantity.unwrap_or(1.0);
@@ -80,16 +99,9 @@ mod inner {
         let unit_price = total_price / qty_value;
         let db = &ctx.data().database;
 
-        // Find the envelope by name (try user-specific first, then shared)
-        let envelope = if let Some(env) =
-            envelope::get_envelope_by_name_and_user(db, &envelope_name, &author_id_str).await?
-        {
-            Some(env)
-        } else {
-            envelope::get_shared_envelope_by_name(db, &envelope_name).await?
-        };
-
-        let Some(envelope) = envelope else {
+        let Some(envelope) =
+            envelope::find_envelope_for_user(db, &envelope_name, &author_id_str).await?
+        else {
             ctx.say(&format!(
                 "❌ Could not find an envelope named '{envelope_name}' that you can use.",
             ))
@@ -99,17 +111,25 @@ mod inner {
 
         // Create the product
         match product::create_product(db, name.clone(), unit_price, envelope.id).await {
-            Ok(_) => {
+            Ok(created) => {
+                if let Some(tag) = category.as_deref() {
+                    product::set_product_category(db, created.id, Some(tag)).await?;
+                }
+
+                let currency = setup::get_currency_format(db).await?;
                 let message = quantity.map_or_else(
                     || {
                         format!(
-                            "✅ Product '{name}' added with unit price **${unit_price:.2}** and linked to envelope '{}'.",
+                            "✅ Product '{name}' added with unit price **{}** and linked to envelope '{}'.",
+                            currency.format(unit_price),
                             envelope.name
                         )
                     },
                     |qty| {
                         format!(
-                            "✅ Product '{name}' added with unit price **${unit_price:.2}** (calculated from ${total_price:.2} for {qty:.1} items) and linked to envelope '{}'.",
+                            "✅ Product '{name}' added with unit price **{}** (calculated from {} for {qty:.1} items) and linked to envelope '{}'.",
+                            currency.format(unit_price),
+                            currency.format(total_price),
                             envelope.name
                         )
                     },
@@ -145,20 +165,38 @@ mod inner {
     }
 
     /// Lists all defined products with their unit prices and linked envelopes.
+    ///
+    /// Products are listed alphabetically, a page at a time, optionally only those with
+    /// the given category tag.
     #[poise::command(slash_command, rename = "list")]
-    pub async fn product_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+    pub async fn product_list(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Only list products with this category tag"]
+        #[autocomplete = "autocomplete::autocomplete_product_category"]
+        category: Option<String>,
+    ) -> Result<()> {
         let db = &ctx.data().database;
 
         let products = product::get_all_active_products(db).await?;
-
         if products.is_empty() {
             ctx.say("No products have been defined yet. Use `/product_manage add` to create some!")
                 .await?;
             return Ok(());
         }
 
+        let products = product::filter_by_category(products, category.as_deref());
+        let category = category.as_deref().and_then(product::normalize_category);
+        if products.is_empty() {
+            ctx.say(format!(
+                "No products are tagged `{}`.",
+                category.as_deref().unwrap_or_default()
+            ))
+            .await?;
+            return Ok(());
+        }
+
         // Fetch envelope names for each product
-        let mut embed_fields = Vec::new();
+        let mut listed = Vec::with_capacity(products.len());
         for prod in products {
             let envelope_name =
                 if let Ok(Some(env)) = envelope::get_envelope_by_id(db, prod.envelope_id).await {
@@ -166,20 +204,13 @@ mod inner {
                 } else {
                     "Unknown Envelope".to_string()
                 };
-
-            let field_name = format!("{} (${:.2})", prod.name, prod.price);
-            let field_value = format!("Linked to: {envelope_name}");
-            embed_fields.push((field_name, field_value, false));
+            listed.push(product_pages::ListedProduct {
+                product: prod,
+                envelope_name,
+            });
         }
 
-        let list_embed = serenity::CreateEmbed::default()
-            .title("**Product List**")
-            .color(0x0058_65F2) // Discord purple
-            .fields(embed_fields);
-
-        ctx.send(poise::CreateReply::default().embed(list_embed))
-            .await?;
-        Ok(())
+        product_pages::send_product_list(ctx, &listed, category.as_deref()).await
     }
 
     /// Updates the unit price of an existing product.
@@ -230,11 +261,19 @@ mod inner {
         // Update the product's price (keeping the same name)
         match product::update_product(db, product.id, product.name.clone(), unit_price).await {
             Ok(_) => {
+                let currency = setup::get_currency_format(db).await?;
                 let message = quantity.map_or_else(
-                    || format!("✅ Price for product '{name}' updated to **${unit_price:.2} per item**."),
+                    || {
+                        format!(
+                            "✅ Price for product '{name}' updated to **{} per item**.",
+                            currency.format(unit_price)
+                        )
+                    },
                     |qty| {
                         format!(
-                            "✅ Price for product '{name}' updated to **${unit_price:.2} per item** (calculated from {qty:.1} items for ${total_price:.2}).",
+                            "✅ Price for product '{name}' updated to **{} per item** (calculated from {qty:.1} items for {}).",
+                            currency.format(unit_price),
+                            currency.format(total_price)
                         )
                     },
                 );
@@ -251,6 +290,187 @@ mod inner {
         Ok(())
     }
 
+    /// Sets or clears the category tag used to filter `/product_manage list`.
+    #[poise::command(slash_command, rename = "tag")]
+    pub async fn product_tag(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the product to tag"]
+        #[autocomplete = "autocomplete::autocomplete_product_name"]
+        name: String,
+        #[description = "Category tag (e.g., 'snacks'); omit to clear"]
+        #[autocomplete = "autocomplete::autocomplete_product_category"]
+        category: Option<String>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let Some(product) = product::get_product_by_name(db, &name).await? else {
+            ctx.say(&format!("❌ Product '{name}' not found.")).await?;
+            return Ok(());
+        };
+
+        let updated = product::set_product_category(db, product.id, category.as_deref()).await?;
+        let message = updated.category.map_or_else(
+            || format!("✅ Cleared the category of product '{}'.", updated.name),
+            |tag| {
+                format!(
+                    "✅ Product '{}' is now in category **{tag}**.",
+                    updated.name
+                )
+            },
+        );
+        ctx.say(message).await?;
+        Ok(())
+    }
+
+    /// Sets or clears the description in front of a product's uses, overriding its envelope's.
+    #[poise::command(slash_command, rename = "describe")]
+    pub async fn product_describe(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the product to describe"]
+        #[autocomplete = "autocomplete::autocomplete_product_name"]
+        name: String,
+        #[description = "Description for its uses (e.g., 'Coffee on {date}'); omit to clear"]
+        description: Option<String>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let Some(product) = product::get_product_by_name(db, &name).await? else {
+            ctx.say(&format!("❌ Product '{name}' not found.")).await?;
+            return Ok(());
+        };
+
+        let updated =
+            product::set_default_description(db, product.id, description.as_deref()).await?;
+        let today = calendar::today(db).await?;
+        let currency = setup::get_currency_format(db).await?;
+        let message = updated.default_description.as_deref().map_or_else(
+            || {
+                format!(
+                    "✅ Cleared the default description of product '{}'; uses fall back to its envelope's.",
+                    updated.name
+                )
+            },
+            |text| {
+                let example = product::expand_placeholders(text, &updated, 1, today, currency);
+                if example == text {
+                    format!(
+                        "✅ Uses of product '{}' are now described as **{text}**. \
+                         Placeholders such as {} are filled in on each use.",
+                        updated.name,
+                        product::DESCRIPTION_PLACEHOLDERS
+                    )
+                } else {
+                    format!(
+                        "✅ Uses of product '{}' are now described as **{text}**, \
+                         e.g. **{example}** for one used today.",
+                        updated.name
+                    )
+                }
+            },
+        );
+        ctx.say(message).await?;
+        Ok(())
+    }
+
+    /// Largest price import attachment accepted, in bytes.
+    const MAX_IMPORT_SIZE: u32 = 256 * 1024;
+    /// Maximum length of a Discord message.
+    const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+    /// Updates product prices in bulk from a CSV or JSON attachment.
+    ///
+    /// Each row holds `name,total_price,quantity` (quantity defaults to 1). Existing
+    /// products get their unit price recalculated; if an envelope is given, products that
+    /// don't exist yet are created and linked to it. The first rows are shown as a table
+    /// before anything changes; all rows are then applied in a single database
+    /// transaction, and a per-row summary is returned.
+    #[poise::command(slash_command, rename = "import")]
+    pub async fn product_import(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "CSV or JSON file with name,total_price,quantity rows"]
+        file: serenity::Attachment,
+        #[description = "Optional: envelope for creating products that don't exist yet"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        create_missing_in: Option<String>,
+    ) -> Result<()> {
+        if file.size > MAX_IMPORT_SIZE {
+            ctx.say(&format!(
+                "❌ '{}' is too large to import (limit {} KB).",
+                file.filename,
+                MAX_IMPORT_SIZE / 1024
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let db = &ctx.data().database;
+        let author_id_str = ctx.author().id.to_string();
+
+        // Resolve the envelope for new products (try user-specific first, then shared)
+        let create_envelope_id = match create_missing_in {
+            Some(envelope_name) => {
+                let Some(envelope) =
+                    envelope::find_envelope_for_user(db, &envelope_name, &author_id_str).await?
+                else {
+                    ctx.say(&format!(
+                        "❌ Could not find an envelope named '{envelope_name}' that you can use.",
+                    ))
+                    .await?;
+                    return Ok(());
+                };
+                Some(envelope.id)
+            }
+            None => None,
+        };
+
+        let bytes = file.download().await?;
+        let Ok(content) = String::from_utf8(bytes) else {
+            ctx.say(&format!("❌ '{}' is not a UTF-8 text file.", file.filename))
+                .await?;
+            return Ok(());
+        };
+
+        let rows = match product::parse_price_import(&content) {
+            Ok(rows) => rows,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        if rows.is_empty() {
+            ctx.say(&format!("❌ '{}' contains no product rows.", file.filename))
+                .await?;
+            return Ok(());
+        }
+
+        // Show the parsed rows before they are applied
+        let preview = product::price_import_table(&rows).preview(table::PREVIEW_ROWS);
+        ctx.say(format!(
+            "📄 '{}' has {} row(s):\n```\n{preview}\n```",
+            file.filename,
+            rows.len()
+        ))
+        .await?;
+
+        let results = product::import_product_prices(db, &rows, create_envelope_id).await?;
+        let summary =
+            product::format_price_import_summary(&results, setup::get_currency_format(db).await?)?;
+
+        let header = format!("📦 **Price import from '{}'**", file.filename);
+        let message = format!("{header}\n```\n{summary}```");
+        if message.len() <= DISCORD_MESSAGE_LIMIT {
+            ctx.say(message).await?;
+        } else {
+            // Too many rows for one message, attach the summary instead
+            ctx.send(poise::CreateReply::default().content(header).attachment(
+                serenity::CreateAttachment::bytes(summary.into_bytes(), "price_import.txt"),
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
     /// Deletes a product from the system by its name.
     #[poise::command(slash_command, rename = "delete")]
     pub async fn product_delete(
@@ -283,22 +503,55 @@ mod inner {
         Ok(())
     }
 
-    /// Records an expense by using a predefined product.
+    /// Records an expense by using a predefined product or bundle.
     ///
     /// This command deducts the total cost (unit price * quantity) of the specified
-    /// product from the appropriate envelope. If a user is specified, the expense
-    /// is recorded for that user (useful for one partner recording expenses for the other).
-    #[poise::command(slash_command)]
+    /// product from the appropriate envelope. With `bundle` instead, every product in the
+    /// bundle is logged against its own envelope in one atomic operation. An admin may
+    /// specify a user to record the expense for them (e.g. for a member who couldn't log it
+    /// themselves). Filling in `envelope` first limits product suggestions to
+    /// that envelope. As a text command, `!p <product> [quantity]` logs a product quickly.
+    #[poise::command(slash_command, prefix_command)]
     pub async fn use_product(
         ctx: poise::Context<'_, BotData, Error>,
         #[description = "Name of the product to use"]
         #[autocomplete = "autocomplete::autocomplete_product_name"]
-        name: String,
+        name: Option<String>,
         #[description = "Quantity of the product (defaults to 1)"] quantity: Option<i64>,
-        #[description = "Optional: user ID to record the expense for"] user: Option<String>,
+        #[description = "Only suggest products from this envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope: Option<String>,
+        #[description = "Member to record the expense for (admins only for others)"] user: Option<
+            serenity::User,
+        >,
+        #[description = "Use a bundle of products instead of a single product"]
+        #[autocomplete = "autocomplete::autocomplete_bundle_name"]
+        bundle: Option<String>,
+        #[description = "Spend even if it exceeds a daily limit"]
+        #[rename = "override"]
+        override_limit: Option<bool>,
     ) -> Result<()> {
         let author_id = ctx.author().id.to_string();
-        let target_user_id = user.as_ref().unwrap_or(&author_id);
+        let Some(target_user_id) = permissions::target_user(ctx, user.as_ref()).await? else {
+            return Ok(());
+        };
+        let target_user_id = &target_user_id;
+
+        let name = match (name, bundle) {
+            (Some(name), None) => name,
+            (None, Some(bundle_name)) if quantity.is_none() => {
+                return use_bundle(ctx, &bundle_name, target_user_id).await;
+            }
+            (None, Some(_)) => {
+                ctx.say("❌ Quantity only applies to single products, not bundles.")
+                    .await?;
+                return Ok(());
+            }
+            _ => {
+                ctx.say("❌ Choose either a product or a bundle.").await?;
+                return Ok(());
+            }
+        };
         let quantity = quantity.unwrap_or(1);
 
         if quantity <= 0 {
@@ -308,14 +561,28 @@ mod inner {
 
         let db = &ctx.data().database;
 
-        // 1. Fetch the product
-        let Some(prod) = product::get_product_by_name(db, &name).await? else {
-            ctx.say(&format!("❌ Product '{name}' not found.")).await?;
+        // 1. Fetch the product, offering the closest name for a typo
+        let Some(prod) = name_suggestions::resolve_product(ctx, &name).await? else {
             return Ok(());
         };
 
         // 2. Determine the target envelope for spending
         let target_envelope = resolve_product_envelope(ctx, db, &prod, target_user_id).await?;
+        if let Some(envelope_name) = &envelope
+            && !target_envelope
+                .name
+                .eq_ignore_ascii_case(envelope_name.trim())
+        {
+            ctx.say(&format!(
+                "❌ Product '{}' comes from '{}', not '{envelope_name}'.",
+                prod.name, target_envelope.name
+            ))
+            .await?;
+            return Ok(());
+        }
+        if !permissions::check_spender(ctx, &target_envelope, target_user_id).await? {
+            return Ok(());
+        }
 
         // 3. Calculate cost and warn about overdraft if needed
         // Cast is safe: for quantities < 2^53, no precision loss occurs in f64
@@ -323,28 +590,234 @@ mod inner {
         let total_cost = prod.price * (quantity as f64);
         check_and_warn_overdraft(ctx, &target_envelope, total_cost).await?;
 
-        // 4. Create the transaction
-        let transaction_description = if user.is_some() {
-            format!(
-                "Product: {} (x{}) - recorded by {}",
-                prod.name, quantity, author_id
-            )
-        } else {
-            format!("Product: {} (x{})", prod.name, quantity)
+        // Over-limit spends need an explicit override or confirmation
+        let Some(clearance) = daily_limit::allow_spend(
+            ctx,
+            &target_envelope,
+            target_user_id,
+            total_cost,
+            override_limit,
+        )
+        .await?
+        else {
+            return Ok(());
         };
-        transaction::create_transaction(
+
+        // 4. Create the transaction
+        let recorded_by = (*target_user_id != author_id).then_some(author_id.as_str());
+        let transaction_description = product::use_description(
+            &prod,
+            &target_envelope,
+            quantity,
+            calendar::today(db).await?,
+            setup::get_currency_format(db).await?,
+            recorded_by,
+        );
+        let reply = delivery::prepare(ctx).await?;
+        let spend = transaction::create_cleared_transaction(
             db,
             target_envelope.id,
             -total_cost,
             transaction_description,
             target_user_id.clone(),
             Some(ctx.id().to_string()),
-            "use_product".to_string(),
+            TransactionType::UseProduct,
+            clearance,
         )
         .await?;
+        remember_transaction(ctx, &spend, &target_envelope.name).await;
 
         // 5. Send confirmation with mini-report
-        send_product_usage_report(ctx, db, &prod, quantity, total_cost, target_envelope.id).await?;
+        send_product_usage_report(
+            ctx,
+            reply,
+            db,
+            &prod,
+            quantity,
+            total_cost,
+            target_envelope.id,
+        )
+        .await?;
+        alerts::after_balance_change(ctx, &[target_envelope.id]).await;
+        notifications::post_spend_notes(ctx, &[spend]).await;
+
+        Ok(())
+    }
+
+    /// Returns units of a product that were used, crediting the envelope they came from.
+    ///
+    /// The return is recorded as a `product_return` transaction linked to the most recent
+    /// use of the product (alone or in a bundle) with enough units left, and is priced at
+    /// what that use paid. An admin may specify a user to return units for them.
+    #[poise::command(slash_command)]
+    pub async fn return_product(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Name of the product to return"]
+        #[autocomplete = "autocomplete::autocomplete_product_name"]
+        name: String,
+        #[description = "Quantity to return (defaults to 1)"] quantity: Option<i64>,
+        #[description = "Member who used the product (admins only for others)"] user: Option<
+            serenity::User,
+        >,
+    ) -> Result<()> {
+        let Some(target_user_id) = permissions::target_user(ctx, user.as_ref()).await? else {
+            return Ok(());
+        };
+        let quantity = quantity.unwrap_or(1);
+        if quantity <= 0 {
+            ctx.say("❌ Quantity must be a positive number.").await?;
+            return Ok(());
+        }
+
+        let db = &ctx.data().database;
+        let Some(prod) = product::get_product_by_name(db, &name).await? else {
+            ctx.say(&format!("❌ Product '{name}' not found.")).await?;
+            return Ok(());
+        };
+        let target_envelope = resolve_product_envelope(ctx, db, &prod, &target_user_id).await?;
+
+        let reply = delivery::prepare(ctx).await?;
+        let returned = match product::return_product(
+            db,
+            &prod,
+            target_envelope.id,
+            quantity,
+            &target_user_id,
+            Some(ctx.id().to_string()), // Makes retried interactions idempotent
+        )
+        .await
+        {
+            Ok(returned) => returned,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let final_envelope = envelope::get_envelope_by_id(db, target_envelope.id)
+            .await?
+            .unwrap_or(target_envelope);
+        let credited = setup::get_currency_format(db)
+            .await?
+            .format(returned.amount);
+        let confirmation = responses::Confirmation::new(
+            format!("Returned Product: {}", prod.name),
+            format!(
+                "✅ Returned {} x{quantity} ({credited}) to '{}'",
+                prod.name, final_envelope.name
+            ),
+        )
+        .field("Envelope", format!("**{}**", final_envelope.name), false)
+        .field("Credited", credited, true)
+        .field(
+            "Return of",
+            format!("Transaction #{}", returned.refund_of.unwrap_or_default()),
+            true,
+        )
+        .balance(final_envelope);
+        responses::confirm(ctx, reply, confirmation).await;
+
+        Ok(())
+    }
+
+    /// Logs every product in a bundle and replies with a summary of the spends.
+    async fn use_bundle(
+        ctx: poise::Context<'_, BotData, Error>,
+        bundle_name: &str,
+        target_user_id: &str,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let Some(found) = bundle::get_bundle_by_name(db, bundle_name).await? else {
+            ctx.say(&format!("❌ Bundle '{bundle_name}' not found."))
+                .await?;
+            return Ok(());
+        };
+
+        let message_id = ctx.id().to_string();
+        let currency = setup::get_currency_format(db).await?;
+        let reply = delivery::prepare(ctx).await?;
+        let spends = match bundle::use_bundle(db, &found, target_user_id, Some(&message_id)).await {
+            Ok(spends) => spends,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(Error::ProductNotFound { name }) => {
+                ctx.say(&format!(
+                        "❌ Product '{name}' in bundle '{}' was deleted. Remove it with `/bundle remove`.",
+                        found.name
+                    ))
+                    .await?;
+                return Ok(());
+            }
+            Err(Error::EnvelopeNotFound { name }) => {
+                ctx.say(&format!(
+                    "❌ No envelope '{name}' is available for bundle '{}'. Nothing was logged.",
+                    found.name
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(Error::InsufficientFunds { current, required }) => {
+                ctx.say(&format!(
+                    "❌ An envelope has {} but needs {}. Nothing was logged.",
+                    currency.format(current),
+                    currency.format(required)
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(Error::ApprovalRequired { amount, threshold }) => {
+                ctx.say(&format!(
+                        "❌ Bundle '{}' costs {}, over the {} large transaction threshold. Record it with `/spend` so another member can approve it.",
+                        found.name,
+                        currency.format(amount),
+                        currency.format(threshold)
+                    ))
+                    .await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        for spend in &spends {
+            remember_transaction(ctx, &spend.transaction, &spend.envelope_name).await;
+        }
+        let total: f64 = spends.iter().map(|spend| -spend.transaction.amount).sum();
+        let mut confirmation = responses::Confirmation::new(
+            format!("Used Bundle: {}", found.name),
+            format!(
+                "✅ Used bundle '{}' ({} spends, {})",
+                found.name,
+                spends.len(),
+                currency.format(total)
+            ),
+        );
+        for spend in &spends {
+            confirmation = confirmation.field(
+                format!("{} × {}", spend.product.name, spend.quantity),
+                format!(
+                    "{} from **{}**",
+                    currency.format(-spend.transaction.amount),
+                    spend.envelope_name
+                ),
+                true,
+            );
+        }
+        confirmation = confirmation.field("Total", currency.format(total), false);
+        responses::confirm(ctx, reply, confirmation).await;
+
+        let mut envelope_ids: Vec<i64> = spends
+            .iter()
+            .map(|spend| spend.transaction.envelope_id)
+            .collect();
+        envelope_ids.sort_unstable();
+        envelope_ids.dedup();
+        alerts::after_balance_change(ctx, &envelope_ids).await;
+        let transactions: Vec<_> = spends.into_iter().map(|spend| spend.transaction).collect();
+        notifications::post_spend_notes(ctx, &transactions).await;
 
         Ok(())
     }
@@ -384,7 +857,7 @@ mod inner {
         author_id: &str,
     ) -> Result<crate::entities::envelope::Model> {
         if let Some(user_env) =
-            envelope::get_envelope_by_name_and_user(db, &template_envelope.name, author_id).await?
+            envelope::resolve_envelope(db, &template_envelope.name, author_id).await?
             && user_env.user_id.as_deref() == Some(author_id)
         {
             return Ok(user_env);
@@ -408,18 +881,22 @@ mod inner {
         total_cost: f64,
     ) -> Result<()> {
         if envelope.balance < total_cost {
+            let currency = setup::get_currency_format(&ctx.data().database).await?;
             ctx.say(&format!(
-                "⚠️ Warning: Envelope '{}' has insufficient funds (${:.2}). Spending ${:.2} will overdraft it.",
-                envelope.name, envelope.balance, total_cost
+                "⚠️ Warning: Envelope '{}' has insufficient funds ({}). Spending {} will overdraft it.",
+                envelope.name,
+                currency.format(envelope.balance),
+                currency.format(total_cost)
             ))
             .await?;
         }
         Ok(())
     }
 
-    /// Sends a mini-report embed showing the product usage result.
+    /// Sends the product usage confirmation, with a mini-report of the envelope's balance.
     async fn send_product_usage_report(
         ctx: poise::Context<'_, BotData, Error>,
+        reply: delivery::ReplyTarget,
         db: &sea_orm::DatabaseConnection,
         prod: &crate::entities::product::Model,
         quantity: i64,
@@ -432,52 +909,31 @@ mod inner {
                 name: format!("ID {envelope_id}"),
             })?;
 
-        let progress_bar = generate_progress_bar(final_envelope.balance, final_envelope.allocation);
-        let balance_emoji = if final_envelope.balance >= 0.0 {
-            "💰"
-        } else {
-            "⚠️"
-        };
-
-        let embed = serenity::CreateEmbed::default()
-            .title(format!("Used Product: {}", prod.name))
-            .color(0x0058_65F2)
-            .field("Envelope", format!("**{}**", final_envelope.name), false)
-            .field(
-                "Cost",
-                format!("${:.2} × {} = ${:.2}", prod.price, quantity, total_cost),
-                true,
-            )
-            .field(
-                format!("{balance_emoji} Balance"),
-                format!(
-                    "${:.2} / ${:.2}",
-                    final_envelope.balance, final_envelope.allocation
-                ),
-                true,
-            )
-            .field("Progress", progress_bar, false);
-
-        ctx.send(poise::CreateReply::default().embed(embed)).await?;
+        let currency = setup::get_currency_format(db).await?;
+        let confirmation = responses::Confirmation::new(
+            format!("Used Product: {}", prod.name),
+            format!(
+                "✅ Used {} x{quantity} ({}) from '{}'",
+                prod.name,
+                currency.format(total_cost),
+                final_envelope.name
+            ),
+        )
+        .field("Envelope", format!("**{}**", final_envelope.name), false)
+        .field(
+            "Cost",
+            format!(
+                "{} × {} = {}",
+                currency.format(prod.price),
+                quantity,
+                currency.format(total_cost)
+            ),
+            true,
+        )
+        .balance(final_envelope);
+        responses::confirm(ctx, reply, confirmation).await;
         Ok(())
     }
-
-    /// Generates a progress bar string for Discord display
-    fn generate_progress_bar(balance: f64, allocation: f64) -> String {
-        if allocation <= 0.0 {
-            return "[No allocation]".to_string();
-        }
-
-        let percentage = (balance / allocation * 100.0).clamp(0.0, 100.0);
-        // Cast safety: percentage ∈ [0, 100], divided by 10 and rounded gives [0, 10],
-        // then clamped again to ensure it's in valid range. Truncation and sign loss are intentional.
-        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-        let filled = (percentage / 10.0).round().clamp(0.0, 10.0) as usize;
-        let empty = 10_usize.saturating_sub(filled);
-
-        let bar = "█".repeat(filled) + &"░".repeat(empty);
-        format!("[{bar}] {percentage:.1}%")
-    }
 }
 
 // Re-export all commands
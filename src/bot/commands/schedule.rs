@@ -0,0 +1,177 @@
+//! Schedule Discord commands - `/schedule` subcommands for one-off future spends.
+//!
+//! `/schedule spend` registers a spend for a date (e.g. rent on the 1st). The background
+//! scheduler executes it when the date arrives and posts a confirmation in the channel it
+//! was scheduled from.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::{BotData, handlers::autocomplete},
+        core::{calendar, envelope, report, schedule, setup},
+        errors::{Error, Result},
+    };
+    use std::fmt::Write;
+
+    /// Parent command for scheduling future spends.
+    #[poise::command(
+        slash_command,
+        subcommands("schedule_spend", "schedule_list", "schedule_cancel")
+    )]
+    pub async fn schedule(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Schedule command. Available subcommands:\n\
+            `/schedule spend` - Schedule a spend for a future date\n\
+            `/schedule list` - Show your upcoming scheduled spends\n\
+            `/schedule cancel` - Cancel a scheduled spend";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Schedules a spend to run on a future date (e.g. rent on the 1st).
+    #[poise::command(slash_command, rename = "spend")]
+    pub async fn schedule_spend(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Envelope the spend comes from"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope: String,
+        #[description = "Amount to spend"] amount: f64,
+        #[description = "Date to run the spend (YYYY-MM-DD)"] date: String,
+        #[description = "Description of the spend"] description: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+
+        let run_on = match report::parse_date(&date) {
+            Ok(run_on) => run_on,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let Some(found) = envelope::find_envelope_for_user(db, &envelope, &author_id).await? else {
+            ctx.say(&format!(
+                "❌ Envelope '{envelope}' not found. Use `/envelopes` to see available envelopes.",
+            ))
+            .await?;
+            return Ok(());
+        };
+
+        match schedule::schedule_spend(
+            db,
+            found.id,
+            &author_id,
+            amount,
+            description,
+            run_on,
+            Some(ctx.channel_id().to_string()),
+            calendar::today(db).await?,
+        )
+        .await
+        {
+            Ok(scheduled) => {
+                ctx.say(&format!(
+                    "🗓️ Scheduled spend #{}: {} from '{}' - {} on {}. \
+                     You'll get a confirmation here when it runs.",
+                    scheduled.id,
+                    setup::get_currency_format(db)
+                        .await?
+                        .format(scheduled.amount),
+                    found.name,
+                    scheduled.description,
+                    scheduled.run_on
+                ))
+                .await?;
+            }
+            Err(Error::InvalidAmount { .. }) => {
+                ctx.say("❌ Invalid amount: must be a number greater than zero")
+                    .await?;
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    /// Lists your upcoming scheduled spends.
+    #[poise::command(slash_command, rename = "list")]
+    pub async fn schedule_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let pending = schedule::get_pending_for_user(db, &ctx.author().id.to_string()).await?;
+
+        if pending.is_empty() {
+            ctx.say("ℹ️ You have no scheduled spends. Add one with `/schedule spend`.")
+                .await?;
+            return Ok(());
+        }
+
+        let currency = setup::get_currency_format(db).await?;
+        let mut response = String::from("🗓️ **Your scheduled spends:**\n");
+        for scheduled in &pending {
+            let envelope_name = envelope::get_envelope_by_id(db, scheduled.envelope_id)
+                .await?
+                .map_or_else(|| "unknown".to_string(), |env| env.name);
+            write!(
+                &mut response,
+                "• #{} on {} - {} from '{envelope_name}' - {}",
+                scheduled.id,
+                scheduled.run_on,
+                currency.format(scheduled.amount),
+                scheduled.description
+            )?;
+            if let Some(error) = &scheduled.last_error {
+                write!(
+                    &mut response,
+                    " (retrying, {} failed attempt(s): {error})",
+                    scheduled.attempts
+                )?;
+            }
+            writeln!(&mut response)?;
+        }
+
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Cancels one of your scheduled spends before it runs.
+    #[poise::command(slash_command, rename = "cancel")]
+    pub async fn schedule_cancel(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "ID of the scheduled spend (see /schedule list)"] id: i64,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        match schedule::cancel_scheduled(db, id, &ctx.author().id.to_string(), chrono::Utc::now())
+            .await
+        {
+            Ok(cancelled) => {
+                ctx.say(&format!(
+                    "✅ Cancelled scheduled spend #{} ({} - {}).",
+                    cancelled.id,
+                    setup::get_currency_format(db)
+                        .await?
+                        .format(cancelled.amount),
+                    cancelled.description
+                ))
+                .await?;
+            }
+            Err(Error::ScheduledTransactionNotFound { .. }) => {
+                ctx.say(&format!(
+                    "❌ You have no pending scheduled spend #{id}. Use `/schedule list` to see them."
+                ))
+                .await?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
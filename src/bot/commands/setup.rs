@@ -0,0 +1,408 @@
+//! Setup Discord command - interactive onboarding for a new server.
+//!
+//! Walks an administrator through nicknames, currency, starter envelopes, and the
+//! announcement channel using select menus and a modal, storing the results through
+//! `core::setup` instead of requiring a hand-edited config.toml.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::BotData,
+        config::users,
+        core::setup,
+        errors::{Error, Result},
+    };
+    use poise::serenity_prelude as serenity;
+    use std::collections::HashMap;
+    use std::fmt::Write;
+    use std::time::Duration;
+
+    /// How long each setup step waits for the user before giving up.
+    const STEP_TIMEOUT: Duration = Duration::from_mins(5);
+
+    /// Longest label Discord accepts on a modal input.
+    const MAX_LABEL: usize = 45;
+
+    /// Walks through the initial configuration of `EnvelopeBuddy` for this server.
+    ///
+    /// Steps: choose household members and their nicknames, pick a currency, select
+    /// starter envelopes from templates, and set the announcement channel.
+    #[poise::command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
+    pub async fn setup(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let prefix = ctx.id().to_string();
+
+        let members_id = format!("{prefix}:members");
+        let reply = ctx
+            .send(
+                poise::CreateReply::default()
+                    .content(step_header(
+                        1,
+                        "Who's in the household? Select up to two members.",
+                    ))
+                    .components(vec![serenity::CreateActionRow::SelectMenu(
+                        serenity::CreateSelectMenu::new(
+                            members_id.clone(),
+                            serenity::CreateSelectMenuKind::User {
+                                default_users: Some(vec![ctx.author().id]),
+                            },
+                        )
+                        .min_values(1)
+                        .max_values(2),
+                    )]),
+            )
+            .await?;
+
+        let Some((member_ids, nicknames)) = choose_members(ctx, &members_id).await? else {
+            return cancel(ctx, &reply).await;
+        };
+        for (member_id, nickname) in &nicknames {
+            setup::save_nickname(db, member_id, nickname).await?;
+        }
+        users::register_nicknames(nicknames.clone());
+
+        let Some(currency) = choose_currency(ctx, &reply, &prefix).await? else {
+            return cancel(ctx, &reply).await;
+        };
+        setup::set_currency(db, &currency).await?;
+        let format = setup::Currency::from_code(&currency);
+
+        let Some(templates) = choose_templates(ctx, &reply, &prefix, format).await? else {
+            return cancel(ctx, &reply).await;
+        };
+        let created = setup::apply_starter_templates(db, &templates, &member_ids).await?;
+
+        let Some(channel) = choose_channel(ctx, &reply, &prefix).await? else {
+            return cancel(ctx, &reply).await;
+        };
+        setup::set_announcement_channel(db, &channel.to_string()).await?;
+
+        setup::mark_setup_complete(db).await?;
+
+        // Summary
+        let mut summary = String::from("✅ **EnvelopeBuddy setup complete!**\n\n");
+        writeln!(&mut summary, "**Members:**")?;
+        for member_id in &member_ids {
+            let nickname = nicknames
+                .get(member_id)
+                .map_or("(no nickname)", String::as_str);
+            writeln!(&mut summary, "• <@{member_id}> — {nickname}")?;
+        }
+        writeln!(&mut summary, "**Currency:** {currency}")?;
+        writeln!(&mut summary, "**Announcements:** <#{channel}>")?;
+        if created.is_empty() {
+            writeln!(&mut summary, "**Envelopes:** none created")?;
+        } else {
+            writeln!(&mut summary, "**Envelopes created:**")?;
+            for env in &created {
+                writeln!(
+                    &mut summary,
+                    "• {} ({}/month)",
+                    env.name,
+                    format.format(env.allocation)
+                )?;
+            }
+        }
+
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content(summary)
+                    .components(Vec::new()),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Step 1: waits for the member selection, then collects nicknames through a modal.
+    ///
+    /// Returns the selected member IDs and the nicknames entered for them.
+    async fn choose_members(
+        ctx: poise::Context<'_, BotData, Error>,
+        members_id: &str,
+    ) -> Result<Option<(Vec<String>, HashMap<String, String>)>> {
+        let Some(interaction) = await_step(ctx, members_id).await else {
+            return Ok(None);
+        };
+        let member_ids: Vec<serenity::UserId> = match &interaction.data.kind {
+            serenity::ComponentInteractionDataKind::UserSelect { values } => values.clone(),
+            _ => Vec::new(),
+        };
+
+        // Discord doesn't keep the selection order, so each field names its member
+        let mut modal =
+            serenity::CreateQuickModal::new("Household nicknames").timeout(STEP_TIMEOUT);
+        for member_id in &member_ids {
+            modal = modal.field(
+                serenity::CreateInputText::new(
+                    serenity::InputTextStyle::Short,
+                    member_label(ctx, *member_id).await,
+                    "",
+                )
+                .max_length(32)
+                .required(false),
+            );
+        }
+
+        // The modal must be the direct response to the select interaction
+        let Some(response) = interaction
+            .quick_modal(ctx.serenity_context(), modal)
+            .await?
+        else {
+            return Ok(None);
+        };
+        response
+            .interaction
+            .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+            .await?;
+
+        // Fields are numbered in the order they were added, one per member
+        let nicknames = response
+            .interaction
+            .data
+            .components
+            .iter()
+            .filter_map(|row| match row.components.first() {
+                Some(serenity::ActionRowComponent::InputText(text)) => Some(text),
+                _ => None,
+            })
+            .filter_map(|text| {
+                let member = text
+                    .custom_id
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| member_ids.get(index))?;
+                let nickname = text.value.as_deref()?.trim();
+                (!nickname.is_empty()).then(|| (member.to_string(), nickname.to_string()))
+            })
+            .collect();
+
+        Ok(Some((
+            member_ids.iter().map(ToString::to_string).collect(),
+            nicknames,
+        )))
+    }
+
+    /// Labels a member's nickname field with their name and ID, shortening the name to
+    /// fit Discord's label limit.
+    async fn member_label(
+        ctx: poise::Context<'_, BotData, Error>,
+        member_id: serenity::UserId,
+    ) -> String {
+        let name = member_id.to_user(ctx).await.map_or_else(
+            |_| "Member".to_string(),
+            |user| user.display_name().to_string(),
+        );
+        let id = format!(" ({member_id})");
+        let room = MAX_LABEL.saturating_sub(id.chars().count());
+        let mut label: String = if name.chars().count() > room {
+            let mut short: String = name.chars().take(room.saturating_sub(1)).collect();
+            short.push('…');
+            short
+        } else {
+            name
+        };
+        label.push_str(&id);
+        label
+    }
+
+    /// Step 2: asks for the household currency.
+    async fn choose_currency(
+        ctx: poise::Context<'_, BotData, Error>,
+        reply: &poise::ReplyHandle<'_>,
+        prefix: &str,
+    ) -> Result<Option<String>> {
+        let custom_id = format!("{prefix}:currency");
+        let options = setup::SUPPORTED_CURRENCIES
+            .iter()
+            .map(|code| serenity::CreateSelectMenuOption::new(*code, *code))
+            .collect();
+        show_step(
+            ctx,
+            reply,
+            2,
+            "Pick the household currency.",
+            serenity::CreateSelectMenu::new(
+                custom_id.clone(),
+                serenity::CreateSelectMenuKind::String { options },
+            ),
+        )
+        .await?;
+
+        let Some(interaction) = await_step(ctx, &custom_id).await else {
+            return Ok(None);
+        };
+        acknowledge(ctx, &interaction).await?;
+
+        Ok(Some(
+            selected_strings(&interaction)
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| setup::DEFAULT_CURRENCY.to_string()),
+        ))
+    }
+
+    /// Step 3: asks which starter envelope templates to create, with allocations in
+    /// the chosen `currency`.
+    async fn choose_templates(
+        ctx: poise::Context<'_, BotData, Error>,
+        reply: &poise::ReplyHandle<'_>,
+        prefix: &str,
+        currency: setup::Currency,
+    ) -> Result<Option<Vec<String>>> {
+        let custom_id = format!("{prefix}:templates");
+        let options = setup::STARTER_TEMPLATES
+            .iter()
+            .map(|template| {
+                let kind = if template.is_individual {
+                    "per member"
+                } else {
+                    "shared"
+                };
+                serenity::CreateSelectMenuOption::new(template.name, template.name).description(
+                    format!(
+                        "{} • {}/month • {kind}",
+                        template.category,
+                        currency.format(template.allocation)
+                    ),
+                )
+            })
+            .collect();
+        show_step(
+            ctx,
+            reply,
+            3,
+            "Select starter envelopes (you can edit them later with `/update_envelope`).",
+            serenity::CreateSelectMenu::new(
+                custom_id.clone(),
+                serenity::CreateSelectMenuKind::String { options },
+            )
+            .min_values(0)
+            .max_values(u8::try_from(setup::STARTER_TEMPLATES.len())?),
+        )
+        .await?;
+
+        let Some(interaction) = await_step(ctx, &custom_id).await else {
+            return Ok(None);
+        };
+        acknowledge(ctx, &interaction).await?;
+
+        Ok(Some(selected_strings(&interaction)))
+    }
+
+    /// Step 4: asks for the announcement channel.
+    async fn choose_channel(
+        ctx: poise::Context<'_, BotData, Error>,
+        reply: &poise::ReplyHandle<'_>,
+        prefix: &str,
+    ) -> Result<Option<serenity::ChannelId>> {
+        let custom_id = format!("{prefix}:channel");
+        show_step(
+            ctx,
+            reply,
+            4,
+            "Where should monthly summaries and alerts be posted?",
+            serenity::CreateSelectMenu::new(
+                custom_id.clone(),
+                serenity::CreateSelectMenuKind::Channel {
+                    channel_types: Some(vec![serenity::ChannelType::Text]),
+                    default_channels: Some(vec![ctx.channel_id()]),
+                },
+            ),
+        )
+        .await?;
+
+        let Some(interaction) = await_step(ctx, &custom_id).await else {
+            return Ok(None);
+        };
+        acknowledge(ctx, &interaction).await?;
+
+        let channel = match &interaction.data.kind {
+            serenity::ComponentInteractionDataKind::ChannelSelect { values } => {
+                values.first().copied()
+            }
+            _ => None,
+        };
+        Ok(Some(channel.unwrap_or_else(|| ctx.channel_id())))
+    }
+
+    /// Replaces the setup message with the prompt and select menu for a step.
+    async fn show_step(
+        ctx: poise::Context<'_, BotData, Error>,
+        reply: &poise::ReplyHandle<'_>,
+        step: u8,
+        prompt: &str,
+        menu: serenity::CreateSelectMenu,
+    ) -> Result<()> {
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content(step_header(step, prompt))
+                    .components(vec![serenity::CreateActionRow::SelectMenu(menu)]),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Acknowledges a component interaction so Discord doesn't show it as failed.
+    async fn acknowledge(
+        ctx: poise::Context<'_, BotData, Error>,
+        interaction: &serenity::ComponentInteraction,
+    ) -> Result<()> {
+        interaction
+            .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+            .await?;
+        Ok(())
+    }
+
+    /// Formats the heading shown above each setup step.
+    fn step_header(step: u8, prompt: &str) -> String {
+        format!("🛠️ **EnvelopeBuddy Setup ({step}/4)**\n{prompt}")
+    }
+
+    /// Waits for the command author to interact with the component with `custom_id`.
+    async fn await_step(
+        ctx: poise::Context<'_, BotData, Error>,
+        custom_id: &str,
+    ) -> Option<serenity::ComponentInteraction> {
+        let custom_id = custom_id.to_string();
+        let author_id = ctx.author().id;
+        serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+            .author_id(author_id)
+            .filter(move |interaction| interaction.data.custom_id == custom_id)
+            .timeout(STEP_TIMEOUT)
+            .await
+    }
+
+    /// Extracts the chosen values from a string select interaction.
+    fn selected_strings(interaction: &serenity::ComponentInteraction) -> Vec<String> {
+        match &interaction.data.kind {
+            serenity::ComponentInteractionDataKind::StringSelect { values } => values.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Replaces the setup message with a timeout notice.
+    async fn cancel(
+        ctx: poise::Context<'_, BotData, Error>,
+        reply: &poise::ReplyHandle<'_>,
+    ) -> Result<()> {
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content("⌛ Setup timed out. Run `/setup` again to continue.")
+                    .components(Vec::new()),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
@@ -0,0 +1,199 @@
+//! Share link Discord commands - read-only balance access for people outside the server.
+//!
+//! Admins create time-limited tokens with `/share create`. Whoever holds a token can run
+//! `/share_status token:<token>` (for example in a DM with the bot) to see envelope
+//! balances; nothing in that view can change data.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::BotData,
+        config,
+        core::{setup, share},
+        errors::{Error, Result},
+    };
+    use std::fmt::Write;
+
+    /// Parent command for managing read-only share links.
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "ADMINISTRATOR",
+        subcommands("share_create", "share_list", "share_revoke")
+    )]
+    pub async fn share(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Share command. Available subcommands:\n\
+            `/share create` - Create a read-only link to envelope balances\n\
+            `/share list` - Show active share links\n\
+            `/share revoke` - Stop a share link from working\n\
+            Viewers use `/share_status token:<token>`.";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Creates a time-limited token that lets someone view balances read-only.
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "ADMINISTRATOR",
+        rename = "create"
+    )]
+    pub async fn share_create(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Hours the link stays valid (default: 168, max: 720)"]
+        #[min = 1]
+        #[max = 720]
+        hours: Option<i64>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let hours = hours.unwrap_or(share::DEFAULT_SHARE_HOURS);
+
+        let reply = match share::create_share_link(
+            db,
+            &ctx.author().id.to_string(),
+            chrono::Duration::hours(hours),
+            chrono::Utc::now(),
+        )
+        .await
+        {
+            Ok(link) => format!(
+                "🔗 Share token: `{}`\nIt expires <t:{}:R>. The viewer runs \
+                 `/share_status token:{}` to see balances; they can't change anything.",
+                link.token,
+                link.expires_at.timestamp(),
+                link.token
+            ),
+            Err(Error::Config { message }) => format!("❌ {message}"),
+            Err(e) => return Err(e),
+        };
+
+        ctx.send(poise::CreateReply::default().content(reply).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Lists share links that haven't expired yet.
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "ADMINISTRATOR",
+        rename = "list"
+    )]
+    pub async fn share_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let links = share::get_active_share_links(db, chrono::Utc::now()).await?;
+
+        let mut response = if links.is_empty() {
+            "ℹ️ There are no active share links.".to_string()
+        } else {
+            String::from("🔗 **Active share links:**\n")
+        };
+        for link in &links {
+            writeln!(
+                &mut response,
+                "• `{}` - created by <@{}>, expires <t:{}:R>",
+                link.token,
+                link.created_by,
+                link.expires_at.timestamp()
+            )?;
+        }
+
+        ctx.send(
+            poise::CreateReply::default()
+                .content(response)
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Revokes a share link so its token stops working.
+    #[poise::command(
+        slash_command,
+        guild_only,
+        required_permissions = "ADMINISTRATOR",
+        rename = "revoke"
+    )]
+    pub async fn share_revoke(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Token of the link to revoke"] token: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let reply = if share::revoke_share_link(db, &token).await? {
+            "✅ Share link revoked.".to_string()
+        } else {
+            format!("❌ No share link with token `{}`.", token.trim())
+        };
+
+        ctx.send(poise::CreateReply::default().content(reply).ephemeral(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Shows envelope balances read-only using a share token.
+    ///
+    /// Works anywhere the bot's commands are available, including DMs, so viewers don't
+    /// need to be members of the household server. Private envelopes are never shown.
+    #[poise::command(slash_command)]
+    pub async fn share_status(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Token you were given"] token: String,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        let Some(link) = share::get_share_link(db, &token, chrono::Utc::now()).await? else {
+            ctx.send(
+                poise::CreateReply::default()
+                    .content("❌ That share token is invalid or has expired.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        };
+
+        let envelopes = share::shared_balances(db).await?;
+        let mut response = format!(
+            "👀 **Envelope balances** (read-only, link expires <t:{}:R>)\n\n",
+            link.expires_at.timestamp()
+        );
+        if envelopes.is_empty() {
+            response.push_str("No envelopes to show.");
+        }
+        let currency = setup::get_currency_format(db).await?;
+        for env in &envelopes {
+            // Individual envelopes share names across members, so name the owner
+            let label = env.user_id.as_deref().map_or_else(
+                || format!("👥 **{}**", env.name),
+                |uid| {
+                    format!(
+                        "👤 **{}** ({})",
+                        env.name,
+                        config::users::get_user_display_name(uid)
+                    )
+                },
+            );
+            writeln!(
+                &mut response,
+                "{label} - {} / {} ({})",
+                currency.format(env.balance),
+                currency.format(env.allocation),
+                env.category
+            )?;
+        }
+
+        ctx.send(
+            poise::CreateReply::default()
+                .content(response)
+                .ephemeral(true),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
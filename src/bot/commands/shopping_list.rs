@@ -0,0 +1,151 @@
+//! Shopping list Discord commands - `/list add`, `/list show`, and `/list clear`.
+//!
+//! Each envelope has a shopping list (see [`crate::core::shopping_list`]). Items are added
+//! as free text or from a product; `/list show` posts the list with menus to check items
+//! off, optionally logging product items as spends.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::{
+            BotData,
+            handlers::{autocomplete, name_suggestions, shopping_list_menus as list_menus},
+        },
+        core::shopping_list,
+        errors::{Error, Result},
+    };
+
+    /// Parent command for the envelopes' shopping lists.
+    #[poise::command(
+        slash_command,
+        rename = "list",
+        subcommands("list_add", "list_show", "list_clear")
+    )]
+    pub async fn shopping_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Shopping list command. Available subcommands:\n\
+            `/list add` - Add an item, or a product, to an envelope's list\n\
+            `/list show` - Show a list with menus to check items off\n\
+            `/list clear` - Remove the checked-off items from a list";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Adds an item to an envelope's shopping list, as free text or from a product.
+    ///
+    /// Items from a product go on the product's envelope's list, and checking them off
+    /// can log the spend at the product's price.
+    #[poise::command(slash_command, rename = "add")]
+    pub async fn list_add(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Envelope whose list to add to (not needed for a product)"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope: Option<String>,
+        #[description = "What to buy"] item: Option<String>,
+        #[description = "Product to buy, so checking it off can log the spend"]
+        #[autocomplete = "autocomplete::autocomplete_product_name"]
+        product: Option<String>,
+        #[description = "How many to buy (defaults to 1)"] quantity: Option<i64>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let quantity = quantity.unwrap_or(1);
+
+        let added = match (item, product, envelope) {
+            (None, Some(product_name), _) => {
+                let Some(prod) = name_suggestions::resolve_product(ctx, &product_name).await?
+                else {
+                    return Ok(());
+                };
+                shopping_list::add_product(db, &prod, quantity, &author_id).await
+            }
+            (Some(item), None, Some(envelope_name)) => {
+                let Some(env) =
+                    name_suggestions::resolve_envelope(ctx, &envelope_name, &author_id).await?
+                else {
+                    return Ok(());
+                };
+                shopping_list::add_item(db, env.id, &item, quantity, &author_id).await
+            }
+            (Some(_), None, None) => {
+                ctx.say("❌ Give the `envelope` whose list the item goes on.")
+                    .await?;
+                return Ok(());
+            }
+            _ => {
+                ctx.say("❌ Give either an `item` or a `product`.").await?;
+                return Ok(());
+            }
+        };
+
+        match added {
+            Ok(added) => {
+                ctx.say(&format!(
+                    "🛒 Added {} ×{} to the list. See it with `/list show`.",
+                    added.name, added.quantity
+                ))
+                .await?;
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    /// Shows an envelope's shopping list with menus to check items off.
+    ///
+    /// One menu only checks items off; the other checks product items off and logs them
+    /// as spends at the product's price.
+    #[poise::command(slash_command, rename = "show")]
+    pub async fn list_show(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Envelope whose list to show"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope: String,
+    ) -> Result<()> {
+        let author_id = ctx.author().id.to_string();
+        let Some(env) = name_suggestions::resolve_envelope(ctx, &envelope, &author_id).await?
+        else {
+            return Ok(());
+        };
+
+        let (content, rows) = list_menus::render(ctx.data(), &env).await?;
+        ctx.send(
+            poise::CreateReply::default()
+                .content(content)
+                .components(rows),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Removes the checked-off items from an envelope's shopping list.
+    #[poise::command(slash_command, rename = "clear")]
+    pub async fn list_clear(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Envelope whose list to clear"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope: String,
+    ) -> Result<()> {
+        let author_id = ctx.author().id.to_string();
+        let Some(env) = name_suggestions::resolve_envelope(ctx, &envelope, &author_id).await?
+        else {
+            return Ok(());
+        };
+
+        let removed = shopping_list::clear_checked(&ctx.data().database, env.id).await?;
+        ctx.say(&format!(
+            "🧹 Removed {removed} checked-off item(s) from the '{}' list.",
+            env.name
+        ))
+        .await?;
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
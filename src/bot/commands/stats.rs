@@ -0,0 +1,84 @@
+//! Stats Discord commands - `/stats` subcommands for how the household uses the bot.
+//!
+//! `/stats usage` shows the most-used commands and the most active members of the
+//! current budget month, counted as commands run (see [`crate::core::usage`]).
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::BotData,
+        config::users,
+        core::{calendar, usage},
+        errors::{Error, Result},
+    };
+    use std::fmt::Write;
+
+    /// Commands and members listed by `/stats usage`.
+    const TOP_USAGE: usize = 5;
+
+    /// Parent command for usage statistics.
+    #[poise::command(slash_command, guild_only, subcommands("stats_usage"))]
+    pub async fn stats(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Stats command. Available subcommands:\n\
+            `/stats usage` - Most-used commands and most active members this month";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Shows this month's most-used commands, most active members, and top spender.
+    ///
+    /// Counts cover the current budget month; the top spender is the member who
+    /// recorded the most spends and product uses.
+    #[poise::command(slash_command, rename = "usage", guild_only)]
+    pub async fn stats_usage(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let cycle = calendar::current_cycle(db, calendar::today(db).await?).await?;
+        let summary = usage::get_usage(db, &cycle, TOP_USAGE).await?;
+
+        let mut response = format!(
+            "📊 **Usage - {}** ({} command(s) run)\n",
+            summary.month, summary.total
+        );
+        if !summary.commands.is_empty() {
+            response.push_str("\n**Most-used commands**\n");
+            for (rank, command) in summary.commands.iter().enumerate() {
+                writeln!(
+                    &mut response,
+                    "{}. `/{}` - {}",
+                    rank + 1,
+                    command.command,
+                    command.count
+                )?;
+            }
+        }
+        if !summary.members.is_empty() {
+            response.push_str("\n**Most active members**\n");
+            for member in &summary.members {
+                writeln!(
+                    &mut response,
+                    "• {} - {} command(s)",
+                    users::get_user_display_name(&member.user_id),
+                    member.count
+                )?;
+            }
+        }
+        match &summary.top_spender {
+            Some(spender) => write!(
+                &mut response,
+                "\n🛒 Most active spender: **{}** with {} spend(s)",
+                users::get_user_display_name(&spender.user_id),
+                spender.count
+            )?,
+            None => response.push_str("\n🛒 No spends recorded this month yet."),
+        }
+
+        ctx.say(response).await?;
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
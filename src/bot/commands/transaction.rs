@@ -1,4 +1,6 @@
-//! Transaction Discord commands - `spend` and `addfunds`.
+//! Transaction Discord commands - `spend`, `again`, the "Log as expense" message command,
+//! `addfunds`, `distribution_plan`, `refund`, `transaction`, `activity`, `daily_limit`,
+//! and `category_cap`.
 //!
 //! This module contains commands that interact with the database through our core modules
 //! to handle financial transactions and reporting within the envelope system.
@@ -6,115 +8,862 @@
 // Inner module to suppress missing_docs warnings for poise macro-generated code
 mod inner {
     #![allow(missing_docs)]
+    // Poise parses every combination of `/spend`'s optional prefix arguments in one future
+    #![allow(clippy::large_stack_frames)]
 
     use crate::{
-        bot::{BotData, handlers::autocomplete},
+        bot::{
+            BotData, delivery,
+            handlers::{
+                alerts, autocomplete, daily_limit, name_suggestions, product_quick_create,
+                wishlist_buttons,
+            },
+            notifications, permissions, remember_transaction, responses,
+        },
         config::users,
-        core::{envelope, transaction},
+        core::{
+            amount_parser, approval, calendar, category_cap, distribution, envelope, fx, location,
+            report, round_up, setup, transaction,
+        },
+        entities::{TransactionType, transaction as transaction_entity},
         errors::{Error, Result},
     };
+    use poise::serenity_prelude as serenity;
+    use std::collections::{HashMap, hash_map::Entry};
 
     /// Records an expense from an envelope.
     ///
     /// This command deducts the specified amount from the envelope balance and creates
     /// a transaction record for tracking purposes. If no user is specified, it uses the
     /// command author's ID for individual envelopes or looks for shared envelopes.
+    ///
+    /// The amount may be a simple expression such as `12.99+4.50*2` or `45/3` (see
+    /// [`amount_parser`]), with cents after a comma if the household writes `12,50`. With
+    /// `pending`, the spend is recorded as pending (e.g. a card authorization) until
+    /// `/transaction clear` confirms the posted amount. The merchant is taken from the
+    /// description's first word unless `merchant` is given. `location`
+    /// records where the spend was made, for the report's "where the money went" field.
+    /// Without a description, the envelope's default description is used.
+    ///
+    /// A spend above the large transaction threshold is held instead: another member must
+    /// approve it before it is recorded, as a cleared spend of the converted amount with
+    /// its description and merchant.
     #[poise::command(slash_command, prefix_command)]
+    #[allow(clippy::too_many_arguments)] // Each slash command option is a function parameter
+    #[allow(clippy::too_many_lines)] // Validation, currency conversion, and limit checks inline
     pub async fn spend(
         ctx: poise::Context<'_, BotData, Error>,
         #[description = "Name of the envelope to spend from"]
         #[autocomplete = "autocomplete::autocomplete_envelope_name"]
         envelope_name: String,
-        #[description = "Amount to spend"] amount: f64,
-        #[description = "Optional user nickname (for individual envelopes)"]
-        #[autocomplete = "autocomplete::autocomplete_user"]
-        user: Option<String>,
+        #[description = "Amount to spend, e.g. 12.50 or 12.99+4.50*2"] amount: String,
+        #[description = "Member whose individual envelope to use (admins only for others)"]
+        user: Option<serenity::User>,
         #[description = "Optional description of the expense"] description: Option<String>,
+        #[description = "Currency the amount was paid in (defaults to the household currency)"]
+        currency: Option<String>,
+        #[description = "Spend even if it exceeds a daily limit"]
+        #[rename = "override"]
+        override_limit: Option<bool>,
+        #[description = "Record as pending until cleared with the posted amount"] pending: Option<
+            bool,
+        >,
+        #[description = "Where the money was spent (defaults to the description's first word)"]
+        merchant: Option<String>,
+        #[description = "Quantity bought, e.g. 3.2, to track the unit price"] quantity: Option<f64>,
+        #[description = "Unit of the quantity, e.g. gal or lb"] unit: Option<String>,
+        #[description = "Where the spend was made, as a place name or lat, long"] location: Option<
+            String,
+        >,
     ) -> Result<()> {
-        const DEFAULT_DESCRIPTION: &str = "Transaction";
+        // Get database connection from context
+        let db = &ctx.data().database;
 
-        // Validate amount parameter
-        if amount.is_nan() || amount.is_infinite() {
-            ctx.say("❌ Invalid amount: must be a valid number").await?;
-            return Ok(());
-        }
+        // Evaluate the amount, which may be an expression, written the household's way
+        let amount = match amount_parser::parse_household_amount(db, &amount).await {
+            Ok(amount) => amount,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
         if amount <= 0.0 {
             ctx.say("❌ Invalid amount: must be greater than zero")
                 .await?;
             return Ok(());
         }
-
-        // Resolve user nickname or default to the command author
-        let author_id = ctx.author().id.to_string();
-        let target_user_id = if let Some(nickname) = user {
-            // Try to resolve the provided nickname
-            if let Some(user_id) = users::resolve_nickname(&nickname) {
-                user_id
-            } else {
-                ctx.say(&format!(
-                    "❌ Unknown nickname '{}'. Available nicknames: {}",
-                    nickname,
-                    users::get_all_nicknames().join(", ")
-                ))
+        if let Some(name) = merchant.as_deref()
+            && transaction::normalize_merchant(name).is_none()
+        {
+            ctx.say(&format!("❌ '{name}' isn't a valid merchant name"))
                 .await?;
+            return Ok(());
+        }
+        let location = location.and_then(|raw| location::normalize_location(&raw));
+        let quantity = match (quantity, unit) {
+            (Some(quantity), unit) => {
+                match transaction::validate_quantity(quantity, unit.as_deref()) {
+                    Ok(unit) => Some((quantity, unit)),
+                    Err(Error::InvalidAmount { .. }) => {
+                        ctx.say("❌ Invalid quantity: must be greater than zero")
+                            .await?;
+                        return Ok(());
+                    }
+                    Err(Error::Config { message }) => {
+                        ctx.say(&format!("❌ {message}")).await?;
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            (None, Some(_)) => {
+                ctx.say("❌ A unit needs a quantity, e.g. `quantity:3.2 unit:gal`")
+                    .await?;
                 return Ok(());
             }
+            (None, None) => None,
+        };
+
+        // Only admins may act for another member
+        let author_id = ctx.author().id.to_string();
+        let Some(target_user_id) = permissions::target_user(ctx, user.as_ref()).await? else {
+            return Ok(());
+        };
+
+        // Find the envelope by name and user, offering the closest name for a typo
+        let Some(envelope) =
+            name_suggestions::resolve_envelope(ctx, &envelope_name, &target_user_id).await?
+        else {
+            return Ok(());
+        };
+        if !permissions::check_spender(ctx, &envelope, &author_id).await? {
+            return Ok(());
+        }
+        let desc = envelope::spend_description(&envelope, description.as_deref());
+
+        // Convert foreign-currency amounts into the household currency
+        let base_currency = setup::get_currency(db).await?;
+        let conversion = match currency {
+            Some(code) if !code.trim().eq_ignore_ascii_case(&base_currency) => {
+                match fx::convert(ctx.data().rates.as_ref(), -amount, &code, &base_currency) {
+                    Ok(conversion) => Some(conversion),
+                    Err(Error::Config { message }) => {
+                        ctx.say(&format!("❌ {message}")).await?;
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            _ => None,
+        };
+
+        // Over-limit spends need an explicit override or confirmation
+        let spend_amount = conversion
+            .as_ref()
+            .map_or(amount, |c| c.converted_amount.abs());
+        let Some(clearance) =
+            daily_limit::allow_spend(ctx, &envelope, &author_id, spend_amount, override_limit)
+                .await?
+        else {
+            return Ok(());
+        };
+
+        // Large spends wait for another member's approval
+        if approval::needs_approval(db, spend_amount).await? {
+            let held = approval::hold_spend(
+                db,
+                envelope.id,
+                &author_id,
+                spend_amount,
+                desc,
+                merchant,
+                ctx.channel_id().to_string(),
+            )
+            .await?;
+            let currency = setup::get_currency_format(db).await?;
+            ctx.send(wishlist_buttons::hold_reply(
+                &held,
+                &envelope.name,
+                currency,
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        // Create the transaction (negative amount for spending)
+        let reply = delivery::prepare(ctx).await?;
+        let is_pending = pending.unwrap_or(false);
+        let transaction_result = if is_pending {
+            let (signed_amount, original) = conversion.as_ref().map_or((-amount, None), |c| {
+                (
+                    c.converted_amount,
+                    Some((c.original_amount, c.original_currency.clone())),
+                )
+            });
+            transaction::create_pending_spend(
+                db,
+                envelope.id,
+                signed_amount,
+                original,
+                desc.clone(),
+                author_id.clone(),
+                Some(ctx.id().to_string()), // Makes retried interactions idempotent
+                clearance,
+            )
+            .await?
+        } else if let Some(ref conversion) = conversion {
+            transaction::create_converted_transaction(
+                db,
+                envelope.id,
+                conversion,
+                desc.clone(),
+                author_id.clone(),
+                Some(ctx.id().to_string()), // Makes retried interactions idempotent
+                TransactionType::Spend,
+                clearance,
+            )
+            .await?
         } else {
-            author_id.clone()
+            transaction::create_cleared_transaction(
+                db,
+                envelope.id,
+                -amount, // Negative amount for spending
+                desc.clone(),
+                author_id.clone(),
+                Some(ctx.id().to_string()), // Makes retried interactions idempotent
+                TransactionType::Spend,
+                clearance,
+            )
+            .await?
         };
-        let desc = description.as_deref().unwrap_or(DEFAULT_DESCRIPTION);
 
-        // Get database connection from context
+        let transaction_result = with_details(
+            db,
+            transaction_result,
+            merchant.as_deref(),
+            quantity,
+            location.as_deref(),
+        )
+        .await;
+        remember_transaction(ctx, &transaction_result, &envelope.name).await;
+
+        let confirmation = spend_confirmation(
+            db,
+            &envelope,
+            &transaction_result,
+            conversion.as_ref(),
+            is_pending,
+        )
+        .await;
+        responses::confirm(ctx, reply, confirmation).await;
+        alerts::after_balance_change(ctx, &[envelope.id]).await;
+        notifications::post_spend_notes(ctx, std::slice::from_ref(&transaction_result)).await;
+
+        Ok(())
+    }
+
+    /// How long "Log as expense" waits for the envelope choice and the modal.
+    const CAPTURE_TIMEOUT: std::time::Duration = std::time::Duration::from_mins(5);
+
+    /// Modal confirming the amount and description of a spend captured from a message.
+    #[derive(Debug, poise::Modal)]
+    #[name = "Log as expense"]
+    struct ExpenseModal {
+        #[name = "Amount"]
+        #[placeholder = "e.g. 12.50 or 12.99+4.50*2"]
+        #[max_length = 64]
+        amount: String,
+        #[name = "Description"]
+        #[max_length = 100]
+        description: Option<String>,
+    }
+
+    /// Logs an amount found in a message, such as a forwarded bank alert, as a spend.
+    ///
+    /// The amount is taken from the message with [`amount_parser::find_amount`]. The
+    /// member picks one of their envelopes, then confirms or corrects the amount and
+    /// description in a modal. The spend goes through the same permission, daily limit,
+    /// and approval checks as `/spend`.
+    #[poise::command(context_menu_command = "Log as expense")]
+    pub async fn log_as_expense(
+        ctx: poise::Context<'_, BotData, Error>,
+        message: serenity::Message,
+    ) -> Result<()> {
         let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let text = message_text(&message);
+        let found = amount_parser::find_amount(&text);
 
-        // Find the envelope by name and user
-        let envelope =
-            envelope::get_envelope_by_name_and_user(db, &envelope_name, &target_user_id).await?;
+        let Some((envelope, interaction, reply)) = choose_envelope(ctx, found).await? else {
+            return Ok(());
+        };
 
-        let Some(envelope) = envelope else {
-            ctx.say(&format!(
-                "❌ Envelope '{envelope_name}' not found. Use `/envelopes` to see available envelopes.",
-            ))
+        // The modal must be the direct response to the select interaction
+        let defaults = ExpenseModal {
+            amount: found.map_or_else(String::new, |amount| format!("{amount:.2}")),
+            description: text
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(|line| line.chars().take(100).collect()),
+        };
+        let form = poise::execute_modal_on_component_interaction::<ExpenseModal>(
+            ctx,
+            interaction,
+            Some(defaults),
+            Some(CAPTURE_TIMEOUT),
+        )
+        .await?;
+        let notice = if form.is_some() {
+            format!("🧾 Logging to '{}'.", envelope.name)
+        } else {
+            "⌛ Timed out; nothing was logged.".to_string()
+        };
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content(notice)
+                    .components(Vec::new()),
+            )
             .await?;
+        let Some(form) = form else {
             return Ok(());
         };
 
-        // Create the transaction (negative amount for spending)
-        let transaction_result = transaction::create_transaction(
+        let amount = match amount_parser::parse_household_amount(db, &form.amount).await {
+            Ok(amount) if amount > 0.0 => amount,
+            Ok(_) => {
+                ctx.say("❌ Invalid amount: must be greater than zero")
+                    .await?;
+                return Ok(());
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        if !permissions::check_spender(ctx, &envelope, &author_id).await? {
+            return Ok(());
+        }
+        let Some(clearance) =
+            daily_limit::allow_spend(ctx, &envelope, &author_id, amount, None).await?
+        else {
+            return Ok(());
+        };
+        let desc = envelope::spend_description(&envelope, form.description.as_deref());
+
+        // Large spends wait for another member's approval
+        if approval::needs_approval(db, amount).await? {
+            let held = approval::hold_spend(
+                db,
+                envelope.id,
+                &author_id,
+                amount,
+                desc,
+                None,
+                ctx.channel_id().to_string(),
+            )
+            .await?;
+            let currency = setup::get_currency_format(db).await?;
+            ctx.send(wishlist_buttons::hold_reply(
+                &held,
+                &envelope.name,
+                currency,
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let reply = delivery::prepare(ctx).await?;
+        let spend = transaction::create_cleared_transaction(
             db,
             envelope.id,
-            -amount, // Negative amount for spending
-            desc.to_string(),
-            author_id.clone(),
-            None, // No Discord message ID
-            "spend".to_string(),
+            -amount,
+            desc,
+            author_id,
+            Some(ctx.id().to_string()), // Makes retried interactions idempotent
+            TransactionType::Spend,
+            clearance,
         )
         .await?;
+        remember_transaction(ctx, &spend, &envelope.name).await;
 
-        ctx.say(&format!(
-            "✅ Spent ${:.2} from envelope '{}' - {} (Transaction ID: {})",
-            amount, envelope_name, desc, transaction_result.id
-        ))
+        let confirmation = spend_confirmation(db, &envelope, &spend, None, false).await;
+        responses::confirm(ctx, reply, confirmation).await;
+        alerts::after_balance_change(ctx, &[envelope.id]).await;
+        notifications::post_spend_notes(ctx, std::slice::from_ref(&spend)).await;
+
+        Ok(())
+    }
+
+    /// Asks the author which of their envelopes a captured spend comes from.
+    ///
+    /// Returns the chosen envelope with the select interaction, which the modal must
+    /// answer, and the prompt. Returns `None` if the author has no envelopes or didn't
+    /// choose in time, after saying so.
+    async fn choose_envelope(
+        ctx: poise::Context<'_, BotData, Error>,
+        found: Option<f64>,
+    ) -> Result<
+        Option<(
+            crate::entities::envelope::Model,
+            serenity::ComponentInteraction,
+            poise::ReplyHandle<'_>,
+        )>,
+    > {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        // Discord select menus hold at most 25 options
+        let envelopes: Vec<_> = ctx
+            .data()
+            .cache
+            .active_envelopes(db)
+            .await?
+            .iter()
+            .filter(|env| !env.is_individual || env.user_id.as_deref() == Some(&author_id))
+            .take(25)
+            .cloned()
+            .collect();
+        if envelopes.is_empty() {
+            ctx.send(
+                poise::CreateReply::default()
+                    .content("❌ You have no envelopes to spend from.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(None);
+        }
+
+        let custom_id = format!("{}:expense_envelope", ctx.id());
+        let options = envelopes
+            .iter()
+            .map(|env| {
+                let label: String = env.name.chars().take(100).collect();
+                serenity::CreateSelectMenuOption::new(label, env.id.to_string())
+            })
+            .collect();
+        let currency = setup::get_currency_format(db).await?;
+        let found_note = found.map_or_else(
+            || "🧾 No amount found in the message; you can enter it next.".to_string(),
+            |amount| format!("🧾 Found **{}** in the message.", currency.format(amount)),
+        );
+        let reply = ctx
+            .send(
+                poise::CreateReply::default()
+                    .content(format!("{found_note}\nWhich envelope is it from?"))
+                    .components(vec![serenity::CreateActionRow::SelectMenu(
+                        serenity::CreateSelectMenu::new(
+                            custom_id.clone(),
+                            serenity::CreateSelectMenuKind::String { options },
+                        )
+                        .placeholder("Envelope"),
+                    )])
+                    .ephemeral(true),
+            )
+            .await?;
+
+        let interaction = serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+            .author_id(ctx.author().id)
+            .filter(move |interaction| interaction.data.custom_id == custom_id)
+            .timeout(CAPTURE_TIMEOUT)
+            .await;
+        let chosen = interaction.and_then(|interaction| {
+            let serenity::ComponentInteractionDataKind::StringSelect { values } =
+                &interaction.data.kind
+            else {
+                return None;
+            };
+            let id = values.first()?.parse::<i64>().ok()?;
+            let env = envelopes.iter().find(|env| env.id == id)?.clone();
+            Some((env, interaction))
+        });
+        let Some((env, interaction)) = chosen else {
+            reply
+                .edit(
+                    ctx,
+                    poise::CreateReply::default()
+                        .content("⌛ Timed out; nothing was logged.")
+                        .components(Vec::new()),
+                )
+                .await?;
+            return Ok(None);
+        };
+        Ok(Some((env, interaction, reply)))
+    }
+
+    /// Modal confirming a repeated spend, pre-filled from the last one.
+    #[derive(Debug, poise::Modal)]
+    #[name = "Spend again"]
+    struct AgainModal {
+        #[name = "Amount"]
+        #[placeholder = "e.g. 12.50 or 12.99+4.50*2"]
+        #[max_length = 64]
+        amount: String,
+        #[name = "Description"]
+        #[max_length = 100]
+        description: Option<String>,
+    }
+
+    /// Repeats your most recent spend: same envelope, amount, and description.
+    ///
+    /// A modal pre-filled with the last spend (with `amount` instead, if given) confirms
+    /// it, so the amount and description can still be adjusted. The merchant and location
+    /// are carried over, and the spend goes through the same permission, daily limit, and
+    /// approval checks as `/spend`.
+    #[poise::command(slash_command)]
+    pub async fn again(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Amount to spend this time (defaults to last time's)"] amount: Option<
+            String,
+        >,
+    ) -> Result<()> {
+        let poise::Context::Application(app_ctx) = ctx else {
+            return Ok(());
+        };
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+
+        let Some(last) = transaction::get_last_spend(db, &author_id).await? else {
+            ctx.say("❌ You haven't recorded a spend to repeat yet.")
+                .await?;
+            return Ok(());
+        };
+        let Some(envelope) = envelope::get_envelope_by_id(db, last.envelope_id)
+            .await?
+            .filter(|env| !env.is_deleted)
+        else {
+            ctx.say("❌ The envelope of your last spend no longer exists.")
+                .await?;
+            return Ok(());
+        };
+
+        // The modal must be the direct response to the command
+        let defaults = AgainModal {
+            amount: amount.unwrap_or_else(|| format!("{:.2}", -last.amount)),
+            description: Some(last.description.chars().take(100).collect()),
+        };
+        let Some(form) =
+            poise::execute_modal(app_ctx, Some(defaults), Some(CAPTURE_TIMEOUT)).await?
+        else {
+            return Ok(());
+        };
+
+        let amount = match amount_parser::parse_household_amount(db, &form.amount).await {
+            Ok(amount) if amount > 0.0 => amount,
+            Ok(_) => {
+                ctx.say("❌ Invalid amount: must be greater than zero")
+                    .await?;
+                return Ok(());
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        if !permissions::check_spender(ctx, &envelope, &author_id).await? {
+            return Ok(());
+        }
+        let Some(clearance) =
+            daily_limit::allow_spend(ctx, &envelope, &author_id, amount, None).await?
+        else {
+            return Ok(());
+        };
+        let desc = envelope::spend_description(&envelope, form.description.as_deref());
+
+        // Large spends wait for another member's approval
+        if approval::needs_approval(db, amount).await? {
+            let held = approval::hold_spend(
+                db,
+                envelope.id,
+                &author_id,
+                amount,
+                desc,
+                last.merchant,
+                ctx.channel_id().to_string(),
+            )
+            .await?;
+            let currency = setup::get_currency_format(db).await?;
+            ctx.send(wishlist_buttons::hold_reply(
+                &held,
+                &envelope.name,
+                currency,
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let reply = delivery::prepare(ctx).await?;
+        let spend = transaction::create_cleared_transaction(
+            db,
+            envelope.id,
+            -amount,
+            desc,
+            author_id,
+            Some(ctx.id().to_string()), // Makes retried interactions idempotent
+            TransactionType::Spend,
+            clearance,
+        )
         .await?;
+        let spend = with_details(
+            db,
+            spend,
+            last.merchant.as_deref(),
+            None,
+            last.location.as_deref(),
+        )
+        .await;
+        remember_transaction(ctx, &spend, &envelope.name).await;
+
+        let confirmation = spend_confirmation(db, &envelope, &spend, None, false).await;
+        responses::confirm(ctx, reply, confirmation).await;
+        alerts::after_balance_change(ctx, &[envelope.id]).await;
+        notifications::post_spend_notes(ctx, std::slice::from_ref(&spend)).await;
 
         Ok(())
     }
 
-    /// Adds funds to an envelope.
+    /// Collects a message's text, including its embeds, where bots put their alerts.
+    fn message_text(message: &serenity::Message) -> String {
+        let mut text = message.content.clone();
+        for embed in &message.embeds {
+            for part in [&embed.title, &embed.description].into_iter().flatten() {
+                text.push('\n');
+                text.push_str(part);
+            }
+        }
+        text
+    }
+
+    /// Describes a new spend for [`responses::confirm`], with the envelope's new balance
+    /// and, unless it's pending, a button to save it as a product.
+    async fn spend_confirmation(
+        db: &sea_orm::DatabaseConnection,
+        envelope: &crate::entities::envelope::Model,
+        spend: &transaction_entity::Model,
+        conversion: Option<&fx::Conversion>,
+        is_pending: bool,
+    ) -> responses::Confirmation {
+        let converted_note = conversion.map_or_else(String::new, |c| {
+            format!(
+                " ({} {:.2} @ {:.4})",
+                c.original_currency,
+                c.original_amount.abs(),
+                c.rate
+            )
+        });
+        let currency = match setup::get_currency_format(db).await {
+            Ok(currency) => currency,
+            Err(e) => {
+                tracing::warn!("Failed to read the currency: {e}");
+                setup::Currency::default()
+            }
+        };
+        let quantity = report::format_quantity(spend, currency);
+        let quantity_note = quantity
+            .as_ref()
+            .map_or_else(String::new, |quantity| format!(" for {quantity}"));
+        let mut confirmation = responses::Confirmation::new(
+            format!("Spent from {}", envelope.name),
+            format!(
+                "✅ Spent {}{converted_note}{quantity_note} from envelope '{}' - {} (Transaction ID: {})",
+                currency.format(spend.amount.abs()),
+                envelope.name,
+                spend.description,
+                spend.id
+            ),
+        )
+        .field(
+            "Amount",
+            format!("{}{converted_note}", currency.format(spend.amount.abs())),
+            true,
+        )
+        .field("Description", &spend.description, true)
+        .field("Transaction ID", spend.id.to_string(), true);
+        if let Some(quantity) = quantity {
+            confirmation = confirmation.field("Quantity", quantity, true);
+        }
+        if let Some(location) = &spend.location {
+            confirmation = confirmation.field("Location", location, true);
+        }
+        if is_pending {
+            confirmation = confirmation.note(format!(
+                "⏳ Pending - confirm the posted amount with `/transaction clear id:{}`",
+                spend.id
+            ));
+        } else {
+            confirmation =
+                confirmation.components(product_quick_create::save_button(db, spend).await);
+        }
+        confirmation = confirmation.note(round_up_note(db, spend.id, currency).await);
+        confirmation = confirmation.note(category_cap_warning(db, &envelope.category).await);
+        with_balance(db, confirmation, envelope.id).await
+    }
+
+    /// Notes the change a spend was rounded up by, if round-ups are on.
+    async fn round_up_note(
+        db: &sea_orm::DatabaseConnection,
+        spend_id: i64,
+        currency: setup::Currency,
+    ) -> String {
+        match round_up::get_round_up(db, spend_id).await {
+            Ok(Some(change)) => format!("🪙 Rounded up: {} saved", currency.format(change)),
+            Ok(None) => String::new(),
+            Err(e) => {
+                tracing::warn!("Failed to look up the round-up of transaction {spend_id}: {e}");
+                String::new()
+            }
+        }
+    }
+
+    /// Adds the envelope's current balance to a confirmation.
+    ///
+    /// A failed lookup is logged and the confirmation is sent without it, since the write
+    /// it confirms has already happened.
+    async fn with_balance(
+        db: &sea_orm::DatabaseConnection,
+        confirmation: responses::Confirmation,
+        envelope_id: i64,
+    ) -> responses::Confirmation {
+        match envelope::get_envelope_by_id(db, envelope_id).await {
+            Ok(Some(updated)) => confirmation.balance(updated),
+            Ok(None) => confirmation,
+            Err(e) => {
+                tracing::warn!("Failed to load envelope {envelope_id} for a confirmation: {e}");
+                confirmation
+            }
+        }
+    }
+
+    /// Records the merchant given to `/spend` on a new transaction.
+    ///
+    /// Failures are logged rather than returned, so the spend keeps the merchant taken from
+    /// its description.
+    async fn with_merchant(
+        db: &sea_orm::DatabaseConnection,
+        spend: transaction_entity::Model,
+        merchant: Option<&str>,
+    ) -> transaction_entity::Model {
+        let Some(merchant) = merchant else {
+            return spend;
+        };
+        match transaction::set_merchant(db, spend.id, merchant).await {
+            Ok(updated) => updated,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to set merchant '{merchant}' on transaction {}: {e}",
+                    spend.id
+                );
+                spend
+            }
+        }
+    }
+
+    /// Records the merchant, quantity, and location given to `/spend` on a new spend,
+    /// keeping the spend if any of them fails.
+    async fn with_details(
+        db: &sea_orm::DatabaseConnection,
+        spend: transaction_entity::Model,
+        merchant: Option<&str>,
+        quantity: Option<(f64, Option<String>)>,
+        location: Option<&str>,
+    ) -> transaction_entity::Model {
+        let spend = with_merchant(db, spend, merchant).await;
+        let spend = with_quantity(db, spend, quantity).await;
+        with_location(db, spend, location).await
+    }
+
+    /// Records where a new spend was made, keeping the spend if that fails.
+    async fn with_location(
+        db: &sea_orm::DatabaseConnection,
+        spend: transaction_entity::Model,
+        location: Option<&str>,
+    ) -> transaction_entity::Model {
+        let Some(place) = location else {
+            return spend;
+        };
+        match location::set_location(db, spend.id, place).await {
+            Ok(updated) => updated,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to set location '{place}' on transaction {}: {e}",
+                    spend.id
+                );
+                spend
+            }
+        }
+    }
+
+    /// Records the quantity bought with a new spend, keeping the spend if that fails.
+    async fn with_quantity(
+        db: &sea_orm::DatabaseConnection,
+        spend: transaction_entity::Model,
+        quantity: Option<(f64, Option<String>)>,
+    ) -> transaction_entity::Model {
+        let Some((quantity, unit)) = quantity else {
+            return spend;
+        };
+        match transaction::set_quantity(db, spend.id, quantity, unit.as_deref()).await {
+            Ok(updated) => updated,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to set quantity {quantity} on transaction {}: {e}",
+                    spend.id
+                );
+                spend
+            }
+        }
+    }
+
+    /// Returns a warning line when the category is past its (soft) monthly cap.
+    ///
+    /// Failures are logged rather than returned, so the warning never fails the spend.
+    async fn category_cap_warning(db: &sea_orm::DatabaseConnection, category: &str) -> String {
+        let status = match calendar::today(db).await {
+            Ok(today) => category_cap::get_category_status(db, category, today).await,
+            Err(e) => Err(e),
+        };
+        let currency = setup::get_currency_format(db).await.unwrap_or_default();
+        match status {
+            Ok(status) if status.is_over_cap() => status.cap.map_or_else(String::new, |cap| {
+                format!(
+                    "\n⚠️ Category '{category}' is over its {} monthly cap ({} spent).",
+                    currency.format(cap.cap),
+                    currency.format(status.spent)
+                )
+            }),
+            Ok(_) => String::new(),
+            Err(e) => {
+                tracing::warn!("Failed to check the cap for category '{category}': {e}");
+                String::new()
+            }
+        }
+    }
+
+    /// Adds funds to an envelope, or spreads a deposit across envelopes.
     ///
     /// This command increases the envelope balance by the specified amount and creates
     /// a transaction record for tracking purposes. If no user is specified, it uses the
     /// command author's ID for individual envelopes or looks for shared envelopes.
+    ///
+    /// With `distribute`, the amount is split across the shared envelopes and the
+    /// member's own individual envelopes instead, in proportion to their allocations or
+    /// by the saved `/distribution_plan`, with one transaction per envelope.
     #[poise::command(slash_command, prefix_command)]
+    #[allow(clippy::too_many_lines)] // Single-envelope and distributed deposits share validation
     pub async fn addfunds(
         ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Amount to add"] amount: f64,
         #[description = "Name of the envelope to add funds to"]
         #[autocomplete = "autocomplete::autocomplete_envelope_name"]
-        envelope_name: String,
-        #[description = "Amount to add"] amount: f64,
-        #[description = "Optional user nickname (for individual envelopes)"]
-        #[autocomplete = "autocomplete::autocomplete_user"]
-        user: Option<String>,
+        envelope_name: Option<String>,
+        #[description = "Spread the amount across envelopes instead of one envelope"]
+        distribute: Option<DistributeChoice>,
+        #[description = "Member whose individual envelope to use (admins only for others)"]
+        user: Option<serenity::User>,
         #[description = "Optional description of the income"] description: Option<String>,
     ) -> Result<()> {
         const DEFAULT_DESCRIPTION: &str = "Income";
@@ -130,59 +879,863 @@ mod inner {
             return Ok(());
         }
 
-        // Resolve user nickname or default to the command author
+        // Only admins may act for another member
         let author_id = ctx.author().id.to_string();
-        let target_user_id = if let Some(nickname) = user {
-            // Try to resolve the provided nickname
-            if let Some(user_id) = users::resolve_nickname(&nickname) {
-                user_id
-            } else {
-                ctx.say(&format!(
-                    "❌ Unknown nickname '{}'. Available nicknames: {}",
-                    nickname,
-                    users::get_all_nicknames().join(", ")
-                ))
-                .await?;
-                return Ok(());
-            }
-        } else {
-            author_id.clone()
+        let Some(target_user_id) = permissions::target_user(ctx, user.as_ref()).await? else {
+            return Ok(());
         };
         let desc = description.as_deref().unwrap_or(DEFAULT_DESCRIPTION);
 
         // Get database connection from context
         let db = &ctx.data().database;
+        let currency = setup::get_currency_format(db).await?;
 
-        // Find the envelope by name and user
-        let envelope =
-            envelope::get_envelope_by_name_and_user(db, &envelope_name, &target_user_id).await?;
+        let envelope_name = match (envelope_name, distribute) {
+            (Some(envelope_name), None) => envelope_name,
+            (None, Some(choice)) => {
+                let shares = match choice {
+                    DistributeChoice::Allocation => {
+                        distribution::allocation_shares(db, &target_user_id, amount).await
+                    }
+                    DistributeChoice::Plan => {
+                        distribution::plan_shares(db, &target_user_id, amount).await
+                    }
+                };
+                let shares = match shares {
+                    Ok(shares) => shares,
+                    Err(Error::Config { message }) => {
+                        ctx.say(&format!("❌ {message}")).await?;
+                        return Ok(());
+                    }
+                    Err(Error::EnvelopeNotFound { name }) => {
+                        ctx.say(&format!(
+                            "❌ Envelope '{name}' from the distribution plan not found. \
+                             Update it with `/distribution_plan`.",
+                        ))
+                        .await?;
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e),
+                };
 
-        let Some(envelope) = envelope else {
-            ctx.say(&format!(
-                "❌ Envelope '{envelope_name}' not found. Use `/envelopes` to see available envelopes.",
-            ))
-            .await?;
+                let reply = delivery::prepare(ctx).await?;
+                let deposits = distribution::distribute_funds(
+                    db,
+                    &shares,
+                    desc,
+                    &author_id,
+                    Some(&ctx.id().to_string()), // Makes retried interactions idempotent
+                )
+                .await?;
+
+                let lines: Vec<String> = shares
+                    .iter()
+                    .map(|(env, share)| format!("• {}: {}", env.name, currency.format(*share)))
+                    .collect();
+                let confirmation = responses::Confirmation::new(
+                    format!("Distributed {}", currency.format(amount)),
+                    format!(
+                        "✅ Distributed {} across {} envelopes - {desc}",
+                        currency.format(amount),
+                        deposits.len()
+                    ),
+                )
+                .field("Description", desc, false)
+                .field("Deposits", lines.join("\n"), false);
+                responses::confirm(ctx, reply, confirmation).await;
+                let envelope_ids: Vec<i64> = shares.iter().map(|(env, _)| env.id).collect();
+                alerts::after_balance_change(ctx, &envelope_ids).await;
+                return Ok(());
+            }
+            _ => {
+                ctx.say("❌ Give either an envelope name or a `distribute` mode, not both.")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        // Find the envelope by name and user, offering the closest name for a typo
+        let Some(envelope) =
+            name_suggestions::resolve_envelope(ctx, &envelope_name, &target_user_id).await?
+        else {
             return Ok(());
         };
 
         // Create the transaction (positive amount for adding funds)
+        let reply = delivery::prepare(ctx).await?;
         let transaction_result = transaction::create_transaction(
             db,
             envelope.id,
             amount, // Positive amount for adding funds
             desc.to_string(),
             author_id.clone(),
-            None, // No Discord message ID
-            "addfunds".to_string(),
+            Some(ctx.id().to_string()), // Makes retried interactions idempotent
+            TransactionType::AddFunds,
         )
         .await?;
+        remember_transaction(ctx, &transaction_result, &envelope.name).await;
+
+        let confirmation = responses::Confirmation::new(
+            format!("Added Funds to {}", envelope.name),
+            format!(
+                "✅ Added {} to envelope '{}' - {} (Transaction ID: {})",
+                currency.format(amount),
+                envelope_name,
+                desc,
+                transaction_result.id
+            ),
+        )
+        .field("Amount", currency.format(amount), true)
+        .field("Description", desc, true)
+        .field("Transaction ID", transaction_result.id.to_string(), true);
+        let confirmation = with_balance(db, confirmation, envelope.id).await;
+        responses::confirm(ctx, reply, confirmation).await;
+        alerts::after_balance_change(ctx, &[envelope.id]).await;
+
+        Ok(())
+    }
+
+    /// How `/addfunds distribute` splits a deposit.
+    #[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+    pub enum DistributeChoice {
+        #[name = "by_allocation"]
+        Allocation,
+        #[name = "by_plan"]
+        Plan,
+    }
+
+    /// Shows or sets the household's percentage plan for `/addfunds distribute:by_plan`.
+    ///
+    /// The plan is written as `Envelope:percent` pairs separated by commas, e.g.
+    /// `Groceries:40, Rent:50, Fun:10`; the percentages must add up to 100.
+    #[poise::command(slash_command)]
+    pub async fn distribution_plan(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "New plan, e.g. Groceries:40, Rent:50, Fun:10"] plan: Option<String>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+
+        if let Some(plan) = plan {
+            let entries = match distribution::parse_plan(&plan) {
+                Ok(entries) => entries,
+                Err(Error::Config { message }) => {
+                    ctx.say(&format!("❌ {message}")).await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            distribution::set_plan(db, &entries).await?;
+            ctx.say(&format!(
+                "✅ Distribution plan saved: {}",
+                distribution::format_plan(&entries)
+            ))
+            .await?;
+            return Ok(());
+        }
+
+        let response = distribution::get_plan(db).await?.map_or_else(
+            || {
+                "📊 No distribution plan saved. Set one with \
+                 `/distribution_plan plan:Groceries:40, Rent:50, Fun:10`."
+                    .to_string()
+            },
+            |entries| {
+                format!(
+                    "📊 Distribution plan: {}",
+                    distribution::format_plan(&entries)
+                )
+            },
+        );
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Refunds part or all of a previous spend.
+    ///
+    /// Pick the spend from your recent transactions (or enter its ID). The refund credits
+    /// the original envelope and is recorded as a "refund" transaction linked to the spend,
+    /// so reports show the net cost.
+    #[poise::command(slash_command)]
+    pub async fn refund(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Spend to refund (pick a recent one or enter its transaction ID)"]
+        #[autocomplete = "autocomplete::autocomplete_refundable_transaction"]
+        transaction_id: i64,
+        #[description = "Amount to refund (defaults to the full remaining amount)"] amount: Option<
+            f64,
+        >,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let currency = setup::get_currency_format(db).await?;
+
+        // Private envelopes can only be refunded by their owner
+        let Some(original) = transaction::get_transaction_by_id(db, transaction_id).await? else {
+            ctx.say(&format!("❌ Transaction {transaction_id} not found."))
+                .await?;
+            return Ok(());
+        };
+        let Some(envelope) = envelope::get_envelope_by_id(db, original.envelope_id).await? else {
+            ctx.say(&format!(
+                "❌ The envelope for transaction {transaction_id} no longer exists."
+            ))
+            .await?;
+            return Ok(());
+        };
+        if !report::can_view(&envelope, &author_id) {
+            ctx.say(&format!("❌ Transaction {transaction_id} not found."))
+                .await?;
+            return Ok(());
+        }
+
+        let reply = delivery::prepare(ctx).await?;
+        let refund = match transaction::create_refund(
+            db,
+            transaction_id,
+            amount,
+            author_id,
+            Some(ctx.id().to_string()), // Makes retried interactions idempotent
+        )
+        .await
+        {
+            Ok(refund) => refund,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(Error::InvalidAmount { amount }) => {
+                let remaining =
+                    -original.amount - transaction::get_refunded_amount(db, transaction_id).await?;
+                ctx.say(&format!(
+                    "❌ Invalid refund amount {}: must be more than zero and at most {}.",
+                    currency.format(amount),
+                    currency.format(remaining)
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        remember_transaction(ctx, &refund, &envelope.name).await;
+
+        delivery::confirm(
+            ctx,
+            reply,
+            format!(
+                "✅ Refunded {} to envelope '{}' for transaction #{} - {} (Transaction ID: {})",
+                currency.format(refund.amount),
+                envelope.name,
+                transaction_id,
+                original.description,
+                refund.id
+            ),
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /// Parent command for managing recorded transactions.
+    #[poise::command(
+        slash_command,
+        rename = "transaction",
+        subcommands("transaction_clear", "transaction_pending", "transaction_purge")
+    )]
+    pub async fn transaction_manage(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Transaction management command. Available subcommands:\n\
+            `/transaction clear` - Confirm a pending spend with its posted amount\n\
+            `/transaction pending` - List spends that are still pending\n\
+            `/transaction purge` - Delete the transactions matching filters, after a preview (admin)";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Clears a pending spend, optionally with the posted amount if it differs.
+    ///
+    /// The envelope balance is adjusted by the difference, e.g. when a tip was added to an
+    /// authorized restaurant bill.
+    #[poise::command(slash_command, rename = "clear")]
+    pub async fn transaction_clear(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "ID of the pending spend"] id: i64,
+        #[description = "Posted amount, if it differs from the pending amount"] amount: Option<f64>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let currency = setup::get_currency_format(db).await?;
+
+        // Private envelopes can only be cleared by their owner
+        let Some(pending) = transaction::get_transaction_by_id(db, id).await? else {
+            ctx.say(&format!("❌ Transaction {id} not found.")).await?;
+            return Ok(());
+        };
+        let Some(envelope) = envelope::get_envelope_by_id(db, pending.envelope_id)
+            .await?
+            .filter(|env| report::can_view(env, &author_id))
+        else {
+            ctx.say(&format!("❌ Transaction {id} not found.")).await?;
+            return Ok(());
+        };
+
+        let reply = delivery::prepare(ctx).await?;
+        let cleared = match transaction::clear_transaction(db, id, amount).await {
+            Ok(cleared) => cleared,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(Error::InvalidAmount { amount }) => {
+                ctx.say(&format!(
+                    "❌ Invalid posted amount {}: must be more than zero.",
+                    currency.format(amount)
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(Error::InsufficientFunds { current, required }) => {
+                ctx.say(&format!(
+                    "❌ Envelope '{}' has {}, not enough for the extra {}.",
+                    envelope.name,
+                    currency.format(current),
+                    currency.format(required)
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(
+                e @ (Error::BelowMinimumBalance { .. }
+                | Error::EnvelopeFrozen { .. }
+                | Error::ApprovalRequired { .. }
+                | Error::DailyLimitExceeded { .. }
+                | Error::CategoryCapExceeded { .. }),
+            ) => {
+                ctx.say(&format!(
+                    "❌ Can't post the extra amount: {}.",
+                    e.user_message(currency)
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let change = cleared.amount - pending.amount;
+        let adjustment = if change == 0.0 {
+            String::new()
+        } else {
+            format!(
+                " (was {}, balance adjusted by {})",
+                currency.format(-pending.amount),
+                currency.format_signed(change)
+            )
+        };
+        delivery::confirm(
+            ctx,
+            reply,
+            format!(
+                "✅ Cleared transaction #{id} in '{}' - {}: {}{adjustment}",
+                envelope.name,
+                cleared.description,
+                currency.format(-cleared.amount)
+            ),
+        )
+        .await;
+        alerts::after_balance_change(ctx, &[envelope.id]).await;
+
+        Ok(())
+    }
+
+    /// Lists spends that are still pending.
+    #[poise::command(slash_command, rename = "pending")]
+    pub async fn transaction_pending(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let tz = calendar::get_timezone(db).await?;
+        let currency = setup::get_currency_format(db).await?;
+
+        let mut lines = Vec::new();
+        for tx in transaction::get_pending_transactions(db).await? {
+            let Some(env) = envelope::get_envelope_by_id(db, tx.envelope_id)
+                .await?
+                .filter(|env| report::can_view(env, &author_id))
+            else {
+                continue;
+            };
+            lines.push(format!(
+                "⏳ #{} {} - {} from '{}' - {}",
+                tx.id,
+                tx.timestamp.with_timezone(&tz).format("%Y-%m-%d"),
+                currency.format(-tx.amount),
+                env.name,
+                tx.description
+            ));
+        }
+
+        if lines.is_empty() {
+            ctx.say("✅ No pending spends.").await?;
+        } else {
+            ctx.say(format!(
+                "**Pending spends** (confirm with `/transaction clear`)\n{}",
+                lines.join("\n")
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes every transaction matching the filters, after a preview (admin only).
+    ///
+    /// The preview shows how many transactions match and their total; they're deleted only
+    /// once the admin presses "Delete", reversing their effect on the envelope balances.
+    #[poise::command(
+        slash_command,
+        rename = "purge",
+        guild_only,
+        required_permissions = "ADMINISTRATOR"
+    )]
+    pub async fn transaction_purge(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Only transactions in this envelope"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope_name: Option<String>,
+        #[description = "First day to include (YYYY-MM-DD)"] from: Option<String>,
+        #[description = "Last day to include (YYYY-MM-DD)"] to: Option<String>,
+        #[description = "Only transactions of this type"]
+        #[rename = "type"]
+        transaction_type: Option<ActivityTypeChoice>,
+        #[description = "Only transactions recorded by this member"] user: Option<serenity::User>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let mut criteria = transaction::TransactionCriteria::new();
+        let mut filters = Vec::new();
+        if let Some(name) = &envelope_name {
+            // An individual envelope of the named member (or the admin), else a shared one
+            let owner_id = user
+                .as_ref()
+                .map_or_else(|| ctx.author().id.to_string(), |user| user.id.to_string());
+            let Some(env) = envelope::find_envelope_for_user(db, name, &owner_id).await? else {
+                ctx.say(&format!("❌ Envelope '{name}' not found.")).await?;
+                return Ok(());
+            };
+            criteria = criteria.envelope(env.id);
+            filters.push(format!("envelope '{}'", env.name));
+        }
+        for (date, is_start) in [(&from, true), (&to, false)] {
+            let Some(date) = date else {
+                continue;
+            };
+            let date = match report::parse_date(date) {
+                Ok(date) => date,
+                Err(Error::Config { message }) => {
+                    ctx.say(&format!("❌ {message}")).await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            };
+            if is_start {
+                criteria = criteria.from(date);
+                filters.push(format!("from {date}"));
+            } else {
+                criteria = criteria.to(date);
+                filters.push(format!("to {date}"));
+            }
+        }
+        if let Some(choice) = transaction_type {
+            let transaction_type = TransactionType::from(choice);
+            criteria = criteria.transaction_type(transaction_type);
+            filters.push(format!("type {transaction_type}"));
+        }
+        if let Some(user) = &user {
+            criteria = criteria.user(user.id.to_string());
+            filters.push(format!("by <@{}>", user.id));
+        }
+
+        let preview = match transaction::preview_purge(db, &criteria).await {
+            Ok(preview) => preview,
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        if preview.count == 0 {
+            ctx.say("📭 No transactions match those filters.").await?;
+            return Ok(());
+        }
+        if let Some(refusal) = purge_refusal(&preview, &filters) {
+            ctx.say(&refusal).await?;
+            return Ok(());
+        }
+
+        let prompt = format!(
+            "⚠️ {} transaction(s) ({}) match, totaling {}. Deleting them reverses their \
+             effect on the envelope balances.",
+            preview.count,
+            filters.join(", "),
+            report::format_transaction_amount(
+                preview.total,
+                setup::get_currency_format(&ctx.data().database).await?
+            )
+        );
+        let (reply, confirmed) = confirm_purge(ctx, prompt, preview.count).await?;
+
+        let notice = if confirmed {
+            run_purge(ctx, &criteria, &filters).await?
+        } else {
+            "🚫 Purge cancelled; nothing was deleted.".to_string()
+        };
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content(notice)
+                    .components(Vec::new()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Explains why a purge that [`transaction::purge_transactions`] would refuse can't go
+    /// ahead, or `None` if it can.
+    fn purge_refusal(preview: &transaction::PurgeSummary, filters: &[String]) -> Option<String> {
+        let names = |names: &[String]| {
+            names
+                .iter()
+                .map(|name| format!("'{name}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let reason = if !preview.overdrawn.is_empty() {
+            format!(
+                "reversing them would leave {} below zero. Narrow the filters or add funds first.",
+                names(&preview.overdrawn)
+            )
+        } else if !preview.closed.is_empty() {
+            format!(
+                "some are in months that already have statements ({}). Narrow the filters to \
+                 after the last monthly update.",
+                names(&preview.closed)
+            )
+        } else {
+            return None;
+        };
+        Some(format!(
+            "❌ {} transaction(s) ({}) match, but {reason}",
+            preview.count,
+            filters.join(", ")
+        ))
+    }
+
+    /// Purges once the admin has confirmed, returning the notice that replaces the preview.
+    async fn run_purge(
+        ctx: poise::Context<'_, BotData, Error>,
+        criteria: &transaction::TransactionCriteria,
+        filters: &[String],
+    ) -> Result<String> {
+        let purged = match transaction::purge_transactions(&ctx.data().database, criteria).await {
+            Ok(purged) => purged,
+            // Balances may have moved since the preview
+            Err(Error::Config { message }) => return Ok(format!("❌ {message}")),
+            Err(e) => return Err(e),
+        };
+        tracing::info!(
+            "{} purged {} transaction(s) ({})",
+            ctx.author().id,
+            purged.count,
+            filters.join(", ")
+        );
+        Ok(format!(
+            "🗑️ Deleted {} transaction(s) totaling {}.",
+            purged.count,
+            report::format_transaction_amount(
+                purged.total,
+                setup::get_currency_format(&ctx.data().database).await?
+            )
+        ))
+    }
+
+    /// Shows the purge preview with "Delete" and "Cancel" buttons, returning its reply
+    /// and whether the admin pressed "Delete" before the buttons timed out.
+    async fn confirm_purge(
+        ctx: poise::Context<'_, BotData, Error>,
+        prompt: String,
+        count: usize,
+    ) -> Result<(poise::ReplyHandle<'_>, bool)> {
+        const CONFIRM_TIMEOUT: std::time::Duration = std::time::Duration::from_mins(1);
+
+        // Suffix the IDs with the interaction so concurrent prompts don't answer each other
+        let confirm_id = format!("transaction_purge:confirm:{}", ctx.id());
+        let cancel_id = format!("transaction_purge:cancel:{}", ctx.id());
+        let buttons = vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(&confirm_id)
+                .label(format!("Delete {count}"))
+                .style(serenity::ButtonStyle::Danger),
+            serenity::CreateButton::new(&cancel_id)
+                .label("Cancel")
+                .style(serenity::ButtonStyle::Secondary),
+        ])];
+        let reply = ctx
+            .send(
+                poise::CreateReply::default()
+                    .content(prompt)
+                    .components(buttons)
+                    .allowed_mentions(serenity::CreateAllowedMentions::new())
+                    .ephemeral(true),
+            )
+            .await?;
 
-        ctx.say(&format!(
-            "✅ Added ${:.2} to envelope '{}' - {} (Transaction ID: {})",
-            amount, envelope_name, desc, transaction_result.id
+        let ids = [confirm_id.clone(), cancel_id];
+        let interaction = serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+            .author_id(ctx.author().id)
+            .filter(move |interaction| ids.contains(&interaction.data.custom_id))
+            .timeout(CONFIRM_TIMEOUT)
+            .await;
+        let confirmed = match interaction {
+            Some(interaction) => {
+                interaction
+                    .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                    .await?;
+                interaction.data.custom_id == confirm_id
+            }
+            None => false,
+        };
+        Ok((reply, confirmed))
+    }
+
+    /// Kinds of transaction `/activity` can be narrowed to.
+    #[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+    pub enum ActivityTypeChoice {
+        #[name = "spend"]
+        Spend,
+        #[name = "addfunds"]
+        AddFunds,
+        #[name = "use_product"]
+        UseProduct,
+        #[name = "refund"]
+        Refund,
+        #[name = "interest"]
+        Interest,
+        #[name = "round_up"]
+        RoundUp,
+        #[name = "product_return"]
+        ProductReturn,
+    }
+
+    impl From<ActivityTypeChoice> for TransactionType {
+        fn from(choice: ActivityTypeChoice) -> Self {
+            match choice {
+                ActivityTypeChoice::Spend => Self::Spend,
+                ActivityTypeChoice::AddFunds => Self::AddFunds,
+                ActivityTypeChoice::UseProduct => Self::UseProduct,
+                ActivityTypeChoice::Refund => Self::Refund,
+                ActivityTypeChoice::Interest => Self::Interest,
+                ActivityTypeChoice::RoundUp => Self::RoundUp,
+                ActivityTypeChoice::ProductReturn => Self::ProductReturn,
+            }
+        }
+    }
+
+    /// Shows the most recent transactions across all envelopes.
+    ///
+    /// Each line shows who recorded the transaction, the envelope, the amount, its type,
+    /// and how long ago it happened. Other members' private envelopes are left out.
+    #[poise::command(slash_command, prefix_command)]
+    pub async fn activity(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Number of transactions to show (default 10, at most 25)"]
+        #[min = 1]
+        #[max = 25]
+        count: Option<u64>,
+        #[description = "Only transactions by this member"] user: Option<serenity::User>,
+        #[description = "Only transactions of this type"]
+        #[rename = "type"]
+        transaction_type: Option<ActivityTypeChoice>,
+    ) -> Result<()> {
+        const DEFAULT_COUNT: u64 = 10;
+        const MAX_COUNT: u64 = 25;
+
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+
+        let filter = transaction::ActivityFilter {
+            user_id: user.map(|user| user.id.to_string()),
+            transaction_type: transaction_type.map(Into::into),
+            viewer_id: Some(author_id),
+        };
+        let count = count.unwrap_or(DEFAULT_COUNT).clamp(1, MAX_COUNT);
+        let recent = transaction::get_recent_activity(db, &filter, count).await?;
+
+        if recent.is_empty() {
+            ctx.say("📭 No matching transactions yet.").await?;
+            return Ok(());
+        }
+
+        let currency = setup::get_currency_format(db).await?;
+        let mut envelope_names: HashMap<i64, String> = HashMap::new();
+        let mut lines = Vec::with_capacity(recent.len());
+        for tx in &recent {
+            let envelope_name = match envelope_names.entry(tx.envelope_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(
+                    envelope::get_envelope_by_id(db, tx.envelope_id)
+                        .await?
+                        .map_or_else(|| "unknown".to_string(), |env| env.name),
+                ),
+            };
+            lines.push(format!(
+                "• <t:{}:R> **{}** · {envelope_name} · {} {} - {} (#{})",
+                tx.timestamp.timestamp(),
+                users::get_user_display_name(&tx.user_id),
+                currency.format_signed(tx.amount),
+                tx.transaction_type,
+                tx.description,
+                tx.id
+            ));
+        }
+
+        ctx.say(format!(
+            "**Recent activity** (last {})\n{}",
+            recent.len(),
+            lines.join("\n")
         ))
         .await?;
+        Ok(())
+    }
 
+    /// Shows or sets your daily spending limit across all envelopes.
+    ///
+    /// Spends that would take the day's total past the limit ask for confirmation first,
+    /// unless they pass `override:true`. Per-envelope limits are set with
+    /// `/update_envelope daily_limit:`.
+    #[poise::command(slash_command)]
+    pub async fn daily_limit(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Most you may spend per day without an override, 0 to disable"]
+        amount: Option<f64>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let currency = setup::get_currency_format(db).await?;
+
+        if let Some(amount) = amount {
+            let new_limit = (amount != 0.0).then_some(amount);
+            match transaction::set_user_daily_limit(db, &author_id, new_limit).await {
+                Ok(()) => {}
+                Err(Error::InvalidAmount { amount }) => {
+                    ctx.say(&format!(
+                        "❌ Invalid daily limit: {}. Limits must be positive.",
+                        currency.format(amount)
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let spent = transaction::get_user_spent_on_day(db, &author_id, chrono::Utc::now()).await?;
+        let response = transaction::get_user_daily_limit(db, &author_id)
+            .await?
+            .map_or_else(
+                || {
+                    format!(
+                        "🚦 You have no daily limit; you've spent {} today. \
+                         Set one with `/daily_limit amount:`.",
+                        currency.format(spent)
+                    )
+                },
+                |limit| {
+                    format!(
+                        "🚦 Your daily limit is {}; you've spent {} today.",
+                        currency.format(limit),
+                        currency.format(spent)
+                    )
+                },
+            );
+        let prefix = if amount.is_some() {
+            "✅ Updated. "
+        } else {
+            ""
+        };
+        ctx.say(format!("{prefix}{response}")).await?;
+        Ok(())
+    }
+
+    /// Shows or sets the monthly spending cap shared by a category's envelopes.
+    ///
+    /// The cap covers the combined spending of every envelope in the category this budget
+    /// month. Hard caps deny spends that would go past it; soft caps allow them with a
+    /// warning.
+    #[poise::command(slash_command)]
+    pub async fn category_cap(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Envelope category"]
+        #[autocomplete = "autocomplete::autocomplete_category"]
+        category: String,
+        #[description = "Most the category may spend per month, 0 to remove the cap"]
+        amount: Option<f64>,
+        #[description = "Deny spends past the cap instead of warning (default: false)"]
+        hard: Option<bool>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let category = category.trim();
+        let currency = setup::get_currency_format(db).await?;
+
+        let existing = category_cap::get_cap(db, category).await?;
+        let new_cap = match (amount, &existing) {
+            (Some(0.0), _) => {
+                category_cap::remove_cap(db, category).await?;
+                None
+            }
+            (Some(amount), _) => Some((amount, hard.unwrap_or(false))),
+            (None, Some(cap)) => hard.map(|hard| (cap.cap, hard)),
+            (None, None) => None,
+        };
+        if let Some((amount, hard)) = new_cap {
+            match category_cap::set_cap(db, category, amount, hard).await {
+                Ok(_) => {}
+                Err(Error::InvalidAmount { amount }) => {
+                    ctx.say(&format!(
+                        "❌ Invalid cap: {}. Caps must be positive.",
+                        currency.format(amount)
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+                Err(Error::Config { message }) => {
+                    ctx.say(&format!("❌ {message}")).await?;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let today = calendar::today(db).await?;
+        let status = category_cap::get_category_status(db, category, today).await?;
+        let response = status.cap.as_ref().map_or_else(
+            || {
+                format!(
+                    "🧢 Category '{category}' has no cap; it has spent {} this month. \
+                     Set one with `/category_cap amount:`.",
+                    currency.format(status.spent)
+                )
+            },
+            |cap| {
+                format!(
+                    "🧢 Category '{category}' has a {} cap of {}; it has spent {} this month.",
+                    if cap.hard { "hard" } else { "soft" },
+                    currency.format(cap.cap),
+                    currency.format(status.spent)
+                )
+            },
+        );
+        let prefix = if amount.is_some() || hard.is_some() {
+            "✅ Updated. "
+        } else {
+            ""
+        };
+        ctx.say(format!("{prefix}{response}")).await?;
         Ok(())
     }
 }
@@ -196,6 +1749,7 @@ mod tests {
     #![allow(clippy::float_cmp)]
     use crate::{
         core::{envelope, transaction},
+        entities::TransactionType,
         errors::Result,
         test_utils::*,
     };
@@ -385,7 +1939,7 @@ mod tests {
             "Groceries shopping".to_string(),
             alice_id.to_string(),
             None,
-            "spend".to_string(),
+            TransactionType::Spend,
         )
         .await?;
 
@@ -0,0 +1,139 @@
+//! Undo Discord commands - `/undo` and its `list`, `last`, and `entry` subcommands.
+//!
+//! Spends, fund additions, refunds, product uses, and envelope edits and deletions are
+//! remembered in each member's undo history (see [`crate::core::undo`]). Members can
+//! reverse any of their last few changes, not only the latest.
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::BotData,
+        core::{
+            setup,
+            undo::{self, UndoAction},
+        },
+        entities::transaction,
+        errors::{Error, Result},
+    };
+    use std::fmt::Write;
+
+    /// Parent command for undoing your recent changes.
+    #[poise::command(slash_command, subcommands("undo_list", "undo_last", "undo_entry"))]
+    pub async fn undo(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Undo command. Available subcommands:\n\
+            `/undo list` - Show your recent changes that can be undone\n\
+            `/undo last` - Undo your latest change\n\
+            `/undo entry <id>` - Undo a specific change from `/undo list`";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Shows your recent changes that can still be undone, newest first.
+    #[poise::command(slash_command, rename = "list")]
+    pub async fn undo_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        let entries = undo::history(db, &author_id).await?;
+        if entries.is_empty() {
+            ctx.say("📭 Nothing to undo.").await?;
+            return Ok(());
+        }
+
+        let mut response = format!(
+            "↩️ **Your recent changes** (last {})\n",
+            undo::HISTORY_LENGTH
+        );
+        for entry in &entries {
+            writeln!(
+                response,
+                "• `#{}` {} - {}",
+                entry.id,
+                entry.created_at.format("%b %-d %H:%M"),
+                entry.summary
+            )?;
+        }
+        response.push_str("\nUndo one with `/undo entry <id>`.");
+        ctx.say(response).await?;
+        Ok(())
+    }
+
+    /// Undoes your latest change.
+    #[poise::command(slash_command, rename = "last")]
+    pub async fn undo_last(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        reply_undo(ctx, None).await
+    }
+
+    /// Undoes one of your recent changes, by its ID from `/undo list`.
+    #[poise::command(slash_command, rename = "entry")]
+    pub async fn undo_entry(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "ID of the change, from /undo list"] id: i64,
+    ) -> Result<()> {
+        reply_undo(ctx, Some(id)).await
+    }
+
+    /// Undoes a change and tells the author how it went.
+    async fn reply_undo(ctx: poise::Context<'_, BotData, Error>, id: Option<i64>) -> Result<()> {
+        let db = &ctx.data().database;
+        let author_id = ctx.author().id.to_string();
+        match undo::undo(db, &author_id, id).await {
+            Ok(entry) => {
+                ctx.say(&format!("↩️ Undid `#{}`: {}", entry.id, entry.summary))
+                    .await?;
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+            }
+            Err(e) => return Err(e),
+        }
+        Ok(())
+    }
+
+    /// Adds a change to the author's undo history.
+    ///
+    /// Failures are logged rather than returned, since the change itself has already
+    /// been made.
+    pub async fn remember_undo(
+        ctx: poise::Context<'_, BotData, Error>,
+        action: UndoAction,
+        summary: &str,
+    ) {
+        let author_id = ctx.author().id.to_string();
+        if let Err(e) = undo::record(&ctx.data().database, &author_id, &action, summary).await {
+            tracing::warn!("Failed to record '{summary}' in the undo history of {author_id}: {e}");
+        }
+    }
+
+    /// Adds a new transaction to the author's undo history.
+    ///
+    /// A transaction returned again for a retried command is only remembered once.
+    pub async fn remember_transaction(
+        ctx: poise::Context<'_, BotData, Error>,
+        recorded: &transaction::Model,
+        envelope_name: &str,
+    ) {
+        let (verb, preposition) = if recorded.amount < 0.0 {
+            ("Spent", "from")
+        } else {
+            ("Added", "to")
+        };
+        let currency = setup::get_currency_format(&ctx.data().database)
+            .await
+            .unwrap_or_default();
+        let summary = format!(
+            "{verb} {} {preposition} '{envelope_name}' - {}",
+            currency.format(recorded.amount.abs()),
+            recorded.description
+        );
+        let action = UndoAction::Transaction {
+            transaction_id: recorded.id,
+        };
+        remember_undo(ctx, action, &summary).await;
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
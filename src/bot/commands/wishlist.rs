@@ -0,0 +1,157 @@
+//! Wishlist Discord commands - `/wishlist propose` and `/wishlist list`.
+//!
+//! Proposals are posted with Approve and Decline buttons for the other member; the
+//! button clicks are handled in [`crate::bot::handlers::wishlist_buttons`].
+
+// Inner module to suppress missing_docs warnings for poise macro-generated code
+mod inner {
+    #![allow(missing_docs)]
+
+    use crate::{
+        bot::{
+            BotData,
+            handlers::{autocomplete, wishlist_buttons},
+            permissions,
+        },
+        config::users,
+        core::{envelope, setup, wishlist},
+        errors::{Error, Result},
+    };
+    use poise::serenity_prelude as serenity;
+    use std::fmt::Write;
+
+    /// Parent command for purchases that need another member's approval.
+    #[poise::command(slash_command, subcommands("wishlist_propose", "wishlist_list"))]
+    pub async fn wishlist(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let help_text = "Wishlist command. Available subcommands:\n\
+            `/wishlist propose` - Ask another member to approve a purchase\n\
+            `/wishlist list` - Show proposals waiting for a response";
+
+        ctx.say(help_text).await?;
+        Ok(())
+    }
+
+    /// Proposes a purchase from an envelope; it's spent once another member approves it.
+    #[poise::command(slash_command, rename = "propose")]
+    pub async fn wishlist_propose(
+        ctx: poise::Context<'_, BotData, Error>,
+        #[description = "Envelope the purchase would be spent from"]
+        #[autocomplete = "autocomplete::autocomplete_envelope_name"]
+        envelope_name: String,
+        #[description = "Purchase amount"] amount: f64,
+        #[description = "What you want to buy"] description: String,
+        #[description = "Member who should approve (defaults to your partner)"] approver: Option<
+            serenity::User,
+        >,
+        #[description = "Member whose individual envelope to use (admins only for others)"]
+        user: Option<serenity::User>,
+    ) -> Result<()> {
+        let db = &ctx.data().database;
+        let Some(proposer_id) = permissions::target_user(ctx, user.as_ref()).await? else {
+            return Ok(());
+        };
+
+        let approver_id = if let Some(approver) = approver {
+            approver.id.to_string()
+        } else {
+            // Default to the only other configured member
+            let others: Vec<String> = users::get_user_nicknames()
+                .into_keys()
+                .filter(|user_id| *user_id != proposer_id)
+                .collect();
+            let [user_id] = others.as_slice() else {
+                ctx.say("❌ Please choose who should approve this purchase.")
+                    .await?;
+                return Ok(());
+            };
+            user_id.clone()
+        };
+
+        let Some(envelope) =
+            envelope::find_envelope_for_user(db, &envelope_name, &proposer_id).await?
+        else {
+            ctx.say(&format!(
+                "❌ Envelope '{envelope_name}' not found. Use `/envelopes` to see available envelopes.",
+            ))
+            .await?;
+            return Ok(());
+        };
+
+        let purchase = match wishlist::propose_purchase(
+            db,
+            envelope.id,
+            &proposer_id,
+            &approver_id,
+            amount,
+            description,
+        )
+        .await
+        {
+            Ok(purchase) => purchase,
+            Err(Error::InvalidAmount { .. }) => {
+                ctx.say("❌ Invalid amount: must be a number greater than zero")
+                    .await?;
+                return Ok(());
+            }
+            Err(Error::Config { message }) => {
+                ctx.say(&format!("❌ {message}")).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        ctx.send(
+            poise::CreateReply::default()
+                .content(wishlist_buttons::proposal_message(
+                    &purchase,
+                    &envelope.name,
+                    setup::get_currency_format(db).await?,
+                ))
+                .components(wishlist_buttons::buttons(purchase.id)),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Lists wishlist proposals that are still waiting for a response.
+    #[poise::command(slash_command, rename = "list")]
+    pub async fn wishlist_list(ctx: poise::Context<'_, BotData, Error>) -> Result<()> {
+        let db = &ctx.data().database;
+        let pending = wishlist::get_pending_purchases(db).await?;
+
+        if pending.is_empty() {
+            ctx.say("ℹ️ No wishlist proposals or large spends are waiting for a response.")
+                .await?;
+            return Ok(());
+        }
+
+        let currency = setup::get_currency_format(db).await?;
+        let mut response = String::from("🛍️ **Pending wishlist proposals:**\n");
+        for purchase in &pending {
+            let envelope_name = envelope::get_envelope_by_id(db, purchase.envelope_id)
+                .await?
+                .map_or_else(|| "unknown".to_string(), |env| env.name);
+            let approver = purchase
+                .approver_id
+                .as_deref()
+                .map_or_else(|| "any member".to_string(), users::get_user_display_name);
+            writeln!(
+                &mut response,
+                "• #{} {} → {}: {} from '{envelope_name}' - {} (expires <t:{}:R>)",
+                purchase.id,
+                users::get_user_display_name(&purchase.proposer_id),
+                approver,
+                currency.format(purchase.amount),
+                purchase.description,
+                purchase.expires_at.timestamp()
+            )?;
+        }
+
+        ctx.say(response).await?;
+        Ok(())
+    }
+}
+
+// Re-export all commands
+pub use inner::*;
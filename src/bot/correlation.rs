@@ -0,0 +1,72 @@
+//! Correlation IDs - Tags every log line written while handling a command.
+//!
+//! [`CorrelatedFramework`] wraps the poise framework and runs each interaction or message
+//! inside a `command` span whose `correlation_id` is the Discord interaction (or message)
+//! ID. Everything logged while the event is handled, including in core functions and
+//! database queries, carries the ID, so a single invocation can be followed through the
+//! logs and matched to the `message_id` stored on its transactions.
+
+use poise::serenity_prelude as serenity;
+use tracing::Instrument;
+
+/// Serenity framework that runs the wrapped framework inside a correlation span.
+pub struct CorrelatedFramework<F> {
+    inner: F,
+}
+
+impl<F> CorrelatedFramework<F> {
+    /// Wraps a framework, typically a `poise::Framework`.
+    pub const fn new(inner: F) -> Self {
+        Self { inner }
+    }
+}
+
+#[serenity::async_trait]
+impl<F: serenity::Framework> serenity::Framework for CorrelatedFramework<F> {
+    async fn init(&mut self, client: &serenity::Client) {
+        self.inner.init(client).await;
+    }
+
+    async fn dispatch(&self, ctx: serenity::Context, event: serenity::FullEvent) {
+        match correlation_span(&event) {
+            Some(span) => self.inner.dispatch(ctx, event).instrument(span).await,
+            None => self.inner.dispatch(ctx, event).await,
+        }
+    }
+}
+
+/// Builds the span for events that can run a command: interactions and messages.
+fn correlation_span(event: &serenity::FullEvent) -> Option<tracing::Span> {
+    match event {
+        serenity::FullEvent::InteractionCreate { interaction } => {
+            let (command, user) = match interaction {
+                serenity::Interaction::Command(command)
+                | serenity::Interaction::Autocomplete(command) => {
+                    (command.data.name.as_str(), Some(command.user.id.get()))
+                }
+                serenity::Interaction::Component(component) => (
+                    component.data.custom_id.as_str(),
+                    Some(component.user.id.get()),
+                ),
+                serenity::Interaction::Modal(modal) => {
+                    (modal.data.custom_id.as_str(), Some(modal.user.id.get()))
+                }
+                _ => ("", None),
+            };
+            Some(tracing::info_span!(
+                "command",
+                correlation_id = %interaction.id(),
+                command,
+                user
+            ))
+        }
+        serenity::FullEvent::Message { new_message } if !new_message.author.bot => {
+            Some(tracing::info_span!(
+                "command",
+                correlation_id = %new_message.id,
+                user = new_message.author.id.get()
+            ))
+        }
+        _ => None,
+    }
+}
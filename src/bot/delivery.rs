@@ -0,0 +1,189 @@
+//! Confirmation delivery - Retries command confirmations through Discord outages.
+//!
+//! Commands that write to the database call [`prepare`] before the write, which defers
+//! the interaction so Discord keeps it open, and [`confirm`] afterwards with the
+//! confirmation text. If Discord is briefly unavailable, [`confirm`] retries with
+//! exponential backoff, editing the deferred response so a retry never posts twice. A
+//! confirmation that still can't be delivered is logged and posted to the audit channel
+//! (see `/config set audit_channel`), since the write it confirms has already happened.
+
+use crate::{bot::BotData, core::setup, errors::Error};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// Attempts made to deliver a confirmation, including the first.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry; doubled for each retry after it.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// How a command's confirmation reaches the user, as decided by [`prepare`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplyTarget {
+    /// Edit the deferred interaction response
+    DeferredResponse,
+    /// Send an interaction followup (a reply, such as a prompt, was already sent)
+    Followup,
+    /// Send a channel message (prefix commands)
+    Message,
+}
+
+/// Gets a command ready to confirm a database write.
+///
+/// Slash commands that haven't replied yet are deferred, so the confirmation can be
+/// delivered by editing the response for as long as the interaction token is valid.
+///
+/// # Errors
+/// Returns an error if the interaction can't be deferred; nothing has been written yet
+/// in that case, so the command can fail normally.
+pub async fn prepare(ctx: poise::Context<'_, BotData, Error>) -> Result<ReplyTarget, Error> {
+    let poise::Context::Application(app) = ctx else {
+        return Ok(ReplyTarget::Message);
+    };
+    if app
+        .has_sent_initial_response
+        .load(std::sync::atomic::Ordering::SeqCst)
+    {
+        return Ok(ReplyTarget::Followup);
+    }
+    ctx.defer().await?;
+    Ok(ReplyTarget::DeferredResponse)
+}
+
+/// Delivers a text confirmation, retrying transient Discord failures with backoff.
+///
+/// Failures are logged and reported to the audit channel rather than returned, so a
+/// lost confirmation never turns a successful write into a failed command.
+pub async fn confirm(
+    ctx: poise::Context<'_, BotData, Error>,
+    target: ReplyTarget,
+    content: impl Into<String>,
+) {
+    let content = content.into();
+    let reply = poise::CreateReply::default().content(content.clone());
+    confirm_reply(ctx, target, reply, &content).await;
+}
+
+/// Like [`confirm`], for replies with embeds; `summary` describes the reply in the audit
+/// channel if it can't be delivered.
+pub async fn confirm_reply(
+    ctx: poise::Context<'_, BotData, Error>,
+    target: ReplyTarget,
+    reply: poise::CreateReply,
+    summary: &str,
+) {
+    let Err((attempts, error)) = send_with_retries(ctx, target, &reply).await else {
+        return;
+    };
+
+    tracing::error!(
+        "Gave up delivering confirmation for /{} to {} after {attempts} attempts: {error}",
+        ctx.command().qualified_name,
+        ctx.author().id
+    );
+    if let Err(e) = report_undelivered(ctx, summary).await {
+        tracing::warn!("Failed to post undelivered confirmation to the audit channel: {e}");
+    }
+}
+
+/// Sends a reply, retrying transient failures with exponential backoff.
+///
+/// On failure, returns the number of attempts made and the last error.
+async fn send_with_retries(
+    ctx: poise::Context<'_, BotData, Error>,
+    target: ReplyTarget,
+    reply: &poise::CreateReply,
+) -> Result<(), (u32, serenity::Error)> {
+    let mut attempt = 1;
+    loop {
+        let Err(error) = send(ctx, target, reply.clone()).await else {
+            return Ok(());
+        };
+        if attempt >= MAX_ATTEMPTS || !is_transient(&error) {
+            return Err((attempt, error));
+        }
+        let delay = backoff(attempt);
+        tracing::warn!(
+            "Confirmation for /{} not delivered (attempt {attempt}), retrying in {}ms: {error}",
+            ctx.command().qualified_name,
+            delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Makes one delivery attempt.
+async fn send(
+    ctx: poise::Context<'_, BotData, Error>,
+    target: ReplyTarget,
+    reply: poise::CreateReply,
+) -> Result<(), serenity::Error> {
+    match (target, ctx) {
+        (ReplyTarget::DeferredResponse, poise::Context::Application(app)) => {
+            let edit =
+                reply.to_slash_initial_response_edit(serenity::EditInteractionResponse::new());
+            app.interaction.edit_response(ctx.http(), edit).await?;
+        }
+        _ => {
+            ctx.send(reply).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether a failed request may succeed if retried (network errors, rate limits, and
+/// Discord server errors).
+fn is_transient(error: &serenity::Error) -> bool {
+    match error {
+        serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response)) => {
+            response.status_code.is_server_error()
+                || response.status_code == serenity::StatusCode::TOO_MANY_REQUESTS
+        }
+        serenity::Error::Http(serenity::HttpError::Request(_)) | serenity::Error::Io(_) => true,
+        _ => false,
+    }
+}
+
+/// Wait before retrying after `attempt` failed attempts.
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF * 2_u32.pow(attempt.saturating_sub(1))
+}
+
+/// Posts an undelivered confirmation to the audit channel, if one is configured.
+async fn report_undelivered(
+    ctx: poise::Context<'_, BotData, Error>,
+    summary: &str,
+) -> Result<(), Error> {
+    let Some(channel_id) = setup::get_audit_channel(&ctx.data().database)
+        .await?
+        .and_then(|id| id.parse().ok())
+        .map(serenity::ChannelId::new)
+    else {
+        return Ok(());
+    };
+
+    let message = serenity::CreateMessage::new()
+        .content(format!(
+            "📭 Couldn't deliver the confirmation for `/{}` to <@{}> in <#{}>. The change \
+             was saved:\n> {summary}",
+            ctx.command().qualified_name,
+            ctx.author().id,
+            ctx.channel_id()
+        ))
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+    channel_id.send_message(ctx.http(), message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles() {
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(2), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,187 @@
+//! Post-transaction alert delivery.
+//!
+//! Commands that change balances call [`after_balance_change`] after replying; other
+//! interactions (such as wishlist buttons) use [`after_balance_change_in`]. The hook runs
+//! every balance check in one place: low-balance alerts, overspend pings and pace
+//! warnings. The checks themselves live in [`crate::core::alerts`]; this module hands the
+//! resulting notifications to [`DiscordNotifier`] (unless `NOTIFIERS` turned Discord off)
+//! and to the optional backends in [`crate::core::notify`].
+
+use crate::{
+    bot::BotData,
+    core::{
+        alerts,
+        notify::{self, Audience, Notification, Notifier, NotifyFuture},
+        setup,
+    },
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use sea_orm::DatabaseConnection;
+
+/// Delivers notifications on Discord: household ones to the announcement channel and
+/// personal ones by DM.
+pub struct DiscordNotifier<'a> {
+    http: &'a serenity::Http,
+    db: &'a DatabaseConnection,
+    /// Receives household notifications when setup hasn't picked an announcement channel
+    fallback_channel: serenity::ChannelId,
+}
+
+impl<'a> DiscordNotifier<'a> {
+    /// Creates a notifier that posts through `http`.
+    #[must_use]
+    pub const fn new(
+        http: &'a serenity::Http,
+        db: &'a DatabaseConnection,
+        fallback_channel: serenity::ChannelId,
+    ) -> Self {
+        Self {
+            http,
+            db,
+            fallback_channel,
+        }
+    }
+
+    async fn post(&self, notification: &Notification) -> Result<()> {
+        let message = notification.mention.as_ref().map_or_else(
+            || serenity::CreateMessage::new().content(&notification.body),
+            |mention| {
+                serenity::CreateMessage::new()
+                    .content(format!("{mention} {}", notification.body))
+                    .allowed_mentions(allowed_mentions(mention))
+            },
+        );
+
+        match &notification.audience {
+            Audience::Member { user_id } => {
+                let user = user_id.parse().map(serenity::UserId::new).map_err(|_| {
+                    Error::UserNotFound {
+                        user_id: user_id.clone(),
+                    }
+                })?;
+                user.create_dm_channel(self.http)
+                    .await?
+                    .send_message(self.http, message)
+                    .await?;
+            }
+            Audience::Household => {
+                announcement_channel(self.db, self.fallback_channel)
+                    .await?
+                    .send_message(self.http, message)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Notifier for DiscordNotifier<'_> {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn send<'b>(&'b self, notification: &'b Notification) -> NotifyFuture<'b> {
+        Box::pin(self.post(notification))
+    }
+}
+
+/// Runs the post-transaction checks for the given envelopes and delivers any alerts.
+///
+/// Failures are logged rather than returned, so a missed alert never fails the command
+/// that changed the balance.
+pub async fn after_balance_change(ctx: poise::Context<'_, BotData, Error>, envelope_ids: &[i64]) {
+    after_balance_change_in(
+        ctx.serenity_context(),
+        &ctx.data().database,
+        ctx.channel_id(),
+        envelope_ids,
+    )
+    .await;
+}
+
+/// Like [`after_balance_change`], for interactions outside a command context.
+///
+/// `fallback_channel` receives channel alerts when setup hasn't picked an announcement
+/// channel.
+pub async fn after_balance_change_in(
+    ctx: &serenity::Context,
+    db: &DatabaseConnection,
+    fallback_channel: serenity::ChannelId,
+    envelope_ids: &[i64],
+) {
+    let discord = DiscordNotifier::new(&ctx.http, db, fallback_channel);
+    for notification in check_alerts(db, envelope_ids).await {
+        if notify::discord_enabled()
+            && let Err(e) = discord.send(&notification).await
+        {
+            tracing::warn!(
+                "Failed to deliver '{}' on Discord: {e}",
+                notification.subject
+            );
+        }
+        notify::send_external(notification);
+    }
+}
+
+/// Collects the low-balance alerts, overspend pings and pace warnings that fire for the
+/// given envelopes.
+async fn check_alerts(db: &DatabaseConnection, envelope_ids: &[i64]) -> Vec<Notification> {
+    let mut notifications = Vec::new();
+    collect(
+        &mut notifications,
+        alerts::check_low_balances(db, envelope_ids).await,
+        alerts::LowBalanceAlert::notification,
+        "low-balance alerts",
+    );
+    collect(
+        &mut notifications,
+        alerts::check_overspends(db, envelope_ids).await,
+        alerts::OverspendAlert::notification,
+        "overspend pings",
+    );
+    collect(
+        &mut notifications,
+        alerts::check_paces(db, envelope_ids).await,
+        alerts::PaceAlert::notification,
+        "pace warnings",
+    );
+    notifications
+}
+
+/// Adds the notifications for a check's alerts, logging the check's failure instead.
+fn collect<T>(
+    notifications: &mut Vec<Notification>,
+    checked: Result<Vec<T>>,
+    notification: fn(&T) -> Notification,
+    what: &str,
+) {
+    match checked {
+        Ok(alerts) => notifications.extend(alerts.iter().map(notification)),
+        Err(e) => tracing::warn!("Failed to check {what}: {e}"),
+    }
+}
+
+/// Allows only the given role or member mention to notify.
+fn allowed_mentions(mention: &str) -> serenity::CreateAllowedMentions {
+    // Mentions are stored normalized as `<@&id>` (role) or `<@id>` (member)
+    let inner = mention.trim_start_matches("<@").trim_end_matches('>');
+    match inner.strip_prefix('&').map(str::parse::<u64>) {
+        Some(Ok(id)) => serenity::CreateAllowedMentions::new().roles([id]),
+        _ => inner.parse::<u64>().map_or_else(
+            |_| serenity::CreateAllowedMentions::new(),
+            |id| serenity::CreateAllowedMentions::new().users([id]),
+        ),
+    }
+}
+
+/// Resolves the announcement channel, falling back to the channel the command was used in.
+async fn announcement_channel(
+    db: &DatabaseConnection,
+    fallback_channel: serenity::ChannelId,
+) -> Result<serenity::ChannelId> {
+    Ok(setup::get_announcement_channel(db)
+        .await?
+        .and_then(|id| id.parse().ok())
+        .map_or(fallback_channel, serenity::ChannelId::new))
+}
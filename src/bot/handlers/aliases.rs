@@ -0,0 +1,84 @@
+//! Custom prefix command aliases.
+//!
+//! Built-in aliases are registered on the commands themselves with [`register_builtin`].
+//! Server-specific aliases can change at runtime, so they are resolved when poise reports
+//! an unknown prefix command: [`dispatch_alias`] looks the name up in
+//! [`crate::core::alias`], rewrites the message to the real command, and dispatches it.
+
+use crate::{bot::BotData, core::alias, errors::Error};
+use poise::serenity_prelude as serenity;
+use std::any::Any;
+use tokio::sync::Mutex;
+
+/// Adds the built-in aliases (e.g. `!s` for `!spend`) to the matching commands.
+pub fn register_builtin(commands: &mut [poise::Command<BotData, Error>]) {
+    for (name, target) in alias::BUILTIN_ALIASES {
+        if let Some(command) = commands.iter_mut().find(|command| command.name == *target) {
+            command.aliases.push((*name).to_string());
+        }
+    }
+}
+
+/// Runs an unknown prefix command as the command its alias stands for.
+///
+/// # Returns
+/// `true` if the message named a custom alias and was dispatched
+pub async fn dispatch_alias(
+    framework: poise::FrameworkContext<'_, BotData, Error>,
+    ctx: &serenity::Context,
+    msg: &serenity::Message,
+    prefix: &str,
+    msg_content: &str,
+    trigger: poise::MessageDispatchTrigger,
+    invocation_data: &Mutex<Box<dyn Any + Send + Sync>>,
+) -> bool {
+    let Some(guild_id) = msg.guild_id else {
+        return false;
+    };
+    let (name, args) = msg_content
+        .split_once(char::is_whitespace)
+        .unwrap_or((msg_content, ""));
+
+    let command = match alias::resolve_alias(
+        &framework.user_data.database,
+        &guild_id.to_string(),
+        name,
+    )
+    .await
+    {
+        Ok(Some(command)) => command,
+        Ok(None) => return false,
+        Err(e) => {
+            tracing::warn!("Failed to resolve alias '{name}': {e}");
+            return false;
+        }
+    };
+    // Aliases pointing at removed commands are ignored rather than looping
+    if poise::find_command(
+        &framework.options.commands,
+        &command,
+        false,
+        &mut Vec::new(),
+    )
+    .is_none()
+    {
+        return false;
+    }
+
+    let mut expanded = msg.clone();
+    expanded.content = format!("{prefix}{command} {args}");
+    let mut parent_commands = Vec::new();
+    if let Err(error) = poise::dispatch_message(
+        framework,
+        ctx,
+        &expanded,
+        trigger,
+        invocation_data,
+        &mut parent_commands,
+    )
+    .await
+    {
+        (framework.options.on_error)(error).await;
+    }
+    true
+}
@@ -0,0 +1,414 @@
+//! Interactive allocation editor for `/allocations edit`.
+//!
+//! [`edit_allocations`] lists the envelopes with their monthly allocations and an "Edit"
+//! button per group of [`MODAL_FIELDS`] envelopes. Each button opens a modal prefilled
+//! with the group's allocations. Entered values are held until "Apply", which writes
+//! them all in one database transaction through [`envelope::set_allocations`] and
+//! replaces the list with a before/after diff.
+
+use crate::{
+    bot::BotData,
+    config::users,
+    core::{
+        amount_parser, envelope,
+        report::{self, EmbedColor},
+        setup::{self, Currency},
+    },
+    entities::envelope as envelope_entity,
+    errors::{Error, Result},
+};
+use poise::serenity_prelude::{self as serenity, futures::StreamExt};
+use std::{collections::HashMap, fmt::Write, time::Duration};
+
+/// How long the editor keeps responding to its buttons.
+const EDIT_TIMEOUT: Duration = Duration::from_mins(10);
+
+/// How long a modal waits for the admin to submit it.
+const MODAL_TIMEOUT: Duration = Duration::from_mins(5);
+
+/// Discord modals hold at most five text inputs.
+pub const MODAL_FIELDS: usize = 5;
+
+/// Most envelopes one editor lists: two rows of edit buttons, plus the Apply row.
+pub const MAX_ENVELOPES: usize = MODAL_FIELDS * 10;
+
+/// Discord limits modal titles and input labels to 45 characters.
+const MAX_LABEL: usize = 45;
+
+/// Custom ID prefix of the edit buttons.
+const EDIT_PREFIX: &str = "allocations:edit";
+/// Custom ID prefix of the apply button.
+const APPLY_PREFIX: &str = "allocations:apply";
+/// Custom ID prefix of the cancel button.
+const CANCEL_PREFIX: &str = "allocations:cancel";
+
+/// Runs the editor for `envelopes` until the command author applies or cancels their
+/// changes, or [`EDIT_TIMEOUT`] passes.
+///
+/// At most [`MAX_ENVELOPES`] envelopes are listed; callers narrow longer lists first.
+///
+/// # Errors
+/// Returns an error if the editor can't be sent or updated, or the changes can't be saved.
+pub async fn edit_allocations(
+    ctx: poise::Context<'_, BotData, Error>,
+    mut envelopes: Vec<envelope_entity::Model>,
+) -> Result<()> {
+    envelopes.truncate(MAX_ENVELOPES);
+    let key = ctx.id();
+    let mut edits: HashMap<i64, f64> = HashMap::new();
+    let color = report::get_theme(&ctx.data().database)
+        .await?
+        .color(EmbedColor::Accent);
+    let currency = setup::get_currency_format(&ctx.data().database).await?;
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(editor_embed(&envelopes, &edits, color, currency))
+                .components(buttons(key, envelopes.len(), &edits)),
+        )
+        .await?;
+
+    let prefix = format!(":{key}");
+    let mut collector = serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .filter(move |interaction| {
+            interaction.data.custom_id.starts_with("allocations:")
+                && interaction.data.custom_id.contains(&prefix)
+        })
+        .timeout(EDIT_TIMEOUT)
+        .stream();
+
+    while let Some(interaction) = collector.next().await {
+        let custom_id = interaction.data.custom_id.clone();
+        if custom_id.starts_with(APPLY_PREFIX) {
+            let content = apply(ctx, &envelopes, &edits, currency).await?;
+            return finish(ctx, &interaction, content).await;
+        }
+        if custom_id.starts_with(CANCEL_PREFIX) {
+            let content = "Allocation edit cancelled; nothing was changed.".to_string();
+            return finish(ctx, &interaction, content).await;
+        }
+        let Some(group) = parse_group(&custom_id) else {
+            continue;
+        };
+
+        let start = group * MODAL_FIELDS;
+        let end = (start + MODAL_FIELDS).min(envelopes.len());
+        let Some(members) = envelopes.get(start..end) else {
+            continue;
+        };
+        collect_group(ctx, &interaction, members, &mut edits, currency).await?;
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .embed(editor_embed(&envelopes, &edits, color, currency))
+                    .components(buttons(key, envelopes.len(), &edits)),
+            )
+            .await?;
+    }
+
+    // Held edits lapse with the buttons
+    reply
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .content("⌛ Allocation edit timed out; nothing was changed.")
+                .components(Vec::new()),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Opens the modal for one group of envelopes and records the values entered.
+///
+/// Values that aren't valid amounts are reported back to the admin and left unchanged.
+async fn collect_group(
+    ctx: poise::Context<'_, BotData, Error>,
+    interaction: &serenity::ComponentInteraction,
+    members: &[envelope_entity::Model],
+    edits: &mut HashMap<i64, f64>,
+    currency: Currency,
+) -> Result<()> {
+    let title = truncate_label(&format!(
+        "Allocations: {} – {}",
+        members.first().map_or("", |env| env.name.as_str()),
+        members.last().map_or("", |env| env.name.as_str())
+    ));
+    let mut modal = serenity::CreateQuickModal::new(title).timeout(MODAL_TIMEOUT);
+    for env in members {
+        let current = edits.get(&env.id).copied().unwrap_or(env.allocation);
+        modal = modal.field(
+            serenity::CreateInputText::new(serenity::InputTextStyle::Short, label(env), "")
+                .value(format!("{current:.2}"))
+                .required(true),
+        );
+    }
+
+    let Some(response) = interaction
+        .quick_modal(ctx.serenity_context(), modal)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    let separator = amount_parser::get_decimal_separator(&ctx.data().database).await?;
+    let mut problems = Vec::new();
+    for (env, input) in members.iter().zip(&response.inputs) {
+        match amount_parser::parse_localized_amount(input, separator) {
+            Ok(allocation) if allocation >= 0.0 => {
+                if (allocation - env.allocation).abs() < f64::EPSILON {
+                    edits.remove(&env.id);
+                } else {
+                    edits.insert(env.id, allocation);
+                }
+            }
+            Ok(allocation) => {
+                problems.push(format!(
+                    "• **{}**: {} is negative",
+                    env.name,
+                    currency.format(allocation)
+                ));
+            }
+            Err(e) => problems.push(format!("• **{}**: {e}", env.name)),
+        }
+    }
+
+    let answer = if problems.is_empty() {
+        serenity::CreateInteractionResponse::Acknowledge
+    } else {
+        serenity::CreateInteractionResponse::Message(
+            serenity::CreateInteractionResponseMessage::new()
+                .content(format!(
+                    "❌ These allocations weren't changed:\n{}",
+                    problems.join("\n")
+                ))
+                .ephemeral(true),
+        )
+    };
+    response.interaction.create_response(ctx, answer).await?;
+    Ok(())
+}
+
+/// Saves the held edits in one transaction and describes the result.
+async fn apply(
+    ctx: poise::Context<'_, BotData, Error>,
+    envelopes: &[envelope_entity::Model],
+    edits: &HashMap<i64, f64>,
+    currency: Currency,
+) -> Result<String> {
+    // Keep the listed order so the diff reads like the editor
+    let allocations: Vec<(i64, f64)> = envelopes
+        .iter()
+        .filter_map(|env| edits.get(&env.id).map(|&allocation| (env.id, allocation)))
+        .collect();
+
+    let changes = match envelope::set_allocations(&ctx.data().database, &allocations).await {
+        Ok(changes) => changes,
+        Err(Error::EnvelopeNotFound { .. }) => {
+            return Ok(
+                "❌ An envelope was deleted while you were editing; nothing was changed."
+                    .to_string(),
+            );
+        }
+        Err(e) => return Err(e),
+    };
+    if changes.is_empty() {
+        return Ok("No allocations were changed.".to_string());
+    }
+
+    for change in &changes {
+        tracing::info!(
+            "Allocation edit: user {} set allocation of '{}' from {:.2} to {:.2}",
+            ctx.author().id,
+            change.envelope_name,
+            change.before,
+            change.after
+        );
+    }
+    Ok(render_diff(envelopes, &changes, currency))
+}
+
+/// Replaces the editor with its final message and removes the buttons.
+async fn finish(
+    ctx: poise::Context<'_, BotData, Error>,
+    interaction: &serenity::ComponentInteraction,
+    content: String,
+) -> Result<()> {
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .embeds(Vec::new())
+                    .components(Vec::new()),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Renders the before/after diff of applied changes, with the listed envelopes' totals,
+/// in `currency`.
+fn render_diff(
+    envelopes: &[envelope_entity::Model],
+    changes: &[envelope::AllocationChange],
+    currency: Currency,
+) -> String {
+    let mut content = format!(
+        "✅ **Allocations updated** ({} envelope{})\n",
+        changes.len(),
+        if changes.len() == 1 { "" } else { "s" }
+    );
+    for change in changes {
+        let _ = writeln!(
+            content,
+            "• **{}**: {} → {} ({})",
+            change_label(change),
+            currency.format(change.before),
+            currency.format(change.after),
+            currency.format_signed(change.after - change.before)
+        );
+    }
+
+    let before: f64 = envelopes.iter().map(|env| env.allocation).sum();
+    let delta: f64 = changes.iter().map(|c| c.after - c.before).sum();
+    let _ = write!(
+        content,
+        "Total monthly allocation: {} → {}",
+        currency.format(before),
+        currency.format(before + delta)
+    );
+    content
+}
+
+/// Builds the editor embed in the theme's `color`, marking envelopes with a held edit.
+fn editor_embed(
+    envelopes: &[envelope_entity::Model],
+    edits: &HashMap<i64, f64>,
+    color: u32,
+    currency: Currency,
+) -> serenity::CreateEmbed {
+    let mut description = String::new();
+    for (index, env) in envelopes.iter().enumerate() {
+        let _ = match edits.get(&env.id) {
+            Some(allocation) => writeln!(
+                description,
+                "{}. {}: {} → **{}**",
+                index + 1,
+                label(env),
+                currency.format(env.allocation),
+                currency.format(*allocation)
+            ),
+            None => writeln!(
+                description,
+                "{}. {}: {}",
+                index + 1,
+                label(env),
+                currency.format(env.allocation)
+            ),
+        };
+    }
+
+    let before: f64 = envelopes.iter().map(|env| env.allocation).sum();
+    let after: f64 = envelopes
+        .iter()
+        .map(|env| edits.get(&env.id).copied().unwrap_or(env.allocation))
+        .sum();
+    let total = if edits.is_empty() {
+        currency.format(before)
+    } else {
+        format!("{} → {}", currency.format(before), currency.format(after))
+    };
+
+    serenity::CreateEmbed::default()
+        .title("Edit Allocations")
+        .color(color)
+        .description(description)
+        .field("Total", total, true)
+        .field("Changes", edits.len().to_string(), true)
+        .footer(serenity::CreateEmbedFooter::new(
+            "Edit a group of envelopes, then Apply to save every change at once.",
+        ))
+}
+
+/// Builds the edit buttons, one per group, and the Apply/Cancel row.
+fn buttons(key: u64, count: usize, edits: &HashMap<i64, f64>) -> Vec<serenity::CreateActionRow> {
+    let groups: Vec<serenity::CreateButton> = (0..count.div_ceil(MODAL_FIELDS))
+        .map(|group| {
+            let start = group * MODAL_FIELDS + 1;
+            let end = ((group + 1) * MODAL_FIELDS).min(count);
+            serenity::CreateButton::new(format!("{EDIT_PREFIX}:{key}:{group}"))
+                .label(format!("Edit {start}–{end}"))
+                .style(serenity::ButtonStyle::Secondary)
+        })
+        .collect();
+
+    let mut rows: Vec<serenity::CreateActionRow> = groups
+        .chunks(5)
+        .map(|row| serenity::CreateActionRow::Buttons(row.to_vec()))
+        .collect();
+    rows.push(serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{APPLY_PREFIX}:{key}"))
+            .label(format!("Apply {} change(s)", edits.len()))
+            .style(serenity::ButtonStyle::Success)
+            .disabled(edits.is_empty()),
+        serenity::CreateButton::new(format!("{CANCEL_PREFIX}:{key}"))
+            .label("Cancel")
+            .style(serenity::ButtonStyle::Danger),
+    ]));
+    rows
+}
+
+/// Returns the group index of an edit button ID.
+fn parse_group(custom_id: &str) -> Option<usize> {
+    custom_id
+        .strip_prefix(EDIT_PREFIX)?
+        .rsplit_once(':')?
+        .1
+        .parse()
+        .ok()
+}
+
+/// Names an envelope, with its owner's nickname for individual envelopes.
+fn label(env: &envelope_entity::Model) -> String {
+    truncate_label(&owned_name(&env.name, env.user_id.as_deref()))
+}
+
+/// Names a changed envelope like [`label`].
+fn change_label(change: &envelope::AllocationChange) -> String {
+    owned_name(&change.envelope_name, change.user_id.as_deref())
+}
+
+/// Adds the owner's nickname to an individual envelope's name.
+fn owned_name(name: &str, user_id: Option<&str>) -> String {
+    user_id.map_or_else(
+        || name.to_string(),
+        |uid| format!("{name} ({})", users::get_user_display_name(uid)),
+    )
+}
+
+/// Shortens text to fit a modal title or input label.
+fn truncate_label(text: &str) -> String {
+    if text.chars().count() <= MAX_LABEL {
+        text.to_string()
+    } else {
+        let mut short: String = text.chars().take(MAX_LABEL - 1).collect();
+        short.push('…');
+        short
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_group() {
+        assert_eq!(parse_group("allocations:edit:42:3"), Some(3));
+        assert_eq!(parse_group("allocations:apply:42"), None);
+        assert_eq!(truncate_label(&"x".repeat(50)).chars().count(), MAX_LABEL);
+    }
+}
@@ -6,9 +6,12 @@
 
 use crate::{
     bot::BotData,
-    core::{envelope, product},
+    core::{
+        bundle, calendar, favorite, preferences, product, settings, setup, statement, transaction,
+    },
     errors::Error,
 };
+use poise::serenity_prelude as serenity;
 
 /// Provides autocomplete suggestions for envelope names.
 ///
@@ -30,7 +33,7 @@ pub async fn autocomplete_envelope_name(
     let user_id = ctx.author().id.to_string();
 
     // Get all active envelopes
-    let Ok(envelopes) = envelope::get_all_active_envelopes(db).await else {
+    let Ok(envelopes) = ctx.data().cache.active_envelopes(db).await else {
         return Vec::new();
     };
 
@@ -40,7 +43,7 @@ pub async fn autocomplete_envelope_name(
     // 1. Name matches the partial input (case-insensitive)
     // 2. User can access it (shared OR belongs to user)
     let mut matching: Vec<String> = envelopes
-        .into_iter()
+        .iter()
         .filter(|env| {
             // Check if name matches
             let name_matches = env.name.to_lowercase().contains(&partial_lower);
@@ -50,7 +53,7 @@ pub async fn autocomplete_envelope_name(
 
             name_matches && can_access
         })
-        .map(|env| env.name) // Return just the envelope name without suffix
+        .map(|env| env.name.clone()) // Return just the envelope name without suffix
         .take(25) // Discord autocomplete limit
         .collect();
 
@@ -62,40 +65,201 @@ pub async fn autocomplete_envelope_name(
 /// Provides autocomplete suggestions for product names.
 ///
 /// This function queries the database for active products that match the user's
-/// partial input and returns up to 25 matching product names with their prices
-/// for easy identification.
+/// partial input and returns up to 25 matching product names. When the command has an
+/// `envelope` option that is already filled, only products from that envelope are
+/// suggested.
 ///
 /// # Arguments
 /// * `ctx` - The poise context containing the database connection
 /// * `partial` - The partial string the user has typed so far
 ///
 /// # Returns
-/// A vector of product names (with prices) that match the partial input
+/// Choices for the product names that match the partial input, labelled with the
+/// product's category when it has one
 pub async fn autocomplete_product_name(
     ctx: poise::Context<'_, BotData, Error>,
     partial: &str,
+) -> Vec<serenity::AutocompleteChoice> {
+    let db = &ctx.data().database;
+
+    // Get all active products and the envelopes they belong to
+    let Ok(products) = ctx.data().cache.active_products(db).await else {
+        return Vec::new();
+    };
+    let Ok(envelopes) = ctx.data().cache.active_envelopes(db).await else {
+        return Vec::new();
+    };
+
+    let envelope_name = filled_option(ctx, "envelope");
+    product::suggest_product_names(&products, &envelopes, partial, envelope_name.as_deref())
+        .into_iter()
+        .map(|name| {
+            let category = products
+                .iter()
+                .find(|prod| prod.name == name)
+                .and_then(|prod| prod.category.as_deref());
+            let label = category.map_or_else(|| name.clone(), |tag| format!("{name} [{tag}]"));
+            // Discord limits choice names to 100 characters
+            let label: String = label.chars().take(100).collect();
+            serenity::AutocompleteChoice::new(label, name)
+        })
+        .collect()
+}
+
+/// Provides autocomplete suggestions for product category tags.
+///
+/// # Returns
+/// Up to 25 category tags in use by active products that contain the partial input
+/// (case-insensitive)
+pub async fn autocomplete_product_category(
+    ctx: poise::Context<'_, BotData, Error>,
+    partial: &str,
+) -> Vec<String> {
+    let db = &ctx.data().database;
+
+    let Ok(products) = ctx.data().cache.active_products(db).await else {
+        return Vec::new();
+    };
+
+    let partial_lower = partial.trim().to_lowercase();
+    product::product_categories(&products)
+        .into_iter()
+        .filter(|tag| tag.contains(&partial_lower))
+        .take(25) // Discord autocomplete limit
+        .collect()
+}
+
+/// Returns the value of another string option the user has already filled in, looking
+/// inside subcommands.
+fn filled_option(ctx: poise::Context<'_, BotData, Error>, name: &str) -> Option<String> {
+    let poise::Context::Application(app_ctx) = ctx else {
+        return None;
+    };
+    find_string_option(&app_ctx.interaction.data.options, name)
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Searches command options, including nested subcommands, for a filled string option.
+fn find_string_option(options: &[serenity::CommandDataOption], name: &str) -> Option<String> {
+    options.iter().find_map(|option| match &option.value {
+        serenity::CommandDataOptionValue::String(value) if option.name == name => {
+            Some(value.clone())
+        }
+        serenity::CommandDataOptionValue::SubCommand(inner)
+        | serenity::CommandDataOptionValue::SubCommandGroup(inner) => {
+            find_string_option(inner, name)
+        }
+        _ => None,
+    })
+}
+
+/// Provides autocomplete suggestions for bundle names.
+///
+/// # Returns
+/// Up to 25 bundle names that contain the partial input (case-insensitive)
+pub async fn autocomplete_bundle_name(
+    ctx: poise::Context<'_, BotData, Error>,
+    partial: &str,
 ) -> Vec<String> {
     let db = &ctx.data().database;
 
-    // Get all active products
-    let Ok(products) = product::get_all_active_products(db).await else {
+    let Ok(bundles) = bundle::get_all_bundles(db).await else {
         return Vec::new();
     };
 
     let partial_lower = partial.to_lowercase();
 
-    // Filter products where name matches the partial input
-    // Return just the name (not formatted with price) so it matches command parameters exactly
-    let mut matching: Vec<String> = products
+    // Bundles are already sorted by name
+    bundles
         .into_iter()
-        .filter(|prod| prod.name.to_lowercase().contains(&partial_lower))
-        .map(|prod| prod.name)
+        .filter(|b| b.name.to_lowercase().contains(&partial_lower))
+        .map(|b| b.name)
         .take(25) // Discord autocomplete limit
-        .collect();
+        .collect()
+}
 
-    // Sort alphabetically for consistent UX
-    matching.sort();
-    matching
+/// Provides autocomplete suggestions for the caller's favorite labels.
+///
+/// # Returns
+/// Up to 25 of the caller's favorite labels that contain the partial input
+/// (case-insensitive)
+pub async fn autocomplete_favorite_label(
+    ctx: poise::Context<'_, BotData, Error>,
+    partial: &str,
+) -> Vec<String> {
+    let db = &ctx.data().database;
+    let user_id = ctx.author().id.to_string();
+
+    let Ok(favorites) = favorite::get_favorites_for_user(db, &user_id).await else {
+        return Vec::new();
+    };
+
+    let partial_lower = partial.to_lowercase();
+
+    // Favorites are already sorted by label
+    favorites
+        .into_iter()
+        .filter(|fav| fav.label.to_lowercase().contains(&partial_lower))
+        .map(|fav| fav.label)
+        .take(25) // Discord autocomplete limit
+        .collect()
+}
+
+/// Provides autocomplete suggestions for months that have statements.
+///
+/// # Returns
+/// Up to 25 statement months (`YYYY-MM`, newest first) that contain the partial input
+pub async fn autocomplete_statement_month(
+    ctx: poise::Context<'_, BotData, Error>,
+    partial: &str,
+) -> Vec<String> {
+    let db = &ctx.data().database;
+
+    let Ok(months) = statement::get_statement_months(db).await else {
+        return Vec::new();
+    };
+
+    months
+        .into_iter()
+        .filter(|month| month.contains(partial.trim()))
+        .take(25) // Discord autocomplete limit
+        .collect()
+}
+
+/// Provides autocomplete suggestions for `/config` setting keys.
+///
+/// # Returns
+/// The setting keys that contain the partial input
+#[allow(clippy::unused_async)]
+pub async fn autocomplete_setting_key(
+    _ctx: poise::Context<'_, BotData, Error>,
+    partial: &str,
+) -> Vec<String> {
+    let partial = partial.trim().to_lowercase();
+    settings::Setting::ALL
+        .iter()
+        .map(|setting| setting.key())
+        .filter(|key| key.contains(&partial))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Provides autocomplete suggestions for `/preferences set` keys.
+///
+/// # Returns
+/// The preference keys that contain the partial input
+#[allow(clippy::unused_async)]
+pub async fn autocomplete_preference_key(
+    _ctx: poise::Context<'_, BotData, Error>,
+    partial: &str,
+) -> Vec<String> {
+    let partial = partial.trim().to_lowercase();
+    preferences::Preference::ALL
+        .iter()
+        .map(|preference| preference.key())
+        .filter(|key| key.contains(&partial))
+        .map(str::to_string)
+        .collect()
 }
 
 /// Provides autocomplete suggestions for category names.
@@ -117,7 +281,7 @@ pub async fn autocomplete_category(
     let db = &ctx.data().database;
 
     // Get all categories from existing envelopes
-    let Ok(categories) = envelope::get_all_categories(db).await else {
+    let Ok(categories) = ctx.data().cache.categories(db).await else {
         return Vec::new();
     };
 
@@ -125,60 +289,60 @@ pub async fn autocomplete_category(
 
     // Filter categories that match the partial input
     categories
-        .into_iter()
+        .iter()
         .filter(|cat| cat.to_lowercase().contains(&partial_lower))
         .take(25) // Discord autocomplete limit
+        .cloned()
         .collect()
 }
 
-/// Provides autocomplete suggestions for user nicknames.
+/// Provides a picker of the user's recent refundable spends for `/refund`.
 ///
-/// This function shows all configured nicknames that match the partial input.
-/// Only nicknames are accepted - no Discord IDs or mentions.
+/// Each suggestion shows the transaction ID, amount, and description, and fills in the
+/// transaction ID when chosen. Typing digits narrows the list by ID; other text searches
+/// descriptions through the full-text index.
 ///
 /// # Arguments
-/// * `ctx` - The poise context
+/// * `ctx` - The poise context containing the database connection
 /// * `partial` - The partial string the user has typed so far
 ///
 /// # Returns
-/// A vector of nickname strings for autocomplete suggestions
-#[allow(clippy::unused_async)]
-pub async fn autocomplete_user(
+/// Autocomplete choices whose values are transaction IDs
+pub async fn autocomplete_refundable_transaction(
     ctx: poise::Context<'_, BotData, Error>,
     partial: &str,
-) -> Vec<String> {
-    use crate::config::users;
-
-    let author_id = ctx.author().id.to_string();
-    let author_nickname = users::get_nickname(&author_id);
-
-    // Get all configured nicknames
-    let all_nicknames = users::get_all_nicknames();
-    let partial_lower = partial.to_lowercase();
+) -> Vec<serenity::AutocompleteChoice> {
+    let db = &ctx.data().database;
+    let user_id = ctx.author().id.to_string();
 
-    let mut suggestions = Vec::new();
+    // Digits narrow by transaction ID; anything else searches descriptions
+    let partial = partial.trim();
+    let search = (!partial.chars().all(|c| c.is_ascii_digit())).then_some(partial);
 
-    // If no partial input, suggest author's nickname first, then all others
-    if partial.is_empty() {
-        if let Some(ref nick) = author_nickname {
-            suggestions.push(nick.clone());
-        }
-        suggestions.extend(all_nicknames.into_iter().filter(|nick| {
-            Some(nick) != author_nickname.as_ref() // Don't duplicate author's nickname
-        }));
-        suggestions.sort();
-        suggestions.truncate(25);
-        return suggestions;
-    }
-
-    // Find matching nicknames
-    for nickname in all_nicknames {
-        if nickname.to_lowercase().contains(&partial_lower) {
-            suggestions.push(nickname);
-        }
-    }
+    let Ok(spends) =
+        transaction::get_recent_refundable_transactions(db, &user_id, search, 25).await
+    else {
+        return Vec::new();
+    };
+    let tz = calendar::get_timezone(db)
+        .await
+        .unwrap_or(calendar::DEFAULT_TIMEZONE);
+    let currency = setup::get_currency_format(db).await.unwrap_or_default();
 
-    suggestions.sort();
-    suggestions.truncate(25); // Discord autocomplete limit
-    suggestions
+    spends
+        .into_iter()
+        .filter(|spend| search.is_some() || spend.id.to_string().starts_with(partial))
+        .map(|spend| {
+            let label = format!(
+                "#{} • {} • {} ({})",
+                spend.id,
+                currency.format(spend.amount.abs()),
+                spend.description,
+                spend.timestamp.with_timezone(&tz).format("%Y-%m-%d")
+            );
+            // Discord limits choice names to 100 characters
+            let label: String = label.chars().take(100).collect();
+            serenity::AutocompleteChoice::new(label, spend.id)
+        })
+        .collect()
 }
@@ -0,0 +1,126 @@
+//! Bill reminder delivery.
+//!
+//! The background scheduler collects reminders with [`crate::core::bill::due_reminders`]
+//! and hands them to [`announce_reminders`], which posts each one to the announcement
+//! channel (or the channel the bill was added from) and, for bills that ask for it,
+//! creates a Discord scheduled event on the due date.
+
+use crate::{
+    core::{bill::BillReminder, calendar, setup},
+    errors::{Error, Result},
+};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use poise::serenity_prelude as serenity;
+use sea_orm::DatabaseConnection;
+
+/// Where scheduled events for bills say they take place.
+const EVENT_LOCATION: &str = "Envelope Buddy";
+
+/// Posts each reminder and creates the scheduled events bills ask for.
+///
+/// Failures are logged rather than returned, so one failed delivery never blocks the
+/// other reminders.
+pub async fn announce_reminders(
+    http: &serenity::Http,
+    db: &DatabaseConnection,
+    reminders: &[BillReminder],
+    now: DateTime<Utc>,
+) {
+    for reminder in reminders {
+        if let Err(e) = post_reminder(http, db, reminder, now).await {
+            tracing::warn!(
+                "Failed to post reminder for bill '{}': {e}",
+                reminder.bill.name
+            );
+        }
+        if reminder.bill.create_event
+            && let Err(e) = create_event(http, db, reminder, now).await
+        {
+            tracing::warn!(
+                "Failed to create scheduled event for bill '{}': {e}",
+                reminder.bill.name
+            );
+        }
+    }
+}
+
+/// Posts a reminder to the announcement channel, or the bill's channel without one.
+async fn post_reminder(
+    http: &serenity::Http,
+    db: &DatabaseConnection,
+    reminder: &BillReminder,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let Some(channel_id) = setup::get_announcement_channel(db)
+        .await?
+        .or_else(|| reminder.bill.channel_id.clone())
+        .and_then(|id| id.parse().ok())
+        .map(serenity::ChannelId::new)
+    else {
+        tracing::warn!(
+            "No channel to post the reminder for bill '{}' to",
+            reminder.bill.name
+        );
+        return Ok(());
+    };
+
+    let today = calendar::local_date(db, now).await?;
+    let currency = setup::get_currency_format(db).await?;
+    let message = serenity::CreateMessage::new().content(reminder.message(today, currency));
+    channel_id.send_message(http, message).await?;
+    Ok(())
+}
+
+/// Creates a one-hour Discord scheduled event at local noon on the due date.
+///
+/// Events can't start in the past, so a reminder posted on the due day itself starts
+/// the event a few minutes from now instead.
+async fn create_event(
+    http: &serenity::Http,
+    db: &DatabaseConnection,
+    reminder: &BillReminder,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let Some(guild_id) = reminder
+        .bill
+        .guild_id
+        .as_deref()
+        .and_then(|id| id.parse().ok())
+        .map(serenity::GuildId::new)
+    else {
+        return Ok(());
+    };
+
+    let tz = calendar::get_timezone(db).await?;
+    let due_noon = reminder
+        .due_on
+        .and_hms_opt(12, 0, 0)
+        .and_then(|noon| tz.from_local_datetime(&noon).earliest())
+        .map_or(now, |noon| noon.with_timezone(&Utc));
+    let start = due_noon.max(now + Duration::minutes(5));
+    let end = start + Duration::hours(1);
+
+    let event = serenity::CreateScheduledEvent::new(
+        serenity::ScheduledEventType::External,
+        format!("Bill due: {}", reminder.bill.name),
+        timestamp(start)?,
+    )
+    .end_time(timestamp(end)?)
+    .location(EVENT_LOCATION)
+    .description(format!(
+        "{} from '{}'",
+        setup::get_currency_format(db)
+            .await?
+            .format(reminder.bill.amount),
+        reminder.envelope_name
+    ));
+    guild_id.create_scheduled_event(http, event).await?;
+    Ok(())
+}
+
+/// Converts a time to a Discord timestamp.
+fn timestamp(time: DateTime<Utc>) -> Result<serenity::Timestamp> {
+    serenity::Timestamp::from_unix_timestamp(time.timestamp()).map_err(|e| Error::Config {
+        message: format!("Invalid event time {time}: {e}"),
+    })
+}
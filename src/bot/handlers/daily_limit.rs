@@ -0,0 +1,129 @@
+//! Daily limit confirmations.
+//!
+//! When a spend would exceed a daily limit (see
+//! [`crate::core::transaction::check_daily_limits`]), the command asks the member to
+//! confirm with a button before spending. Passing `override:true` skips the prompt.
+//! Either way the spend is recorded with a [`Clearance`] past the limits, which are
+//! otherwise enforced again as it is recorded.
+
+use crate::{
+    bot::BotData,
+    core::{
+        setup,
+        transaction::{self, Clearance},
+    },
+    entities::envelope,
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// How long the member has to confirm an over-limit spend.
+const CONFIRM_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// Button that confirms the spend.
+const CONFIRM_ID: &str = "daily_limit:confirm";
+/// Button that cancels the spend.
+const CANCEL_ID: &str = "daily_limit:cancel";
+
+/// Decides whether a spend of `amount` (positive) may go ahead.
+///
+/// Returns the spend's clearance if it is within the daily limits, `override_limit` is
+/// set, or the author confirmed it; `None` if they cancelled or didn't answer.
+///
+/// # Errors
+/// Returns an error if the limits can't be checked or the prompt can't be sent.
+pub async fn allow_spend(
+    ctx: poise::Context<'_, BotData, Error>,
+    envelope: &envelope::Model,
+    user_id: &str,
+    amount: f64,
+    override_limit: Option<bool>,
+) -> Result<Option<Clearance>> {
+    let over_limit = Clearance {
+        over_daily_limit: true,
+        ..Default::default()
+    };
+    if override_limit.unwrap_or(false) {
+        return Ok(Some(over_limit));
+    }
+
+    let db = &ctx.data().database;
+    match transaction::check_daily_limits(db, envelope, user_id, amount, chrono::Utc::now()).await {
+        Ok(()) => Ok(Some(Clearance::default())),
+        Err(e @ Error::DailyLimitExceeded { .. }) => {
+            let reason = e.user_message(setup::get_currency_format(db).await?);
+            Ok(confirm_over_limit(ctx, &reason)
+                .await?
+                .then_some(over_limit))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Asks the command author to confirm an over-limit spend.
+///
+/// `reason` explains which limit would be exceeded. Returns `true` if the author pressed
+/// "Spend anyway"; on cancel or timeout the prompt is replaced with a notice and `false`
+/// is returned.
+///
+/// # Errors
+/// Returns an error if the prompt can't be sent or edited.
+pub async fn confirm_over_limit(
+    ctx: poise::Context<'_, BotData, Error>,
+    reason: &str,
+) -> Result<bool> {
+    // Suffix the IDs with the interaction so concurrent prompts don't answer each other
+    let confirm_id = format!("{CONFIRM_ID}:{}", ctx.id());
+    let cancel_id = format!("{CANCEL_ID}:{}", ctx.id());
+    let buttons = vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(&confirm_id)
+            .label("Spend anyway")
+            .style(serenity::ButtonStyle::Danger),
+        serenity::CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(serenity::ButtonStyle::Secondary),
+    ])];
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content(format!("⚠️ {reason}. Spend anyway?"))
+                .components(buttons)
+                .ephemeral(true),
+        )
+        .await?;
+
+    let ids = [confirm_id.clone(), cancel_id];
+    let interaction = serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .filter(move |interaction| ids.contains(&interaction.data.custom_id))
+        .timeout(CONFIRM_TIMEOUT)
+        .await;
+
+    let confirmed = match interaction {
+        Some(interaction) => {
+            interaction
+                .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            interaction.data.custom_id == confirm_id
+        }
+        None => false,
+    };
+
+    let notice = if confirmed {
+        "✅ Over-limit spend confirmed."
+    } else {
+        "🚫 Spend cancelled; nothing was recorded."
+    };
+    reply
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .content(notice)
+                .components(Vec::new()),
+        )
+        .await?;
+
+    Ok(confirmed)
+}
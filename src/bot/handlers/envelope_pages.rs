@@ -0,0 +1,254 @@
+//! Transaction paging for `/envelope_info`.
+//!
+//! `/envelope_info` registers a [`PageState`] under its interaction ID and attaches
+//! "Newer"/"Show more" buttons and a month select menu whose custom IDs carry that ID.
+//! Clicks arrive through the framework's event handler, which passes them to
+//! [`handle_component`]; it moves the view and edits the message in place. Views expire
+//! after [`VIEW_TTL`], after which the command has to be run again.
+
+use crate::{
+    bot::{BotData, handlers::reply_privately},
+    core::{report, setup, transaction},
+    errors::Result,
+};
+use chrono::NaiveDate;
+use poise::serenity_prelude as serenity;
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Transactions shown per page.
+pub const PAGE_SIZE: u64 = 5;
+
+/// How long a view keeps responding to its buttons.
+pub const VIEW_TTL: Duration = Duration::from_mins(15);
+
+/// Custom ID prefix shared by every paging component.
+const PREFIX: &str = "envelope_info:";
+
+/// Select menu option meaning "no month filter".
+const ALL_MONTHS: &str = "all";
+
+/// Discord allows at most 25 options in a select menu; one is "All months".
+const MAX_MONTH_OPTIONS: usize = 24;
+
+/// What one `/envelope_info` message is currently showing.
+#[derive(Debug, Clone)]
+pub struct PageState {
+    /// Envelope being shown
+    pub envelope_id: i64,
+    /// Discord user ID of the member who ran the command; only they can page
+    pub viewer_id: serenity::UserId,
+    /// Envelope details shown above the transactions
+    pub header: String,
+    /// Month filter (first day of the month), or `None` for all transactions
+    pub month: Option<NaiveDate>,
+    /// Zero-based page number
+    pub page: u64,
+    /// Months offered in the select menu, newest first
+    pub months: Vec<NaiveDate>,
+}
+
+/// Paging state of open `/envelope_info` messages, keyed by command interaction ID.
+#[derive(Debug, Default)]
+pub struct EnvelopePages {
+    views: Mutex<HashMap<u64, (Instant, PageState)>>,
+}
+
+impl EnvelopePages {
+    /// Registers a view, dropping views older than [`VIEW_TTL`].
+    pub fn insert(&self, key: u64, state: PageState) {
+        if let Ok(mut views) = self.views.lock() {
+            views.retain(|_, (created, _)| created.elapsed() < VIEW_TTL);
+            views.insert(key, (Instant::now(), state));
+        }
+    }
+
+    /// Returns a view if it exists and hasn't expired.
+    #[must_use]
+    pub fn get(&self, key: u64) -> Option<PageState> {
+        let views = self.views.lock().ok()?;
+        views
+            .get(&key)
+            .filter(|(created, _)| created.elapsed() < VIEW_TTL)
+            .map(|(_, state)| state.clone())
+    }
+
+    /// Replaces a view's state, keeping its original expiry.
+    fn update(&self, key: u64, state: PageState) {
+        if let Ok(mut views) = self.views.lock()
+            && let Some(entry) = views.get_mut(&key)
+        {
+            entry.1 = state;
+        }
+    }
+}
+
+/// Renders a view's message content and components.
+///
+/// Returns the state with its page clamped to the pages that exist.
+///
+/// # Errors
+/// Returns an error if the transactions can't be loaded or formatted.
+pub async fn render(
+    data: &BotData,
+    key: u64,
+    mut state: PageState,
+) -> Result<(String, Vec<serenity::CreateActionRow>, PageState)> {
+    let page = transaction::get_transaction_page(
+        &data.database,
+        state.envelope_id,
+        state.month,
+        state.page,
+        PAGE_SIZE,
+    )
+    .await?;
+    state.page = page.page;
+
+    let title = state.month.map_or_else(
+        || "Recent Transactions".to_string(),
+        |month| format!("Transactions in {}", month.format("%B %Y")),
+    );
+    let mut content = format!("{}**{title}:**", state.header);
+    if page.pages > 1 {
+        write!(
+            &mut content,
+            " _(page {} of {})_",
+            page.page + 1,
+            page.pages
+        )?;
+    }
+    content.push('\n');
+    if page.transactions.is_empty() {
+        content.push_str("_No transactions_\n");
+    }
+    let currency = setup::get_currency_format(&data.database).await?;
+    for txn in &page.transactions {
+        writeln!(
+            &mut content,
+            "• {}",
+            report::format_transaction_summary(txn, currency)
+        )?;
+    }
+
+    let mut components = Vec::new();
+    if page.pages > 1 {
+        components.push(serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(format!("{PREFIX}{key}:newer"))
+                .label("◀ Newer")
+                .style(serenity::ButtonStyle::Secondary)
+                .disabled(page.page == 0),
+            serenity::CreateButton::new(format!("{PREFIX}{key}:older"))
+                .label("Show more ▶")
+                .style(serenity::ButtonStyle::Primary)
+                .disabled(page.page + 1 >= page.pages),
+        ]));
+    }
+    if !state.months.is_empty() {
+        components.push(serenity::CreateActionRow::SelectMenu(month_menu(
+            key, &state,
+        )));
+    }
+
+    Ok((content, components, state))
+}
+
+/// Builds the select menu for jumping between months.
+fn month_menu(key: u64, state: &PageState) -> serenity::CreateSelectMenu {
+    let mut options = vec![
+        serenity::CreateSelectMenuOption::new("All months", ALL_MONTHS)
+            .default_selection(state.month.is_none()),
+    ];
+    options.extend(state.months.iter().take(MAX_MONTH_OPTIONS).map(|month| {
+        serenity::CreateSelectMenuOption::new(
+            month.format("%B %Y").to_string(),
+            month.format("%Y-%m").to_string(),
+        )
+        .default_selection(state.month == Some(*month))
+    }));
+
+    serenity::CreateSelectMenu::new(
+        format!("{PREFIX}{key}:month"),
+        serenity::CreateSelectMenuKind::String { options },
+    )
+    .placeholder("Jump to month")
+}
+
+/// Handles a click on an `/envelope_info` paging component. Other interactions are
+/// ignored.
+///
+/// # Errors
+/// Returns an error if the transactions can't be loaded or the Discord response fails.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &BotData,
+) -> Result<()> {
+    let Some((key, action)) = interaction
+        .data
+        .custom_id
+        .strip_prefix(PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+    else {
+        return Ok(());
+    };
+    let Ok(key) = key.parse::<u64>() else {
+        return Ok(());
+    };
+
+    let Some(mut state) = data.envelope_pages.get(key) else {
+        return reply_privately(
+            ctx,
+            interaction,
+            "⌛ This view has expired. Run `/envelope_info` again to page through transactions.",
+        )
+        .await;
+    };
+    if interaction.user.id != state.viewer_id {
+        return reply_privately(
+            ctx,
+            interaction,
+            "❌ Only the member who ran this `/envelope_info` can page through it.",
+        )
+        .await;
+    }
+
+    match action {
+        "newer" => state.page = state.page.saturating_sub(1),
+        "older" => state.page += 1,
+        "month" => {
+            state.month = selected_month(interaction);
+            state.page = 0;
+        }
+        _ => return Ok(()),
+    }
+
+    let (content, components, state) = render(data, key, state).await?;
+    data.envelope_pages.update(key, state);
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(components),
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Reads the month chosen in the select menu, or `None` for "All months".
+fn selected_month(interaction: &serenity::ComponentInteraction) -> Option<NaiveDate> {
+    let serenity::ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind
+    else {
+        return None;
+    };
+    values
+        .first()
+        .filter(|value| value.as_str() != ALL_MONTHS)
+        .and_then(|value| NaiveDate::parse_from_str(&format!("{value}-01"), "%Y-%m-%d").ok())
+}
@@ -0,0 +1,88 @@
+//! Enrollment of members who are given the household's budget role.
+//!
+//! With `/config set budget_role`, a server member who gets the role is enrolled through
+//! [`setup::enroll_member`]: their copy of every individual envelope template is created
+//! and a welcome DM lists them. Role changes only reach the bot with the privileged
+//! Server Members intent, which is requested at startup once a budget role is set.
+
+use crate::{
+    bot::BotData,
+    config::users,
+    core::setup::{self, Currency},
+    entities::envelope,
+    errors::Result,
+};
+use poise::serenity_prelude as serenity;
+use std::fmt::Write;
+
+/// Enrolls the member if the update gave them the budget role.
+///
+/// A welcome DM the member doesn't accept is logged rather than returned; the envelopes
+/// are created either way.
+///
+/// # Errors
+/// Returns an error if a database query or insert fails.
+pub async fn handle_member_update(
+    ctx: &serenity::Context,
+    event: &serenity::GuildMemberUpdateEvent,
+    data: &BotData,
+) -> Result<()> {
+    let db = &data.database;
+    let Some(role_id) = setup::get_budget_role(db).await? else {
+        return Ok(());
+    };
+    if !event.roles.iter().any(|role| role.to_string() == role_id) {
+        return Ok(());
+    }
+    let user_id = event.user.id.to_string();
+    let Some(created) = setup::enroll_member(db, &user_id).await? else {
+        return Ok(());
+    };
+
+    // Members named in /setup or the .env keep their nickname
+    if users::get_nickname(&user_id).is_none() {
+        let nickname = event
+            .nick
+            .clone()
+            .unwrap_or_else(|| event.user.display_name().to_string());
+        setup::save_nickname(db, &user_id, &nickname).await?;
+        users::register_nicknames([(user_id.clone(), nickname)]);
+    }
+
+    let currency = setup::get_currency_format(db).await?;
+    let message = serenity::CreateMessage::new().content(welcome_message(&created, currency)?);
+    let sent = async {
+        let channel = event.user.id.create_dm_channel(&ctx.http).await?;
+        channel.send_message(&ctx.http, message).await
+    }
+    .await;
+    if let Err(e) = sent {
+        tracing::warn!("Failed to send the welcome DM to new member {user_id}: {e}");
+    }
+    Ok(())
+}
+
+/// The welcome DM: the member's new envelopes and the commands to start with.
+fn welcome_message(created: &[envelope::Model], currency: Currency) -> Result<String> {
+    let mut text = "👋 Welcome to the household budget!\n".to_string();
+    if created.is_empty() {
+        writeln!(text, "The household has no personal envelopes yet.")?;
+    } else {
+        writeln!(text, "Your personal envelopes are ready:")?;
+        for env in created {
+            writeln!(
+                text,
+                "• **{}** - {} a month ({})",
+                env.name,
+                currency.format(env.allocation),
+                env.category
+            )?;
+        }
+    }
+    write!(
+        text,
+        "\nRecord a purchase with `/spend`, see where everything stands with `/report summary`, \
+         and set how your confirmations look with `/preferences`. `/help` lists every command."
+    )?;
+    Ok(text)
+}
@@ -3,5 +3,63 @@
 //! This module provides handlers for Discord interactions such as autocomplete,
 //! button clicks, and other non-command interactions.
 
+/// Low-balance alert delivery after balance-changing commands
+pub mod alerts;
+/// Server-specific prefix command aliases
+pub mod aliases;
+/// Modal editor for `/allocations edit`
+pub mod allocation_editor;
 /// Autocomplete handlers for envelope names, product names, and categories
 pub mod autocomplete;
+/// Reminders and scheduled events for recurring bills
+pub mod bills;
+/// Confirmation buttons for spends over a daily limit
+pub mod daily_limit;
+/// Transaction paging buttons and month menu for `/envelope_info`
+pub mod envelope_pages;
+/// Envelopes and a welcome DM for members given the budget role
+pub mod member_onboarding;
+/// Allocation suggestion buttons posted after the monthly update
+pub mod monthly_review;
+/// "Did you mean?" prompts for mistyped envelope and product names
+pub mod name_suggestions;
+/// Alphabetical page buttons for `/product list`
+pub mod product_pages;
+/// "Save as product?" button and modal on `/spend` confirmations
+pub mod product_quick_create;
+/// Confirmation buttons for `/reconcile` balance fixes
+pub mod reconcile;
+/// Confirmations for scheduled spends run by the background scheduler
+pub mod scheduled;
+/// Check-off menus for `/list show`
+pub mod shopping_list_menus;
+/// Approve/Decline buttons for wishlist proposals and held large spends
+pub mod wishlist_buttons;
+
+use crate::errors::Result;
+use poise::serenity_prelude as serenity;
+
+/// A response message only the member who triggered the interaction sees.
+pub fn private_message(content: impl Into<String>) -> serenity::CreateInteractionResponse {
+    serenity::CreateInteractionResponse::Message(
+        serenity::CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    )
+}
+
+/// Answers a button click or menu selection with a message only the member sees,
+/// leaving the message they interacted with untouched.
+///
+/// # Errors
+/// Returns an error if the response can't be sent.
+pub async fn reply_privately(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    content: &str,
+) -> Result<()> {
+    interaction
+        .create_response(ctx, private_message(content))
+        .await?;
+    Ok(())
+}
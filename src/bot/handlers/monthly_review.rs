@@ -0,0 +1,289 @@
+//! Interactive monthly review.
+//!
+//! After `/update` closes a month, [`post_review`] lists the envelopes whose allocation
+//! didn't fit it (see [`crate::core::review`]) with a button per envelope to apply the
+//! suggested allocation. Accepted changes are logged and posted to the audit channel
+//! (see `/config set audit_channel`).
+
+use crate::{
+    bot::BotData,
+    core::{
+        review::{self, AllocationSuggestion, ReviewReason},
+        setup::{self, Currency},
+    },
+    errors::{Error, Result},
+};
+use poise::serenity_prelude::{self as serenity, futures::StreamExt};
+use std::{fmt::Write, time::Duration};
+
+/// How long the review keeps responding to its buttons.
+const REVIEW_TIMEOUT: Duration = Duration::from_mins(10);
+
+/// Custom ID prefix of the accept buttons.
+const ACCEPT_PREFIX: &str = "monthly_review:accept";
+/// Custom ID prefix of the skip buttons.
+const SKIP_PREFIX: &str = "monthly_review:skip";
+
+/// Discord allows at most five action rows per message; one row per envelope.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// What became of a suggestion during the review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    Pending,
+    Accepted,
+    Skipped,
+}
+
+/// Posts the review of the month `closed_month` and applies the suggestions the command
+/// author accepts until every one is answered or [`REVIEW_TIMEOUT`] passes.
+///
+/// Does nothing if no envelope needs a change.
+///
+/// # Errors
+/// Returns an error if the suggestions can't be loaded or the review can't be sent.
+pub async fn post_review(
+    ctx: poise::Context<'_, BotData, Error>,
+    closed_month: &str,
+) -> Result<()> {
+    let db = &ctx.data().database;
+    let mut suggestions = review::suggest_adjustments(db, closed_month).await?;
+    if suggestions.is_empty() {
+        return Ok(());
+    }
+    let hidden = suggestions.len().saturating_sub(MAX_SUGGESTIONS);
+    suggestions.truncate(MAX_SUGGESTIONS);
+    let currency = setup::get_currency_format(db).await?;
+
+    let key = ctx.id();
+    let mut decisions = vec![Decision::Pending; suggestions.len()];
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content(render(
+                    closed_month,
+                    &suggestions,
+                    &decisions,
+                    hidden,
+                    currency,
+                ))
+                .components(buttons(key, &suggestions, &decisions, currency)),
+        )
+        .await?;
+
+    let ids: Vec<String> = (0..suggestions.len())
+        .flat_map(|index| {
+            [
+                format!("{ACCEPT_PREFIX}:{key}:{index}"),
+                format!("{SKIP_PREFIX}:{key}:{index}"),
+            ]
+        })
+        .collect();
+    let mut collector = serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .filter(move |interaction| ids.contains(&interaction.data.custom_id))
+        .timeout(REVIEW_TIMEOUT)
+        .stream();
+
+    while decisions.contains(&Decision::Pending)
+        && let Some(interaction) = collector.next().await
+    {
+        interaction
+            .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+            .await?;
+        let Some((action, index)) = parse_id(&interaction.data.custom_id) else {
+            continue;
+        };
+        if decisions[index] != Decision::Pending {
+            continue;
+        }
+
+        decisions[index] = if action == ACCEPT_PREFIX {
+            accept(ctx, &suggestions[index], currency).await?
+        } else {
+            Decision::Skipped
+        };
+        reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content(render(
+                        closed_month,
+                        &suggestions,
+                        &decisions,
+                        hidden,
+                        currency,
+                    ))
+                    .components(buttons(key, &suggestions, &decisions, currency)),
+            )
+            .await?;
+    }
+
+    // Unanswered suggestions lapse with the buttons
+    reply
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .content(render(
+                    closed_month,
+                    &suggestions,
+                    &decisions,
+                    hidden,
+                    currency,
+                ))
+                .components(Vec::new()),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Applies a suggestion and records it; a suggestion that no longer applies is skipped.
+async fn accept(
+    ctx: poise::Context<'_, BotData, Error>,
+    suggestion: &AllocationSuggestion,
+    currency: Currency,
+) -> Result<Decision> {
+    match review::apply_suggestion(&ctx.data().database, suggestion).await {
+        Ok(_) => {}
+        Err(Error::EnvelopeNotFound { .. }) => return Ok(Decision::Skipped),
+        Err(e) => return Err(e),
+    }
+
+    let entry = format!(
+        "<@{}> changed **{}**'s allocation from {} to {} in the monthly review.",
+        ctx.author().id,
+        suggestion.envelope_name,
+        currency.format(suggestion.allocation),
+        currency.format(suggestion.suggested)
+    );
+    tracing::info!(
+        "Monthly review: user {} set allocation of '{}' from {:.2} to {:.2}",
+        ctx.author().id,
+        suggestion.envelope_name,
+        suggestion.allocation,
+        suggestion.suggested
+    );
+    if let Err(e) = post_audit_entry(ctx, &entry).await {
+        tracing::warn!("Failed to post monthly review change to the audit channel: {e}");
+    }
+    Ok(Decision::Accepted)
+}
+
+/// Posts an entry to the audit channel, if one is configured.
+async fn post_audit_entry(ctx: poise::Context<'_, BotData, Error>, entry: &str) -> Result<()> {
+    let Some(channel_id) = setup::get_audit_channel(&ctx.data().database)
+        .await?
+        .and_then(|id| id.parse().ok())
+        .map(serenity::ChannelId::new)
+    else {
+        return Ok(());
+    };
+
+    let message = serenity::CreateMessage::new()
+        .content(format!("📝 {entry}"))
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+    channel_id.send_message(ctx.http(), message).await?;
+    Ok(())
+}
+
+/// Splits a button ID into its prefix and suggestion index.
+fn parse_id(custom_id: &str) -> Option<(&'static str, usize)> {
+    let (prefix, index) = custom_id.rsplit_once(':')?;
+    let index = index.parse().ok()?;
+    [ACCEPT_PREFIX, SKIP_PREFIX]
+        .into_iter()
+        .find(|candidate| prefix.starts_with(candidate))
+        .map(|candidate| (candidate, index))
+}
+
+/// Renders the review message, with amounts in `currency`.
+fn render(
+    closed_month: &str,
+    suggestions: &[AllocationSuggestion],
+    decisions: &[Decision],
+    hidden: usize,
+    currency: Currency,
+) -> String {
+    let mut content = format!("📋 **Monthly Review ({closed_month})**\n");
+    for (suggestion, decision) in suggestions.iter().zip(decisions) {
+        let finding = match suggestion.reason {
+            ReviewReason::Overspent => {
+                format!("ended at {}", currency.format(suggestion.close_balance))
+            }
+            ReviewReason::Underspent => format!(
+                "spent {} of {}",
+                currency.format(suggestion.total_spent),
+                currency.format(suggestion.allocation)
+            ),
+        };
+        let status = match decision {
+            Decision::Pending => format!(
+                "suggest {} → {}",
+                currency.format(suggestion.allocation),
+                currency.format(suggestion.suggested)
+            ),
+            Decision::Accepted => format!(
+                "✅ allocation now {}",
+                currency.format(suggestion.suggested)
+            ),
+            Decision::Skipped => format!("⏭️ kept {}", currency.format(suggestion.allocation)),
+        };
+        let _ = writeln!(
+            content,
+            "• **{}** {finding}: {status}",
+            suggestion.envelope_name
+        );
+    }
+    if hidden > 0 {
+        let _ = writeln!(
+            content,
+            "_…and {hidden} more; adjust them with `/update_envelope`._"
+        );
+    }
+    content
+}
+
+/// Builds one row of buttons per undecided suggestion.
+fn buttons(
+    key: u64,
+    suggestions: &[AllocationSuggestion],
+    decisions: &[Decision],
+    currency: Currency,
+) -> Vec<serenity::CreateActionRow> {
+    suggestions
+        .iter()
+        .zip(decisions)
+        .enumerate()
+        .filter(|(_, (_, decision))| **decision == Decision::Pending)
+        .map(|(index, (suggestion, _))| {
+            let label = format!(
+                "{}: {}",
+                suggestion.envelope_name,
+                currency.format_signed(suggestion.delta())
+            );
+            serenity::CreateActionRow::Buttons(vec![
+                serenity::CreateButton::new(format!("{ACCEPT_PREFIX}:{key}:{index}"))
+                    .label(label)
+                    .style(serenity::ButtonStyle::Primary),
+                serenity::CreateButton::new(format!("{SKIP_PREFIX}:{key}:{index}"))
+                    .label("Keep")
+                    .style(serenity::ButtonStyle::Secondary),
+            ])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_id() {
+        assert_eq!(
+            parse_id("monthly_review:accept:42:3"),
+            Some((ACCEPT_PREFIX, 3))
+        );
+        assert_eq!(parse_id("monthly_review:skip:42:0"), Some((SKIP_PREFIX, 0)));
+        assert_eq!(parse_id("reconcile:confirm:42"), None);
+    }
+}
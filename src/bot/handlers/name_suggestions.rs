@@ -0,0 +1,153 @@
+//! "Did you mean?" prompts for mistyped envelope and product names.
+//!
+//! Commands look names up with [`resolve_envelope`] and [`resolve_product`]. When the
+//! exact lookup finds nothing but [`crate::core::fuzzy`] finds a close name, the member is
+//! asked to confirm it with a button instead of getting a dead-end error; confirming
+//! continues the command with the suggested name.
+
+use crate::{
+    bot::BotData,
+    core::{envelope, product},
+    entities::{envelope as envelope_entity, product as product_entity},
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// How long the member has to accept a suggestion.
+const CONFIRM_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// Button that accepts the suggestion.
+const ACCEPT_ID: &str = "suggestion:accept";
+/// Button that dismisses the suggestion.
+const DISMISS_ID: &str = "suggestion:dismiss";
+
+/// Finds `user_id`'s envelope named `name`, offering the closest name if there is none.
+///
+/// Returns `None` after telling the member the envelope wasn't found, or that they
+/// dismissed the suggestion.
+///
+/// # Errors
+/// Returns an error if a lookup fails or the prompt can't be sent.
+pub async fn resolve_envelope(
+    ctx: poise::Context<'_, BotData, Error>,
+    name: &str,
+    user_id: &str,
+) -> Result<Option<envelope_entity::Model>> {
+    let db = &ctx.data().database;
+    if let Some(env) = envelope::resolve_envelope(db, name, user_id).await? {
+        return Ok(Some(env));
+    }
+
+    let not_found =
+        format!("❌ Envelope '{name}' not found. Use `/envelopes` to see available envelopes.");
+    let Some(suggestion) = envelope::suggest_envelope_name(db, name, user_id).await? else {
+        ctx.say(not_found).await?;
+        return Ok(None);
+    };
+    if !confirm_suggestion(ctx, &format!("Envelope '{name}' not found"), &suggestion).await? {
+        return Ok(None);
+    }
+    let env = envelope::resolve_envelope(db, &suggestion, user_id).await?;
+    if env.is_none() {
+        ctx.say(not_found).await?;
+    }
+    Ok(env)
+}
+
+/// Finds the product named `name`, offering the closest name if there is none.
+///
+/// Returns `None` after telling the member the product wasn't found, or that they
+/// dismissed the suggestion.
+///
+/// # Errors
+/// Returns an error if a lookup fails or the prompt can't be sent.
+pub async fn resolve_product(
+    ctx: poise::Context<'_, BotData, Error>,
+    name: &str,
+) -> Result<Option<product_entity::Model>> {
+    let db = &ctx.data().database;
+    if let Some(prod) = product::get_product_by_name(db, name).await? {
+        return Ok(Some(prod));
+    }
+
+    let not_found = format!("❌ Product '{name}' not found.");
+    let Some(suggestion) = product::suggest_product_name(db, name).await? else {
+        ctx.say(not_found).await?;
+        return Ok(None);
+    };
+    if !confirm_suggestion(ctx, &format!("Product '{name}' not found"), &suggestion).await? {
+        return Ok(None);
+    }
+    let prod = product::get_product_by_name(db, &suggestion).await?;
+    if prod.is_none() {
+        ctx.say(not_found).await?;
+    }
+    Ok(prod)
+}
+
+/// Asks the command author whether they meant `suggestion`.
+///
+/// `problem` says what went wrong with the typed name. Returns `true` if the author
+/// accepted; on dismissal or timeout the prompt is replaced with a notice and `false` is
+/// returned.
+async fn confirm_suggestion(
+    ctx: poise::Context<'_, BotData, Error>,
+    problem: &str,
+    suggestion: &str,
+) -> Result<bool> {
+    // Suffix the IDs with the interaction so concurrent prompts don't answer each other
+    let accept_id = format!("{ACCEPT_ID}:{}", ctx.id());
+    let dismiss_id = format!("{DISMISS_ID}:{}", ctx.id());
+    let label: String = format!("Use '{suggestion}'").chars().take(80).collect();
+    let buttons = vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(&accept_id)
+            .label(label)
+            .style(serenity::ButtonStyle::Primary),
+        serenity::CreateButton::new(&dismiss_id)
+            .label("Cancel")
+            .style(serenity::ButtonStyle::Secondary),
+    ])];
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content(format!("❓ {problem}. Did you mean '{suggestion}'?"))
+                .components(buttons)
+                .ephemeral(true),
+        )
+        .await?;
+
+    let ids = [accept_id.clone(), dismiss_id];
+    let interaction = serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .filter(move |interaction| ids.contains(&interaction.data.custom_id))
+        .timeout(CONFIRM_TIMEOUT)
+        .await;
+
+    let accepted = match interaction {
+        Some(interaction) => {
+            interaction
+                .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            interaction.data.custom_id == accept_id
+        }
+        None => false,
+    };
+
+    let notice = if accepted {
+        format!("✅ Using '{suggestion}'.")
+    } else {
+        format!("🚫 {problem}; nothing was done.")
+    };
+    reply
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .content(notice)
+                .components(Vec::new()),
+        )
+        .await?;
+
+    Ok(accepted)
+}
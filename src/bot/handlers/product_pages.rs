@@ -0,0 +1,210 @@
+//! Alphabetical paging for `/product list`.
+//!
+//! Long product lists are split into pages of [`PAGE_SIZE`] products, in name order,
+//! with "Previous"/"Next" buttons. Each page's title shows the letters it spans. The
+//! buttons answer the command author until [`PAGE_TIMEOUT`] passes.
+
+use crate::{
+    bot::BotData,
+    core::{
+        report::{self, EmbedColor},
+        setup::{self, Currency},
+    },
+    entities::product,
+    errors::{Error, Result},
+};
+use poise::serenity_prelude::{self as serenity, futures::StreamExt};
+use std::time::Duration;
+
+/// Products shown per page.
+pub const PAGE_SIZE: usize = 10;
+
+/// How long the page buttons keep responding.
+const PAGE_TIMEOUT: Duration = Duration::from_mins(5);
+
+/// Button that shows the previous page.
+const PREV_ID: &str = "product_list:prev";
+/// Button that shows the next page.
+const NEXT_ID: &str = "product_list:next";
+
+/// A product as listed, with the name of the envelope it charges to.
+#[derive(Debug, Clone)]
+pub struct ListedProduct {
+    /// The product
+    pub product: product::Model,
+    /// Name of the linked envelope
+    pub envelope_name: String,
+}
+
+/// Sends the product list, with page buttons if it doesn't fit on one page.
+///
+/// `products` must already be sorted by name. `category` is the filter the list was
+/// made with, shown in the footer.
+///
+/// # Errors
+/// Returns an error if the list can't be sent or edited.
+pub async fn send_product_list(
+    ctx: poise::Context<'_, BotData, Error>,
+    products: &[ListedProduct],
+    category: Option<&str>,
+) -> Result<()> {
+    let color = report::get_theme(&ctx.data().database)
+        .await?
+        .color(EmbedColor::Accent);
+    let currency = setup::get_currency_format(&ctx.data().database).await?;
+    let pages: Vec<&[ListedProduct]> = products.chunks(PAGE_SIZE).collect();
+    if pages.len() <= 1 {
+        ctx.send(
+            poise::CreateReply::default()
+                .embed(page_embed(products, 0, 1, category, color, currency)),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    // Suffix the IDs with the interaction so concurrent lists don't page each other
+    let prev_id = format!("{PREV_ID}:{}", ctx.id());
+    let next_id = format!("{NEXT_ID}:{}", ctx.id());
+    let mut page = 0;
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .embed(page_embed(
+                    pages[page],
+                    page,
+                    pages.len(),
+                    category,
+                    color,
+                    currency,
+                ))
+                .components(vec![buttons(&prev_id, &next_id, page, pages.len())]),
+        )
+        .await?;
+
+    let ids = [prev_id.clone(), next_id.clone()];
+    let mut collector = serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .filter(move |interaction| ids.contains(&interaction.data.custom_id))
+        .timeout(PAGE_TIMEOUT)
+        .stream();
+
+    while let Some(interaction) = collector.next().await {
+        page = if interaction.data.custom_id == next_id {
+            (page + 1).min(pages.len() - 1)
+        } else {
+            page.saturating_sub(1)
+        };
+        interaction
+            .create_response(
+                ctx,
+                serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .embed(page_embed(
+                            pages[page],
+                            page,
+                            pages.len(),
+                            category,
+                            color,
+                            currency,
+                        ))
+                        .components(vec![buttons(&prev_id, &next_id, page, pages.len())]),
+                ),
+            )
+            .await?;
+    }
+
+    // Leave the last page up without buttons once they stop responding
+    reply
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .embed(page_embed(
+                    pages[page],
+                    page,
+                    pages.len(),
+                    category,
+                    color,
+                    currency,
+                ))
+                .components(Vec::new()),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Builds the embed for one page of products, in the theme's `color` with prices in
+/// `currency`.
+fn page_embed(
+    products: &[ListedProduct],
+    page: usize,
+    pages: usize,
+    category: Option<&str>,
+    color: u32,
+    currency: Currency,
+) -> serenity::CreateEmbed {
+    let title = if pages > 1 {
+        format!("**Product List** ({})", letter_range(products))
+    } else {
+        "**Product List**".to_string()
+    };
+
+    let fields = products.iter().map(|listed| {
+        let name = format!(
+            "{} ({})",
+            listed.product.name,
+            currency.format(listed.product.price)
+        );
+        let value = listed.product.category.as_ref().map_or_else(
+            || format!("Linked to: {}", listed.envelope_name),
+            |tag| format!("Linked to: {} • Category: {tag}", listed.envelope_name),
+        );
+        (name, value, false)
+    });
+
+    let mut footer = Vec::new();
+    if let Some(category) = category {
+        footer.push(format!("Category: {category}"));
+    }
+    if pages > 1 {
+        footer.push(format!("Page {} of {pages}", page + 1));
+    }
+
+    let embed = serenity::CreateEmbed::default()
+        .title(title)
+        .color(color)
+        .fields(fields);
+    if footer.is_empty() {
+        embed
+    } else {
+        embed.footer(serenity::CreateEmbedFooter::new(footer.join(" • ")))
+    }
+}
+
+/// Returns the first letters a page spans, e.g. `A–F`.
+fn letter_range(products: &[ListedProduct]) -> String {
+    let initial = |listed: Option<&ListedProduct>| {
+        listed
+            .and_then(|listed| listed.product.name.chars().next())
+            .map_or('?', |c| c.to_ascii_uppercase())
+    };
+    let (first, last) = (initial(products.first()), initial(products.last()));
+    if first == last {
+        first.to_string()
+    } else {
+        format!("{first}–{last}")
+    }
+}
+
+/// Builds the Previous/Next buttons, disabled at either end.
+fn buttons(prev_id: &str, next_id: &str, page: usize, pages: usize) -> serenity::CreateActionRow {
+    serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(prev_id)
+            .label("◀ Previous")
+            .style(serenity::ButtonStyle::Secondary)
+            .disabled(page == 0),
+        serenity::CreateButton::new(next_id)
+            .label("Next ▶")
+            .style(serenity::ButtonStyle::Secondary)
+            .disabled(page + 1 >= pages),
+    ])
+}
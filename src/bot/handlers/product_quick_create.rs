@@ -0,0 +1,221 @@
+//! "Save as product?" button on `/spend` confirmations.
+//!
+//! A spend for something bought repeatedly can be turned into a product in two clicks.
+//! [`save_button`] adds the button to the confirmation, with the spend's transaction ID
+//! in its custom ID. Clicks arrive through the framework's event handler, which passes
+//! them to [`handle_component`]: it opens a modal prefilled with the spend's description,
+//! amount (per unit when a quantity was recorded), and envelope, and saves the submitted
+//! values with [`product::create_product`].
+
+use crate::{
+    bot::{
+        BotData,
+        handlers::{private_message, reply_privately},
+    },
+    core::{amount_parser, envelope, product, setup, transaction},
+    entities::transaction as transaction_entity,
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// Custom ID prefix of the "Save as product?" button.
+const SAVE_PREFIX: &str = "product:quick_create:";
+
+/// How long the modal waits for the member to submit it.
+const MODAL_TIMEOUT: Duration = Duration::from_mins(5);
+
+/// Longest product name the modal accepts.
+const MAX_NAME_LEN: u16 = 100;
+
+/// Builds the button offered under a spend's confirmation, or no button if a product
+/// with the spend's description already exists.
+///
+/// A failed lookup is logged and the button left out, since the spend itself succeeded.
+pub async fn save_button(
+    db: &sea_orm::DatabaseConnection,
+    spend: &transaction_entity::Model,
+) -> Vec<serenity::CreateActionRow> {
+    match product::get_product_by_name(db, spend.description.trim()).await {
+        Ok(None) => vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(format!("{SAVE_PREFIX}{}", spend.id))
+                .label("Save as product?")
+                .emoji('💾')
+                .style(serenity::ButtonStyle::Secondary),
+        ])],
+        Ok(Some(_)) => Vec::new(),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to look up a product for transaction {}: {e}",
+                spend.id
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Handles a click on a "Save as product?" button. Other component interactions are
+/// ignored.
+///
+/// # Errors
+/// Returns an error if the spend can't be loaded, the modal can't be shown, or the
+/// product can't be saved for a reason other than invalid input.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &BotData,
+) -> Result<()> {
+    let Some(transaction_id) = interaction
+        .data
+        .custom_id
+        .strip_prefix(SAVE_PREFIX)
+        .and_then(|id| id.parse::<i64>().ok())
+    else {
+        return Ok(());
+    };
+
+    let db = &data.database;
+    let spend = transaction::get_transaction_by_id(db, transaction_id).await?;
+    let source = match &spend {
+        Some(spend) => envelope::get_envelope_by_id(db, spend.envelope_id).await?,
+        None => None,
+    };
+    let (Some(spend), Some(source)) = (spend, source) else {
+        return reply_privately(ctx, interaction, "❌ That spend no longer exists.").await;
+    };
+
+    // A spend with a quantity suggests the price of one unit
+    let price = spend.amount.abs() / spend.quantity.filter(|q| *q > 0.0).unwrap_or(1.0);
+    let modal = serenity::CreateQuickModal::new("Save as product")
+        .timeout(MODAL_TIMEOUT)
+        .field(
+            serenity::CreateInputText::new(serenity::InputTextStyle::Short, "Name", "")
+                .value(
+                    spend
+                        .description
+                        .chars()
+                        .take(usize::from(MAX_NAME_LEN))
+                        .collect::<String>(),
+                )
+                .max_length(MAX_NAME_LEN)
+                .required(true),
+        )
+        .field(
+            serenity::CreateInputText::new(serenity::InputTextStyle::Short, "Price", "")
+                .value(format!("{price:.2}"))
+                .required(true),
+        )
+        .field(
+            serenity::CreateInputText::new(serenity::InputTextStyle::Short, "Envelope", "")
+                .value(&source.name)
+                .required(true),
+        );
+
+    let Some(response) = interaction.quick_modal(ctx, modal).await? else {
+        return Ok(());
+    };
+    let [name, price, envelope_name] = response.inputs.as_slice() else {
+        return Ok(());
+    };
+    let content = save_product(
+        db,
+        &interaction.user.id.to_string(),
+        name,
+        price,
+        envelope_name,
+    )
+    .await?;
+    let saved = content.starts_with('✅');
+    response
+        .interaction
+        .create_response(ctx, private_message(content))
+        .await?;
+
+    // The button has done its job; failing to remove it is harmless
+    if saved {
+        let edit = serenity::EditMessage::new().components(Vec::new());
+        if let Err(e) = interaction
+            .channel_id
+            .edit_message(ctx, interaction.message.id, edit)
+            .await
+        {
+            tracing::debug!("Failed to remove the save-as-product button: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Creates the product entered in the modal and describes the outcome.
+async fn save_product(
+    db: &sea_orm::DatabaseConnection,
+    user_id: &str,
+    name: &str,
+    price: &str,
+    envelope_name: &str,
+) -> Result<String> {
+    let name = name.trim();
+    let price = match amount_parser::parse_household_amount(db, price).await {
+        Ok(price) if price >= 0.0 => price,
+        Ok(_) => return Ok("❌ The price can't be negative.".to_string()),
+        Err(Error::Config { message }) => return Ok(format!("❌ {message}")),
+        Err(e) => return Err(e),
+    };
+    if product::get_product_by_name(db, name).await?.is_some() {
+        return Ok(format!(
+            "⚠️ A product named '{name}' already exists. Product names must be unique."
+        ));
+    }
+    // The member's own envelope first, then a shared one, as in `/product add`
+    let envelope_name = envelope_name.trim();
+    let Some(target) = envelope::find_envelope_for_user(db, envelope_name, user_id).await? else {
+        return Ok(format!(
+            "❌ Could not find an envelope named '{envelope_name}' that you can use."
+        ));
+    };
+
+    match product::create_product(db, name.to_string(), price, target.id).await {
+        Ok(created) => Ok(format!(
+            "✅ Product '{}' saved with unit price **{}** in envelope '{}'. Log it next time with `/use_product`.",
+            created.name,
+            setup::get_currency_format(db).await?.format(created.price),
+            target.name
+        )),
+        Err(Error::Config { message }) => Ok(format!("❌ {message}")),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_save_product_from_modal() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+
+        let saved = save_product(&db, "user1", " Oat Milk ", "4.49", &env.name).await?;
+        assert!(saved.starts_with('✅'), "{saved}");
+        let created = product::get_product_by_name(&db, "Oat Milk").await?;
+        assert_eq!(created.map(|p| p.envelope_id), Some(env.id));
+
+        // Duplicates, bad prices, and unknown envelopes are explained, not saved
+        assert!(
+            save_product(&db, "user1", "Oat Milk", "4.49", &env.name)
+                .await?
+                .starts_with('⚠')
+        );
+        assert!(
+            save_product(&db, "user1", "Bread", "abc", &env.name)
+                .await?
+                .starts_with('❌')
+        );
+        assert!(
+            save_product(&db, "user1", "Bread", "3", "nowhere")
+                .await?
+                .starts_with('❌')
+        );
+        assert!(product::get_product_by_name(&db, "Bread").await?.is_none());
+        Ok(())
+    }
+}
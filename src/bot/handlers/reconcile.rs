@@ -0,0 +1,82 @@
+//! Reconcile confirmations.
+//!
+//! `/reconcile` lists the balances that drifted from their history and asks the admin to
+//! confirm with a button before [`crate::core::reconcile::fix_balances`] rewrites them.
+
+use crate::{
+    bot::BotData,
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// How long the admin has to confirm the fix.
+const CONFIRM_TIMEOUT: Duration = Duration::from_mins(2);
+
+/// Button that applies the fixes.
+const CONFIRM_ID: &str = "reconcile:confirm";
+/// Button that leaves the balances alone.
+const CANCEL_ID: &str = "reconcile:cancel";
+
+/// Shows the discrepancy `summary` and asks the command author to fix the balances.
+///
+/// Returns `true` if the author pressed "Fix balances". The buttons are removed either
+/// way; on cancel or timeout the prompt says nothing was changed.
+///
+/// # Errors
+/// Returns an error if the prompt can't be sent or edited.
+pub async fn confirm_fix(ctx: poise::Context<'_, BotData, Error>, summary: &str) -> Result<bool> {
+    // Suffix the IDs with the interaction so concurrent prompts don't answer each other
+    let confirm_id = format!("{CONFIRM_ID}:{}", ctx.id());
+    let cancel_id = format!("{CANCEL_ID}:{}", ctx.id());
+    let buttons = vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(&confirm_id)
+            .label("Fix balances")
+            .style(serenity::ButtonStyle::Danger),
+        serenity::CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(serenity::ButtonStyle::Secondary),
+    ])];
+
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content(summary)
+                .components(buttons)
+                .ephemeral(true),
+        )
+        .await?;
+
+    let ids = [confirm_id.clone(), cancel_id];
+    let interaction = serenity::ComponentInteractionCollector::new(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .filter(move |interaction| ids.contains(&interaction.data.custom_id))
+        .timeout(CONFIRM_TIMEOUT)
+        .await;
+
+    let confirmed = match interaction {
+        Some(interaction) => {
+            interaction
+                .create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+                .await?;
+            interaction.data.custom_id == confirm_id
+        }
+        None => false,
+    };
+
+    let content = if confirmed {
+        format!("{summary}\n\n🔧 Fixing balances...")
+    } else {
+        format!("{summary}\n\n🚫 Cancelled; no balances were changed.")
+    };
+    reply
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .content(content)
+                .components(Vec::new()),
+        )
+        .await?;
+
+    Ok(confirmed)
+}
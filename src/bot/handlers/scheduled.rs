@@ -0,0 +1,107 @@
+//! Scheduled spend confirmations.
+//!
+//! The background scheduler runs due spends through [`crate::core::schedule::run_due`]
+//! and hands the results to [`announce_runs`], which tells the member what happened in
+//! the channel they scheduled from (or by DM) and notes executed spends in the envelope's
+//! thread. Retries after the first failure are
+//! silent so a spend waiting on funds doesn't post every hour.
+
+use crate::{
+    bot::notifications,
+    core::{
+        envelope,
+        schedule::{MAX_ATTEMPTS, RunOutcome, ScheduledRun},
+        setup::{self, Currency},
+    },
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use sea_orm::DatabaseConnection;
+
+/// Posts a confirmation for each run that needs one.
+///
+/// Delivery failures are logged rather than returned, so one unreachable member never
+/// blocks the others' confirmations.
+pub async fn announce_runs(http: &serenity::Http, db: &DatabaseConnection, runs: &[ScheduledRun]) {
+    let currency = setup::get_currency_format(db).await.unwrap_or_default();
+    for run in runs {
+        if let RunOutcome::Executed(spend) = &run.outcome {
+            notifications::post_spend_notes_in(http, db, std::slice::from_ref(spend)).await;
+        }
+        let Some(message) = confirmation(db, run, currency).await else {
+            continue;
+        };
+        if let Err(e) = deliver(http, run, message).await {
+            tracing::warn!(
+                "Failed to post confirmation for scheduled spend {}: {e}",
+                run.scheduled.id
+            );
+        }
+    }
+}
+
+/// Builds the confirmation text for a run, or `None` if the run is a silent retry.
+async fn confirmation(
+    db: &DatabaseConnection,
+    run: &ScheduledRun,
+    currency: Currency,
+) -> Option<String> {
+    let scheduled = &run.scheduled;
+    let envelope_name = envelope::get_envelope_by_id(db, scheduled.envelope_id)
+        .await
+        .ok()
+        .flatten()
+        .map_or_else(|| "unknown".to_string(), |env| env.name);
+    let mention = format!("<@{}>", scheduled.user_id);
+    let amount = currency.format(scheduled.amount);
+
+    match &run.outcome {
+        RunOutcome::Executed(spend) => Some(format!(
+            "⏰ {mention} Scheduled spend #{} ran: {amount} from '{envelope_name}' - {} \
+             (Transaction ID: {})",
+            scheduled.id, scheduled.description, spend.id
+        )),
+        RunOutcome::Retrying { error } if scheduled.attempts == 1 => Some(format!(
+            "⚠️ {mention} Scheduled spend #{} ({amount} from '{envelope_name}' - {}) couldn't \
+             run: {error}. It will be retried up to {} more time(s).",
+            scheduled.id,
+            scheduled.description,
+            MAX_ATTEMPTS - scheduled.attempts
+        )),
+        RunOutcome::Retrying { .. } => None,
+        RunOutcome::Failed { error } => Some(format!(
+            "❌ {mention} Scheduled spend #{} ({amount} from '{envelope_name}' - {}) failed \
+             {} times and was given up: {error}",
+            scheduled.id, scheduled.description, scheduled.attempts
+        )),
+    }
+}
+
+/// Sends a confirmation to the scheduling channel, or the member's DMs without one.
+async fn deliver(http: &serenity::Http, run: &ScheduledRun, content: String) -> Result<()> {
+    let message = serenity::CreateMessage::new().content(content);
+
+    if let Some(channel_id) = run
+        .scheduled
+        .channel_id
+        .as_deref()
+        .and_then(|id| id.parse().ok())
+        .map(serenity::ChannelId::new)
+    {
+        channel_id.send_message(http, message).await?;
+        return Ok(());
+    }
+
+    let user_id = &run.scheduled.user_id;
+    let user = user_id
+        .parse()
+        .map(serenity::UserId::new)
+        .map_err(|_| Error::UserNotFound {
+            user_id: user_id.clone(),
+        })?;
+    user.create_dm_channel(http)
+        .await?
+        .send_message(http, message)
+        .await?;
+    Ok(())
+}
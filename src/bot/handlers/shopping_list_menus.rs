@@ -0,0 +1,197 @@
+//! Shopping list check-off menus.
+//!
+//! `/list show` posts an envelope's list with two menus whose custom IDs carry the
+//! envelope ID: one checks items off, the other checks product items off and logs them
+//! as spends at the product's price. Selections arrive through the framework's event
+//! handler, which passes them to [`handle_component`]; the list message is then redrawn.
+
+use crate::{
+    bot::{
+        BotData,
+        handlers::{alerts, reply_privately},
+        notifications,
+    },
+    core::{calendar, envelope, report, setup, shopping_list},
+    entities::envelope as envelope_entity,
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use std::{collections::HashMap, fmt::Write};
+
+/// Custom ID prefix of the list menus.
+const PREFIX: &str = "shopping_list:";
+/// Menu action that only checks items off.
+const CHECK: &str = "check";
+/// Menu action that checks items off and logs them as spends.
+const BUY: &str = "buy";
+
+/// Discord select menus hold at most 25 options.
+const MAX_OPTIONS: usize = 25;
+/// Longest list text before the remaining items are left out, under Discord's limit.
+const MAX_CONTENT_LENGTH: usize = 1800;
+
+/// Renders an envelope's list as message text and its check-off menus.
+///
+/// # Errors
+/// Returns an error if the list or the products can't be loaded.
+pub async fn render(
+    data: &BotData,
+    envelope: &envelope_entity::Model,
+) -> Result<(String, Vec<serenity::CreateActionRow>)> {
+    let db = &data.database;
+    let items = shopping_list::get_items(db, envelope.id).await?;
+    let products = data.cache.active_products(db).await?;
+    let prices: HashMap<i64, f64> = products.iter().map(|prod| (prod.id, prod.price)).collect();
+    let currency = setup::get_currency_format(db).await?;
+
+    let mut content = format!("🛒 **Shopping list: {}**\n", envelope.name);
+    if items.is_empty() {
+        content.push_str("Nothing on the list. Add items with `/list add`.");
+    }
+    for (shown, item) in items.iter().enumerate() {
+        if content.len() > MAX_CONTENT_LENGTH {
+            writeln!(content, "…and {} more", items.len() - shown)?;
+            break;
+        }
+        let price = item
+            .product_id
+            .and_then(|id| prices.get(&id))
+            .map_or_else(String::new, |price| {
+                format!(" ({} each)", currency.format(*price))
+            });
+        if item.checked_at.is_some() {
+            let logged = item
+                .transaction_id
+                .map_or_else(String::new, |id| format!(" - logged as #{id}"));
+            writeln!(content, "☑️ ~~{} ×{}~~{logged}", item.name, item.quantity)?;
+        } else {
+            writeln!(content, "⬜ {} ×{}{price}", item.name, item.quantity)?;
+        }
+    }
+
+    let open: Vec<_> = items
+        .iter()
+        .filter(|item| item.checked_at.is_none())
+        .take(MAX_OPTIONS)
+        .collect();
+    let buyable: Vec<_> = open
+        .iter()
+        .filter(|item| item.product_id.is_some_and(|id| prices.contains_key(&id)))
+        .copied()
+        .collect();
+
+    let mut rows = Vec::new();
+    for (action, placeholder, items) in [
+        (CHECK, "Check off…", &open),
+        (BUY, "Check off and log the spend…", &buyable),
+    ] {
+        if items.is_empty() {
+            continue;
+        }
+        let options = items
+            .iter()
+            .map(|item| {
+                let label: String = format!("{} ×{}", item.name, item.quantity)
+                    .chars()
+                    .take(100)
+                    .collect();
+                serenity::CreateSelectMenuOption::new(label, item.id.to_string())
+            })
+            .collect();
+        let max_values = u8::try_from(items.len()).unwrap_or(u8::MAX);
+        rows.push(serenity::CreateActionRow::SelectMenu(
+            serenity::CreateSelectMenu::new(
+                format!("{PREFIX}{action}:{}", envelope.id),
+                serenity::CreateSelectMenuKind::String { options },
+            )
+            .placeholder(placeholder)
+            .max_values(max_values),
+        ));
+    }
+
+    Ok((content, rows))
+}
+
+/// Handles a selection in a shopping list menu. Other component interactions are ignored.
+///
+/// Items that can't be checked off (for example because the spend is refused) are
+/// listed in a private reply; the rest are checked off and the list is redrawn.
+///
+/// # Errors
+/// Returns an error if a database query or the Discord response fails.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &BotData,
+) -> Result<()> {
+    let Some((action, id)) = interaction
+        .data
+        .custom_id
+        .strip_prefix(PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+    else {
+        return Ok(());
+    };
+    let Ok(envelope_id) = id.parse::<i64>() else {
+        return Ok(());
+    };
+    let serenity::ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind
+    else {
+        return Ok(());
+    };
+
+    let db = &data.database;
+    let user_id = interaction.user.id.to_string();
+    let Some(env) = envelope::get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted && report::can_view(env, &user_id))
+    else {
+        return reply_privately(
+            ctx,
+            interaction,
+            "❌ This list's envelope is no longer available.",
+        )
+        .await;
+    };
+
+    let today = calendar::today(db).await?;
+    let currency = setup::get_currency_format(db).await?;
+    let mut spends = Vec::new();
+    let mut problems = Vec::new();
+    for item_id in values.iter().filter_map(|value| value.parse::<i64>().ok()) {
+        match shopping_list::check_off(db, &env, item_id, &user_id, action == BUY, today).await {
+            Ok(checked) => spends.extend(checked.spend),
+            Err(e @ (Error::Database(_) | Error::Discord(_))) => return Err(e),
+            Err(Error::Config { message }) => problems.push(format!("• {message}")),
+            Err(e) => problems.push(format!("• Item #{item_id}: {}", e.user_message(currency))),
+        }
+    }
+
+    let (content, rows) = render(data, &env).await?;
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(rows),
+            ),
+        )
+        .await?;
+    if !problems.is_empty() {
+        interaction
+            .create_followup(
+                ctx,
+                serenity::CreateInteractionResponseFollowup::new()
+                    .content(format!("❌ Not checked off:\n{}", problems.join("\n")))
+                    .ephemeral(true),
+            )
+            .await?;
+    }
+    if !spends.is_empty() {
+        alerts::after_balance_change_in(ctx, db, interaction.channel_id, &[env.id]).await;
+        notifications::post_spend_notes_in(&ctx.http, db, &spends).await;
+    }
+
+    Ok(())
+}
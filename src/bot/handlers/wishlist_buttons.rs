@@ -0,0 +1,360 @@
+//! Wishlist button handling.
+//!
+//! `/wishlist propose` posts a message with Approve and Decline buttons whose custom IDs
+//! carry the proposal ID. `/spend` does the same with [`hold_reply`] for a spend held by
+//! the large transaction threshold, pinging every other member. Button clicks arrive
+//! through the framework's event handler, which passes them to [`handle_component`]; the
+//! decision itself is made by [`crate::core::wishlist`]. Declined and expired holds are
+//! reported to the member who made the spend through every configured notifier.
+
+use crate::{
+    bot::{
+        BotData,
+        handlers::{alerts, reply_privately},
+        notifications,
+    },
+    config::users,
+    core::{
+        envelope,
+        notify::{self, Audience, Notification, Notifier},
+        setup::{self, Currency},
+        wishlist::{self, PurchaseResponse},
+    },
+    entities::pending_purchase,
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use sea_orm::DatabaseConnection;
+
+/// Custom ID prefix of the Approve button.
+const APPROVE_PREFIX: &str = "wishlist:approve:";
+/// Custom ID prefix of the Decline button.
+const DECLINE_PREFIX: &str = "wishlist:decline:";
+
+/// Builds the Approve/Decline buttons for a proposal.
+#[must_use]
+pub fn buttons(purchase_id: i64) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{APPROVE_PREFIX}{purchase_id}"))
+            .label("Approve")
+            .style(serenity::ButtonStyle::Success),
+        serenity::CreateButton::new(format!("{DECLINE_PREFIX}{purchase_id}"))
+            .label("Decline")
+            .style(serenity::ButtonStyle::Danger),
+    ])]
+}
+
+/// Formats the message asking the approver to respond to a proposal, with the amount in
+/// `currency`.
+#[must_use]
+pub fn proposal_message(
+    purchase: &pending_purchase::Model,
+    envelope_name: &str,
+    currency: Currency,
+) -> String {
+    let approver = purchase
+        .approver_id
+        .as_deref()
+        .map_or_else(String::new, |approver_id| format!("<@{approver_id}>, "));
+    format!(
+        "🛍️ {approver}{} wants to spend {} from '{envelope_name}' - {}\n\
+        Expires <t:{}:R>.",
+        users::get_user_display_name(&purchase.proposer_id),
+        currency.format(purchase.amount),
+        purchase.description,
+        purchase.expires_at.timestamp()
+    )
+}
+
+/// Builds the message announcing a held spend, pinging every other configured member.
+#[must_use]
+pub fn hold_reply(
+    hold: &pending_purchase::Model,
+    envelope_name: &str,
+    currency: Currency,
+) -> poise::CreateReply {
+    let approvers: Vec<u64> = users::get_user_nicknames()
+        .into_keys()
+        .filter(|user_id| *user_id != hold.proposer_id)
+        .filter_map(|user_id| user_id.parse().ok())
+        .collect();
+    let mentions = if approvers.is_empty() {
+        "Another member".to_string()
+    } else {
+        approvers
+            .iter()
+            .map(|id| format!("<@{id}>"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    poise::CreateReply::default()
+        .content(format!(
+            "✋ {}'s spend of {} from '{envelope_name}' - {} is over the large transaction \
+             threshold.\n{mentions}, please approve or decline it. Expires <t:{}:R>.",
+            users::get_user_display_name(&hold.proposer_id),
+            currency.format(hold.amount),
+            hold.description,
+            hold.expires_at.timestamp()
+        ))
+        .allowed_mentions(serenity::CreateAllowedMentions::new().users(approvers))
+        .components(buttons(hold.id))
+}
+
+/// Handles a click on a wishlist button. Other component interactions are ignored.
+///
+/// # Errors
+/// Returns an error if the database update or the Discord response fails.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    data: &BotData,
+) -> Result<()> {
+    let custom_id = interaction.data.custom_id.as_str();
+    let (approve, id) = if let Some(id) = custom_id.strip_prefix(APPROVE_PREFIX) {
+        (true, id)
+    } else if let Some(id) = custom_id.strip_prefix(DECLINE_PREFIX) {
+        (false, id)
+    } else {
+        return Ok(());
+    };
+    let Ok(purchase_id) = id.parse::<i64>() else {
+        return Ok(());
+    };
+
+    let db = &data.database;
+    let currency = setup::get_currency_format(db).await?;
+    let user_id = interaction.user.id.to_string();
+    let response =
+        match wishlist::respond_to_purchase(db, purchase_id, &user_id, approve, chrono::Utc::now())
+            .await
+        {
+            Ok(response) => response,
+            Err(Error::InsufficientFunds { current, required }) => {
+                return reply_privately(
+                    ctx,
+                    interaction,
+                    &format!(
+                        "❌ Can't approve yet: the envelope has {}, the purchase needs {}.",
+                        currency.format(current),
+                        currency.format(required)
+                    ),
+                )
+                .await;
+            }
+            Err(e @ Error::DailyLimitExceeded { .. }) => {
+                let message = format!("❌ Can't approve yet: {}.", e.user_message(currency));
+                return reply_privately(ctx, interaction, &message).await;
+            }
+            Err(Error::PurchaseNotFound { .. }) => {
+                return update_message(ctx, interaction, "❌ This proposal no longer exists.")
+                    .await;
+            }
+            Err(e) => return Err(e),
+        };
+
+    reply_to_response(ctx, interaction, db, response, currency).await
+}
+
+/// What a pending purchase is called in messages: a held spend or a wishlist purchase.
+const fn noun(purchase: &pending_purchase::Model) -> &'static str {
+    if wishlist::is_hold(purchase) {
+        "spend"
+    } else {
+        "purchase"
+    }
+}
+
+/// Updates the proposal message, or replies privately, to reflect a response.
+async fn reply_to_response(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    db: &DatabaseConnection,
+    response: PurchaseResponse,
+    currency: Currency,
+) -> Result<()> {
+    let responder = users::get_user_display_name(&interaction.user.id.to_string());
+    match response {
+        PurchaseResponse::Approved {
+            purchase,
+            transaction,
+        } => {
+            update_message(
+                ctx,
+                interaction,
+                &format!(
+                    "✅ {responder} approved {}'s {}: {} - {} (Transaction ID: {})",
+                    users::get_user_display_name(&purchase.proposer_id),
+                    noun(&purchase),
+                    currency.format(purchase.amount),
+                    purchase.description,
+                    transaction.id
+                ),
+            )
+            .await?;
+            alerts::after_balance_change_in(
+                ctx,
+                db,
+                interaction.channel_id,
+                &[purchase.envelope_id],
+            )
+            .await;
+            notifications::post_spend_notes_in(&ctx.http, db, &[*transaction]).await;
+        }
+        PurchaseResponse::Declined(purchase) => {
+            let body = format!(
+                "🚫 {responder} declined {}'s {}: {} - {}",
+                users::get_user_display_name(&purchase.proposer_id),
+                noun(&purchase),
+                currency.format(purchase.amount),
+                purchase.description
+            );
+            update_message(ctx, interaction, &body).await?;
+            if wishlist::is_hold(&purchase) {
+                tell_requester(&ctx.http, db, &purchase, "Large spend declined", body).await;
+            }
+        }
+        PurchaseResponse::Expired(purchase) => {
+            let body = expired_message(&purchase, currency);
+            update_message(ctx, interaction, &body).await?;
+            if wishlist::is_hold(&purchase) {
+                tell_requester(&ctx.http, db, &purchase, "Large spend expired", body).await;
+            }
+        }
+        PurchaseResponse::AlreadyDecided(purchase) => {
+            reply_privately(
+                ctx,
+                interaction,
+                &format!(
+                    "ℹ️ This {} was already {}.",
+                    noun(&purchase),
+                    purchase.status
+                ),
+            )
+            .await?;
+        }
+        PurchaseResponse::NotApprover(purchase) => {
+            reply_privately(ctx, interaction, &not_approver_message(&purchase)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Tells a member who clicked a button that the proposal isn't theirs to answer.
+fn not_approver_message(purchase: &pending_purchase::Model) -> String {
+    purchase.approver_id.as_deref().map_or_else(
+        || "❌ Another member has to approve your spend.".to_string(),
+        |approver_id| {
+            format!(
+                "❌ Only {} can respond to this proposal.",
+                users::get_user_display_name(approver_id)
+            )
+        },
+    )
+}
+
+/// Tells the members who made the given held spends that nobody answered in time.
+///
+/// Wishlist proposals among `expired` are skipped. Delivery failures are logged rather
+/// than returned, so one unreachable member never blocks the others' notices.
+pub async fn announce_expired(
+    http: &serenity::Http,
+    db: &DatabaseConnection,
+    expired: &[pending_purchase::Model],
+) {
+    let currency = match setup::get_currency_format(db).await {
+        Ok(currency) => currency,
+        Err(e) => {
+            tracing::warn!("Failed to read the currency: {e}");
+            Currency::default()
+        }
+    };
+    for hold in expired
+        .iter()
+        .filter(|purchase| wishlist::is_hold(purchase))
+    {
+        let body = expired_message(hold, currency);
+        tell_requester(http, db, hold, "Large spend expired", body).await;
+    }
+}
+
+/// Describes a proposal or held spend that expired without an answer.
+fn expired_message(purchase: &pending_purchase::Model, currency: Currency) -> String {
+    if wishlist::is_hold(purchase) {
+        format!(
+            "⌛ Nobody approved {}'s spend in time, so it wasn't recorded: {} - {}",
+            users::get_user_display_name(&purchase.proposer_id),
+            currency.format(purchase.amount),
+            purchase.description
+        )
+    } else {
+        format!(
+            "⌛ This proposal expired: {} - {}",
+            currency.format(purchase.amount),
+            purchase.description
+        )
+    }
+}
+
+/// Notifies the member who made a held spend, by DM and through the external notifiers.
+async fn tell_requester(
+    http: &serenity::Http,
+    db: &DatabaseConnection,
+    hold: &pending_purchase::Model,
+    subject: &str,
+    body: String,
+) {
+    let envelope_name = envelope::get_envelope_by_id(db, hold.envelope_id)
+        .await
+        .ok()
+        .flatten()
+        .map_or_else(|| "unknown".to_string(), |env| env.name);
+    let notification = Notification {
+        subject: format!("{subject} in {envelope_name}"),
+        body,
+        audience: Audience::Member {
+            user_id: hold.proposer_id.clone(),
+        },
+        mention: None,
+    };
+
+    if notify::discord_enabled() {
+        // Personal notifications go by DM, so the channel is only a formality
+        let channel = hold
+            .channel_id
+            .as_deref()
+            .and_then(|id| id.parse::<u64>().ok())
+            .filter(|id| *id > 0)
+            .map(serenity::ChannelId::new)
+            .unwrap_or_default();
+        let discord = alerts::DiscordNotifier::new(http, db, channel);
+        if let Err(e) = discord.send(&notification).await {
+            tracing::warn!(
+                "Failed to tell {} about hold {}: {e}",
+                hold.proposer_id,
+                hold.id
+            );
+        }
+    }
+    notify::send_external(notification);
+}
+
+/// Replaces the proposal message and removes its buttons.
+async fn update_message(
+    ctx: &serenity::Context,
+    interaction: &serenity::ComponentInteraction,
+    content: &str,
+) -> Result<()> {
+    interaction
+        .create_response(
+            ctx,
+            serenity::CreateInteractionResponse::UpdateMessage(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(Vec::new()),
+            ),
+        )
+        .await?;
+    Ok(())
+}
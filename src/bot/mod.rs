@@ -5,10 +5,32 @@
 
 /// Discord command implementations (envelope, transaction, product, general)
 pub mod commands;
+/// Correlation IDs attached to the logs of each command invocation
+pub mod correlation;
+/// Command confirmations retried through Discord outages
+pub mod delivery;
 /// Discord interaction handlers (autocomplete, etc.)
 pub mod handlers;
+/// Spend notes posted to envelope threads
+pub mod notifications;
+/// Checks on who a command may act for
+pub mod permissions;
+/// Report models from the core layer rendered as Discord embeds
+pub mod render;
+/// Confirmations rendered compact or detailed per member
+pub mod responses;
 
+use crate::{
+    bot::handlers::envelope_pages::EnvelopePages,
+    config::cooldowns::CommandCooldowns,
+    core::{
+        cache::Cache,
+        fx::{RateProvider, StaticRateProvider},
+    },
+};
+use chrono::{DateTime, Utc};
 use sea_orm::DatabaseConnection;
+use std::time::Duration;
 
 /// Shared data available to all bot commands.
 /// This structure holds the database connection and any other global state
@@ -16,16 +38,67 @@ use sea_orm::DatabaseConnection;
 pub struct BotData {
     /// Database connection for all database operations
     pub database: DatabaseConnection,
+    /// Exchange rate source for foreign-currency transactions
+    pub rates: Box<dyn RateProvider>,
+    /// Per-user cooldowns for expensive commands
+    pub cooldowns: CommandCooldowns,
+    /// When the bot started, for uptime in `/status`
+    pub started_at: DateTime<Utc>,
+    /// In-memory cache of hot envelope and product reads
+    pub cache: Cache,
+    /// Paging state of open `/envelope_info` messages
+    pub envelope_pages: EnvelopePages,
+    /// Whether the bot connected with the Server Members intent, needed to see members
+    /// given the budget role
+    pub members_intent: bool,
 }
 
 impl BotData {
     /// Creates a new `BotData` instance with the given database connection.
     /// This is typically called during bot initialization to set up the
-    /// shared context for all commands.
+    /// shared context for all commands. Exchange rates default to the built-in
+    /// static table and cooldowns to their built-in defaults.
     #[must_use]
-    pub const fn new(database: DatabaseConnection) -> Self {
-        Self { database }
+    pub fn new(database: DatabaseConnection) -> Self {
+        Self {
+            database,
+            rates: Box::new(StaticRateProvider::default()),
+            cooldowns: CommandCooldowns::default(),
+            started_at: Utc::now(),
+            cache: Cache::default(),
+            envelope_pages: EnvelopePages::default(),
+            members_intent: false,
+        }
     }
+
+    /// Replaces the exchange rate provider used for foreign-currency transactions.
+    #[must_use]
+    pub fn with_rate_provider(mut self, rates: impl RateProvider + 'static) -> Self {
+        self.rates = Box::new(rates);
+        self
+    }
+
+    /// Replaces the per-user command cooldowns.
+    #[must_use]
+    pub const fn with_cooldowns(mut self, cooldowns: CommandCooldowns) -> Self {
+        self.cooldowns = cooldowns;
+        self
+    }
+
+    /// Records whether the bot connected with the Server Members intent.
+    #[must_use]
+    pub const fn with_members_intent(mut self, members_intent: bool) -> Self {
+        self.members_intent = members_intent;
+        self
+    }
+}
+
+/// Formats the friendly reply shown when a user runs a command during its cooldown.
+#[must_use]
+pub fn cooldown_message(command: &str, remaining: Duration) -> String {
+    // Round up so users are never told to wait 0s
+    let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    format!("⏳ `/{command}` is cooling down. Try again in {secs}s.")
 }
 
 pub use commands::*;
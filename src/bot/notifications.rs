@@ -0,0 +1,108 @@
+//! Envelope thread notifications - Spend notes posted to each envelope's Discord thread.
+//!
+//! An envelope can be linked to a thread with `/envelope_thread`. Commands that record
+//! spends call [`post_spend_notes`] after replying (other interactions, such as wishlist
+//! buttons and scheduled spends, use [`post_spend_notes_in`]), and a one-line note is
+//! posted in the thread of each linked envelope. All serenity calls for envelope threads
+//! live in this module.
+
+use crate::{
+    bot::BotData,
+    config::users,
+    core::{
+        envelope,
+        setup::{self, Currency},
+    },
+    entities::{envelope as envelope_entity, transaction},
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use sea_orm::DatabaseConnection;
+
+/// Creates a public thread for an envelope in `channel`.
+///
+/// # Errors
+/// Returns an error if Discord refuses to create the thread (e.g. missing permissions or
+/// a channel that can't hold threads).
+pub async fn create_envelope_thread(
+    http: &serenity::Http,
+    channel: serenity::ChannelId,
+    envelope_name: &str,
+) -> Result<serenity::ChannelId> {
+    let builder = serenity::CreateThread::new(format!("💰 {envelope_name}"))
+        .kind(serenity::ChannelType::PublicThread)
+        .auto_archive_duration(serenity::AutoArchiveDuration::OneWeek);
+    let thread = channel.create_thread(http, builder).await?;
+    Ok(thread.id)
+}
+
+/// Posts a note for each spend in its envelope's linked thread.
+///
+/// Failures are logged rather than returned, so an unreachable thread never fails the
+/// command that recorded the spend.
+pub async fn post_spend_notes(
+    ctx: poise::Context<'_, BotData, Error>,
+    transactions: &[transaction::Model],
+) {
+    post_spend_notes_in(ctx.http(), &ctx.data().database, transactions).await;
+}
+
+/// Like [`post_spend_notes`], for interactions outside a command context.
+pub async fn post_spend_notes_in(
+    http: &serenity::Http,
+    db: &DatabaseConnection,
+    transactions: &[transaction::Model],
+) {
+    for tx in transactions {
+        if let Err(e) = post_spend_note(http, db, tx).await {
+            tracing::warn!(
+                "Failed to post spend note for transaction {} to envelope {}'s thread: {e}",
+                tx.id,
+                tx.envelope_id
+            );
+        }
+    }
+}
+
+/// Posts one note, if the envelope has a thread.
+///
+/// Private envelopes never post notes, since threads are visible to the whole server.
+async fn post_spend_note(
+    http: &serenity::Http,
+    db: &DatabaseConnection,
+    tx: &transaction::Model,
+) -> Result<()> {
+    let Some(env) = envelope::get_envelope_by_id(db, tx.envelope_id).await? else {
+        return Ok(());
+    };
+    let Some(thread_id) = env
+        .thread_id
+        .as_deref()
+        .and_then(|id| id.parse().ok())
+        .map(serenity::ChannelId::new)
+    else {
+        return Ok(());
+    };
+    if env.is_private {
+        return Ok(());
+    }
+
+    let currency = setup::get_currency_format(db).await?;
+    let message = serenity::CreateMessage::new()
+        .content(spend_note(&env, tx, currency))
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+    thread_id.send_message(http, message).await?;
+    Ok(())
+}
+
+/// Formats the note, e.g. `💸 alice spent $12.50 - Weekly shop (balance $87.50, #42)`.
+fn spend_note(env: &envelope_entity::Model, tx: &transaction::Model, currency: Currency) -> String {
+    format!(
+        "💸 {} spent {} - {} (balance {}, #{})",
+        users::get_user_display_name(&tx.user_id),
+        currency.format(-tx.amount),
+        tx.description,
+        currency.format(env.balance),
+        tx.id
+    )
+}
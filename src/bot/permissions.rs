@@ -0,0 +1,121 @@
+//! Permissions - Who a command may act for.
+//!
+//! Commands with a `user` option resolve it through [`target_user`]: members may only
+//! name themselves, while admins (the server owner, or members with a role that has the
+//! Administrator permission) may act on anyone's individual envelopes. Prefix commands
+//! don't carry the author's permissions, so [`is_admin`] checks their roles against the
+//! server's, from the cache filled by the Guilds intent or fetched when it misses.
+//!
+//! Shared envelopes restricted to certain spenders (see
+//! [`crate::core::envelope_permission`]) are checked with [`check_spender`] before a
+//! command asks any other questions about a spend.
+
+use crate::{
+    bot::BotData,
+    core::envelope_permission,
+    entities::envelope,
+    errors::{Error, Result},
+};
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+
+/// Checks whether the command author is a server admin.
+///
+/// Always `false` outside a server, or if the author's roles can't be looked up.
+pub async fn is_admin(ctx: poise::Context<'_, BotData, Error>) -> bool {
+    let Some(member) = ctx.author_member().await else {
+        return false;
+    };
+    // Slash commands carry the author's permissions; prefix commands are checked against
+    // the server's roles, from the cache or else fetched
+    if let Some(permissions) = member.permissions {
+        return permissions.administrator();
+    }
+    if let Some(is_admin) = ctx
+        .guild()
+        .map(|guild| holds_admin(&member, guild.owner_id, &guild.roles))
+    {
+        return is_admin;
+    }
+    match member.guild_id.to_partial_guild(ctx).await {
+        Ok(guild) => holds_admin(&member, guild.owner_id, &guild.roles),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to fetch server {} for an admin check: {e}",
+                member.guild_id
+            );
+            false
+        }
+    }
+}
+
+/// Whether `member` owns the server or has a role with the Administrator permission.
+fn holds_admin(
+    member: &serenity::Member,
+    owner_id: serenity::UserId,
+    roles: &HashMap<serenity::RoleId, serenity::Role>,
+) -> bool {
+    owner_id == member.user.id
+        || member
+            .roles
+            .iter()
+            .filter_map(|role_id| roles.get(role_id))
+            .any(|role| role.permissions.administrator())
+}
+
+/// Resolves the user ID a command acts for: `user` if given, otherwise the author.
+///
+/// Returns `None` after telling the author if they named another member without being
+/// an admin, in which case the command should stop.
+///
+/// # Errors
+/// Returns an error if the refusal can't be sent.
+pub async fn target_user(
+    ctx: poise::Context<'_, BotData, Error>,
+    user: Option<&serenity::User>,
+) -> Result<Option<String>> {
+    let author_id = ctx.author().id;
+    match user {
+        Some(user) if user.id != author_id && !is_admin(ctx).await => {
+            ctx.send(
+                poise::CreateReply::default()
+                    .content(
+                        "❌ Only admins can act on another member's envelopes. Leave `user` \
+                         empty to use your own.",
+                    )
+                    .ephemeral(true),
+            )
+            .await?;
+            Ok(None)
+        }
+        Some(user) => Ok(Some(user.id.to_string())),
+        None => Ok(Some(author_id.to_string())),
+    }
+}
+
+/// Checks that `user_id` may spend from `envelope`.
+///
+/// Returns `false` after telling the author who may spend from it instead, in which
+/// case the command should stop.
+///
+/// # Errors
+/// Returns an error if the spenders can't be looked up or the refusal can't be sent.
+pub async fn check_spender(
+    ctx: poise::Context<'_, BotData, Error>,
+    envelope: &envelope::Model,
+    user_id: &str,
+) -> Result<bool> {
+    match envelope_permission::check_can_spend(&ctx.data().database, envelope, user_id).await {
+        Ok(()) => Ok(true),
+        Err(denied @ Error::SpendNotPermitted { .. }) => {
+            ctx.send(
+                poise::CreateReply::default()
+                    .content(format!("🔒 {denied}."))
+                    .ephemeral(true),
+            )
+            .await?;
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,205 @@
+//! Rendering of core report models as Discord embeds.
+//!
+//! [`crate::core::report`] computes every figure in a [`ReportModel`]; this module only
+//! turns the model into text, so other frontends can format the same data their own way.
+
+#[cfg(feature = "charts")]
+use crate::core::charts;
+use crate::{
+    config,
+    core::report::{self, EnvelopeFigures, EnvelopeLine, ReportModel, Theme},
+    errors::Result,
+};
+use poise::serenity_prelude as serenity;
+use std::fmt::Write;
+
+/// Builds the `/report` embed.
+///
+/// `labels` names each of the model's envelope lines, in order, as the field titles.
+///
+/// # Errors
+/// Returns an error if formatting the text fails.
+pub fn report_embed(
+    model: &ReportModel,
+    labels: &[String],
+    theme: Theme,
+) -> Result<serenity::CreateEmbed> {
+    let mut fields = Vec::new();
+    for (line, label) in model.envelopes.iter().zip(labels) {
+        let value = match line {
+            EnvelopeLine::Visible(figures) => envelope_field(figures, model, theme)?,
+            EnvelopeLine::Hidden(_) => format!("🔒 *{}*", report::HIDDEN_LABEL),
+        };
+        fields.push((label.clone(), value, false)); // false = not inline
+    }
+
+    // Running balance of money owed between members
+    if !model.ious.is_empty() {
+        let mut value = String::new();
+        for net in &model.ious {
+            writeln!(
+                &mut value,
+                "{} owes {} **{}**",
+                config::users::get_user_display_name(&net.debtor_id),
+                config::users::get_user_display_name(&net.creditor_id),
+                model.currency.format(net.amount)
+            )?;
+        }
+        fields.push(("🤝 IOUs".to_string(), value, false));
+    }
+
+    // Spending by the locations recorded with `/spend location:`
+    if !model.locations.is_empty() {
+        let mut value = String::new();
+        for spending in &model.locations {
+            writeln!(
+                &mut value,
+                "{} **{}** ({} transaction(s))",
+                spending.location,
+                model.currency.format(spending.spent),
+                spending.transaction_count
+            )?;
+        }
+        fields.push(("📍 Where the Money Went".to_string(), value, false));
+    }
+
+    let count = model.envelopes.len();
+    Ok(serenity::CreateEmbed::default()
+        .title("📊 Full Envelope Report")
+        .description(summary_text(model, theme)?)
+        .color(theme.color(report::EmbedColor::Report))
+        .fields(fields)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "EnvelopeBuddy v0.2.0 | {count} envelope{}",
+            if count == 1 { "" } else { "s" }
+        ))))
+}
+
+/// Bars of the spend vs allocation chart attached to `/report`, one per visible envelope.
+#[cfg(feature = "charts")]
+#[must_use]
+pub fn chart_bars(model: &ReportModel, labels: &[String]) -> Vec<charts::ChartBar> {
+    model
+        .envelopes
+        .iter()
+        .zip(labels)
+        .filter_map(|(line, label)| match line {
+            EnvelopeLine::Visible(figures) => Some(charts::ChartBar {
+                label: label.clone(),
+                spent: figures.spent,
+                allocation: figures.envelope.allocation,
+            }),
+            EnvelopeLine::Hidden(_) => None,
+        })
+        .collect()
+}
+
+/// The date line and household totals above the envelopes.
+fn summary_text(model: &ReportModel, theme: Theme) -> Result<String> {
+    let summary = &model.summary;
+    let currency = model.currency;
+    let mut text = format!(
+        "As of: {} (Day {}/{} of budget month)\n\n",
+        model.today.format("%Y-%m-%d"),
+        model.cycle.day_number(model.today),
+        model.cycle.days()
+    );
+    writeln!(&mut text, "**🏠 Household Summary**")?;
+    writeln!(
+        &mut text,
+        "**Allocated:** {} | **Spent:** {} ({:.1}%) | **Remaining:** {}",
+        currency.format(summary.total_allocated),
+        currency.format(summary.total_spent),
+        summary.spent_percent(),
+        currency.format(summary.total_remaining)
+    )?;
+    write!(
+        &mut text,
+        "**Pace:** {} (expected {:.1}%)",
+        theme.status(model.status),
+        model.expected_percent
+    )?;
+    Ok(text)
+}
+
+/// One envelope's field: balance, spending, pace, and any savings and weekly lines.
+fn envelope_field(figures: &EnvelopeFigures, model: &ReportModel, theme: Theme) -> Result<String> {
+    let env = &figures.envelope;
+    let currency = model.currency;
+    let mut value = String::new();
+    writeln!(
+        &mut value,
+        "**{}:** {} / {}",
+        if figures.pending.is_some() {
+            "Available"
+        } else {
+            "Balance"
+        },
+        currency.format(env.balance),
+        currency.format(env.allocation)
+    )?;
+    if let Some(pending) = figures.pending {
+        writeln!(
+            &mut value,
+            "**Pending:** {} | **Cleared Balance:** {}",
+            currency.format(-pending),
+            currency.format(env.balance - pending)
+        )?;
+    }
+    writeln!(
+        &mut value,
+        "**Spent:** {} ({:.1}%)",
+        currency.format(figures.spent.abs()),
+        figures.spent_percent.abs()
+    )?;
+    writeln!(
+        &mut value,
+        "**Expected Pace:** {} ({:.1}%)",
+        currency.format(figures.expected_spent),
+        model.expected_percent
+    )?;
+    writeln!(
+        &mut value,
+        "**Progress:** {} {:.1}%",
+        theme.progress_bar(figures.progress, Some(10)),
+        figures.progress
+    )?;
+    writeln!(&mut value, "**Status:** {}", theme.status(figures.status))?;
+
+    if let Some(goal) = env.goal {
+        writeln!(
+            &mut value,
+            "**Goal:** {}",
+            report::format_goal_progress(env.balance, goal, currency)
+        )?;
+    }
+    if let Some(earned) = figures.interest {
+        writeln!(
+            &mut value,
+            "**Interest Earned:** {}",
+            currency.format(earned)
+        )?;
+    }
+
+    // Week-by-week spending, each week compared to its share of the allocation
+    if !figures.weeks.is_empty() {
+        writeln!(&mut value, "**By Week:**")?;
+    }
+    for (number, (week, status)) in figures.weeks.iter().enumerate() {
+        let label = if week.range.to >= model.today {
+            "This week".to_string()
+        } else {
+            format!("Week {}", number + 1)
+        };
+        writeln!(
+            &mut value,
+            "{label} ({} - {}): {} / {} {}",
+            week.range.from.format("%b %d"),
+            week.range.to.format("%b %d"),
+            currency.format(week.spent),
+            currency.format(week.share),
+            theme.status(*status)
+        )?;
+    }
+    Ok(value)
+}
@@ -0,0 +1,240 @@
+//! Confirmation responses - One confirmation, shown the way each member prefers.
+//!
+//! Commands that confirm a spend or deposit describe it once as a [`Confirmation`] and
+//! hand it to [`confirm`], which looks up the author's verbosity (see
+//! [`crate::core::preferences`]). Compact confirmations are a single line of text, which
+//! fits in a mobile notification; detailed ones are an embed with the fields and a
+//! mini-report of the envelope's balance, styled by the household's [`Theme`]. Both are
+//! delivered through [`delivery`].
+
+use crate::{
+    bot::{BotData, delivery},
+    core::{
+        preferences::{self, Verbosity},
+        report::{self, EmbedColor, Theme},
+        setup::{self, Currency},
+    },
+    entities::envelope,
+    errors::Error,
+};
+use poise::serenity_prelude as serenity;
+
+/// A command confirmation, rendered compact or detailed when sent.
+#[derive(Debug, Clone)]
+pub struct Confirmation {
+    title: String,
+    line: String,
+    fields: Vec<(String, String, bool)>,
+    notes: Vec<String>,
+    envelope: Option<envelope::Model>,
+    components: Vec<serenity::CreateActionRow>,
+}
+
+impl Confirmation {
+    /// Starts a confirmation with the embed title and the one-line compact text.
+    #[must_use]
+    pub fn new(title: impl Into<String>, line: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            line: line.into(),
+            fields: Vec::new(),
+            notes: Vec::new(),
+            envelope: None,
+            components: Vec::new(),
+        }
+    }
+
+    /// Adds an embed field, shown only in detailed confirmations.
+    #[must_use]
+    pub fn field(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+        inline: bool,
+    ) -> Self {
+        self.fields.push((name.into(), value.into(), inline));
+        self
+    }
+
+    /// Adds a note, such as a warning, shown under the confirmation in both forms.
+    ///
+    /// Blank notes are ignored, so optional warnings can be passed as they are.
+    #[must_use]
+    pub fn note(mut self, note: impl AsRef<str>) -> Self {
+        let note = note.as_ref().trim();
+        if !note.is_empty() {
+            self.notes.push(note.to_string());
+        }
+        self
+    }
+
+    /// Adds a mini-report of the envelope's balance to detailed confirmations.
+    #[must_use]
+    pub fn balance(mut self, envelope: envelope::Model) -> Self {
+        self.envelope = Some(envelope);
+        self
+    }
+
+    /// Adds buttons under the confirmation in both forms.
+    #[must_use]
+    pub fn components(mut self, components: Vec<serenity::CreateActionRow>) -> Self {
+        self.components.extend(components);
+        self
+    }
+
+    /// Renders the compact form: the line, followed by any notes.
+    #[must_use]
+    pub fn compact(&self) -> String {
+        std::iter::once(self.line.as_str())
+            .chain(self.notes.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the detailed form as an embed in `theme`, writing the balance in `currency`.
+    pub fn detailed(&self, theme: Theme, currency: Currency) -> serenity::CreateEmbed {
+        let mut embed = serenity::CreateEmbed::default()
+            .title(&self.title)
+            .color(theme.color(EmbedColor::Accent))
+            .fields(self.fields.clone());
+        if let Some(envelope) = &self.envelope {
+            let balance_emoji = if envelope.balance >= 0.0 {
+                "💰"
+            } else {
+                "⚠️"
+            };
+            embed = embed
+                .field(
+                    format!("{balance_emoji} Balance"),
+                    format!(
+                        "{} / {}",
+                        currency.format(envelope.balance),
+                        currency.format(envelope.allocation)
+                    ),
+                    true,
+                )
+                .field(
+                    "Progress",
+                    progress_bar(theme, envelope.balance, envelope.allocation),
+                    false,
+                );
+        }
+        if !self.notes.is_empty() {
+            embed = embed.description(self.notes.join("\n"));
+        }
+        embed
+    }
+}
+
+/// Sends a confirmation in the form the command author prefers.
+///
+/// If the preference can't be read, the confirmation is sent in the default form.
+/// Delivery failures are handled by [`delivery::confirm_reply`].
+pub async fn confirm(
+    ctx: poise::Context<'_, BotData, Error>,
+    target: delivery::ReplyTarget,
+    confirmation: Confirmation,
+) {
+    let author_id = ctx.author().id.to_string();
+    let data = ctx.data();
+    let stored = data
+        .cache
+        .preference(&data.database, &author_id, preferences::VERBOSITY_KEY)
+        .await;
+    let verbosity = match stored {
+        Ok(value) => value
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!("Failed to read the verbosity of {author_id}: {e}");
+            Verbosity::default()
+        }
+    };
+
+    match verbosity {
+        Verbosity::Compact if confirmation.components.is_empty() => {
+            delivery::confirm(ctx, target, confirmation.compact()).await;
+        }
+        Verbosity::Compact => {
+            let content = confirmation.compact();
+            let reply = poise::CreateReply::default()
+                .content(content.clone())
+                .components(confirmation.components);
+            delivery::confirm_reply(ctx, target, reply, &content).await;
+        }
+        Verbosity::Detailed => confirm_detailed(ctx, target, &confirmation).await,
+    }
+}
+
+/// Sends a confirmation as an embed in the household's theme and currency, falling back
+/// to the defaults if either can't be read.
+async fn confirm_detailed(
+    ctx: poise::Context<'_, BotData, Error>,
+    target: delivery::ReplyTarget,
+    confirmation: &Confirmation,
+) {
+    let db = &ctx.data().database;
+    let theme = match report::get_theme(db).await {
+        Ok(theme) => theme,
+        Err(e) => {
+            tracing::warn!("Failed to read the theme: {e}");
+            Theme::default()
+        }
+    };
+    let currency = match setup::get_currency_format(db).await {
+        Ok(currency) => currency,
+        Err(e) => {
+            tracing::warn!("Failed to read the currency: {e}");
+            Currency::default()
+        }
+    };
+    let mut reply = poise::CreateReply::default().embed(confirmation.detailed(theme, currency));
+    if !confirmation.components.is_empty() {
+        reply = reply.components(confirmation.components.clone());
+    }
+    delivery::confirm_reply(ctx, target, reply, &confirmation.compact()).await;
+}
+
+/// Generates a progress bar of the balance left, e.g. `[████░░░░░░] 40.0%`.
+fn progress_bar(theme: Theme, balance: f64, allocation: f64) -> String {
+    if allocation <= 0.0 {
+        return "[No allocation]".to_string();
+    }
+
+    let percentage = (balance / allocation * 100.0).clamp(0.0, 100.0);
+    format!(
+        "{} {percentage:.1}%",
+        theme.progress_bar(percentage, Some(10))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_joins_notes() {
+        let confirmation = Confirmation::new("Spent", "✅ Spent $5.00")
+            .field("Amount", "$5.00", true)
+            .note("")
+            .note("\n⚠️ Over cap");
+        assert_eq!(confirmation.compact(), "✅ Spent $5.00\n⚠️ Over cap");
+    }
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(
+            progress_bar(Theme::Classic, 40.0, 100.0),
+            "[████░░░░░░] 40.0%"
+        );
+        assert_eq!(
+            progress_bar(Theme::Classic, -5.0, 100.0),
+            "[░░░░░░░░░░] 0.0%"
+        );
+        assert_eq!(
+            progress_bar(Theme::Minimal, 50.0, 100.0),
+            "[■■■■■□□□□□] 50.0%"
+        );
+        assert_eq!(progress_bar(Theme::Classic, 5.0, 0.0), "[No allocation]");
+    }
+}
@@ -0,0 +1,363 @@
+//! Headless administration - `envelope-buddy admin <command>`.
+//!
+//! Runs the household's maintenance jobs against the bot's database without Discord,
+//! for cron jobs and for recovery while the bot is down. Every command goes straight to
+//! [`crate::core`], so the same rules apply as in the slash commands; the output is plain
+//! text for logs and terminals. Commands that write tell a running bot through
+//! [`cache::announce_external_write`], so it stops serving rows it cached before.
+//!
+//! - `report [user_id]` - the current month's report, as seen by `user_id` (private
+//!   envelopes of other members are hidden)
+//! - `update` - the monthly update, if one is due
+//! - `export [file]` - the envelopes in config.toml format, to `file` or standard output
+//! - `import <file>` - a config.toml file applied to the envelopes
+//! - `import-prices <file>` - product prices from a CSV or JSON file
+//! - `reconcile [--fix]` - balances that drifted from their history, fixed with `--fix`
+
+use crate::{
+    config,
+    core::{
+        cache, calendar, config_sync, monthly, product, reconcile,
+        report::{self, EnvelopeLine, PaceStatus, ReportModel},
+        setup::{self, Currency},
+    },
+    errors::{Error, Result},
+};
+use sea_orm::DatabaseConnection;
+use std::{fmt::Write, path::PathBuf};
+
+/// Usage shown for `help` and for arguments that aren't a command.
+pub const USAGE: &str = "Usage: envelope-buddy admin <command>\n\
+    \n\
+    Commands:\n  \
+      report [user_id]       Show this month's report, as seen by user_id\n  \
+      update                 Run the monthly update if one is due\n  \
+      export [file]          Write the envelopes as config.toml (default: standard output)\n  \
+      import <file>          Apply a config.toml file to the envelopes\n  \
+      import-prices <file>   Update product prices from a CSV or JSON file\n  \
+      reconcile [--fix]      Check balances against their history, fixing drift with --fix\n  \
+      help                   Show this message";
+
+/// An administration command read from the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminCommand {
+    /// Shows the current month's report
+    Report {
+        /// Member the report is shown to; other members' private envelopes are hidden
+        viewer_id: Option<String>,
+    },
+    /// Runs the monthly update if one is due
+    Update,
+    /// Exports the envelopes in config.toml format
+    Export {
+        /// File to write, or standard output
+        path: Option<PathBuf>,
+    },
+    /// Applies a config.toml file to the envelopes
+    Import {
+        /// File to read
+        path: PathBuf,
+    },
+    /// Updates product prices from a CSV or JSON file
+    ImportPrices {
+        /// File to read
+        path: PathBuf,
+    },
+    /// Checks balances against their history
+    Reconcile {
+        /// Rewrite the drifted balances
+        fix: bool,
+    },
+    /// Shows the usage
+    Help,
+}
+
+impl AdminCommand {
+    /// Parses the arguments following `admin`.
+    ///
+    /// # Errors
+    /// Returns `Error::Config` with the usage if the command is unknown or its
+    /// arguments are wrong.
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let command = match args.as_slice() {
+            [] | ["help" | "--help" | "-h"] => Self::Help,
+            ["report"] => Self::Report { viewer_id: None },
+            ["report", viewer_id] => Self::Report {
+                viewer_id: Some((*viewer_id).to_string()),
+            },
+            ["update"] => Self::Update,
+            ["export"] => Self::Export { path: None },
+            ["export", path] => Self::Export {
+                path: Some(PathBuf::from(path)),
+            },
+            ["import", path] => Self::Import {
+                path: PathBuf::from(path),
+            },
+            ["import-prices", path] => Self::ImportPrices {
+                path: PathBuf::from(path),
+            },
+            ["reconcile"] => Self::Reconcile { fix: false },
+            ["reconcile", "--fix"] => Self::Reconcile { fix: true },
+            _ => {
+                return Err(Error::Config {
+                    message: format!("Unknown admin command '{}'\n\n{USAGE}", args.join(" ")),
+                });
+            }
+        };
+        Ok(command)
+    }
+
+    /// Whether the command can change the database.
+    #[must_use]
+    pub const fn writes(&self) -> bool {
+        matches!(
+            self,
+            Self::Update
+                | Self::Import { .. }
+                | Self::ImportPrices { .. }
+                | Self::Reconcile { fix: true }
+        )
+    }
+}
+
+/// Runs a command and returns its output.
+///
+/// After a command that writes, a running bot is told to drop its cached rows.
+///
+/// # Errors
+/// Returns `Error::Config` if a file can't be read, written, or parsed, or an error if
+/// the command fails.
+pub async fn run(db: &DatabaseConnection, command: AdminCommand) -> Result<String> {
+    let writes = command.writes();
+    let output = execute(db, command).await?;
+    if writes {
+        cache::announce_external_write(db).await?;
+    }
+    Ok(output)
+}
+
+/// Runs a command without telling the bot about its writes.
+async fn execute(db: &DatabaseConnection, command: AdminCommand) -> Result<String> {
+    match command {
+        AdminCommand::Help => Ok(USAGE.to_string()),
+        AdminCommand::Report { viewer_id } => {
+            let today = calendar::today(db).await?;
+            let viewer_id = viewer_id.unwrap_or_default();
+            format_report(&report::build_report_model(db, &viewer_id, today).await?)
+        }
+        AdminCommand::Update => match monthly::process_monthly_updates(db).await? {
+            Some(result) => Ok(format!(
+                "Monthly update complete\n\n{}",
+                monthly::format_monthly_update_summary(
+                    &result,
+                    setup::get_currency_format(db).await?
+                )?
+            )),
+            None => Ok("No monthly update is due".to_string()),
+        },
+        AdminCommand::Export { path } => {
+            let snapshot = config_sync::export_envelopes(db).await?;
+            let text = config::envelopes::format_config(&snapshot)?;
+            match path {
+                Some(path) => {
+                    std::fs::write(&path, text).map_err(|e| Error::Config {
+                        message: format!("Failed to write {}: {e}", path.display()),
+                    })?;
+                    Ok(format!(
+                        "Exported {} envelope(s) to {}",
+                        snapshot.envelopes.len(),
+                        path.display()
+                    ))
+                }
+                None => Ok(text),
+            }
+        }
+        AdminCommand::Import { path } => {
+            let imported = config::envelopes::load_config(&path)?;
+            let result = config_sync::sync_envelopes(db, &imported).await?;
+            config_sync::format_sync_summary(&result)
+        }
+        AdminCommand::ImportPrices { path } => {
+            let content = std::fs::read_to_string(&path).map_err(|e| Error::Config {
+                message: format!("Failed to read {}: {e}", path.display()),
+            })?;
+            let rows = product::parse_price_import(&content)?;
+            let results = product::import_product_prices(db, &rows, None).await?;
+            product::format_price_import_summary(&results, setup::get_currency_format(db).await?)
+        }
+        AdminCommand::Reconcile { fix } => {
+            let report = if fix {
+                reconcile::fix_balances(db).await?
+            } else {
+                reconcile::check_balances(db).await?
+            };
+            format_reconcile(&report, fix, setup::get_currency_format(db).await?)
+        }
+    }
+}
+
+/// Formats the report as plain text, one envelope per line.
+fn format_report(model: &ReportModel) -> Result<String> {
+    let summary = &model.summary;
+    let currency = model.currency;
+    let mut out = format!(
+        "Report as of {} (day {}/{} of budget month)\n",
+        model.today.format("%Y-%m-%d"),
+        model.cycle.day_number(model.today),
+        model.cycle.days()
+    );
+    writeln!(
+        out,
+        "Allocated {} | Spent {} ({:.1}%) | Remaining {} | {} (expected {:.1}%)\n",
+        currency.format(summary.total_allocated),
+        currency.format(summary.total_spent),
+        summary.spent_percent(),
+        currency.format(summary.total_remaining),
+        status_label(model.status),
+        model.expected_percent
+    )?;
+    for line in &model.envelopes {
+        match line {
+            EnvelopeLine::Visible(figures) => writeln!(
+                out,
+                "{}: {} / {}, spent {} ({:.1}%), {}",
+                figures.envelope.name,
+                currency.format(figures.envelope.balance),
+                currency.format(figures.envelope.allocation),
+                currency.format(figures.spent.abs()),
+                figures.spent_percent.abs(),
+                status_label(figures.status)
+            )?,
+            EnvelopeLine::Hidden(hidden) => {
+                writeln!(out, "{}: {}", hidden.name, report::HIDDEN_LABEL)?;
+            }
+        }
+    }
+    for net in &model.ious {
+        writeln!(
+            out,
+            "IOU: {} owes {} {}",
+            config::users::get_user_display_name(&net.debtor_id),
+            config::users::get_user_display_name(&net.creditor_id),
+            currency.format(net.amount)
+        )?;
+    }
+    Ok(out)
+}
+
+/// Words for a pace status, where emoji don't belong.
+const fn status_label(status: PaceStatus) -> &'static str {
+    match status {
+        PaceStatus::OnTrack => "on track",
+        PaceStatus::SlightlyOver => "slightly over pace",
+        PaceStatus::WellOver => "well over pace",
+    }
+}
+
+/// Formats a balance check, or the balances fixed with `--fix`.
+fn format_reconcile(
+    report: &reconcile::ReconcileReport,
+    fixed: bool,
+    currency: Currency,
+) -> Result<String> {
+    let mut out = if fixed {
+        format!(
+            "Fixed {} balance(s) to match their history\n",
+            report.discrepancies.len()
+        )
+    } else {
+        format!(
+            "{} of {} checked balance(s) drifted\n",
+            report.discrepancies.len(),
+            report.checked
+        )
+    };
+    for discrepancy in &report.discrepancies {
+        writeln!(
+            out,
+            "{}: stored {}, expected {} ({})",
+            discrepancy.envelope.name,
+            currency.format(discrepancy.envelope.balance),
+            currency.format(discrepancy.expected),
+            currency.format_signed(discrepancy.difference())
+        )?;
+    }
+    if !report.skipped.is_empty() {
+        writeln!(
+            out,
+            "Skipped (no statement yet): {}",
+            report.skipped.join(", ")
+        )?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_admin_commands() -> Result<()> {
+        assert_eq!(AdminCommand::parse(&args(""))?, AdminCommand::Help);
+        assert_eq!(
+            AdminCommand::parse(&args("report 42"))?,
+            AdminCommand::Report {
+                viewer_id: Some("42".to_string())
+            }
+        );
+        assert_eq!(
+            AdminCommand::parse(&args("reconcile --fix"))?,
+            AdminCommand::Reconcile { fix: true }
+        );
+        assert_eq!(
+            AdminCommand::parse(&args("import-prices prices.csv"))?,
+            AdminCommand::ImportPrices {
+                path: PathBuf::from("prices.csv")
+            }
+        );
+        for line in ["import", "reconcile --force", "delete everything"] {
+            assert!(matches!(
+                AdminCommand::parse(&args(line)),
+                Err(Error::Config { .. })
+            ));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_admin_commands() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+
+        let report = run(&db, AdminCommand::Report { viewer_id: None }).await?;
+        assert!(report.contains(&env.name));
+        let exported = run(&db, AdminCommand::Export { path: None }).await?;
+        assert_eq!(
+            config::envelopes::parse_config(&exported)?.envelopes.len(),
+            1
+        );
+        let reconciled = run(&db, AdminCommand::Reconcile { fix: false }).await?;
+        assert!(reconciled.starts_with("0 of"));
+
+        // Only writes tell the bot to drop its cache
+        assert_eq!(cache::check_external_writes(&db, None).await?, None);
+        run(&db, AdminCommand::Reconcile { fix: true }).await?;
+        assert!(cache::check_external_writes(&db, None).await?.is_some());
+        assert!(
+            run(
+                &db,
+                AdminCommand::Import {
+                    path: PathBuf::from("/nonexistent/config.toml")
+                }
+            )
+            .await
+            .is_err()
+        );
+        Ok(())
+    }
+}
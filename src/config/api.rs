@@ -0,0 +1,102 @@
+//! Transaction ingestion endpoint configuration from environment variables.
+//!
+//! The endpoint (built with the `api` feature) is off unless `API_TOKEN` is set. It
+//! listens on `API_BIND` (default `127.0.0.1:8080`). Spends without a `user` are recorded
+//! for `API_DEFAULT_USER`, and confirmations are posted to `API_RELAY_CHANNEL_ID` if set.
+
+use super::non_empty_from_env;
+use std::net::SocketAddr;
+
+/// How the transaction ingestion endpoint is served.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiConfig {
+    /// Bearer token every request must present
+    pub token: String,
+    /// Address to listen on
+    pub bind: SocketAddr,
+    /// Nickname or Discord user ID for spends that don't name a user
+    pub default_user: Option<String>,
+    /// Discord channel that receives a confirmation for each spend
+    pub relay_channel_id: Option<u64>,
+}
+
+impl ApiConfig {
+    /// Loads the endpoint configuration, or `None` if `API_TOKEN` is unset or empty.
+    /// An invalid `API_BIND` falls back to the default address, and an invalid
+    /// `API_RELAY_CHANNEL_ID` disables relaying.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let token = non_empty_from_env("API_TOKEN")?;
+
+        let bind = non_empty_from_env("API_BIND")
+            .and_then(|value| {
+                let addr = value.parse().ok();
+                if addr.is_none() {
+                    tracing::warn!("Invalid API_BIND '{value}', using {}", default_bind());
+                }
+                addr
+            })
+            .unwrap_or_else(default_bind);
+
+        Some(Self {
+            token,
+            bind,
+            default_user: non_empty_from_env("API_DEFAULT_USER"),
+            relay_channel_id: non_empty_from_env("API_RELAY_CHANNEL_ID")
+                .and_then(|value| value.parse().ok())
+                .filter(|id| *id > 0),
+        })
+    }
+}
+
+/// The default listening address (local only; put a reverse proxy in front).
+fn default_bind() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 8080))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_config_from_env() {
+        temp_env::with_vars(
+            vec![
+                ("API_TOKEN", Some(" secret ")),
+                ("API_BIND", Some("0.0.0.0:9000")),
+                ("API_DEFAULT_USER", Some("alice")),
+                ("API_RELAY_CHANNEL_ID", Some("not-a-channel")),
+            ],
+            || {
+                let config = ApiConfig::from_env();
+                assert_eq!(
+                    config,
+                    Some(ApiConfig {
+                        token: "secret".to_string(),
+                        bind: SocketAddr::from(([0, 0, 0, 0], 9000)),
+                        default_user: Some("alice".to_string()),
+                        relay_channel_id: None,
+                    })
+                );
+            },
+        );
+
+        temp_env::with_vars(
+            vec![
+                ("API_TOKEN", Some("secret")),
+                ("API_BIND", Some("nowhere")),
+                ("API_DEFAULT_USER", None),
+                ("API_RELAY_CHANNEL_ID", Some("123")),
+            ],
+            || {
+                let config = ApiConfig::from_env();
+                assert_eq!(config.as_ref().map(|c| c.bind), Some(default_bind()));
+                assert_eq!(config.and_then(|c| c.relay_channel_id), Some(123));
+            },
+        );
+
+        temp_env::with_var_unset("API_TOKEN", || {
+            assert_eq!(ApiConfig::from_env(), None);
+        });
+    }
+}
@@ -0,0 +1,114 @@
+//! Command cooldown configuration from environment variables.
+//!
+//! Expensive commands get a per-user cooldown so a misbehaving client can't hammer the
+//! database. Each cooldown can be tuned (or disabled with `0`) through the environment.
+
+use std::time::Duration;
+
+/// Default per-user cooldown for `/report`.
+const DEFAULT_REPORT_COOLDOWN: Duration = Duration::from_secs(10);
+/// Default per-user cooldown for `/envelope export`.
+const DEFAULT_EXPORT_COOLDOWN: Duration = Duration::from_secs(30);
+/// Default per-user cooldown for `/update`.
+const DEFAULT_UPDATE_COOLDOWN: Duration = Duration::from_mins(1);
+/// Default per-user cooldown for `/reconcile`.
+const DEFAULT_RECONCILE_COOLDOWN: Duration = Duration::from_mins(5);
+
+/// Per-user cooldowns for expensive commands. `None` disables the cooldown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandCooldowns {
+    /// Cooldown for `/report`
+    pub report: Option<Duration>,
+    /// Cooldown for `/envelope export`
+    pub export: Option<Duration>,
+    /// Cooldown for `/update`
+    pub update: Option<Duration>,
+    /// Cooldown for `/reconcile`
+    pub reconcile: Option<Duration>,
+}
+
+impl Default for CommandCooldowns {
+    fn default() -> Self {
+        Self {
+            report: Some(DEFAULT_REPORT_COOLDOWN),
+            export: Some(DEFAULT_EXPORT_COOLDOWN),
+            update: Some(DEFAULT_UPDATE_COOLDOWN),
+            reconcile: Some(DEFAULT_RECONCILE_COOLDOWN),
+        }
+    }
+}
+
+impl CommandCooldowns {
+    /// Loads cooldowns from `REPORT_COOLDOWN_SECS`, `EXPORT_COOLDOWN_SECS`,
+    /// `UPDATE_COOLDOWN_SECS`, and `RECONCILE_COOLDOWN_SECS`, falling back to the
+    /// defaults for unset or invalid values. A value of `0` disables that cooldown.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            report: cooldown_from_env("REPORT_COOLDOWN_SECS", defaults.report),
+            export: cooldown_from_env("EXPORT_COOLDOWN_SECS", defaults.export),
+            update: cooldown_from_env("UPDATE_COOLDOWN_SECS", defaults.update),
+            reconcile: cooldown_from_env("RECONCILE_COOLDOWN_SECS", defaults.reconcile),
+        }
+    }
+
+    /// Returns the cooldown for a command's qualified name (such as `envelope export`),
+    /// if it has one.
+    #[must_use]
+    pub fn for_command(&self, qualified_name: &str) -> Option<Duration> {
+        match qualified_name {
+            "report" => self.report,
+            "envelope export" => self.export,
+            "update" => self.update,
+            "reconcile" => self.reconcile,
+            _ => None,
+        }
+    }
+}
+
+/// Reads a cooldown in seconds from `var`, keeping `default` if it is unset or invalid.
+fn cooldown_from_env(var: &str, default: Option<Duration>) -> Option<Duration> {
+    std::env::var(var)
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map_or(default, |secs| {
+            (secs > 0).then(|| Duration::from_secs(secs))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldowns_from_env() {
+        temp_env::with_vars(
+            vec![
+                ("REPORT_COOLDOWN_SECS", Some("5")),
+                ("EXPORT_COOLDOWN_SECS", Some("0")),
+                ("UPDATE_COOLDOWN_SECS", Some("soon")),
+                ("RECONCILE_COOLDOWN_SECS", None),
+            ],
+            || {
+                let cooldowns = CommandCooldowns::from_env();
+                assert_eq!(cooldowns.report, Some(Duration::from_secs(5)));
+                assert_eq!(cooldowns.export, None);
+                assert_eq!(cooldowns.update, Some(DEFAULT_UPDATE_COOLDOWN));
+                assert_eq!(cooldowns.reconcile, Some(DEFAULT_RECONCILE_COOLDOWN));
+
+                assert_eq!(
+                    cooldowns.for_command("report"),
+                    Some(Duration::from_secs(5))
+                );
+                assert_eq!(cooldowns.for_command("spend"), None);
+                assert_eq!(
+                    cooldowns.for_command("update"),
+                    Some(DEFAULT_UPDATE_COOLDOWN)
+                );
+                assert_eq!(cooldowns.for_command("product update"), None);
+                assert_eq!(cooldowns.for_command("export"), None);
+            },
+        );
+    }
+}
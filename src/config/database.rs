@@ -6,9 +6,136 @@
 //! method to automatically generate SQL statements from the entity models, ensuring that the
 //! database schema matches the Rust struct definitions without requiring manual SQL.
 
-use crate::entities::{Envelope, Product, SystemState, Transaction};
+use crate::core::envelope::collapse_whitespace;
+use crate::entities::{
+    BalanceSnapshot, Bill, Bundle, BundleItem, CategoryCap, CommandJournal, CommandUsage, Envelope,
+    EnvelopePermission, Favorite, Iou, MonthlyStatement, MonthlyUpdateLog, PendingPurchase,
+    Product, ScheduledTransaction, ShoppingListItem, SystemState, Transaction, TransactionType,
+    UserPreference,
+};
 use crate::errors::Result;
-use sea_orm::{ConnectionTrait, Database, DatabaseConnection, Schema};
+use sea_orm::{
+    ActiveEnum, ConnectOptions, ConnectionTrait, Database, DatabaseBackend, DatabaseConnection,
+    Schema, Statement,
+};
+use std::time::Duration;
+
+/// Shared in-memory database used by `--demo`.
+///
+/// Every connection to this URL within the process sees the same data, whichever pool
+/// it belongs to; nothing is written to disk.
+pub const DEMO_DATABASE_URL: &str = "sqlite:file:envelope_buddy_demo?mode=memory";
+
+/// Columns added to existing tables after their initial release.
+///
+/// `create_table_from_entity` only helps for fresh databases; tables that already exist
+/// are left untouched by `IF NOT EXISTS`, so these columns are added with `ALTER TABLE`
+/// when missing. Each entry is `(table, column, SQL column definition)`.
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("envelopes", "contribution", "REAL NOT NULL DEFAULT 0.0"),
+    ("envelopes", "goal", "REAL"),
+    ("envelopes", "is_private", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("envelopes", "alert_below", "REAL"),
+    ("envelopes", "daily_limit", "REAL"),
+    ("envelopes", "thread_id", "TEXT"),
+    ("envelopes", "weekly", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("envelopes", "ping_mention", "TEXT"),
+    ("envelopes", "min_balance", "REAL"),
+    ("envelopes", "interest_rate", "REAL"),
+    ("envelopes", "pinned", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("envelopes", "sort_order", "INTEGER"),
+    ("envelopes", "default_description", "TEXT"),
+    ("envelopes", "frozen", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("envelopes", "frozen_until", "DATE"),
+    ("envelopes", "pace_on_track", "REAL"),
+    ("envelopes", "pace_slightly_over", "REAL"),
+    ("envelopes", "pace_never_warn", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("products", "category", "TEXT"),
+    ("products", "default_description", "TEXT"),
+    ("transactions", "original_amount", "REAL"),
+    ("transactions", "original_currency", "TEXT"),
+    ("transactions", "refund_of", "INTEGER"),
+    ("transactions", "pending", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("transactions", "merchant", "TEXT"),
+    ("transactions", "quantity", "REAL"),
+    ("transactions", "unit", "TEXT"),
+    ("transactions", "round_up_of", "INTEGER"),
+    ("transactions", "location", "TEXT"),
+    ("transactions", "deleted_at", "TIMESTAMP"),
+    ("monthly_statements", "allocation", "REAL"),
+    ("monthly_statements", "applied_opening", "REAL"),
+    ("pending_purchases", "merchant", "TEXT"),
+    ("pending_purchases", "channel_id", "TEXT"),
+    ("pending_purchases", "decided_by", "TEXT"),
+];
+
+/// Unique indexes that the entity definitions can't express on their own.
+///
+/// Created with `IF NOT EXISTS` for both fresh and existing databases. Each entry is
+/// `(index name, table, columns)`.
+const UNIQUE_INDEXES: &[(&str, &str, &[&str])] = &[
+    (
+        "idx_transactions_message_id",
+        "transactions",
+        &["message_id"],
+    ),
+    (
+        "idx_balance_snapshots_envelope_date",
+        "balance_snapshots",
+        &["envelope_id", "snapshot_date"],
+    ),
+    (
+        "idx_bundle_items_bundle_product",
+        "bundle_items",
+        &["bundle_id", "product_id"],
+    ),
+    (
+        "idx_favorites_user_label",
+        "favorites",
+        &["user_id", "label"],
+    ),
+    (
+        "idx_envelope_permissions_envelope_user",
+        "envelope_permissions",
+        &["envelope_id", "user_id"],
+    ),
+    ("idx_category_caps_category", "category_caps", &["category"]),
+    ("idx_bills_name", "bills", &["name"]),
+    (
+        "idx_user_preferences_user_key",
+        "user_preferences",
+        &["user_id", "key"],
+    ),
+    (
+        "idx_monthly_statements_envelope_month",
+        "monthly_statements",
+        &["envelope_id", "month"],
+    ),
+    (
+        "idx_command_usage_command_user_month",
+        "command_usage",
+        &["command", "user_id", "month"],
+    ),
+];
+
+/// `SQLite` FTS5 table indexing transaction descriptions for full-text search.
+pub const TRANSACTION_SEARCH_TABLE: &str = "transactions_fts";
+
+/// Triggers keeping [`TRANSACTION_SEARCH_TABLE`] in sync with the transactions table.
+const TRANSACTION_SEARCH_TRIGGERS: &[&str] = &[
+    "CREATE TRIGGER IF NOT EXISTS transactions_fts_insert AFTER INSERT ON transactions BEGIN
+        INSERT INTO transactions_fts(rowid, description) VALUES (new.id, new.description);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS transactions_fts_delete AFTER DELETE ON transactions BEGIN
+        INSERT INTO transactions_fts(transactions_fts, rowid, description)
+            VALUES ('delete', old.id, old.description);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS transactions_fts_update AFTER UPDATE OF description ON transactions BEGIN
+        INSERT INTO transactions_fts(transactions_fts, rowid, description)
+            VALUES ('delete', old.id, old.description);
+        INSERT INTO transactions_fts(rowid, description) VALUES (new.id, new.description);
+    END",
+];
 
 /// Gets the database URL from environment variable or returns default `SQLite` path.
 ///
@@ -39,11 +166,31 @@ pub async fn create_connection() -> Result<DatabaseConnection> {
     Database::connect(&database_url).await.map_err(Into::into)
 }
 
+/// Connects to the `--demo` database ([`DEMO_DATABASE_URL`]).
+///
+/// An in-memory database disappears with its last connection, so this pool keeps one
+/// connection open for as long as the process runs.
+///
+/// # Errors
+/// Returns an error if the connection cannot be established.
+pub async fn connect_demo() -> Result<DatabaseConnection> {
+    // Long enough to never expire while the bot runs
+    let forever = Duration::from_secs(u64::from(u32::MAX));
+    let mut options = ConnectOptions::new(DEMO_DATABASE_URL);
+    options
+        .min_connections(1)
+        .idle_timeout(forever)
+        .max_lifetime(forever);
+    Database::connect(options).await.map_err(Into::into)
+}
+
 /// Creates all necessary database tables using `SeaORM`'s schema generation from entity definitions.
 ///
 /// This function uses the `DeriveEntityModel` macros to automatically generate proper SQL
 /// statements for table creation, ensuring the database schema matches the Rust struct definitions.
-/// It creates tables for envelopes, products, transactions, and system state.
+/// It creates tables for envelopes, products, transactions, system state, IOUs, balance
+/// snapshots, pending wishlist purchases, product bundles, favorites, category caps, bills,
+/// user preferences, and envelope permissions.
 /// Uses `IF NOT EXISTS` to safely run on existing databases (idempotent).
 ///
 /// # Errors
@@ -56,29 +203,312 @@ pub async fn create_tables(db: &DatabaseConnection) -> Result<()> {
     let schema = Schema::new(builder);
 
     // Create tables using SeaORM's schema generation with IF NOT EXISTS
-    let mut envelope_table = schema.create_table_from_entity(Envelope);
-    envelope_table.if_not_exists();
-    let mut product_table = schema.create_table_from_entity(Product);
-    product_table.if_not_exists();
-    let mut transaction_table = schema.create_table_from_entity(Transaction);
-    transaction_table.if_not_exists();
-    let mut system_state_table = schema.create_table_from_entity(SystemState);
-    system_state_table.if_not_exists();
-
-    db.execute(builder.build(&envelope_table)).await?;
-    db.execute(builder.build(&product_table)).await?;
-    db.execute(builder.build(&transaction_table)).await?;
-    db.execute(builder.build(&system_state_table)).await?;
+    let tables = vec![
+        schema.create_table_from_entity(Envelope),
+        schema.create_table_from_entity(Product),
+        schema.create_table_from_entity(Transaction),
+        schema.create_table_from_entity(SystemState),
+        schema.create_table_from_entity(Iou),
+        schema.create_table_from_entity(BalanceSnapshot),
+        schema.create_table_from_entity(PendingPurchase),
+        schema.create_table_from_entity(Bundle),
+        schema.create_table_from_entity(BundleItem),
+        schema.create_table_from_entity(Favorite),
+        schema.create_table_from_entity(ScheduledTransaction),
+        schema.create_table_from_entity(MonthlyStatement),
+        schema.create_table_from_entity(MonthlyUpdateLog),
+        schema.create_table_from_entity(CategoryCap),
+        schema.create_table_from_entity(Bill),
+        schema.create_table_from_entity(UserPreference),
+        schema.create_table_from_entity(CommandUsage),
+        schema.create_table_from_entity(EnvelopePermission),
+        schema.create_table_from_entity(CommandJournal),
+        schema.create_table_from_entity(ShoppingListItem),
+    ];
+    for mut table in tables {
+        table.if_not_exists();
+        db.execute(builder.build(&table)).await?;
+    }
+
+    migrate_schema(db).await?;
+
+    Ok(())
+}
+
+/// Brings tables created by older versions up to date with the current entity definitions.
+///
+/// Adds any column listed in `ADDED_COLUMNS` that is missing from its table, clears
+/// duplicated transaction message IDs, creates the indexes in `UNIQUE_INDEXES`,
+/// normalizes transaction types and envelope name spacing, and sets up the transaction
+/// search index. Only `SQLite` is supported; other backends are expected to be managed
+/// externally and are skipped.
+///
+/// # Errors
+/// Returns an error if inspecting a table, adding a column, or creating an index fails
+/// (for example, when existing rows already violate a unique index).
+pub async fn migrate_schema(db: &DatabaseConnection) -> Result<()> {
+    let backend = db.get_database_backend();
+    if backend != DatabaseBackend::Sqlite {
+        return Ok(());
+    }
+
+    for (table, column, definition) in ADDED_COLUMNS {
+        let columns = table_columns(db, table).await?;
+        // An empty column list means the table doesn't exist yet; nothing to migrate
+        if columns.is_empty() || columns.iter().any(|name| name == column) {
+            continue;
+        }
+
+        db.execute(Statement::from_string(
+            backend,
+            format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+        ))
+        .await?;
+    }
+
+    // Older versions could record a retried command twice; the index needs one of each
+    clear_duplicate_message_ids(db).await?;
+
+    for (index, table, columns) in UNIQUE_INDEXES {
+        let existing = table_columns(db, table).await?;
+        if !columns
+            .iter()
+            .all(|column| existing.iter().any(|name| name == column))
+        {
+            continue;
+        }
+
+        db.execute(Statement::from_string(
+            backend,
+            format!(
+                "CREATE UNIQUE INDEX IF NOT EXISTS {index} ON {table} ({})",
+                columns.join(", ")
+            ),
+        ))
+        .await?;
+    }
+
+    normalize_transaction_types(db).await?;
+    normalize_envelope_names(db).await?;
+    create_transaction_search_index(db).await
+}
+
+/// Clears `message_id` on transactions that repeat an earlier transaction's.
+///
+/// Before `idx_transactions_message_id` existed, a retried command (such as
+/// `/use_product`) could record the same message ID twice. The earliest row keeps it and
+/// the later ones are set to `NULL` and logged, so the unique index can be created.
+async fn clear_duplicate_message_ids(db: &DatabaseConnection) -> Result<()> {
+    let columns = table_columns(db, "transactions").await?;
+    if !columns.iter().any(|name| name == "message_id") {
+        return Ok(());
+    }
+
+    let backend = db.get_database_backend();
+    let rows = db
+        .query_all(Statement::from_string(
+            backend,
+            "SELECT id, message_id FROM transactions t \
+             WHERE message_id IS NOT NULL AND id > \
+             (SELECT MIN(id) FROM transactions WHERE message_id = t.message_id)"
+                .to_string(),
+        ))
+        .await?;
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let mut ids = Vec::with_capacity(rows.len());
+    for row in rows {
+        let id: i64 = row.try_get("", "id")?;
+        let message_id: String = row.try_get("", "message_id")?;
+        tracing::warn!(
+            "Transaction #{id} repeats message ID {message_id} of an earlier transaction; \
+             clearing its message ID"
+        );
+        ids.push(id.to_string());
+    }
+    db.execute(Statement::from_string(
+        backend,
+        format!(
+            "UPDATE transactions SET message_id = NULL WHERE id IN ({})",
+            ids.join(", ")
+        ),
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Rewrites `transaction_type` values from older versions to their canonical form.
+///
+/// Variants such as `"Spend"` or `"add_funds"` are mapped to the matching
+/// [`TransactionType`]. Values that don't name a known type become a spend or an add
+/// funds depending on the sign of the amount, so every row can be loaded.
+async fn normalize_transaction_types(db: &DatabaseConnection) -> Result<()> {
+    let columns = table_columns(db, "transactions").await?;
+    if !columns.iter().any(|name| name == "transaction_type") {
+        return Ok(());
+    }
+
+    let backend = db.get_database_backend();
+    let rows = db
+        .query_all(Statement::from_string(
+            backend,
+            "SELECT DISTINCT transaction_type FROM transactions".to_string(),
+        ))
+        .await?;
+
+    for row in rows {
+        let value: String = row.try_get("", "transaction_type")?;
+        let statement = match value.parse::<TransactionType>() {
+            Ok(kind) if kind.to_value() == value => continue,
+            Ok(kind) => Statement::from_sql_and_values(
+                backend,
+                "UPDATE transactions SET transaction_type = ? WHERE transaction_type = ?",
+                [kind.to_value().into(), value.into()],
+            ),
+            Err(_) => {
+                tracing::warn!(
+                    "Unknown transaction type '{value}'; treating those transactions as \
+                     spends or added funds by amount"
+                );
+                Statement::from_sql_and_values(
+                    backend,
+                    format!(
+                        "UPDATE transactions SET transaction_type = \
+                         CASE WHEN amount < 0 THEN '{}' ELSE '{}' END \
+                         WHERE transaction_type = ?",
+                        TransactionType::Spend.to_value(),
+                        TransactionType::AddFunds.to_value()
+                    ),
+                    [value.into()],
+                )
+            }
+        };
+        db.execute(statement).await?;
+    }
+
+    Ok(())
+}
+
+/// Trims envelope names and collapses runs of whitespace to one space.
+///
+/// Names are stored this way since they were first validated, and lookups compare
+/// against the collapsed form, so older names like `"Eating  Out"` could otherwise never
+/// be found. Names that are nothing but whitespace are left as they are.
+async fn normalize_envelope_names(db: &DatabaseConnection) -> Result<()> {
+    let columns = table_columns(db, "envelopes").await?;
+    if !columns.iter().any(|name| name == "name") {
+        return Ok(());
+    }
+
+    let backend = db.get_database_backend();
+    let rows = db
+        .query_all(Statement::from_string(
+            backend,
+            "SELECT id, name FROM envelopes".to_string(),
+        ))
+        .await?;
+
+    for row in rows {
+        let id: i64 = row.try_get("", "id")?;
+        let name: String = row.try_get("", "name")?;
+        let collapsed = collapse_whitespace(&name);
+        if collapsed == name || collapsed.is_empty() {
+            continue;
+        }
+
+        tracing::info!("Renaming envelope '{name}' (id {id}) to '{collapsed}'");
+        db.execute(Statement::from_sql_and_values(
+            backend,
+            "UPDATE envelopes SET name = ? WHERE id = ?",
+            [collapsed.into(), id.into()],
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Creates the FTS5 index over transaction descriptions and the triggers maintaining it.
+///
+/// The index is an external-content table over `transactions`, so it only stores the
+/// search terms. When it is first created, existing transactions are indexed with a
+/// rebuild.
+async fn create_transaction_search_index(db: &DatabaseConnection) -> Result<()> {
+    if table_columns(db, "transactions").await?.is_empty() {
+        return Ok(());
+    }
+
+    let backend = db.get_database_backend();
+    let exists = !db
+        .query_all(Statement::from_string(
+            backend,
+            format!(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '{TRANSACTION_SEARCH_TABLE}'"
+            ),
+        ))
+        .await?
+        .is_empty();
+
+    if !exists {
+        db.execute(Statement::from_string(
+            backend,
+            format!(
+                "CREATE VIRTUAL TABLE {TRANSACTION_SEARCH_TABLE} \
+                USING fts5(description, content='transactions', content_rowid='id')"
+            ),
+        ))
+        .await?;
+    }
+
+    for trigger in TRANSACTION_SEARCH_TRIGGERS {
+        db.execute(Statement::from_string(backend, (*trigger).to_string()))
+            .await?;
+    }
+
+    if !exists {
+        db.execute(Statement::from_string(
+            backend,
+            format!(
+                "INSERT INTO {TRANSACTION_SEARCH_TABLE}({TRANSACTION_SEARCH_TABLE}) VALUES ('rebuild')"
+            ),
+        ))
+        .await?;
+    }
 
     Ok(())
 }
 
+/// Lists the column names of a `SQLite` table using `PRAGMA table_info`.
+async fn table_columns(db: &DatabaseConnection, table: &str) -> Result<Vec<String>> {
+    let rows = db
+        .query_all(Statement::from_string(
+            DatabaseBackend::Sqlite,
+            format!("PRAGMA table_info({table})"),
+        ))
+        .await?;
+
+    rows.iter()
+        .map(|row| row.try_get::<String>("", "name").map_err(Into::into))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::entities::{
-        envelope::Model as EnvelopeModel, product::Model as ProductModel,
+        balance_snapshot::Model as BalanceSnapshotModel, bill::Model as BillModel,
+        bundle::Model as BundleModel, bundle_item::Model as BundleItemModel,
+        category_cap::Model as CategoryCapModel, command_journal::Model as CommandJournalModel,
+        command_usage::Model as CommandUsageModel, envelope::Model as EnvelopeModel,
+        envelope_permission::Model as EnvelopePermissionModel, favorite::Model as FavoriteModel,
+        iou::Model as IouModel, monthly_statement::Model as MonthlyStatementModel,
+        pending_purchase::Model as PendingPurchaseModel, product::Model as ProductModel,
+        scheduled_transaction::Model as ScheduledTransactionModel,
+        shopping_list_item::Model as ShoppingListItemModel,
         system_state::Model as SystemStateModel, transaction::Model as TransactionModel,
+        user_preference::Model as UserPreferenceModel,
     };
     use sea_orm::{EntityTrait, QuerySelect};
 
@@ -113,7 +543,162 @@ mod tests {
             .all(&db)
             .await?;
         let _: Vec<SystemStateModel> = SystemState::find().limit(1).all(&db).await?;
+        let _: Vec<IouModel> = Iou::find().limit(1).all(&db).await?;
+        let _: Vec<BalanceSnapshotModel> = BalanceSnapshot::find().limit(1).all(&db).await?;
+        let _: Vec<PendingPurchaseModel> = PendingPurchase::find().limit(1).all(&db).await?;
+        let _: Vec<BundleModel> = Bundle::find().limit(1).all(&db).await?;
+        let _: Vec<BundleItemModel> = BundleItem::find().limit(1).all(&db).await?;
+        let _: Vec<FavoriteModel> = Favorite::find().limit(1).all(&db).await?;
+        let _: Vec<ScheduledTransactionModel> =
+            ScheduledTransaction::find().limit(1).all(&db).await?;
+        let _: Vec<MonthlyStatementModel> = MonthlyStatement::find().limit(1).all(&db).await?;
+        let _: Vec<CategoryCapModel> = CategoryCap::find().limit(1).all(&db).await?;
+        let _: Vec<BillModel> = Bill::find().limit(1).all(&db).await?;
+        let _: Vec<UserPreferenceModel> = UserPreference::find().limit(1).all(&db).await?;
+        let _: Vec<CommandUsageModel> = CommandUsage::find().limit(1).all(&db).await?;
+        let _: Vec<EnvelopePermissionModel> = EnvelopePermission::find().limit(1).all(&db).await?;
+        let _: Vec<CommandJournalModel> = CommandJournal::find().limit(1).all(&db).await?;
+        let _: Vec<ShoppingListItemModel> = ShoppingListItem::find().limit(1).all(&db).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_schema_adds_missing_columns() -> Result<()> {
+        let db = Database::connect("sqlite::memory:").await?;
 
+        // Simulate a database created before the sinking-fund columns existed
+        db.execute(Statement::from_string(
+            DatabaseBackend::Sqlite,
+            "CREATE TABLE envelopes (id INTEGER PRIMARY KEY, name TEXT NOT NULL)".to_string(),
+        ))
+        .await?;
+        let before = table_columns(&db, "envelopes").await?;
+        assert!(!before.contains(&"contribution".to_string()));
+
+        migrate_schema(&db).await?;
+        let after = table_columns(&db, "envelopes").await?;
+        assert!(after.contains(&"contribution".to_string()));
+        assert!(after.contains(&"goal".to_string()));
+
+        // Running again is a no-op
+        migrate_schema(&db).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_schema_normalizes_transaction_types() -> Result<()> {
+        let (db, env) = crate::test_utils::setup_with_envelope().await?;
+
+        // Rows written before transaction types were validated
+        db.execute(Statement::from_string(
+            DatabaseBackend::Sqlite,
+            format!(
+                "INSERT INTO transactions \
+                 (envelope_id, amount, description, timestamp, user_id, transaction_type) \
+                 VALUES \
+                 ({id}, -5.0, 'a', '2025-01-01T00:00:00Z', 'u', 'Spend'), \
+                 ({id}, 10.0, 'b', '2025-01-01T00:00:00Z', 'u', ' add_funds'), \
+                 ({id}, -2.0, 'c', '2025-01-01T00:00:00Z', 'u', 'use product'), \
+                 ({id}, -3.0, 'd', '2025-01-01T00:00:00Z', 'u', 'mystery'), \
+                 ({id}, 4.0, 'e', '2025-01-01T00:00:00Z', 'u', 'mystery')",
+                id = env.id
+            ),
+        ))
+        .await?;
+
+        migrate_schema(&db).await?;
+        let types: Vec<TransactionType> = Transaction::find()
+            .all(&db)
+            .await?
+            .into_iter()
+            .map(|tx| tx.transaction_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                TransactionType::Spend,
+                TransactionType::AddFunds,
+                TransactionType::UseProduct,
+                TransactionType::Spend,
+                TransactionType::AddFunds,
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_schema_collapses_envelope_name_spacing() -> Result<()> {
+        let (db, env) = crate::test_utils::setup_with_envelope().await?;
+
+        // A name stored before names were normalized
+        db.execute(Statement::from_sql_and_values(
+            DatabaseBackend::Sqlite,
+            "UPDATE envelopes SET name = ? WHERE id = ?",
+            [" Eating   Out ".into(), env.id.into()],
+        ))
+        .await?;
+
+        migrate_schema(&db).await?;
+        let found = crate::core::envelope::get_shared_envelope_by_name(&db, "eating out").await?;
+        assert_eq!(found.map(|env| env.name).as_deref(), Some("Eating Out"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_schema_clears_duplicate_message_ids() -> Result<()> {
+        let db = Database::connect("sqlite::memory:").await?;
+
+        // A transactions table from before message IDs were unique, with a retried command
+        // recorded twice
+        db.execute(Statement::from_string(
+            DatabaseBackend::Sqlite,
+            "CREATE TABLE transactions (id INTEGER PRIMARY KEY, envelope_id INTEGER NOT NULL, \
+             amount REAL NOT NULL, description TEXT NOT NULL, timestamp TEXT NOT NULL, \
+             user_id TEXT NOT NULL, message_id TEXT, transaction_type TEXT NOT NULL)"
+                .to_string(),
+        ))
+        .await?;
+        db.execute(Statement::from_string(
+            DatabaseBackend::Sqlite,
+            "INSERT INTO transactions \
+             (id, envelope_id, amount, description, timestamp, user_id, message_id, \
+              transaction_type) VALUES \
+             (1, 1, -5.0, 'a', '2025-01-01T00:00:00Z', 'u', 'm1', 'use_product'), \
+             (2, 1, -5.0, 'a', '2025-01-01T00:00:00Z', 'u', 'm1', 'use_product'), \
+             (3, 1, -2.0, 'b', '2025-01-01T00:00:00Z', 'u', 'm2', 'spend'), \
+             (4, 1, -5.0, 'a', '2025-01-01T00:00:00Z', 'u', 'm1', 'use_product')"
+                .to_string(),
+        ))
+        .await?;
+
+        migrate_schema(&db).await?;
+        let message_ids: Vec<(i64, Option<String>)> = Transaction::find()
+            .all(&db)
+            .await?
+            .into_iter()
+            .map(|tx| (tx.id, tx.message_id))
+            .collect();
+        assert_eq!(
+            message_ids,
+            vec![
+                (1, Some("m1".to_string())),
+                (2, None),
+                (3, Some("m2".to_string())),
+                (4, None),
+            ]
+        );
+
+        // The index now holds, and running again is a no-op
+        assert!(
+            db.execute(Statement::from_string(
+                DatabaseBackend::Sqlite,
+                "UPDATE transactions SET message_id = 'm1' WHERE id = 3".to_string(),
+            ))
+            .await
+            .is_err()
+        );
+        migrate_schema(&db).await?;
         Ok(())
     }
 
@@ -124,4 +709,22 @@ mod tests {
         test_connection(&db).await?;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_demo_connections_share_data() -> Result<()> {
+        let db = connect_demo().await?;
+        create_tables(&db).await?;
+        crate::test_utils::create_test_envelope(&db, "Shared").await?;
+
+        // A separate pool in the same process sees the same database
+        let other = Database::connect(DEMO_DATABASE_URL).await?;
+        let names: Vec<String> = Envelope::find()
+            .all(&other)
+            .await?
+            .into_iter()
+            .map(|env| env.name)
+            .collect();
+        assert_eq!(names, ["Shared"]);
+        Ok(())
+    }
 }
@@ -2,21 +2,22 @@
 //!
 //! This module provides functionality to load initial envelope configurations
 //! from a TOML configuration file. The envelopes defined in config.toml are
-//! used to seed the database on first run or when envelopes are missing.
+//! used to seed the database on first run or when envelopes are missing. The same format
+//! is produced by `/envelope export` and accepted by `/envelope import`.
 
 use crate::errors::{Error, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 /// Configuration structure representing the entire config.toml file
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
     /// List of envelope configurations to seed
     pub envelopes: Vec<EnvelopeConfig>,
 }
 
 /// Configuration for a single envelope
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct EnvelopeConfig {
     /// Name of the envelope
     pub name: String,
@@ -28,6 +29,12 @@ pub struct EnvelopeConfig {
     pub is_individual: bool,
     /// Whether unused balance rolls over to next month
     pub rollover: bool,
+    /// Fixed sinking-fund contribution added each monthly update (defaults to 0.0)
+    #[serde(default)]
+    pub contribution: f64,
+    /// Optional savings goal for sinking-fund envelopes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<f64>,
 }
 
 /// Loads envelope configuration from a TOML file
@@ -49,11 +56,29 @@ pub fn load_config<P: AsRef<Path>>(path: P) -> Result<Config> {
         message: format!("Failed to read config file: {e}"),
     })?;
 
-    toml::from_str(&contents).map_err(|e| Error::Config {
+    parse_config(&contents)
+}
+
+/// Parses envelope configuration from TOML text in the config.toml format
+///
+/// # Errors
+/// Returns an error if the TOML syntax is invalid or required fields are missing.
+pub fn parse_config(contents: &str) -> Result<Config> {
+    toml::from_str(contents).map_err(|e| Error::Config {
         message: format!("Failed to parse config.toml: {e}"),
     })
 }
 
+/// Formats envelope configuration as TOML in the config.toml format
+///
+/// # Errors
+/// Returns an error if the configuration can't be serialized.
+pub fn format_config(config: &Config) -> Result<String> {
+    toml::to_string(config).map_err(|e| Error::Config {
+        message: format!("Failed to format config.toml: {e}"),
+    })
+}
+
 /// Loads envelope configuration from the default location (./config.toml)
 ///
 /// # Returns
@@ -103,5 +128,27 @@ mod tests {
         assert_eq!(config.envelopes[1].name, "game");
         assert!(config.envelopes[1].is_individual);
         assert!(config.envelopes[1].rollover);
+
+        // Sinking-fund fields are optional
+        assert_eq!(config.envelopes[0].contribution, 0.0);
+        assert_eq!(config.envelopes[0].goal, None);
+    }
+
+    #[test]
+    fn test_parse_sinking_fund_config() {
+        let toml_str = r#"
+            [[envelopes]]
+            name = "car-tires"
+            category = "savings"
+            allocation = 0.0
+            is_individual = false
+            rollover = true
+            contribution = 50.0
+            goal = 800.0
+        "#;
+
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.envelopes[0].contribution, 50.0);
+        assert_eq!(config.envelopes[0].goal, Some(800.0));
     }
 }
@@ -0,0 +1,121 @@
+//! Transaction journal configuration from environment variables.
+//!
+//! The journal is off unless `TRANSACTION_JOURNAL_PATH` is set. The format defaults to
+//! NDJSON, or CSV when the path ends in `.csv`, and can be forced with
+//! `TRANSACTION_JOURNAL_FORMAT`. Files rotate once they reach
+//! `TRANSACTION_JOURNAL_MAX_BYTES`, keeping `TRANSACTION_JOURNAL_MAX_FILES` old files.
+
+use std::path::PathBuf;
+
+/// Default size at which the journal file is rotated (10 MiB).
+const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+/// Default number of rotated journal files kept next to the active one.
+const DEFAULT_MAX_FILES: usize = 5;
+
+/// Line format of the journal file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalFormat {
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+/// Where and how committed transactions are journaled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalConfig {
+    /// Path of the active journal file
+    pub path: PathBuf,
+    /// Line format
+    pub format: JournalFormat,
+    /// Size in bytes at which the active file is rotated
+    pub max_bytes: u64,
+    /// Number of rotated files kept (`journal.1` is the newest)
+    pub max_files: usize,
+}
+
+impl JournalConfig {
+    /// Loads the journal configuration, or `None` if `TRANSACTION_JOURNAL_PATH` is unset
+    /// or empty. Invalid sizes and counts fall back to the defaults.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var("TRANSACTION_JOURNAL_PATH")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)?;
+
+        let format = match std::env::var("TRANSACTION_JOURNAL_FORMAT")
+            .ok()
+            .map(|value| value.trim().to_lowercase())
+            .as_deref()
+        {
+            Some("csv") => JournalFormat::Csv,
+            Some("ndjson" | "json") => JournalFormat::Ndjson,
+            _ if path.extension().is_some_and(|ext| ext == "csv") => JournalFormat::Csv,
+            _ => JournalFormat::Ndjson,
+        };
+
+        Some(Self {
+            path,
+            format,
+            max_bytes: positive_from_env("TRANSACTION_JOURNAL_MAX_BYTES")
+                .unwrap_or(DEFAULT_MAX_BYTES),
+            max_files: positive_from_env("TRANSACTION_JOURNAL_MAX_FILES")
+                .and_then(|files| usize::try_from(files).ok())
+                .unwrap_or(DEFAULT_MAX_FILES),
+        })
+    }
+}
+
+/// Reads a positive integer from `var`, ignoring unset, invalid, or zero values.
+fn positive_from_env(var: &str) -> Option<u64> {
+    std::env::var(var)
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|value| *value > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_config_from_env() {
+        temp_env::with_vars(
+            vec![
+                ("TRANSACTION_JOURNAL_PATH", Some("data/journal.csv")),
+                ("TRANSACTION_JOURNAL_FORMAT", None),
+                ("TRANSACTION_JOURNAL_MAX_BYTES", Some("2048")),
+                ("TRANSACTION_JOURNAL_MAX_FILES", Some("0")),
+            ],
+            || {
+                let config = JournalConfig::from_env();
+                assert_eq!(
+                    config,
+                    Some(JournalConfig {
+                        path: PathBuf::from("data/journal.csv"),
+                        format: JournalFormat::Csv,
+                        max_bytes: 2048,
+                        max_files: DEFAULT_MAX_FILES,
+                    })
+                );
+            },
+        );
+
+        temp_env::with_vars(
+            vec![
+                ("TRANSACTION_JOURNAL_PATH", Some("data/journal.csv")),
+                ("TRANSACTION_JOURNAL_FORMAT", Some("ndjson")),
+            ],
+            || {
+                let config = JournalConfig::from_env();
+                assert_eq!(config.map(|c| c.format), Some(JournalFormat::Ndjson));
+            },
+        );
+
+        temp_env::with_var_unset("TRANSACTION_JOURNAL_PATH", || {
+            assert_eq!(JournalConfig::from_env(), None);
+        });
+    }
+}
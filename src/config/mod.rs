@@ -1,8 +1,28 @@
+/// Transaction ingestion endpoint configuration from environment variables
+pub mod api;
+
+/// Command cooldown configuration from environment variables
+pub mod cooldowns;
+
 /// Database configuration and connection management
 pub mod database;
 
 /// Envelope configuration loading from config.toml
 pub mod envelopes;
 
+/// Transaction journal configuration from environment variables
+pub mod journal;
+
+/// Notification backend configuration from environment variables
+pub mod notify;
+
 /// User nickname configuration from environment variables
 pub mod users;
+
+/// Reads `var`, ignoring unset or blank values.
+pub(crate) fn non_empty_from_env(var: &str) -> Option<String> {
+    std::env::var(var)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
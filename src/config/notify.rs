@@ -0,0 +1,228 @@
+//! Notification backend configuration from environment variables.
+//!
+//! `NOTIFIERS` lists the backends that deliver alerts and the monthly update summary,
+//! comma separated: `discord` (the default), `email` (`email` feature), and `matrix`
+//! (`matrix` feature). Email needs `SMTP_HOST`, `NOTIFY_EMAIL_FROM`, and
+//! `NOTIFY_EMAIL_TO`; Matrix needs `MATRIX_HOMESERVER`, `MATRIX_ACCESS_TOKEN`, and
+//! `MATRIX_ROOM_ID`.
+
+use super::non_empty_from_env;
+use crate::errors::{Error, Result};
+use std::collections::HashMap;
+
+/// Default SMTP submission port (STARTTLS).
+const DEFAULT_SMTP_PORT: u16 = 587;
+
+/// Which backends deliver notifications, and how to reach the optional ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotifyConfig {
+    /// Whether alerts are posted to Discord
+    pub discord: bool,
+    /// Email delivery, if selected
+    pub email: Option<EmailConfig>,
+    /// Matrix delivery, if selected
+    pub matrix: Option<MatrixConfig>,
+}
+
+impl Default for NotifyConfig {
+    /// Discord only.
+    fn default() -> Self {
+        Self {
+            discord: true,
+            email: None,
+            matrix: None,
+        }
+    }
+}
+
+/// SMTP settings for email notifications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailConfig {
+    /// SMTP server, reached with STARTTLS
+    pub host: String,
+    /// SMTP port (`SMTP_PORT`, default 587)
+    pub port: u16,
+    /// SMTP login, if the server needs one (`SMTP_USERNAME` and `SMTP_PASSWORD`)
+    pub credentials: Option<(String, String)>,
+    /// Sender address
+    pub from: String,
+    /// Addresses that receive household notifications
+    pub to: Vec<String>,
+    /// Address of each member by Discord user ID, for their personal alerts
+    /// (`NOTIFY_EMAIL_MEMBERS`, e.g. `123:alex@example.com,456:sam@example.com`)
+    pub members: HashMap<String, String>,
+}
+
+/// Matrix settings for notifications posted to a room.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixConfig {
+    /// Homeserver base URL, e.g. `https://matrix.example.org`
+    pub homeserver: String,
+    /// Access token of the account that posts
+    pub access_token: String,
+    /// Room that receives household notifications
+    pub room_id: String,
+}
+
+impl NotifyConfig {
+    /// Loads the notification configuration. Without `NOTIFIERS`, only Discord is used.
+    ///
+    /// # Errors
+    /// Returns `Error::Config` if `NOTIFIERS` names an unknown backend or a selected
+    /// backend is missing required settings.
+    pub fn from_env() -> Result<Self> {
+        let Some(backends) = non_empty_from_env("NOTIFIERS") else {
+            return Ok(Self::default());
+        };
+
+        let mut config = Self {
+            discord: false,
+            email: None,
+            matrix: None,
+        };
+        for backend in backends.split(',').map(str::trim).filter(|b| !b.is_empty()) {
+            match backend.to_lowercase().as_str() {
+                "discord" => config.discord = true,
+                "email" => config.email = Some(EmailConfig::from_env()?),
+                "matrix" => config.matrix = Some(MatrixConfig::from_env()?),
+                other => {
+                    return Err(Error::Config {
+                        message: format!(
+                            "Unknown notifier '{other}' in NOTIFIERS (use discord, email, or matrix)"
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl EmailConfig {
+    /// Loads the SMTP settings.
+    ///
+    /// # Errors
+    /// Returns `Error::Config` if a required variable is missing or `SMTP_PORT` is invalid.
+    fn from_env() -> Result<Self> {
+        let port = match non_empty_from_env("SMTP_PORT") {
+            Some(value) => value.parse().map_err(|_| Error::Config {
+                message: format!("Invalid SMTP_PORT '{value}'"),
+            })?,
+            None => DEFAULT_SMTP_PORT,
+        };
+        let credentials = non_empty_from_env("SMTP_USERNAME").map(|user| {
+            (
+                user,
+                non_empty_from_env("SMTP_PASSWORD").unwrap_or_default(),
+            )
+        });
+        let members = non_empty_from_env("NOTIFY_EMAIL_MEMBERS")
+            .map(|value| {
+                value
+                    .split(',')
+                    .filter_map(|entry| entry.split_once(':'))
+                    .map(|(user_id, address)| {
+                        (user_id.trim().to_string(), address.trim().to_string())
+                    })
+                    .filter(|(user_id, address)| !user_id.is_empty() && !address.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            host: required_from_env("SMTP_HOST", "email")?,
+            port,
+            credentials,
+            from: required_from_env("NOTIFY_EMAIL_FROM", "email")?,
+            to: required_from_env("NOTIFY_EMAIL_TO", "email")?
+                .split(',')
+                .map(str::trim)
+                .filter(|address| !address.is_empty())
+                .map(str::to_string)
+                .collect(),
+            members,
+        })
+    }
+}
+
+impl MatrixConfig {
+    /// Loads the Matrix settings.
+    ///
+    /// # Errors
+    /// Returns `Error::Config` if a required variable is missing.
+    fn from_env() -> Result<Self> {
+        Ok(Self {
+            homeserver: required_from_env("MATRIX_HOMESERVER", "matrix")?
+                .trim_end_matches('/')
+                .to_string(),
+            access_token: required_from_env("MATRIX_ACCESS_TOKEN", "matrix")?,
+            room_id: required_from_env("MATRIX_ROOM_ID", "matrix")?,
+        })
+    }
+}
+
+/// Reads `var`, which the `backend` notifier needs.
+fn required_from_env(var: &str, backend: &str) -> Result<String> {
+    non_empty_from_env(var).ok_or_else(|| Error::Config {
+        message: format!("{var} must be set to use the {backend} notifier"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_notify_config_defaults_to_discord() {
+        temp_env::with_var_unset("NOTIFIERS", || {
+            assert_eq!(NotifyConfig::from_env().ok(), Some(NotifyConfig::default()));
+        });
+    }
+
+    #[test]
+    fn test_notify_config_from_env() {
+        temp_env::with_vars(
+            vec![
+                ("NOTIFIERS", Some("email, matrix")),
+                ("SMTP_HOST", Some("smtp.example.com")),
+                ("SMTP_PORT", None),
+                ("SMTP_USERNAME", None),
+                ("NOTIFY_EMAIL_FROM", Some("bot@example.com")),
+                ("NOTIFY_EMAIL_TO", Some("alex@example.com, sam@example.com")),
+                ("NOTIFY_EMAIL_MEMBERS", Some("123:alex@example.com,bad")),
+                ("MATRIX_HOMESERVER", Some("https://matrix.example.org/")),
+                ("MATRIX_ACCESS_TOKEN", Some("token")),
+                ("MATRIX_ROOM_ID", Some("!room:example.org")),
+            ],
+            || {
+                let config = NotifyConfig::from_env().unwrap();
+                assert!(!config.discord);
+                let email = config.email.unwrap();
+                assert_eq!(email.port, DEFAULT_SMTP_PORT);
+                assert_eq!(email.credentials, None);
+                assert_eq!(email.to, vec!["alex@example.com", "sam@example.com"]);
+                assert_eq!(email.members.len(), 1);
+                assert_eq!(
+                    email.members.get("123").map(String::as_str),
+                    Some("alex@example.com")
+                );
+                let matrix = config.matrix.unwrap();
+                assert_eq!(matrix.homeserver, "https://matrix.example.org");
+            },
+        );
+    }
+
+    #[test]
+    fn test_notify_config_rejects_bad_settings() {
+        temp_env::with_vars(vec![("NOTIFIERS", Some("discord,pager"))], || {
+            assert!(NotifyConfig::from_env().is_err());
+        });
+        temp_env::with_vars(
+            vec![("NOTIFIERS", Some("matrix")), ("MATRIX_HOMESERVER", None)],
+            || {
+                assert!(NotifyConfig::from_env().is_err());
+            },
+        );
+    }
+}
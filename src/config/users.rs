@@ -1,15 +1,30 @@
 //! User configuration module for loading user nicknames from environment variables.
 //!
 //! This module provides functionality to load and map Discord user IDs to friendly
-//! nicknames configured in the `.env` file. Nicknames are optional and will fall back
-//! to Discord usernames if not configured.
+//! nicknames configured in the `.env` file or saved through `/setup`. Nicknames are
+//! optional and will fall back to Discord usernames if not configured.
 
 use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// Nicknames saved through `/setup`, registered at startup and whenever setup runs.
+static REGISTERED_NICKNAMES: OnceLock<RwLock<HashMap<String, String>>> = OnceLock::new();
+
+/// Registers nicknames loaded from the database so they are visible to the lookup
+/// functions in this module. Registered nicknames take precedence over `.env` entries
+/// for the same user ID.
+pub fn register_nicknames(nicknames: impl IntoIterator<Item = (String, String)>) {
+    let registry = REGISTERED_NICKNAMES.get_or_init(|| RwLock::new(HashMap::new()));
+    if let Ok(mut guard) = registry.write() {
+        guard.extend(nicknames);
+    }
+}
 
 /// Gets a mapping of user IDs to their configured nicknames from environment variables.
 ///
 /// Reads `COUPLE_USER_ID_1`, `COUPLE_USER_ID_2`, `USER_NICKNAME_1`, and `USER_NICKNAME_2`
-/// from the environment and creates a `HashMap` for quick lookups.
+/// from the environment and creates a `HashMap` for quick lookups, then layers any
+/// nicknames registered via [`register_nicknames`] on top.
 ///
 /// # Returns
 ///
@@ -35,6 +50,13 @@ pub fn get_user_nicknames() -> HashMap<String, String> {
         nicknames.insert(user_id_2, nickname_2);
     }
 
+    // Nicknames saved through /setup override the environment
+    if let Some(registry) = REGISTERED_NICKNAMES.get()
+        && let Ok(guard) = registry.read()
+    {
+        nicknames.extend(guard.iter().map(|(id, nick)| (id.clone(), nick.clone())));
+    }
+
     nicknames
 }
 
@@ -0,0 +1,611 @@
+//! Alert business logic - Low-balance warnings and overspend pings after balance changes.
+//!
+//! Envelopes can set an `alert_below` threshold. Every operation that changes a balance
+//! calls [`check_low_balance`] once its changes are committed; when the new balance is
+//! below the threshold, an alert is returned for the bot layer to deliver. Each envelope
+//! alerts at most once per calendar month, tracked in the `system_state` table.
+//!
+//! Envelopes can also link a role or member mention that [`check_overspend`] pings when
+//! the envelope goes below zero or spends more than its allocation this budget cycle.
+//! Pings fire at most once per cycle.
+//!
+//! [`check_pace`] is the mid-month pace checker: it warns once per cycle when an
+//! envelope's spending is 🔴 against the expected pace, using the envelope's own pace
+//! thresholds where it has them. Envelopes set to never warn (such as rent) are skipped.
+
+use crate::{
+    core::{
+        calendar, envelope,
+        notify::{Audience, Notification},
+        report,
+        setup::{self, Currency},
+        state, statement,
+    },
+    errors::Result,
+};
+use chrono::NaiveDate;
+use sea_orm::DatabaseConnection;
+
+/// Prefix of the `system_state` keys recording the month an envelope last alerted.
+const LOW_BALANCE_KEY_PREFIX: &str = "low_balance_alert:";
+
+/// Prefix of the `system_state` keys recording the cycle an envelope last pinged.
+const OVERSPEND_KEY_PREFIX: &str = "overspend_ping:";
+
+/// Prefix of the `system_state` keys recording the cycle an envelope last warned about
+/// its pace.
+const PACE_KEY_PREFIX: &str = "pace_warning:";
+
+/// Where a low-balance alert should be delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlertTarget {
+    /// The announcement channel (shared envelopes)
+    Channel,
+    /// A direct message to the envelope owner (individual envelopes)
+    DirectMessage {
+        /// Discord user ID of the owner
+        user_id: String,
+    },
+}
+
+/// A low-balance warning for a single envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LowBalanceAlert {
+    /// Envelope ID
+    pub envelope_id: i64,
+    /// Envelope name
+    pub envelope_name: String,
+    /// Balance after the change that triggered the alert
+    pub balance: f64,
+    /// Configured `alert_below` threshold
+    pub threshold: f64,
+    /// Where the alert should be posted
+    pub target: AlertTarget,
+    /// Household currency the amounts are written in
+    pub currency: Currency,
+}
+
+impl LowBalanceAlert {
+    /// Formats the alert for display in Discord.
+    #[must_use]
+    pub fn message(&self) -> String {
+        format!(
+            "⚠️ **Low balance:** envelope '{}' is down to {} (alert threshold {}).",
+            self.envelope_name,
+            self.currency.format(self.balance),
+            self.currency.format(self.threshold)
+        )
+    }
+
+    /// Packages the alert for the notification backends.
+    #[must_use]
+    pub fn notification(&self) -> Notification {
+        let audience = match &self.target {
+            AlertTarget::Channel => Audience::Household,
+            AlertTarget::DirectMessage { user_id } => Audience::Member {
+                user_id: user_id.clone(),
+            },
+        };
+        Notification {
+            subject: format!("Low balance in {}", self.envelope_name),
+            body: self.message(),
+            audience,
+            mention: None,
+        }
+    }
+}
+
+/// Why an envelope's overspend ping fired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverspendReason {
+    /// The balance went below zero
+    BelowZero,
+    /// This cycle's spending went past the allocation
+    OverBudget,
+}
+
+/// An overspend ping for a single envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverspendAlert {
+    /// Envelope ID
+    pub envelope_id: i64,
+    /// Envelope name
+    pub envelope_name: String,
+    /// Role or member mention to ping
+    pub mention: String,
+    /// Balance after the change that triggered the ping
+    pub balance: f64,
+    /// Monthly allocation
+    pub allocation: f64,
+    /// Spent from the envelope so far this cycle
+    pub spent: f64,
+    /// Which condition fired
+    pub reason: OverspendReason,
+    /// Household currency the amounts are written in
+    pub currency: Currency,
+}
+
+impl OverspendAlert {
+    /// Formats the ping for display in Discord.
+    #[must_use]
+    pub fn message(&self) -> String {
+        format!("🚨 {} envelope {}", self.mention, self.details())
+    }
+
+    /// Packages the ping for the notification backends. The mention only pings on Discord.
+    #[must_use]
+    pub fn notification(&self) -> Notification {
+        let subject = match self.reason {
+            OverspendReason::BelowZero => format!("{} is below zero", self.envelope_name),
+            OverspendReason::OverBudget => format!("{} is over budget", self.envelope_name),
+        };
+        Notification {
+            subject,
+            body: format!("🚨 Envelope {}", self.details()),
+            audience: Audience::Household,
+            mention: Some(self.mention.clone()),
+        }
+    }
+
+    /// What happened, after the envelope's name.
+    fn details(&self) -> String {
+        match self.reason {
+            OverspendReason::BelowZero => format!(
+                "'{}' is below zero: balance {}.",
+                self.envelope_name,
+                self.currency.format(self.balance)
+            ),
+            OverspendReason::OverBudget => format!(
+                "'{}' is over budget: {} spent of {} this cycle.",
+                self.envelope_name,
+                self.currency.format(self.spent),
+                self.currency.format(self.allocation)
+            ),
+        }
+    }
+}
+
+/// A warning that an envelope is spending well ahead of the expected pace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaceAlert {
+    /// Envelope ID
+    pub envelope_id: i64,
+    /// Envelope name
+    pub envelope_name: String,
+    /// Spent from the envelope so far this cycle, as a percentage of its allocation
+    pub spent_percent: f64,
+    /// Percentage of the budget month elapsed
+    pub expected_percent: f64,
+    /// Where the warning should be posted
+    pub target: AlertTarget,
+}
+
+impl PaceAlert {
+    /// Formats the warning for display in Discord.
+    #[must_use]
+    pub fn message(&self) -> String {
+        format!(
+            "🔴 **Ahead of pace:** envelope '{}' has spent {:.0}% of its allocation with {:.0}% \
+             of the month gone.",
+            self.envelope_name, self.spent_percent, self.expected_percent
+        )
+    }
+
+    /// Packages the warning for the notification backends.
+    #[must_use]
+    pub fn notification(&self) -> Notification {
+        let audience = match &self.target {
+            AlertTarget::Channel => Audience::Household,
+            AlertTarget::DirectMessage { user_id } => Audience::Member {
+                user_id: user_id.clone(),
+            },
+        };
+        Notification {
+            subject: format!("{} is ahead of pace", self.envelope_name),
+            body: self.message(),
+            audience,
+            mention: None,
+        }
+    }
+}
+
+/// Checks an envelope's balance after a change and returns an alert if it should fire.
+///
+/// An alert fires when the envelope has an `alert_below` threshold, its balance is below
+/// that threshold, and it has not already alerted this month. Firing records the month
+/// so later changes in the same month stay quiet.
+///
+/// # Errors
+/// Returns an error if the database query or state update fails.
+pub async fn check_low_balance(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+) -> Result<Option<LowBalanceAlert>> {
+    check_low_balance_on(db, envelope_id, calendar::today(db).await?).await
+}
+
+/// Runs [`check_low_balance`] for each envelope, collecting the alerts that fire.
+///
+/// # Errors
+/// Returns an error if any database query or state update fails.
+pub async fn check_low_balances(
+    db: &DatabaseConnection,
+    envelope_ids: &[i64],
+) -> Result<Vec<LowBalanceAlert>> {
+    let mut alerts = Vec::new();
+    for &envelope_id in envelope_ids {
+        if let Some(alert) = check_low_balance(db, envelope_id).await? {
+            alerts.push(alert);
+        }
+    }
+    Ok(alerts)
+}
+
+/// [`check_low_balance`] with an explicit date, so deduplication can be tested.
+async fn check_low_balance_on(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    today: NaiveDate,
+) -> Result<Option<LowBalanceAlert>> {
+    let Some(env) = envelope::get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+    else {
+        return Ok(None);
+    };
+    let Some(threshold) = env.alert_below else {
+        return Ok(None);
+    };
+    if env.balance >= threshold {
+        return Ok(None);
+    }
+
+    let key = format!("{LOW_BALANCE_KEY_PREFIX}{envelope_id}");
+    let month = today.format("%Y-%m").to_string();
+    if state::get_value(db, &key).await?.as_deref() == Some(month.as_str()) {
+        return Ok(None);
+    }
+    state::set_value(db, &key, &month).await?;
+
+    let target = match env.user_id {
+        Some(user_id) if env.is_individual => AlertTarget::DirectMessage { user_id },
+        _ => AlertTarget::Channel,
+    };
+
+    Ok(Some(LowBalanceAlert {
+        envelope_id,
+        envelope_name: env.name,
+        balance: env.balance,
+        threshold,
+        target,
+        currency: setup::get_currency_format(db).await?,
+    }))
+}
+
+/// Checks an envelope after a change and returns a ping if it has overspent.
+///
+/// A ping fires when the envelope links a mention, its balance is below zero or this
+/// cycle's spending exceeds its allocation, and it has not already pinged this cycle.
+///
+/// # Errors
+/// Returns an error if the database query or state update fails.
+pub async fn check_overspend(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+) -> Result<Option<OverspendAlert>> {
+    check_overspend_on(db, envelope_id, calendar::today(db).await?).await
+}
+
+/// Runs [`check_overspend`] for each envelope, collecting the pings that fire.
+///
+/// # Errors
+/// Returns an error if any database query or state update fails.
+pub async fn check_overspends(
+    db: &DatabaseConnection,
+    envelope_ids: &[i64],
+) -> Result<Vec<OverspendAlert>> {
+    let mut alerts = Vec::new();
+    for &envelope_id in envelope_ids {
+        if let Some(alert) = check_overspend(db, envelope_id).await? {
+            alerts.push(alert);
+        }
+    }
+    Ok(alerts)
+}
+
+/// [`check_overspend`] with an explicit date, so deduplication can be tested.
+async fn check_overspend_on(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    today: NaiveDate,
+) -> Result<Option<OverspendAlert>> {
+    let Some(env) = envelope::get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+    else {
+        return Ok(None);
+    };
+    let Some(mention) = env.ping_mention else {
+        return Ok(None);
+    };
+
+    let cycle = calendar::current_cycle(db, today).await?;
+    let range = report::ReportRange::new(cycle.start, cycle.end)?;
+    let spent = report::get_spending_in_range(db, &range)
+        .await?
+        .get(&envelope_id)
+        .copied()
+        .unwrap_or(0.0);
+
+    let reason = if env.balance < 0.0 {
+        OverspendReason::BelowZero
+    } else if spent > env.allocation {
+        OverspendReason::OverBudget
+    } else {
+        return Ok(None);
+    };
+
+    let key = format!("{OVERSPEND_KEY_PREFIX}{envelope_id}");
+    let month = statement::month_label(&cycle);
+    if state::get_value(db, &key).await?.as_deref() == Some(month.as_str()) {
+        return Ok(None);
+    }
+    state::set_value(db, &key, &month).await?;
+
+    Ok(Some(OverspendAlert {
+        envelope_id,
+        envelope_name: env.name,
+        mention,
+        balance: env.balance,
+        allocation: env.allocation,
+        spent,
+        reason,
+        currency: setup::get_currency_format(db).await?,
+    }))
+}
+
+/// Checks an envelope's pace after a change and returns a warning if it should fire.
+///
+/// A warning fires while the budget month is still running (not on its last day), when
+/// the envelope's spending classifies as 🔴 under its pace thresholds (see
+/// [`report::get_pace_settings`]), and it has not already warned this cycle. Envelopes
+/// without an allocation or set to never warn stay quiet.
+///
+/// # Errors
+/// Returns an error if the database query or state update fails.
+pub async fn check_pace(db: &DatabaseConnection, envelope_id: i64) -> Result<Option<PaceAlert>> {
+    check_pace_on(db, envelope_id, calendar::today(db).await?).await
+}
+
+/// Runs [`check_pace`] for each envelope, collecting the warnings that fire.
+///
+/// # Errors
+/// Returns an error if any database query or state update fails.
+pub async fn check_paces(db: &DatabaseConnection, envelope_ids: &[i64]) -> Result<Vec<PaceAlert>> {
+    let mut alerts = Vec::new();
+    for &envelope_id in envelope_ids {
+        if let Some(alert) = check_pace(db, envelope_id).await? {
+            alerts.push(alert);
+        }
+    }
+    Ok(alerts)
+}
+
+/// [`check_pace`] with an explicit date, so deduplication can be tested.
+async fn check_pace_on(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    today: NaiveDate,
+) -> Result<Option<PaceAlert>> {
+    let Some(env) = envelope::get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted && env.allocation > 0.0)
+    else {
+        return Ok(None);
+    };
+    let thresholds = report::get_pace_settings(db)
+        .await?
+        .for_envelope(envelope_id);
+    if thresholds.is_never() {
+        return Ok(None);
+    }
+
+    let cycle = calendar::current_cycle(db, today).await?;
+    if today >= cycle.end {
+        return Ok(None);
+    }
+    // Same figures as the report's status: spent is what's gone from the allocation
+    // Days in month is small, precision loss negligible
+    #[allow(clippy::cast_precision_loss)]
+    let expected_percent = cycle.day_number(today) as f64 / cycle.days() as f64 * 100.0;
+    let spent_percent = (env.allocation - env.balance) / env.allocation * 100.0;
+    if thresholds.classify(spent_percent, expected_percent) != report::PaceStatus::WellOver {
+        return Ok(None);
+    }
+
+    let key = format!("{PACE_KEY_PREFIX}{envelope_id}");
+    let month = statement::month_label(&cycle);
+    if state::get_value(db, &key).await?.as_deref() == Some(month.as_str()) {
+        return Ok(None);
+    }
+    state::set_value(db, &key, &month).await?;
+
+    let target = match env.user_id {
+        Some(user_id) if env.is_individual => AlertTarget::DirectMessage { user_id },
+        _ => AlertTarget::Channel,
+    };
+    Ok(Some(PaceAlert {
+        envelope_id,
+        envelope_name: env.name,
+        spent_percent,
+        expected_percent,
+        target,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_low_balance_alert_fires_once_per_month() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let january = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+
+        // No threshold, no alert
+        create_test_transaction(&db, env.id, 20.0).await?;
+        assert!(check_low_balance_on(&db, env.id, january).await?.is_none());
+
+        // Above the threshold, no alert
+        envelope::set_alert_threshold(&db, env.id, Some(10.0)).await?;
+        assert!(check_low_balance_on(&db, env.id, january).await?.is_none());
+
+        // Dropping below fires once
+        create_test_transaction(&db, env.id, -15.0).await?;
+        let alert = check_low_balance_on(&db, env.id, january).await?.unwrap();
+        assert_eq!(alert.balance, 5.0);
+        assert_eq!(alert.threshold, 10.0);
+        assert_eq!(alert.target, AlertTarget::Channel);
+        assert!(alert.message().contains("$5.00"));
+        assert!(
+            LowBalanceAlert {
+                currency: Currency::from_code("EUR"),
+                ..alert.clone()
+            }
+            .message()
+            .contains("€5.00")
+        );
+        let notification = alert.notification();
+        assert_eq!(notification.audience, Audience::Household);
+        assert_eq!(notification.body, alert.message());
+        assert!(check_low_balance_on(&db, env.id, january).await?.is_none());
+
+        // A new month alerts again
+        let february = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        assert!(check_low_balance_on(&db, env.id, february).await?.is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_individual_envelope_alerts_by_dm() -> Result<()> {
+        let db = setup_test_db().await?;
+        let env = create_custom_envelope(
+            &db,
+            "game",
+            Some("user1".to_string()),
+            "quality_of_life",
+            80.0,
+            true,
+            false,
+        )
+        .await?;
+        envelope::set_alert_threshold(&db, env.id, Some(25.0)).await?;
+
+        let alerts = check_low_balances(&db, &[env.id, 999]).await?;
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(
+            alerts[0].target,
+            AlertTarget::DirectMessage {
+                user_id: "user1".to_string()
+            }
+        );
+        assert_eq!(
+            alerts[0].notification().audience,
+            Audience::Member {
+                user_id: "user1".to_string()
+            }
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_overspend_pings_once_per_cycle() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let today = calendar::today(&db).await?;
+
+        // Overdrawn without a linked mention stays quiet
+        envelope::update_envelope_balance_atomic(&db, env.id, -10.0).await?;
+        assert!(check_overspend_on(&db, env.id, today).await?.is_none());
+
+        // Linking a mention pings once for the negative balance
+        envelope::set_ping_mention(&db, env.id, Some("<@&42>")).await?;
+        let alert = check_overspend_on(&db, env.id, today).await?.unwrap();
+        assert_eq!(alert.reason, OverspendReason::BelowZero);
+        assert_eq!(alert.mention, "<@&42>");
+        assert!(alert.message().starts_with("🚨 <@&42>"));
+        let notification = alert.notification();
+        assert_eq!(notification.mention.as_deref(), Some("<@&42>"));
+        assert!(!notification.body.contains("<@&42>"));
+        assert!(check_overspend_on(&db, env.id, today).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_overspend_over_allocation() -> Result<()> {
+        let db = setup_test_db().await?;
+        let env = create_custom_envelope(&db, "fun", None, "necessary", 50.0, false, false).await?;
+        envelope::set_ping_mention(&db, env.id, Some("<@!7>")).await?;
+        let today = calendar::today(&db).await?;
+
+        // Spending within the allocation is fine, even with money left over from a top-up
+        create_test_transaction(&db, env.id, 100.0).await?;
+        create_test_transaction(&db, env.id, -40.0).await?;
+        assert!(check_overspend_on(&db, env.id, today).await?.is_none());
+
+        create_test_transaction(&db, env.id, -20.0).await?;
+        let alert = check_overspend_on(&db, env.id, today).await?.unwrap();
+        assert_eq!(alert.reason, OverspendReason::OverBudget);
+        assert_eq!(alert.mention, "<@7>");
+        assert_eq!(alert.spent, 60.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pace_warning_respects_envelope_thresholds() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        // Day 10 of a 31-day month: about a third of the month has gone
+        let today = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+        envelope::update_envelope_balance_atomic(&db, env.id, -env.balance).await?;
+        envelope::update_envelope_balance_atomic(&db, env.id, env.allocation * 0.4).await?;
+
+        // 60% spent is 🔴 under the defaults, and warns once per cycle
+        let alert = check_pace_on(&db, env.id, today).await?.unwrap();
+        assert!((alert.spent_percent - 60.0).abs() < 1e-9);
+        assert_eq!(alert.target, AlertTarget::Channel);
+        assert!(alert.message().contains("60%"));
+        assert!(check_pace_on(&db, env.id, today).await?.is_none());
+
+        // A lenient override on the envelope keeps it quiet next month
+        let february = NaiveDate::from_ymd_opt(2025, 2, 10).unwrap();
+        let lenient = report::PaceThresholds::new(30.0, 50.0)?;
+        report::set_pace_thresholds(&db, Some(env.id), Some(lenient)).await?;
+        assert!(check_pace_on(&db, env.id, february).await?.is_none());
+
+        // So does never warning, even far ahead of pace
+        let march = NaiveDate::from_ymd_opt(2025, 3, 2).unwrap();
+        report::set_pace_thresholds(&db, Some(env.id), Some(report::PaceThresholds::NEVER)).await?;
+        assert!(
+            envelope::get_envelope_by_id(&db, env.id)
+                .await?
+                .unwrap()
+                .pace_never_warn
+        );
+        assert!(check_pace_on(&db, env.id, march).await?.is_none());
+
+        // The household thresholds apply again once the override is removed
+        report::set_pace_thresholds(&db, Some(env.id), None).await?;
+        assert!(check_pace_on(&db, env.id, march).await?.is_some());
+
+        // No warning on the month's last day; the overspend ping covers the month's end
+        let april_end = NaiveDate::from_ymd_opt(2025, 4, 30).unwrap();
+        assert!(check_pace_on(&db, env.id, april_end).await?.is_none());
+        Ok(())
+    }
+}
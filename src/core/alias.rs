@@ -0,0 +1,168 @@
+//! Command aliases - Short names for prefix commands such as `!s` for `!spend`.
+//!
+//! A few aliases are built in ([`BUILTIN_ALIASES`]) and registered with the commands at
+//! startup. Each server can add its own with `/alias set`; they are stored in the
+//! `system_state` table per guild and resolved by [`resolve_alias`] when a prefix
+//! message doesn't name a command. Checking that an alias points at a real command is
+//! left to the bot layer, which knows the registered commands.
+
+use crate::{
+    core::state,
+    errors::{Error, Result},
+};
+use sea_orm::ConnectionTrait;
+
+/// Aliases every server has, as `(alias, command)` pairs.
+pub const BUILTIN_ALIASES: &[(&str, &str)] =
+    &[("s", "spend"), ("p", "use_product"), ("r", "report")];
+
+/// Longest alias that can be saved.
+pub const MAX_ALIAS_LENGTH: usize = 16;
+
+/// `system_state` key prefix for custom aliases, followed by `<guild_id>:<alias>`.
+const ALIAS_KEY_PREFIX: &str = "alias:";
+
+/// Key prefix for one guild's aliases.
+fn guild_prefix(guild_id: &str) -> String {
+    format!("{ALIAS_KEY_PREFIX}{guild_id}:")
+}
+
+/// Validates an alias and returns it in canonical (lowercase) form.
+///
+/// # Errors
+/// Returns `Error::Config` if the alias is empty, too long, contains anything but
+/// letters, digits, `-`, and `_`, or is one of the built-in aliases.
+pub fn normalize_alias(alias: &str) -> Result<String> {
+    let alias = alias.trim().to_lowercase();
+    if alias.is_empty()
+        || alias.len() > MAX_ALIAS_LENGTH
+        || !alias
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(Error::Config {
+            message: format!(
+                "Aliases must be 1-{MAX_ALIAS_LENGTH} letters, digits, '-' or '_' (got '{alias}')"
+            ),
+        });
+    }
+    if BUILTIN_ALIASES.iter().any(|(builtin, _)| *builtin == alias) {
+        return Err(Error::Config {
+            message: format!("'{alias}' is a built-in alias and can't be changed"),
+        });
+    }
+    Ok(alias)
+}
+
+/// Saves a custom alias for a guild, replacing any previous target.
+///
+/// # Errors
+/// Returns `Error::Config` if the alias is invalid (see [`normalize_alias`]), or an
+/// error if the database write fails.
+pub async fn set_alias<C>(db: &C, guild_id: &str, alias: &str, command: &str) -> Result<String>
+where
+    C: ConnectionTrait,
+{
+    let alias = normalize_alias(alias)?;
+    state::set_value(db, &format!("{}{alias}", guild_prefix(guild_id)), command).await?;
+    Ok(alias)
+}
+
+/// Removes a custom alias from a guild.
+///
+/// # Returns
+/// `true` if the alias existed
+///
+/// # Errors
+/// Returns an error if a database query or delete fails.
+pub async fn remove_alias<C>(db: &C, guild_id: &str, alias: &str) -> Result<bool>
+where
+    C: ConnectionTrait,
+{
+    let key = format!("{}{}", guild_prefix(guild_id), alias.trim().to_lowercase());
+    if state::get_value(db, &key).await?.is_none() {
+        return Ok(false);
+    }
+    state::delete_value(db, &key).await?;
+    Ok(true)
+}
+
+/// Lists a guild's custom aliases as `(alias, command)` pairs, sorted by alias.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_aliases<C>(db: &C, guild_id: &str) -> Result<Vec<(String, String)>>
+where
+    C: ConnectionTrait,
+{
+    let prefix = guild_prefix(guild_id);
+    let mut aliases: Vec<(String, String)> = state::get_values_with_prefix(db, &prefix)
+        .await?
+        .into_iter()
+        .filter_map(|(key, command)| Some((key.strip_prefix(&prefix)?.to_string(), command)))
+        .collect();
+    aliases.sort();
+    Ok(aliases)
+}
+
+/// Looks up the command an alias stands for in a guild, built-in aliases first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn resolve_alias<C>(db: &C, guild_id: &str, name: &str) -> Result<Option<String>>
+where
+    C: ConnectionTrait,
+{
+    let name = name.trim().to_lowercase();
+    if let Some((_, command)) = BUILTIN_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return Ok(Some((*command).to_string()));
+    }
+    state::get_value(db, &format!("{}{name}", guild_prefix(guild_id))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_normalize_alias() -> Result<()> {
+        assert_eq!(normalize_alias(" Cof ")?, "cof");
+        assert!(normalize_alias("").is_err());
+        assert!(normalize_alias("two words").is_err());
+        assert!(normalize_alias("s").is_err());
+        assert!(normalize_alias(&"x".repeat(MAX_ALIAS_LENGTH + 1)).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_aliases_per_guild() -> Result<()> {
+        let db = setup_test_db().await?;
+        assert_eq!(
+            resolve_alias(&db, "1", "S").await?,
+            Some("spend".to_string())
+        );
+        assert_eq!(resolve_alias(&db, "1", "cof").await?, None);
+
+        set_alias(&db, "1", "Cof", "favorite_use").await?;
+        set_alias(&db, "1", "bal", "envelopes").await?;
+        assert_eq!(
+            resolve_alias(&db, "1", "cof").await?,
+            Some("favorite_use".to_string())
+        );
+        // Other guilds keep their own table
+        assert_eq!(resolve_alias(&db, "2", "cof").await?, None);
+        assert_eq!(
+            get_aliases(&db, "1").await?,
+            vec![
+                ("bal".to_string(), "envelopes".to_string()),
+                ("cof".to_string(), "favorite_use".to_string()),
+            ]
+        );
+
+        assert!(remove_alias(&db, "1", "cof").await?);
+        assert!(!remove_alias(&db, "1", "cof").await?);
+        assert_eq!(resolve_alias(&db, "1", "cof").await?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+//! Funding rules - Envelopes funded only once another envelope is full.
+//!
+//! A rule such as "fund Vacation only when Emergency Fund is at 100%" is stored in the
+//! `system_state` table under the dependent envelope's ID. An envelope is full when its
+//! balance reaches its savings goal, or its monthly allocation if it has no goal.
+//!
+//! [`apply_funding_rules`] is the allocation planner shared by `/addfunds distribute`
+//! and the monthly update: money planned for an envelope whose prerequisite isn't full
+//! goes to the prerequisite instead, up to what it needs to be full, and only the rest
+//! stays with the envelope. Rules can chain (Vacation after Emergency Fund after Rent),
+//! but never loop.
+
+use crate::{
+    core::state,
+    entities::envelope,
+    errors::{Error, Result},
+};
+use sea_orm::ConnectionTrait;
+use std::{collections::HashMap, hash::BuildHasher};
+
+/// `system_state` key prefix for funding rules, followed by the dependent envelope's ID.
+/// The value is the ID of the envelope that must be full first.
+const FUNDING_RULE_PREFIX: &str = "funding_rule:";
+
+/// Funding planned for an envelope that went to its prerequisite instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldFunding {
+    /// Name of the envelope whose funding was held back
+    pub envelope_name: String,
+    /// Name of the envelope that wasn't full yet and received the funding
+    pub requires_name: String,
+    /// Amount moved to the prerequisite
+    pub amount: f64,
+}
+
+/// Loads every funding rule, mapping each dependent envelope's ID to the ID of the
+/// envelope that must be full first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_funding_rules<C>(db: &C) -> Result<HashMap<i64, i64>>
+where
+    C: ConnectionTrait,
+{
+    Ok(state::get_values_with_prefix(db, FUNDING_RULE_PREFIX)
+        .await?
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let envelope_id = key.strip_prefix(FUNDING_RULE_PREFIX)?.parse().ok()?;
+            Some((envelope_id, value.parse().ok()?))
+        })
+        .collect())
+}
+
+/// Makes `envelope_id` wait for `requires_id` to be full before it is funded, or removes
+/// its rule when `requires_id` is `None`. An envelope has at most one rule; setting a new
+/// one replaces it.
+///
+/// # Errors
+/// Returns `Error::Config` if the envelope would wait for itself, directly or through a
+/// chain of rules, or an error if the database query or write fails.
+pub async fn set_funding_rule<C>(db: &C, envelope_id: i64, requires_id: Option<i64>) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let key = format!("{FUNDING_RULE_PREFIX}{envelope_id}");
+    let Some(requires_id) = requires_id else {
+        return state::delete_value(db, &key).await;
+    };
+
+    let rules = get_funding_rules(db).await?;
+    let mut next = Some(requires_id);
+    for _ in 0..=rules.len() {
+        match next {
+            Some(id) if id == envelope_id => {
+                return Err(Error::Config {
+                    message:
+                        "An envelope can't wait for itself to be full, even through other rules"
+                            .to_string(),
+                });
+            }
+            Some(id) => next = rules.get(&id).copied(),
+            None => break,
+        }
+    }
+    state::set_value(db, &key, &requires_id.to_string()).await
+}
+
+/// Points the funding rules of a merged envelope at the envelope it was merged into.
+///
+/// Rules waiting for `source_id` wait for `target_id` instead, and the source's own rule
+/// moves to the target unless the target already has one. Rules that would make the
+/// target wait for itself, directly or through a chain, are dropped.
+///
+/// # Errors
+/// Returns an error if a database query or write fails.
+pub async fn move_funding_rules<C>(db: &C, source_id: i64, target_id: i64) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let rules = get_funding_rules(db).await?;
+    let mut moved = Vec::new();
+    for (&envelope_id, &requires_id) in &rules {
+        if envelope_id == source_id {
+            state::delete_value(db, &format!("{FUNDING_RULE_PREFIX}{source_id}")).await?;
+            if !rules.contains_key(&target_id) {
+                moved.push((target_id, requires_id));
+            }
+        } else if requires_id == source_id {
+            state::delete_value(db, &format!("{FUNDING_RULE_PREFIX}{envelope_id}")).await?;
+            moved.push((envelope_id, target_id));
+        }
+    }
+
+    for (envelope_id, requires_id) in moved {
+        match set_funding_rule(db, envelope_id, Some(requires_id)).await {
+            Ok(()) | Err(Error::Config { .. }) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Balance at which an envelope counts as full: its savings goal, or its monthly
+/// allocation when it has no goal.
+#[must_use]
+pub fn funding_target(env: &envelope::Model) -> f64 {
+    env.goal.unwrap_or(env.allocation)
+}
+
+/// Whether an envelope is at 100% of its [`funding_target`].
+#[must_use]
+pub fn is_full(env: &envelope::Model) -> bool {
+    env.balance >= funding_target(env)
+}
+
+/// Moves planned funding away from envelopes whose prerequisite won't be full, in place.
+///
+/// `planned` pairs each envelope, with its balance before the funding, with the amount
+/// planned for it. A prerequisite counts as full once its balance plus its own planned
+/// amount reaches its [`funding_target`]; until then, the dependent envelope's amount
+/// tops it up and only the rest stays with the dependent. Prerequisites come before the
+/// envelopes waiting for them, so a chain fills from the front. Rules whose envelopes
+/// aren't in `planned` are skipped.
+///
+/// # Returns
+/// The funding moved to a prerequisite, in the order it was moved.
+pub fn apply_funding_rules<S: BuildHasher>(
+    planned: &mut [(envelope::Model, f64)],
+    rules: &HashMap<i64, i64, S>,
+) -> Vec<HeldFunding> {
+    let index: HashMap<i64, usize> = planned
+        .iter()
+        .enumerate()
+        .map(|(position, (env, _))| (env.id, position))
+        .collect();
+
+    // Shallowest rules first, so each prerequisite's own rule is settled before it is
+    // checked
+    let depth = |envelope_id: i64| {
+        let mut depth = 0;
+        let mut next = rules.get(&envelope_id);
+        while let Some(id) = next
+            && depth <= rules.len()
+        {
+            depth += 1;
+            next = rules.get(id);
+        }
+        depth
+    };
+    let mut ordered: Vec<(i64, i64)> = rules
+        .iter()
+        .map(|(&id, &requires)| (id, requires))
+        .collect();
+    ordered.sort_by_key(|&(id, _)| (depth(id), id));
+
+    let mut held = Vec::new();
+    for (envelope_id, requires_id) in ordered {
+        let (Some(&dependent), Some(&prerequisite)) =
+            (index.get(&envelope_id), index.get(&requires_id))
+        else {
+            continue;
+        };
+        let (requires, requires_amount) = &planned[prerequisite];
+        let needed = funding_target(requires) - requires.balance - requires_amount;
+        let moved = (planned[dependent].1.min(needed) * 100.0).round() / 100.0;
+        if moved <= 0.0 {
+            continue;
+        }
+
+        planned[dependent].1 = ((planned[dependent].1 - moved) * 100.0).round() / 100.0;
+        planned[prerequisite].1 = ((planned[prerequisite].1 + moved) * 100.0).round() / 100.0;
+        held.push(HeldFunding {
+            envelope_name: planned[dependent].0.name.clone(),
+            requires_name: planned[prerequisite].0.name.clone(),
+            amount: moved,
+        });
+    }
+    held
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_apply_funding_rules() -> Result<()> {
+        let db = setup_test_db().await?;
+        let mut emergency =
+            create_custom_envelope(&db, "Emergency", None, "savings", 100.0, false, true).await?;
+        emergency.goal = Some(1000.0);
+        emergency.balance = 900.0;
+        let vacation =
+            create_custom_envelope(&db, "Vacation", None, "savings", 200.0, false, true).await?;
+        let food =
+            create_custom_envelope(&db, "Food", None, "necessary", 300.0, false, false).await?;
+
+        set_funding_rule(&db, vacation.id, Some(emergency.id)).await?;
+        let rules = get_funding_rules(&db).await?;
+
+        // Emergency needs 50 past its own share, taken from Vacation's
+        let mut planned = vec![
+            (emergency.clone(), 50.0),
+            (vacation.clone(), 200.0),
+            (food.clone(), 300.0),
+        ];
+        let held = apply_funding_rules(&mut planned, &rules);
+        let amounts: Vec<f64> = planned.iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![100.0, 150.0, 300.0]);
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].requires_name, "Emergency");
+        assert_eq!(held[0].amount, 50.0);
+
+        // A full prerequisite holds nothing back
+        emergency.balance = 1000.0;
+        let mut planned = vec![(emergency.clone(), 0.0), (vacation.clone(), 200.0)];
+        assert!(apply_funding_rules(&mut planned, &rules).is_empty());
+        assert_eq!(planned[1].1, 200.0);
+
+        // Rules can't loop back to the envelope
+        set_funding_rule(&db, emergency.id, Some(food.id)).await?;
+        assert!(matches!(
+            set_funding_rule(&db, food.id, Some(vacation.id)).await,
+            Err(Error::Config { .. })
+        ));
+        assert!(set_funding_rule(&db, food.id, Some(food.id)).await.is_err());
+
+        set_funding_rule(&db, vacation.id, None).await?;
+        set_funding_rule(&db, emergency.id, None).await?;
+        assert!(get_funding_rules(&db).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_move_funding_rules() -> Result<()> {
+        let db = setup_test_db().await?;
+        let emergency = create_test_envelope(&db, "Emergency").await?;
+        let vacation = create_test_envelope(&db, "Vacation").await?;
+        let travel = create_test_envelope(&db, "Travel").await?;
+        let fun = create_test_envelope(&db, "Fun").await?;
+
+        // Merging Vacation into Travel moves its own rule and the rules waiting on it
+        set_funding_rule(&db, vacation.id, Some(emergency.id)).await?;
+        set_funding_rule(&db, fun.id, Some(vacation.id)).await?;
+        move_funding_rules(&db, vacation.id, travel.id).await?;
+        let rules = get_funding_rules(&db).await?;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules.get(&travel.id), Some(&emergency.id));
+        assert_eq!(rules.get(&fun.id), Some(&travel.id));
+
+        // A rule that would make the target wait for itself is dropped
+        move_funding_rules(&db, fun.id, travel.id).await?;
+        let rules = get_funding_rules(&db).await?;
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules.get(&travel.id), Some(&emergency.id));
+        Ok(())
+    }
+}
@@ -0,0 +1,468 @@
+//! Amount expressions - Arithmetic in amounts entered with `/spend`.
+//!
+//! Amounts may be written as simple expressions such as `12.99+4.50*2` or `45/3`, which
+//! is handy for splitting a shared bill. [`parse_amount`] evaluates them with a small
+//! recursive-descent parser supporting `+`, `-`, `*`, `/`, parentheses, and an optional
+//! `$` before each number. Nothing else is accepted, input length and nesting depth are
+//! capped, and the result must be a finite amount within [`MAX_AMOUNT`], rounded to
+//! cents.
+//!
+//! Households that write `12,50` set the [`DecimalSeparator`] to a comma; amounts typed
+//! into commands are read with [`parse_household_amount`], which turns the household's
+//! decimal and thousands separators into the `12.50` form the parser expects.
+//!
+//! [`find_amount`] instead picks an amount out of free text, such as a forwarded bank
+//! alert, for the "Log as expense" message command.
+
+use crate::{
+    core::state,
+    errors::{Error, Result},
+};
+use sea_orm::ConnectionTrait;
+
+/// Largest amount (in either direction) an expression may evaluate to.
+pub const MAX_AMOUNT: f64 = 1_000_000_000.0;
+
+/// Longest expression accepted, in characters.
+const MAX_LENGTH: usize = 64;
+
+/// Deepest nesting of parentheses and signs accepted.
+const MAX_DEPTH: usize = 16;
+
+/// Most significant digits in one number, so every literal is exact in an `f64`.
+const MAX_DIGITS: usize = 15;
+
+/// `system_state` key holding the household's decimal separator.
+const DECIMAL_SEPARATOR_KEY: &str = "decimal_separator";
+
+/// How the household writes cents: `12.50` or `12,50`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecimalSeparator {
+    /// `1,234.50`: a point before the cents, commas between thousands
+    #[default]
+    Point,
+    /// `1.234,50`: a comma before the cents, points between thousands
+    Comma,
+}
+
+impl DecimalSeparator {
+    /// Every separator, in the order they're listed.
+    pub const ALL: [Self; 2] = [Self::Point, Self::Comma];
+
+    /// Name of the separator as typed in `/config set decimal_separator`.
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::Point => "point",
+            Self::Comma => "comma",
+        }
+    }
+
+    /// Looks up a separator by its [`key`](Self::key), ignoring case.
+    #[must_use]
+    pub fn from_key(key: &str) -> Option<Self> {
+        let key = key.trim();
+        Self::ALL
+            .into_iter()
+            .find(|separator| separator.key().eq_ignore_ascii_case(key))
+    }
+
+    /// The decimal separator and the thousands separator that goes with it.
+    const fn marks(self) -> (char, char) {
+        match self {
+            Self::Point => ('.', ','),
+            Self::Comma => (',', '.'),
+        }
+    }
+}
+
+/// Loads the household's decimal separator, defaulting to a point.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_decimal_separator<C>(db: &C) -> Result<DecimalSeparator>
+where
+    C: ConnectionTrait,
+{
+    Ok(state::get_value(db, DECIMAL_SEPARATOR_KEY)
+        .await?
+        .and_then(|value| DecimalSeparator::from_key(&value))
+        .unwrap_or_default())
+}
+
+/// Saves the household's decimal separator.
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_decimal_separator<C>(db: &C, separator: DecimalSeparator) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    state::set_value(db, DECIMAL_SEPARATOR_KEY, separator.key()).await
+}
+
+/// Evaluates an amount typed by a member, written the household's way.
+///
+/// # Errors
+/// Returns an error if the separator can't be loaded, or `Error::Config` as
+/// [`parse_localized_amount`] does.
+pub async fn parse_household_amount<C>(db: &C, input: &str) -> Result<f64>
+where
+    C: ConnectionTrait,
+{
+    parse_localized_amount(input, get_decimal_separator(db).await?)
+}
+
+/// Evaluates an amount expression written with `separator` before the cents.
+///
+/// A thousands separator counts only between a digit and a group of exactly three
+/// digits, so `1.234,50` is read as `1234.50` with a comma separator. With a comma
+/// separator a point before anything else still marks the cents, since amounts the bot
+/// fills in are written as `12.50`; with a point separator any other comma is an error
+/// rather than a guess.
+///
+/// # Errors
+/// Returns `Error::Config` as [`parse_amount`] does.
+pub fn parse_localized_amount(input: &str, separator: DecimalSeparator) -> Result<f64> {
+    let (decimal, thousands) = separator.marks();
+    let chars: Vec<char> = input.chars().collect();
+    let normalized: String = chars
+        .iter()
+        .enumerate()
+        .filter(|&(pos, c)| *c != thousands || !separates_thousands(&chars, pos))
+        .map(|(_, c)| if *c == decimal { '.' } else { *c })
+        .collect();
+
+    let comma_typo = separator == DecimalSeparator::Point && normalized.contains(',');
+    parse_amount(&normalized).map_err(|e| match e {
+        Error::Config { message } if comma_typo => Error::Config {
+            message: format!(
+                "{message} (write cents after a point, like 12.50, or ask an admin to \
+                 `/config set decimal_separator comma`)"
+            ),
+        },
+        e => e,
+    })
+}
+
+/// Whether the mark at `pos` sits between a digit and a group of exactly three digits.
+fn separates_thousands(chars: &[char], pos: usize) -> bool {
+    let digit_at = |i: usize| chars.get(i).is_some_and(char::is_ascii_digit);
+    pos > 0 && digit_at(pos - 1) && (pos + 1..=pos + 3).all(digit_at) && !digit_at(pos + 4)
+}
+
+/// Evaluates an amount expression and rounds the result to cents.
+///
+/// Plain numbers such as `12.50` are the simplest expressions. Operators follow the
+/// usual precedence: `*` and `/` before `+` and `-`.
+///
+/// # Errors
+/// Returns `Error::Config` if the expression is empty, too long or too deeply nested,
+/// contains anything but numbers, operators, parentheses, `$`, and spaces, divides by
+/// zero, or evaluates to something that isn't a finite amount within [`MAX_AMOUNT`].
+pub fn parse_amount(input: &str) -> Result<f64> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(invalid("the amount is empty"));
+    }
+    if input.chars().count() > MAX_LENGTH {
+        return Err(invalid(&format!(
+            "expressions can be at most {MAX_LENGTH} characters"
+        )));
+    }
+
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expression()?;
+    if let Some(c) = parser.peek() {
+        return Err(invalid(&format!("unexpected '{c}'")));
+    }
+    if !value.is_finite() || value.abs() > MAX_AMOUNT {
+        return Err(invalid(&format!(
+            "the result must be within {MAX_AMOUNT:.0}"
+        )));
+    }
+    Ok((value * 100.0).round() / 100.0)
+}
+
+/// Finds the amount a message most likely refers to.
+///
+/// The first number written with `$` wins; otherwise the first number with exactly two
+/// decimals, so card numbers, dates, and times are skipped. Commas are read as thousands
+/// separators. Returns `None` if no positive amount within [`MAX_AMOUNT`] is found.
+#[must_use]
+pub fn find_amount(text: &str) -> Option<f64> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut with_cents = None;
+    let mut pos = 0;
+    while pos < chars.len() {
+        // A number starts at a digit that doesn't continue a word or another number
+        let starts_number = chars[pos].is_ascii_digit()
+            && (pos == 0 || !(chars[pos - 1].is_alphanumeric() || chars[pos - 1] == '.'));
+        if !starts_number {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        while chars
+            .get(pos)
+            .is_some_and(|c| c.is_ascii_digit() || *c == ',' || *c == '.')
+        {
+            pos += 1;
+        }
+        let literal: String = chars[start..pos].iter().collect();
+        let literal = literal.trim_end_matches([',', '.']);
+        let Some(value) = literal
+            .replace(',', "")
+            .parse::<f64>()
+            .ok()
+            .filter(|value| *value > 0.0 && *value <= MAX_AMOUNT)
+        else {
+            continue;
+        };
+        let value = (value * 100.0).round() / 100.0;
+
+        let has_symbol = chars[..start]
+            .iter()
+            .rev()
+            .find(|c| !c.is_whitespace())
+            .is_some_and(|c| *c == '$');
+        if has_symbol {
+            return Some(value);
+        }
+        let has_cents = literal
+            .rsplit_once('.')
+            .is_some_and(|(_, cents)| cents.len() == 2);
+        if has_cents && with_cents.is_none() {
+            with_cents = Some(value);
+        }
+    }
+    with_cents
+}
+
+/// Wraps a parse failure in the error shown to the user.
+fn invalid(reason: &str) -> Error {
+    Error::Config {
+        message: format!("Invalid amount: {reason}"),
+    }
+}
+
+/// Recursive-descent parser over the expression's characters.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Returns the next character, skipping whitespace between tokens.
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Consumes `c` if it is the next character.
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// `expression := term (('+' | '-') term)*`
+    fn expression(&mut self) -> Result<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+            check_finite(value)?;
+        }
+    }
+
+    /// `term := factor (('*' | '/') factor)*`
+    fn term(&mut self) -> Result<f64> {
+        let mut value = self.factor()?;
+        loop {
+            if self.eat('*') {
+                value *= self.factor()?;
+            } else if self.eat('/') {
+                let divisor = self.factor()?;
+                if divisor == 0.0 {
+                    return Err(invalid("division by zero"));
+                }
+                value /= divisor;
+            } else {
+                return Ok(value);
+            }
+            check_finite(value)?;
+        }
+    }
+
+    /// `factor := ('+' | '-') factor | '(' expression ')' | number`
+    fn factor(&mut self) -> Result<f64> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(invalid("the expression is nested too deeply"));
+        }
+        let value = if self.eat('-') {
+            -self.factor()?
+        } else if self.eat('+') {
+            self.factor()?
+        } else if self.eat('(') {
+            let value = self.expression()?;
+            if !self.eat(')') {
+                return Err(invalid("missing ')'"));
+            }
+            value
+        } else {
+            self.number()?
+        };
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    /// `number := '$'? digits ('.' digits)?`
+    fn number(&mut self) -> Result<f64> {
+        self.eat('$');
+        self.skip_whitespace();
+        // Digits of one number can't be separated by whitespace
+        let start = self.pos;
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+        {
+            self.pos += 1;
+        }
+        let literal: String = self.chars[start..self.pos].iter().collect();
+        if literal.is_empty() {
+            return Err(self.peek().map_or_else(
+                || invalid("the expression ends early"),
+                |c| invalid(&format!("unexpected '{c}'")),
+            ));
+        }
+        if literal.chars().filter(char::is_ascii_digit).count() > MAX_DIGITS {
+            return Err(invalid(&format!(
+                "numbers can have at most {MAX_DIGITS} digits"
+            )));
+        }
+        literal
+            .parse()
+            .map_err(|_| invalid(&format!("'{literal}' is not a number")))
+    }
+}
+
+/// Rejects intermediate results that overflowed.
+fn check_finite(value: f64) -> Result<()> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(invalid("the result is too large"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+
+    #[test]
+    fn test_parse_amount_expressions() -> Result<()> {
+        assert_eq!(parse_amount("12.50")?, 12.5);
+        assert_eq!(parse_amount("$12.99 + $4.50 * 2")?, 21.99);
+        assert_eq!(parse_amount("45/3")?, 15.0);
+        assert_eq!(parse_amount("(10+5)*2-1")?, 29.0);
+        assert_eq!(parse_amount("100/3")?, 33.33);
+        assert_eq!(parse_amount("-(5-8)")?, 3.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_amount_rejects_invalid_input() {
+        for input in [
+            "",
+            "abc",
+            "12..5",
+            "5/0",
+            "5/(2-2)",
+            "(5+2",
+            "5+",
+            "5 5",
+            "1e5",
+            "9999999999",
+            "1234567890123456",
+            "((((((((((((((((((1))))))))))))))))))",
+        ] {
+            assert!(
+                matches!(parse_amount(input), Err(Error::Config { .. })),
+                "{input:?} should be rejected"
+            );
+        }
+        assert!(parse_amount(&"1+".repeat(40)).is_err());
+    }
+
+    #[test]
+    fn test_parse_localized_amount() -> Result<()> {
+        let comma = DecimalSeparator::Comma;
+        assert_eq!(parse_localized_amount("12,50", comma)?, 12.5);
+        assert_eq!(parse_localized_amount("1.234,50", comma)?, 1234.5);
+        assert_eq!(parse_localized_amount("1.234.567", comma)?, 1_234_567.0);
+        assert_eq!(parse_localized_amount("12,99 + 4,50*2", comma)?, 21.99);
+        assert_eq!(parse_localized_amount("12.50", comma)?, 12.5);
+        assert!(parse_localized_amount("1,234.50", comma).is_err());
+
+        let point = DecimalSeparator::Point;
+        assert_eq!(parse_localized_amount("1,234.50", point)?, 1234.5);
+        assert_eq!(parse_localized_amount("12,500", point)?, 12500.0);
+        assert_eq!(parse_localized_amount("12.50", point)?, 12.5);
+        // Never silently read as 1250
+        assert!(matches!(
+            parse_localized_amount("12,50", point),
+            Err(Error::Config { message }) if message.contains("decimal_separator")
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_decimal_separator_setting() -> Result<()> {
+        let db = crate::test_utils::setup_test_db().await?;
+        assert_eq!(get_decimal_separator(&db).await?, DecimalSeparator::Point);
+        set_decimal_separator(&db, DecimalSeparator::Comma).await?;
+        assert_eq!(parse_household_amount(&db, "3,75").await?, 3.75);
+        assert_eq!(
+            DecimalSeparator::from_key("Comma"),
+            Some(DecimalSeparator::Comma)
+        );
+        assert_eq!(DecimalSeparator::from_key("dot"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_amount() {
+        assert_eq!(
+            find_amount("Card x1234: 10/17 purchase of $1,234.50 at STORE"),
+            Some(1234.5)
+        );
+        assert_eq!(find_amount("You spent 42.17 at Cafe on 10/17"), Some(42.17));
+        assert_eq!(find_amount("Paid 3.99 then $ 20 tip."), Some(20.0));
+        assert_eq!(find_amount("Order 55512 shipped at 10.5"), None);
+        assert_eq!(find_amount("Balance: $0.00"), None);
+        assert_eq!(find_amount("no amounts here"), None);
+    }
+}
@@ -0,0 +1,181 @@
+//! Two-person approval business logic - Large spends held for another member.
+//!
+//! When the household sets a large transaction threshold with [`set_threshold`], a spend
+//! above it is held with [`hold_spend`] instead of being committed. Holds are wishlist
+//! purchases that any member other than the one who made them can answer through
+//! [`wishlist::respond_to_purchase`]: approval commits the spend as the requester,
+//! declining just closes the hold. Holds left unanswered for [`HOLD_TTL_HOURS`] hours
+//! expire like any other proposal.
+//!
+//! Holds live in the wishlist's `pending_purchases` table rather than a table of their
+//! own: a hold is a proposal without a named approver (`approver_id` is `NULL`), so it
+//! shares the Approve/Decline buttons, the expiry sweep, and the approval path that
+//! re-checks funds and limits before committing.
+//!
+//! The threshold is enforced on every spend, however it is made: a spend above it that
+//! wasn't approved fails with `Error::ApprovalRequired` (see
+//! [`crate::core::transaction::create_transaction`]).
+
+use crate::{
+    core::{state, wishlist},
+    entities::pending_purchase,
+    errors::{Error, Result},
+};
+use chrono::Utc;
+use sea_orm::{Set, prelude::*};
+
+/// `system_state` key holding the large transaction threshold.
+const THRESHOLD_KEY: &str = "large_transaction_threshold";
+
+/// Hours a hold stays open before it expires.
+pub const HOLD_TTL_HOURS: i64 = 24;
+
+/// Gets the amount above which spends need another member's approval, or `None` if
+/// every spend is committed straight away.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_threshold<C>(db: &C) -> Result<Option<f64>>
+where
+    C: ConnectionTrait,
+{
+    Ok(state::get_value(db, THRESHOLD_KEY)
+        .await?
+        .and_then(|value| value.parse::<f64>().ok())
+        .filter(|threshold| threshold.is_finite() && *threshold > 0.0))
+}
+
+/// Sets the large transaction threshold; `None` turns approvals off.
+///
+/// # Errors
+/// Returns an error if:
+/// - The threshold is not positive and finite (`Error::InvalidAmount`)
+/// - The database write fails
+pub async fn set_threshold<C>(db: &C, threshold: Option<f64>) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    match threshold {
+        Some(amount) if !amount.is_finite() || amount <= 0.0 => {
+            Err(Error::InvalidAmount { amount })
+        }
+        Some(amount) => state::set_value(db, THRESHOLD_KEY, &amount.to_string()).await,
+        None => state::delete_value(db, THRESHOLD_KEY).await,
+    }
+}
+
+/// Whether a spend of `amount` must be held for approval.
+///
+/// # Errors
+/// Returns an error if the threshold can't be read.
+pub async fn needs_approval<C>(db: &C, amount: f64) -> Result<bool>
+where
+    C: ConnectionTrait,
+{
+    Ok(get_threshold(db)
+        .await?
+        .is_some_and(|threshold| amount > threshold))
+}
+
+/// Records a spend waiting for another member's approval as a pending purchase.
+///
+/// # Errors
+/// Returns an error if:
+/// - The amount is not positive and finite (`Error::InvalidAmount`)
+/// - The database insert fails
+pub async fn hold_spend(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    requester_id: &str,
+    amount: f64,
+    description: String,
+    merchant: Option<String>,
+    channel_id: String,
+) -> Result<pending_purchase::Model> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(Error::InvalidAmount { amount });
+    }
+
+    let now = Utc::now();
+    let record = pending_purchase::ActiveModel {
+        envelope_id: Set(envelope_id),
+        proposer_id: Set(requester_id.to_string()),
+        approver_id: Set(None),
+        amount: Set(amount),
+        description: Set(description),
+        merchant: Set(merchant),
+        channel_id: Set(Some(channel_id)),
+        status: Set(wishlist::STATUS_PENDING.to_string()),
+        decided_by: Set(None),
+        transaction_id: Set(None),
+        created_at: Set(now),
+        expires_at: Set(now + chrono::Duration::hours(HOLD_TTL_HOURS)),
+        decided_at: Set(None),
+        ..Default::default()
+    };
+
+    record.insert(db).await.map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_threshold() -> Result<()> {
+        let db = setup_test_db().await?;
+        assert_eq!(get_threshold(&db).await?, None);
+        assert!(!needs_approval(&db, 10_000.0).await?);
+
+        set_threshold(&db, Some(500.0)).await?;
+        assert!(!needs_approval(&db, 500.0).await?);
+        assert!(needs_approval(&db, 500.01).await?);
+        assert!(matches!(
+            set_threshold(&db, Some(-1.0)).await,
+            Err(Error::InvalidAmount { .. })
+        ));
+
+        set_threshold(&db, None).await?;
+        assert_eq!(get_threshold(&db).await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hold_spend() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let held = hold_spend(
+            &db,
+            env.id,
+            "user1",
+            600.0,
+            "Laptop".to_string(),
+            Some("Bestbuy".to_string()),
+            "42".to_string(),
+        )
+        .await?;
+        assert!(wishlist::is_hold(&held));
+        assert_eq!(held.status, wishlist::STATUS_PENDING);
+        assert_eq!(held.channel_id.as_deref(), Some("42"));
+        assert_eq!(
+            held.expires_at - held.created_at,
+            chrono::Duration::hours(HOLD_TTL_HOURS)
+        );
+        assert_eq!(wishlist::get_pending_purchases(&db).await?.len(), 1);
+
+        assert!(matches!(
+            hold_spend(
+                &db,
+                env.id,
+                "user1",
+                0.0,
+                "Nothing".to_string(),
+                None,
+                "42".to_string()
+            )
+            .await,
+            Err(Error::InvalidAmount { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,291 @@
+//! Data integrity audit - Finds records that reference missing data or disagree.
+//!
+//! [`run_audit`] checks for:
+//! - transactions whose envelope no longer exists
+//! - active products linked to a missing or deleted envelope
+//! - individual templates (individual envelopes with no owner) holding money or
+//!   transactions, which only members' own copies should
+//! - balances that drifted from their history (see [`reconcile::check_balances`])
+//!
+//! The bot audits at startup and logs what it finds; admins can run `/audit` any time.
+//! Only products are fixed automatically ([`fix_safe_findings`] retires them, since they
+//! can't be used anyway). Drifted balances have `/reconcile`, and orphaned transactions
+//! and templates are left for an admin to look at.
+
+use crate::{
+    core::{
+        cache::{self, CachedTable},
+        reconcile::{self, Discrepancy},
+        setup::Currency,
+    },
+    entities::{Envelope, Product, Transaction, envelope, product, transaction},
+    errors::Result,
+};
+use sea_orm::{
+    Condition, QueryOrder, Set,
+    prelude::*,
+    sea_query::{Query, SelectStatement},
+};
+use std::fmt::Write;
+
+/// Most findings of each kind listed by [`format_report`].
+const MAX_LISTED: usize = 10;
+
+/// Everything the audit found; empty lists mean the check passed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditReport {
+    /// Transactions whose envelope doesn't exist
+    pub orphaned_transactions: Vec<transaction::Model>,
+    /// Active products whose envelope is missing or deleted
+    pub orphaned_products: Vec<product::Model>,
+    /// Individual templates with a balance or transactions of their own
+    pub funded_templates: Vec<envelope::Model>,
+    /// Envelopes whose stored balance doesn't match their history
+    pub drifted_balances: Vec<Discrepancy>,
+}
+
+impl AuditReport {
+    /// Total number of findings.
+    #[must_use]
+    pub const fn finding_count(&self) -> usize {
+        self.orphaned_transactions.len()
+            + self.orphaned_products.len()
+            + self.funded_templates.len()
+            + self.drifted_balances.len()
+    }
+
+    /// Whether every check passed.
+    #[must_use]
+    pub const fn is_clean(&self) -> bool {
+        self.finding_count() == 0
+    }
+}
+
+/// Runs every integrity check. Nothing is changed.
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn run_audit(db: &DatabaseConnection) -> Result<AuditReport> {
+    let orphaned_transactions = Transaction::find()
+        .filter(transaction::Column::EnvelopeId.not_in_subquery(envelope_ids(None)))
+        .order_by_asc(transaction::Column::Id)
+        .all(db)
+        .await?;
+
+    let orphaned_products = Product::find()
+        .filter(product::Column::IsDeleted.eq(false))
+        .filter(
+            Condition::any()
+                .add(product::Column::EnvelopeId.not_in_subquery(envelope_ids(None)))
+                .add(product::Column::EnvelopeId.in_subquery(envelope_ids(Some(true)))),
+        )
+        .order_by_asc(product::Column::Name)
+        .all(db)
+        .await?;
+
+    let templates = Envelope::find()
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(envelope::Column::IsIndividual.eq(true))
+        .filter(envelope::Column::UserId.is_null())
+        .order_by_asc(envelope::Column::Name)
+        .all(db)
+        .await?;
+    let mut funded_templates = Vec::new();
+    for template in templates {
+        let transactions = Transaction::find_active()
+            .filter(transaction::Column::EnvelopeId.eq(template.id))
+            .count(db)
+            .await?;
+        if template.balance.abs() >= 0.005 || transactions > 0 {
+            funded_templates.push(template);
+        }
+    }
+
+    Ok(AuditReport {
+        orphaned_transactions,
+        orphaned_products,
+        funded_templates,
+        drifted_balances: reconcile::check_balances(db).await?.discrepancies,
+    })
+}
+
+/// IDs of every envelope, or only deleted (`Some(true)`) or active (`Some(false)`) ones.
+fn envelope_ids(deleted: Option<bool>) -> SelectStatement {
+    let mut query = Query::select();
+    query.column(envelope::Column::Id).from(Envelope);
+    if let Some(deleted) = deleted {
+        query.and_where(envelope::Column::IsDeleted.eq(deleted));
+    }
+    query
+}
+
+/// Fixes the findings that are safe to fix without a person deciding.
+///
+/// Products linked to a missing or deleted envelope are retired (soft-deleted), as they
+/// can no longer be used. Other findings are left alone.
+///
+/// # Returns
+/// The number of products retired
+///
+/// # Errors
+/// Returns an error if a database update fails.
+pub async fn fix_safe_findings(db: &DatabaseConnection, report: &AuditReport) -> Result<usize> {
+    let now = chrono::Utc::now().naive_utc();
+    for orphan in &report.orphaned_products {
+        let mut active_model: product::ActiveModel = orphan.clone().into();
+        active_model.is_deleted = Set(true);
+        active_model.updated_at = Set(now);
+        active_model.update(db).await?;
+    }
+    if !report.orphaned_products.is_empty() {
+        cache::invalidate(CachedTable::Products);
+    }
+    Ok(report.orphaned_products.len())
+}
+
+/// Formats the findings for display, one section per kind of problem, with amounts in
+/// `currency`.
+///
+/// # Errors
+/// Returns an error if string formatting fails.
+pub fn format_report(report: &AuditReport, currency: Currency) -> Result<String> {
+    let mut out = String::new();
+    if report.is_clean() {
+        out.push_str("✅ No integrity problems found.");
+        return Ok(out);
+    }
+
+    section(
+        &mut out,
+        "Transactions with a missing envelope",
+        &report.orphaned_transactions,
+        |tx| {
+            format!(
+                "#{} envelope {} {} - {}",
+                tx.id,
+                tx.envelope_id,
+                currency.format(tx.amount),
+                tx.description
+            )
+        },
+    )?;
+    section(
+        &mut out,
+        "Products linked to a missing or deleted envelope (safe to fix)",
+        &report.orphaned_products,
+        |prod| format!("{} (envelope {})", prod.name, prod.envelope_id),
+    )?;
+    section(
+        &mut out,
+        "Individual templates with money of their own",
+        &report.funded_templates,
+        |env| {
+            format!(
+                "{} (id {}) balance {}",
+                env.name,
+                env.id,
+                currency.format(env.balance)
+            )
+        },
+    )?;
+    section(
+        &mut out,
+        "Balances that drifted from their history (fix with /reconcile)",
+        &report.drifted_balances,
+        |discrepancy| {
+            format!(
+                "{}: stored {}, expected {}",
+                discrepancy.envelope.name,
+                currency.format(discrepancy.envelope.balance),
+                currency.format(discrepancy.expected)
+            )
+        },
+    )?;
+    Ok(out.trim_end().to_string())
+}
+
+/// Appends one kind of finding to `out`, if there are any.
+fn section<T>(
+    out: &mut String,
+    title: &str,
+    items: &[T],
+    describe: impl Fn(&T) -> String,
+) -> Result<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "**{title}** ({})", items.len())?;
+    for item in items.iter().take(MAX_LISTED) {
+        writeln!(out, "• {}", describe(item))?;
+    }
+    if items.len() > MAX_LISTED {
+        writeln!(out, "…and {} more", items.len() - MAX_LISTED)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_audit_finds_and_fixes() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        assert!(run_audit(&db).await?.is_clean());
+
+        // A product on a deleted envelope, and a transaction on one that is gone
+        let gone =
+            create_custom_envelope(&db, "gone", None, "necessary", 10.0, false, false).await?;
+        let coffee = create_test_product(&db, "Coffee", gone.id).await?;
+        create_test_product(&db, "Tea", env.id).await?;
+        let mut active_model: envelope::ActiveModel = gone.into();
+        active_model.is_deleted = Set(true);
+        active_model.update(&db).await?;
+        // Foreign keys prevent this today, but databases from older versions may have it
+        let stray = create_test_transaction(&db, env.id, 5.0).await?;
+        db.execute_unprepared("PRAGMA foreign_keys = OFF").await?;
+        let mut active_model: transaction::ActiveModel = stray.into();
+        active_model.envelope_id = Set(9999);
+        let stray = active_model.update(&db).await?;
+        db.execute_unprepared("PRAGMA foreign_keys = ON").await?;
+
+        // A template that somehow got a balance
+        let template = create_custom_envelope(
+            &db,
+            "allowance",
+            Some("user1".to_string()),
+            "fun",
+            20.0,
+            true,
+            false,
+        )
+        .await?;
+        let mut active_model: envelope::ActiveModel = template.into();
+        active_model.user_id = Set(None);
+        active_model.balance = Set(20.0);
+        active_model.update(&db).await?;
+
+        let report = run_audit(&db).await?;
+        assert_eq!(report.orphaned_transactions, vec![stray]);
+        assert_eq!(report.orphaned_products.len(), 1);
+        assert_eq!(report.orphaned_products[0].id, coffee.id);
+        assert_eq!(report.funded_templates.len(), 1);
+        assert_eq!(report.finding_count(), 3);
+        let text = format_report(&report, Currency::default())?;
+        assert!(text.contains("Coffee"));
+        assert!(text.contains("allowance"));
+        let euros = format_report(&report, Currency::from_code("EUR"))?;
+        assert!(euros.contains('€'));
+        assert!(!euros.contains('$'));
+
+        // Only the product is fixed
+        assert_eq!(fix_safe_findings(&db, &report).await?, 1);
+        let after = run_audit(&db).await?;
+        assert!(after.orphaned_products.is_empty());
+        assert_eq!(after.finding_count(), 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+//! Bill business logic - Recurring monthly bills, reminders, and payment matching.
+//!
+//! A bill is due on the same day every month (days past the end of a short month fall
+//! on its last day) and is paid from one envelope. The background scheduler calls
+//! [`due_reminders`] regularly; each due date is reminded once, `remind_days` before it,
+//! unless it's already paid. Every spend runs [`record_payment`], which marks a bill
+//! paid when the spend comes from its envelope and either matches the bill amount or
+//! mentions the bill's name in its description.
+
+use crate::{
+    core::{calendar, envelope, setup::Currency},
+    entities::{Bill, bill, transaction},
+    errors::{Error, Result},
+};
+use chrono::{Datelike, Days, Months, NaiveDate, Utc};
+use sea_orm::{QueryOrder, Set, prelude::*};
+
+/// Days before the due date that reminders are posted, unless the bill sets its own.
+pub const DEFAULT_REMIND_DAYS: i32 = 3;
+
+/// Most days before the due date a reminder may be posted.
+pub const MAX_REMIND_DAYS: i32 = 14;
+
+/// How late a payment may be and still count for the due date it missed.
+const LATE_PAYMENT_DAYS: u64 = 7;
+
+/// Fields for a bill about to be added.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewBill {
+    /// Bill name, unique (case-insensitive)
+    pub name: String,
+    /// Amount due each month (positive)
+    pub amount: f64,
+    /// Day of the month the bill is due, `1..=31`
+    pub due_day: i32,
+    /// Envelope the bill is paid from
+    pub envelope_id: i64,
+    /// Discord user ID of the member adding the bill
+    pub user_id: String,
+    /// Channel reminders fall back to without an announcement channel
+    pub channel_id: Option<String>,
+    /// Server to create scheduled events in
+    pub guild_id: Option<String>,
+    /// Days before the due date to post the reminder, `0..=MAX_REMIND_DAYS`
+    pub remind_days: i32,
+    /// Whether reminders also create a Discord scheduled event
+    pub create_event: bool,
+}
+
+/// A reminder that a bill is coming due, returned by [`due_reminders`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BillReminder {
+    /// The bill, as updated by the reminder
+    pub bill: bill::Model,
+    /// The due date being reminded
+    pub due_on: NaiveDate,
+    /// Name of the envelope the bill is paid from
+    pub envelope_name: String,
+}
+
+impl BillReminder {
+    /// Formats the reminder for display in Discord, with the amount in `currency`.
+    #[must_use]
+    pub fn message(&self, today: NaiveDate, currency: Currency) -> String {
+        let when = match (self.due_on - today).num_days() {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            days => format!("in {days} days"),
+        };
+        format!(
+            "🧾 **Bill reminder:** {} ({}) is due {when}, on {}. Pay it from '{}'; \
+             a matching `/spend` marks it paid.",
+            self.bill.name,
+            currency.format(self.bill.amount),
+            self.due_on.format("%b %d"),
+            self.envelope_name
+        )
+    }
+}
+
+/// Returns the bill's due date in the month containing `date`.
+fn due_in_month(due_day: i32, date: NaiveDate) -> NaiveDate {
+    let first = date.with_day(1).unwrap_or(date);
+    let last = first
+        .checked_add_months(Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(first);
+    let day = u32::try_from(due_day).unwrap_or(1).clamp(1, last.day());
+    first.with_day(day).unwrap_or(last)
+}
+
+/// Returns the first due date on or after `date` for a bill due on `due_day`.
+#[must_use]
+pub fn due_date_on_or_after(due_day: i32, date: NaiveDate) -> NaiveDate {
+    let due = due_in_month(due_day, date);
+    if due >= date {
+        return due;
+    }
+    date.with_day(1)
+        .and_then(|first| first.checked_add_months(Months::new(1)))
+        .map_or(due, |next_month| due_in_month(due_day, next_month))
+}
+
+/// Adds a recurring bill.
+///
+/// # Errors
+/// Returns an error if:
+/// - The name is empty or already used by another bill, the due day is outside
+///   `1..=31`, or `remind_days` is outside `0..=MAX_REMIND_DAYS` (`Error::Config`)
+/// - The amount is not positive and finite (`Error::InvalidAmount`)
+/// - The database query or insert fails
+pub async fn add_bill(db: &DatabaseConnection, new: NewBill) -> Result<bill::Model> {
+    let name = new.name.trim();
+    if name.is_empty() {
+        return Err(Error::Config {
+            message: "Bill name cannot be empty".to_string(),
+        });
+    }
+    if !new.amount.is_finite() || new.amount <= 0.0 {
+        return Err(Error::InvalidAmount { amount: new.amount });
+    }
+    if !(1..=31).contains(&new.due_day) {
+        return Err(Error::Config {
+            message: "Due day must be between 1 and 31".to_string(),
+        });
+    }
+    if !(0..=MAX_REMIND_DAYS).contains(&new.remind_days) {
+        return Err(Error::Config {
+            message: format!("Reminders can be at most {MAX_REMIND_DAYS} days before the due date"),
+        });
+    }
+    if get_bill_by_name(db, name).await?.is_some() {
+        return Err(Error::Config {
+            message: format!("A bill named '{name}' already exists"),
+        });
+    }
+
+    let record = bill::ActiveModel {
+        name: Set(name.to_string()),
+        amount: Set(new.amount),
+        due_day: Set(new.due_day),
+        envelope_id: Set(new.envelope_id),
+        user_id: Set(new.user_id),
+        channel_id: Set(new.channel_id),
+        guild_id: Set(new.guild_id),
+        remind_days: Set(new.remind_days),
+        create_event: Set(new.create_event),
+        reminded_for: Set(None),
+        paid_for: Set(None),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    record.insert(db).await.map_err(Into::into)
+}
+
+/// Retrieves every bill, ordered by due day and name.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_bills(db: &DatabaseConnection) -> Result<Vec<bill::Model>> {
+    Bill::find()
+        .order_by_asc(bill::Column::DueDay)
+        .order_by_asc(bill::Column::Name)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Finds a bill by name (case-insensitive).
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_bill_by_name(db: &DatabaseConnection, name: &str) -> Result<Option<bill::Model>> {
+    let name = name.trim().to_lowercase();
+    Ok(get_bills(db)
+        .await?
+        .into_iter()
+        .find(|bill| bill.name.to_lowercase() == name))
+}
+
+/// Removes a bill.
+///
+/// # Returns
+/// `true` if a bill with that name existed
+///
+/// # Errors
+/// Returns an error if the database query or delete fails.
+pub async fn remove_bill(db: &DatabaseConnection, name: &str) -> Result<bool> {
+    let Some(existing) = get_bill_by_name(db, name).await? else {
+        return Ok(false);
+    };
+    Bill::delete_by_id(existing.id).exec(db).await?;
+    Ok(true)
+}
+
+/// Returns the bills whose reminder is due `today`, recording each so it isn't repeated.
+///
+/// A bill is reminded when its next due date (today or later) is at most `remind_days`
+/// away, hasn't been paid, and hasn't been reminded yet.
+///
+/// # Errors
+/// Returns an error if the database query or update fails.
+pub async fn due_reminders(db: &DatabaseConnection, today: NaiveDate) -> Result<Vec<BillReminder>> {
+    let mut reminders = Vec::new();
+    for bill in get_bills(db).await? {
+        let due_on = due_date_on_or_after(bill.due_day, today);
+        if bill.paid_for.is_some_and(|paid| paid >= due_on)
+            || bill.reminded_for == Some(due_on)
+            || (due_on - today).num_days() > i64::from(bill.remind_days)
+        {
+            continue;
+        }
+
+        let envelope_name = envelope::get_envelope_by_id(db, bill.envelope_id)
+            .await?
+            .map_or_else(|| "unknown".to_string(), |env| env.name);
+        let mut active_model: bill::ActiveModel = bill.into();
+        active_model.reminded_for = Set(Some(due_on));
+        reminders.push(BillReminder {
+            bill: active_model.update(db).await?,
+            due_on,
+            envelope_name,
+        });
+    }
+    Ok(reminders)
+}
+
+/// Marks bills paid by a spend, returning the bills it paid.
+///
+/// A spend pays a bill when it comes from the bill's envelope and its amount matches
+/// the bill to the cent or its description contains the bill's name. It covers the
+/// earliest unpaid due date from up to a week before the spend onward, so a late
+/// payment still settles the due date it missed.
+///
+/// # Errors
+/// Returns an error if the database query or update fails.
+pub async fn record_payment<C>(db: &C, spend: &transaction::Model) -> Result<Vec<bill::Model>>
+where
+    C: ConnectionTrait,
+{
+    if spend.amount >= 0.0 {
+        return Ok(Vec::new());
+    }
+
+    let bills = Bill::find()
+        .filter(bill::Column::EnvelopeId.eq(spend.envelope_id))
+        .all(db)
+        .await?;
+    let description = spend.description.to_lowercase();
+    let spend_date = calendar::local_date(db, spend.timestamp).await?;
+
+    let mut paid = Vec::new();
+    for bill in bills {
+        if (bill.amount + spend.amount).abs() >= 0.005
+            && !description.contains(&bill.name.to_lowercase())
+        {
+            continue;
+        }
+
+        let earliest = spend_date
+            .checked_sub_days(Days::new(LATE_PAYMENT_DAYS))
+            .unwrap_or(spend_date);
+        let mut due_on = due_date_on_or_after(bill.due_day, earliest);
+        if let Some(paid_for) = bill.paid_for
+            && paid_for >= due_on
+        {
+            due_on = due_date_on_or_after(bill.due_day, paid_for.succ_opt().unwrap_or(paid_for));
+        }
+
+        let mut active_model: bill::ActiveModel = bill.into();
+        active_model.paid_for = Set(Some(due_on));
+        paid.push(active_model.update(db).await?);
+    }
+    Ok(paid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::transaction, entities::TransactionType, test_utils::*};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_due_date_on_or_after() {
+        assert_eq!(
+            due_date_on_or_after(15, date(2025, 3, 10)),
+            date(2025, 3, 15)
+        );
+        assert_eq!(
+            due_date_on_or_after(15, date(2025, 3, 15)),
+            date(2025, 3, 15)
+        );
+        assert_eq!(
+            due_date_on_or_after(15, date(2025, 3, 16)),
+            date(2025, 4, 15)
+        );
+        // Short months fall back to their last day
+        assert_eq!(
+            due_date_on_or_after(31, date(2025, 2, 3)),
+            date(2025, 2, 28)
+        );
+        assert_eq!(
+            due_date_on_or_after(31, date(2025, 3, 1)),
+            date(2025, 3, 31)
+        );
+        assert_eq!(
+            due_date_on_or_after(5, date(2025, 12, 20)),
+            date(2026, 1, 5)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bill_reminders_and_payment() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        transaction::create_transaction(
+            &db,
+            env.id,
+            100.0,
+            "Deposit".to_string(),
+            "test_user".to_string(),
+            None,
+            TransactionType::AddFunds,
+        )
+        .await?;
+        let today = Utc::now().date_naive();
+        let due_day =
+            i32::try_from(today.checked_add_days(Days::new(2)).unwrap_or(today).day()).unwrap_or(1);
+        let new_bill = |name: &str| NewBill {
+            name: name.to_string(),
+            amount: 45.0,
+            due_day,
+            envelope_id: env.id,
+            user_id: "test_user".to_string(),
+            channel_id: None,
+            guild_id: None,
+            remind_days: DEFAULT_REMIND_DAYS,
+            create_event: false,
+        };
+        add_bill(&db, new_bill("Internet")).await?;
+        assert!(matches!(
+            add_bill(&db, new_bill("internet")).await,
+            Err(Error::Config { .. })
+        ));
+
+        // Reminded once per due date
+        let reminders = due_reminders(&db, today).await?;
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].due_on, due_date_on_or_after(due_day, today));
+        assert!(
+            reminders[0]
+                .message(today, Currency::from_code("GBP"))
+                .contains("Internet (£45.00)")
+        );
+        assert!(due_reminders(&db, today).await?.is_empty());
+
+        // A spend for another amount that doesn't name the bill leaves it unpaid
+        transaction::create_transaction(
+            &db,
+            env.id,
+            -12.0,
+            "Snacks".to_string(),
+            "test_user".to_string(),
+            None,
+            TransactionType::Spend,
+        )
+        .await?;
+        let bill = get_bill_by_name(&db, "Internet").await?;
+        assert_eq!(bill.and_then(|bill| bill.paid_for), None);
+
+        transaction::create_transaction(
+            &db,
+            env.id,
+            -45.0,
+            "ISP".to_string(),
+            "test_user".to_string(),
+            None,
+            TransactionType::Spend,
+        )
+        .await?;
+        let bill = get_bill_by_name(&db, "Internet").await?;
+        assert_eq!(
+            bill.and_then(|bill| bill.paid_for),
+            Some(due_date_on_or_after(due_day, today))
+        );
+
+        assert!(remove_bill(&db, "INTERNET").await?);
+        assert!(get_bills(&db).await?.is_empty());
+        Ok(())
+    }
+}
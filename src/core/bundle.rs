@@ -0,0 +1,459 @@
+//! Bundle business logic - Products used together as one purchase.
+//!
+//! A bundle such as "Taco Night" lists products with quantities. [`use_bundle`] logs a
+//! spend for every component against that product's envelope in a single database
+//! transaction: either every component is recorded or none are. The bundle counts as
+//! one purchase for the large transaction threshold, so its total is what needs approval.
+
+use crate::{
+    core::{
+        approval,
+        cache::{self, CachedTable},
+        envelope,
+        journal::{self, JournalEvent},
+        transaction,
+    },
+    entities::{
+        Bundle, BundleItem, Product, TransactionType, bundle, bundle_item,
+        envelope as envelope_entity, product, transaction as transaction_entity,
+    },
+    errors::{Error, Result},
+};
+use sea_orm::{QueryOrder, Set, TransactionTrait, prelude::*};
+
+/// One component spend recorded by [`use_bundle`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentSpend {
+    /// The product that was used
+    pub product: product::Model,
+    /// Units of the product used
+    pub quantity: i64,
+    /// Name of the envelope the spend was charged to
+    pub envelope_name: String,
+    /// The spend transaction
+    pub transaction: transaction_entity::Model,
+}
+
+/// Creates an empty bundle.
+///
+/// # Errors
+/// Returns an error if:
+/// - The name is empty or already used by another bundle (`Error::Config`)
+/// - The database insert fails
+pub async fn create_bundle(db: &DatabaseConnection, name: &str) -> Result<bundle::Model> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::Config {
+            message: "Bundle name cannot be empty".to_string(),
+        });
+    }
+    if get_bundle_by_name(db, name).await?.is_some() {
+        return Err(Error::Config {
+            message: format!("A bundle named '{name}' already exists"),
+        });
+    }
+
+    let bundle = bundle::ActiveModel {
+        name: Set(name.to_string()),
+        created_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
+    bundle.insert(db).await.map_err(Into::into)
+}
+
+/// Finds a bundle by its name.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_bundle_by_name(
+    db: &DatabaseConnection,
+    name: &str,
+) -> Result<Option<bundle::Model>> {
+    Bundle::find()
+        .filter(bundle::Column::Name.eq(name))
+        .one(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Retrieves all bundles, ordered alphabetically by name.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_all_bundles(db: &DatabaseConnection) -> Result<Vec<bundle::Model>> {
+    Bundle::find()
+        .order_by_asc(bundle::Column::Name)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Deletes a bundle and its items. Transactions logged through it are kept.
+///
+/// # Errors
+/// Returns an error if the database transaction fails.
+pub async fn delete_bundle(db: &DatabaseConnection, bundle_id: i64) -> Result<()> {
+    let txn = db.begin().await?;
+    BundleItem::delete_many()
+        .filter(bundle_item::Column::BundleId.eq(bundle_id))
+        .exec(&txn)
+        .await?;
+    Bundle::delete_by_id(bundle_id).exec(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Sets how many units of a product a bundle uses, adding the product if needed.
+///
+/// # Errors
+/// Returns an error if:
+/// - The quantity is not positive (`Error::Config`)
+/// - The database query or write fails
+pub async fn set_bundle_item(
+    db: &DatabaseConnection,
+    bundle_id: i64,
+    product_id: i64,
+    quantity: i64,
+) -> Result<bundle_item::Model> {
+    if quantity <= 0 {
+        return Err(Error::Config {
+            message: "Quantity must be a positive number".to_string(),
+        });
+    }
+
+    let existing = BundleItem::find()
+        .filter(bundle_item::Column::BundleId.eq(bundle_id))
+        .filter(bundle_item::Column::ProductId.eq(product_id))
+        .one(db)
+        .await?;
+
+    if let Some(item) = existing {
+        let mut active_model: bundle_item::ActiveModel = item.into();
+        active_model.quantity = Set(quantity);
+        return active_model.update(db).await.map_err(Into::into);
+    }
+
+    let item = bundle_item::ActiveModel {
+        bundle_id: Set(bundle_id),
+        product_id: Set(product_id),
+        quantity: Set(quantity),
+        ..Default::default()
+    };
+    item.insert(db).await.map_err(Into::into)
+}
+
+/// Removes a product from a bundle.
+///
+/// # Returns
+/// `true` if the product was part of the bundle
+///
+/// # Errors
+/// Returns an error if the database delete fails.
+pub async fn remove_bundle_item(
+    db: &DatabaseConnection,
+    bundle_id: i64,
+    product_id: i64,
+) -> Result<bool> {
+    let result = BundleItem::delete_many()
+        .filter(bundle_item::Column::BundleId.eq(bundle_id))
+        .filter(bundle_item::Column::ProductId.eq(product_id))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// Retrieves a bundle's items with their products, in the order they were added.
+///
+/// Products that were deleted after being added are included (with `is_deleted` set) so
+/// they can be shown and removed.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_bundle_components(
+    db: &DatabaseConnection,
+    bundle_id: i64,
+) -> Result<Vec<(bundle_item::Model, product::Model)>> {
+    let rows = BundleItem::find()
+        .filter(bundle_item::Column::BundleId.eq(bundle_id))
+        .find_also_related(Product)
+        .order_by_asc(bundle_item::Column::Id)
+        .all(db)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(item, product)| Some((item, product?)))
+        .collect())
+}
+
+/// Total cost of one use of a bundle at current product prices.
+#[must_use]
+pub fn bundle_cost(components: &[(bundle_item::Model, product::Model)]) -> f64 {
+    components
+        .iter()
+        .map(|(item, product)| {
+            // Cast is safe: for quantities < 2^53, no precision loss occurs in f64
+            #[allow(clippy::cast_precision_loss)]
+            let quantity = item.quantity as f64;
+            product.price * quantity
+        })
+        .sum()
+}
+
+/// Logs a spend for every product in a bundle, all or nothing.
+///
+/// Each component is charged to its product's envelope; for individual envelopes the
+/// user's own instance is used. All spends are committed in one database transaction,
+/// so if any of them fails (e.g. insufficient funds) nothing is recorded. When
+/// `message_id` is given, each spend is keyed on it so a retried command doesn't spend
+/// twice.
+///
+/// # Errors
+/// Returns an error if:
+/// - The bundle has no products (`Error::Config`)
+/// - The bundle's total is over the large transaction threshold
+///   (`Error::ApprovalRequired`)
+/// - A component product was deleted (`Error::ProductNotFound`)
+/// - A component's envelope is missing, or the user has no instance of an individual
+///   envelope (`Error::EnvelopeNotFound`)
+/// - A spend fails (e.g. `Error::InsufficientFunds`)
+/// - The database transaction fails
+pub async fn use_bundle(
+    db: &DatabaseConnection,
+    bundle: &bundle::Model,
+    user_id: &str,
+    message_id: Option<&str>,
+) -> Result<Vec<ComponentSpend>> {
+    let components = get_bundle_components(db, bundle.id).await?;
+    if components.is_empty() {
+        return Err(Error::Config {
+            message: format!("Bundle '{}' has no products yet", bundle.name),
+        });
+    }
+    let total = bundle_cost(&components);
+    if let Some(threshold) = approval::get_threshold(db).await?
+        && total > threshold
+    {
+        return Err(Error::ApprovalRequired {
+            amount: total,
+            threshold,
+        });
+    }
+
+    // Resolve every envelope before writing anything
+    let mut planned = Vec::with_capacity(components.len());
+    for (item, product) in components {
+        if product.is_deleted {
+            return Err(Error::ProductNotFound { name: product.name });
+        }
+        let envelope = resolve_envelope(db, &product, user_id).await?;
+        planned.push((item, product, envelope));
+    }
+
+    let txn = db.begin().await?;
+    let mut spends = Vec::with_capacity(planned.len());
+    let mut created = Vec::with_capacity(planned.len());
+    for (item, product, envelope) in planned {
+        // Cast is safe: for quantities < 2^53, no precision loss occurs in f64
+        #[allow(clippy::cast_precision_loss)]
+        let cost = product.price * (item.quantity as f64);
+        let (transaction, is_new) = transaction::create_unjournaled_transaction(
+            &txn,
+            envelope.id,
+            -cost,
+            format!(
+                "Bundle: {} - Product: {} (x{})",
+                bundle.name, product.name, item.quantity
+            ),
+            user_id.to_string(),
+            message_id.map(|id| format!("{id}:bundle:{}", product.id)),
+            TransactionType::UseProduct,
+        )
+        .await?;
+        created.push(is_new);
+        spends.push(ComponentSpend {
+            product,
+            quantity: item.quantity,
+            envelope_name: envelope.name,
+            transaction,
+        });
+    }
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    for (spend, _) in spends.iter().zip(created).filter(|(_, is_new)| *is_new) {
+        journal::record(JournalEvent::Created, &spend.transaction);
+    }
+
+    Ok(spends)
+}
+
+/// Finds the envelope a product is charged to for `user_id`.
+async fn resolve_envelope(
+    db: &DatabaseConnection,
+    product: &product::Model,
+    user_id: &str,
+) -> Result<envelope_entity::Model> {
+    let template = envelope::get_envelope_by_id(db, product.envelope_id)
+        .await?
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: format!("ID {}", product.envelope_id),
+        })?;
+
+    if !template.is_individual {
+        return Ok(template);
+    }
+
+    envelope::resolve_envelope(db, &template.name, user_id)
+        .await?
+        .filter(|env| env.user_id.as_deref() == Some(user_id))
+        .ok_or(Error::EnvelopeNotFound {
+            name: template.name,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_use_bundle_spends_each_component() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries = create_test_envelope(&db, "Groceries").await?;
+        let snacks = create_custom_envelope(
+            &db,
+            "Snacks",
+            Some("test_user".to_string()),
+            "personal",
+            50.0,
+            true,
+            false,
+        )
+        .await?;
+        create_test_transaction(&db, groceries.id, 100.0).await?;
+        create_test_transaction(&db, snacks.id, 50.0).await?;
+
+        let tortillas = create_custom_product(&db, "Tortillas", 3.0, groceries.id).await?;
+        let beef = create_custom_product(&db, "Ground Beef", 6.5, groceries.id).await?;
+        let salsa = create_custom_product(&db, "Salsa", 4.0, snacks.id).await?;
+
+        let bundle = create_bundle(&db, "Taco Night").await?;
+        set_bundle_item(&db, bundle.id, tortillas.id, 1).await?;
+        set_bundle_item(&db, bundle.id, beef.id, 2).await?;
+        set_bundle_item(&db, bundle.id, salsa.id, 1).await?;
+        assert_eq!(
+            bundle_cost(&get_bundle_components(&db, bundle.id).await?),
+            20.0
+        );
+
+        let spends = use_bundle(&db, &bundle, "test_user", Some("msg1")).await?;
+        assert_eq!(spends.len(), 3);
+        assert_eq!(spends[1].transaction.amount, -13.0);
+        assert_eq!(spends[2].envelope_name, "Snacks");
+
+        let groceries = envelope::get_envelope_by_id(&db, groceries.id)
+            .await?
+            .unwrap();
+        let snacks = envelope::get_envelope_by_id(&db, snacks.id).await?.unwrap();
+        assert_eq!(groceries.balance, 84.0);
+        assert_eq!(snacks.balance, 46.0);
+
+        // A retried command doesn't spend again
+        use_bundle(&db, &bundle, "test_user", Some("msg1")).await?;
+        let groceries = envelope::get_envelope_by_id(&db, groceries.id)
+            .await?
+            .unwrap();
+        assert_eq!(groceries.balance, 84.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_use_bundle_is_atomic() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries = create_test_envelope(&db, "Groceries").await?;
+        let snacks = create_test_envelope(&db, "Snacks").await?;
+        create_test_transaction(&db, groceries.id, 100.0).await?;
+
+        let bread = create_custom_product(&db, "Bread", 5.0, groceries.id).await?;
+        let chips = create_custom_product(&db, "Chips", 3.0, snacks.id).await?;
+        let bundle = create_bundle(&db, "Picnic").await?;
+        set_bundle_item(&db, bundle.id, bread.id, 1).await?;
+        set_bundle_item(&db, bundle.id, chips.id, 1).await?;
+
+        // Snacks has no funds, so the bread spend is rolled back too
+        let result = use_bundle(&db, &bundle, "test_user", None).await;
+        assert!(matches!(result, Err(Error::InsufficientFunds { .. })));
+        let groceries = envelope::get_envelope_by_id(&db, groceries.id)
+            .await?
+            .unwrap();
+        assert_eq!(groceries.balance, 100.0);
+        assert_eq!(
+            transaction::get_transactions_for_envelope(&db, groceries.id)
+                .await?
+                .len(),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bundle_total_needs_approval() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 100.0).await?;
+        approval::set_threshold(&db, Some(20.0)).await?;
+
+        // Each component is under the threshold, but the bundle isn't
+        let steak = create_custom_product(&db, "Steak", 15.0, env.id).await?;
+        let wine = create_custom_product(&db, "Wine", 12.0, env.id).await?;
+        let bundle = create_bundle(&db, "Date Night").await?;
+        set_bundle_item(&db, bundle.id, steak.id, 1).await?;
+        set_bundle_item(&db, bundle.id, wine.id, 1).await?;
+
+        let result = use_bundle(&db, &bundle, "test_user", None).await;
+        assert!(matches!(
+            result,
+            Err(Error::ApprovalRequired { amount, threshold }) if amount == 27.0 && threshold == 20.0
+        ));
+        let env = envelope::get_envelope_by_id(&db, env.id).await?.unwrap();
+        assert_eq!(env.balance, 100.0);
+
+        approval::set_threshold(&db, Some(30.0)).await?;
+        assert_eq!(use_bundle(&db, &bundle, "test_user", None).await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bundle_composition() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let product = create_test_product(&db, "Coffee", env.id).await?;
+        let bundle = create_bundle(&db, "Breakfast").await?;
+
+        assert!(matches!(
+            create_bundle(&db, "Breakfast").await,
+            Err(Error::Config { .. })
+        ));
+        assert!(matches!(
+            use_bundle(&db, &bundle, "test_user", None).await,
+            Err(Error::Config { .. })
+        ));
+
+        // Adding a product again changes its quantity
+        set_bundle_item(&db, bundle.id, product.id, 1).await?;
+        set_bundle_item(&db, bundle.id, product.id, 3).await?;
+        let components = get_bundle_components(&db, bundle.id).await?;
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].0.quantity, 3);
+
+        assert!(remove_bundle_item(&db, bundle.id, product.id).await?);
+        assert!(!remove_bundle_item(&db, bundle.id, product.id).await?);
+
+        delete_bundle(&db, bundle.id).await?;
+        assert!(get_bundle_by_name(&db, "Breakfast").await?.is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+//! Read cache - Hot query results shared by all commands.
+//!
+//! Autocomplete runs on every keystroke and mostly re-reads the same envelope and
+//! product lists, and every confirmation reads its author's preferences. [`Cache`] lives
+//! in `BotData` and keeps those in memory between commands. Core functions that write
+//! envelopes, products, or preferences call [`invalidate`], which bumps a process-wide
+//! generation counter for the table; entries filled under an older generation count as
+//! misses and are reloaded on the next read. Hits and misses are counted for `/status`.
+//!
+//! The counters only live in the bot's process. Other processes writing the same
+//! database, such as `envelope-buddy admin`, call [`announce_external_write`] afterwards,
+//! and the bot polls [`check_external_writes`] to drop everything it has cached.
+
+use crate::{
+    core::{envelope, preferences, product, state},
+    entities::{envelope as envelope_entity, product as product_entity},
+    errors::Result,
+};
+use chrono::{SecondsFormat, Utc};
+use sea_orm::{ConnectionTrait, DatabaseConnection};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+static ENVELOPE_GENERATION: AtomicU64 = AtomicU64::new(0);
+static PRODUCT_GENERATION: AtomicU64 = AtomicU64::new(0);
+static PREFERENCE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// `system_state` key stamped by other processes after they write to the database.
+const EXTERNAL_WRITE_KEY: &str = "cache_external_write";
+
+/// A group of cached rows that is invalidated together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachedTable {
+    /// Envelopes, including their balances and the categories derived from them
+    Envelopes,
+    /// Products
+    Products,
+    /// Members' personal preferences
+    Preferences,
+}
+
+impl CachedTable {
+    const fn counter(self) -> &'static AtomicU64 {
+        match self {
+            Self::Envelopes => &ENVELOPE_GENERATION,
+            Self::Products => &PRODUCT_GENERATION,
+            Self::Preferences => &PREFERENCE_GENERATION,
+        }
+    }
+}
+
+/// Marks every cached copy of `table` as stale.
+///
+/// Call after a write to the table has been committed. Writes made inside a database
+/// transaction should invalidate again once the transaction commits, so a read racing
+/// the commit can't keep pre-commit rows cached.
+pub fn invalidate(table: CachedTable) {
+    table.counter().fetch_add(1, Ordering::AcqRel);
+}
+
+/// Records that this process changed the database behind another process's cache.
+///
+/// Call after the writes have been committed; the bot invalidates every table the next
+/// time it runs [`check_external_writes`].
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn announce_external_write<C>(db: &C) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let stamp = Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true);
+    state::set_value(db, EXTERNAL_WRITE_KEY, &stamp).await
+}
+
+/// Invalidates every cached table if another process has written to the database since
+/// `seen`, the stamp returned by the previous check.
+///
+/// # Returns
+/// The current stamp, to pass to the next check
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn check_external_writes<C>(db: &C, seen: Option<&str>) -> Result<Option<String>>
+where
+    C: ConnectionTrait,
+{
+    let stamp = state::get_value(db, EXTERNAL_WRITE_KEY).await?;
+    if stamp.as_deref() != seen {
+        for table in [
+            CachedTable::Envelopes,
+            CachedTable::Products,
+            CachedTable::Preferences,
+        ] {
+            invalidate(table);
+        }
+    }
+    Ok(stamp)
+}
+
+/// Current generation of `table`; it changes on every [`invalidate`].
+#[must_use]
+pub fn generation(table: CachedTable) -> u64 {
+    table.counter().load(Ordering::Acquire)
+}
+
+/// Hit and miss counts of a [`Cache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Reads served from memory
+    pub hits: u64,
+    /// Reads that went to the database
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Fraction of reads served from memory, or `None` before the first read.
+    #[must_use]
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        // Cast is safe: counts stay far below 2^53, so no precision is lost in f64
+        #[allow(clippy::cast_precision_loss)]
+        (total > 0).then(|| self.hits as f64 / total as f64)
+    }
+}
+
+/// A cached value and the table generation it was loaded under.
+struct Entry<T> {
+    generation: u64,
+    value: Arc<T>,
+}
+
+/// One cached query result.
+struct Slot<T> {
+    table: CachedTable,
+    entry: RwLock<Option<Entry<T>>>,
+}
+
+impl<T> Slot<T> {
+    const fn new(table: CachedTable) -> Self {
+        Self {
+            table,
+            entry: RwLock::new(None),
+        }
+    }
+
+    /// Returns the cached value if it was loaded under the current generation.
+    fn fresh(&self) -> Option<Arc<T>> {
+        let current = generation(self.table);
+        let guard = self.entry.read().ok()?;
+        guard
+            .as_ref()
+            .filter(|entry| entry.generation == current)
+            .map(|entry| Arc::clone(&entry.value))
+    }
+
+    fn store(&self, generation: u64, value: T) -> Arc<T> {
+        let value = Arc::new(value);
+        if let Ok(mut guard) = self.entry.write() {
+            *guard = Some(Entry {
+                generation,
+                value: Arc::clone(&value),
+            });
+        }
+        value
+    }
+}
+
+/// In-memory cache of envelope, product, and preference reads, shared by all commands.
+pub struct Cache {
+    envelopes: Slot<Vec<envelope_entity::Model>>,
+    categories: Slot<Vec<String>>,
+    products: Slot<Vec<product_entity::Model>>,
+    preferences: Slot<HashMap<(String, String), String>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            envelopes: Slot::new(CachedTable::Envelopes),
+            categories: Slot::new(CachedTable::Envelopes),
+            products: Slot::new(CachedTable::Products),
+            preferences: Slot::new(CachedTable::Preferences),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Cache {
+    /// All active envelopes, in display order (see [`envelope::get_all_active_envelopes`]).
+    ///
+    /// # Errors
+    /// Returns an error if the database query fails on a miss.
+    pub async fn active_envelopes(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<Arc<Vec<envelope_entity::Model>>> {
+        if let Some(hit) = self.hit(&self.envelopes) {
+            return Ok(hit);
+        }
+        // Read the generation before querying so a write during the query leaves the
+        // entry stale rather than caching old rows as current
+        let loaded_under = generation(CachedTable::Envelopes);
+        let envelopes = envelope::get_all_active_envelopes(db).await?;
+        Ok(self.envelopes.store(loaded_under, envelopes))
+    }
+
+    /// Active envelopes with exactly the given name: the shared envelope and/or one
+    /// individual envelope per member.
+    ///
+    /// # Errors
+    /// Returns an error if the database query fails on a miss.
+    pub async fn envelopes_named(
+        &self,
+        db: &DatabaseConnection,
+        name: &str,
+    ) -> Result<Vec<envelope_entity::Model>> {
+        Ok(self
+            .active_envelopes(db)
+            .await?
+            .iter()
+            .filter(|env| env.name == name)
+            .cloned()
+            .collect())
+    }
+
+    /// Distinct categories of active envelopes, sorted (see [`envelope::get_all_categories`]).
+    ///
+    /// # Errors
+    /// Returns an error if the database query fails on a miss.
+    pub async fn categories(&self, db: &DatabaseConnection) -> Result<Arc<Vec<String>>> {
+        if let Some(hit) = self.hit(&self.categories) {
+            return Ok(hit);
+        }
+        let loaded_under = generation(CachedTable::Envelopes);
+        let categories = envelope::get_all_categories(db).await?;
+        Ok(self.categories.store(loaded_under, categories))
+    }
+
+    /// All active products, ordered by name (see [`product::get_all_active_products`]).
+    ///
+    /// # Errors
+    /// Returns an error if the database query fails on a miss.
+    pub async fn active_products(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<Arc<Vec<product_entity::Model>>> {
+        if let Some(hit) = self.hit(&self.products) {
+            return Ok(hit);
+        }
+        let loaded_under = generation(CachedTable::Products);
+        let products = product::get_all_active_products(db).await?;
+        Ok(self.products.store(loaded_under, products))
+    }
+
+    /// A member's stored value of a preference, if set (see [`preferences::get_value`]).
+    ///
+    /// # Errors
+    /// Returns an error if the database query fails on a miss.
+    pub async fn preference(
+        &self,
+        db: &DatabaseConnection,
+        user_id: &str,
+        key: &str,
+    ) -> Result<Option<String>> {
+        let values = if let Some(hit) = self.hit(&self.preferences) {
+            hit
+        } else {
+            let loaded_under = generation(CachedTable::Preferences);
+            let values = preferences::get_all_values(db).await?;
+            self.preferences.store(loaded_under, values)
+        };
+        Ok(values.get(&(user_id.to_string(), key.to_string())).cloned())
+    }
+
+    /// Hit and miss counts since the bot started.
+    #[must_use]
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Looks up a slot, counting the read as a hit or a miss.
+    fn hit<T>(&self, slot: &Slot<T>) -> Option<Arc<T>> {
+        let fresh = slot.fresh();
+        let counter = if fresh.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        fresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_cache_hits_until_invalidated() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let cache = Cache::default();
+
+        let first = cache.envelopes_named(&db, &env.name).await?;
+        assert_eq!(first.len(), 1);
+        assert_eq!(cache.envelopes_named(&db, &env.name).await?, first);
+        // Other tests can invalidate concurrently, so only the total is deterministic
+        let stats = cache.stats();
+        assert_eq!(stats.hits + stats.misses, 2);
+        assert!(stats.misses >= 1);
+
+        // Creating an envelope goes through core, which invalidates the cached list
+        create_custom_envelope(&db, "cached_new", None, "test", 10.0, false, false).await?;
+        let envelopes = cache.active_envelopes(&db).await?;
+        assert!(envelopes.iter().any(|e| e.name == "cached_new"));
+        assert!(cache.categories(&db).await?.contains(&"test".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_external_writes_invalidate() -> Result<()> {
+        let db = setup_test_db().await?;
+        let seen = check_external_writes(&db, None).await?;
+        assert_eq!(seen, None);
+
+        announce_external_write(&db).await?;
+        let before = generation(CachedTable::Products);
+        let seen = check_external_writes(&db, seen.as_deref()).await?;
+        assert!(seen.is_some());
+        assert!(generation(CachedTable::Products) > before);
+
+        // Nothing new was written, so the stamp is unchanged
+        assert_eq!(check_external_writes(&db, seen.as_deref()).await?, seen);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hit_rate() {
+        assert_eq!(CacheStats::default().hit_rate(), None);
+        let stats = CacheStats { hits: 3, misses: 1 };
+        assert!((stats.hit_rate().unwrap() - 0.75).abs() < f64::EPSILON);
+    }
+}
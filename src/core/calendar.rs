@@ -0,0 +1,307 @@
+//! Budget calendar - Budget months that start on a configurable day.
+//!
+//! Households paid on a fixed day can run their budget payday-to-payday (e.g. the 25th
+//! to the 24th) instead of by calendar month. The cycle start day is stored in the
+//! `system_state` table and defaults to the 1st, which gives plain calendar months.
+//! Monthly updates, report periods, and pace calculations all go through
+//! [`BudgetCycle`] so they agree on where a month begins.
+//!
+//! Days themselves follow the household timezone (UTC unless configured). Timestamps are
+//! stored in UTC; [`today`], [`local_date`] and [`utc_bounds`] convert between the two so
+//! "today" and month boundaries flip at local midnight everywhere.
+
+use crate::{
+    core::state,
+    errors::{Error, Result},
+};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use sea_orm::ConnectionTrait;
+
+const CYCLE_START_DAY_KEY: &str = "cycle_start_day";
+const TIMEZONE_KEY: &str = "timezone";
+
+/// Household timezone used when none has been configured.
+pub const DEFAULT_TIMEZONE: Tz = Tz::UTC;
+
+/// Cycle start day used when none has been configured (calendar months).
+pub const DEFAULT_CYCLE_START_DAY: u32 = 1;
+
+/// Latest allowed cycle start day, so every month contains it.
+pub const MAX_CYCLE_START_DAY: u32 = 28;
+
+/// One budget month, from its start day up to the day before the next start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetCycle {
+    /// First day of the cycle
+    pub start: NaiveDate,
+    /// Last day of the cycle (inclusive)
+    pub end: NaiveDate,
+}
+
+impl BudgetCycle {
+    /// Returns the cycle that contains `date` for cycles starting on `start_day`.
+    ///
+    /// `start_day` is clamped to `1..=MAX_CYCLE_START_DAY`.
+    #[must_use]
+    pub fn containing(date: NaiveDate, start_day: u32) -> Self {
+        let start_day = start_day.clamp(1, MAX_CYCLE_START_DAY);
+        let month = if date.day() >= start_day {
+            date
+        } else {
+            date.checked_sub_months(Months::new(1)).unwrap_or(date)
+        };
+        let start = month.with_day(start_day).unwrap_or(month);
+        let end = start
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(start);
+        Self { start, end }
+    }
+
+    /// Returns the cycle immediately before this one.
+    #[must_use]
+    pub fn previous(&self) -> Self {
+        let last_day = self.start.pred_opt().unwrap_or(self.start);
+        Self::containing(last_day, self.start.day())
+    }
+
+    /// Returns the cycle immediately after this one.
+    #[must_use]
+    pub fn next(&self) -> Self {
+        let first_day = self.end.succ_opt().unwrap_or(self.end);
+        Self::containing(first_day, self.start.day())
+    }
+
+    /// Number of days in the cycle.
+    #[must_use]
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days() + 1
+    }
+
+    /// Position of `date` within the cycle, starting at 1 on the first day.
+    #[must_use]
+    pub fn day_number(&self, date: NaiveDate) -> i64 {
+        ((date - self.start).num_days() + 1).clamp(1, self.days())
+    }
+}
+
+/// Gets the configured cycle start day, falling back to [`DEFAULT_CYCLE_START_DAY`].
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_cycle_start_day<C>(db: &C) -> Result<u32>
+where
+    C: ConnectionTrait,
+{
+    Ok(state::get_value(db, CYCLE_START_DAY_KEY)
+        .await?
+        .and_then(|value| value.parse().ok())
+        .filter(|day| (1..=MAX_CYCLE_START_DAY).contains(day))
+        .unwrap_or(DEFAULT_CYCLE_START_DAY))
+}
+
+/// Sets the day of the month budget cycles start on.
+///
+/// # Errors
+/// Returns an error if the day is outside `1..=MAX_CYCLE_START_DAY` (`Error::Config`) or
+/// the database write fails.
+pub async fn set_cycle_start_day<C>(db: &C, day: u32) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    if !(1..=MAX_CYCLE_START_DAY).contains(&day) {
+        return Err(Error::Config {
+            message: format!("Cycle start day must be between 1 and {MAX_CYCLE_START_DAY}"),
+        });
+    }
+
+    state::set_value(db, CYCLE_START_DAY_KEY, &day.to_string()).await
+}
+
+/// Returns the current cycle for `today` using the configured start day.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn current_cycle<C>(db: &C, today: NaiveDate) -> Result<BudgetCycle>
+where
+    C: ConnectionTrait,
+{
+    Ok(BudgetCycle::containing(
+        today,
+        get_cycle_start_day(db).await?,
+    ))
+}
+
+/// Gets the configured household timezone, falling back to [`DEFAULT_TIMEZONE`].
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_timezone<C>(db: &C) -> Result<Tz>
+where
+    C: ConnectionTrait,
+{
+    Ok(state::get_value(db, TIMEZONE_KEY)
+        .await?
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_TIMEZONE))
+}
+
+/// Sets the household timezone from an IANA name such as `America/New_York`.
+///
+/// # Errors
+/// Returns an error if the name isn't a known timezone (`Error::Config`) or the database
+/// write fails.
+pub async fn set_timezone<C>(db: &C, name: &str) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let tz: Tz = name.trim().parse().map_err(|_| Error::Config {
+        message: format!(
+            "'{}' is not a known timezone, e.g. America/New_York",
+            name.trim()
+        ),
+    })?;
+
+    state::set_value(db, TIMEZONE_KEY, tz.name()).await
+}
+
+/// Returns the household's local date at `at`.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn local_date<C>(db: &C, at: DateTime<Utc>) -> Result<NaiveDate>
+where
+    C: ConnectionTrait,
+{
+    Ok(at.with_timezone(&get_timezone(db).await?).date_naive())
+}
+
+/// Returns today's date in the household timezone.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn today<C>(db: &C) -> Result<NaiveDate>
+where
+    C: ConnectionTrait,
+{
+    local_date(db, Utc::now()).await
+}
+
+/// Returns the UTC instant local midnight starts `date` in `tz`.
+///
+/// Days whose midnight is skipped by a daylight-saving change start an hour later.
+#[must_use]
+pub fn start_of_day(tz: Tz, date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(midnight + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .map_or_else(|| midnight.and_utc(), |start| start.with_timezone(&Utc))
+}
+
+/// Converts the local days `from..=to` to the UTC range `[start, end)` for timestamp queries.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn utc_bounds<C>(
+    db: &C,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<(DateTime<Utc>, DateTime<Utc>)>
+where
+    C: ConnectionTrait,
+{
+    let tz = get_timezone(db).await?;
+    let after = to.checked_add_days(Days::new(1)).unwrap_or(to);
+    Ok((start_of_day(tz, from), start_of_day(tz, after)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap_or_default()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, hour: u32) -> DateTime<Utc> {
+        date(y, m, d)
+            .and_hms_opt(hour, 0, 0)
+            .unwrap_or_default()
+            .and_utc()
+    }
+
+    #[test]
+    fn test_cycle_containing() {
+        // Calendar months by default
+        let january = BudgetCycle::containing(date(2025, 1, 15), 1);
+        assert_eq!(january.start, date(2025, 1, 1));
+        assert_eq!(january.end, date(2025, 1, 31));
+        assert_eq!(january.day_number(date(2025, 1, 15)), 15);
+
+        // Payday on the 25th
+        let before_payday = BudgetCycle::containing(date(2025, 3, 10), 25);
+        assert_eq!(before_payday.start, date(2025, 2, 25));
+        assert_eq!(before_payday.end, date(2025, 3, 24));
+        assert_eq!(before_payday.days(), 28);
+        assert_eq!(before_payday.day_number(date(2025, 3, 10)), 14);
+
+        let on_payday = BudgetCycle::containing(date(2025, 3, 25), 25);
+        assert_eq!(on_payday, before_payday.next());
+        assert_eq!(on_payday.previous(), before_payday);
+
+        // Crossing the year boundary
+        let new_year = BudgetCycle::containing(date(2025, 1, 5), 25);
+        assert_eq!(new_year.start, date(2024, 12, 25));
+        assert_eq!(new_year.end, date(2025, 1, 24));
+    }
+
+    #[tokio::test]
+    async fn test_cycle_start_day_setting() -> Result<()> {
+        let db = setup_test_db().await?;
+        assert_eq!(get_cycle_start_day(&db).await?, DEFAULT_CYCLE_START_DAY);
+
+        set_cycle_start_day(&db, 25).await?;
+        assert_eq!(get_cycle_start_day(&db).await?, 25);
+        assert_eq!(
+            current_cycle(&db, date(2025, 3, 10)).await?.start,
+            date(2025, 2, 25)
+        );
+
+        assert!(matches!(
+            set_cycle_start_day(&db, 31).await,
+            Err(Error::Config { .. })
+        ));
+        assert!(set_cycle_start_day(&db, 0).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_timezone_day_boundaries() -> Result<()> {
+        let db = setup_test_db().await?;
+        assert_eq!(get_timezone(&db).await?, DEFAULT_TIMEZONE);
+
+        // 02:00 UTC is still the previous evening in New York
+        let late_evening = utc(2025, 3, 1, 2);
+        assert_eq!(local_date(&db, late_evening).await?, date(2025, 3, 1));
+        set_timezone(&db, " America/New_York ").await?;
+        assert_eq!(get_timezone(&db).await?, Tz::America__New_York);
+        assert_eq!(local_date(&db, late_evening).await?, date(2025, 2, 28));
+
+        // February's local days span 05:00 UTC Feb 1 to 05:00 UTC Mar 1
+        let (start, end) = utc_bounds(&db, date(2025, 2, 1), date(2025, 2, 28)).await?;
+        assert_eq!(start, utc(2025, 2, 1, 5));
+        assert_eq!(end, utc(2025, 3, 1, 5));
+
+        assert!(matches!(
+            set_timezone(&db, "Mars/Olympus_Mons").await,
+            Err(Error::Config { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,333 @@
+//! Category cap business logic - Monthly spending caps shared across a category.
+//!
+//! A cap limits the combined spending of every envelope in a category over the current
+//! budget month, counted like report spending: expenses less refunds, by transaction
+//! timestamp in the household timezone. Hard caps are enforced by [`check_cap`], which
+//! [`crate::core::transaction::create_transaction`] runs before every spend; soft caps
+//! never block a spend, and the bot warns once a category is past one.
+
+use crate::{
+    core::calendar::{self, BudgetCycle},
+    entities::{
+        CategoryCap, Envelope, Transaction, TransactionType, category_cap, envelope, transaction,
+    },
+    errors::{Error, Result},
+};
+use chrono::NaiveDate;
+use sea_orm::{Condition, QueryOrder, QuerySelect, Set, prelude::*};
+
+/// A category's spending this budget month, with its cap if one is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryStatus {
+    /// Envelope category
+    pub category: String,
+    /// Sum of the category's monthly envelope allocations
+    pub allocation: f64,
+    /// Spending this budget month across the category's envelopes
+    pub spent: f64,
+    /// The category's cap, if any
+    pub cap: Option<category_cap::Model>,
+}
+
+impl CategoryStatus {
+    /// Returns whether spending has gone past the cap.
+    #[must_use]
+    pub fn is_over_cap(&self) -> bool {
+        self.cap
+            .as_ref()
+            .is_some_and(|cap| self.spent > cap.cap + 0.005)
+    }
+}
+
+/// Sets (or replaces) the monthly cap for a category.
+///
+/// # Errors
+/// Returns an error if:
+/// - The category is empty (`Error::Config`)
+/// - The cap is not positive and finite (`Error::InvalidAmount`)
+/// - The database query or write fails
+pub async fn set_cap(
+    db: &DatabaseConnection,
+    category: &str,
+    cap: f64,
+    hard: bool,
+) -> Result<category_cap::Model> {
+    let category = category.trim();
+    if category.is_empty() {
+        return Err(Error::Config {
+            message: "Category cannot be empty".to_string(),
+        });
+    }
+    if !cap.is_finite() || cap <= 0.0 {
+        return Err(Error::InvalidAmount { amount: cap });
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    if let Some(existing) = get_cap(db, category).await? {
+        let mut active_model: category_cap::ActiveModel = existing.into();
+        active_model.cap = Set(cap);
+        active_model.hard = Set(hard);
+        active_model.updated_at = Set(now);
+        return active_model.update(db).await.map_err(Into::into);
+    }
+
+    let record = category_cap::ActiveModel {
+        category: Set(category.to_string()),
+        cap: Set(cap),
+        hard: Set(hard),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+    record.insert(db).await.map_err(Into::into)
+}
+
+/// Removes a category's cap.
+///
+/// # Returns
+/// `true` if the category had a cap
+///
+/// # Errors
+/// Returns an error if the database query or delete fails.
+pub async fn remove_cap(db: &DatabaseConnection, category: &str) -> Result<bool> {
+    let Some(existing) = get_cap(db, category.trim()).await? else {
+        return Ok(false);
+    };
+    CategoryCap::delete_by_id(existing.id).exec(db).await?;
+    Ok(true)
+}
+
+/// Finds the cap for a category, if one is set.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_cap<C>(db: &C, category: &str) -> Result<Option<category_cap::Model>>
+where
+    C: ConnectionTrait,
+{
+    CategoryCap::find()
+        .filter(category_cap::Column::Category.eq(category))
+        .one(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Retrieves every category cap, ordered by category.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_caps(db: &DatabaseConnection) -> Result<Vec<category_cap::Model>> {
+    CategoryCap::find()
+        .order_by_asc(category_cap::Column::Category)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Totals the spending of a category's envelopes within a budget month.
+///
+/// Round-ups move change to savings rather than spending it, so they aren't counted.
+async fn category_spent<C>(db: &C, category: &str, cycle: &BudgetCycle) -> Result<f64>
+where
+    C: ConnectionTrait,
+{
+    let envelope_ids: Vec<i64> = Envelope::find()
+        .select_only()
+        .column(envelope::Column::Id)
+        .filter(envelope::Column::Category.eq(category))
+        .into_tuple()
+        .all(db)
+        .await?;
+    if envelope_ids.is_empty() {
+        return Ok(0.0);
+    }
+
+    let (start, end) = calendar::utc_bounds(db, cycle.start, cycle.end).await?;
+    let total: Option<Option<f64>> = Transaction::find_active()
+        .select_only()
+        .column_as(transaction::Column::Amount.sum(), "total")
+        .filter(transaction::Column::EnvelopeId.is_in(envelope_ids))
+        .filter(transaction::Column::TransactionType.ne(TransactionType::RoundUp))
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .filter(
+            Condition::any()
+                .add(transaction::Column::Amount.lt(0.0))
+                .add(transaction::Column::TransactionType.is_in(TransactionType::REFUNDS)),
+        )
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    Ok(-total.flatten().unwrap_or(0.0))
+}
+
+/// Checks a spend of `amount` (positive) from a category against its hard cap.
+///
+/// Soft caps and uncapped categories always pass.
+///
+/// # Errors
+/// Returns an error if:
+/// - The spend would take the category's spending this budget month past a hard cap
+///   (`Error::CategoryCapExceeded`)
+/// - The database query fails
+pub async fn check_cap<C>(db: &C, category: &str, amount: f64, today: NaiveDate) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let Some(cap) = get_cap(db, category).await?.filter(|cap| cap.hard) else {
+        return Ok(());
+    };
+
+    let cycle = calendar::current_cycle(db, today).await?;
+    let spent = category_spent(db, category, &cycle).await? + amount;
+    if spent > cap.cap + 0.005 {
+        return Err(Error::CategoryCapExceeded {
+            category: cap.category,
+            cap: cap.cap,
+            spent,
+        });
+    }
+    Ok(())
+}
+
+/// Returns a category's spending this budget month and its cap.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_category_status(
+    db: &DatabaseConnection,
+    category: &str,
+    today: NaiveDate,
+) -> Result<CategoryStatus> {
+    let cycle = calendar::current_cycle(db, today).await?;
+    let allocation = crate::core::envelope::get_all_active_envelopes(db)
+        .await?
+        .iter()
+        .filter(|env| env.category == category)
+        .map(|env| env.allocation)
+        .sum();
+
+    Ok(CategoryStatus {
+        category: category.to_string(),
+        allocation,
+        spent: category_spent(db, category, &cycle).await?,
+        cap: get_cap(db, category).await?,
+    })
+}
+
+/// Returns the status of every category in use by an active envelope or capped.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_category_statuses(
+    db: &DatabaseConnection,
+    today: NaiveDate,
+) -> Result<Vec<CategoryStatus>> {
+    let cycle = calendar::current_cycle(db, today).await?;
+    let envelopes = crate::core::envelope::get_all_active_envelopes(db).await?;
+    let mut caps = get_caps(db).await?;
+
+    let mut categories: Vec<String> = envelopes
+        .iter()
+        .map(|env| env.category.clone())
+        .chain(caps.iter().map(|cap| cap.category.clone()))
+        .collect();
+    categories.sort();
+    categories.dedup();
+
+    let mut statuses = Vec::with_capacity(categories.len());
+    for category in categories {
+        let allocation = envelopes
+            .iter()
+            .filter(|env| env.category == category)
+            .map(|env| env.allocation)
+            .sum();
+        let cap = caps
+            .iter()
+            .position(|cap| cap.category == category)
+            .map(|index| caps.swap_remove(index));
+        statuses.push(CategoryStatus {
+            spent: category_spent(db, &category, &cycle).await?,
+            category,
+            allocation,
+            cap,
+        });
+    }
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{core::transaction, test_utils::*};
+
+    #[tokio::test]
+    async fn test_hard_cap_denies_spend_across_category() -> Result<()> {
+        let db = setup_test_db().await?;
+        let movies =
+            create_custom_envelope(&db, "movies", None, "fun", 200.0, false, false).await?;
+        let games = create_custom_envelope(&db, "games", None, "fun", 200.0, false, false).await?;
+        for envelope_id in [movies.id, games.id] {
+            transaction::create_transaction(
+                &db,
+                envelope_id,
+                200.0,
+                "Deposit".to_string(),
+                "test_user".to_string(),
+                None,
+                TransactionType::AddFunds,
+            )
+            .await?;
+        }
+        set_cap(&db, "fun", 300.0, true).await?;
+
+        transaction::create_transaction(
+            &db,
+            movies.id,
+            -180.0,
+            "Concert".to_string(),
+            "test_user".to_string(),
+            None,
+            TransactionType::Spend,
+        )
+        .await?;
+        let result = transaction::create_transaction(
+            &db,
+            games.id,
+            -150.0,
+            "Console".to_string(),
+            "test_user".to_string(),
+            None,
+            TransactionType::Spend,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(Error::CategoryCapExceeded { cap, spent, .. }) if cap == 300.0 && spent == 330.0
+        ));
+
+        // Soft caps allow the spend and report the overage
+        set_cap(&db, "fun", 300.0, false).await?;
+        transaction::create_transaction(
+            &db,
+            games.id,
+            -150.0,
+            "Console".to_string(),
+            "test_user".to_string(),
+            None,
+            TransactionType::Spend,
+        )
+        .await?;
+        let today = chrono::Utc::now().date_naive();
+        let status = get_category_status(&db, "fun", today).await?;
+        assert_eq!(status.spent, 330.0);
+        assert_eq!(status.allocation, 400.0);
+        assert!(status.is_over_cap());
+
+        assert!(remove_cap(&db, "fun").await?);
+        assert!(!remove_cap(&db, "fun").await?);
+        assert!(!get_category_status(&db, "fun", today).await?.is_over_cap());
+        Ok(())
+    }
+}
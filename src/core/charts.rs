@@ -0,0 +1,210 @@
+//! Chart rendering business logic - PNG images for reports.
+//!
+//! Renders per-envelope spend vs allocation as a bar chart using `plotters`. Only
+//! compiled with the `charts` feature; the bot attaches the resulting PNG to report
+//! embeds and falls back to text-only replies when rendering fails.
+
+use crate::{
+    core::setup::Currency,
+    errors::{Error, Result},
+};
+use image::{ColorType, ImageEncoder, codecs::png::PngEncoder};
+use plotters::prelude::*;
+use std::fmt::Display;
+
+/// File name used when attaching the report chart to a Discord message.
+pub const REPORT_CHART_FILENAME: &str = "report.png";
+
+/// Chart width in pixels.
+const WIDTH: u32 = 900;
+/// Chart height in pixels.
+const HEIGHT: u32 = 500;
+
+/// Allocation bar color (matches the report embed blue).
+const ALLOCATION_COLOR: RGBColor = RGBColor(0x34, 0x98, 0xDB);
+/// Spent bar color when within allocation.
+const SPENT_COLOR: RGBColor = RGBColor(0x1F, 0x4E, 0x79);
+/// Spent bar color when over allocation.
+const OVERSPENT_COLOR: RGBColor = RGBColor(0xE7, 0x4C, 0x3C);
+
+/// One envelope's data point in a spend vs allocation chart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartBar {
+    /// Label shown under the bar (e.g. "groceries (Shared)")
+    pub label: String,
+    /// Amount spent this period (negative values are drawn as zero)
+    pub spent: f64,
+    /// Monthly allocation
+    pub allocation: f64,
+}
+
+/// Renders a bar chart of spend vs allocation for each envelope as PNG bytes.
+///
+/// Each envelope gets a wide, light allocation bar with a narrower spent bar drawn
+/// over it. Spent bars that exceed the allocation are highlighted in red. The amount
+/// axis is labelled with `currency`'s symbol.
+///
+/// # Errors
+/// Returns an error if there is nothing to chart, or if drawing or PNG encoding fails
+/// (for example, when no system font is available for labels).
+pub fn render_spend_vs_allocation(bars: &[ChartBar], currency: Currency) -> Result<Vec<u8>> {
+    if bars.is_empty() {
+        return Err(Error::Chart {
+            message: "No envelopes to chart".to_string(),
+        });
+    }
+
+    let mut buffer = vec![0_u8; (WIDTH * HEIGHT * 3).try_into()?];
+    draw_spend_vs_allocation(&mut buffer, bars, currency)?;
+
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(&buffer, WIDTH, HEIGHT, ColorType::Rgb8)
+        .map_err(chart_error)?;
+
+    Ok(png)
+}
+
+/// Draws the chart into a raw RGB buffer of `WIDTH` x `HEIGHT` pixels.
+fn draw_spend_vs_allocation(
+    buffer: &mut [u8],
+    bars: &[ChartBar],
+    currency: Currency,
+) -> Result<()> {
+    let root = BitMapBackend::with_buffer(buffer, (WIDTH, HEIGHT)).into_drawing_area();
+    root.fill(&WHITE).map_err(chart_error)?;
+
+    let max_amount = bars
+        .iter()
+        .map(|bar| bar.allocation.max(bar.spent))
+        .fold(0.0, f64::max)
+        .max(1.0)
+        * 1.1;
+
+    let mut chart = ChartBuilder::on(&root)
+        .caption("Spend vs Allocation", ("sans-serif", 28))
+        .margin(20)
+        .x_label_area_size(50)
+        .y_label_area_size(70)
+        .build_cartesian_2d((0..bars.len()).into_segmented(), 0.0..max_amount)
+        .map_err(chart_error)?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(bars.len())
+        .x_label_formatter(&|value| match value {
+            SegmentValue::CenterOf(index) => bars
+                .get(*index)
+                .map_or_else(String::new, |bar| bar.label.clone()),
+            _ => String::new(),
+        })
+        .y_label_formatter(&|amount| format!("{}{amount:.0}", currency.symbol))
+        .draw()
+        .map_err(chart_error)?;
+
+    chart
+        .draw_series(bars.iter().enumerate().map(|(index, bar)| {
+            let mut rect = Rectangle::new(
+                [
+                    (SegmentValue::Exact(index), 0.0),
+                    (SegmentValue::Exact(index + 1), bar.allocation),
+                ],
+                ALLOCATION_COLOR.mix(0.35).filled(),
+            );
+            rect.set_margin(0, 0, 8, 8);
+            rect
+        }))
+        .map_err(chart_error)?
+        .label("Allocation")
+        .legend(|(x, y)| {
+            Rectangle::new(
+                [(x, y - 5), (x + 12, y + 5)],
+                ALLOCATION_COLOR.mix(0.35).filled(),
+            )
+        });
+
+    chart
+        .draw_series(bars.iter().enumerate().map(|(index, bar)| {
+            let color = if bar.spent > bar.allocation {
+                OVERSPENT_COLOR
+            } else {
+                SPENT_COLOR
+            };
+            let mut rect = Rectangle::new(
+                [
+                    (SegmentValue::Exact(index), 0.0),
+                    (SegmentValue::Exact(index + 1), bar.spent.max(0.0)),
+                ],
+                color.filled(),
+            );
+            rect.set_margin(0, 0, 24, 24);
+            rect
+        }))
+        .map_err(chart_error)?
+        .label("Spent")
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 12, y + 5)], SPENT_COLOR.filled()));
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(chart_error)?;
+
+    root.present().map_err(chart_error)?;
+    Ok(())
+}
+
+/// Converts a drawing or encoding error into an [`Error::Chart`].
+fn chart_error(err: impl Display) -> Error {
+    Error::Chart {
+        message: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    /// Whether a system font is available for labels; minimal CI images often lack one.
+    fn system_font_available() -> bool {
+        ("sans-serif", 12).into_font().box_size("x").is_ok()
+    }
+
+    #[test]
+    fn test_render_empty_is_error() {
+        assert!(matches!(
+            render_spend_vs_allocation(&[], Currency::default()),
+            Err(Error::Chart { .. })
+        ));
+    }
+
+    #[test]
+    fn test_render_produces_png() -> Result<()> {
+        if !system_font_available() {
+            eprintln!("skipping test_render_produces_png: no system font for chart labels");
+            return Ok(());
+        }
+
+        let bars = vec![
+            ChartBar {
+                label: "groceries (Shared)".to_string(),
+                spent: 320.0,
+                allocation: 500.0,
+            },
+            ChartBar {
+                label: "game (Alice)".to_string(),
+                spent: 95.0,
+                allocation: 80.0,
+            },
+        ];
+
+        let png = render_spend_vs_allocation(&bars, Currency::default())?;
+        assert_eq!(png.get(..8).unwrap(), b"\x89PNG\r\n\x1a\n");
+
+        Ok(())
+    }
+}
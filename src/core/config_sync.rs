@@ -0,0 +1,540 @@
+//! Config synchronization business logic - Applies config.toml changes to the database.
+//!
+//! Startup seeding only creates envelopes on a fresh database. This module diffs a parsed
+//! [`Config`] against the existing envelopes and applies additions and setting updates, so
+//! edits to config.toml can take effect without a restart. Envelopes are never deleted
+//! and balances are never touched. [`export_envelopes`] goes the other way, turning the
+//! current envelopes back into a [`Config`] for `/envelope export`, and
+//! [`envelope_table`] previews either direction in Discord.
+
+use crate::{
+    config::envelopes::{Config, EnvelopeConfig},
+    core::{
+        cache::{self, CachedTable},
+        envelope,
+        setup::{self, Currency},
+        table::{Column, Table},
+    },
+    entities::envelope as envelope_entity,
+    errors::Result,
+};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use std::fmt::Write;
+
+/// A single envelope whose settings were changed to match the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeChange {
+    /// Envelope name
+    pub name: String,
+    /// Owner for individual envelopes, `None` for shared
+    pub user_id: Option<String>,
+    /// Human-readable descriptions of each changed field
+    pub changes: Vec<String>,
+}
+
+/// Summary of a config synchronization run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigSyncResult {
+    /// Names of shared envelopes created from the config
+    pub created: Vec<String>,
+    /// Envelopes whose settings were updated
+    pub updated: Vec<EnvelopeChange>,
+    /// Number of existing envelopes that already matched the config
+    pub unchanged: usize,
+}
+
+impl ConfigSyncResult {
+    /// Returns true if the run made no changes.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// Applies the envelope definitions in `config` to the database.
+///
+/// - Shared envelopes missing from the database are created.
+/// - Existing shared envelopes, and every user's copy of an individual envelope, have
+///   their category, allocation, rollover, and sinking-fund settings updated to match.
+/// - Individual envelopes are never created here; they are instantiated per user.
+///
+/// # Errors
+/// Returns an error if a config entry is invalid (e.g. negative allocation) or a database
+/// query or update fails.
+pub async fn sync_envelopes(db: &DatabaseConnection, config: &Config) -> Result<ConfigSyncResult> {
+    // Validate every entry up front so a bad one can't leave the sync half-applied
+    for env_config in &config.envelopes {
+        envelope::validate_sinking_fund(
+            env_config.contribution,
+            env_config.goal,
+            env_config.rollover,
+        )?;
+    }
+
+    let mut result = ConfigSyncResult::default();
+    let currency = setup::get_currency_format(db).await?;
+
+    for env_config in &config.envelopes {
+        let existing = if env_config.is_individual {
+            envelope::get_individual_envelopes_by_name(db, &env_config.name).await?
+        } else {
+            envelope::get_shared_envelope_by_name(db, &env_config.name)
+                .await?
+                .into_iter()
+                .collect()
+        };
+
+        if existing.is_empty() {
+            if !env_config.is_individual {
+                create_from_config(db, env_config).await?;
+                result.created.push(env_config.name.clone());
+            }
+            continue;
+        }
+
+        for env in existing {
+            let changes = diff_envelope(&env, env_config, currency);
+            if changes.is_empty() {
+                result.unchanged += 1;
+                continue;
+            }
+
+            let name = env.name.clone();
+            let user_id = env.user_id.clone();
+            apply_config(db, env, env_config).await?;
+            result.updated.push(EnvelopeChange {
+                name,
+                user_id,
+                changes,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+/// Lists the settings of `env` that differ from `env_config`, with amounts in `currency`.
+#[must_use]
+pub fn diff_envelope(
+    env: &envelope_entity::Model,
+    env_config: &EnvelopeConfig,
+    currency: Currency,
+) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if env.category != env_config.category {
+        changes.push(format!(
+            "category '{}' → '{}'",
+            env.category, env_config.category
+        ));
+    }
+    if amount_changed(env.allocation, env_config.allocation) {
+        changes.push(format!(
+            "allocation {} → {}",
+            currency.format(env.allocation),
+            currency.format(env_config.allocation)
+        ));
+    }
+    if env.rollover != env_config.rollover {
+        changes.push(format!(
+            "rollover {} → {}",
+            on_off(env.rollover),
+            on_off(env_config.rollover)
+        ));
+    }
+    if amount_changed(env.contribution, env_config.contribution) {
+        changes.push(format!(
+            "contribution {} → {}",
+            currency.format(env.contribution),
+            currency.format(env_config.contribution)
+        ));
+    }
+    let goal_changed = match (env.goal, env_config.goal) {
+        (Some(old), Some(new)) => amount_changed(old, new),
+        (None, None) => false,
+        _ => true,
+    };
+    if goal_changed {
+        changes.push(format!(
+            "goal {} → {}",
+            format_goal(env.goal, currency),
+            format_goal(env_config.goal, currency)
+        ));
+    }
+
+    changes
+}
+
+/// Formats a sync result for display in Discord.
+///
+/// # Errors
+/// Returns an error if string formatting fails.
+pub fn format_sync_summary(result: &ConfigSyncResult) -> Result<String> {
+    let mut summary = String::new();
+
+    if result.is_empty() {
+        writeln!(
+            &mut summary,
+            "No changes - {} envelope(s) already match config.toml",
+            result.unchanged
+        )?;
+        return Ok(summary);
+    }
+
+    if !result.created.is_empty() {
+        writeln!(&mut summary, "Created:")?;
+        for name in &result.created {
+            writeln!(&mut summary, "  + {name}")?;
+        }
+    }
+
+    if !result.updated.is_empty() {
+        writeln!(&mut summary, "Updated:")?;
+        for change in &result.updated {
+            let owner = change
+                .user_id
+                .as_deref()
+                .map_or_else(String::new, |uid| format!(" (user {uid})"));
+            writeln!(
+                &mut summary,
+                "  ~ {}{owner}: {}",
+                change.name,
+                change.changes.join(", ")
+            )?;
+        }
+    }
+
+    writeln!(&mut summary, "Unchanged: {}", result.unchanged)?;
+    Ok(summary)
+}
+
+/// Builds a config.toml snapshot of the active envelopes.
+///
+/// Individual envelopes appear once, with the settings of the first member's copy in
+/// display order. Balances and settings config.toml doesn't carry (such as minimum
+/// balances or privacy) are left out.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn export_envelopes(db: &DatabaseConnection) -> Result<Config> {
+    let mut envelopes: Vec<EnvelopeConfig> = Vec::new();
+    for env in envelope::get_all_active_envelopes(db).await? {
+        if env.is_individual
+            && envelopes
+                .iter()
+                .any(|existing| existing.is_individual && existing.name == env.name)
+        {
+            continue;
+        }
+        envelopes.push(EnvelopeConfig {
+            name: env.name,
+            category: env.category,
+            allocation: env.allocation,
+            is_individual: env.is_individual,
+            rollover: env.rollover,
+            contribution: env.contribution,
+            goal: env.goal,
+        });
+    }
+    Ok(Config { envelopes })
+}
+
+/// Lays out a config's envelopes as a table, one row per envelope, for previewing an
+/// export or import.
+#[must_use]
+pub fn envelope_table(config: &Config) -> Table {
+    let mut table = Table::new(vec![
+        Column::left("Envelope"),
+        Column::left("Category").max_width(16),
+        Column::right("Allocation"),
+        Column::left("Scope"),
+        Column::left("Rollover"),
+        Column::right("Goal"),
+    ]);
+    for env in &config.envelopes {
+        table.row(vec![
+            env.name.clone(),
+            env.category.clone(),
+            format!("{:.2}", env.allocation),
+            if env.is_individual {
+                "individual"
+            } else {
+                "shared"
+            }
+            .to_string(),
+            on_off(env.rollover).to_string(),
+            env.goal
+                .map_or_else(String::new, |goal| format!("{goal:.2}")),
+        ]);
+    }
+    table
+}
+
+/// Creates a shared envelope from its config entry.
+async fn create_from_config(db: &DatabaseConnection, env_config: &EnvelopeConfig) -> Result<()> {
+    let created = envelope::create_envelope(
+        db,
+        env_config.name.clone(),
+        None,
+        env_config.category.clone(),
+        env_config.allocation,
+        false,
+        env_config.rollover,
+    )
+    .await?;
+
+    if env_config.contribution > 0.0 || env_config.goal.is_some() {
+        envelope::set_sinking_fund(db, created.id, env_config.contribution, env_config.goal)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Overwrites an envelope's settings with the values from its config entry.
+async fn apply_config(
+    db: &DatabaseConnection,
+    env: envelope_entity::Model,
+    env_config: &EnvelopeConfig,
+) -> Result<()> {
+    let env_id = env.id;
+    let mut active_model: envelope_entity::ActiveModel = env.into();
+    active_model.category = Set(env_config.category.clone());
+    active_model.allocation = Set(env_config.allocation);
+    active_model.rollover = Set(env_config.rollover);
+    active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+
+    // Sinking-fund settings go through the validating setter
+    envelope::set_sinking_fund(db, env_id, env_config.contribution, env_config.goal).await?;
+    Ok(())
+}
+
+/// Compares two dollar amounts, ignoring sub-cent floating point noise.
+fn amount_changed(old: f64, new: f64) -> bool {
+    (old - new).abs() >= 0.005
+}
+
+const fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
+
+fn format_goal(goal: Option<f64>, currency: Currency) -> String {
+    goal.map_or_else(|| "none".to_string(), |amount| currency.format(amount))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::test_utils::*;
+
+    fn config_from(toml_str: &str) -> Config {
+        toml::from_str(toml_str).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sync_creates_and_updates() -> Result<()> {
+        let db = setup_test_db().await?;
+        create_test_envelope(&db, "groceries").await?;
+        create_custom_envelope(
+            &db,
+            "game",
+            Some("user1".to_string()),
+            "quality_of_life",
+            80.0,
+            true,
+            true,
+        )
+        .await?;
+
+        let config = config_from(
+            r#"
+            [[envelopes]]
+            name = "groceries"
+            category = "necessary"
+            allocation = 550.0
+            is_individual = false
+            rollover = false
+
+            [[envelopes]]
+            name = "utilities"
+            category = "necessary"
+            allocation = 200.0
+            is_individual = false
+            rollover = false
+            goal = 1000.0
+
+            [[envelopes]]
+            name = "game"
+            category = "quality_of_life"
+            allocation = 80.0
+            is_individual = true
+            rollover = true
+
+            [[envelopes]]
+            name = "drink"
+            category = "quality_of_life"
+            allocation = 60.0
+            is_individual = true
+            rollover = false
+            "#,
+        );
+
+        let result = sync_envelopes(&db, &config).await?;
+        assert_eq!(result.created, vec!["utilities".to_string()]);
+        assert_eq!(result.updated.len(), 1);
+        assert_eq!(result.updated[0].name, "groceries");
+        assert_eq!(result.unchanged, 1); // game already matches
+
+        let groceries = envelope::get_shared_envelope_by_name(&db, "groceries")
+            .await?
+            .unwrap();
+        assert_eq!(groceries.allocation, 550.0);
+        let utilities = envelope::get_shared_envelope_by_name(&db, "utilities")
+            .await?
+            .unwrap();
+        assert_eq!(utilities.goal, Some(1000.0));
+
+        // Individual templates are not instantiated without a user
+        assert!(
+            envelope::get_individual_envelopes_by_name(&db, "drink")
+                .await?
+                .is_empty()
+        );
+
+        // A second run is a no-op
+        let again = sync_envelopes(&db, &config).await?;
+        assert!(again.is_empty());
+        assert_eq!(again.unchanged, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sync_rejects_invalid_sinking_fund_before_writing() -> Result<()> {
+        let db = setup_test_db().await?;
+        create_test_envelope(&db, "groceries").await?;
+
+        let config = config_from(
+            r#"
+            [[envelopes]]
+            name = "groceries"
+            category = "necessary"
+            allocation = 550.0
+            is_individual = false
+            rollover = false
+
+            [[envelopes]]
+            name = "vacation"
+            category = "quality_of_life"
+            allocation = 100.0
+            is_individual = false
+            rollover = false
+            contribution = 50.0
+            "#,
+        );
+
+        assert!(matches!(
+            sync_envelopes(&db, &config).await,
+            Err(crate::errors::Error::Config { .. })
+        ));
+
+        // Neither the update nor the create went through
+        let groceries = envelope::get_shared_envelope_by_name(&db, "groceries")
+            .await?
+            .unwrap();
+        assert_ne!(groceries.allocation, 550.0);
+        assert!(
+            envelope::get_shared_envelope_by_name(&db, "vacation")
+                .await?
+                .is_none()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sync_updates_every_individual_copy() -> Result<()> {
+        let db = setup_test_db().await?;
+        for user in ["user1", "user2"] {
+            create_custom_envelope(
+                &db,
+                "game",
+                Some(user.to_string()),
+                "quality_of_life",
+                80.0,
+                true,
+                true,
+            )
+            .await?;
+        }
+
+        let config = config_from(
+            r#"
+            [[envelopes]]
+            name = "game"
+            category = "quality_of_life"
+            allocation = 100.0
+            is_individual = true
+            rollover = true
+            contribution = 10.0
+            "#,
+        );
+
+        let result = sync_envelopes(&db, &config).await?;
+        assert_eq!(result.updated.len(), 2);
+        for env in envelope::get_individual_envelopes_by_name(&db, "game").await? {
+            assert_eq!(env.allocation, 100.0);
+            assert_eq!(env.contribution, 10.0);
+        }
+
+        let summary = format_sync_summary(&result)?;
+        assert!(summary.contains("allocation $80.00 → $100.00"));
+        assert!(summary.contains("(user user2)"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_round_trips_through_sync() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries =
+            create_custom_envelope(&db, "groceries", None, "necessary", 100.0, false, true).await?;
+        envelope::set_sinking_fund(&db, groceries.id, 25.0, Some(300.0)).await?;
+        for user in ["user1", "user2"] {
+            create_custom_envelope(
+                &db,
+                "game",
+                Some(user.to_string()),
+                "quality_of_life",
+                80.0,
+                true,
+                true,
+            )
+            .await?;
+        }
+
+        // Individual envelopes are exported once
+        let exported = export_envelopes(&db).await?;
+        assert_eq!(exported.envelopes.len(), 2);
+        let text = crate::config::envelopes::format_config(&exported)?;
+        assert!(text.contains("[[envelopes]]"));
+        assert!(text.contains("goal = 300.0"));
+        let preview = envelope_table(&exported).render();
+        assert_eq!(preview.lines().count(), 3);
+        assert!(
+            preview
+                .lines()
+                .any(|line| line.starts_with("game") && line.contains("individual"))
+        );
+
+        // Importing the snapshot into the same database changes nothing
+        let parsed = crate::config::envelopes::parse_config(&text)?;
+        let result = sync_envelopes(&db, &parsed).await?;
+        assert!(result.is_empty(), "{result:?}");
+        assert_eq!(result.unchanged, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+//! Cooldown business logic - Persisted per-user attempt counters.
+//!
+//! poise's built-in cooldowns live in memory and reset whenever the bot restarts. Commands
+//! that must stay rate limited across restarts (like `/update`) record each attempt here
+//! instead, in the `system_state` table under `cooldown:<command>:<user>`.
+
+use crate::{core::state, errors::Result};
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+
+/// Prefix of the `system_state` keys holding cooldown counters.
+const COOLDOWN_KEY_PREFIX: &str = "cooldown:";
+
+/// Result of recording a command attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttemptOutcome {
+    /// The command may run; a new cooldown window starts now
+    Allowed,
+    /// The command is still cooling down
+    Limited {
+        /// Time left until the command may run again
+        remaining: Duration,
+        /// Attempts made in the current window, including this one
+        attempts: u32,
+    },
+}
+
+/// Records an attempt by `user_id` to run `command` and checks it against `cooldown`.
+///
+/// An allowed attempt starts a new window. Attempts inside the window are counted and
+/// rejected with the time remaining.
+///
+/// # Errors
+/// Returns an error if reading or writing the counter fails.
+pub async fn record_attempt(
+    db: &DatabaseConnection,
+    command: &str,
+    user_id: &str,
+    cooldown: Duration,
+) -> Result<AttemptOutcome> {
+    record_attempt_at(db, command, user_id, cooldown, Utc::now()).await
+}
+
+/// [`record_attempt`] with an explicit current time, so windows can be tested.
+async fn record_attempt_at(
+    db: &DatabaseConnection,
+    command: &str,
+    user_id: &str,
+    cooldown: Duration,
+    now: DateTime<Utc>,
+) -> Result<AttemptOutcome> {
+    let key = format!("{COOLDOWN_KEY_PREFIX}{command}:{user_id}");
+
+    if let Some((window_start, attempts)) = state::get_value(db, &key)
+        .await?
+        .as_deref()
+        .and_then(parse_counter)
+    {
+        // A clock that moved backwards counts as no time elapsed
+        let elapsed = (now - window_start).to_std().unwrap_or_default();
+        if let Some(remaining) = cooldown.checked_sub(elapsed).filter(|r| !r.is_zero()) {
+            let attempts = attempts.saturating_add(1);
+            state::set_value(db, &key, &format_counter(window_start, attempts)).await?;
+            return Ok(AttemptOutcome::Limited {
+                remaining,
+                attempts,
+            });
+        }
+    }
+
+    state::set_value(db, &key, &format_counter(now, 1)).await?;
+    Ok(AttemptOutcome::Allowed)
+}
+
+/// Serializes a counter as `<window start RFC 3339>;<attempts>`.
+fn format_counter(window_start: DateTime<Utc>, attempts: u32) -> String {
+    format!("{};{attempts}", window_start.to_rfc3339())
+}
+
+/// Parses a counter written by [`format_counter`]; malformed values are ignored.
+fn parse_counter(value: &str) -> Option<(DateTime<Utc>, u32)> {
+    let (start, attempts) = value.split_once(';')?;
+    let start = DateTime::parse_from_rfc3339(start)
+        .ok()?
+        .with_timezone(&Utc);
+    Some((start, attempts.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_record_attempt_window() -> Result<()> {
+        let db = setup_test_db().await?;
+        let cooldown = Duration::from_mins(1);
+        let start = Utc::now();
+
+        let first = record_attempt_at(&db, "update", "alice", cooldown, start).await?;
+        assert_eq!(first, AttemptOutcome::Allowed);
+
+        let later = start + chrono::Duration::seconds(20);
+        let second = record_attempt_at(&db, "update", "alice", cooldown, later).await?;
+        assert_eq!(
+            second,
+            AttemptOutcome::Limited {
+                remaining: Duration::from_secs(40),
+                attempts: 2
+            }
+        );
+        let third = record_attempt_at(&db, "update", "alice", cooldown, later).await?;
+        assert!(matches!(third, AttemptOutcome::Limited { attempts: 3, .. }));
+
+        // Other users and commands have their own counters
+        let other = record_attempt_at(&db, "update", "bob", cooldown, later).await?;
+        assert_eq!(other, AttemptOutcome::Allowed);
+
+        // Once the window passes the command is allowed again
+        let after = start + chrono::Duration::seconds(61);
+        let fourth = record_attempt_at(&db, "update", "alice", cooldown, after).await?;
+        assert_eq!(fourth, AttemptOutcome::Allowed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_counter() {
+        let now = Utc::now();
+        let parsed = parse_counter(&format_counter(now, 4)).unwrap();
+        assert_eq!(parsed.1, 4);
+        assert!(parse_counter("garbage").is_none());
+        assert!(parse_counter("2025-01-01T00:00:00Z;x").is_none());
+    }
+}
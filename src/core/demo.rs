@@ -0,0 +1,384 @@
+//! Demo data - A sample household for trying the bot without real data.
+//!
+//! Started with `--demo`, the bot runs on a disposable in-memory database filled by
+//! [`seed`]: shared envelopes for a two-member household, an individual "Fun Money"
+//! envelope for each member, a few products, and three budget months of spending ending
+//! today. Amounts come from a fixed-seed generator, so every run shows the same data,
+//! which keeps screenshots reproducible and lets tests use [`seed`] as a ready-made
+//! fixture.
+
+use crate::{
+    core::{calendar, envelope as envelope_core, monthly, product, setup, transaction as tx_core},
+    entities::{TransactionType, envelope, transaction},
+    errors::{Error, Result},
+};
+use chrono::{DateTime, Days, NaiveDate, TimeDelta, Utc};
+use sea_orm::{Set, prelude::*};
+
+/// Discord user IDs and nicknames of the demo household's members.
+pub const DEMO_MEMBERS: [(&str, &str); 2] = [
+    ("100000000000000001", "Alex"),
+    ("100000000000000002", "Sam"),
+];
+
+/// Seed of the generator behind the demo amounts, dates, and descriptions.
+const DEMO_SEED: u64 = 0x0E4E_10BE;
+
+/// A sample envelope and the spending generated for it each month.
+struct DemoEnvelope {
+    name: &'static str,
+    category: &'static str,
+    allocation: f64,
+    rollover: bool,
+    pinned: bool,
+    /// Descriptions spends are picked from; the first word is the merchant
+    descriptions: &'static [&'static str],
+    /// Smallest and largest spend
+    amounts: (f64, f64),
+    /// Spends per full month
+    spends: u32,
+}
+
+const SHARED_ENVELOPES: &[DemoEnvelope] = &[
+    DemoEnvelope {
+        name: "Rent",
+        category: "Housing",
+        allocation: 1500.0,
+        rollover: false,
+        pinned: true,
+        descriptions: &["Rent"],
+        amounts: (1500.0, 1500.0),
+        spends: 1,
+    },
+    DemoEnvelope {
+        name: "Groceries",
+        category: "Food",
+        allocation: 600.0,
+        rollover: false,
+        pinned: true,
+        descriptions: &["Costco run", "Safeway", "Trader Joe's", "Farmers market"],
+        amounts: (25.0, 90.0),
+        spends: 8,
+    },
+    DemoEnvelope {
+        name: "Dining Out",
+        category: "Food",
+        allocation: 200.0,
+        rollover: false,
+        pinned: false,
+        descriptions: &["Chipotle", "Pizza night", "Thai takeout", "Starbucks"],
+        amounts: (8.0, 45.0),
+        spends: 6,
+    },
+    DemoEnvelope {
+        name: "Gas",
+        category: "Transportation",
+        allocation: 150.0,
+        rollover: false,
+        pinned: false,
+        descriptions: &["Shell", "Chevron"],
+        amounts: (30.0, 55.0),
+        spends: 3,
+    },
+    DemoEnvelope {
+        name: "Emergency Fund",
+        category: "Savings",
+        allocation: 100.0,
+        rollover: true,
+        pinned: false,
+        descriptions: &[],
+        amounts: (0.0, 0.0),
+        spends: 0,
+    },
+];
+
+/// Individual envelope each member gets a copy of.
+const FUN_MONEY: DemoEnvelope = DemoEnvelope {
+    name: "Fun Money",
+    category: "Personal",
+    allocation: 75.0,
+    rollover: false,
+    pinned: false,
+    descriptions: &["Movie tickets", "Bookstore", "Video game", "Concert"],
+    amounts: (10.0, 30.0),
+    spends: 3,
+};
+
+/// Sample products: name, unit price, and envelope.
+const PRODUCTS: [(&str, f64, &str); 3] = [
+    ("Coffee", 4.50, "Dining Out"),
+    ("Milk", 3.99, "Groceries"),
+    ("Eggs", 5.49, "Groceries"),
+];
+
+/// What [`seed`] created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DemoSummary {
+    /// Envelopes created, counting each member's individual envelope
+    pub envelopes: usize,
+    /// Products created
+    pub products: usize,
+    /// Transactions created
+    pub transactions: usize,
+}
+
+/// Small deterministic generator (a 64-bit LCG), so demo data needs no `rand` dependency.
+struct DemoRng(u64);
+
+impl DemoRng {
+    const fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.0 >> 33
+    }
+
+    /// A number in `0..n`, for `n > 0`.
+    const fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// An amount in `low..=high`, in whole cents.
+    fn amount(&mut self, (low, high): (f64, f64)) -> f64 {
+        // Casts are safe: demo amounts are a few thousand cents at most
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let cents = {
+            let span = ((high - low) * 100.0).round() as u64;
+            (low * 100.0).round() + self.below(span + 1) as f64
+        };
+        cents / 100.0
+    }
+}
+
+/// Fills an empty database with the demo household, with spending up to `today`.
+///
+/// The current month's balances reflect its spending, earlier months are history, and
+/// the monthly update is recorded as done for the current month.
+///
+/// # Errors
+/// Returns an error if:
+/// - The database already has envelopes (`Error::Config`)
+/// - A database query or write fails
+pub async fn seed(db: &DatabaseConnection, today: NaiveDate) -> Result<DemoSummary> {
+    if !envelope_core::get_all_active_envelopes(db)
+        .await?
+        .is_empty()
+    {
+        return Err(Error::Config {
+            message: "Demo data can only be added to an empty database".to_string(),
+        });
+    }
+    for (user_id, nickname) in DEMO_MEMBERS {
+        setup::save_nickname(db, user_id, nickname).await?;
+    }
+
+    let mut rng = DemoRng(DEMO_SEED);
+    let mut summary = DemoSummary {
+        envelopes: 0,
+        products: 0,
+        transactions: 0,
+    };
+    for spec in SHARED_ENVELOPES {
+        summary.transactions += seed_envelope(db, &mut rng, spec, None, today).await?;
+        summary.envelopes += 1;
+    }
+    for (user_id, _) in DEMO_MEMBERS {
+        summary.transactions +=
+            seed_envelope(db, &mut rng, &FUN_MONEY, Some(user_id), today).await?;
+        summary.envelopes += 1;
+    }
+
+    for (name, price, envelope_name) in PRODUCTS {
+        if let Some(env) = envelope_core::get_shared_envelope_by_name(db, envelope_name).await? {
+            product::create_product(db, name.to_string(), price, env.id).await?;
+            summary.products += 1;
+        }
+    }
+
+    let current = calendar::current_cycle(db, today).await?;
+    monthly::set_last_monthly_update_date(db, current.start).await?;
+    Ok(summary)
+}
+
+/// Creates one demo envelope with three months of funding and spending, returning the
+/// number of transactions recorded.
+async fn seed_envelope(
+    db: &DatabaseConnection,
+    rng: &mut DemoRng,
+    spec: &DemoEnvelope,
+    owner: Option<&str>,
+    today: NaiveDate,
+) -> Result<usize> {
+    let created = envelope_core::create_envelope(
+        db,
+        spec.name.to_string(),
+        owner.map(str::to_string),
+        spec.category.to_string(),
+        spec.allocation,
+        owner.is_some(),
+        spec.rollover,
+    )
+    .await?;
+    if spec.pinned {
+        envelope_core::set_display_order(db, created.id, true, None).await?;
+    }
+
+    let tz = calendar::get_timezone(db).await?;
+    let current = calendar::current_cycle(db, today).await?;
+    let cycles = [current.previous().previous(), current.previous(), current];
+    let mut count = 0;
+    let mut balance = 0.0;
+    for cycle in cycles {
+        let is_current = cycle == current;
+        if !spec.rollover {
+            balance = 0.0;
+        }
+        let funded_at = calendar::start_of_day(tz, cycle.start) + TimeDelta::hours(6);
+        let funder = owner.unwrap_or(DEMO_MEMBERS[0].0);
+        insert(
+            db,
+            created.id,
+            spec.allocation,
+            "Monthly allocation",
+            funder,
+            funded_at,
+            TransactionType::AddFunds,
+        )
+        .await?;
+        balance += spec.allocation;
+        count += 1;
+
+        // The current month only has the days up to today
+        let days = if is_current {
+            cycle.day_number(today)
+        } else {
+            cycle.days()
+        };
+        let spends = if is_current {
+            // Casts are safe: cycles have at most 31 days and a handful of spends
+            #[allow(
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss,
+                clippy::cast_precision_loss
+            )]
+            let scaled = (f64::from(spec.spends) * days as f64 / cycle.days() as f64).ceil() as u32;
+            scaled
+        } else {
+            spec.spends
+        };
+
+        let mut spent = 0.0;
+        for index in 0..spends {
+            let amount = rng.amount(spec.amounts);
+            // Leave a little of every allocation unspent
+            if spent + amount > spec.allocation {
+                continue;
+            }
+            spent += amount;
+            // Rent is due on the first day; everything else lands on a random day
+            #[allow(clippy::cast_sign_loss)] // days is at least 1
+            let offset = if spec.spends == 1 {
+                0
+            } else {
+                rng.below(days as u64)
+            };
+            let date = cycle.start + Days::new(offset);
+            #[allow(clippy::cast_possible_wrap)] // Below 12
+            let hour = 8 + rng.below(12) as i64;
+            let at = (calendar::start_of_day(tz, date) + TimeDelta::hours(hour)).min(Utc::now());
+            #[allow(clippy::cast_possible_truncation)] // Index into a short list
+            let description = spec.descriptions[rng.below(spec.descriptions.len() as u64) as usize];
+            let spender = owner.unwrap_or(DEMO_MEMBERS[(index % 2) as usize].0);
+            insert(
+                db,
+                created.id,
+                -amount,
+                description,
+                spender,
+                at,
+                TransactionType::Spend,
+            )
+            .await?;
+            balance -= amount;
+            count += 1;
+        }
+    }
+
+    let mut active_model: envelope::ActiveModel = created.into();
+    active_model.balance = Set((balance * 100.0).round() / 100.0);
+    active_model.update(db).await?;
+    Ok(count)
+}
+
+/// Inserts a backdated demo transaction; balances are set once per envelope afterwards.
+async fn insert(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    amount: f64,
+    description: &str,
+    user_id: &str,
+    at: DateTime<Utc>,
+    kind: TransactionType,
+) -> Result<()> {
+    transaction::ActiveModel {
+        envelope_id: Set(envelope_id),
+        amount: Set(amount),
+        description: Set(description.to_string()),
+        timestamp: Set(at),
+        user_id: Set(user_id.to_string()),
+        transaction_type: Set(kind),
+        merchant: Set((amount < 0.0)
+            .then(|| tx_core::extract_merchant(description))
+            .flatten()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::report, test_utils::*};
+
+    #[tokio::test]
+    async fn test_seed_demo_household() -> Result<()> {
+        let (db, summary) = setup_demo_db().await?;
+        assert_eq!(
+            summary.envelopes,
+            SHARED_ENVELOPES.len() + DEMO_MEMBERS.len()
+        );
+        assert_eq!(summary.products, PRODUCTS.len());
+        assert!(summary.transactions > 30);
+
+        // Pinned envelopes come first, and balances match the current month
+        let envelopes = envelope_core::get_all_active_envelopes(&db).await?;
+        assert_eq!(envelopes[0].name, "Groceries");
+        assert_eq!(envelopes[1].name, "Rent");
+        let rent = &envelopes[1];
+        assert!(rent.balance.abs() < 1e-9);
+        let emergency = envelope_core::get_shared_envelope_by_name(&db, "Emergency Fund")
+            .await?
+            .ok_or(Error::EnvelopeNotFound {
+                name: "Emergency Fund".to_string(),
+            })?;
+        assert!((emergency.balance - 300.0).abs() < 1e-9);
+        for env in &envelopes {
+            assert!(env.balance >= 0.0, "{} is overspent", env.name);
+        }
+
+        // The same seed gives the same data, and the database must be empty
+        let (other, _) = setup_demo_db().await?;
+        let totals = report::household_summary(&db, DEMO_MEMBERS[0].0).await?;
+        let other_totals = report::household_summary(&other, DEMO_MEMBERS[0].0).await?;
+        assert!((totals.total_spent - other_totals.total_spent).abs() < 1e-9);
+        assert!(seed(&db, calendar::today(&db).await?).await.is_err());
+        Ok(())
+    }
+}
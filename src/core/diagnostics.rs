@@ -0,0 +1,301 @@
+//! Diagnostics business logic - Health information for `/status`.
+//!
+//! Gathers what's needed to debug a remote install from Discord: build version and
+//! commit, uptime, database size and row counts, the last monthly update, and when each
+//! background job last ran. Jobs report their runs through [`record_job_run`], stored in
+//! the `system_state` table.
+
+use crate::{
+    core::{cache::CacheStats, monthly, state},
+    entities::{
+        BalanceSnapshot, Envelope, Iou, PendingPurchase, Product, SystemState, Transaction,
+    },
+    errors::Result,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait};
+use std::{fmt::Write, path::PathBuf, time::Duration};
+
+/// Package version of the running build.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Git commit the running build was compiled from, when known.
+pub const COMMIT: Option<&str> = option_env!("GIT_COMMIT");
+
+/// Prefix of the `system_state` keys recording when each background job last ran.
+const JOB_RUN_KEY_PREFIX: &str = "job_last_run:";
+
+/// Snapshot of the bot's health.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// Time since the bot started
+    pub uptime: Duration,
+    /// Size of the `SQLite` database file in bytes, if it is a local file
+    pub database_size: Option<u64>,
+    /// Row count of each table, by table name
+    pub row_counts: Vec<(String, u64)>,
+    /// Month-start date of the last monthly update
+    pub last_monthly_update: Option<NaiveDate>,
+    /// Last run time of each background job, by job name
+    pub job_runs: Vec<(String, DateTime<Utc>)>,
+    /// Read cache hits and misses since the bot started
+    pub cache: CacheStats,
+}
+
+/// Records that a background job just ran.
+///
+/// # Errors
+/// Returns an error if the state update fails.
+pub async fn record_job_run(db: &DatabaseConnection, job: &str, at: DateTime<Utc>) -> Result<()> {
+    state::set_value(db, &format!("{JOB_RUN_KEY_PREFIX}{job}"), &at.to_rfc3339()).await
+}
+
+/// Retrieves when each background job last ran, sorted by job name.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_job_runs(db: &DatabaseConnection) -> Result<Vec<(String, DateTime<Utc>)>> {
+    let mut runs: Vec<(String, DateTime<Utc>)> =
+        state::get_values_with_prefix(db, JOB_RUN_KEY_PREFIX)
+            .await?
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let job = key.strip_prefix(JOB_RUN_KEY_PREFIX)?.to_string();
+                let at = DateTime::parse_from_rfc3339(&value)
+                    .ok()?
+                    .with_timezone(&Utc);
+                Some((job, at))
+            })
+            .collect();
+    runs.sort();
+    Ok(runs)
+}
+
+/// Counts the rows in every table.
+///
+/// # Errors
+/// Returns an error if a count query fails.
+pub async fn table_row_counts(db: &DatabaseConnection) -> Result<Vec<(String, u64)>> {
+    Ok(vec![
+        count_rows(db, Envelope).await?,
+        count_rows(db, Transaction).await?,
+        count_rows(db, Product).await?,
+        count_rows(db, Iou).await?,
+        count_rows(db, PendingPurchase).await?,
+        count_rows(db, BalanceSnapshot).await?,
+        count_rows(db, SystemState).await?,
+    ])
+}
+
+/// Counts the rows of one entity's table.
+async fn count_rows<E>(db: &DatabaseConnection, entity: E) -> Result<(String, u64)>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+{
+    let count = E::find().count(db).await?;
+    Ok((entity.table_name().to_string(), count))
+}
+
+/// Returns the size of the database file behind a `SQLite` URL, if it can be read.
+///
+/// In-memory databases and non-`SQLite` URLs have no file and return `None`.
+#[must_use]
+pub fn database_file_size(database_url: &str) -> Option<u64> {
+    let path = sqlite_path(database_url)?;
+    std::fs::metadata(path).ok().map(|metadata| metadata.len())
+}
+
+/// Extracts the file path from a `SQLite` URL such as `sqlite://data/db.sqlite?mode=rwc`.
+fn sqlite_path(database_url: &str) -> Option<PathBuf> {
+    let rest = database_url
+        .strip_prefix("sqlite://")
+        .or_else(|| database_url.strip_prefix("sqlite:"))?;
+    let path = rest.split('?').next().unwrap_or_default();
+    if path.is_empty() || path == ":memory:" {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
+
+/// Collects diagnostics for a bot that started at `started_at`.
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn collect(
+    db: &DatabaseConnection,
+    database_url: &str,
+    started_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    cache: CacheStats,
+) -> Result<Diagnostics> {
+    Ok(Diagnostics {
+        uptime: (now - started_at).to_std().unwrap_or_default(),
+        database_size: database_file_size(database_url),
+        row_counts: table_row_counts(db).await?,
+        last_monthly_update: monthly::get_last_monthly_update_date(db).await?,
+        job_runs: get_job_runs(db).await?,
+        cache,
+    })
+}
+
+/// Formats diagnostics for display in Discord.
+///
+/// # Errors
+/// Returns an error if string formatting fails.
+pub fn format_diagnostics(diagnostics: &Diagnostics) -> Result<String> {
+    let mut output = String::new();
+
+    writeln!(
+        &mut output,
+        "**Version:** v{VERSION} ({})",
+        COMMIT.unwrap_or("unknown commit")
+    )?;
+    writeln!(
+        &mut output,
+        "**Uptime:** {}",
+        format_uptime(diagnostics.uptime)
+    )?;
+    writeln!(
+        &mut output,
+        "**Database size:** {}",
+        diagnostics
+            .database_size
+            .map_or_else(|| "n/a".to_string(), format_bytes)
+    )?;
+    writeln!(
+        &mut output,
+        "**Last monthly update:** {}",
+        diagnostics.last_monthly_update.map_or_else(
+            || "never".to_string(),
+            |date| date.format("%B %Y").to_string()
+        )
+    )?;
+    writeln!(
+        &mut output,
+        "**Cache hit rate:** {} ({} hits, {} misses)",
+        diagnostics
+            .cache
+            .hit_rate()
+            .map_or_else(|| "n/a".to_string(), |rate| format!("{:.0}%", rate * 100.0)),
+        diagnostics.cache.hits,
+        diagnostics.cache.misses
+    )?;
+
+    writeln!(&mut output, "\n**Rows:**")?;
+    for (table, count) in &diagnostics.row_counts {
+        writeln!(&mut output, "• {table}: {count}")?;
+    }
+
+    writeln!(&mut output, "\n**Background jobs:**")?;
+    if diagnostics.job_runs.is_empty() {
+        writeln!(&mut output, "• none have run yet")?;
+    }
+    for (job, at) in &diagnostics.job_runs {
+        writeln!(&mut output, "• {job}: <t:{}:R>", at.timestamp())?;
+    }
+
+    Ok(output)
+}
+
+/// Formats a duration as days, hours, and minutes, e.g. "2d 3h 15m".
+#[must_use]
+pub fn format_uptime(uptime: Duration) -> String {
+    let minutes = uptime.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / 1440, (minutes / 60) % 24, minutes % 60);
+    if days > 0 {
+        format!("{days}d {hours}h {minutes}m")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. "1.5 MiB".
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    // File sizes are far below 2^52, precision loss negligible
+    #[allow(clippy::cast_precision_loss)]
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_collect_diagnostics() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 25.0).await?;
+
+        let ran_at = DateTime::parse_from_rfc3339("2025-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        record_job_run(&db, "wishlist_expiry", ran_at).await?;
+        record_job_run(&db, "balance_snapshots", ran_at).await?;
+
+        let started_at = Utc::now() - chrono::Duration::minutes(90);
+        let cache = CacheStats { hits: 3, misses: 1 };
+        let diagnostics = collect(&db, "sqlite::memory:", started_at, Utc::now(), cache).await?;
+        assert_eq!(diagnostics.database_size, None);
+        assert!(
+            diagnostics
+                .row_counts
+                .contains(&("envelopes".to_string(), 1))
+        );
+        assert!(
+            diagnostics
+                .row_counts
+                .contains(&("transactions".to_string(), 1))
+        );
+        assert_eq!(
+            diagnostics.job_runs,
+            vec![
+                ("balance_snapshots".to_string(), ran_at),
+                ("wishlist_expiry".to_string(), ran_at)
+            ]
+        );
+
+        let formatted = format_diagnostics(&diagnostics)?;
+        assert!(formatted.contains("**Uptime:** 1h 30m"));
+        assert!(formatted.contains("**Last monthly update:** never"));
+        assert!(formatted.contains("**Cache hit rate:** 75% (3 hits, 1 misses)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sqlite_path() {
+        assert_eq!(
+            sqlite_path("sqlite://data/envelope_buddy.sqlite?mode=rwc"),
+            Some(PathBuf::from("data/envelope_buddy.sqlite"))
+        );
+        assert_eq!(sqlite_path("sqlite:bot.db"), Some(PathBuf::from("bot.db")));
+        assert_eq!(sqlite_path("sqlite::memory:"), None);
+        assert_eq!(sqlite_path("postgres://localhost/db"), None);
+    }
+
+    #[test]
+    fn test_formatting_helpers() {
+        assert_eq!(format_uptime(Duration::from_secs(59)), "0m");
+        assert_eq!(
+            format_uptime(Duration::from_mins(2 * 1440 + 3 * 60 + 15)),
+            "2d 3h 15m"
+        );
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536 * 1024), "1.5 MiB");
+    }
+}
@@ -0,0 +1,392 @@
+//! Bulk deposit distribution - Splits one deposit (e.g. a paycheck) across envelopes.
+//!
+//! A deposit is split either in proportion to the envelopes' monthly allocations
+//! ([`allocation_shares`]) or by the household's saved percentage plan
+//! ([`plan_shares`]). Amounts are rounded to cents, with the rounding difference going
+//! to the largest share so the parts always add up to the deposit. [`distribute_funds`]
+//! then records one `addfunds` transaction per envelope in a single database
+//! transaction.
+//!
+//! Both splits follow the household's funding rules (see [`allocation`]): a share for an
+//! envelope waiting on another to be full goes to that envelope first.
+
+use crate::{
+    core::{
+        allocation,
+        cache::{self, CachedTable},
+        envelope, journal, state, transaction,
+    },
+    entities::{TransactionType, envelope as envelope_entity, transaction as transaction_entity},
+    errors::{Error, Result},
+};
+use journal::JournalEvent;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+
+/// `system_state` key holding the saved percentage plan, as written by [`format_plan`].
+const PLAN_KEY: &str = "distribution_plan";
+
+/// Tolerance when checking that plan percentages add up to 100.
+const PERCENT_TOLERANCE: f64 = 0.01;
+
+/// One envelope's share in the saved percentage plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanEntry {
+    /// Envelope name; individual envelopes resolve to the depositing member's own
+    pub envelope_name: String,
+    /// Percentage of each deposit this envelope receives
+    pub percent: f64,
+}
+
+/// Parses a plan written as `Groceries:40, Rent:50, Fun:10`.
+///
+/// # Errors
+/// Returns `Error::Config` if an entry is malformed, a percentage isn't positive, an
+/// envelope is listed twice, or the percentages don't add up to 100.
+pub fn parse_plan(text: &str) -> Result<Vec<PlanEntry>> {
+    let invalid = |message: String| Error::Config { message };
+
+    let mut entries: Vec<PlanEntry> = Vec::new();
+    for part in text
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (name, percent) = part
+            .rsplit_once(':')
+            .ok_or_else(|| invalid(format!("'{part}' should look like Envelope:percent")))?;
+        let name = name.trim();
+        let percent: f64 = percent
+            .trim()
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| invalid(format!("'{}' is not a percentage", percent.trim())))?;
+        if name.is_empty() || !percent.is_finite() || percent <= 0.0 {
+            return Err(invalid(format!(
+                "'{part}' needs an envelope name and a positive percentage"
+            )));
+        }
+        if entries
+            .iter()
+            .any(|entry| entry.envelope_name.eq_ignore_ascii_case(name))
+        {
+            return Err(invalid(format!("'{name}' is listed more than once")));
+        }
+        entries.push(PlanEntry {
+            envelope_name: name.to_string(),
+            percent,
+        });
+    }
+
+    let total: f64 = entries.iter().map(|entry| entry.percent).sum();
+    if entries.is_empty() || (total - 100.0).abs() > PERCENT_TOLERANCE {
+        return Err(invalid(format!(
+            "Plan percentages must add up to 100 (got {total:.2})"
+        )));
+    }
+    Ok(entries)
+}
+
+/// Formats a plan the way [`parse_plan`] reads it.
+#[must_use]
+pub fn format_plan(plan: &[PlanEntry]) -> String {
+    plan.iter()
+        .map(|entry| format!("{}:{}", entry.envelope_name, entry.percent))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Gets the saved percentage plan, if one has been set.
+///
+/// # Errors
+/// Returns an error if the database query fails or the stored plan can't be read.
+pub async fn get_plan(db: &DatabaseConnection) -> Result<Option<Vec<PlanEntry>>> {
+    state::get_value(db, PLAN_KEY)
+        .await?
+        .map(|value| parse_plan(&value))
+        .transpose()
+}
+
+/// Saves the percentage plan used by `/addfunds distribute:plan`.
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_plan(db: &DatabaseConnection, plan: &[PlanEntry]) -> Result<()> {
+    state::set_value(db, PLAN_KEY, &format_plan(plan)).await
+}
+
+/// Splits `total` across weighted items, rounded to cents.
+///
+/// The rounding difference goes to the item with the largest weight, so the parts add
+/// up to `total` exactly (in cents). Items with no weight are left out.
+#[must_use]
+pub fn split_by_weights<T>(total: f64, items: Vec<(T, f64)>) -> Vec<(T, f64)> {
+    let items: Vec<(T, f64)> = items
+        .into_iter()
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect();
+    let total_weight: f64 = items.iter().map(|(_, weight)| weight).sum();
+    if items.is_empty() || total_weight <= 0.0 {
+        return Vec::new();
+    }
+
+    // First item with the largest weight
+    let largest = (1..items.len()).fold(0, |best, index| {
+        if items[index].1 > items[best].1 {
+            index
+        } else {
+            best
+        }
+    });
+    let mut parts: Vec<(T, f64)> = items
+        .into_iter()
+        .map(|(item, weight)| {
+            let share = (total * weight / total_weight * 100.0).round() / 100.0;
+            (item, share)
+        })
+        .collect();
+    let assigned: f64 = parts.iter().map(|(_, share)| share).sum();
+    parts[largest].1 = ((parts[largest].1 + total - assigned) * 100.0).round() / 100.0;
+    parts
+}
+
+/// Splits `total` across the envelopes `user_id` can deposit to (shared envelopes and
+/// their own individual envelopes) in proportion to the monthly allocations.
+///
+/// # Errors
+/// Returns an error if the database query fails, or `Error::Config` if none of the
+/// envelopes has an allocation.
+pub async fn allocation_shares(
+    db: &DatabaseConnection,
+    user_id: &str,
+    total: f64,
+) -> Result<Vec<(envelope_entity::Model, f64)>> {
+    let weighted = envelope::get_all_active_envelopes(db)
+        .await?
+        .into_iter()
+        .filter(|env| !env.is_individual || env.user_id.as_deref() == Some(user_id))
+        .map(|env| {
+            let allocation = env.allocation;
+            (env, allocation)
+        })
+        .collect();
+
+    let shares = split_by_weights(total, weighted);
+    if shares.is_empty() {
+        return Err(Error::Config {
+            message: "No envelopes have an allocation to distribute by".to_string(),
+        });
+    }
+    apply_funding_rules(db, shares).await
+}
+
+/// Splits `total` by the saved percentage plan, resolving each envelope for `user_id`
+/// (their own individual envelope first, then the shared one).
+///
+/// # Errors
+/// Returns an error if the database query fails, `Error::Config` if no plan is saved,
+/// or `Error::EnvelopeNotFound` if a planned envelope doesn't exist for the member.
+pub async fn plan_shares(
+    db: &DatabaseConnection,
+    user_id: &str,
+    total: f64,
+) -> Result<Vec<(envelope_entity::Model, f64)>> {
+    let Some(plan) = get_plan(db).await? else {
+        return Err(Error::Config {
+            message: "No distribution plan saved yet. Set one with `/distribution_plan`."
+                .to_string(),
+        });
+    };
+
+    let mut weighted = Vec::with_capacity(plan.len());
+    for entry in plan {
+        let env = envelope::find_envelope_for_user(db, &entry.envelope_name, user_id)
+            .await?
+            .ok_or(Error::EnvelopeNotFound {
+                name: entry.envelope_name,
+            })?;
+        weighted.push((env, entry.percent));
+    }
+    apply_funding_rules(db, split_by_weights(total, weighted)).await
+}
+
+/// Moves shares of envelopes waiting on a prerequisite that isn't full to the
+/// prerequisite (see [`allocation::apply_funding_rules`]).
+///
+/// A prerequisite outside the split joins it only if it receives something; the split's
+/// own envelopes stay listed even when their whole share was held back.
+async fn apply_funding_rules(
+    db: &DatabaseConnection,
+    mut shares: Vec<(envelope_entity::Model, f64)>,
+) -> Result<Vec<(envelope_entity::Model, f64)>> {
+    let rules = allocation::get_funding_rules(db).await?;
+    let split = shares.len();
+    // Added prerequisites may wait on another envelope in turn
+    let mut next = 0;
+    while next < shares.len() {
+        let (env, share) = &shares[next];
+        next += 1;
+        let Some(&requires_id) = rules.get(&env.id) else {
+            continue;
+        };
+        if (next <= split && *share <= 0.0) || shares.iter().any(|(env, _)| env.id == requires_id) {
+            continue;
+        }
+        if let Some(requires) = envelope::get_envelope_by_id(db, requires_id)
+            .await?
+            .filter(|env| !env.is_deleted)
+        {
+            shares.push((requires, 0.0));
+        }
+    }
+
+    allocation::apply_funding_rules(&mut shares, &rules);
+    let mut position = 0;
+    shares.retain(|(_, share)| {
+        position += 1;
+        position <= split || *share > 0.0
+    });
+    Ok(shares)
+}
+
+/// Records one `addfunds` transaction per share, all or nothing.
+///
+/// Each transaction's message ID is derived from `message_id` and the envelope, so a
+/// retried command doesn't deposit twice.
+///
+/// # Errors
+/// Returns an error if any transaction fails; nothing is recorded in that case.
+pub async fn distribute_funds(
+    db: &DatabaseConnection,
+    shares: &[(envelope_entity::Model, f64)],
+    description: &str,
+    user_id: &str,
+    message_id: Option<&str>,
+) -> Result<Vec<transaction_entity::Model>> {
+    let txn = db.begin().await?;
+    let mut deposits = Vec::with_capacity(shares.len());
+    for (env, amount) in shares.iter().filter(|(_, amount)| *amount > 0.0) {
+        deposits.push(
+            transaction::create_unjournaled_transaction(
+                &txn,
+                env.id,
+                *amount,
+                description.to_string(),
+                user_id.to_string(),
+                message_id.map(|id| format!("{id}:distribute:{}", env.id)),
+                TransactionType::AddFunds,
+            )
+            .await?,
+        );
+    }
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+
+    Ok(deposits
+        .into_iter()
+        .map(|(deposit, is_new)| {
+            if is_new {
+                journal::record(JournalEvent::Created, &deposit);
+            }
+            deposit
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_split_by_weights_adds_up() {
+        let parts = split_by_weights(100.0, vec![("a", 1.0), ("b", 1.0), ("c", 1.0)]);
+        let amounts: Vec<f64> = parts.iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![33.34, 33.33, 33.33]);
+
+        let parts = split_by_weights(1000.0, vec![("rent", 600.0), ("food", 300.0), ("x", 0.0)]);
+        assert_eq!(parts, vec![("rent", 666.67), ("food", 333.33)]);
+    }
+
+    #[test]
+    fn test_parse_plan() -> Result<()> {
+        let plan = parse_plan("Groceries:40, Rent: 50%, Fun:10")?;
+        assert_eq!(plan.len(), 3);
+        assert_eq!(plan[1].envelope_name, "Rent");
+        assert_eq!(plan[1].percent, 50.0);
+        assert_eq!(format_plan(&plan), "Groceries:40, Rent:50, Fun:10");
+
+        assert!(parse_plan("Groceries:40, Rent:50").is_err());
+        assert!(parse_plan("Groceries:50, groceries:50").is_err());
+        assert!(parse_plan("Groceries").is_err());
+        assert!(parse_plan("Groceries:-10, Rent:110").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_distribute_by_allocation_and_plan() -> Result<()> {
+        let db = setup_test_db().await?;
+        let rent =
+            create_custom_envelope(&db, "Rent", None, "necessary", 300.0, false, false).await?;
+        let food =
+            create_custom_envelope(&db, "Food", None, "necessary", 100.0, false, false).await?;
+        // Another member's individual envelope doesn't receive this member's deposit
+        create_custom_envelope(
+            &db,
+            "Hobby",
+            Some("partner".to_string()),
+            "quality_of_life",
+            100.0,
+            true,
+            false,
+        )
+        .await?;
+
+        let shares = allocation_shares(&db, "user1", 200.0).await?;
+        let deposits = distribute_funds(&db, &shares, "Paycheck", "user1", Some("42")).await?;
+        assert_eq!(deposits.len(), 2);
+        let rent_after = envelope::get_envelope_by_id(&db, rent.id).await?;
+        assert_eq!(rent_after.map(|env| env.balance), Some(150.0));
+
+        // A retried command doesn't deposit twice
+        distribute_funds(&db, &shares, "Paycheck", "user1", Some("42")).await?;
+        let food_after = envelope::get_envelope_by_id(&db, food.id).await?;
+        assert_eq!(food_after.map(|env| env.balance), Some(50.0));
+
+        assert!(matches!(
+            plan_shares(&db, "user1", 100.0).await,
+            Err(Error::Config { .. })
+        ));
+        set_plan(&db, &parse_plan("Food:75, Rent:25")?).await?;
+        let shares = plan_shares(&db, "user1", 100.0).await?;
+        assert_eq!(shares[0].0.id, food.id);
+        assert_eq!(shares[0].1, 75.0);
+
+        set_plan(&db, &parse_plan("Food:75, Travel:25")?).await?;
+        assert!(matches!(
+            plan_shares(&db, "user1", 100.0).await,
+            Err(Error::EnvelopeNotFound { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_distribute_follows_funding_rules() -> Result<()> {
+        let db = setup_test_db().await?;
+        let emergency =
+            create_custom_envelope(&db, "Emergency", None, "savings", 0.0, false, true).await?;
+        envelope::set_sinking_fund(&db, emergency.id, 0.0, Some(60.0)).await?;
+        let vacation =
+            create_custom_envelope(&db, "Vacation", None, "savings", 100.0, false, true).await?;
+        allocation::set_funding_rule(&db, vacation.id, Some(emergency.id)).await?;
+
+        // Emergency has no allocation, so it joins the split only to be filled first
+        let shares = allocation_shares(&db, "user1", 100.0).await?;
+        let amounts: Vec<(&str, f64)> = shares
+            .iter()
+            .map(|(env, share)| (env.name.as_str(), *share))
+            .collect();
+        assert_eq!(amounts, vec![("Vacation", 40.0), ("Emergency", 60.0)]);
+        Ok(())
+    }
+}
@@ -2,23 +2,167 @@
 //!
 //! Provides functions for creating, retrieving, updating, and managing envelopes.
 //! All functions are async and return Result types for error handling.
+//!
+//! Individual envelopes from config.toml are stored as templates: individual envelopes
+//! with no owner. They aren't real envelopes and are left out of
+//! [`get_all_active_envelopes`] and the monthly update. [`resolve_envelope`] gives each
+//! member their own copy of a template the first time they use it.
+//!
+//! Names are normalized by [`normalize_envelope_name`] when an envelope is created or
+//! renamed, and compared ignoring case and spacing, so "Groceries " and "groceries" are
+//! the same envelope. Name lookups prefer an exact match, which keeps working databases
+//! that already hold such near-duplicates; [`find_duplicate_names`] reports them.
 
 use crate::{
-    entities::{Envelope, envelope},
+    core::{
+        allocation,
+        cache::{self, CachedTable},
+        envelope_permission, fuzzy, product, report,
+    },
+    entities::{
+        Bill, Envelope, Favorite, PendingPurchase, ScheduledTransaction, ShoppingListItem, bill,
+        envelope, favorite, pending_purchase, product as product_entity, scheduled_transaction,
+        shopping_list_item, transaction as transaction_entity,
+    },
     errors::{Error, Result},
 };
-use sea_orm::{QueryOrder, Set, prelude::*};
+use chrono::NaiveDate;
+use sea_orm::{
+    Condition, QueryOrder, Set, TransactionTrait,
+    prelude::*,
+    sea_query::{Func, NullOrdering, Order, SimpleExpr},
+};
+use std::collections::HashMap;
+
+/// Longest envelope name allowed, in characters.
+pub const MAX_ENVELOPE_NAME_LEN: usize = 50;
+
+/// Normalizes an envelope name: trims it and collapses runs of whitespace to one space.
+///
+/// Emoji may decorate a name ("🛒 Groceries") but the name needs at least one letter or
+/// digit, so it can still be typed in commands and autocomplete.
+///
+/// # Errors
+/// Returns `Error::Config` if the name is empty, longer than [`MAX_ENVELOPE_NAME_LEN`]
+/// characters, contains control characters, or has no letters or digits.
+pub fn normalize_envelope_name(raw: &str) -> Result<String> {
+    let name = collapse_whitespace(raw);
+    let problem = if name.is_empty() {
+        Some("Envelope name cannot be empty".to_string())
+    } else if name.chars().count() > MAX_ENVELOPE_NAME_LEN {
+        Some(format!(
+            "Envelope names can be at most {MAX_ENVELOPE_NAME_LEN} characters"
+        ))
+    } else if name.chars().any(char::is_control) {
+        Some("Envelope names can't contain control characters".to_string())
+    } else if !name.chars().any(char::is_alphanumeric) {
+        Some("Envelope names need at least one letter or digit".to_string())
+    } else {
+        None
+    };
+    problem.map_or(Ok(name), |message| Err(Error::Config { message }))
+}
+
+/// Trims a name and collapses runs of whitespace to one space.
+pub(crate) fn collapse_whitespace(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Key two names share when they differ only in case or spacing.
+///
+/// Only ASCII letters are folded, the same as `SQLite`'s `LOWER`, so the key matches
+/// what [`name_matches`] compares in queries.
+fn name_key(name: &str) -> String {
+    collapse_whitespace(name).to_ascii_lowercase()
+}
+
+/// Query condition matching envelopes named `name`, ignoring case and spacing.
+fn name_matches(name: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col(envelope::Column::Name))).eq(name_key(name))
+}
+
+/// Drops near-duplicate matches of a name lookup in favor of an exact match.
+///
+/// Within each scope (shared, or one user's), an envelope named exactly `name` wins over
+/// ones that only differ in case, which older versions allowed.
+fn prefer_exact(matches: Vec<envelope::Model>, name: &str) -> Vec<envelope::Model> {
+    let name = collapse_whitespace(name);
+    let exact_scopes: Vec<Option<String>> = matches
+        .iter()
+        .filter(|env| env.name == name)
+        .map(|env| env.user_id.clone())
+        .collect();
+    matches
+        .into_iter()
+        .filter(|env| env.name == name || !exact_scopes.contains(&env.user_id))
+        .collect()
+}
+
+/// Finds an active envelope in the same scope (shared, or the given user's) whose name
+/// matches `name` ignoring case and spacing.
+async fn find_name_conflict<C: ConnectionTrait>(
+    db: &C,
+    name: &str,
+    user_id: Option<&str>,
+) -> Result<Option<envelope::Model>> {
+    let scope = user_id.map_or_else(
+        || envelope::Column::UserId.is_null(),
+        |uid| envelope::Column::UserId.eq(uid),
+    );
+    Envelope::find()
+        .filter(name_matches(name))
+        .filter(scope)
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .one(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Groups active envelopes whose names differ only in case or spacing within the same
+/// scope (shared, or one user's), such as "Groceries" and "groceries " created by older
+/// versions.
+///
+/// Each group is sorted by ID. Names are compared like lookups do, so merging or renaming
+/// one envelope of each group resolves the ambiguity.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn find_duplicate_names(db: &DatabaseConnection) -> Result<Vec<Vec<envelope::Model>>> {
+    let envelopes = Envelope::find()
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .order_by_asc(envelope::Column::Id)
+        .all(db)
+        .await?;
+
+    let mut groups: HashMap<(Option<String>, String), Vec<envelope::Model>> = HashMap::new();
+    for env in envelopes {
+        groups
+            .entry((env.user_id.clone(), name_key(&env.name)))
+            .or_default()
+            .push(env);
+    }
+    let mut duplicates: Vec<Vec<envelope::Model>> = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    duplicates.sort_by_key(|group| group[0].id);
+    Ok(duplicates)
+}
 
-/// Retrieves all active (non-deleted) envelopes from the database, ordered alphabetically by name.
+/// Retrieves all active (non-deleted) envelopes from the database in display order.
 ///
-/// This function is commonly used to display the complete list of available envelopes
-/// to users, such as in autocomplete suggestions or envelope selection interfaces.
+/// Pinned envelopes come first, then within each group envelopes with a `sort_order`
+/// from lowest to highest, then the rest alphabetically by name. This is the order of
+/// `/report`, `/envelopes`, and autocomplete suggestions.
 ///
 /// # Errors
 /// Returns an error if the database query fails.
 pub async fn get_all_active_envelopes(db: &DatabaseConnection) -> Result<Vec<envelope::Model>> {
     Envelope::find()
         .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(not_template())
+        .order_by_desc(envelope::Column::Pinned)
+        .order_by_with_nulls(envelope::Column::SortOrder, Order::Asc, NullOrdering::Last)
         .order_by_asc(envelope::Column::Name)
         .all(db)
         .await
@@ -27,9 +171,9 @@ pub async fn get_all_active_envelopes(db: &DatabaseConnection) -> Result<Vec<env
 
 /// Finds a shared envelope by its name, returning None if not found or deleted.
 ///
-/// This function looks for shared envelopes (where `is_individual = false` and `user_id IS NULL`).
-/// It will return an error if multiple shared envelopes with the same name exist,
-/// as this would indicate data corruption.
+/// This function looks for shared envelopes (where `is_individual = false` and `user_id IS NULL`),
+/// ignoring case and spacing in the name. It will return an error if multiple shared
+/// envelopes with the same name exist, as this would indicate data corruption.
 ///
 /// # Errors
 /// Returns an error if:
@@ -40,12 +184,13 @@ pub async fn get_shared_envelope_by_name(
     name: &str,
 ) -> Result<Option<envelope::Model>> {
     let results = Envelope::find()
-        .filter(envelope::Column::Name.eq(name))
+        .filter(name_matches(name))
         .filter(envelope::Column::IsDeleted.eq(false))
         .filter(envelope::Column::IsIndividual.eq(false))
         .filter(envelope::Column::UserId.is_null())
         .all(db)
         .await?;
+    let results = prefer_exact(results, name);
 
     let count = results.len();
     let mut iter = results.into_iter();
@@ -63,6 +208,7 @@ pub async fn get_shared_envelope_by_name(
 ///
 /// This function is essential for personal envelopes where users can only access
 /// their own envelopes, preventing unauthorized access to other users' personal finances.
+/// The name is matched ignoring case and spacing.
 ///
 /// # Errors
 /// Returns an error if the database query fails.
@@ -71,15 +217,199 @@ pub async fn get_envelope_by_name_and_user(
     name: &str,
     user_id: &str,
 ) -> Result<Option<envelope::Model>> {
-    Envelope::find()
-        .filter(envelope::Column::Name.eq(name))
+    let results = Envelope::find()
+        .filter(name_matches(name))
         .filter(envelope::Column::UserId.eq(user_id))
         .filter(envelope::Column::IsDeleted.eq(false))
-        .one(db)
+        .order_by_asc(envelope::Column::Id)
+        .all(db)
+        .await?;
+    Ok(prefer_exact(results, name).into_iter().next())
+}
+
+/// Finds all active individual envelopes with the given name, one per user, ignoring
+/// case and spacing in the name.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_individual_envelopes_by_name(
+    db: &DatabaseConnection,
+    name: &str,
+) -> Result<Vec<envelope::Model>> {
+    let results = Envelope::find()
+        .filter(name_matches(name))
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(envelope::Column::IsIndividual.eq(true))
+        .order_by_asc(envelope::Column::UserId)
+        .all(db)
+        .await?;
+    Ok(prefer_exact(results, name))
+}
+
+/// Query condition matching every envelope except individual templates (see the
+/// module docs).
+#[must_use]
+pub fn not_template() -> Condition {
+    Condition::any()
+        .add(envelope::Column::IsIndividual.eq(false))
+        .add(envelope::Column::UserId.is_not_null())
+}
+
+/// Finds the active individual template with the given name, if any, ignoring case and
+/// spacing in the name.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_template_by_name(
+    db: &DatabaseConnection,
+    name: &str,
+) -> Result<Option<envelope::Model>> {
+    let results = Envelope::find()
+        .filter(name_matches(name))
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(envelope::Column::IsIndividual.eq(true))
+        .filter(envelope::Column::UserId.is_null())
+        .order_by_asc(envelope::Column::Id)
+        .all(db)
+        .await?;
+    Ok(prefer_exact(results, name).into_iter().next())
+}
+
+/// Retrieves every active individual template, ordered by name.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_templates(db: &DatabaseConnection) -> Result<Vec<envelope::Model>> {
+    Envelope::find()
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(envelope::Column::IsIndividual.eq(true))
+        .filter(envelope::Column::UserId.is_null())
+        .order_by_asc(envelope::Column::Name)
+        .all(db)
         .await
         .map_err(Into::into)
 }
 
+/// Finds a user's own envelope by name, creating it from an individual template on
+/// first use.
+///
+/// If the user has no envelope named `name` but an individual template of that name
+/// exists, the user's copy is created with [`instantiate_template`]. Shared envelopes
+/// are not considered; see [`find_envelope_for_user`] for the full lookup.
+///
+/// # Errors
+/// Returns an error if a database query or insert fails.
+pub async fn resolve_envelope(
+    db: &DatabaseConnection,
+    name: &str,
+    user_id: &str,
+) -> Result<Option<envelope::Model>> {
+    if let Some(env) = get_envelope_by_name_and_user(db, name, user_id).await? {
+        return Ok(Some(env));
+    }
+    let Some(template) = get_template_by_name(db, name).await? else {
+        return Ok(None);
+    };
+    instantiate_template(db, &template, user_id).await.map(Some)
+}
+
+/// Finds the envelope `user_id` means by `name`: their own (created from an individual
+/// template on first use, see [`resolve_envelope`]), else a shared one.
+///
+/// Envelopes the user may not see (see [`report::can_view`]) are treated as missing, so
+/// commands can reply "not found" without revealing them.
+///
+/// # Errors
+/// Returns an error if a database query or insert fails, or several shared envelopes
+/// match `name`.
+pub async fn find_envelope_for_user(
+    db: &DatabaseConnection,
+    name: &str,
+    user_id: &str,
+) -> Result<Option<envelope::Model>> {
+    let found = match resolve_envelope(db, name, user_id).await? {
+        Some(env) => Some(env),
+        None => get_shared_envelope_by_name(db, name).await?,
+    };
+    Ok(found.filter(|env| report::can_view(env, user_id)))
+}
+
+/// Suggests the envelope name `user_id` most likely meant by a name that matched none.
+///
+/// Candidates are the active envelopes the user can see (see [`fuzzy::closest_match`]).
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn suggest_envelope_name(
+    db: &DatabaseConnection,
+    name: &str,
+    user_id: &str,
+) -> Result<Option<String>> {
+    let envelopes = get_all_active_envelopes(db).await?;
+    let names = envelopes
+        .iter()
+        .filter(|env| report::can_view(env, user_id))
+        .map(|env| env.name.as_str());
+    Ok(fuzzy::closest_match(name, names).map(str::to_string))
+}
+
+/// Creates a user's copy of an individual template.
+///
+/// The copy gets the template's category, allocation, rollover, and sinking-fund
+/// settings. Its balance starts at the full allocation, as if the user had been there
+/// for the last monthly update, so the envelope can be spent from right away.
+///
+/// # Errors
+/// Returns an error if the envelope isn't a template or the database insert fails.
+pub async fn instantiate_template(
+    db: &DatabaseConnection,
+    template: &envelope::Model,
+    user_id: &str,
+) -> Result<envelope::Model> {
+    if !template.is_individual || template.user_id.is_some() {
+        return Err(Error::Config {
+            message: format!("Envelope '{}' is not an individual template", template.name),
+        });
+    }
+
+    let instance = envelope::ActiveModel {
+        name: Set(template.name.clone()),
+        user_id: Set(Some(user_id.to_string())),
+        category: Set(template.category.clone()),
+        allocation: Set(template.allocation),
+        balance: Set(template.allocation),
+        is_individual: Set(true),
+        rollover: Set(template.rollover),
+        is_deleted: Set(false),
+        contribution: Set(template.contribution),
+        goal: Set(template.goal),
+        is_private: Set(false),
+        alert_below: Set(template.alert_below),
+        daily_limit: Set(template.daily_limit),
+        min_balance: Set(template.min_balance),
+        thread_id: Set(None),
+        weekly: Set(template.weekly),
+        ping_mention: Set(template.ping_mention.clone()),
+        interest_rate: Set(template.interest_rate),
+        pinned: Set(template.pinned),
+        sort_order: Set(template.sort_order),
+        default_description: Set(template.default_description.clone()),
+        frozen: Set(false),
+        pace_on_track: Set(template.pace_on_track),
+        pace_slightly_over: Set(template.pace_slightly_over),
+        pace_never_warn: Set(template.pace_never_warn),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    cache::invalidate(CachedTable::Envelopes);
+    tracing::info!(
+        "Created individual envelope '{}' for user {user_id} from its template",
+        instance.name
+    );
+    Ok(instance)
+}
+
 /// Finds an envelope by its unique ID, used for direct envelope lookups.
 ///
 /// This function is used when the envelope ID is known, such as when
@@ -122,13 +452,16 @@ pub async fn get_all_categories(db: &DatabaseConnection) -> Result<Vec<String>>
 
 /// Creates a new envelope with the specified parameters, performing input validation.
 ///
-/// This function validates that the name is not empty, the allocation is non-negative,
-/// and trims whitespace from the name. It initializes the envelope with zero balance.
+/// This function normalizes the name with [`normalize_envelope_name`] and validates that
+/// the allocation is non-negative and that no active envelope in the same scope (shared,
+/// or the same user) has the name, ignoring case and spacing. It initializes the envelope
+/// with zero balance.
 ///
 /// # Errors
 /// Returns an error if:
-/// - The envelope name is empty or whitespace-only
+/// - The envelope name is invalid (`Error::Config`)
 /// - The allocation amount is negative
+/// - The name is already taken (`Error::EnvelopeAlreadyExists`)
 /// - The database insert operation fails
 pub async fn create_envelope(
     db: &DatabaseConnection,
@@ -140,11 +473,7 @@ pub async fn create_envelope(
     rollover: bool,
 ) -> Result<envelope::Model> {
     // Validate inputs
-    if name.trim().is_empty() {
-        return Err(Error::Config {
-            message: "Envelope name cannot be empty".to_string(),
-        });
-    }
+    let name = normalize_envelope_name(&name)?;
 
     if allocation < 0.0 {
         return Err(Error::InvalidAmount { amount: allocation });
@@ -152,25 +481,1071 @@ pub async fn create_envelope(
 
     // Individual envelopes MUST have a user_id
     if is_individual && user_id.is_none() {
-        return Err(Error::IndividualEnvelopeWithoutUser {
-            name: name.trim().to_string(),
+        return Err(Error::IndividualEnvelopeWithoutUser { name });
+    }
+
+    if let Some(existing) = find_name_conflict(db, &name, user_id.as_deref()).await? {
+        return Err(Error::EnvelopeAlreadyExists {
+            name: existing.name,
+        });
+    }
+
+    let envelope = envelope::ActiveModel {
+        name: Set(name),
+        user_id: Set(user_id),
+        category: Set(category),
+        allocation: Set(allocation),
+        balance: Set(0.0),
+        is_individual: Set(is_individual),
+        rollover: Set(rollover),
+        is_deleted: Set(false),
+        contribution: Set(0.0),
+        goal: Set(None),
+        is_private: Set(false),
+        alert_below: Set(None),
+        daily_limit: Set(None),
+        min_balance: Set(None),
+        thread_id: Set(None),
+        weekly: Set(false),
+        ping_mention: Set(None),
+        pinned: Set(false),
+        frozen: Set(false),
+        pace_never_warn: Set(false),
+        ..Default::default()
+    };
+
+    let result = envelope.insert(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(result)
+}
+
+/// Creates an individual template: an individual envelope with no owner, copied for
+/// each member by [`resolve_envelope`] the first time they use it.
+///
+/// The name is normalized and checked against shared envelopes and other templates like
+/// in [`create_envelope`].
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope name is invalid (`Error::Config`)
+/// - The allocation amount is negative
+/// - The name is already taken (`Error::EnvelopeAlreadyExists`)
+/// - The database insert operation fails
+pub async fn create_template(
+    db: &DatabaseConnection,
+    name: String,
+    category: String,
+    allocation: f64,
+    rollover: bool,
+) -> Result<envelope::Model> {
+    let name = normalize_envelope_name(&name)?;
+
+    if allocation < 0.0 {
+        return Err(Error::InvalidAmount { amount: allocation });
+    }
+
+    if let Some(existing) = find_name_conflict(db, &name, None).await? {
+        return Err(Error::EnvelopeAlreadyExists {
+            name: existing.name,
+        });
+    }
+
+    let template = envelope::ActiveModel {
+        name: Set(name),
+        user_id: Set(None),
+        category: Set(category),
+        allocation: Set(allocation),
+        balance: Set(0.0),
+        is_individual: Set(true),
+        rollover: Set(rollover),
+        is_deleted: Set(false),
+        contribution: Set(0.0),
+        goal: Set(None),
+        is_private: Set(false),
+        alert_below: Set(None),
+        daily_limit: Set(None),
+        min_balance: Set(None),
+        thread_id: Set(None),
+        weekly: Set(false),
+        ping_mention: Set(None),
+        pinned: Set(false),
+        frozen: Set(false),
+        pace_never_warn: Set(false),
+        ..Default::default()
+    };
+
+    let result = template.insert(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(result)
+}
+
+/// Checks sinking-fund settings for an envelope with the given rollover setting without
+/// touching the database, so callers can reject them before creating anything.
+///
+/// # Errors
+/// Returns `Error::InvalidAmount` if the contribution is negative or not finite, or the
+/// goal is not a positive, finite amount. Returns `Error::Config` for a contribution to an
+/// envelope that doesn't roll over, since the monthly update would reset what it saved.
+pub fn validate_sinking_fund(contribution: f64, goal: Option<f64>, rollover: bool) -> Result<()> {
+    if !contribution.is_finite() || contribution < 0.0 {
+        return Err(Error::InvalidAmount {
+            amount: contribution,
+        });
+    }
+    if contribution > 0.0 && !rollover {
+        return Err(Error::Config {
+            message: "Sinking-fund contributions need rollover, or the balance they build \
+                      would be reset every month"
+                .to_string(),
+        });
+    }
+
+    if let Some(goal_amount) = goal
+        && (!goal_amount.is_finite() || goal_amount <= 0.0)
+    {
+        return Err(Error::InvalidAmount {
+            amount: goal_amount,
+        });
+    }
+    Ok(())
+}
+
+/// Configures an envelope as a sinking fund with a scheduled monthly contribution.
+///
+/// The contribution is added to the balance during each monthly update, on top of
+/// the regular allocation. An optional goal records the savings target used for
+/// progress display. Passing a contribution of zero turns the behavior off.
+///
+/// # Errors
+/// Returns an error if:
+/// - The contribution is negative or not finite
+/// - The goal is not a positive, finite amount
+/// - The contribution is positive but the envelope doesn't roll over (`Error::Config`)
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_sinking_fund(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    contribution: f64,
+    goal: Option<f64>,
+) -> Result<envelope::Model> {
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+    validate_sinking_fund(contribution, goal, envelope.rollover)?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.contribution = Set(contribution);
+    active_model.goal = Set(goal);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Sets an envelope's monthly allocation.
+///
+/// # Errors
+/// Returns an error if:
+/// - The allocation is negative or not finite
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_allocation(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    allocation: f64,
+) -> Result<envelope::Model> {
+    if !allocation.is_finite() || allocation < 0.0 {
+        return Err(Error::InvalidAmount { amount: allocation });
+    }
+
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.allocation = Set(allocation);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// An envelope's allocation before and after [`set_allocations`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllocationChange {
+    /// Envelope that changed
+    pub envelope_id: i64,
+    /// Envelope name
+    pub envelope_name: String,
+    /// Owner of an individual envelope
+    pub user_id: Option<String>,
+    /// Allocation before the change
+    pub before: f64,
+    /// Allocation after the change
+    pub after: f64,
+}
+
+/// Sets the monthly allocations of several envelopes in one database transaction.
+///
+/// `allocations` pairs envelope IDs with their new allocation. Either every allocation is
+/// written or none is. Envelopes whose allocation doesn't change are left out of the
+/// returned changes.
+///
+/// # Errors
+/// Returns an error, without changing anything, if:
+/// - An allocation is negative or not finite (`Error::InvalidAmount`)
+/// - An envelope does not exist or is deleted (`Error::EnvelopeNotFound`)
+/// - A database query or update fails
+pub async fn set_allocations(
+    db: &DatabaseConnection,
+    allocations: &[(i64, f64)],
+) -> Result<Vec<AllocationChange>> {
+    if let Some(&(_, amount)) = allocations
+        .iter()
+        .find(|(_, allocation)| !allocation.is_finite() || *allocation < 0.0)
+    {
+        return Err(Error::InvalidAmount { amount });
+    }
+
+    let txn = db.begin().await?;
+    let mut changes = Vec::new();
+    for &(envelope_id, allocation) in allocations {
+        let envelope = Envelope::find_by_id(envelope_id)
+            .one(&txn)
+            .await?
+            .filter(|env| !env.is_deleted)
+            .ok_or_else(|| Error::EnvelopeNotFound {
+                name: envelope_id.to_string(),
+            })?;
+        if (envelope.allocation - allocation).abs() < f64::EPSILON {
+            continue;
+        }
+
+        changes.push(AllocationChange {
+            envelope_id,
+            envelope_name: envelope.name.clone(),
+            user_id: envelope.user_id.clone(),
+            before: envelope.allocation,
+            after: allocation,
+        });
+        let mut active_model: envelope::ActiveModel = envelope.into();
+        active_model.allocation = Set(allocation);
+        active_model.update(&txn).await?;
+    }
+
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(changes)
+}
+
+/// Marks an individual envelope as private (or public again).
+///
+/// Private envelopes are only visible to their owner; see [`crate::core::report`] for
+/// how other household members see them.
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope does not exist or is deleted
+/// - The envelope is shared (only individual envelopes can be private)
+/// - The database update operation fails
+pub async fn set_private(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    is_private: bool,
+) -> Result<envelope::Model> {
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    if is_private && !envelope.is_individual {
+        return Err(Error::Config {
+            message: format!(
+                "Envelope '{}' is shared; only individual envelopes can be private",
+                envelope.name
+            ),
+        });
+    }
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.is_private = Set(is_private);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Turns weekly mode on or off for an envelope.
+///
+/// See [`crate::core::report::get_weekly_spending`] for the weekly breakdown.
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_weekly(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    weekly: bool,
+) -> Result<envelope::Model> {
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.weekly = Set(weekly);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Freezes an envelope against new spends, or unfreezes it.
+///
+/// With `until`, the freeze lasts through that day in the household timezone and then
+/// ends on its own (see [`is_frozen_on`]); without it, it lasts until unfrozen.
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_frozen(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    frozen: bool,
+    until: Option<NaiveDate>,
+) -> Result<envelope::Model> {
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.frozen = Set(frozen);
+    active_model.frozen_until = Set(until.filter(|_| frozen));
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Whether an envelope's freeze still applies on `today`.
+#[must_use]
+pub fn is_frozen_on(envelope: &envelope::Model, today: NaiveDate) -> bool {
+    envelope.frozen && envelope.frozen_until.is_none_or(|until| today <= until)
+}
+
+/// Sets (or clears) the balance below which a low-balance alert is posted.
+///
+/// See [`crate::core::alerts`] for when alerts fire and how they are deduplicated.
+///
+/// # Errors
+/// Returns an error if:
+/// - The threshold is negative or not finite
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_alert_threshold(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    alert_below: Option<f64>,
+) -> Result<envelope::Model> {
+    if let Some(threshold) = alert_below
+        && (!threshold.is_finite() || threshold < 0.0)
+    {
+        return Err(Error::InvalidAmount { amount: threshold });
+    }
+
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.alert_below = Set(alert_below);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Sets (or clears) the most that may be spent from an envelope in one day.
+///
+/// See [`crate::core::transaction::check_daily_limits`] for how the ceiling is enforced.
+///
+/// # Errors
+/// Returns an error if:
+/// - The limit is not positive and finite
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_daily_limit(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    daily_limit: Option<f64>,
+) -> Result<envelope::Model> {
+    if let Some(limit) = daily_limit
+        && (!limit.is_finite() || limit <= 0.0)
+    {
+        return Err(Error::InvalidAmount { amount: limit });
+    }
+
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.daily_limit = Set(daily_limit);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Pins an envelope to the top of reports and envelope lists, and sets (or clears) its
+/// position among them.
+///
+/// See [`get_all_active_envelopes`] for how envelopes are ordered.
+///
+/// # Errors
+/// Returns an error if:
+/// - The position is not positive (`Error::Config`)
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_display_order(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    pinned: bool,
+    sort_order: Option<i32>,
+) -> Result<envelope::Model> {
+    if let Some(position) = sort_order
+        && position < 1
+    {
+        return Err(Error::Config {
+            message: format!("Sort order must be 1 or more, not {position}"),
+        });
+    }
+
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.pinned = Set(pinned);
+    active_model.sort_order = Set(sort_order);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Sets (or clears) the balance spends may not take an envelope below.
+///
+/// See [`crate::core::transaction::create_transaction`] for how the floor is enforced.
+///
+/// # Errors
+/// Returns an error if:
+/// - The floor is negative or not finite
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_min_balance(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    min_balance: Option<f64>,
+) -> Result<envelope::Model> {
+    if let Some(floor) = min_balance
+        && (!floor.is_finite() || floor < 0.0)
+    {
+        return Err(Error::InvalidAmount { amount: floor });
+    }
+
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.min_balance = Set(min_balance);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Sets (or clears) the monthly interest rate, in percent, paid on a rollover envelope.
+///
+/// See [`crate::core::monthly::process_monthly_updates`] for when interest is paid.
+///
+/// # Errors
+/// Returns an error if:
+/// - The rate is not positive and finite, or above 100%
+/// - The envelope does not exist or is deleted
+/// - The envelope doesn't roll over (its balance resets, so there's nothing to earn on)
+/// - The database update operation fails
+pub async fn set_interest_rate(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    interest_rate: Option<f64>,
+) -> Result<envelope::Model> {
+    if let Some(rate) = interest_rate
+        && (!rate.is_finite() || rate <= 0.0 || rate > 100.0)
+    {
+        return Err(Error::InvalidAmount { amount: rate });
+    }
+
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    if interest_rate.is_some() && !envelope.rollover {
+        return Err(Error::Config {
+            message: format!(
+                "Envelope '{}' doesn't roll over, so it can't earn interest.",
+                envelope.name
+            ),
+        });
+    }
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.interest_rate = Set(interest_rate);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Description of spends recorded without one and without an envelope default.
+pub const FALLBACK_DESCRIPTION: &str = "Transaction";
+
+/// Longest default description kept, in characters.
+pub const MAX_DEFAULT_DESCRIPTION_LEN: usize = 100;
+
+/// Normalizes a default description for an envelope or product: collapses whitespace
+/// and keeps at most [`MAX_DEFAULT_DESCRIPTION_LEN`] characters. Returns `None` if it's
+/// blank.
+#[must_use]
+pub fn normalize_default_description(raw: &str) -> Option<String> {
+    let description: String = collapse_whitespace(raw)
+        .chars()
+        .take(MAX_DEFAULT_DESCRIPTION_LEN)
+        .collect();
+    let description = description.trim_end().to_string();
+    (!description.is_empty()).then_some(description)
+}
+
+/// Sets (or clears) the description given to an envelope's spends recorded without one.
+///
+/// A blank description clears it.
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_default_description(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    description: Option<&str>,
+) -> Result<envelope::Model> {
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.default_description = Set(description.and_then(normalize_default_description));
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Picks the description of a spend: the one given, else the envelope's default
+/// description, else [`FALLBACK_DESCRIPTION`].
+#[must_use]
+pub fn spend_description(envelope: &envelope::Model, given: Option<&str>) -> String {
+    given
+        .map(str::trim)
+        .filter(|desc| !desc.is_empty())
+        .or(envelope.default_description.as_deref())
+        .unwrap_or(FALLBACK_DESCRIPTION)
+        .to_string()
+}
+
+/// Normalizes a Discord role or member mention to `<@&id>` or `<@id>`.
+///
+/// Accepts the raw mention forms Discord inserts into messages (`<@&id>`, `<@id>` and the
+/// legacy nickname form `<@!id>`). Returns `None` for anything else.
+#[must_use]
+pub fn normalize_mention(raw: &str) -> Option<String> {
+    let inner = raw.trim().strip_prefix("<@")?.strip_suffix('>')?;
+    let (prefix, id) = inner.strip_prefix('&').map_or_else(
+        || ("", inner.strip_prefix('!').unwrap_or(inner)),
+        |id| ("&", id),
+    );
+    let is_snowflake = !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit());
+    is_snowflake.then(|| format!("<@{prefix}{id}>"))
+}
+
+/// Sets (or clears) the role or member pinged when an envelope overspends.
+///
+/// See [`crate::core::alerts::check_overspends`] for when the ping fires.
+///
+/// # Errors
+/// Returns an error if:
+/// - The mention is not a role or member mention
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_ping_mention(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    mention: Option<&str>,
+) -> Result<envelope::Model> {
+    let mention = mention
+        .map(|raw| {
+            normalize_mention(raw).ok_or_else(|| Error::Config {
+                message: format!("'{raw}' is not a role or member mention."),
+            })
+        })
+        .transpose()?;
+
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.ping_mention = Set(mention);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Links an envelope to a Discord thread for spend notes, or unlinks it with `None`.
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_thread(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    thread_id: Option<String>,
+) -> Result<envelope::Model> {
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let mut active_model: envelope::ActiveModel = envelope.into();
+    active_model.thread_id = Set(thread_id);
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(updated)
+}
+
+/// Renames an envelope while keeping its ID, so transactions and product links follow it.
+///
+/// Individual envelopes are renamed for every user at once, because products linked to an
+/// individual envelope resolve each user's copy by name. The new name must not be used by
+/// any envelope in the same scope (shared, or the same user), including soft-deleted ones,
+/// so re-enabling an old envelope can never produce a duplicate. Names are normalized and
+/// compared ignoring case and spacing, so changing only a name's case is allowed.
+///
+/// # Returns
+/// All envelopes that were renamed
+///
+/// # Errors
+/// Returns an error if:
+/// - The new name is invalid (`Error::Config`)
+/// - The envelope does not exist or is deleted (`Error::EnvelopeNotFound`)
+/// - The new name is already taken (`Error::EnvelopeAlreadyExists`)
+/// - A database query or update fails
+pub async fn rename_envelope(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    new_name: &str,
+) -> Result<Vec<envelope::Model>> {
+    let new_name = normalize_envelope_name(new_name)?;
+
+    let envelope = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let targets = if envelope.is_individual {
+        get_individual_envelopes_by_name(db, &envelope.name).await?
+    } else {
+        vec![envelope]
+    };
+    let target_ids: Vec<i64> = targets.iter().map(|env| env.id).collect();
+
+    let txn = db.begin().await?;
+
+    // Any envelope in the same scope, active or soft-deleted, blocks the new name
+    for target in &targets {
+        let scope = target.user_id.as_deref().map_or_else(
+            || envelope::Column::UserId.is_null(),
+            |uid| envelope::Column::UserId.eq(uid),
+        );
+        let conflict = Envelope::find()
+            .filter(name_matches(&new_name))
+            .filter(scope)
+            .filter(envelope::Column::Id.is_not_in(target_ids.clone()))
+            .one(&txn)
+            .await?;
+        if let Some(conflict) = conflict {
+            return Err(Error::EnvelopeAlreadyExists {
+                name: conflict.name,
+            });
+        }
+    }
+
+    let mut renamed = Vec::with_capacity(targets.len());
+    for target in targets {
+        let mut active_model: envelope::ActiveModel = target.into();
+        active_model.name = Set(new_name.clone());
+        renamed.push(active_model.update(&txn).await?);
+    }
+
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(renamed)
+}
+
+/// Brings back a soft-deleted envelope.
+///
+/// Its name is normalized with [`normalize_envelope_name`] and checked for clashes like
+/// in [`create_envelope`], since another envelope may have taken it meanwhile.
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope does not exist or isn't deleted (`Error::EnvelopeNotFound`)
+/// - Its name is invalid (`Error::Config`)
+/// - Its name has been taken since (`Error::EnvelopeAlreadyExists`)
+/// - The database update operation fails
+pub async fn restore_envelope(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+) -> Result<envelope::Model> {
+    let deleted = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+    let name = normalize_envelope_name(&deleted.name)?;
+    if let Some(existing) = find_name_conflict(db, &name, deleted.user_id.as_deref()).await? {
+        return Err(Error::EnvelopeAlreadyExists {
+            name: existing.name,
+        });
+    }
+
+    let mut active_model: envelope::ActiveModel = deleted.into();
+    active_model.name = Set(name);
+    active_model.is_deleted = Set(false);
+    let restored = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(restored)
+}
+
+/// Envelopes and products created by [`clone_envelope`].
+#[derive(Debug, Clone)]
+pub struct ClonedEnvelope {
+    /// The new envelopes, one per member for individual envelopes
+    pub envelopes: Vec<envelope::Model>,
+    /// Copies of the source's linked products, named `<product> (<new envelope>)`
+    pub products: Vec<product_entity::Model>,
+}
+
+/// Copies an envelope's settings to a new envelope, e.g. for a seasonal variant.
+///
+/// The copy gets the source's category, rollover, sinking-fund contribution and goal,
+/// privacy, low-balance and daily-limit thresholds, default description, and pace
+/// thresholds, plus a copy of each linked product. It starts with a zero balance and
+/// the source's allocation unless `allocation` is given. Individual envelopes are cloned
+/// for every member who has the source envelope, like [`rename_envelope`]. Everything is
+/// created in one database transaction.
+///
+/// # Errors
+/// Returns an error if:
+/// - The new name is invalid, or a product copy's name is taken (`Error::Config`)
+/// - The allocation is negative or not finite (`Error::InvalidAmount`)
+/// - The source does not exist or is deleted (`Error::EnvelopeNotFound`)
+/// - The new name is already taken (`Error::EnvelopeAlreadyExists`)
+/// - A database query or insert fails
+pub async fn clone_envelope(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    new_name: &str,
+    allocation: Option<f64>,
+) -> Result<ClonedEnvelope> {
+    let new_name = normalize_envelope_name(new_name)?;
+    if let Some(amount) = allocation
+        && (!amount.is_finite() || amount < 0.0)
+    {
+        return Err(Error::InvalidAmount { amount });
+    }
+
+    let source = get_envelope_by_id(db, envelope_id)
+        .await?
+        .filter(|env| !env.is_deleted)
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+    let sources = if source.is_individual {
+        get_individual_envelopes_by_name(db, &source.name).await?
+    } else {
+        vec![source]
+    };
+
+    let txn = db.begin().await?;
+
+    let mut envelopes = Vec::with_capacity(sources.len());
+    for source in &sources {
+        // Any envelope in the same scope, active or soft-deleted, blocks the new name
+        let scope = source.user_id.as_deref().map_or_else(
+            || envelope::Column::UserId.is_null(),
+            |uid| envelope::Column::UserId.eq(uid),
+        );
+        let conflict = Envelope::find()
+            .filter(name_matches(&new_name))
+            .filter(scope)
+            .one(&txn)
+            .await?;
+        if let Some(conflict) = conflict {
+            return Err(Error::EnvelopeAlreadyExists {
+                name: conflict.name,
+            });
+        }
+
+        let clone = envelope::ActiveModel {
+            name: Set(new_name.clone()),
+            user_id: Set(source.user_id.clone()),
+            category: Set(source.category.clone()),
+            allocation: Set(allocation.unwrap_or(source.allocation)),
+            balance: Set(0.0),
+            is_individual: Set(source.is_individual),
+            rollover: Set(source.rollover),
+            is_deleted: Set(false),
+            contribution: Set(source.contribution),
+            goal: Set(source.goal),
+            is_private: Set(source.is_private),
+            alert_below: Set(source.alert_below),
+            daily_limit: Set(source.daily_limit),
+            min_balance: Set(source.min_balance),
+            thread_id: Set(None),
+            weekly: Set(source.weekly),
+            ping_mention: Set(source.ping_mention.clone()),
+            interest_rate: Set(source.interest_rate),
+            pinned: Set(source.pinned),
+            sort_order: Set(source.sort_order),
+            default_description: Set(source.default_description.clone()),
+            frozen: Set(false),
+            pace_on_track: Set(source.pace_on_track),
+            pace_slightly_over: Set(source.pace_slightly_over),
+            pace_never_warn: Set(source.pace_never_warn),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        envelopes.push(clone);
+    }
+
+    let mut products = Vec::new();
+    for (source, clone) in sources.iter().zip(&envelopes) {
+        let linked = product_entity::Entity::find()
+            .filter(product_entity::Column::EnvelopeId.eq(source.id))
+            .filter(product_entity::Column::IsDeleted.eq(false))
+            .order_by_asc(product_entity::Column::Name)
+            .all(&txn)
+            .await?;
+        for linked_product in linked {
+            let name = format!("{} ({new_name})", linked_product.name);
+            if product::get_product_by_name(&txn, &name).await?.is_some() {
+                return Err(Error::Config {
+                    message: format!("A product named '{name}' already exists"),
+                });
+            }
+            let copy = product::create_product(&txn, name, linked_product.price, clone.id).await?;
+            let copy = match linked_product.default_description.as_deref() {
+                Some(description) => {
+                    product::set_default_description(&txn, copy.id, Some(description)).await?
+                }
+                None => copy,
+            };
+            products.push(copy);
+        }
+    }
+
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(ClonedEnvelope {
+        envelopes,
+        products,
+    })
+}
+
+/// What [`merge_envelopes`] moved from the source envelope to the target.
+#[derive(Debug, Clone)]
+pub struct MergedEnvelope {
+    /// The target envelope after the merge
+    pub target: envelope::Model,
+    /// Number of transactions re-pointed to the target
+    pub transactions: u64,
+    /// Number of products re-linked to the target
+    pub products: u64,
+    /// Balance moved from the source to the target
+    pub balance: f64,
+}
+
+/// Merges a duplicate envelope into another one.
+///
+/// Transactions, product links, favorites, bills, scheduled spends, wishlist proposals and
+/// held large spends, and shopping list items are re-pointed from the source to the
+/// target, spend permissions are combined so everyone who could spend from either
+/// envelope can spend from the target, funding rules naming the source name the target,
+/// the source's balance is added to the target's, and the source is soft-deleted, all in
+/// one database transaction. Both envelopes must be in the same scope: both shared, or
+/// both owned by the same member.
+///
+/// Every transaction moves, including those in months that are already closed, while
+/// monthly statements and balance snapshots stay with the source as history. Past
+/// statements therefore no longer match the target's transaction history.
+///
+/// # Errors
+/// Returns an error if:
+/// - Source and target are the same envelope, or in different scopes (`Error::Config`)
+/// - Either envelope does not exist or is deleted (`Error::EnvelopeNotFound`)
+/// - A database query or update fails
+pub async fn merge_envelopes(
+    db: &DatabaseConnection,
+    source_id: i64,
+    target_id: i64,
+) -> Result<MergedEnvelope> {
+    if source_id == target_id {
+        return Err(Error::Config {
+            message: "An envelope can't be merged into itself".to_string(),
+        });
+    }
+
+    let txn = db.begin().await?;
+    let mut envelopes = Vec::with_capacity(2);
+    for id in [source_id, target_id] {
+        let env = Envelope::find_by_id(id)
+            .one(&txn)
+            .await?
+            .filter(|env| !env.is_deleted)
+            .ok_or_else(|| Error::EnvelopeNotFound {
+                name: id.to_string(),
+            })?;
+        envelopes.push(env);
+    }
+    let (Some(target), Some(source)) = (envelopes.pop(), envelopes.pop()) else {
+        return Err(Error::EnvelopeNotFound {
+            name: source_id.to_string(),
+        });
+    };
+    if source.user_id != target.user_id || source.is_individual != target.is_individual {
+        return Err(Error::Config {
+            message: format!(
+                "'{}' and '{}' must both be shared, or both belong to the same member",
+                source.name, target.name
+            ),
         });
     }
 
-    let envelope = envelope::ActiveModel {
-        name: Set(name.trim().to_string()),
-        user_id: Set(user_id),
-        category: Set(category),
-        allocation: Set(allocation),
-        balance: Set(0.0),
-        is_individual: Set(is_individual),
-        rollover: Set(rollover),
-        is_deleted: Set(false),
-        ..Default::default()
-    };
-
-    let result = envelope.insert(db).await?;
-    Ok(result)
+    let transactions = crate::entities::Transaction::update_many()
+        .col_expr(
+            transaction_entity::Column::EnvelopeId,
+            Expr::value(target.id),
+        )
+        .filter(transaction_entity::Column::EnvelopeId.eq(source.id))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+    let products = product_entity::Entity::update_many()
+        .col_expr(product_entity::Column::EnvelopeId, Expr::value(target.id))
+        .filter(product_entity::Column::EnvelopeId.eq(source.id))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+    Favorite::update_many()
+        .col_expr(favorite::Column::EnvelopeId, Expr::value(target.id))
+        .filter(favorite::Column::EnvelopeId.eq(source.id))
+        .exec(&txn)
+        .await?;
+    Bill::update_many()
+        .col_expr(bill::Column::EnvelopeId, Expr::value(target.id))
+        .filter(bill::Column::EnvelopeId.eq(source.id))
+        .exec(&txn)
+        .await?;
+    ScheduledTransaction::update_many()
+        .col_expr(
+            scheduled_transaction::Column::EnvelopeId,
+            Expr::value(target.id),
+        )
+        .filter(scheduled_transaction::Column::EnvelopeId.eq(source.id))
+        .exec(&txn)
+        .await?;
+    PendingPurchase::update_many()
+        .col_expr(pending_purchase::Column::EnvelopeId, Expr::value(target.id))
+        .filter(pending_purchase::Column::EnvelopeId.eq(source.id))
+        .exec(&txn)
+        .await?;
+    ShoppingListItem::update_many()
+        .col_expr(
+            shopping_list_item::Column::EnvelopeId,
+            Expr::value(target.id),
+        )
+        .filter(shopping_list_item::Column::EnvelopeId.eq(source.id))
+        .exec(&txn)
+        .await?;
+    envelope_permission::merge_spenders(&txn, source.id, target.id).await?;
+    allocation::move_funding_rules(&txn, source.id, target.id).await?;
+
+    let balance = source.balance;
+    let mut source_model: envelope::ActiveModel = source.into();
+    source_model.balance = Set(0.0);
+    source_model.is_deleted = Set(true);
+    source_model.thread_id = Set(None);
+    source_model.update(&txn).await?;
+
+    let target_balance = target.balance;
+    let mut target_model: envelope::ActiveModel = target.into();
+    target_model.balance = Set(target_balance + balance);
+    let target = target_model.update(&txn).await?;
+
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    cache::invalidate(CachedTable::Products);
+    Ok(MergedEnvelope {
+        target,
+        transactions,
+        products,
+        balance,
+    })
 }
 
 /// Updates the balance of an existing envelope by atomically adding an amount.
@@ -212,6 +1587,7 @@ where
         .filter(envelope::Column::Id.eq(envelope_id))
         .exec(db)
         .await?;
+    cache::invalidate(CachedTable::Envelopes);
 
     // Fetch and return the updated envelope
     // This will error if envelope doesn't exist (was deleted or never existed)
@@ -325,7 +1701,7 @@ mod tests {
         // Manually create two shared envelopes with the same name to simulate data corruption
         // (This shouldn't happen in normal operation due to application-level constraints)
         create_test_envelope(&db, "Duplicate").await?;
-        create_test_envelope(&db, "Duplicate").await?;
+        insert_unchecked_envelope(&db, "Duplicate").await?;
 
         // Attempting to get the envelope should return a DuplicateSharedEnvelope error
         let result = get_shared_envelope_by_name(&db, "Duplicate").await;
@@ -626,6 +2002,222 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_set_sinking_fund() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        assert_eq!(envelope.contribution, 0.0);
+        assert_eq!(envelope.goal, None);
+
+        // Without rollover the monthly update would reset what contributions save
+        let result = set_sinking_fund(&db, envelope.id, 50.0, Some(600.0)).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+        assert!(
+            set_sinking_fund(&db, envelope.id, 0.0, Some(600.0))
+                .await
+                .is_ok()
+        );
+        let mut active_model: envelope::ActiveModel = envelope.clone().into();
+        active_model.rollover = Set(true);
+        active_model.update(&db).await?;
+
+        let updated = set_sinking_fund(&db, envelope.id, 50.0, Some(600.0)).await?;
+        assert_eq!(updated.contribution, 50.0);
+        assert_eq!(updated.goal, Some(600.0));
+
+        // Invalid amounts are rejected
+        let result = set_sinking_fund(&db, envelope.id, -5.0, None).await;
+        assert!(matches!(result, Err(Error::InvalidAmount { amount: -5.0 })));
+        let result = set_sinking_fund(&db, envelope.id, 50.0, Some(0.0)).await;
+        assert!(matches!(result, Err(Error::InvalidAmount { amount: 0.0 })));
+        assert!(matches!(
+            validate_sinking_fund(50.0, Some(f64::NAN), true),
+            Err(Error::InvalidAmount { .. })
+        ));
+        assert!(validate_sinking_fund(0.0, None, false).is_ok());
+
+        // Clearing the goal keeps the contribution
+        let updated = set_sinking_fund(&db, envelope.id, 50.0, None).await?;
+        assert_eq!(updated.goal, None);
+
+        // Unknown envelopes are reported
+        let result = set_sinking_fund(&db, 999, 50.0, None).await;
+        assert!(matches!(result, Err(Error::EnvelopeNotFound { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_private() -> Result<()> {
+        let db = setup_test_db().await?;
+        let personal = create_custom_envelope(
+            &db,
+            "game",
+            Some("user1".to_string()),
+            "quality_of_life",
+            80.0,
+            true,
+            false,
+        )
+        .await?;
+        assert!(!personal.is_private);
+
+        let updated = set_private(&db, personal.id, true).await?;
+        assert!(updated.is_private);
+        let updated = set_private(&db, personal.id, false).await?;
+        assert!(!updated.is_private);
+
+        // Shared envelopes cannot be private
+        let shared = create_test_envelope(&db, "groceries").await?;
+        let result = set_private(&db, shared.id, true).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_alert_threshold() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        assert_eq!(envelope.alert_below, None);
+
+        let updated = set_alert_threshold(&db, envelope.id, Some(25.0)).await?;
+        assert_eq!(updated.alert_below, Some(25.0));
+        let cleared = set_alert_threshold(&db, envelope.id, None).await?;
+        assert_eq!(cleared.alert_below, None);
+
+        let result = set_alert_threshold(&db, envelope.id, Some(-5.0)).await;
+        assert!(matches!(result, Err(Error::InvalidAmount { .. })));
+        let result = set_alert_threshold(&db, 999, Some(5.0)).await;
+        assert!(matches!(result, Err(Error::EnvelopeNotFound { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_min_balance() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        assert_eq!(envelope.min_balance, None);
+
+        let updated = set_min_balance(&db, envelope.id, Some(100.0)).await?;
+        assert_eq!(updated.min_balance, Some(100.0));
+        let cleared = set_min_balance(&db, envelope.id, None).await?;
+        assert_eq!(cleared.min_balance, None);
+
+        let result = set_min_balance(&db, envelope.id, Some(-1.0)).await;
+        assert!(matches!(result, Err(Error::InvalidAmount { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_display_order() -> Result<()> {
+        let db = setup_test_db().await?;
+        let apples = create_test_envelope(&db, "Apples").await?;
+        let bills = create_test_envelope(&db, "Bills").await?;
+        let cats = create_test_envelope(&db, "Cats").await?;
+        let dining = create_test_envelope(&db, "Dining").await?;
+
+        set_display_order(&db, dining.id, true, None).await?;
+        set_display_order(&db, cats.id, false, Some(1)).await?;
+        set_display_order(&db, bills.id, false, Some(2)).await?;
+
+        // Pinned first, then by sort order, then unordered by name
+        let names: Vec<String> = get_all_active_envelopes(&db)
+            .await?
+            .into_iter()
+            .map(|env| env.name)
+            .collect();
+        assert_eq!(names, ["Dining", "Cats", "Bills", "Apples"]);
+        assert!(!apples.pinned);
+
+        let result = set_display_order(&db, apples.id, false, Some(0)).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_allocations() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries = create_test_envelope(&db, "groceries").await?;
+        let gas = create_test_envelope(&db, "gas").await?;
+
+        // A missing envelope rolls back the whole batch
+        let result = set_allocations(&db, &[(groceries.id, 250.0), (9999, 10.0)]).await;
+        assert!(matches!(result, Err(Error::EnvelopeNotFound { .. })));
+        let unchanged = get_envelope_by_id(&db, groceries.id).await?.unwrap();
+        assert_eq!(unchanged.allocation, 100.0);
+
+        let result = set_allocations(&db, &[(groceries.id, -5.0)]).await;
+        assert!(matches!(result, Err(Error::InvalidAmount { .. })));
+
+        let changes = set_allocations(&db, &[(groceries.id, 250.0), (gas.id, 100.0)]).await?;
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].envelope_name, "groceries");
+        assert_eq!((changes[0].before, changes[0].after), (100.0, 250.0));
+        let updated = get_envelope_by_id(&db, groceries.id).await?.unwrap();
+        assert_eq!(updated.allocation, 250.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_ping_mention() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        assert_eq!(envelope.ping_mention, None);
+
+        let role = set_ping_mention(&db, envelope.id, Some(" <@&123> ")).await?;
+        assert_eq!(role.ping_mention.as_deref(), Some("<@&123>"));
+        let member = set_ping_mention(&db, envelope.id, Some("<@!456>")).await?;
+        assert_eq!(member.ping_mention.as_deref(), Some("<@456>"));
+        let cleared = set_ping_mention(&db, envelope.id, None).await?;
+        assert_eq!(cleared.ping_mention, None);
+
+        for invalid in ["@everyone", "<#123>", "<@&>", "<@12a>"] {
+            let result = set_ping_mention(&db, envelope.id, Some(invalid)).await;
+            assert!(matches!(result, Err(Error::Config { .. })), "{invalid}");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_default_description() -> Result<()> {
+        let db = setup_test_db().await?;
+        let envelope = create_test_envelope(&db, "groceries").await?;
+        assert_eq!(spend_description(&envelope, None), FALLBACK_DESCRIPTION);
+
+        let envelope =
+            set_default_description(&db, envelope.id, Some("  Groceries   run ")).await?;
+        assert_eq!(
+            envelope.default_description.as_deref(),
+            Some("Groceries run")
+        );
+        assert_eq!(spend_description(&envelope, None), "Groceries run");
+        assert_eq!(spend_description(&envelope, Some("  ")), "Groceries run");
+        assert_eq!(spend_description(&envelope, Some(" Milk ")), "Milk");
+
+        let envelope = set_default_description(&db, envelope.id, Some(" ")).await?;
+        assert_eq!(envelope.default_description, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_thread() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        assert_eq!(envelope.thread_id, None);
+
+        let linked = set_thread(&db, envelope.id, Some("1234".to_string())).await?;
+        assert_eq!(linked.thread_id.as_deref(), Some("1234"));
+        let unlinked = set_thread(&db, envelope.id, None).await?;
+        assert_eq!(unlinked.thread_id, None);
+
+        let result = set_thread(&db, 999, None).await;
+        assert!(matches!(result, Err(Error::EnvelopeNotFound { .. })));
+
+        Ok(())
+    }
+
     /// Tests that ``create_envelope`` incorrectly allows individual envelopes with ``user_id=NULL``.
     ///
     /// This is a bug because individual envelopes MUST have a ``user_id``. The ``seed_envelopes``
@@ -638,6 +2230,83 @@ mod tests {
     /// Expected behavior:
     /// - Should reject envelopes where ``is_individual=true`` but ``user_id=None``
     /// - OR have validation in ``create_envelope`` to prevent this invalid state
+    #[tokio::test]
+    async fn test_resolve_envelope_instantiates_template() -> Result<()> {
+        let db = setup_test_db().await?;
+        let template = create_template(
+            &db,
+            "game".to_string(),
+            "quality_of_life".to_string(),
+            80.0,
+            true,
+        )
+        .await?;
+        assert!(template.is_individual);
+        assert_eq!(template.user_id, None);
+        // Templates aren't real envelopes
+        assert!(get_all_active_envelopes(&db).await?.is_empty());
+
+        let alice = resolve_envelope(&db, "game", "alice").await?.unwrap();
+        assert_ne!(alice.id, template.id);
+        assert_eq!(alice.user_id.as_deref(), Some("alice"));
+        assert_eq!(alice.category, "quality_of_life");
+        assert!(alice.rollover);
+        assert_eq!(alice.balance, 80.0);
+
+        // Later lookups find the same copy, and other members get their own
+        assert_eq!(
+            resolve_envelope(&db, "game", "alice").await?.unwrap().id,
+            alice.id
+        );
+        let bob = resolve_envelope(&db, "game", "bob").await?.unwrap();
+        assert_ne!(bob.id, alice.id);
+        assert_eq!(get_all_active_envelopes(&db).await?.len(), 2);
+
+        assert!(resolve_envelope(&db, "missing", "alice").await?.is_none());
+        assert!(instantiate_template(&db, &alice, "bob").await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_envelope_for_user() -> Result<()> {
+        let (db, shared) = setup_with_envelope().await?;
+        create_template(
+            &db,
+            "game".to_string(),
+            "quality_of_life".to_string(),
+            80.0,
+            false,
+        )
+        .await?;
+        let hobby = create_custom_envelope(
+            &db,
+            "Hobby",
+            Some("alice".to_string()),
+            "personal",
+            50.0,
+            true,
+            false,
+        )
+        .await?;
+        set_private(&db, hobby.id, true).await?;
+
+        // Own envelopes, then templates, then shared ones
+        let found = find_envelope_for_user(&db, "hobby", "alice")
+            .await?
+            .unwrap();
+        assert_eq!(found.id, hobby.id);
+        let game = find_envelope_for_user(&db, "game", "alice").await?.unwrap();
+        assert_eq!(game.user_id.as_deref(), Some("alice"));
+        let found = find_envelope_for_user(&db, &shared.name, "alice")
+            .await?
+            .unwrap();
+        assert_eq!(found.id, shared.id);
+
+        // Nobody else finds a private envelope
+        assert!(find_envelope_for_user(&db, "hobby", "bob").await?.is_none());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_create_individual_envelope_without_user_id() -> Result<()> {
         let db = setup_test_db().await?;
@@ -715,4 +2384,317 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_rename_shared_envelope() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries = create_test_envelope(&db, "groceries").await?;
+        let product = create_test_product(&db, "Milk", groceries.id).await?;
+
+        let renamed = rename_envelope(&db, groceries.id, " food ").await?;
+        assert_eq!(renamed.len(), 1);
+        assert_eq!(renamed[0].id, groceries.id);
+        assert_eq!(renamed[0].name, "food");
+
+        // Product links follow the envelope ID
+        let linked = get_envelope_by_id(&db, product.envelope_id).await?.unwrap();
+        assert_eq!(linked.name, "food");
+
+        // Case-only renames of the same envelope are allowed
+        rename_envelope(&db, groceries.id, "Food").await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_rejects_active_and_deleted_duplicates() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries = create_test_envelope(&db, "groceries").await?;
+        create_test_envelope(&db, "utilities").await?;
+        let old = create_test_envelope(&db, "old-fund").await?;
+        let mut active_model: envelope::ActiveModel = old.into();
+        active_model.is_deleted = Set(true);
+        active_model.update(&db).await?;
+
+        let result = rename_envelope(&db, groceries.id, "utilities").await;
+        assert!(matches!(result, Err(Error::EnvelopeAlreadyExists { .. })));
+        let result = rename_envelope(&db, groceries.id, "old-fund").await;
+        assert!(matches!(result, Err(Error::EnvelopeAlreadyExists { .. })));
+        let result = rename_envelope(&db, groceries.id, "   ").await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+
+        Ok(())
+    }
+
+    /// Adds a shared envelope without the name checks, as older versions allowed.
+    async fn insert_unchecked_envelope(
+        db: &DatabaseConnection,
+        name: &str,
+    ) -> Result<envelope::Model> {
+        let created = create_test_envelope(db, "unchecked placeholder").await?;
+        let mut active_model: envelope::ActiveModel = created.into();
+        active_model.name = Set(name.to_string());
+        active_model.update(db).await.map_err(Into::into)
+    }
+
+    #[test]
+    fn test_normalize_envelope_name() {
+        assert_eq!(
+            normalize_envelope_name("  Date   Night ").unwrap(),
+            "Date Night"
+        );
+        assert_eq!(
+            normalize_envelope_name("🛒 Groceries").unwrap(),
+            "🛒 Groceries"
+        );
+        assert!(normalize_envelope_name("🛒🛒").is_err());
+        assert!(normalize_envelope_name("Rent\u{7}").is_err());
+        assert!(normalize_envelope_name(&"x".repeat(MAX_ENVELOPE_NAME_LEN + 1)).is_err());
+        assert!(normalize_envelope_name(&"x".repeat(MAX_ENVELOPE_NAME_LEN)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_names_are_unique_ignoring_case_and_spacing() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries = create_test_envelope(&db, "Groceries").await?;
+        let utilities = create_test_envelope(&db, "Home  Utilities").await?;
+        assert_eq!(utilities.name, "Home Utilities");
+
+        let result = create_envelope(
+            &db,
+            "groceries ".to_string(),
+            None,
+            "necessary".to_string(),
+            100.0,
+            false,
+            false,
+        )
+        .await;
+        assert!(
+            matches!(result, Err(Error::EnvelopeAlreadyExists { name }) if name == "Groceries")
+        );
+        let result = rename_envelope(&db, groceries.id, "home utilities").await;
+        assert!(matches!(result, Err(Error::EnvelopeAlreadyExists { .. })));
+
+        // Lookups ignore case and spacing too
+        let found = get_shared_envelope_by_name(&db, " GROCERIES")
+            .await?
+            .unwrap();
+        assert_eq!(found.id, groceries.id);
+
+        // A member's individual envelope may share a shared envelope's name
+        create_custom_envelope(
+            &db,
+            "groceries",
+            Some("user1".to_string()),
+            "personal",
+            50.0,
+            true,
+            false,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicate_names() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries = create_test_envelope(&db, "Groceries").await?;
+        create_test_envelope(&db, "Rent").await?;
+
+        let legacy = insert_unchecked_envelope(&db, "groceries").await?;
+
+        let duplicates = find_duplicate_names(&db).await?;
+        assert_eq!(duplicates.len(), 1);
+        let ids: Vec<i64> = duplicates[0].iter().map(|env| env.id).collect();
+        assert_eq!(ids, vec![groceries.id, legacy.id]);
+
+        // Lookups prefer the exact spelling instead of reporting a duplicate
+        let found = get_shared_envelope_by_name(&db, "groceries")
+            .await?
+            .unwrap();
+        assert_eq!(found.id, legacy.id);
+        let found = get_shared_envelope_by_name(&db, "Groceries")
+            .await?
+            .unwrap();
+        assert_eq!(found.id, groceries.id);
+        assert!(get_shared_envelope_by_name(&db, "GROCERIES").await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_individual_envelope_renames_all_users() -> Result<()> {
+        let db = setup_test_db().await?;
+        let mut ids = Vec::new();
+        for user in ["user1", "user2"] {
+            let env = create_custom_envelope(
+                &db,
+                "game",
+                Some(user.to_string()),
+                "quality_of_life",
+                80.0,
+                true,
+                false,
+            )
+            .await?;
+            ids.push(env.id);
+        }
+
+        let renamed = rename_envelope(&db, ids[0], "hobbies").await?;
+        assert_eq!(renamed.len(), 2);
+        assert!(
+            get_envelope_by_name_and_user(&db, "hobbies", "user2")
+                .await?
+                .is_some()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clone_envelope_copies_settings_and_products() -> Result<()> {
+        let db = setup_test_db().await?;
+        let vacation = create_test_envelope(&db, "Vacation 2024").await?;
+        let mut active_model: envelope::ActiveModel = vacation.clone().into();
+        active_model.rollover = Set(true);
+        active_model.balance = Set(250.0);
+        active_model.alert_below = Set(Some(20.0));
+        active_model.daily_limit = Set(Some(75.0));
+        active_model.update(&db).await?;
+        let thresholds = report::PaceThresholds::new(5.0, 15.0)?;
+        report::set_pace_thresholds(&db, Some(vacation.id), Some(thresholds)).await?;
+        create_test_product(&db, "Hotel", vacation.id).await?;
+
+        let cloned = clone_envelope(&db, vacation.id, "Vacation 2025", Some(400.0)).await?;
+        assert_eq!(cloned.envelopes.len(), 1);
+        let copy = &cloned.envelopes[0];
+        assert_eq!(copy.name, "Vacation 2025");
+        assert_eq!(copy.category, vacation.category);
+        assert_eq!(copy.allocation, 400.0);
+        assert_eq!(copy.balance, 0.0);
+        assert!(copy.rollover);
+        assert_eq!(copy.alert_below, Some(20.0));
+        assert_eq!(copy.daily_limit, Some(75.0));
+        let pace = report::get_pace_settings(&db).await?;
+        assert_eq!(pace.for_envelope(copy.id), thresholds);
+
+        assert_eq!(cloned.products.len(), 1);
+        assert_eq!(cloned.products[0].name, "Hotel (Vacation 2025)");
+        assert_eq!(cloned.products[0].envelope_id, copy.id);
+
+        // The name is taken now, and nothing is created on failure
+        let result = clone_envelope(&db, vacation.id, "Vacation 2025", None).await;
+        assert!(matches!(result, Err(Error::EnvelopeAlreadyExists { .. })));
+        assert_eq!(get_all_active_envelopes(&db).await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clone_individual_envelope_clones_all_users() -> Result<()> {
+        let db = setup_test_db().await?;
+        let mut first = None;
+        for user in ["user1", "user2"] {
+            let env = create_custom_envelope(
+                &db,
+                "game",
+                Some(user.to_string()),
+                "quality_of_life",
+                80.0,
+                true,
+                false,
+            )
+            .await?;
+            first.get_or_insert(env.id);
+        }
+
+        let cloned = clone_envelope(&db, first.unwrap_or_default(), "game 2025", None).await?;
+        assert_eq!(cloned.envelopes.len(), 2);
+        let copy = get_envelope_by_name_and_user(&db, "game 2025", "user2")
+            .await?
+            .unwrap();
+        assert!(copy.is_individual);
+        assert_eq!(copy.allocation, 80.0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_envelopes() -> Result<()> {
+        let db = setup_test_db().await?;
+        let source = create_test_envelope(&db, "EatingOut").await?;
+        let target = create_test_envelope(&db, "Eating Out").await?;
+        create_test_transaction(&db, source.id, 40.0).await?;
+        create_test_transaction(&db, source.id, -15.0).await?;
+        create_test_transaction(&db, target.id, 10.0).await?;
+        create_test_product(&db, "Pizza", source.id).await?;
+        envelope_permission::grant_spender(&db, &source, "user1", "admin").await?;
+        envelope_permission::grant_spender(&db, &target, "user2", "admin").await?;
+        let savings = create_test_envelope(&db, "Savings").await?;
+        allocation::set_funding_rule(&db, savings.id, Some(source.id)).await?;
+        let held = crate::core::approval::hold_spend(
+            &db,
+            source.id,
+            "user1",
+            20.0,
+            "Tasting menu".to_string(),
+            None,
+            "42".to_string(),
+        )
+        .await?;
+        crate::core::shopping_list::add_item(&db, source.id, "Napkins", 1, "user1").await?;
+
+        let merged = merge_envelopes(&db, source.id, target.id).await?;
+        assert_eq!(merged.transactions, 2);
+        assert_eq!(merged.products, 1);
+        assert_eq!(merged.balance, 25.0);
+        assert_eq!(merged.target.balance, 35.0);
+
+        let moved = crate::core::transaction::get_transactions_for_envelope(&db, target.id).await?;
+        assert_eq!(moved.len(), 3);
+        let linked = product::get_products_for_envelopes(&db, &[target.id]).await?;
+        assert_eq!(linked.len(), 1);
+        let held = PendingPurchase::find_by_id(held.id)
+            .one(&db)
+            .await?
+            .unwrap();
+        assert_eq!(held.envelope_id, target.id);
+        let items = crate::core::shopping_list::get_items(&db, target.id).await?;
+        assert_eq!(items.len(), 1);
+        let spenders = envelope_permission::get_spenders(&db, target.id).await?;
+        assert_eq!(spenders.len(), 2);
+        let rules = allocation::get_funding_rules(&db).await?;
+        assert_eq!(rules.get(&savings.id), Some(&target.id));
+        let source = get_envelope_by_id(&db, source.id).await?.unwrap();
+        assert!(source.is_deleted);
+        assert_eq!(source.balance, 0.0);
+
+        // The source is gone now
+        let result = merge_envelopes(&db, source.id, target.id).await;
+        assert!(matches!(result, Err(Error::EnvelopeNotFound { .. })));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_envelopes_requires_same_scope() -> Result<()> {
+        let db = setup_test_db().await?;
+        let shared = create_test_envelope(&db, "game").await?;
+        let own = create_custom_envelope(
+            &db,
+            "games",
+            Some("user1".to_string()),
+            "quality_of_life",
+            80.0,
+            true,
+            false,
+        )
+        .await?;
+
+        let result = merge_envelopes(&db, own.id, shared.id).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+        let result = merge_envelopes(&db, shared.id, shared.id).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+        assert!(!get_envelope_by_id(&db, own.id).await?.unwrap().is_deleted);
+        Ok(())
+    }
 }
@@ -0,0 +1,266 @@
+//! Envelope permission business logic - Who may spend from a shared envelope.
+//!
+//! By default every member can spend from every shared envelope. Granting a member a
+//! permission with [`grant_spender`] restricts the envelope to its granted spenders (for
+//! example, only one partner manages "Car"). Spends and product uses by anyone else are
+//! refused by [`check_can_spend`], which transaction creation calls for every spend.
+//! Seeing an envelope and adding funds to it stay open to everyone.
+
+use crate::{
+    core::monthly::SYSTEM_USER_ID,
+    entities::{EnvelopePermission, envelope, envelope_permission},
+    errors::{Error, Result},
+};
+use sea_orm::{QueryOrder, Set, prelude::*};
+
+/// Retrieves the members allowed to spend from an envelope, oldest grant first.
+///
+/// An empty list means every member may spend from it.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_spenders<C>(db: &C, envelope_id: i64) -> Result<Vec<envelope_permission::Model>>
+where
+    C: ConnectionTrait,
+{
+    EnvelopePermission::find()
+        .filter(envelope_permission::Column::EnvelopeId.eq(envelope_id))
+        .order_by_asc(envelope_permission::Column::CreatedAt)
+        .order_by_asc(envelope_permission::Column::Id)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Allows `user_id` to spend from a shared envelope, restricting it to its spenders.
+///
+/// # Returns
+/// `true` if the permission was granted, `false` if the member already had it
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope is an individual one, which only its owner spends from (`Error::Config`)
+/// - The database query or insert fails
+pub async fn grant_spender(
+    db: &DatabaseConnection,
+    envelope: &envelope::Model,
+    user_id: &str,
+    granted_by: &str,
+) -> Result<bool> {
+    if envelope.is_individual {
+        return Err(Error::Config {
+            message: format!(
+                "'{}' is an individual envelope; spend permissions only apply to shared envelopes",
+                envelope.name
+            ),
+        });
+    }
+    let spenders = get_spenders(db, envelope.id).await?;
+    if spenders.iter().any(|spender| spender.user_id == user_id) {
+        return Ok(false);
+    }
+
+    envelope_permission::ActiveModel {
+        envelope_id: Set(envelope.id),
+        user_id: Set(user_id.to_string()),
+        granted_by: Set(granted_by.to_string()),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(true)
+}
+
+/// Takes away `user_id`'s permission to spend from an envelope.
+///
+/// Removing the last spender opens the envelope to every member again.
+///
+/// # Returns
+/// `true` if the member had the permission
+///
+/// # Errors
+/// Returns an error if the database delete fails.
+pub async fn revoke_spender(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    user_id: &str,
+) -> Result<bool> {
+    let result = EnvelopePermission::delete_many()
+        .filter(envelope_permission::Column::EnvelopeId.eq(envelope_id))
+        .filter(envelope_permission::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// Removes every spend permission on an envelope, opening it to every member.
+///
+/// # Returns
+/// The number of permissions removed
+///
+/// # Errors
+/// Returns an error if the database delete fails.
+pub async fn clear_spenders(db: &DatabaseConnection, envelope_id: i64) -> Result<u64> {
+    let result = EnvelopePermission::delete_many()
+        .filter(envelope_permission::Column::EnvelopeId.eq(envelope_id))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Gives the target of an envelope merge the spenders of both envelopes.
+///
+/// Everyone who could spend from either envelope can spend from the target afterwards:
+/// if either was open to every member the target is opened too, otherwise the source's
+/// spenders are added to the target's. The source is left without permissions.
+///
+/// # Errors
+/// Returns an error if a database query, update, or delete fails.
+pub async fn merge_spenders<C>(db: &C, source_id: i64, target_id: i64) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let source = get_spenders(db, source_id).await?;
+    let target = get_spenders(db, target_id).await?;
+    if source.is_empty() || target.is_empty() {
+        EnvelopePermission::delete_many()
+            .filter(envelope_permission::Column::EnvelopeId.is_in([source_id, target_id]))
+            .exec(db)
+            .await?;
+        return Ok(());
+    }
+
+    for spender in source {
+        if target
+            .iter()
+            .any(|existing| existing.user_id == spender.user_id)
+        {
+            spender.delete(db).await?;
+        } else {
+            let mut active_model: envelope_permission::ActiveModel = spender.into();
+            active_model.envelope_id = Set(target_id);
+            active_model.update(db).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `user_id` may spend from `envelope`.
+///
+/// Individual envelopes, envelopes without permissions, and spends made by the bot
+/// itself always pass.
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope has spenders and `user_id` isn't one of them (`Error::SpendNotPermitted`)
+/// - The database query fails
+pub async fn check_can_spend<C>(db: &C, envelope: &envelope::Model, user_id: &str) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    if envelope.is_individual || user_id == SYSTEM_USER_ID {
+        return Ok(());
+    }
+    let spenders = get_spenders(db, envelope.id).await?;
+    if spenders.is_empty() || spenders.iter().any(|spender| spender.user_id == user_id) {
+        return Ok(());
+    }
+    Err(Error::SpendNotPermitted {
+        envelope: envelope.name.clone(),
+        spenders: format_spenders(&spenders),
+    })
+}
+
+/// Lists spenders as Discord mentions, e.g. `<@1> and <@2>`.
+#[must_use]
+pub fn format_spenders(spenders: &[envelope_permission::Model]) -> String {
+    let mentions: Vec<String> = spenders
+        .iter()
+        .map(|spender| format!("<@{}>", spender.user_id))
+        .collect();
+    match mentions.split_last() {
+        None => "everyone".to_string(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_spenders_restrict_shared_envelope() -> Result<()> {
+        let db = setup_test_db().await?;
+        let car =
+            create_custom_envelope(&db, "car", None, "necessary", 100.0, false, false).await?;
+
+        // Open to everyone until a spender is granted
+        check_can_spend(&db, &car, "user2").await?;
+        assert!(grant_spender(&db, &car, "user1", "admin").await?);
+        assert!(!grant_spender(&db, &car, "user1", "admin").await?);
+        check_can_spend(&db, &car, "user1").await?;
+        check_can_spend(&db, &car, SYSTEM_USER_ID).await?;
+        assert!(matches!(
+            check_can_spend(&db, &car, "user2").await,
+            Err(Error::SpendNotPermitted { spenders, .. }) if spenders == "<@user1>"
+        ));
+
+        grant_spender(&db, &car, "user3", "admin").await?;
+        assert_eq!(
+            format_spenders(&get_spenders(&db, car.id).await?),
+            "<@user1> and <@user3>"
+        );
+
+        // Revoking every spender opens it up again
+        assert!(revoke_spender(&db, car.id, "user1").await?);
+        assert!(!revoke_spender(&db, car.id, "user1").await?);
+        assert_eq!(clear_spenders(&db, car.id).await?, 1);
+        check_can_spend(&db, &car, "user2").await?;
+
+        // Individual envelopes are only ever spent from by their owner
+        let allowance = create_custom_envelope(
+            &db,
+            "allowance",
+            Some("user2".to_string()),
+            "quality_of_life",
+            50.0,
+            true,
+            false,
+        )
+        .await?;
+        assert!(matches!(
+            grant_spender(&db, &allowance, "user1", "admin").await,
+            Err(Error::Config { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_merge_spenders() -> Result<()> {
+        let db = setup_test_db().await?;
+        let car = create_test_envelope(&db, "car").await?;
+        let auto = create_test_envelope(&db, "auto").await?;
+        let bus = create_test_envelope(&db, "bus").await?;
+        grant_spender(&db, &car, "user1", "admin").await?;
+        grant_spender(&db, &car, "user2", "admin").await?;
+        grant_spender(&db, &auto, "user2", "admin").await?;
+        grant_spender(&db, &auto, "user3", "admin").await?;
+
+        // Both restricted: the target gets everyone from both
+        merge_spenders(&db, car.id, auto.id).await?;
+        assert!(get_spenders(&db, car.id).await?.is_empty());
+        assert_eq!(
+            format_spenders(&get_spenders(&db, auto.id).await?),
+            "<@user1>, <@user2> and <@user3>"
+        );
+
+        // An open envelope opens the target
+        merge_spenders(&db, bus.id, auto.id).await?;
+        assert!(get_spenders(&db, auto.id).await?.is_empty());
+        Ok(())
+    }
+}
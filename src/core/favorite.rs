@@ -0,0 +1,186 @@
+//! Favorite business logic - Saved spends replayed with one short command.
+//!
+//! A member saves a spend they repeat often (envelope, amount, and description) under a
+//! label with [`save_favorite`], then logs it again with [`use_favorite`]. Favorites
+//! belong to the member who saved them; labels are unique per member.
+
+use crate::{
+    core::transaction,
+    entities::{Favorite, TransactionType, favorite, transaction as transaction_entity},
+    errors::{Error, Result},
+};
+use sea_orm::{QueryOrder, Set, prelude::*};
+
+/// Saves a favorite spend for `user_id`, replacing any favorite with the same label.
+///
+/// # Errors
+/// Returns an error if:
+/// - The label is empty (`Error::Config`)
+/// - The amount is not positive and finite (`Error::InvalidAmount`)
+/// - The database query or write fails
+pub async fn save_favorite(
+    db: &DatabaseConnection,
+    user_id: &str,
+    label: &str,
+    envelope_id: i64,
+    amount: f64,
+    description: String,
+) -> Result<favorite::Model> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(Error::Config {
+            message: "Favorite label cannot be empty".to_string(),
+        });
+    }
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(Error::InvalidAmount { amount });
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    if let Some(existing) = get_favorite(db, user_id, label).await? {
+        let mut active_model: favorite::ActiveModel = existing.into();
+        active_model.envelope_id = Set(envelope_id);
+        active_model.amount = Set(amount);
+        active_model.description = Set(description);
+        active_model.updated_at = Set(now);
+        return active_model.update(db).await.map_err(Into::into);
+    }
+
+    let record = favorite::ActiveModel {
+        user_id: Set(user_id.to_string()),
+        label: Set(label.to_string()),
+        envelope_id: Set(envelope_id),
+        amount: Set(amount),
+        description: Set(description),
+        updated_at: Set(now),
+        ..Default::default()
+    };
+    record.insert(db).await.map_err(Into::into)
+}
+
+/// Finds one of a member's favorites by label (case-insensitive).
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_favorite(
+    db: &DatabaseConnection,
+    user_id: &str,
+    label: &str,
+) -> Result<Option<favorite::Model>> {
+    let label = label.trim().to_lowercase();
+    Ok(get_favorites_for_user(db, user_id)
+        .await?
+        .into_iter()
+        .find(|fav| fav.label.to_lowercase() == label))
+}
+
+/// Retrieves a member's favorites, ordered by label.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_favorites_for_user(
+    db: &DatabaseConnection,
+    user_id: &str,
+) -> Result<Vec<favorite::Model>> {
+    Favorite::find()
+        .filter(favorite::Column::UserId.eq(user_id))
+        .order_by_asc(favorite::Column::Label)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Deletes one of a member's favorites.
+///
+/// # Returns
+/// `true` if a favorite with that label existed
+///
+/// # Errors
+/// Returns an error if the database query or delete fails.
+pub async fn delete_favorite(db: &DatabaseConnection, user_id: &str, label: &str) -> Result<bool> {
+    let Some(existing) = get_favorite(db, user_id, label).await? else {
+        return Ok(false);
+    };
+    Favorite::delete_by_id(existing.id).exec(db).await?;
+    Ok(true)
+}
+
+/// Logs a favorite's spend for the member who saved it.
+///
+/// Like [`transaction::create_transaction`], the call is idempotent on `message_id`.
+///
+/// # Errors
+/// Returns an error if the spend fails (e.g. `Error::InsufficientFunds`, or
+/// `Error::EnvelopeNotFound` if the envelope was deleted).
+pub async fn use_favorite(
+    db: &DatabaseConnection,
+    favorite: &favorite::Model,
+    message_id: Option<String>,
+) -> Result<transaction_entity::Model> {
+    transaction::create_transaction(
+        db,
+        favorite.envelope_id,
+        -favorite.amount,
+        favorite.description.clone(),
+        favorite.user_id.clone(),
+        message_id,
+        TransactionType::Spend,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{core::envelope, test_utils::*};
+
+    #[tokio::test]
+    async fn test_save_and_use_favorite() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 50.0).await?;
+
+        let fav =
+            save_favorite(&db, "user1", "parking", env.id, 4.5, "Parking".to_string()).await?;
+        let spend = use_favorite(&db, &fav, Some("msg1".to_string())).await?;
+        assert_eq!(spend.amount, -4.5);
+        assert_eq!(spend.description, "Parking");
+        assert_eq!(spend.user_id, "user1");
+
+        // Retried interactions don't spend twice
+        use_favorite(&db, &fav, Some("msg1".to_string())).await?;
+        let env = envelope::get_envelope_by_id(&db, env.id).await?.unwrap();
+        assert_eq!(env.balance, 45.5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_favorites_are_per_user() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        save_favorite(&db, "user1", "coffee", env.id, 3.0, "Coffee".to_string()).await?;
+        save_favorite(&db, "user2", "coffee", env.id, 5.0, "Latte".to_string()).await?;
+
+        // Saving the same label again replaces it
+        save_favorite(&db, "user1", "Coffee", env.id, 3.5, "Coffee".to_string()).await?;
+        let favorites = get_favorites_for_user(&db, "user1").await?;
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].amount, 3.5);
+        assert_eq!(
+            get_favorite(&db, "user2", "COFFEE").await?.unwrap().amount,
+            5.0
+        );
+
+        assert!(delete_favorite(&db, "user1", "coffee").await?);
+        assert!(!delete_favorite(&db, "user1", "coffee").await?);
+        assert!(get_favorite(&db, "user2", "coffee").await?.is_some());
+
+        assert!(matches!(
+            save_favorite(&db, "user1", "bad", env.id, 0.0, String::new()).await,
+            Err(Error::InvalidAmount { .. })
+        ));
+
+        Ok(())
+    }
+}
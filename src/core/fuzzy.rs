@@ -0,0 +1,92 @@
+//! Fuzzy name matching - Suggestions for mistyped envelope and product names.
+//!
+//! Autocomplete keeps slash command names exact, but prefix commands and typed-over
+//! suggestions can still miss. [`closest_match`] picks the candidate a mistyped name most
+//! likely meant: the only name it is a prefix of, or else the name within a small edit
+//! distance. Case, spaces, hyphens, and underscores are ignored throughout.
+
+/// Picks the candidate `input` most likely meant, or `None` if nothing is close enough.
+///
+/// A candidate that `input` is a prefix of wins if it is the only one. Otherwise the
+/// candidate with the smallest edit distance is chosen, as long as the distance is at
+/// most a third of the input's length (and at least 1). Ties go to the earlier candidate.
+#[must_use]
+pub fn closest_match<'a, I>(input: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let key = normalize(input);
+    if key.is_empty() {
+        return None;
+    }
+    let candidates: Vec<(&str, String)> = candidates
+        .into_iter()
+        .map(|candidate| (candidate, normalize(candidate)))
+        .collect();
+
+    let mut prefixed = candidates.iter().filter(|(_, name)| name.starts_with(&key));
+    if let (Some((only, _)), None) = (prefixed.next(), prefixed.next()) {
+        return Some(only);
+    }
+
+    let max_distance = (key.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|(candidate, name)| (*candidate, edit_distance(&key, name)))
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate)
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+#[must_use]
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = Vec::with_capacity(b.len() + 1);
+        current.push(i + 1);
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Lowercases a name and drops the separators people type inconsistently.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("grocries", "groceries"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_closest_match() {
+        let names = ["Groceries", "Eating Out", "Gas", "Games"];
+
+        assert_eq!(closest_match("grocries", names), Some("Groceries"));
+        assert_eq!(closest_match("eating-out", names), Some("Eating Out"));
+        assert_eq!(closest_match("eat", names), Some("Eating Out"));
+        // "gam" prefixes one name; "ga" prefixes two, so the nearest by edits wins
+        assert_eq!(closest_match("gam", names), Some("Games"));
+        assert_eq!(closest_match("ga", names), Some("Gas"));
+        assert_eq!(closest_match("gamez", names), Some("Games"));
+        assert_eq!(closest_match("rent", names), None);
+        assert_eq!(closest_match("  ", names), None);
+    }
+}
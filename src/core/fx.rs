@@ -0,0 +1,204 @@
+//! Foreign exchange business logic - Currency conversion for transactions.
+//!
+//! Amounts entered in a foreign currency are converted to the household currency (see
+//! [`crate::core::setup::get_currency`]) before they touch an envelope. Exchange rates
+//! come from a [`RateProvider`], so the built-in static table can be swapped for an
+//! API-backed provider without changing the callers.
+
+use crate::errors::{Error, Result};
+use std::collections::HashMap;
+
+/// Source of exchange rates between currency codes.
+///
+/// Implementations must be cheap to call from command handlers. Providers backed by a
+/// remote API are expected to refresh a cached table in the background and answer from
+/// that cache.
+pub trait RateProvider: Send + Sync {
+    /// Returns how many units of `to` one unit of `from` is worth, if known.
+    fn rate(&self, from: &str, to: &str) -> Option<f64>;
+}
+
+/// Exchange rates from a fixed table of US-dollar values per unit of each currency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticRateProvider {
+    usd_per_unit: HashMap<String, f64>,
+}
+
+impl Default for StaticRateProvider {
+    /// Approximate rates for the currencies offered during setup.
+    fn default() -> Self {
+        Self::new([
+            ("USD", 1.0),
+            ("CAD", 0.73),
+            ("EUR", 1.08),
+            ("GBP", 1.27),
+            ("AUD", 0.66),
+            ("NZD", 0.60),
+            ("JPY", 0.0067),
+        ])
+    }
+}
+
+impl StaticRateProvider {
+    /// Creates a provider from `(currency code, US dollars per unit)` pairs.
+    #[must_use]
+    pub fn new<'a>(usd_per_unit: impl IntoIterator<Item = (&'a str, f64)>) -> Self {
+        Self {
+            usd_per_unit: usd_per_unit
+                .into_iter()
+                .map(|(code, value)| (code.to_uppercase(), value))
+                .collect(),
+        }
+    }
+
+    /// Creates the default provider, overriding rates from the `FX_RATES` environment
+    /// variable (e.g. `FX_RATES="CAD=0.74,EUR=1.10"`, in US dollars per unit).
+    ///
+    /// # Errors
+    /// Returns an error if `FX_RATES` is set but an entry is malformed or not positive.
+    pub fn from_env() -> Result<Self> {
+        let mut provider = Self::default();
+        if let Ok(overrides) = std::env::var("FX_RATES") {
+            provider
+                .usd_per_unit
+                .extend(parse_rate_overrides(&overrides)?);
+        }
+        Ok(provider)
+    }
+}
+
+impl RateProvider for StaticRateProvider {
+    fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        let from_usd = self.usd_per_unit.get(&from.to_uppercase())?;
+        let to_usd = self.usd_per_unit.get(&to.to_uppercase())?;
+        Some(from_usd / to_usd)
+    }
+}
+
+/// Result of converting an amount between currencies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    /// Amount as entered, in `original_currency`
+    pub original_amount: f64,
+    /// Currency code the amount was entered in
+    pub original_currency: String,
+    /// Amount in the household currency, rounded to cents
+    pub converted_amount: f64,
+    /// Currency code of `converted_amount`
+    pub base_currency: String,
+    /// Rate applied (units of base currency per unit of original currency)
+    pub rate: f64,
+}
+
+/// Converts an amount into the base currency using the given provider.
+///
+/// # Errors
+/// Returns an error if:
+/// - The amount is not finite (`Error::InvalidAmount`)
+/// - The provider has no rate for the currency pair (`Error::Config`)
+pub fn convert(
+    provider: &dyn RateProvider,
+    amount: f64,
+    from: &str,
+    base_currency: &str,
+) -> Result<Conversion> {
+    if !amount.is_finite() {
+        return Err(Error::InvalidAmount { amount });
+    }
+
+    let from = from.trim().to_uppercase();
+    let base = base_currency.trim().to_uppercase();
+    let rate = if from == base {
+        1.0
+    } else {
+        provider.rate(&from, &base).ok_or_else(|| Error::Config {
+            message: format!("No exchange rate available from {from} to {base}"),
+        })?
+    };
+
+    Ok(Conversion {
+        original_amount: amount,
+        original_currency: from,
+        converted_amount: (amount * rate * 100.0).round() / 100.0,
+        base_currency: base,
+        rate,
+    })
+}
+
+/// Parses `FX_RATES`-style overrides: comma-separated `CODE=value` pairs.
+fn parse_rate_overrides(value: &str) -> Result<Vec<(String, f64)>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let invalid = || Error::Config {
+                message: format!("Invalid FX_RATES entry '{entry}', expected CODE=rate"),
+            };
+            let (code, rate) = entry.split_once('=').ok_or_else(invalid)?;
+            let rate: f64 = rate.trim().parse().map_err(|_| invalid())?;
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(invalid());
+            }
+            Ok((code.trim().to_uppercase(), rate))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+
+    #[test]
+    fn test_static_rates() {
+        let provider = StaticRateProvider::new([("USD", 1.0), ("CAD", 0.75)]);
+        assert_eq!(provider.rate("CAD", "USD"), Some(0.75));
+        assert_eq!(provider.rate("usd", "cad"), Some(1.0 / 0.75));
+        assert_eq!(provider.rate("XYZ", "USD"), None);
+    }
+
+    #[test]
+    fn test_convert_rounds_to_cents() -> Result<()> {
+        let provider = StaticRateProvider::new([("USD", 1.0), ("CAD", 0.7312)]);
+
+        let conversion = convert(&provider, 50.0, "cad", "USD")?;
+        assert_eq!(conversion.original_amount, 50.0);
+        assert_eq!(conversion.original_currency, "CAD");
+        assert_eq!(conversion.converted_amount, 36.56);
+        assert_eq!(conversion.base_currency, "USD");
+
+        // Same currency needs no rate
+        let same = convert(&provider, 12.34, "USD", "usd")?;
+        assert_eq!(same.converted_amount, 12.34);
+        assert_eq!(same.rate, 1.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_unknown_currency() {
+        let provider = StaticRateProvider::default();
+        assert!(matches!(
+            convert(&provider, 10.0, "XYZ", "USD"),
+            Err(Error::Config { .. })
+        ));
+        assert!(matches!(
+            convert(&provider, f64::NAN, "CAD", "USD"),
+            Err(Error::InvalidAmount { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_rate_overrides() -> Result<()> {
+        let parsed = parse_rate_overrides("cad=0.74, EUR=1.1")?;
+        assert_eq!(
+            parsed,
+            vec![("CAD".to_string(), 0.74), ("EUR".to_string(), 1.1)]
+        );
+
+        assert!(parse_rate_overrides("CAD").is_err());
+        assert!(parse_rate_overrides("CAD=-1").is_err());
+        Ok(())
+    }
+}
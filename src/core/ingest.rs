@@ -0,0 +1,195 @@
+//! Ingest business logic - Spends pushed from outside Discord.
+//!
+//! External sources such as an iOS Shortcuts automation post spends to the HTTP
+//! endpoint (`api` feature), which checks the caller with [`token_matches`] and records
+//! each [`ExternalSpend`] with [`record_external_spend`]. Spends go through
+//! [`transaction::create_transaction`] like `/spend`, so the same balance checks, floors,
+//! and caps apply. A spend with an `external_id` is recorded once no matter how often it
+//! is retried.
+
+use crate::{
+    core::{envelope, transaction},
+    entities::{TransactionType, envelope as envelope_entity, transaction as transaction_entity},
+    errors::{Error, Result},
+};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+/// Prefix that keeps external IDs apart from Discord message IDs in `message_id`.
+const EXTERNAL_ID_PREFIX: &str = "api:";
+
+/// A spend pushed by an external source.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExternalSpend {
+    /// Name of the envelope to spend from
+    pub envelope: String,
+    /// Amount spent, as a positive number
+    pub amount: f64,
+    /// Description of the expense (defaults to the envelope's default description)
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Where the money was spent (defaults to the description's first word)
+    #[serde(default)]
+    pub merchant: Option<String>,
+    /// Nickname or Discord user ID of the member who spent (defaults to the configured user)
+    #[serde(default)]
+    pub user: Option<String>,
+    /// The source's own ID for the spend, which makes retries idempotent
+    #[serde(default)]
+    pub external_id: Option<String>,
+}
+
+/// A recorded external spend with the envelope it came from.
+#[derive(Debug, Clone)]
+pub struct RecordedSpend {
+    /// The envelope after the spend
+    pub envelope: envelope_entity::Model,
+    /// The spend transaction
+    pub transaction: transaction_entity::Model,
+}
+
+/// Checks a presented token against the configured one.
+///
+/// Compares every byte so the time taken doesn't reveal how much of the token matched.
+#[must_use]
+pub fn token_matches(expected: &str, provided: &str) -> bool {
+    let (expected, provided) = (expected.as_bytes(), provided.as_bytes());
+    expected.len() == provided.len()
+        && expected
+            .iter()
+            .zip(provided)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Records an external spend for `user_id`.
+///
+/// The member's own envelope of that name is preferred (created from an individual
+/// template on first use), then the shared one.
+///
+/// # Errors
+/// Returns an error if:
+/// - The amount is not positive and finite (`Error::InvalidAmount`)
+/// - The merchant has no letters (`Error::Config`)
+/// - The envelope does not exist (`Error::EnvelopeNotFound`)
+/// - The spend is refused by [`transaction::create_transaction`]
+/// - A database query or write fails
+pub async fn record_external_spend(
+    db: &DatabaseConnection,
+    spend: &ExternalSpend,
+    user_id: &str,
+) -> Result<RecordedSpend> {
+    if !spend.amount.is_finite() || spend.amount <= 0.0 {
+        return Err(Error::InvalidAmount {
+            amount: spend.amount,
+        });
+    }
+    if let Some(name) = spend.merchant.as_deref()
+        && transaction::normalize_merchant(name).is_none()
+    {
+        return Err(Error::Config {
+            message: format!("'{name}' isn't a valid merchant name"),
+        });
+    }
+
+    let name = spend.envelope.trim();
+    let env = envelope::find_envelope_for_user(db, name, user_id)
+        .await?
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: name.to_string(),
+        })?;
+
+    let description = envelope::spend_description(&env, spend.description.as_deref());
+    let message_id = spend
+        .external_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| format!("{EXTERNAL_ID_PREFIX}{id}"));
+
+    let mut created = transaction::create_transaction(
+        db,
+        env.id,
+        -spend.amount, // Negative amount for spending
+        description,
+        user_id.to_string(),
+        message_id,
+        TransactionType::Spend,
+    )
+    .await?;
+    if let Some(merchant) = spend.merchant.as_deref() {
+        created = transaction::set_merchant(db, created.id, merchant).await?;
+    }
+
+    let envelope = envelope::get_envelope_by_id(db, env.id)
+        .await?
+        .unwrap_or(env);
+    Ok(RecordedSpend {
+        envelope,
+        transaction: created,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::test_utils::*;
+
+    fn external_spend(amount: f64, external_id: Option<&str>) -> ExternalSpend {
+        ExternalSpend {
+            envelope: "Test Envelope".to_string(),
+            amount,
+            description: Some("Costco run".to_string()),
+            merchant: None,
+            user: None,
+            external_id: external_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("secret", "secret"));
+        assert!(!token_matches("secret", "secreT"));
+        assert!(!token_matches("secret", "secret2"));
+        assert!(!token_matches("secret", ""));
+    }
+
+    #[tokio::test]
+    async fn test_record_external_spend() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 50.0).await?;
+
+        let recorded =
+            record_external_spend(&db, &external_spend(12.5, Some("abc")), "user1").await?;
+        assert_eq!(recorded.transaction.amount, -12.5);
+        assert_eq!(recorded.transaction.user_id, "user1");
+        assert_eq!(recorded.transaction.message_id.as_deref(), Some("api:abc"));
+        assert_eq!(recorded.transaction.merchant.as_deref(), Some("costco"));
+        assert_eq!(recorded.envelope.balance, 37.5);
+
+        // Retries with the same external ID don't spend twice
+        let retried =
+            record_external_spend(&db, &external_spend(12.5, Some("abc")), "user1").await?;
+        assert_eq!(retried.transaction.id, recorded.transaction.id);
+        assert_eq!(retried.envelope.balance, 37.5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_record_external_spend_rejects_invalid() -> Result<()> {
+        let (db, _env) = setup_with_envelope().await?;
+
+        let result = record_external_spend(&db, &external_spend(-5.0, None), "user1").await;
+        assert!(matches!(result, Err(Error::InvalidAmount { .. })));
+
+        let mut missing = external_spend(5.0, None);
+        missing.envelope = "nope".to_string();
+        let result = record_external_spend(&db, &missing, "user1").await;
+        assert!(matches!(result, Err(Error::EnvelopeNotFound { .. })));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+//! IOU business logic - Tracks money household members owe each other.
+//!
+//! When one partner covers an expense for the other (for example, paying for something
+//! from their own individual envelope), the debt is recorded as an IOU. IOUs between the
+//! same two members are netted into a single running balance, and settling marks every
+//! outstanding IOU between the pair as paid.
+
+use crate::{
+    entities::iou,
+    errors::{Error, Result},
+};
+use sea_orm::{Condition, QueryOrder, Set, TransactionTrait, prelude::*};
+use std::collections::BTreeMap;
+
+/// Net amount one member owes another after offsetting IOUs in both directions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetBalance {
+    /// Discord user ID of the member who owes money
+    pub debtor_id: String,
+    /// Discord user ID of the member who is owed money
+    pub creditor_id: String,
+    /// Net amount owed in dollars (always positive)
+    pub amount: f64,
+}
+
+/// Records that `debtor_id` owes `creditor_id` the given amount.
+///
+/// # Errors
+/// Returns an error if:
+/// - The amount is not positive and finite (`Error::InvalidAmount`)
+/// - The creditor and debtor are the same member (`Error::Config`)
+/// - A linked transaction does not exist (`Error::TransactionNotFound`)
+/// - The database insert fails
+pub async fn add_iou(
+    db: &DatabaseConnection,
+    creditor_id: &str,
+    debtor_id: &str,
+    amount: f64,
+    description: String,
+    transaction_id: Option<i64>,
+) -> Result<iou::Model> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(Error::InvalidAmount { amount });
+    }
+    if creditor_id == debtor_id {
+        return Err(Error::Config {
+            message: "An IOU needs two different members".to_string(),
+        });
+    }
+    if let Some(id) = transaction_id
+        && crate::core::transaction::get_transaction_by_id(db, id)
+            .await?
+            .is_none()
+    {
+        return Err(Error::TransactionNotFound { id });
+    }
+
+    let record = iou::ActiveModel {
+        creditor_id: Set(creditor_id.to_string()),
+        debtor_id: Set(debtor_id.to_string()),
+        amount: Set(amount),
+        description: Set(description),
+        transaction_id: Set(transaction_id),
+        created_at: Set(chrono::Utc::now()),
+        settled_at: Set(None),
+        ..Default::default()
+    };
+
+    record.insert(db).await.map_err(Into::into)
+}
+
+/// Retrieves all outstanding (unsettled) IOUs, oldest first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_outstanding_ious(db: &DatabaseConnection) -> Result<Vec<iou::Model>> {
+    crate::entities::Iou::find()
+        .filter(iou::Column::SettledAt.is_null())
+        .order_by_asc(iou::Column::CreatedAt)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Marks every outstanding IOU between two members as settled, in either direction.
+///
+/// # Returns
+/// The IOUs that were settled (empty if nothing was outstanding)
+///
+/// # Errors
+/// Returns an error if the database query or update fails.
+pub async fn settle_ious(
+    db: &DatabaseConnection,
+    user_a: &str,
+    user_b: &str,
+) -> Result<Vec<iou::Model>> {
+    let txn = db.begin().await?;
+
+    let between_pair = Condition::any()
+        .add(
+            Condition::all()
+                .add(iou::Column::CreditorId.eq(user_a))
+                .add(iou::Column::DebtorId.eq(user_b)),
+        )
+        .add(
+            Condition::all()
+                .add(iou::Column::CreditorId.eq(user_b))
+                .add(iou::Column::DebtorId.eq(user_a)),
+        );
+    let outstanding = crate::entities::Iou::find()
+        .filter(iou::Column::SettledAt.is_null())
+        .filter(between_pair)
+        .all(&txn)
+        .await?;
+
+    let now = chrono::Utc::now();
+    let mut settled = Vec::with_capacity(outstanding.len());
+    for record in outstanding {
+        let mut active_model: iou::ActiveModel = record.into();
+        active_model.settled_at = Set(Some(now));
+        settled.push(active_model.update(&txn).await?);
+    }
+
+    txn.commit().await?;
+    Ok(settled)
+}
+
+/// Nets IOUs into one balance per pair of members.
+///
+/// IOUs in opposite directions cancel out; pairs that net to zero are omitted. Results
+/// are ordered by the pair's user IDs so output is stable.
+#[must_use]
+pub fn net_balances(ious: &[iou::Model]) -> Vec<NetBalance> {
+    // Keyed by (lower ID, higher ID); positive means the higher ID owes the lower ID
+    let mut totals: BTreeMap<(&str, &str), f64> = BTreeMap::new();
+    for record in ious {
+        let (creditor, debtor) = (record.creditor_id.as_str(), record.debtor_id.as_str());
+        if creditor < debtor {
+            *totals.entry((creditor, debtor)).or_default() += record.amount;
+        } else {
+            *totals.entry((debtor, creditor)).or_default() -= record.amount;
+        }
+    }
+
+    totals
+        .into_iter()
+        .filter(|(_, amount)| amount.abs() >= 0.005)
+        .map(|((lower, higher), amount)| {
+            let (debtor, creditor) = if amount > 0.0 {
+                (higher, lower)
+            } else {
+                (lower, higher)
+            };
+            NetBalance {
+                debtor_id: debtor.to_string(),
+                creditor_id: creditor.to_string(),
+                amount: amount.abs(),
+            }
+        })
+        .collect()
+}
+
+/// Retrieves the net balance between each pair of members from outstanding IOUs.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_net_balances(db: &DatabaseConnection) -> Result<Vec<NetBalance>> {
+    Ok(net_balances(&get_outstanding_ious(db).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_add_iou_validation() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+
+        let tx = create_test_transaction(&db, envelope.id, 30.0).await?;
+        let record = add_iou(&db, "alice", "bob", 30.0, "Dinner".to_string(), Some(tx.id)).await?;
+        assert_eq!(record.transaction_id, Some(tx.id));
+        assert!(record.settled_at.is_none());
+
+        let result = add_iou(&db, "alice", "bob", 0.0, String::new(), None).await;
+        assert!(matches!(result, Err(Error::InvalidAmount { .. })));
+        let result = add_iou(&db, "alice", "alice", 5.0, String::new(), None).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+        let result = add_iou(&db, "alice", "bob", 5.0, String::new(), Some(999)).await;
+        assert!(matches!(
+            result,
+            Err(Error::TransactionNotFound { id: 999 })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_net_balances_and_settle() -> Result<()> {
+        let db = setup_test_db().await?;
+        add_iou(&db, "alice", "bob", 30.0, "Dinner".to_string(), None).await?;
+        add_iou(&db, "bob", "alice", 12.5, "Gas".to_string(), None).await?;
+        add_iou(&db, "carol", "alice", 10.0, "Coffee".to_string(), None).await?;
+
+        let balances = get_net_balances(&db).await?;
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[0].debtor_id, "bob");
+        assert_eq!(balances[0].creditor_id, "alice");
+        assert_eq!(balances[0].amount, 17.5);
+        assert_eq!(balances[1].debtor_id, "alice");
+        assert_eq!(balances[1].creditor_id, "carol");
+
+        // Settling clears both directions between the pair only
+        let settled = settle_ious(&db, "bob", "alice").await?;
+        assert_eq!(settled.len(), 2);
+        assert!(settled.iter().all(|record| record.settled_at.is_some()));
+
+        let balances = get_net_balances(&db).await?;
+        assert_eq!(balances.len(), 1);
+        assert_eq!(balances[0].creditor_id, "carol");
+        assert!(settle_ious(&db, "alice", "bob").await?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_net_balances_cancel_out() {
+        let record = |creditor: &str, debtor: &str, amount: f64| iou::Model {
+            id: 0,
+            creditor_id: creditor.to_string(),
+            debtor_id: debtor.to_string(),
+            amount,
+            description: String::new(),
+            transaction_id: None,
+            created_at: chrono::Utc::now(),
+            settled_at: None,
+        };
+
+        let ious = vec![record("alice", "bob", 20.0), record("bob", "alice", 20.0)];
+        assert!(net_balances(&ious).is_empty());
+    }
+}
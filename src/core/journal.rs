@@ -0,0 +1,292 @@
+//! Transaction journal - Append-only copy of committed transactions for outside tools.
+//!
+//! When a journal is configured (see [`crate::config::journal`]), [`start`] spawns a
+//! writer thread and every committed transaction is handed to it through [`record`].
+//! Recording never blocks or fails the command: entries are queued on a channel, and
+//! write errors are logged by the writer. The journal is an NDJSON or CSV file that
+//! rotates by size, so spreadsheets or accounting tools like `GnuCash` can ingest
+//! transactions without opening the `SQLite` database.
+//!
+//! Deleted transactions are journaled as a `"deleted"` entry rather than removed, so
+//! the file stays append-only. Likewise, clearing a pending spend appends a `"cleared"`
+//! entry with the posted amount.
+
+use crate::{
+    config::journal::{JournalConfig, JournalFormat},
+    entities::{TransactionType, transaction},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::{
+        OnceLock,
+        mpsc::{self, Receiver, Sender},
+    },
+};
+
+/// Queue feeding the writer thread, set once by [`start`].
+static SINK: OnceLock<Sender<JournalEntry>> = OnceLock::new();
+
+/// Why a transaction is being journaled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalEvent {
+    /// The transaction was committed
+    Created,
+    /// The transaction was deleted and its amount reversed
+    Deleted,
+    /// The pending transaction was cleared, with its posted amount
+    Cleared,
+}
+
+/// One line of the journal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JournalEntry {
+    /// What happened to the transaction
+    pub event: JournalEvent,
+    /// When the entry was recorded
+    pub recorded_at: DateTime<Utc>,
+    /// Transaction ID
+    pub id: i64,
+    /// Envelope ID
+    pub envelope_id: i64,
+    /// Signed amount (negative for spending)
+    pub amount: f64,
+    /// Description
+    pub description: String,
+    /// When the transaction was created
+    pub timestamp: DateTime<Utc>,
+    /// Discord user ID who created it
+    pub user_id: String,
+    /// Kind of transaction, e.g. `"spend"`
+    pub transaction_type: TransactionType,
+    /// Amount as entered, for foreign-currency entries
+    pub original_amount: Option<f64>,
+    /// Currency of `original_amount`
+    pub original_currency: Option<String>,
+    /// For refunds, the spend being reversed
+    pub refund_of: Option<i64>,
+    /// Whether the spend is still pending
+    pub pending: bool,
+}
+
+impl JournalEntry {
+    /// Builds an entry for a transaction.
+    #[must_use]
+    pub fn new(event: JournalEvent, transaction: &transaction::Model) -> Self {
+        Self {
+            event,
+            recorded_at: Utc::now(),
+            id: transaction.id,
+            envelope_id: transaction.envelope_id,
+            amount: transaction.amount,
+            description: transaction.description.clone(),
+            timestamp: transaction.timestamp,
+            user_id: transaction.user_id.clone(),
+            transaction_type: transaction.transaction_type,
+            original_amount: transaction.original_amount,
+            original_currency: transaction.original_currency.clone(),
+            refund_of: transaction.refund_of,
+            pending: transaction.pending,
+        }
+    }
+}
+
+/// Starts the journal writer thread. Later calls are ignored.
+pub fn start(config: JournalConfig) {
+    if SINK.get().is_some() {
+        return;
+    }
+    let (sender, receiver) = mpsc::channel();
+    if SINK.set(sender).is_ok() {
+        std::thread::spawn(move || run_writer(JournalWriter::new(config), &receiver));
+    }
+}
+
+/// Queues a transaction for the journal. Does nothing if no journal is configured.
+pub fn record(event: JournalEvent, transaction: &transaction::Model) {
+    if let Some(sink) = SINK.get()
+        && sink.send(JournalEntry::new(event, transaction)).is_err()
+    {
+        tracing::warn!(
+            "Transaction journal writer has stopped; entry {} dropped",
+            transaction.id
+        );
+    }
+}
+
+/// Appends queued entries until every sender is gone.
+fn run_writer(mut writer: JournalWriter, receiver: &Receiver<JournalEntry>) {
+    for entry in receiver {
+        if let Err(e) = writer.append(&entry) {
+            tracing::error!(
+                "Failed to journal transaction {} to {}: {e}",
+                entry.id,
+                writer.config.path.display()
+            );
+        }
+    }
+}
+
+/// Appends entries to the journal file, rotating it by size.
+pub struct JournalWriter {
+    config: JournalConfig,
+}
+
+impl JournalWriter {
+    /// Creates a writer for the configured journal file.
+    #[must_use]
+    pub const fn new(config: JournalConfig) -> Self {
+        Self { config }
+    }
+
+    /// Appends one entry, rotating the file first if it has reached its size limit.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be rotated, opened, or written.
+    pub fn append(&mut self, entry: &JournalEntry) -> io::Result<()> {
+        let size = fs::metadata(&self.config.path).map_or(0, |metadata| metadata.len());
+        if size >= self.config.max_bytes {
+            self.rotate()?;
+        }
+        let is_new = fs::metadata(&self.config.path).map_or(true, |metadata| metadata.len() == 0);
+
+        if let Some(parent) = self.config.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.path)?;
+
+        match self.config.format {
+            JournalFormat::Ndjson => {
+                let line = serde_json::to_string(entry)?;
+                writeln!(file, "{line}")?;
+            }
+            JournalFormat::Csv => write_csv(&mut file, entry, is_new)?,
+        }
+        file.flush()
+    }
+
+    /// Shifts `journal.N` to `journal.N+1` (dropping the oldest) and moves the active
+    /// file to `journal.1`.
+    fn rotate(&self) -> io::Result<()> {
+        let max_files = self.config.max_files;
+        let oldest = self.rotated_path(max_files);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for n in (1..max_files).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                fs::rename(from, self.rotated_path(n + 1))?;
+            }
+        }
+        fs::rename(&self.config.path, self.rotated_path(1))
+    }
+
+    /// Path of the `n`th rotated file, e.g. `journal.ndjson.2`.
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.config.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    }
+}
+
+/// Writes one CSV row, preceded by the header row for a new file.
+fn write_csv(file: &mut File, entry: &JournalEntry, with_header: bool) -> io::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(with_header)
+        .from_writer(file);
+    writer.serialize(entry).map_err(io::Error::other)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    fn entry(id: i64, description: &str) -> JournalEntry {
+        JournalEntry::new(
+            JournalEvent::Created,
+            &transaction::Model {
+                id,
+                envelope_id: 1,
+                amount: -12.5,
+                description: description.to_string(),
+                timestamp: Utc::now(),
+                user_id: "user1".to_string(),
+                message_id: None,
+                transaction_type: TransactionType::Spend,
+                original_amount: None,
+                original_currency: None,
+                refund_of: None,
+                pending: false,
+                merchant: None,
+                quantity: None,
+                unit: None,
+                round_up_of: None,
+                location: None,
+                deleted_at: None,
+            },
+        )
+    }
+
+    fn temp_journal(name: &str, format: JournalFormat, max_bytes: u64) -> JournalConfig {
+        let dir = std::env::temp_dir().join(format!(
+            "envelope-buddy-journal-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        JournalConfig {
+            path: dir.join("journal"),
+            format,
+            max_bytes,
+            max_files: 2,
+        }
+    }
+
+    #[test]
+    fn test_ndjson_journal_rotates() {
+        let config = temp_journal("ndjson", JournalFormat::Ndjson, 200);
+        let mut writer = JournalWriter::new(config.clone());
+
+        for id in 1..=6 {
+            writer.append(&entry(id, "Groceries")).unwrap();
+        }
+
+        let active = fs::read_to_string(&config.path).unwrap();
+        let last: serde_json::Value = serde_json::from_str(active.lines().last().unwrap()).unwrap();
+        assert_eq!(last["id"], 6);
+        assert_eq!(last["event"], "created");
+        assert!(writer.rotated_path(1).exists());
+        // Only max_files rotated files are kept
+        assert!(!writer.rotated_path(3).exists());
+
+        fs::remove_dir_all(config.path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_csv_journal_has_one_header() {
+        let config = temp_journal("csv", JournalFormat::Csv, 1024 * 1024);
+        let mut writer = JournalWriter::new(config.clone());
+
+        writer.append(&entry(1, "Coffee, large")).unwrap();
+        writer.append(&entry(2, "Bagel")).unwrap();
+
+        let content = fs::read_to_string(&config.path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("event,recorded_at,id,"));
+        assert!(lines[1].contains("\"Coffee, large\""));
+
+        fs::remove_dir_all(config.path.parent().unwrap()).unwrap();
+    }
+}
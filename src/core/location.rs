@@ -0,0 +1,205 @@
+//! Spend locations - Where the money was spent.
+//!
+//! `/spend location:` records where a spend was made, either as free text ("Costco
+//! Eastside") or as `lat, long` coordinates sent by a phone shortcut. [`normalize_location`]
+//! tidies both forms: coordinates are rounded to about 100 meters so spends at the same
+//! store group together, and free text is compared ignoring case. Refunds share their
+//! spend's location. [`get_top_locations`] totals a period's spending per location for
+//! the report's "where the money went" section.
+
+use crate::{
+    core::transaction::hidden_envelope_ids,
+    entities::{Transaction, TransactionType, transaction},
+    errors::{Error, Result},
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{Condition, Set, prelude::*};
+use std::collections::HashMap;
+
+/// Longest location kept, in characters.
+const MAX_LOCATION_LEN: usize = 60;
+
+/// Normalizes a location, returning `None` if it's blank.
+///
+/// Coordinates such as `"47.60621,-122.33207"` become `"47.606, -122.332"`; free text has
+/// its whitespace collapsed and keeps at most [`MAX_LOCATION_LEN`] characters.
+#[must_use]
+pub fn normalize_location(raw: &str) -> Option<String> {
+    if let Some((lat, long)) = parse_coordinates(raw) {
+        return Some(format!("{lat:.3}, {long:.3}"));
+    }
+    let location: String = raw
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_LOCATION_LEN)
+        .collect();
+    let location = location.trim_end().to_string();
+    (!location.is_empty()).then_some(location)
+}
+
+/// Parses `lat, long` (comma or space separated) within the valid coordinate ranges.
+fn parse_coordinates(raw: &str) -> Option<(f64, f64)> {
+    let mut parts = raw
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty());
+    let lat: f64 = parts.next()?.parse().ok()?;
+    let long: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&long)
+    {
+        return None;
+    }
+    Some((lat, long))
+}
+
+/// Sets where a transaction was made.
+///
+/// # Errors
+/// Returns an error if:
+/// - The location is blank, or the transaction does not exist (`Error::Config`)
+/// - The database update fails
+pub async fn set_location(
+    db: &DatabaseConnection,
+    transaction_id: i64,
+    location: &str,
+) -> Result<transaction::Model> {
+    let Some(location) = normalize_location(location) else {
+        return Err(Error::Config {
+            message: "Location can't be blank".to_string(),
+        });
+    };
+    let transaction = Transaction::find_active_by_id(transaction_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| Error::Config {
+            message: "Transaction not found".to_string(),
+        })?;
+
+    let mut active_model: transaction::ActiveModel = transaction.into();
+    active_model.location = Set(Some(location));
+    active_model.update(db).await.map_err(Into::into)
+}
+
+/// A location's spending over a period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationSpending {
+    /// Location as first recorded in the period
+    pub location: String,
+    /// Spending in dollars, less refunds
+    pub spent: f64,
+    /// Number of spends and refunds
+    pub transaction_count: usize,
+}
+
+/// Totals spending per location between `start` (inclusive) and `end` (exclusive),
+/// largest first, keeping the top `limit`.
+///
+/// Spends from other members' private envelopes are left out, and transactions without
+/// a location aren't counted.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_top_locations(
+    db: &DatabaseConnection,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    viewer_id: &str,
+    limit: usize,
+) -> Result<Vec<LocationSpending>> {
+    let hidden = hidden_envelope_ids(db, viewer_id).await?;
+    let transactions = Transaction::find_active()
+        .filter(transaction::Column::Location.is_not_null())
+        .filter(transaction::Column::EnvelopeId.is_not_in(hidden))
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .filter(
+            Condition::any()
+                .add(transaction::Column::Amount.lt(0.0))
+                .add(transaction::Column::TransactionType.is_in(TransactionType::REFUNDS)),
+        )
+        .all(db)
+        .await?;
+
+    // Free-text spellings that differ only in case are the same place
+    let mut totals: HashMap<String, LocationSpending> = HashMap::new();
+    for tx in transactions {
+        let Some(location) = tx.location else {
+            continue;
+        };
+        let entry = totals
+            .entry(location.to_lowercase())
+            .or_insert_with(|| LocationSpending {
+                location,
+                spent: 0.0,
+                transaction_count: 0,
+            });
+        entry.spent -= tx.amount;
+        entry.transaction_count += 1;
+    }
+
+    let mut locations: Vec<LocationSpending> = totals.into_values().collect();
+    locations.sort_by(|a, b| {
+        b.spent
+            .total_cmp(&a.spent)
+            .then_with(|| a.location.cmp(&b.location))
+    });
+    locations.truncate(limit);
+    Ok(locations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::transaction as transaction_core, test_utils::*};
+
+    #[test]
+    fn test_normalize_location() {
+        assert_eq!(
+            normalize_location("47.60621,-122.33207").as_deref(),
+            Some("47.606, -122.332")
+        );
+        assert_eq!(
+            normalize_location(" 47.6062 -122.3321 ").as_deref(),
+            Some("47.606, -122.332")
+        );
+        assert_eq!(
+            normalize_location("  Costco   Eastside ").as_deref(),
+            Some("Costco Eastside")
+        );
+        // Out-of-range numbers are kept as text
+        assert_eq!(normalize_location("123 456").as_deref(), Some("123 456"));
+        assert_eq!(normalize_location("   "), None);
+    }
+
+    #[tokio::test]
+    async fn test_top_locations() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 100.0).await?;
+        let start = Utc::now() - chrono::Duration::hours(1);
+
+        for (amount, location) in [(-20.0, "Costco"), (-15.0, "costco"), (-25.0, "Corner Deli")] {
+            let spend = create_test_transaction(&db, env.id, amount).await?;
+            set_location(&db, spend.id, location).await?;
+        }
+        let spend = create_test_transaction(&db, env.id, -30.0).await?;
+        let spend = set_location(&db, spend.id, "Corner Deli").await?;
+        let refund =
+            transaction_core::create_refund(&db, spend.id, Some(10.0), "user".to_string(), None)
+                .await?;
+        assert_eq!(refund.location.as_deref(), Some("Corner Deli"));
+        create_test_transaction(&db, env.id, -5.0).await?;
+
+        let end = Utc::now() + chrono::Duration::hours(1);
+        let locations = get_top_locations(&db, start, end, "user", 5).await?;
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[0].location, "Corner Deli");
+        assert!((locations[0].spent - 45.0).abs() < 1e-9);
+        assert_eq!(locations[0].transaction_count, 3);
+        assert_eq!(locations[1].location, "Costco");
+        assert!((locations[1].spent - 35.0).abs() < 1e-9);
+
+        assert!(set_location(&db, spend.id, " ").await.is_err());
+        Ok(())
+    }
+}
@@ -1,5 +1,51 @@
+pub mod alerts;
+pub mod alias;
+pub mod allocation;
+pub mod amount_parser;
+pub mod approval;
+pub mod audit;
+pub mod bill;
+pub mod bundle;
+pub mod cache;
+pub mod calendar;
+pub mod category_cap;
+#[cfg(feature = "charts")]
+pub mod charts;
+pub mod config_sync;
+pub mod cooldown;
+pub mod demo;
+pub mod diagnostics;
+pub mod distribution;
 pub mod envelope;
+pub mod envelope_permission;
+pub mod favorite;
+pub mod fuzzy;
+pub mod fx;
+pub mod ingest;
+pub mod iou;
+pub mod journal;
+pub mod location;
 pub mod monthly;
+pub mod notify;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod preferences;
 pub mod product;
+pub mod reconcile;
 pub mod report;
+pub mod retention;
+pub mod review;
+pub mod round_up;
+pub mod schedule;
+pub mod settings;
+pub mod setup;
+pub mod share;
+pub mod shopping_list;
+pub mod snapshot;
+pub mod state;
+pub mod statement;
+pub mod table;
 pub mod transaction;
+pub mod undo;
+pub mod usage;
+pub mod wishlist;
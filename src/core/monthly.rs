@@ -4,18 +4,59 @@
 //! This module provides functionality for processing monthly updates to envelopes,
 //! including resetting balances for non-rollover envelopes and rolling over balances
 //! for rollover envelopes. It also tracks the last monthly update timestamp using the
-//! `system_state` table to prevent duplicate updates within the same month.
+//! `system_state` table to prevent duplicate updates within the same month. Months are
+//! budget cycles, which may start on a configured payday (see [`calendar`]).
+//!
+//! Rollover envelopes with an interest rate earn interest on the balance they closed
+//! the month with, recorded as an `interest` transaction so reports can total it.
+//!
+//! A non-rollover envelope that closes the month negative is handled by the
+//! [`DeficitPolicy`]: the deficit is forgiven at the reset (the default), subtracted from
+//! the next month's allocation, or moved to a designated debt envelope.
+//!
+//! The summary also totals the change saved by round-ups over the closed month (see
+//! [`round_up`]).
+//!
+//! Funding rules (see [`allocation`]) are part of the plan: this month's allocation and
+//! contribution for an envelope waiting on another envelope to be full go to that
+//! envelope until it is. [`plan_openings`] works out every new balance without writing
+//! anything, so reconciliation can replay it from the statements.
+//!
+//! The date in `system_state` only decides whether an update looks due. Each update also
+//! claims its month in the `monthly_update_log` table, whose month column is unique, in
+//! the same database transaction as the balance changes. A second run for the same month
+//! (after a restart, a manual edit of `system_state`, or a racing `/update`) finds the
+//! month taken and changes nothing. A month earlier than one already logged means the
+//! host clock went backwards, and the update is skipped as well.
 
 use crate::{
-    entities::{Envelope, SystemState, envelope, system_state},
+    core::{
+        allocation::{self, HeldFunding},
+        cache::{self, CachedTable},
+        calendar::{self, BudgetCycle},
+        journal::{self, JournalEvent},
+        retention, round_up,
+        setup::Currency,
+        state, statement, transaction,
+    },
+    entities::{Envelope, MonthlyUpdateLog, TransactionType, envelope, monthly_update_log},
     errors::{Error, Result},
 };
-use chrono::{Datelike, NaiveDate, Utc};
-use sea_orm::{Set, TransactionTrait, prelude::*};
-use std::fmt::Write;
+use chrono::{NaiveDate, Utc};
+use sea_orm::{QueryOrder, QuerySelect, Set, TransactionTrait, prelude::*, sea_query::OnConflict};
+use std::{collections::HashMap, fmt::Write, hash::BuildHasher};
 
 const LAST_MONTHLY_UPDATE_KEY: &str = "last_monthly_update";
 
+/// `system_state` key holding the [`DeficitPolicy`].
+const DEFICIT_POLICY_KEY: &str = "deficit_policy";
+
+/// `system_state` key holding the ID of the envelope deficits are moved to.
+const DEBT_ENVELOPE_KEY: &str = "debt_envelope";
+
+/// User ID recorded on transactions the monthly update makes itself.
+pub const SYSTEM_USER_ID: &str = "system";
+
 /// Represents the result of a monthly update operation for a single envelope.
 #[derive(Debug, Clone)]
 pub struct EnvelopeUpdateResult {
@@ -29,6 +70,16 @@ pub struct EnvelopeUpdateResult {
     pub allocation: f64,
     /// Whether rollover is enabled for this envelope
     pub rollover: bool,
+    /// Sinking-fund contribution added on top of the allocation (0.0 if none)
+    pub contribution: f64,
+    /// Savings goal for sinking-fund envelopes, if one is set
+    pub goal: Option<f64>,
+    /// Interest paid on the closing balance (0.0 if none)
+    pub interest: f64,
+    /// Closing deficit subtracted from this month's allocation (0.0 if none)
+    pub carried_deficit: f64,
+    /// Closing deficit moved to the debt envelope (0.0 if none)
+    pub debt_transfer: f64,
 }
 
 /// Represents the result of processing monthly updates for all envelopes.
@@ -42,13 +93,166 @@ pub struct MonthlyUpdateResult {
     pub rollover_count: usize,
     /// Number of envelopes that were reset (no rollover)
     pub reset_count: usize,
+    /// Total sinking-fund contributions applied across all envelopes
+    pub total_contributions: f64,
+    /// Total interest paid across all envelopes
+    pub total_interest: f64,
+    /// Total deficits carried into this month's allocations
+    pub total_carried_deficit: f64,
+    /// Total deficits moved to the debt envelope
+    pub total_debt_transfer: f64,
+    /// Name of the envelope deficits were moved to, if any were
+    pub debt_envelope: Option<String>,
+    /// Funding moved to envelopes that weren't full yet, by the funding rules
+    pub held_funding: Vec<HeldFunding>,
+    /// Change saved by round-ups over the closed month
+    pub total_round_ups: f64,
+    /// Transactions pruned by the retention policy
+    pub pruned_transactions: u64,
     /// Date when the update was performed
     pub update_date: NaiveDate,
+    /// Budget month closed by the update, as `YYYY-MM` (see [`statement::month_label`])
+    pub closed_month: String,
+}
+
+/// What the monthly update does with a non-rollover envelope that closed the month
+/// negative.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeficitPolicy {
+    /// The deficit is dropped when the balance resets
+    #[default]
+    Forgive,
+    /// The deficit is subtracted from the next month's allocation
+    CarryOver,
+    /// The deficit is moved to the debt envelope, or carried over if none is set
+    Debt,
+}
+
+impl DeficitPolicy {
+    /// Every policy, in the order they're listed.
+    pub const ALL: [Self; 3] = [Self::Forgive, Self::CarryOver, Self::Debt];
+
+    /// Name of the policy as typed in `/config set deficit_policy`.
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::Forgive => "forgive",
+            Self::CarryOver => "carryover",
+            Self::Debt => "debt",
+        }
+    }
+
+    /// Looks up a policy by its [`key`](Self::key), ignoring case.
+    #[must_use]
+    pub fn from_key(key: &str) -> Option<Self> {
+        let key = key.trim();
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.key().eq_ignore_ascii_case(key))
+    }
+}
+
+/// Loads the deficit policy, or [`DeficitPolicy::Forgive`] if none is saved.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_deficit_policy<C>(db: &C) -> Result<DeficitPolicy>
+where
+    C: ConnectionTrait,
+{
+    Ok(state::get_value(db, DEFICIT_POLICY_KEY)
+        .await?
+        .and_then(|value| DeficitPolicy::from_key(&value))
+        .unwrap_or_default())
+}
+
+/// Saves the deficit policy.
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_deficit_policy<C>(db: &C, policy: DeficitPolicy) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    state::set_value(db, DEFICIT_POLICY_KEY, policy.key()).await
 }
 
-/// Checks if a monthly update is needed by comparing the last update date
-/// with the current date. Returns true if we've entered a new month since
-/// the last update, or if no previous update exists.
+/// Loads the envelope deficits are moved to, or `None` if none is set or it was deleted.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_debt_envelope<C>(db: &C) -> Result<Option<envelope::Model>>
+where
+    C: ConnectionTrait,
+{
+    let Some(id) = state::get_value(db, DEBT_ENVELOPE_KEY)
+        .await?
+        .and_then(|value| value.parse::<i64>().ok())
+    else {
+        return Ok(None);
+    };
+    Ok(Envelope::find_by_id(id)
+        .one(db)
+        .await?
+        .filter(|env| !env.is_deleted))
+}
+
+/// Saves the envelope deficits are moved to under [`DeficitPolicy::Debt`].
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_debt_envelope<C>(db: &C, envelope_id: i64) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    state::set_value(db, DEBT_ENVELOPE_KEY, &envelope_id.to_string()).await
+}
+
+/// Records the update opening `current` in the monthly update log, returning whether
+/// this run claimed the month.
+///
+/// The insert is a no-op when the month is already logged, so of two runs for the same
+/// month only one ever gets `true`. A logged month later than `current` means the clock
+/// went backwards; nothing is claimed then either.
+async fn claim_month<C>(db: &C, current: &BudgetCycle, closed_month: &str) -> Result<bool>
+where
+    C: ConnectionTrait,
+{
+    let month = statement::month_label(current);
+    let latest: Option<String> = MonthlyUpdateLog::find()
+        .select_only()
+        .column(monthly_update_log::Column::Month)
+        .order_by_desc(monthly_update_log::Column::Month)
+        .into_tuple()
+        .one(db)
+        .await?;
+    if let Some(latest) = latest.filter(|latest| *latest > month) {
+        tracing::warn!(
+            "Skipping the monthly update for {month}: {latest} was already updated, so the clock may be behind"
+        );
+        return Ok(false);
+    }
+
+    let inserted = MonthlyUpdateLog::insert(monthly_update_log::ActiveModel {
+        month: Set(month),
+        closed_month: Set(closed_month.to_string()),
+        performed_at: Set(Utc::now()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(monthly_update_log::Column::Month)
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    Ok(inserted == 1)
+}
+
+/// Checks if a monthly update is needed.
+///
+/// Compares the last update date with the start of the current budget cycle. Returns
+/// true if a new cycle has begun since the last update, or if no previous update exists.
 ///
 /// # Arguments
 /// * `db` - Database connection
@@ -61,12 +265,9 @@ pub struct MonthlyUpdateResult {
 /// Returns an error if the database query to retrieve the last update date fails.
 pub async fn is_monthly_update_needed(db: &DatabaseConnection) -> Result<bool> {
     let last_update = get_last_monthly_update_date(db).await?;
-    let now = Utc::now().date_naive();
+    let cycle = calendar::current_cycle(db, calendar::today(db).await?).await?;
 
-    last_update.map_or_else(
-        || Ok(true),
-        |last_date| Ok(last_date.year() != now.year() || last_date.month() != now.month()),
-    )
+    Ok(last_update.is_none_or(|last_date| last_date < cycle.start))
 }
 
 /// Retrieves the date of the last monthly update from the `system_state` table.
@@ -83,22 +284,15 @@ pub async fn is_monthly_update_needed(db: &DatabaseConnection) -> Result<bool> {
 /// - The database query fails
 /// - The stored date string cannot be parsed
 pub async fn get_last_monthly_update_date(db: &DatabaseConnection) -> Result<Option<NaiveDate>> {
-    let state = SystemState::find()
-        .filter(system_state::Column::Key.eq(LAST_MONTHLY_UPDATE_KEY))
-        .one(db)
-        .await?;
-
-    match state {
-        Some(s) => {
+    state::get_value(db, LAST_MONTHLY_UPDATE_KEY)
+        .await?
+        .map(|value| {
             // Parse the stored date string (format: YYYY-MM-DD)
-            NaiveDate::parse_from_str(&s.value, "%Y-%m-%d")
-                .map(Some)
-                .map_err(|e| Error::Config {
-                    message: format!("Failed to parse last update date: {e}"),
-                })
-        }
-        None => Ok(None),
-    }
+            NaiveDate::parse_from_str(&value, "%Y-%m-%d").map_err(|e| Error::Config {
+                message: format!("Failed to parse last update date: {e}"),
+            })
+        })
+        .transpose()
 }
 
 /// Updates the last monthly update date in the `system_state` table.
@@ -106,59 +300,43 @@ pub async fn get_last_monthly_update_date(db: &DatabaseConnection) -> Result<Opt
 /// # Arguments
 /// * `db` - Database connection
 /// * `date` - The date to store as the last update date
-async fn set_last_monthly_update_date<C>(db: &C, date: NaiveDate) -> Result<()>
+pub(crate) async fn set_last_monthly_update_date<C>(db: &C, date: NaiveDate) -> Result<()>
 where
     C: ConnectionTrait,
 {
     let date_str = date.format("%Y-%m-%d").to_string();
-    let now = Utc::now().naive_utc();
-
-    // Check if the key exists
-    let existing = SystemState::find()
-        .filter(system_state::Column::Key.eq(LAST_MONTHLY_UPDATE_KEY))
-        .one(db)
-        .await?;
-
-    if let Some(state) = existing {
-        // Update existing record
-        let mut active_model: system_state::ActiveModel = state.into();
-        active_model.value = Set(date_str);
-        active_model.updated_at = Set(now);
-        active_model.update(db).await?;
-    } else {
-        // Insert new record
-        let new_state = system_state::ActiveModel {
-            key: Set(LAST_MONTHLY_UPDATE_KEY.to_string()),
-            value: Set(date_str),
-            updated_at: Set(now),
-            ..Default::default()
-        };
-        new_state.insert(db).await?;
-    }
-
-    Ok(())
+    state::set_value(db, LAST_MONTHLY_UPDATE_KEY, &date_str).await
 }
 
 /// Processes monthly updates for all active envelopes. This function:
 ///
-/// 1. Checks if an update is needed (prevents duplicate updates in same month)
-/// 2. For each active envelope:
+/// 1. Checks if an update is needed (prevents duplicate updates in same month), and
+///    claims the month in the monthly update log so a repeated run is a no-op
+/// 2. Archives a statement per envelope for the month being closed
+/// 3. For each active envelope:
 ///    - If rollover is enabled: adds allocation to existing balance
-///    - If rollover is disabled: resets balance to allocation amount
-/// 3. Records the update date in `system_state`
+///    - If rollover is disabled: resets balance to allocation amount, carrying a closing
+///      deficit over or to the debt envelope as the [`DeficitPolicy`] says
+///    - Adds the envelope's sinking-fund contribution, if any, on top
+///    - Pays interest on a rollover envelope's closing balance at its rate, if any
+/// 4. Moves the deficits collected for the debt envelope to it
+/// 5. Applies the transaction retention policy (see [`retention`])
+/// 6. Records the update date in `system_state`
 ///
 /// # Arguments
 /// * `db` - Database connection
 ///
 /// # Returns
 /// * `Ok(Some(result))` - Update was performed with detailed results
-/// * `Ok(None)` - No update needed (already updated this month)
+/// * `Ok(None)` - No update needed (already updated this month, or the clock is behind
+///   the last logged update)
 ///
 /// # Errors
 /// Returns an error if:
 /// - The database transaction fails to begin or commit
 /// - Envelope balance updates fail
 /// - Recording the update date fails
+#[allow(clippy::too_many_lines, clippy::cognitive_complexity)] // Every step runs in the one database transaction
 pub async fn process_monthly_updates(
     db: &DatabaseConnection,
 ) -> Result<Option<MonthlyUpdateResult>> {
@@ -171,33 +349,90 @@ pub async fn process_monthly_updates(
     // All envelope updates must succeed or all must fail
     let txn = db.begin().await?;
 
-    let now = Utc::now().date_naive();
+    let now = calendar::today(&txn).await?;
+    let current = calendar::current_cycle(&txn, now).await?;
+    let closed = current.previous();
+    let closed_month = statement::month_label(&closed);
+    if !claim_month(&txn, &current, &closed_month).await? {
+        txn.rollback().await?;
+        return Ok(None);
+    }
+
     let mut results = Vec::new();
     let mut rollover_count = 0;
     let mut reset_count = 0;
+    let mut total_contributions = 0.0;
+    let mut total_interest = 0.0;
+    let mut interest_transactions = Vec::new();
+    let mut total_carried_deficit = 0.0;
+    let mut total_debt_transfer = 0.0;
+
+    let policy = get_deficit_policy(&txn).await?;
+    let debt_envelope = deficit_target(&txn, policy).await?;
 
     // Get all active envelopes
     let envelopes = Envelope::find()
         .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(crate::core::envelope::not_template())
         .all(&txn)
         .await?;
 
+    // Archive the month being closed before balances change
+    statement::record_statements(&txn, &envelopes, &closed, Utc::now()).await?;
+    let total_round_ups = round_up::get_round_up_total(&txn, closed.start, closed.end).await?;
+    let rules = allocation::get_funding_rules(&txn).await?;
+    // Updates run on demand, so spends may already have been made in the new month: the
+    // month is opened from the balances it closed with, and those carry over on top
+    let net_after = statement::net_after_close(&txn, &closed).await?;
+    let closing: Vec<envelope::Model> = envelopes
+        .iter()
+        .map(|env| envelope::Model {
+            balance: env.balance - net_after.get(&env.id).copied().unwrap_or(0.0),
+            ..env.clone()
+        })
+        .collect();
+    let plan = plan_openings(
+        &closing,
+        policy,
+        debt_envelope.as_ref().map(|debt| debt.id),
+        &rules,
+    );
+
     // Process each envelope
-    for env in envelopes {
+    for (env, opening) in envelopes.into_iter().zip(plan.openings) {
         let old_balance = env.balance;
-        let new_balance = if env.rollover {
-            // Rollover: add allocation to existing balance
-            env.balance + env.allocation
-        } else {
-            // No rollover: reset to allocation
-            env.allocation
-        };
+        total_carried_deficit += opening.carried_deficit;
+        total_debt_transfer += opening.debt_transfer;
+        total_contributions += env.contribution;
+        let mut new_balance = opening.balance + net_after.get(&env.id).copied().unwrap_or(0.0);
+        statement::record_opening(&txn, env.id, &closed_month, opening.balance).await?;
 
         // Update the envelope balance
         let mut active_model: envelope::ActiveModel = env.clone().into();
         active_model.balance = Set(new_balance);
         active_model.update(&txn).await?;
 
+        // Interest goes through a transaction, which also adds it to the balance
+        let interest = opening.interest;
+        if interest > 0.0 {
+            let rate = env.interest_rate.unwrap_or_default();
+            let (deposit, created) = transaction::create_unjournaled_transaction(
+                &txn,
+                env.id,
+                interest,
+                format!("Interest ({rate}% monthly)"),
+                SYSTEM_USER_ID.to_string(),
+                Some(format!("interest:{}:{closed_month}", env.id)),
+                TransactionType::Interest,
+            )
+            .await?;
+            if created {
+                interest_transactions.push(deposit);
+            }
+            new_balance += interest;
+            total_interest += interest;
+        }
+
         // Track statistics
         if env.rollover {
             rollover_count += 1;
@@ -212,24 +447,201 @@ pub async fn process_monthly_updates(
             new_balance,
             allocation: env.allocation,
             rollover: env.rollover,
+            contribution: env.contribution,
+            goal: env.goal,
+            interest,
+            carried_deficit: opening.carried_deficit,
+            debt_transfer: opening.debt_transfer,
         });
     }
 
+    // Prune raw history older than the retention policy keeps
+    let pruned_transactions = retention::apply_retention(&txn, &current, Utc::now())
+        .await?
+        .map_or(0, |result| result.pruned_transactions);
+
     // Record the update date
     set_last_monthly_update_date(&txn, now).await?;
 
     // Commit the transaction - all updates succeed or all fail
     txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    for deposit in &interest_transactions {
+        journal::record(JournalEvent::Created, deposit);
+    }
 
     Ok(Some(MonthlyUpdateResult {
         total_envelopes_processed: results.len(),
         rollover_count,
         reset_count,
+        total_contributions,
+        total_interest,
+        total_carried_deficit,
+        total_debt_transfer,
+        debt_envelope: debt_envelope
+            .filter(|_| total_debt_transfer > 0.0)
+            .map(|debt| debt.name),
+        held_funding: plan.held_funding,
+        total_round_ups,
+        pruned_transactions,
         updated_envelopes: results,
         update_date: now,
+        closed_month,
     }))
 }
 
+/// The envelope deficits are moved to under `policy`, if any.
+///
+/// Under [`DeficitPolicy::Debt`] without a debt envelope, deficits carry over instead so
+/// they don't vanish.
+async fn deficit_target<C>(db: &C, policy: DeficitPolicy) -> Result<Option<envelope::Model>>
+where
+    C: ConnectionTrait,
+{
+    if policy != DeficitPolicy::Debt {
+        return Ok(None);
+    }
+    let debt_envelope = get_debt_envelope(db).await?;
+    if debt_envelope.is_none() {
+        tracing::warn!("No debt envelope is set; carrying deficits over instead");
+    }
+    Ok(debt_envelope)
+}
+
+/// How the monthly update opens one envelope's new month, from [`plan_openings`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Opening {
+    /// Balance the update sets, before interest
+    pub balance: f64,
+    /// Interest paid on the closing balance, recorded as a transaction on top
+    pub interest: f64,
+    /// Closing deficit subtracted from this month's allocation
+    pub carried_deficit: f64,
+    /// Closing deficit moved to the debt envelope
+    pub debt_transfer: f64,
+}
+
+/// How the monthly update opens the new month, from [`plan_openings`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpeningPlan {
+    /// Each envelope's opening, in the order the envelopes were given
+    pub openings: Vec<Opening>,
+    /// Funding moved to envelopes that weren't full yet, by the funding rules
+    pub held_funding: Vec<HeldFunding>,
+}
+
+/// Works out how the monthly update opens the new month for each of `closing`, envelopes
+/// holding the balances they closed the month with.
+///
+/// Rollover envelopes add their allocation to the closing balance; the others reset to
+/// it, less any deficit carried over under `policy`. Sinking-fund contributions come on
+/// top. The funding `rules` then move funding to envelopes that aren't full yet (see
+/// [`allocation::apply_funding_rules`]), and the debt envelope (when it is among
+/// `closing`) takes the deficits moved to it. Nothing is written: the monthly update
+/// applies the plan, and reconciliation works balances forward from statements with it.
+#[must_use]
+pub fn plan_openings<S: BuildHasher>(
+    closing: &[envelope::Model],
+    policy: DeficitPolicy,
+    debt_envelope_id: Option<i64>,
+    rules: &HashMap<i64, i64, S>,
+) -> OpeningPlan {
+    // Deficits only move to a debt envelope opened along with them
+    let debt_index = debt_envelope_id.and_then(|id| closing.iter().position(|env| env.id == id));
+    let debt_envelope_id = debt_index.map(|index| closing[index].id);
+
+    let mut openings: Vec<Opening> = closing
+        .iter()
+        .map(|env| {
+            let (carried_deficit, debt_transfer) = split_deficit(env, policy, debt_envelope_id);
+            let base_balance = if env.rollover {
+                // Rollover: add allocation to existing balance
+                env.balance + env.allocation
+            } else {
+                // No rollover: reset to allocation, less any deficit carried over
+                env.allocation - carried_deficit
+            };
+            Opening {
+                // Sinking funds receive their contribution separately from the allocation
+                balance: base_balance + env.contribution,
+                interest: monthly_interest(env),
+                carried_deficit,
+                debt_transfer,
+            }
+        })
+        .collect();
+
+    // Envelopes waiting on one that isn't full give it this month's funding
+    let funding: Vec<f64> = closing
+        .iter()
+        .zip(&openings)
+        .map(|(env, opening)| {
+            (env.allocation - opening.carried_deficit + env.contribution).max(0.0)
+        })
+        .collect();
+    let mut planned: Vec<(envelope::Model, f64)> = closing
+        .iter()
+        .zip(&openings)
+        .zip(&funding)
+        .map(|((env, opening), &funding)| {
+            let before_funding = envelope::Model {
+                balance: opening.balance + opening.interest - funding,
+                ..env.clone()
+            };
+            (before_funding, funding)
+        })
+        .collect();
+    let held_funding = allocation::apply_funding_rules(&mut planned, rules);
+    for ((opening, (_, planned_funding)), funding) in
+        openings.iter_mut().zip(&planned).zip(&funding)
+    {
+        opening.balance += planned_funding - funding;
+    }
+
+    // The debt envelope takes the deficits after its own opening
+    if let Some(index) = debt_index {
+        let total_debt_transfer: f64 = openings.iter().map(|opening| opening.debt_transfer).sum();
+        openings[index].balance -= total_debt_transfer;
+    }
+    OpeningPlan {
+        openings,
+        held_funding,
+    }
+}
+
+/// Splits a non-rollover envelope's closing deficit into the part carried into its next
+/// allocation and the part moved to the debt envelope, as `(carried, moved)`.
+///
+/// Rollover envelopes keep their negative balance, so they have no deficit to split. The
+/// debt envelope carries its own deficit over.
+fn split_deficit(
+    env: &envelope::Model,
+    policy: DeficitPolicy,
+    debt_envelope_id: Option<i64>,
+) -> (f64, f64) {
+    let deficit = if env.rollover {
+        0.0
+    } else {
+        (-env.balance).max(0.0)
+    };
+    match (policy, debt_envelope_id) {
+        (DeficitPolicy::Forgive, _) => (0.0, 0.0),
+        (DeficitPolicy::Debt, Some(debt_id)) if debt_id != env.id => (0.0, deficit),
+        (DeficitPolicy::CarryOver | DeficitPolicy::Debt, _) => (deficit, 0.0),
+    }
+}
+
+/// Interest a rollover envelope earns on the balance it closed the month with, rounded
+/// to the cent. Envelopes without a rate, that reset, or that closed at or below zero
+/// earn nothing.
+#[must_use]
+pub fn monthly_interest(env: &envelope::Model) -> f64 {
+    match env.interest_rate {
+        Some(rate) if env.rollover && env.balance > 0.0 => (env.balance * rate).round() / 100.0,
+        _ => 0.0,
+    }
+}
+
 /// Formats a monthly update result into a human-readable summary string.
 /// This is useful for logging or displaying the results of a monthly update.
 ///
@@ -241,7 +653,10 @@ pub async fn process_monthly_updates(
 ///
 /// # Errors
 /// Returns an error if formatting fails (this should never happen in practice)
-pub fn format_monthly_update_summary(result: &MonthlyUpdateResult) -> Result<String> {
+pub fn format_monthly_update_summary(
+    result: &MonthlyUpdateResult,
+    currency: Currency,
+) -> Result<String> {
     let mut summary = format!(
         "Monthly Update - {} - Processed {} envelopes\n",
         result.update_date.format("%B %Y"),
@@ -254,6 +669,8 @@ pub fn format_monthly_update_summary(result: &MonthlyUpdateResult) -> Result<Str
         result.rollover_count, result.reset_count
     )?;
 
+    write_totals(&mut summary, result, currency)?;
+
     for envelope_result in &result.updated_envelopes {
         let change_type = if envelope_result.rollover {
             "Rollover"
@@ -263,23 +680,135 @@ pub fn format_monthly_update_summary(result: &MonthlyUpdateResult) -> Result<Str
 
         writeln!(
             summary,
-            "  {} - {} | ${:.2} → ${:.2} (Allocation: ${:.2})",
+            "  {} - {} | {} → {} (Allocation: {})",
             envelope_result.envelope_name,
             change_type,
-            envelope_result.old_balance,
-            envelope_result.new_balance,
-            envelope_result.allocation
+            currency.format(envelope_result.old_balance),
+            currency.format(envelope_result.new_balance),
+            currency.format(envelope_result.allocation)
         )?;
+
+        if envelope_result.contribution > 0.0 {
+            write!(
+                summary,
+                "      + {} contribution",
+                currency.format(envelope_result.contribution)
+            )?;
+            if let Some(goal) = envelope_result.goal {
+                let progress =
+                    crate::core::report::calculate_progress(envelope_result.new_balance, goal);
+                write!(
+                    summary,
+                    " | Goal: {} ({progress:.1}%)",
+                    currency.format(goal)
+                )?;
+            }
+            writeln!(summary)?;
+        }
+
+        if envelope_result.interest > 0.0 {
+            writeln!(
+                summary,
+                "      + {} interest",
+                currency.format(envelope_result.interest)
+            )?;
+        }
+
+        if envelope_result.carried_deficit > 0.0 {
+            writeln!(
+                summary,
+                "      - {} deficit carried over",
+                currency.format(envelope_result.carried_deficit)
+            )?;
+        }
+
+        if envelope_result.debt_transfer > 0.0 {
+            writeln!(
+                summary,
+                "      - {} deficit moved to '{}'",
+                currency.format(envelope_result.debt_transfer),
+                result.debt_envelope.as_deref().unwrap_or_default()
+            )?;
+        }
     }
 
     Ok(summary)
 }
 
+/// Writes the household-wide totals of a monthly update, skipping those that are zero.
+fn write_totals(
+    summary: &mut String,
+    result: &MonthlyUpdateResult,
+    currency: Currency,
+) -> Result<()> {
+    if result.total_contributions > 0.0 {
+        writeln!(
+            summary,
+            "  Sinking fund contributions: {}\n",
+            currency.format(result.total_contributions)
+        )?;
+    }
+
+    if result.total_interest > 0.0 {
+        writeln!(
+            summary,
+            "  Interest earned: {}\n",
+            currency.format(result.total_interest)
+        )?;
+    }
+
+    if result.total_carried_deficit > 0.0 {
+        writeln!(
+            summary,
+            "  Deficits carried into this month: {}\n",
+            currency.format(result.total_carried_deficit)
+        )?;
+    }
+
+    if let Some(debt_envelope) = &result.debt_envelope {
+        writeln!(
+            summary,
+            "  Deficits moved to '{debt_envelope}': {}\n",
+            currency.format(result.total_debt_transfer)
+        )?;
+    }
+
+    for held in &result.held_funding {
+        writeln!(
+            summary,
+            "  {} of '{}' funding went to '{}' until it is full\n",
+            currency.format(held.amount),
+            held.envelope_name,
+            held.requires_name
+        )?;
+    }
+
+    if result.total_round_ups > 0.0 {
+        writeln!(
+            summary,
+            "  Round-ups saved in {}: {}\n",
+            result.closed_month,
+            currency.format(result.total_round_ups)
+        )?;
+    }
+
+    if result.pruned_transactions > 0 {
+        writeln!(
+            summary,
+            "  Pruned {} transaction(s) past the retention period\n",
+            result.pruned_transactions
+        )?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used)]
     #![allow(clippy::float_cmp)]
     use super::*;
+    use crate::core::envelope::update_envelope_balance_atomic;
+    use crate::entities::{SystemState, system_state};
     use crate::test_utils::*;
 
     #[tokio::test]
@@ -293,6 +822,23 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_is_monthly_update_needed_follows_cycle_start() -> Result<()> {
+        let db = setup_test_db().await?;
+        calendar::set_cycle_start_day(&db, 25).await?;
+        let cycle = calendar::current_cycle(&db, Utc::now().date_naive()).await?;
+
+        // Updated on the last day of the previous cycle
+        set_last_monthly_update_date(&db, cycle.start.pred_opt().unwrap()).await?;
+        assert!(is_monthly_update_needed(&db).await?);
+
+        // Updated on payday
+        set_last_monthly_update_date(&db, cycle.start).await?;
+        assert!(!is_monthly_update_needed(&db).await?);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_get_last_monthly_update_date_none() -> Result<()> {
         let db = setup_test_db().await?;
@@ -385,6 +931,49 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_process_monthly_updates_pays_interest() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        let savings =
+            create_custom_envelope(&db, "Savings", None, "savings", 100.0, false, true).await?;
+        crate::core::envelope::update_envelope_balance_atomic(&db, savings.id, 200.0).await?;
+        crate::core::envelope::set_interest_rate(&db, savings.id, Some(1.5)).await?;
+
+        let result = process_monthly_updates(&db).await?.unwrap();
+        assert_eq!(result.total_interest, 3.0);
+        let env_result = &result.updated_envelopes[0];
+        assert_eq!(env_result.interest, 3.0);
+        assert_eq!(env_result.new_balance, 303.0); // 200.0 + 100.0 allocation + 3.0 interest
+
+        let updated = Envelope::find_by_id(savings.id).one(&db).await?.unwrap();
+        assert_eq!(updated.balance, 303.0);
+
+        let interest = crate::entities::Transaction::find()
+            .filter(
+                crate::entities::transaction::Column::TransactionType.eq(TransactionType::Interest),
+            )
+            .all(&db)
+            .await?;
+        assert_eq!(interest.len(), 1);
+        assert_eq!(interest[0].amount, 3.0);
+        assert_eq!(interest[0].user_id, SYSTEM_USER_ID);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_interest_rate_requires_rollover() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries =
+            create_custom_envelope(&db, "Groceries", None, "food", 300.0, false, false).await?;
+
+        let result = crate::core::envelope::set_interest_rate(&db, groceries.id, Some(1.0)).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_process_monthly_updates_reset_envelope() -> Result<()> {
         let db = setup_test_db().await?;
@@ -428,6 +1017,77 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_process_monthly_updates_deficit_policies() -> Result<()> {
+        let db = setup_test_db().await?;
+        let food =
+            create_custom_envelope(&db, "Food", None, "necessary", 200.0, false, false).await?;
+        update_envelope_balance_atomic(&db, food.id, -30.0).await?;
+
+        // Carried over: the deficit comes out of the new month's allocation
+        set_deficit_policy(&db, DeficitPolicy::CarryOver).await?;
+        let result = process_monthly_updates(&db).await?.unwrap();
+        assert_eq!(result.total_carried_deficit, 30.0);
+        assert_eq!(result.updated_envelopes[0].new_balance, 170.0);
+        assert!(
+            format_monthly_update_summary(&result, Currency::default())?
+                .contains("$30.00 deficit carried over")
+        );
+
+        // Moved to the debt envelope, which still gets its own update first
+        let debt = create_custom_envelope(&db, "Debt", None, "necessary", 0.0, false, true).await?;
+        set_debt_envelope(&db, debt.id).await?;
+        set_deficit_policy(&db, DeficitPolicy::Debt).await?;
+        update_envelope_balance_atomic(&db, food.id, -220.0).await?;
+        // Forget this month's update so it runs again
+        state::delete_value(&db, LAST_MONTHLY_UPDATE_KEY).await?;
+        MonthlyUpdateLog::delete_many().exec(&db).await?;
+        let result = process_monthly_updates(&db).await?.unwrap();
+        assert_eq!(result.total_carried_deficit, 0.0);
+        assert_eq!(result.total_debt_transfer, 50.0);
+        assert_eq!(result.debt_envelope.as_deref(), Some("Debt"));
+        let food = Envelope::find_by_id(food.id).one(&db).await?.unwrap();
+        let debt = Envelope::find_by_id(debt.id).one(&db).await?.unwrap();
+        assert_eq!(food.balance, 200.0);
+        assert_eq!(debt.balance, -50.0);
+        assert!(
+            format_monthly_update_summary(&result, Currency::default())?
+                .contains("moved to 'Debt'")
+        );
+
+        assert_eq!(
+            DeficitPolicy::from_key("CarryOver"),
+            Some(DeficitPolicy::CarryOver)
+        );
+        assert_eq!(get_deficit_policy(&db).await?, DeficitPolicy::Debt);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transactions_since_the_month_closed_carry_over() -> Result<()> {
+        let db = setup_test_db().await?;
+        let food =
+            create_custom_envelope(&db, "Food", None, "necessary", 200.0, false, false).await?;
+        let fun = create_custom_envelope(&db, "Fun", None, "fun", 50.0, false, false).await?;
+        // Food closed the month with $20 left and Fun $30 short, then both were used
+        // before the update ran
+        update_envelope_balance_atomic(&db, food.id, 20.0).await?;
+        update_envelope_balance_atomic(&db, fun.id, -30.0).await?;
+        create_test_transaction(&db, food.id, -15.0).await?;
+        create_test_transaction(&db, fun.id, 50.0).await?;
+        set_deficit_policy(&db, DeficitPolicy::CarryOver).await?;
+
+        // Fun's deficit is carried even though its balance is positive now, and the new
+        // transactions aren't wiped by the reset
+        let result = process_monthly_updates(&db).await?.unwrap();
+        assert_eq!(result.total_carried_deficit, 30.0);
+        let food = Envelope::find_by_id(food.id).one(&db).await?.unwrap();
+        assert_eq!(food.balance, 185.0);
+        let fun = Envelope::find_by_id(fun.id).one(&db).await?.unwrap();
+        assert_eq!(fun.balance, 70.0);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_process_monthly_updates_multiple_envelopes() -> Result<()> {
         let db = setup_test_db().await?;
@@ -510,6 +1170,44 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_monthly_update_log_blocks_repeat_after_state_edit() -> Result<()> {
+        let db = setup_test_db().await?;
+        let env = create_test_envelope(&db, "Test").await?;
+        assert!(process_monthly_updates(&db).await?.is_some());
+        let balance = || crate::core::envelope::get_envelope_by_id(&db, env.id);
+        let before = balance().await?.unwrap().balance;
+
+        // Rewinding the stored date makes an update look due, but the log refuses it
+        let today = calendar::today(&db).await?;
+        let cycle = calendar::current_cycle(&db, today).await?;
+        set_last_monthly_update_date(&db, cycle.previous().start).await?;
+        assert!(is_monthly_update_needed(&db).await?);
+        assert!(process_monthly_updates(&db).await?.is_none());
+        assert_eq!(balance().await?.unwrap().balance, before);
+        assert_eq!(MonthlyUpdateLog::find().count(&db).await?, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_monthly_update_skipped_when_clock_is_behind() -> Result<()> {
+        let db = setup_test_db().await?;
+        create_test_envelope(&db, "Test").await?;
+
+        // A later month was already updated, e.g. before the host clock jumped back
+        let today = calendar::today(&db).await?;
+        let next = calendar::current_cycle(&db, today).await?.next();
+        let txn = db.begin().await?;
+        assert!(claim_month(&txn, &next, "unused").await?);
+        txn.commit().await?;
+
+        assert!(process_monthly_updates(&db).await?.is_none());
+        assert_eq!(get_last_monthly_update_date(&db).await?, None);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_process_monthly_updates_records_date() -> Result<()> {
         let db = setup_test_db().await?;
@@ -538,7 +1236,16 @@ mod tests {
             total_envelopes_processed: 3,
             rollover_count: 2,
             reset_count: 1,
+            total_contributions: 0.0,
+            total_interest: 0.0,
+            total_carried_deficit: 0.0,
+            total_debt_transfer: 0.0,
+            debt_envelope: None,
+            held_funding: Vec::new(),
+            total_round_ups: 12.5,
+            pruned_transactions: 0,
             update_date: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            closed_month: "2024-02".to_string(),
             updated_envelopes: vec![
                 EnvelopeUpdateResult {
                     envelope_name: "Savings".to_string(),
@@ -546,6 +1253,11 @@ mod tests {
                     new_balance: 200.0,
                     allocation: 100.0,
                     rollover: true,
+                    contribution: 0.0,
+                    goal: None,
+                    interest: 0.0,
+                    carried_deficit: 0.0,
+                    debt_transfer: 0.0,
                 },
                 EnvelopeUpdateResult {
                     envelope_name: "Food".to_string(),
@@ -553,11 +1265,16 @@ mod tests {
                     new_balance: 150.0,
                     allocation: 150.0,
                     rollover: false,
+                    contribution: 0.0,
+                    goal: None,
+                    interest: 0.0,
+                    carried_deficit: 0.0,
+                    debt_transfer: 0.0,
                 },
             ],
         };
 
-        let summary = format_monthly_update_summary(&result)?;
+        let summary = format_monthly_update_summary(&result, Currency::default())?;
 
         // Verify summary contains key information
         assert!(summary.contains("March 2024"));
@@ -568,6 +1285,69 @@ mod tests {
         assert!(summary.contains("Food"));
         assert!(summary.contains("$100.00 → $200.00"));
         assert!(summary.contains("$50.00 → $150.00"));
+        assert!(summary.contains("Round-ups saved in 2024-02: $12.50"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_monthly_updates_applies_contribution() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        // Sinking fund: $50/month toward a $600 goal, no regular allocation
+        let envelope =
+            create_custom_envelope(&db, "Car Tires", None, "savings", 0.0, false, true).await?;
+        crate::core::envelope::set_sinking_fund(&db, envelope.id, 50.0, Some(600.0)).await?;
+        crate::core::envelope::update_envelope_balance_atomic(&db, envelope.id, 100.0).await?;
+
+        let update_result = process_monthly_updates(&db).await?.unwrap();
+        assert_eq!(update_result.total_contributions, 50.0);
+
+        let env_result = &update_result.updated_envelopes[0];
+        assert_eq!(env_result.old_balance, 100.0);
+        assert_eq!(env_result.new_balance, 150.0);
+        assert_eq!(env_result.contribution, 50.0);
+        assert_eq!(env_result.goal, Some(600.0));
+
+        let updated_envelope = Envelope::find_by_id(envelope.id).one(&db).await?.unwrap();
+        assert_eq!(updated_envelope.balance, 150.0);
+
+        let summary = format_monthly_update_summary(&update_result, Currency::default())?;
+        assert!(summary.contains("Sinking fund contributions: $50.00"));
+        assert!(summary.contains("Goal: $600.00 (25.0%)"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_monthly_updates_follows_funding_rules() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        // Emergency ($400 of a $500 goal) is filled before Vacation gets anything
+        let emergency =
+            create_custom_envelope(&db, "Emergency", None, "savings", 50.0, false, true).await?;
+        crate::core::envelope::set_sinking_fund(&db, emergency.id, 0.0, Some(500.0)).await?;
+        crate::core::envelope::update_envelope_balance_atomic(&db, emergency.id, 400.0).await?;
+        let vacation =
+            create_custom_envelope(&db, "Vacation", None, "savings", 200.0, false, true).await?;
+        allocation::set_funding_rule(&db, vacation.id, Some(emergency.id)).await?;
+
+        let update_result = process_monthly_updates(&db).await?.unwrap();
+        let emergency_after = Envelope::find_by_id(emergency.id).one(&db).await?.unwrap();
+        let vacation_after = Envelope::find_by_id(vacation.id).one(&db).await?.unwrap();
+        assert_eq!(emergency_after.balance, 500.0);
+        assert_eq!(vacation_after.balance, 150.0);
+        assert_eq!(update_result.held_funding.len(), 1);
+        assert_eq!(update_result.held_funding[0].amount, 50.0);
+        let new_balances: Vec<f64> = update_result
+            .updated_envelopes
+            .iter()
+            .map(|result| result.new_balance)
+            .collect();
+        assert!(new_balances.contains(&500.0) && new_balances.contains(&150.0));
+
+        let summary = format_monthly_update_summary(&update_result, Currency::default())?;
+        assert!(summary.contains("$50.00 of 'Vacation' funding went to 'Emergency'"));
 
         Ok(())
     }
@@ -0,0 +1,407 @@
+//! Notifications - Alerts and summaries delivered outside a command's reply.
+//!
+//! A [`Notification`] is platform-neutral text for the whole household or for one
+//! member. Each way of delivering it implements [`Notifier`]: Discord (see
+//! `bot::notifications::DiscordNotifier`) is the default, and email (`email` feature) and
+//! Matrix (`matrix` feature) can be selected with `NOTIFIERS` (see
+//! [`crate::config::notify`]). [`start`] builds the selected optional backends once, and
+//! [`send_external`] hands a notification to each of them in the background, so a slow
+//! mail server never delays a command. Delivery failures are logged, not returned.
+//!
+//! Personal notifications stay personal: email sends them only to the member's own
+//! address, and Matrix, whose room is shared, skips them.
+
+use crate::{
+    config::notify::{EmailConfig, MatrixConfig, NotifyConfig},
+    errors::Result,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, OnceLock},
+};
+
+/// Who a notification is for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Audience {
+    /// Everyone in the household (the announcement channel on Discord)
+    Household,
+    /// One member (a direct message on Discord)
+    Member {
+        /// Discord user ID of the member
+        user_id: String,
+    },
+}
+
+/// An alert or summary to deliver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// One-line summary, used as the email subject
+    pub subject: String,
+    /// Full text
+    pub body: String,
+    /// Who it is for
+    pub audience: Audience,
+    /// Role or member mention pinged on Discord, if any; other backends ignore it
+    pub mention: Option<String>,
+}
+
+impl Notification {
+    /// A notification for the whole household.
+    #[must_use]
+    pub fn household(subject: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            body: body.into(),
+            audience: Audience::Household,
+            mention: None,
+        }
+    }
+}
+
+/// Future returned by [`Notifier::send`].
+pub type NotifyFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// A way of delivering notifications.
+pub trait Notifier: Send + Sync {
+    /// Short name of the backend, for logs.
+    fn name(&self) -> &'static str;
+
+    /// Delivers a notification. Backends that can't reach its audience skip it.
+    fn send<'a>(&'a self, notification: &'a Notification) -> NotifyFuture<'a>;
+}
+
+/// The selected backends, set once by [`start`].
+struct Backends {
+    discord: bool,
+    external: Vec<Arc<dyn Notifier>>,
+}
+
+static BACKENDS: OnceLock<Backends> = OnceLock::new();
+
+/// Builds the notification backends selected by `config`. Later calls are ignored.
+///
+/// # Errors
+/// Returns `Error::Config` if a selected backend wasn't compiled in or its settings are
+/// invalid (such as a malformed email address).
+pub fn start(config: &NotifyConfig) -> Result<()> {
+    if BACKENDS.get().is_some() {
+        return Ok(());
+    }
+    let mut external = Vec::new();
+    if let Some(email) = &config.email {
+        external.push(email_backend(email)?);
+    }
+    if let Some(matrix) = &config.matrix {
+        external.push(matrix_backend(matrix)?);
+    }
+    let _ = BACKENDS.set(Backends {
+        discord: config.discord,
+        external,
+    });
+    Ok(())
+}
+
+/// Whether notifications should be posted to Discord (the default until [`start`] says
+/// otherwise).
+#[must_use]
+pub fn discord_enabled() -> bool {
+    BACKENDS.get().is_none_or(|backends| backends.discord)
+}
+
+/// Sends a notification through every optional backend in the background. Does nothing
+/// if none is configured.
+pub fn send_external(notification: Notification) {
+    let Some(backends) = BACKENDS.get().filter(|b| !b.external.is_empty()) else {
+        return;
+    };
+    let notification = Arc::new(notification);
+    for backend in &backends.external {
+        let backend = Arc::clone(backend);
+        let notification = Arc::clone(&notification);
+        tokio::spawn(async move {
+            if let Err(e) = backend.send(&notification).await {
+                tracing::warn!(
+                    "Failed to send '{}' by {}: {e}",
+                    notification.subject,
+                    backend.name()
+                );
+            }
+        });
+    }
+}
+
+/// Error for a backend that was selected but not compiled in.
+#[cfg(not(all(feature = "email", feature = "matrix")))]
+fn missing_feature(backend: &str) -> crate::errors::Error {
+    crate::errors::Error::Config {
+        message: format!(
+            "The {backend} notifier needs the `{backend}` feature; rebuild with --features {backend}"
+        ),
+    }
+}
+
+#[cfg(feature = "email")]
+fn email_backend(config: &EmailConfig) -> Result<Arc<dyn Notifier>> {
+    Ok(Arc::new(email::EmailNotifier::new(config)?))
+}
+
+#[cfg(not(feature = "email"))]
+fn email_backend(_config: &EmailConfig) -> Result<Arc<dyn Notifier>> {
+    Err(missing_feature("email"))
+}
+
+#[cfg(feature = "matrix")]
+fn matrix_backend(config: &MatrixConfig) -> Result<Arc<dyn Notifier>> {
+    Ok(Arc::new(matrix::MatrixNotifier::new(config)?))
+}
+
+#[cfg(not(feature = "matrix"))]
+fn matrix_backend(_config: &MatrixConfig) -> Result<Arc<dyn Notifier>> {
+    Err(missing_feature("matrix"))
+}
+
+/// Email over SMTP with STARTTLS.
+#[cfg(feature = "email")]
+mod email {
+    use super::{Audience, Notification, Notifier, NotifyFuture};
+    use crate::{
+        config::notify::EmailConfig,
+        errors::{Error, Result},
+    };
+    use lettre::{
+        AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::Mailbox,
+        transport::smtp::authentication::Credentials,
+    };
+    use std::collections::HashMap;
+
+    /// Sends household notifications to the configured addresses and personal ones to
+    /// the member's own address.
+    pub struct EmailNotifier {
+        transport: AsyncSmtpTransport<Tokio1Executor>,
+        from: Mailbox,
+        household: Vec<Mailbox>,
+        members: HashMap<String, Mailbox>,
+    }
+
+    impl EmailNotifier {
+        /// Prepares the SMTP transport; nothing is sent until the first notification.
+        pub fn new(config: &EmailConfig) -> Result<Self> {
+            let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                .map_err(|e| Error::Config {
+                    message: format!("Invalid SMTP_HOST '{}': {e}", config.host),
+                })?
+                .port(config.port);
+            if let Some((user, password)) = &config.credentials {
+                builder = builder.credentials(Credentials::new(user.clone(), password.clone()));
+            }
+
+            Ok(Self {
+                transport: builder.build(),
+                from: mailbox(&config.from)?,
+                household: config
+                    .to
+                    .iter()
+                    .map(|address| mailbox(address))
+                    .collect::<Result<_>>()?,
+                members: config
+                    .members
+                    .iter()
+                    .map(|(user_id, address)| Ok((user_id.clone(), mailbox(address)?)))
+                    .collect::<Result<_>>()?,
+            })
+        }
+    }
+
+    /// Parses an address from the configuration.
+    fn mailbox(address: &str) -> Result<Mailbox> {
+        address.parse().map_err(|e| Error::Config {
+            message: format!("Invalid email address '{address}': {e}"),
+        })
+    }
+
+    impl Notifier for EmailNotifier {
+        fn name(&self) -> &'static str {
+            "email"
+        }
+
+        fn send<'a>(&'a self, notification: &'a Notification) -> NotifyFuture<'a> {
+            Box::pin(async move {
+                let recipients: Vec<Mailbox> = match &notification.audience {
+                    Audience::Household => self.household.clone(),
+                    Audience::Member { user_id } => {
+                        self.members.get(user_id).cloned().into_iter().collect()
+                    }
+                };
+                if recipients.is_empty() {
+                    return Ok(());
+                }
+
+                let mut builder = Message::builder()
+                    .from(self.from.clone())
+                    .subject(format!("EnvelopeBuddy: {}", notification.subject));
+                for recipient in recipients {
+                    builder = builder.to(recipient);
+                }
+                let message =
+                    builder
+                        .body(notification.body.clone())
+                        .map_err(|e| Error::Notification {
+                            message: e.to_string(),
+                        })?;
+                self.transport
+                    .send(message)
+                    .await
+                    .map_err(|e| Error::Notification {
+                        message: e.to_string(),
+                    })?;
+                Ok(())
+            })
+        }
+    }
+}
+
+/// Matrix room messages through the client-server API.
+#[cfg(feature = "matrix")]
+mod matrix {
+    use super::{Audience, Notification, Notifier, NotifyFuture};
+    use crate::{
+        config::notify::MatrixConfig,
+        errors::{Error, Result},
+    };
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Posts household notifications to one room as the configured account.
+    pub struct MatrixNotifier {
+        client: reqwest::Client,
+        homeserver: reqwest::Url,
+        config: MatrixConfig,
+        /// Makes each request's transaction ID unique, so retries aren't posted twice
+        next_txn: AtomicU64,
+    }
+
+    impl MatrixNotifier {
+        /// Prepares the HTTP client; nothing is sent until the first notification.
+        pub fn new(config: &MatrixConfig) -> Result<Self> {
+            let homeserver =
+                reqwest::Url::parse(&config.homeserver).map_err(|e| Error::Config {
+                    message: format!("Invalid MATRIX_HOMESERVER '{}': {e}", config.homeserver),
+                })?;
+            Ok(Self {
+                client: reqwest::Client::new(),
+                homeserver,
+                config: config.clone(),
+                next_txn: AtomicU64::new(0),
+            })
+        }
+
+        /// URL of the `PUT /rooms/{room}/send/m.room.message/{txn}` endpoint.
+        pub(super) fn send_url(&self, txn_id: &str) -> Result<reqwest::Url> {
+            let mut url = self.homeserver.clone();
+            url.path_segments_mut()
+                .map_err(|()| Error::Config {
+                    message: "MATRIX_HOMESERVER can't be used as a base URL".to_string(),
+                })?
+                .pop_if_empty()
+                .extend([
+                    "_matrix",
+                    "client",
+                    "v3",
+                    "rooms",
+                    &self.config.room_id,
+                    "send",
+                    "m.room.message",
+                    txn_id,
+                ]);
+            Ok(url)
+        }
+    }
+
+    impl Notifier for MatrixNotifier {
+        fn name(&self) -> &'static str {
+            "matrix"
+        }
+
+        fn send<'a>(&'a self, notification: &'a Notification) -> NotifyFuture<'a> {
+            Box::pin(async move {
+                if notification.audience != Audience::Household {
+                    return Ok(());
+                }
+                let txn_id = format!(
+                    "envelope-buddy-{}-{}",
+                    chrono::Utc::now().timestamp_millis(),
+                    self.next_txn.fetch_add(1, Ordering::Relaxed)
+                );
+                let body = serde_json::json!({
+                    "msgtype": "m.text",
+                    "body": format!("{}\n\n{}", notification.subject, notification.body),
+                });
+                self.client
+                    .put(self.send_url(&txn_id)?)
+                    .bearer_auth(&self.config.access_token)
+                    .json(&body)
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                    .map_err(|e| Error::Notification {
+                        message: e.to_string(),
+                    })?;
+                Ok(())
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_notification_defaults_before_start() {
+        let notification = Notification::household("Monthly update", "All done");
+        assert_eq!(notification.audience, Audience::Household);
+        assert_eq!(notification.mention, None);
+
+        // Tests never call `start` with backends, so Discord stays on and nothing is sent
+        assert!(discord_enabled());
+        send_external(notification);
+    }
+
+    #[cfg(not(feature = "email"))]
+    #[test]
+    fn test_start_rejects_backend_without_feature() {
+        let config = NotifyConfig {
+            email: Some(EmailConfig {
+                host: "smtp.example.com".to_string(),
+                port: 587,
+                credentials: None,
+                from: "bot@example.com".to_string(),
+                to: vec!["alex@example.com".to_string()],
+                members: std::collections::HashMap::new(),
+            }),
+            ..NotifyConfig::default()
+        };
+        assert!(matches!(
+            start(&config),
+            Err(crate::errors::Error::Config { .. })
+        ));
+        assert!(BACKENDS.get().is_none());
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_matrix_send_url() {
+        let notifier = matrix::MatrixNotifier::new(&MatrixConfig {
+            homeserver: "https://matrix.example.org".to_string(),
+            access_token: "token".to_string(),
+            room_id: "!room:example.org".to_string(),
+        })
+        .unwrap();
+        let url = notifier.send_url("txn-1").unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://matrix.example.org/_matrix/client/v3/rooms/!room:example.org/send/m.room.message/txn-1"
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! PDF statement business logic - Printable monthly statements.
+//!
+//! [`build_statement`] gathers a closed budget month's statements (see
+//! [`crate::core::statement`]) and its largest spends as the viewer may see them, and
+//! [`render_statement`] lays them out as an A4 document: a household summary, a table of
+//! envelopes, and the top transactions. Only compiled with the `pdf` feature. Text uses
+//! the built-in Helvetica fonts, so characters outside Windows-1252 (such as emoji) are
+//! left out.
+
+use crate::{
+    core::{
+        calendar,
+        report::{self, ReportRange},
+        setup::{self, Currency},
+        statement::{self, StatementTotals, StatementView},
+    },
+    errors::{Error, Result},
+};
+use chrono::NaiveDate;
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+    Point,
+};
+use sea_orm::DatabaseConnection;
+use std::{collections::HashMap, fmt::Display};
+
+/// A4 page width in millimetres.
+const PAGE_WIDTH: f32 = 210.0;
+/// A4 page height in millimetres.
+const PAGE_HEIGHT: f32 = 297.0;
+/// Blank border around each page in millimetres.
+const MARGIN: f32 = 20.0;
+/// Height of a table row in millimetres.
+const ROW_HEIGHT: f32 = 6.0;
+/// Font size of table rows in points.
+const BODY_SIZE: f32 = 9.0;
+/// Font size of section headings in points.
+const HEADING_SIZE: f32 = 13.0;
+/// Font size of the title in points.
+const TITLE_SIZE: f32 = 18.0;
+/// Number of transactions listed under "Top transactions".
+const TOP_SPENDS: u64 = 10;
+/// Longest envelope name or description printed before it is cut short.
+const MAX_TEXT_CHARS: usize = 34;
+
+/// One of the month's largest spends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopSpend {
+    /// Name of the envelope it was spent from
+    pub envelope_name: String,
+    /// Day the spend was recorded, in the household timezone
+    pub date: NaiveDate,
+    /// Amount spent (positive)
+    pub amount: f64,
+    /// Transaction description
+    pub description: String,
+}
+
+/// Everything printed on a month's PDF statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementDocument {
+    /// Budget month as `YYYY-MM`
+    pub month: String,
+    /// Household totals across the visible statements
+    pub totals: StatementTotals,
+    /// Per-envelope statements, by envelope name
+    pub statements: Vec<StatementView>,
+    /// The month's largest spends from visible envelopes, biggest first
+    pub top_spends: Vec<TopSpend>,
+    /// How the household currency is written
+    pub currency: Currency,
+}
+
+/// File name used when attaching a month's PDF statement to a Discord message.
+#[must_use]
+pub fn statement_filename(month: &str) -> String {
+    format!("statement-{month}.pdf")
+}
+
+/// Gathers a month's statements and largest spends as seen by `viewer_id`.
+///
+/// Private envelopes of other members appear by name only and their spends are left
+/// out. Top transactions come from the raw history, so they are missing for months the
+/// retention policy has pruned.
+///
+/// # Returns
+/// `None` if the month has no statements
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn build_statement(
+    db: &DatabaseConnection,
+    month: &str,
+    viewer_id: &str,
+) -> Result<Option<StatementDocument>> {
+    let statements = statement::get_statements(db, month, viewer_id).await?;
+    if statements.is_empty() {
+        return Ok(None);
+    }
+    let totals = StatementTotals::from_views(&statements);
+
+    let mut top_spends = Vec::new();
+    if let Some((from, to)) = totals.period {
+        let names: HashMap<i64, &str> = statements
+            .iter()
+            .filter_map(|view| match view {
+                StatementView::Visible(s) => Some((s.envelope_id, s.envelope_name.as_str())),
+                StatementView::Hidden { .. } => None,
+            })
+            .collect();
+        let envelope_ids: Vec<i64> = names.keys().copied().collect();
+        let range = ReportRange::new(from, to)?;
+        let tz = calendar::get_timezone(db).await?;
+        for tx in report::get_largest_spends(db, &range, &envelope_ids, TOP_SPENDS).await? {
+            top_spends.push(TopSpend {
+                envelope_name: names
+                    .get(&tx.envelope_id)
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+                date: tx.timestamp.with_timezone(&tz).date_naive(),
+                amount: -tx.amount,
+                description: tx.description,
+            });
+        }
+    }
+
+    Ok(Some(StatementDocument {
+        month: month.to_string(),
+        totals,
+        statements,
+        top_spends,
+        currency: setup::get_currency_format(db).await?,
+    }))
+}
+
+/// Renders a statement as PDF bytes.
+///
+/// # Errors
+/// Returns an error if the fonts can't be added or the document can't be written.
+pub fn render_statement(document: &StatementDocument) -> Result<Vec<u8>> {
+    let mut writer = PageWriter::new(&format!("Statement for {}", document.month))?;
+
+    writer.text(
+        &format!("Envelope Buddy statement for {}", document.month),
+        TITLE_SIZE,
+        true,
+    );
+    let totals = &document.totals;
+    let currency = document.currency;
+    if let Some((start, end)) = totals.period {
+        writer.text(&format!("{start} to {end}"), BODY_SIZE, false);
+    }
+    writer.gap(ROW_HEIGHT);
+
+    writer.text("Household summary", HEADING_SIZE, true);
+    writer.row(
+        &[
+            (
+                0.0,
+                format!("Opened: {}", format_balance(totals.open_balance, currency)),
+            ),
+            (
+                45.0,
+                format!("Closed: {}", format_balance(totals.close_balance, currency)),
+            ),
+            (
+                90.0,
+                format!("Spent: {}", currency.format(totals.total_spent)),
+            ),
+            (135.0, format!("Transactions: {}", totals.transaction_count)),
+        ],
+        false,
+    );
+    writer.gap(ROW_HEIGHT);
+
+    writer.text("Envelopes", HEADING_SIZE, true);
+    writer.row(
+        &[
+            (0.0, "Envelope".to_string()),
+            (70.0, "Opened".to_string()),
+            (95.0, "Closed".to_string()),
+            (120.0, "Spent".to_string()),
+            (145.0, "Transactions".to_string()),
+        ],
+        true,
+    );
+    writer.rule();
+    for view in &document.statements {
+        match view {
+            StatementView::Visible(s) => writer.row(
+                &[
+                    (0.0, truncate(&s.envelope_name)),
+                    (70.0, format_balance(s.open_balance, currency)),
+                    (95.0, format_balance(s.close_balance, currency)),
+                    (120.0, currency.format(s.total_spent)),
+                    (145.0, s.transaction_count.to_string()),
+                ],
+                false,
+            ),
+            StatementView::Hidden { envelope_name } => writer.row(
+                &[
+                    (0.0, truncate(envelope_name)),
+                    (70.0, report::HIDDEN_LABEL.to_string()),
+                ],
+                false,
+            ),
+        }
+    }
+    writer.gap(ROW_HEIGHT);
+
+    writer.text("Top transactions", HEADING_SIZE, true);
+    if document.top_spends.is_empty() {
+        writer.text(
+            "No spending on record (transactions may have been pruned).",
+            BODY_SIZE,
+            false,
+        );
+    } else {
+        writer.row(
+            &[
+                (0.0, "Date".to_string()),
+                (25.0, "Envelope".to_string()),
+                (70.0, "Amount".to_string()),
+                (95.0, "Description".to_string()),
+            ],
+            true,
+        );
+        writer.rule();
+        for spend in &document.top_spends {
+            writer.row(
+                &[
+                    (0.0, spend.date.to_string()),
+                    (25.0, truncate(&spend.envelope_name)),
+                    (70.0, currency.format(spend.amount)),
+                    (95.0, truncate(&spend.description)),
+                ],
+                false,
+            );
+        }
+    }
+
+    writer.finish()
+}
+
+/// Formats a statement balance, which is missing for months aggregated from history.
+fn format_balance(balance: Option<f64>, currency: Currency) -> String {
+    balance.map_or_else(|| "n/a".to_string(), |balance| currency.format(balance))
+}
+
+/// Cuts text longer than [`MAX_TEXT_CHARS`] short so it stays in its column.
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_TEXT_CHARS {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(MAX_TEXT_CHARS - 3).collect();
+    format!("{kept}...")
+}
+
+/// Writes lines top to bottom, starting a new page when the current one is full.
+struct PageWriter {
+    document: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    /// Baseline of the next line, in millimetres from the bottom of the page
+    y: f32,
+}
+
+impl PageWriter {
+    /// Creates a document with its first page.
+    fn new(title: &str) -> Result<Self> {
+        let (document, page, layer) =
+            PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Statement");
+        let regular = document
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(pdf_error)?;
+        let bold = document
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(pdf_error)?;
+        let layer = document.get_page(page).get_layer(layer);
+        Ok(Self {
+            document,
+            layer,
+            regular,
+            bold,
+            y: PAGE_HEIGHT - MARGIN,
+        })
+    }
+
+    /// Starts a new page if less than `height` is left on this one.
+    fn ensure_space(&mut self, height: f32) {
+        if self.y - height >= MARGIN {
+            return;
+        }
+        let (page, layer) = self
+            .document
+            .add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Statement");
+        self.layer = self.document.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Writes one line of text at the left margin.
+    fn text(&mut self, text: &str, size: f32, bold: bool) {
+        self.row_sized(&[(0.0, text.to_string())], size, bold);
+    }
+
+    /// Writes a table row, each cell starting `offset` millimetres from the left margin.
+    fn row(&mut self, cells: &[(f32, String)], bold: bool) {
+        self.row_sized(cells, BODY_SIZE, bold);
+    }
+
+    /// Writes a row of cells in a font size, leaving room for taller text.
+    fn row_sized(&mut self, cells: &[(f32, String)], size: f32, bold: bool) {
+        let height = ROW_HEIGHT.max(size * 0.5);
+        self.ensure_space(height);
+        let font = if bold { &self.bold } else { &self.regular };
+        for (offset, text) in cells {
+            self.layer
+                .use_text(text, size, Mm(MARGIN + offset), Mm(self.y), font);
+        }
+        self.y -= height;
+    }
+
+    /// Draws a thin line across the page under the previous row.
+    fn rule(&mut self) {
+        let y = self.y + ROW_HEIGHT - 1.5;
+        self.layer.set_outline_thickness(0.5);
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(MARGIN), Mm(y)), false),
+                (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(y)), false),
+            ],
+            is_closed: false,
+        });
+        self.y -= 1.5;
+    }
+
+    /// Leaves `height` millimetres of blank space.
+    fn gap(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Writes the finished document.
+    fn finish(self) -> Result<Vec<u8>> {
+        self.document.save_to_bytes().map_err(pdf_error)
+    }
+}
+
+fn pdf_error(err: impl Display) -> Error {
+    Error::Pdf {
+        message: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{
+        core::{calendar::BudgetCycle, envelope as envelope_core},
+        entities::transaction,
+        test_utils::*,
+    };
+    use chrono::Utc;
+    use sea_orm::{ActiveModelTrait, Set};
+
+    #[tokio::test]
+    async fn test_build_and_render_statement() -> Result<()> {
+        let db = setup_test_db().await?;
+        let env =
+            create_custom_envelope(&db, "groceries", None, "food", 300.0, false, false).await?;
+        let january = BudgetCycle::containing(NaiveDate::from_ymd_opt(2025, 1, 10).unwrap(), 1);
+        for (amount, day) in [(300.0, 1), (-40.0, 5), (-120.0, 12), (-15.0, 20)] {
+            let tx = create_test_transaction(&db, env.id, amount).await?;
+            let mut active_model: transaction::ActiveModel = tx.into();
+            active_model.timestamp = Set(NaiveDate::from_ymd_opt(2025, 1, day)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+                .and_utc());
+            active_model.update(&db).await?;
+        }
+        let envelopes = envelope_core::get_all_active_envelopes(&db).await?;
+        statement::record_statements(&db, &envelopes, &january, Utc::now()).await?;
+
+        assert!(build_statement(&db, "2024-12", "someone").await?.is_none());
+        let document = build_statement(&db, "2025-01", "someone").await?.unwrap();
+        assert_eq!(document.totals.total_spent, 175.0);
+        let amounts: Vec<f64> = document.top_spends.iter().map(|s| s.amount).collect();
+        assert_eq!(amounts, vec![120.0, 40.0, 15.0]);
+        assert_eq!(document.top_spends[0].envelope_name, "groceries");
+
+        let pdf = render_statement(&document)?;
+        assert!(pdf.starts_with(b"%PDF"));
+        Ok(())
+    }
+}
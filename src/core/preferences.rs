@@ -0,0 +1,287 @@
+//! User preferences business logic - Per-member settings in the `user_preferences` table.
+//!
+//! Preferences are stored as string values by member and key, like `system_state` is for
+//! the household. [`get_value`] and [`set_value`] hold the upsert logic; typed accessors
+//! such as [`get_verbosity`] build on them. Each [`Preference`] members may change with
+//! `/preferences` has a key and a parser, so new ones only need an entry there. Reads on
+//! every command go through [`crate::core::cache::Cache::preference`] instead.
+
+use crate::{
+    core::cache::{self, CachedTable},
+    entities::{UserPreference, user_preference},
+    errors::{Error, Result},
+};
+use chrono::Utc;
+use sea_orm::{Set, prelude::*};
+use std::{collections::HashMap, fmt, str::FromStr};
+
+/// Key of the confirmation verbosity preference.
+pub const VERBOSITY_KEY: &str = "verbosity";
+
+/// How much detail a member's command confirmations show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    /// One line of text, which fits in a mobile notification
+    Compact,
+    /// An embed with the details and the envelope's balance
+    #[default]
+    Detailed,
+}
+
+impl Verbosity {
+    /// Returns the stored name of the verbosity.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Compact => "compact",
+            Self::Detailed => "detailed",
+        }
+    }
+}
+
+impl fmt::Display for Verbosity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Verbosity {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "compact" => Ok(Self::Compact),
+            "detailed" => Ok(Self::Detailed),
+            other => Err(Error::Config {
+                message: format!("Unknown verbosity '{other}'. Use compact or detailed."),
+            }),
+        }
+    }
+}
+
+/// A personal preference members may change with `/preferences`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preference {
+    /// How much detail command confirmations show
+    Verbosity,
+}
+
+impl Preference {
+    /// Every preference, in the order `/preferences view` lists them.
+    pub const ALL: [Self; 1] = [Self::Verbosity];
+
+    /// Name of the preference as typed in `/preferences set`.
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::Verbosity => VERBOSITY_KEY,
+        }
+    }
+
+    /// Looks up a preference by its [`key`](Self::key), ignoring case.
+    #[must_use]
+    pub fn from_key(key: &str) -> Option<Self> {
+        let key = key.trim();
+        Self::ALL
+            .into_iter()
+            .find(|preference| preference.key().eq_ignore_ascii_case(key))
+    }
+
+    /// Values the preference accepts, shown next to it in `/preferences view`.
+    #[must_use]
+    pub const fn expected(self) -> &'static str {
+        match self {
+            Self::Verbosity => "compact or detailed",
+        }
+    }
+
+    /// Value of a member who never set the preference.
+    const fn default_value(self) -> &'static str {
+        match self {
+            Self::Verbosity => Verbosity::Detailed.as_str(),
+        }
+    }
+
+    /// Checks a value typed by a member and returns it in its stored form.
+    fn normalize(self, value: &str) -> Result<String> {
+        match self {
+            Self::Verbosity => Ok(value.parse::<Verbosity>()?.as_str().to_string()),
+        }
+    }
+}
+
+/// Returns a member's value of `preference`, or its default if unset or unreadable.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_preference(
+    db: &DatabaseConnection,
+    user_id: &str,
+    preference: Preference,
+) -> Result<String> {
+    Ok(get_value(db, user_id, preference.key())
+        .await?
+        .and_then(|value| preference.normalize(&value).ok())
+        .unwrap_or_else(|| preference.default_value().to_string()))
+}
+
+/// Checks `value` and saves it as a member's `preference`.
+///
+/// # Errors
+/// Returns `Error::Config` if the value isn't one the preference accepts, or an error if
+/// the database write fails.
+pub async fn set_preference(
+    db: &DatabaseConnection,
+    user_id: &str,
+    preference: Preference,
+    value: &str,
+) -> Result<()> {
+    let value = preference.normalize(value)?;
+    set_value(db, user_id, preference.key(), &value).await
+}
+
+/// Loads every member's stored preferences, keyed by member and preference key.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_all_values(db: &DatabaseConnection) -> Result<HashMap<(String, String), String>> {
+    Ok(UserPreference::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|preference| ((preference.user_id, preference.key), preference.value))
+        .collect())
+}
+
+/// Retrieves the raw string value of a member's preference, if set.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_value<C>(db: &C, user_id: &str, key: &str) -> Result<Option<String>>
+where
+    C: ConnectionTrait,
+{
+    let preference = UserPreference::find()
+        .filter(user_preference::Column::UserId.eq(user_id))
+        .filter(user_preference::Column::Key.eq(key))
+        .one(db)
+        .await?;
+
+    Ok(preference.map(|p| p.value))
+}
+
+/// Stores a member's preference, inserting or updating as needed.
+///
+/// # Errors
+/// Returns an error if the database query or write fails.
+pub async fn set_value<C>(db: &C, user_id: &str, key: &str, value: &str) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let now = Utc::now().naive_utc();
+
+    let existing = UserPreference::find()
+        .filter(user_preference::Column::UserId.eq(user_id))
+        .filter(user_preference::Column::Key.eq(key))
+        .one(db)
+        .await?;
+
+    if let Some(preference) = existing {
+        let mut active_model: user_preference::ActiveModel = preference.into();
+        active_model.value = Set(value.to_string());
+        active_model.updated_at = Set(now);
+        active_model.update(db).await?;
+    } else {
+        let new_preference = user_preference::ActiveModel {
+            user_id: Set(user_id.to_string()),
+            key: Set(key.to_string()),
+            value: Set(value.to_string()),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        new_preference.insert(db).await?;
+    }
+    cache::invalidate(CachedTable::Preferences);
+
+    Ok(())
+}
+
+/// Returns how verbose a member's confirmations are, [`Verbosity::Detailed`] by default.
+///
+/// An unreadable stored value also falls back to the default.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_verbosity(db: &DatabaseConnection, user_id: &str) -> Result<Verbosity> {
+    Ok(get_value(db, user_id, VERBOSITY_KEY)
+        .await?
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default())
+}
+
+/// Sets how verbose a member's confirmations are.
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_verbosity(
+    db: &DatabaseConnection,
+    user_id: &str,
+    verbosity: Verbosity,
+) -> Result<()> {
+    set_value(db, user_id, VERBOSITY_KEY, verbosity.as_str()).await
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_verbosity_per_user() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        assert_eq!(get_verbosity(&db, "alice").await?, Verbosity::Detailed);
+
+        set_verbosity(&db, "alice", Verbosity::Compact).await?;
+        assert_eq!(get_verbosity(&db, "alice").await?, Verbosity::Compact);
+        assert_eq!(get_verbosity(&db, "bob").await?, Verbosity::Detailed);
+
+        // Changing it again updates the same row
+        set_verbosity(&db, "alice", Verbosity::Detailed).await?;
+        assert_eq!(get_verbosity(&db, "alice").await?, Verbosity::Detailed);
+        assert_eq!(UserPreference::find().count(&db).await?, 1);
+
+        assert_eq!("Compact".parse::<Verbosity>()?, Verbosity::Compact);
+        assert!("loud".parse::<Verbosity>().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_preferences_by_key() -> Result<()> {
+        let db = setup_test_db().await?;
+        let verbosity = Preference::from_key("Verbosity").unwrap();
+        assert_eq!(get_preference(&db, "alice", verbosity).await?, "detailed");
+
+        set_preference(&db, "alice", verbosity, " COMPACT ").await?;
+        assert_eq!(get_preference(&db, "alice", verbosity).await?, "compact");
+        assert_eq!(get_verbosity(&db, "alice").await?, Verbosity::Compact);
+        assert!(matches!(
+            set_preference(&db, "alice", verbosity, "loud").await,
+            Err(Error::Config { .. })
+        ));
+        assert!(Preference::from_key("language").is_none());
+
+        // The cache serves the stored value and sees later changes
+        let cache = cache::Cache::default();
+        let cached = cache.preference(&db, "alice", VERBOSITY_KEY).await?;
+        assert_eq!(cached.as_deref(), Some("compact"));
+        set_verbosity(&db, "alice", Verbosity::Detailed).await?;
+        let cached = cache.preference(&db, "alice", VERBOSITY_KEY).await?;
+        assert_eq!(cached.as_deref(), Some("detailed"));
+        assert_eq!(cache.preference(&db, "bob", VERBOSITY_KEY).await?, None);
+
+        Ok(())
+    }
+}
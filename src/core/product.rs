@@ -4,12 +4,25 @@
 //! within the envelope system. Products are predefined items with fixed prices that can be
 //! quickly used in transactions via the `use_product` command. All functions are async and
 //! return Result types for proper error handling throughout the system.
+//!
+//! Default descriptions may contain placeholders such as `{quantity}` and `{date}`, which
+//! [`expand_placeholders`] fills in each time the product is used.
 
 use crate::{
-    entities::{Product, product},
+    core::{
+        cache::{self, CachedTable},
+        envelope as envelope_core, fuzzy,
+        setup::Currency,
+        table::{Column, Table},
+        transaction as transaction_core,
+    },
+    entities::{Product, Transaction, TransactionType, envelope, product, transaction},
     errors::{Error, Result},
 };
-use sea_orm::{QueryOrder, Set, prelude::*};
+use chrono::NaiveDate;
+use sea_orm::{QueryOrder, Set, TransactionTrait, prelude::*};
+use serde::Deserialize;
+use std::fmt::Write;
 
 /// Retrieves all active (non-deleted) products from the database, ordered alphabetically by name.
 ///
@@ -27,6 +40,51 @@ pub async fn get_all_active_products(db: &DatabaseConnection) -> Result<Vec<prod
         .map_err(Into::into)
 }
 
+/// Suggests the product name most likely meant by a name that matched none (see
+/// [`fuzzy::closest_match`]).
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn suggest_product_name(db: &DatabaseConnection, name: &str) -> Result<Option<String>> {
+    let products = get_all_active_products(db).await?;
+    Ok(
+        fuzzy::closest_match(name, products.iter().map(|prod| prod.name.as_str()))
+            .map(str::to_string),
+    )
+}
+
+/// Suggests product names for autocomplete, alphabetically and at most 25.
+///
+/// Matches products whose name contains `partial` (case-insensitive). With
+/// `envelope_name`, only products linked to an envelope of that name are suggested.
+/// Envelopes are matched by name, so a product linked to an individual envelope is
+/// suggested for every member's envelope of the same name.
+#[must_use]
+pub fn suggest_product_names(
+    products: &[product::Model],
+    envelopes: &[envelope::Model],
+    partial: &str,
+    envelope_name: Option<&str>,
+) -> Vec<String> {
+    let partial_lower = partial.to_lowercase();
+    let in_envelope = |prod: &product::Model| {
+        envelope_name.is_none_or(|name| {
+            envelopes
+                .iter()
+                .any(|env| env.id == prod.envelope_id && env.name.eq_ignore_ascii_case(name.trim()))
+        })
+    };
+
+    let mut matching: Vec<String> = products
+        .iter()
+        .filter(|prod| prod.name.to_lowercase().contains(&partial_lower) && in_envelope(prod))
+        .map(|prod| prod.name.clone())
+        .collect();
+    matching.sort();
+    matching.truncate(25); // Discord autocomplete limit
+    matching
+}
+
 /// Finds a specific product by its name, returning None if not found or deleted.
 ///
 /// This function is used for product lookups when users reference products by name
@@ -34,10 +92,10 @@ pub async fn get_all_active_products(db: &DatabaseConnection) -> Result<Vec<prod
 ///
 /// # Errors
 /// Returns an error if the database query fails.
-pub async fn get_product_by_name(
-    db: &DatabaseConnection,
-    name: &str,
-) -> Result<Option<product::Model>> {
+pub async fn get_product_by_name<C>(db: &C, name: &str) -> Result<Option<product::Model>>
+where
+    C: ConnectionTrait,
+{
     Product::find()
         .filter(product::Column::Name.eq(name))
         .filter(product::Column::IsDeleted.eq(false))
@@ -46,6 +104,23 @@ pub async fn get_product_by_name(
         .map_err(Into::into)
 }
 
+/// Retrieves the active products linked to any of the given envelopes.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_products_for_envelopes(
+    db: &DatabaseConnection,
+    envelope_ids: &[i64],
+) -> Result<Vec<product::Model>> {
+    Product::find()
+        .filter(product::Column::EnvelopeId.is_in(envelope_ids.iter().copied()))
+        .filter(product::Column::IsDeleted.eq(false))
+        .order_by_asc(product::Column::Name)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
 /// Retrieves a specific product by its unique ID.
 /// This function is used for product lookups when the ID is known, such as when
 /// processing transactions that reference a product by ID.
@@ -73,12 +148,15 @@ pub async fn get_product_by_id(
 /// - The product name is empty or whitespace-only
 /// - The price is negative or not finite (NaN, infinity)
 /// - The database insert operation fails
-pub async fn create_product(
-    db: &DatabaseConnection,
+pub async fn create_product<C>(
+    db: &C,
     name: String,
     price: f64,
     envelope_id: i64,
-) -> Result<product::Model> {
+) -> Result<product::Model>
+where
+    C: ConnectionTrait,
+{
     // Validate inputs
     if name.trim().is_empty() {
         return Err(Error::Config {
@@ -100,12 +178,15 @@ pub async fn create_product(
         name: Set(name.trim().to_string()),
         price: Set(price),
         envelope_id: Set(envelope_id),
+        category: Set(None),
         is_deleted: Set(false),
         created_at: Set(now),
         updated_at: Set(now),
         ..Default::default()
     };
-    product.insert(db).await.map_err(Into::into)
+    let created = product.insert(db).await?;
+    cache::invalidate(CachedTable::Products);
+    Ok(created)
 }
 
 /// Updates an existing product's name and price, performing input validation.
@@ -119,12 +200,15 @@ pub async fn create_product(
 /// - The price is negative or not finite (NaN, infinity)
 /// - The product does not exist or is already deleted
 /// - The database update operation fails
-pub async fn update_product(
-    db: &DatabaseConnection,
+pub async fn update_product<C>(
+    db: &C,
     product_id: i64,
     new_name: String,
     new_price: f64,
-) -> Result<product::Model> {
+) -> Result<product::Model>
+where
+    C: ConnectionTrait,
+{
     // Validate inputs
     if new_name.trim().is_empty() {
         return Err(Error::Config {
@@ -158,7 +242,167 @@ pub async fn update_product(
     product.price = Set(new_price);
     product.updated_at = Set(chrono::Utc::now().naive_utc());
 
-    product.update(db).await.map_err(Into::into)
+    let updated = product.update(db).await?;
+    cache::invalidate(CachedTable::Products);
+    Ok(updated)
+}
+
+/// Normalizes a product category tag: trimmed and lowercased, `None` if blank.
+#[must_use]
+pub fn normalize_category(raw: &str) -> Option<String> {
+    let category = raw.trim().to_lowercase();
+    (!category.is_empty()).then_some(category)
+}
+
+/// Sets (or clears) a product's category tag.
+///
+/// The tag is normalized with [`normalize_category`], so a blank tag clears it.
+///
+/// # Errors
+/// Returns an error if:
+/// - The product does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_product_category<C>(
+    db: &C,
+    product_id: i64,
+    category: Option<&str>,
+) -> Result<product::Model>
+where
+    C: ConnectionTrait,
+{
+    let product = Product::find_by_id(product_id)
+        .one(db)
+        .await?
+        .filter(|prod| !prod.is_deleted)
+        .ok_or_else(|| Error::ProductNotFound {
+            name: product_id.to_string(),
+        })?;
+
+    let mut active_model: product::ActiveModel = product.into();
+    active_model.category = Set(category.and_then(normalize_category));
+    active_model.updated_at = Set(chrono::Utc::now().naive_utc());
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Products);
+    Ok(updated)
+}
+
+/// Sets (or clears) the description prefixed to a product's uses.
+///
+/// The description is normalized with [`envelope_core::normalize_default_description`], so a
+/// blank one clears it, and the product then falls back to its envelope's default.
+///
+/// # Errors
+/// Returns an error if:
+/// - The product does not exist or is deleted
+/// - The database update operation fails
+pub async fn set_default_description<C>(
+    db: &C,
+    product_id: i64,
+    description: Option<&str>,
+) -> Result<product::Model>
+where
+    C: ConnectionTrait,
+{
+    let product = Product::find_by_id(product_id)
+        .one(db)
+        .await?
+        .filter(|prod| !prod.is_deleted)
+        .ok_or_else(|| Error::ProductNotFound {
+            name: product_id.to_string(),
+        })?;
+
+    let mut active_model: product::ActiveModel = product.into();
+    active_model.default_description =
+        Set(description.and_then(envelope_core::normalize_default_description));
+    active_model.updated_at = Set(chrono::Utc::now().naive_utc());
+    let updated = active_model.update(db).await?;
+    cache::invalidate(CachedTable::Products);
+    Ok(updated)
+}
+
+/// Placeholders a default description may contain, as listed to members.
+pub const DESCRIPTION_PLACEHOLDERS: &str = "{quantity}, {date}, {product}, {total}";
+
+/// Fills in the placeholders of a product use's default description.
+///
+/// `{quantity}` becomes the quantity used, `{date}` the day of the use (`YYYY-MM-DD`),
+/// `{product}` the product name, and `{total}` the cost of the use in `currency`.
+/// Anything else in braces is left as written.
+#[allow(clippy::literal_string_with_formatting_args)] // Placeholders look like format arguments
+#[must_use]
+pub fn expand_placeholders(
+    template: &str,
+    product: &product::Model,
+    quantity: i64,
+    date: NaiveDate,
+    currency: Currency,
+) -> String {
+    // Cast is safe: for quantities < 2^53, no precision loss occurs in f64
+    #[allow(clippy::cast_precision_loss)]
+    let total = product.price * quantity as f64;
+    template
+        .replace("{quantity}", &quantity.to_string())
+        .replace("{date}", &date.format("%Y-%m-%d").to_string())
+        .replace("{product}", &product.name)
+        .replace("{total}", &currency.format(total))
+}
+
+/// Builds the description of a product use on `date`, e.g. `"Product: Coffee (x2)"`.
+///
+/// The product's default description, or else its envelope's, is put in front
+/// (`"Morning run - Product: Coffee (x2)"`) with its placeholders expanded (see
+/// [`expand_placeholders`]), and uses recorded for another member end with who recorded
+/// them. The `Product: <name> (x<quantity>)` part is what [`return_product`] looks for.
+#[must_use]
+pub fn use_description(
+    product: &product::Model,
+    envelope: &envelope::Model,
+    quantity: i64,
+    date: NaiveDate,
+    currency: Currency,
+    recorded_by: Option<&str>,
+) -> String {
+    let mut description = format!("Product: {} (x{quantity})", product.name);
+    if let Some(prefix) = product
+        .default_description
+        .as_deref()
+        .or(envelope.default_description.as_deref())
+    {
+        let prefix = expand_placeholders(prefix, product, quantity, date, currency);
+        description = format!("{prefix} - {description}");
+    }
+    if let Some(author) = recorded_by {
+        description = format!("{description} - recorded by {author}");
+    }
+    description
+}
+
+/// Returns the distinct category tags in use among `products`, alphabetically.
+#[must_use]
+pub fn product_categories(products: &[product::Model]) -> Vec<String> {
+    let mut categories: Vec<String> = products
+        .iter()
+        .filter_map(|prod| prod.category.clone())
+        .collect();
+    categories.sort();
+    categories.dedup();
+    categories
+}
+
+/// Keeps the products tagged with `category` (case-insensitive), or all of them if
+/// `category` is `None`.
+#[must_use]
+pub fn filter_by_category(
+    products: Vec<product::Model>,
+    category: Option<&str>,
+) -> Vec<product::Model> {
+    let Some(category) = category.and_then(normalize_category) else {
+        return products;
+    };
+    products
+        .into_iter()
+        .filter(|prod| prod.category.as_deref() == Some(category.as_str()))
+        .collect()
 }
 
 /// Soft deletes a product by marking it as deleted, preserving transaction history.
@@ -187,7 +431,347 @@ pub async fn delete_product(db: &DatabaseConnection, product_id: i64) -> Result<
     product.is_deleted = Set(true);
     product.updated_at = Set(chrono::Utc::now().naive_utc());
 
-    product.update(db).await.map_err(Into::into)
+    let updated = product.update(db).await?;
+    cache::invalidate(CachedTable::Products);
+    Ok(updated)
+}
+
+/// Returns `quantity` units of a product a member used, crediting the envelope it was
+/// charged to.
+///
+/// The return is a [`TransactionType::ProductReturn`] linked through `refund_of` to the
+/// member's most recent use of the product from `envelope_id` (alone or in a bundle)
+/// that still has enough left to return, and is priced at what that use paid per unit
+/// rather than the current price. Like `/refund`, several partial returns can be made
+/// against one use. The call is idempotent on `message_id`.
+///
+/// # Errors
+/// Returns an error if:
+/// - The quantity is not positive (`Error::InvalidAmount`)
+/// - No recent use has `quantity` units left to return (`Error::Config`)
+/// - A database query or the database transaction fails
+pub async fn return_product(
+    db: &DatabaseConnection,
+    product: &product::Model,
+    envelope_id: i64,
+    quantity: i64,
+    user_id: &str,
+    message_id: Option<String>,
+) -> Result<transaction::Model> {
+    if quantity <= 0 {
+        // Cast is safe: only used to report the rejected quantity
+        #[allow(clippy::cast_precision_loss)]
+        return Err(Error::InvalidAmount {
+            amount: quantity as f64,
+        });
+    }
+    if let Some(ref id) = message_id
+        && let Some(existing) = transaction_core::get_transaction_by_message_id(db, id).await?
+    {
+        return Ok(existing);
+    }
+
+    let marker = format!("Product: {} (x", product.name);
+    let uses = Transaction::find_active()
+        .filter(transaction::Column::EnvelopeId.eq(envelope_id))
+        .filter(transaction::Column::UserId.eq(user_id))
+        .filter(transaction::Column::TransactionType.eq(TransactionType::UseProduct))
+        .filter(transaction::Column::Description.contains(&marker))
+        .filter(transaction::Column::Pending.eq(false))
+        .order_by_desc(transaction::Column::Timestamp)
+        .order_by_desc(transaction::Column::Id)
+        .all(db)
+        .await?;
+
+    for usage in uses {
+        let Some(units_used) = used_quantity(&usage.description, &marker) else {
+            continue;
+        };
+        // Casts are safe: for quantities < 2^53, no precision loss occurs in f64
+        #[allow(clippy::cast_precision_loss)]
+        let amount = -usage.amount / units_used as f64 * quantity as f64;
+        let remaining = -usage.amount - transaction_core::get_refunded_amount(db, usage.id).await?;
+        if amount <= remaining + 0.005 {
+            return transaction_core::create_linked_refund(
+                db,
+                usage.id,
+                Some(amount.min(remaining)),
+                user_id.to_string(),
+                message_id,
+                TransactionType::ProductReturn,
+            )
+            .await;
+        }
+    }
+
+    Err(Error::Config {
+        message: format!(
+            "No recent use of '{}' has {quantity} left to return",
+            product.name
+        ),
+    })
+}
+
+/// Reads the quantity from a product use's description, e.g. `3` from
+/// `"Product: Coffee (x3) - recorded by 123"`.
+fn used_quantity(description: &str, marker: &str) -> Option<i64> {
+    let (_, rest) = description.split_once(marker)?;
+    let (digits, _) = rest.split_once(')')?;
+    digits.parse().ok().filter(|quantity| *quantity > 0)
+}
+
+/// One row of a batch price import: the total paid for a quantity of a product.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PriceImportRow {
+    /// Product name
+    pub name: String,
+    /// Total price paid for `quantity` items
+    pub total_price: f64,
+    /// Number of items the total covers (defaults to 1)
+    #[serde(default = "default_quantity")]
+    pub quantity: f64,
+}
+
+const fn default_quantity() -> f64 {
+    1.0
+}
+
+/// What happened to a single row of a price import.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PriceImportOutcome {
+    /// An existing product's unit price was changed
+    Updated {
+        /// Unit price before the import
+        old_price: f64,
+        /// Unit price after the import
+        new_price: f64,
+    },
+    /// A missing product was created
+    Created {
+        /// Unit price of the new product
+        price: f64,
+    },
+    /// No product has this name and creating missing products was not requested
+    NotFound,
+    /// The row's values were rejected
+    Invalid {
+        /// Why the row was rejected
+        reason: String,
+    },
+}
+
+/// Result for a single row of a price import.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceImportResult {
+    /// Product name from the row
+    pub name: String,
+    /// What happened to the row
+    pub outcome: PriceImportOutcome,
+}
+
+/// Parses a price import file as JSON (an array of objects) or CSV.
+///
+/// CSV rows are `name,total_price[,quantity]`; a leading `name,...` header row is
+/// optional. JSON is detected by a leading `[`.
+///
+/// # Errors
+/// Returns an error (`Error::Config`) describing the first malformed row if the content
+/// can't be parsed.
+pub fn parse_price_import(content: &str) -> Result<Vec<PriceImportRow>> {
+    let trimmed = content.trim_start_matches('\u{feff}').trim();
+
+    if trimmed.starts_with('[') {
+        return serde_json::from_str(trimmed).map_err(|e| Error::Config {
+            message: format!("Invalid JSON price import: {e}"),
+        });
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(trimmed.as_bytes());
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let line = index + 1;
+        let record = record.map_err(|e| Error::Config {
+            message: format!("Invalid CSV on line {line}: {e}"),
+        })?;
+
+        let name = record.get(0).unwrap_or_default();
+        if name.is_empty() {
+            continue;
+        }
+        if line == 1 && name.eq_ignore_ascii_case("name") {
+            continue;
+        }
+
+        let parse_number = |field: Option<&str>, column: &str| {
+            field
+                .filter(|value| !value.is_empty())
+                .map(|value| {
+                    value
+                        .trim_start_matches('$')
+                        .parse::<f64>()
+                        .map_err(|_| Error::Config {
+                            message: format!("Invalid {column} '{value}' on line {line}"),
+                        })
+                })
+                .transpose()
+        };
+
+        let total_price =
+            parse_number(record.get(1), "total_price")?.ok_or_else(|| Error::Config {
+                message: format!("Missing total_price on line {line}"),
+            })?;
+        let quantity = parse_number(record.get(2), "quantity")?.unwrap_or_else(default_quantity);
+
+        rows.push(PriceImportRow {
+            name: name.to_string(),
+            total_price,
+            quantity,
+        });
+    }
+
+    Ok(rows)
+}
+
+/// Applies a batch of price updates in a single database transaction.
+///
+/// Each row's unit price is `total_price / quantity`. Existing products get the new
+/// unit price; missing products are created and linked to `create_missing_in` when it is
+/// given, and reported as [`PriceImportOutcome::NotFound`] otherwise. Rows with invalid
+/// values are reported and skipped. If any database operation fails, no changes are kept.
+///
+/// # Errors
+/// Returns an error if a database operation fails.
+pub async fn import_product_prices(
+    db: &DatabaseConnection,
+    rows: &[PriceImportRow],
+    create_missing_in: Option<i64>,
+) -> Result<Vec<PriceImportResult>> {
+    let txn = db.begin().await?;
+    let mut results = Vec::with_capacity(rows.len());
+
+    for row in rows {
+        let name = row.name.trim().to_string();
+        let outcome = match validate_import_row(row) {
+            Err(reason) => PriceImportOutcome::Invalid { reason },
+            Ok(unit_price) => match get_product_by_name(&txn, &name).await? {
+                Some(existing) => {
+                    let old_price = existing.price;
+                    update_product(&txn, existing.id, existing.name, unit_price).await?;
+                    PriceImportOutcome::Updated {
+                        old_price,
+                        new_price: unit_price,
+                    }
+                }
+                None => match create_missing_in {
+                    Some(envelope_id) => {
+                        create_product(&txn, name.clone(), unit_price, envelope_id).await?;
+                        PriceImportOutcome::Created { price: unit_price }
+                    }
+                    None => PriceImportOutcome::NotFound,
+                },
+            },
+        };
+        results.push(PriceImportResult { name, outcome });
+    }
+
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Products);
+    Ok(results)
+}
+
+/// Checks a row's values, returning the unit price or a reason for rejecting it.
+fn validate_import_row(row: &PriceImportRow) -> std::result::Result<f64, String> {
+    if row.name.trim().is_empty() {
+        return Err("name is empty".to_string());
+    }
+    if !row.total_price.is_finite() || row.total_price < 0.0 {
+        return Err(format!("invalid total price {}", row.total_price));
+    }
+    if !row.quantity.is_finite() || row.quantity <= 0.0 {
+        return Err(format!("invalid quantity {}", row.quantity));
+    }
+    Ok(row.total_price / row.quantity)
+}
+
+/// Lays out parsed price import rows as a table with each row's unit price, for
+/// previewing an import before it is applied.
+#[must_use]
+pub fn price_import_table(rows: &[PriceImportRow]) -> Table {
+    let mut table = Table::new(vec![
+        Column::left("Product"),
+        Column::right("Total"),
+        Column::right("Qty"),
+        Column::right("Unit"),
+    ]);
+    for row in rows {
+        table.row(vec![
+            row.name.clone(),
+            format!("{:.2}", row.total_price),
+            format!("{}", row.quantity),
+            validate_import_row(row)
+                .map_or_else(|_| "invalid".to_string(), |price| format!("{price:.2}")),
+        ]);
+    }
+    table
+}
+
+/// Formats price import results as one line per row followed by totals, with prices in
+/// `currency`.
+///
+/// # Errors
+/// Returns an error if string formatting fails.
+pub fn format_price_import_summary(
+    results: &[PriceImportResult],
+    currency: Currency,
+) -> Result<String> {
+    let mut summary = String::new();
+    let (mut updated, mut created, mut skipped) = (0, 0, 0);
+
+    for result in results {
+        match &result.outcome {
+            PriceImportOutcome::Updated {
+                old_price,
+                new_price,
+            } => {
+                updated += 1;
+                writeln!(
+                    &mut summary,
+                    "~ {}: {} → {}",
+                    result.name,
+                    currency.format(*old_price),
+                    currency.format(*new_price)
+                )?;
+            }
+            PriceImportOutcome::Created { price } => {
+                created += 1;
+                writeln!(
+                    &mut summary,
+                    "+ {}: {} (new)",
+                    result.name,
+                    currency.format(*price)
+                )?;
+            }
+            PriceImportOutcome::NotFound => {
+                skipped += 1;
+                writeln!(&mut summary, "? {}: no such product", result.name)?;
+            }
+            PriceImportOutcome::Invalid { reason } => {
+                skipped += 1;
+                writeln!(&mut summary, "✗ {}: {reason}", result.name)?;
+            }
+        }
+    }
+
+    writeln!(
+        &mut summary,
+        "\n{updated} updated, {created} created, {skipped} skipped"
+    )?;
+    Ok(summary)
 }
 
 #[cfg(test)]
@@ -195,7 +779,7 @@ mod tests {
     #![allow(clippy::unwrap_used)]
     #![allow(clippy::float_cmp)]
     use super::*;
-    use crate::test_utils::*;
+    use crate::{entities::TransactionType, test_utils::*};
     use sea_orm::{DatabaseBackend, MockDatabase};
 
     #[tokio::test]
@@ -392,6 +976,33 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_product_categories_and_filter() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+
+        let chips = create_product(&db, "Chips".to_string(), 3.0, envelope.id).await?;
+        let soda = create_product(&db, "Soda".to_string(), 2.0, envelope.id).await?;
+        create_product(&db, "Batteries".to_string(), 8.0, envelope.id).await?;
+
+        let chips = set_product_category(&db, chips.id, Some("  Snacks ")).await?;
+        assert_eq!(chips.category.as_deref(), Some("snacks"));
+        set_product_category(&db, soda.id, Some("snacks")).await?;
+
+        let products = get_all_active_products(&db).await?;
+        assert_eq!(product_categories(&products), vec!["snacks".to_string()]);
+
+        let snacks = filter_by_category(products.clone(), Some("SNACKS"));
+        let names: Vec<&str> = snacks.iter().map(|prod| prod.name.as_str()).collect();
+        assert_eq!(names, vec!["Chips", "Soda"]);
+        assert_eq!(filter_by_category(products, None).len(), 3);
+
+        // A blank tag clears the category
+        let soda = set_product_category(&db, soda.id, Some(" ")).await?;
+        assert_eq!(soda.category, None);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_get_all_active_products_excludes_deleted() -> Result<()> {
         let (db, envelope) = setup_with_envelope().await?;
@@ -435,6 +1046,31 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_suggest_product_names_by_envelope() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries = create_test_envelope(&db, "Groceries").await?;
+        let electronics = create_test_envelope(&db, "Electronics").await?;
+        create_custom_product(&db, "Milk", 3.5, groceries.id).await?;
+        create_custom_product(&db, "Bread", 2.0, groceries.id).await?;
+        create_custom_product(&db, "Batteries", 8.0, electronics.id).await?;
+
+        let products = get_all_active_products(&db).await?;
+        let envelopes = crate::core::envelope::get_all_active_envelopes(&db).await?;
+
+        assert_eq!(
+            suggest_product_names(&products, &envelopes, "b", None),
+            vec!["Batteries", "Bread"]
+        );
+        assert_eq!(
+            suggest_product_names(&products, &envelopes, "", Some("groceries")),
+            vec!["Bread", "Milk"]
+        );
+        assert!(suggest_product_names(&products, &envelopes, "", Some("Travel")).is_empty());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_full_workflow_envelope_product_transaction() -> Result<()> {
         let db = setup_test_db().await?;
@@ -452,7 +1088,7 @@ mod tests {
             "Monthly allocation".to_string(),
             "user123".to_string(),
             None,
-            "addfunds".to_string(),
+            TransactionType::AddFunds,
         )
         .await?;
         assert_eq!(add_funds_tx.amount, 50.0);
@@ -476,12 +1112,12 @@ mod tests {
             "2x Coffee".to_string(),
             "user123".to_string(),
             Some("discord_msg_456".to_string()),
-            "use_product".to_string(),
+            TransactionType::UseProduct,
         )
         .await?;
         assert_eq!(use_product_tx.amount, -10.0);
         assert_eq!(use_product_tx.description, "2x Coffee");
-        assert_eq!(use_product_tx.transaction_type, "use_product");
+        assert_eq!(use_product_tx.transaction_type, TransactionType::UseProduct);
 
         // Verify final balance
         let final_envelope = crate::core::envelope::get_envelope_by_id(&db, envelope.id)
@@ -495,8 +1131,204 @@ mod tests {
         assert_eq!(transactions.len(), 2);
 
         // Transactions should be ordered by timestamp (newest first)
-        assert_eq!(transactions[0].transaction_type, "use_product");
-        assert_eq!(transactions[1].transaction_type, "addfunds");
+        assert_eq!(
+            transactions[0].transaction_type,
+            TransactionType::UseProduct
+        );
+        assert_eq!(transactions[1].transaction_type, TransactionType::AddFunds);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[allow(clippy::literal_string_with_formatting_args)] // Placeholders look like format arguments
+    async fn test_use_description() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let coffee = create_test_product(&db, "Coffee", env.id).await?;
+        let date = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap_or_default();
+        assert_eq!(
+            use_description(&coffee, &env, 2, date, Currency::default(), None),
+            "Product: Coffee (x2)"
+        );
+
+        // The envelope's default applies until the product overrides it
+        let env = envelope_core::set_default_description(&db, env.id, Some("Cafe")).await?;
+        assert_eq!(
+            use_description(&coffee, &env, 2, date, Currency::default(), Some("123")),
+            "Cafe - Product: Coffee (x2) - recorded by 123"
+        );
+        let coffee = set_default_description(&db, coffee.id, Some("Morning coffee")).await?;
+        let description = use_description(&coffee, &env, 1, date, Currency::default(), None);
+        assert_eq!(description, "Morning coffee - Product: Coffee (x1)");
+        assert_eq!(used_quantity(&description, "Product: Coffee (x"), Some(1));
+
+        // Placeholders are filled in for each use
+        let coffee = set_default_description(
+            &db,
+            coffee.id,
+            Some("{quantity} cups of {product} on {date} ({total}) {unknown}"),
+        )
+        .await?;
+        let euro = Currency::from_code("EUR");
+        let description = use_description(&coffee, &env, 3, date, euro, None);
+        assert_eq!(
+            description,
+            format!(
+                "3 cups of Coffee on 2025-03-14 ({}) {{unknown}} - Product: Coffee (x3)",
+                euro.format(coffee.price * 3.0)
+            )
+        );
+        assert_eq!(used_quantity(&description, "Product: Coffee (x"), Some(3));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_return_product() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        create_test_transaction(&db, envelope.id, 50.0).await?;
+        let coffee = create_custom_product(&db, "Coffee", 5.0, envelope.id).await?;
+        let older = create_custom_transaction(
+            &db,
+            envelope.id,
+            -12.0,
+            "Product: Coffee (x3)",
+            "alice",
+            None,
+            TransactionType::UseProduct,
+        )
+        .await?;
+        let newer = create_custom_transaction(
+            &db,
+            envelope.id,
+            -5.0,
+            "Product: Coffee (x1)",
+            "alice",
+            None,
+            TransactionType::UseProduct,
+        )
+        .await?;
+
+        // The most recent use is returned first
+        let first = return_product(&db, &coffee, envelope.id, 1, "alice", None).await?;
+        assert_eq!(first.transaction_type, TransactionType::ProductReturn);
+        assert_eq!(first.refund_of, Some(newer.id));
+        assert_eq!(first.amount, 5.0);
+
+        // Then older uses, at the price they paid
+        let second = return_product(
+            &db,
+            &coffee,
+            envelope.id,
+            2,
+            "alice",
+            Some("m1".to_string()),
+        )
+        .await?;
+        assert_eq!(second.refund_of, Some(older.id));
+        assert_eq!(second.amount, 8.0);
+        let retried = return_product(
+            &db,
+            &coffee,
+            envelope.id,
+            2,
+            "alice",
+            Some("m1".to_string()),
+        )
+        .await?;
+        assert_eq!(retried.id, second.id);
+
+        let result = return_product(&db, &coffee, envelope.id, 2, "alice", None).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+        let result = return_product(&db, &coffee, envelope.id, 1, "bob", None).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+        let result = return_product(&db, &coffee, envelope.id, 0, "alice", None).await;
+        assert!(matches!(result, Err(Error::InvalidAmount { .. })));
+
+        let envelope = crate::core::envelope::get_envelope_by_id(&db, envelope.id)
+            .await?
+            .unwrap();
+        assert_eq!(envelope.balance, 50.0 - 12.0 - 5.0 + 5.0 + 8.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_price_import_csv() -> Result<()> {
+        let rows =
+            parse_price_import("name,total_price,quantity\nSoda,$6.99,12\n\"Milk, 2%\",4.50\n")?;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].name, "Soda");
+        assert_eq!(rows[0].total_price, 6.99);
+        assert_eq!(rows[0].quantity, 12.0);
+        assert_eq!(rows[1].name, "Milk, 2%");
+        assert_eq!(rows[1].quantity, 1.0);
+
+        assert!(parse_price_import("Soda,abc").is_err());
+        assert!(parse_price_import("Soda").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_price_import_json() -> Result<()> {
+        let rows = parse_price_import(
+            r#"[{"name": "Soda", "total_price": 6.99, "quantity": 12}, {"name": "Milk", "total_price": 4.5}]"#,
+        )?;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].quantity, 1.0);
+
+        assert!(parse_price_import("[{\"name\": \"Soda\"}]").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_product_prices() -> Result<()> {
+        let (db, _envelope, product) = setup_with_product().await?;
+        let rows = vec![
+            PriceImportRow {
+                name: product.name.clone(),
+                total_price: 24.0,
+                quantity: 12.0,
+            },
+            PriceImportRow {
+                name: "Bread".to_string(),
+                total_price: 3.0,
+                quantity: 1.0,
+            },
+            PriceImportRow {
+                name: "Eggs".to_string(),
+                total_price: 5.0,
+                quantity: 0.0,
+            },
+        ];
+
+        // Without an envelope for new products, missing ones are only reported
+        let results = import_product_prices(&db, &rows, None).await?;
+        assert_eq!(
+            results[0].outcome,
+            PriceImportOutcome::Updated {
+                old_price: product.price,
+                new_price: 2.0
+            }
+        );
+        assert_eq!(results[1].outcome, PriceImportOutcome::NotFound);
+        assert!(matches!(
+            results[2].outcome,
+            PriceImportOutcome::Invalid { .. }
+        ));
+        assert!(get_product_by_name(&db, "Bread").await?.is_none());
+
+        // With an envelope, missing products are created
+        let results = import_product_prices(&db, &rows[1..2], Some(product.envelope_id)).await?;
+        assert_eq!(
+            results[0].outcome,
+            PriceImportOutcome::Created { price: 3.0 }
+        );
+        assert!(get_product_by_name(&db, "Bread").await?.is_some());
+
+        let summary = format_price_import_summary(&results, Currency::default())?;
+        assert!(summary.contains("0 updated, 1 created, 0 skipped"));
+        let yen = format_price_import_summary(&results, Currency::from_code("JPY"))?;
+        assert!(yen.contains("+ Bread: ¥3 (new)"));
 
         Ok(())
     }
@@ -0,0 +1,360 @@
+//! Reconciliation business logic - Finding and fixing balances that drifted.
+//!
+//! Balances are stored on the envelope and adjusted by every transaction, so manual
+//! database edits or bugs can leave them out of step with the history. An envelope's
+//! expected balance is worked forward from its latest monthly statement: from the opening
+//! the monthly update applied after the statement's month, with every transaction dated
+//! after that month applied on top.
+//!
+//! Statements recorded before openings were archived replay the monthly update's plan
+//! instead ([`monthly::plan_openings`], from the statement's closing balance and
+//! allocation, and the current deficit policy, funding rules and other settings).
+//! Envelopes closed in the same month are planned together, since deficits and funding
+//! can move between them.
+//!
+//! Envelopes without a statement with balances (created since the last monthly update,
+//! or only aggregated by the retention policy) have no starting point and are skipped.
+
+use crate::{
+    core::{
+        allocation,
+        cache::{self, CachedTable},
+        calendar, monthly,
+    },
+    entities::{Envelope, MonthlyStatement, Transaction, envelope, monthly_statement, transaction},
+    errors::Result,
+};
+use sea_orm::{QueryOrder, QuerySelect, Set, TransactionTrait, prelude::*};
+use std::collections::{BTreeMap, HashMap};
+
+/// Differences smaller than this (in dollars) are rounding noise, not drift.
+const TOLERANCE: f64 = 0.005;
+
+/// An envelope whose stored balance doesn't match its history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    /// The envelope as stored, with its drifted balance
+    pub envelope: envelope::Model,
+    /// Balance worked out from the statement and transactions
+    pub expected: f64,
+    /// Statement month the balance was worked forward from
+    pub statement_month: String,
+    /// Transactions applied since that statement
+    pub transaction_count: usize,
+}
+
+impl Discrepancy {
+    /// Returns how far the stored balance is from the expected one (positive if the
+    /// stored balance is too high).
+    #[must_use]
+    pub fn difference(&self) -> f64 {
+        self.envelope.balance - self.expected
+    }
+}
+
+/// Outcome of checking every envelope's balance.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReconcileReport {
+    /// Number of envelopes whose balance could be recomputed
+    pub checked: usize,
+    /// Envelopes whose stored balance differs from the recomputed one
+    pub discrepancies: Vec<Discrepancy>,
+    /// Names of envelopes skipped because they have no statement with balances
+    pub skipped: Vec<String>,
+}
+
+/// Recomputes every active envelope's balance and reports the ones that drifted.
+///
+/// Nothing is changed; see [`fix_balances`].
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn check_balances<C>(db: &C) -> Result<ReconcileReport>
+where
+    C: ConnectionTrait,
+{
+    let envelopes = Envelope::find()
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(crate::core::envelope::not_template())
+        .order_by_asc(envelope::Column::Name)
+        .all(db)
+        .await?;
+
+    let mut report = ReconcileReport::default();
+    let mut closed = Vec::new();
+    for env in envelopes {
+        let Some(statement) = latest_statement(db, env.id).await? else {
+            report.skipped.push(env.name);
+            continue;
+        };
+        let Some(close_balance) = statement.close_balance else {
+            report.skipped.push(env.name);
+            continue;
+        };
+        closed.push((env, statement, close_balance));
+    }
+
+    // Replay each closed month's openings for statements that didn't archive them
+    let policy = monthly::get_deficit_policy(db).await?;
+    let debt_envelope_id = monthly::get_debt_envelope(db).await?.map(|debt| debt.id);
+    let rules = allocation::get_funding_rules(db).await?;
+    let mut months: BTreeMap<&str, Vec<envelope::Model>> = BTreeMap::new();
+    for (env, statement, close_balance) in &closed {
+        months
+            .entry(statement.month.as_str())
+            .or_default()
+            .push(envelope::Model {
+                balance: *close_balance,
+                allocation: statement.allocation.unwrap_or(env.allocation),
+                ..env.clone()
+            });
+    }
+    let mut openings: HashMap<i64, f64> = HashMap::new();
+    for closing in months.values() {
+        let plan = monthly::plan_openings(closing, policy, debt_envelope_id, &rules);
+        for (env, opening) in closing.iter().zip(plan.openings) {
+            openings.insert(env.id, opening.balance);
+        }
+    }
+
+    for (env, statement, _) in closed {
+        let (_, since) =
+            calendar::utc_bounds(db, statement.period_end, statement.period_end).await?;
+        let amounts: Vec<f64> = Transaction::find_active()
+            .select_only()
+            .column(transaction::Column::Amount)
+            .filter(transaction::Column::EnvelopeId.eq(env.id))
+            .filter(transaction::Column::Timestamp.gte(since))
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        let opening = statement
+            .applied_opening
+            .or_else(|| openings.get(&env.id).copied())
+            .unwrap_or_default();
+        let expected = opening + amounts.iter().sum::<f64>();
+
+        report.checked += 1;
+        if (env.balance - expected).abs() >= TOLERANCE {
+            report.discrepancies.push(Discrepancy {
+                envelope: env,
+                expected,
+                statement_month: statement.month,
+                transaction_count: amounts.len(),
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// Latest statement of an envelope with balances, by period.
+async fn latest_statement<C>(db: &C, envelope_id: i64) -> Result<Option<monthly_statement::Model>>
+where
+    C: ConnectionTrait,
+{
+    MonthlyStatement::find()
+        .filter(monthly_statement::Column::EnvelopeId.eq(envelope_id))
+        .filter(monthly_statement::Column::CloseBalance.is_not_null())
+        .order_by_desc(monthly_statement::Column::PeriodEnd)
+        .one(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Recomputes every balance and sets the drifted ones to their expected value.
+///
+/// The check and the fixes run in one database transaction, so a transaction recorded
+/// meanwhile can't be lost and either every balance is fixed or none is.
+///
+/// # Returns
+/// The discrepancies that were fixed, with the balances they had before
+///
+/// # Errors
+/// Returns an error if a database query or update fails.
+pub async fn fix_balances(db: &DatabaseConnection) -> Result<ReconcileReport> {
+    let txn = db.begin().await?;
+
+    let report = check_balances(&txn).await?;
+    for discrepancy in &report.discrepancies {
+        let mut active_model: envelope::ActiveModel = discrepancy.envelope.clone().into();
+        active_model.balance = Set(discrepancy.expected);
+        active_model.update(&txn).await?;
+    }
+
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{
+        core::{calendar::BudgetCycle, statement},
+        entities::TransactionType,
+        test_utils::*,
+    };
+    use chrono::Utc;
+
+    #[tokio::test]
+    async fn test_fix_balances_from_statement() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries =
+            create_custom_envelope(&db, "groceries", None, "food", 300.0, false, false).await?;
+        let savings =
+            create_custom_envelope(&db, "savings", None, "save", 100.0, false, true).await?;
+        let fresh = create_custom_envelope(&db, "fresh", None, "misc", 50.0, false, false).await?;
+
+        // Close last month with balances of 40 (groceries) and 250 (savings), as the
+        // monthly update would, then apply the update to the balances
+        let today = Utc::now().date_naive();
+        let last_month = BudgetCycle::containing(today, 1).previous();
+        let envelopes = Envelope::find().all(&db).await?;
+        statement::record_statements(&db, &envelopes, &last_month, Utc::now()).await?;
+        let statements = MonthlyStatement::find().all(&db).await?;
+        for (envelope_id, close) in [(groceries.id, 40.0), (savings.id, 250.0)] {
+            let statement = statements
+                .iter()
+                .find(|s| s.envelope_id == envelope_id)
+                .cloned()
+                .ok_or(crate::errors::Error::EnvelopeNotFound {
+                    name: envelope_id.to_string(),
+                })?;
+            let mut active_model: monthly_statement::ActiveModel = statement.into();
+            active_model.close_balance = Set(Some(close));
+            active_model.update(&db).await?;
+        }
+        MonthlyStatement::delete_many()
+            .filter(monthly_statement::Column::EnvelopeId.eq(fresh.id))
+            .exec(&db)
+            .await?;
+        for (envelope_id, balance) in [(groceries.id, 300.0), (savings.id, 350.0)] {
+            crate::core::envelope::update_envelope_balance_atomic(&db, envelope_id, balance)
+                .await?;
+        }
+
+        // A spend this month, then a manual edit that drifts the groceries balance
+        crate::core::transaction::create_transaction(
+            &db,
+            groceries.id,
+            -25.0,
+            "Market".to_string(),
+            "test_user".to_string(),
+            None,
+            TransactionType::Spend,
+        )
+        .await?;
+        crate::core::envelope::update_envelope_balance_atomic(&db, groceries.id, 10.0).await?;
+
+        let report = check_balances(&db).await?;
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.skipped, vec!["fresh".to_string()]);
+        assert_eq!(report.discrepancies.len(), 1);
+        let discrepancy = &report.discrepancies[0];
+        assert_eq!(discrepancy.envelope.id, groceries.id);
+        assert_eq!(discrepancy.expected, 275.0);
+        assert_eq!(discrepancy.difference(), 10.0);
+        assert_eq!(discrepancy.transaction_count, 1);
+
+        let fixed = fix_balances(&db).await?;
+        assert_eq!(fixed.discrepancies.len(), 1);
+        let groceries = Envelope::find_by_id(groceries.id).one(&db).await?;
+        assert_eq!(groceries.map(|env| env.balance), Some(275.0));
+        assert!(check_balances(&db).await?.discrepancies.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_no_drift_after_deficits_move() -> Result<()> {
+        let db = setup_test_db().await?;
+        let food = create_custom_envelope(&db, "Food", None, "food", 100.0, false, false).await?;
+        let fun = create_custom_envelope(&db, "Fun", None, "fun", 50.0, false, false).await?;
+        let debt = create_custom_envelope(&db, "Debt", None, "debt", 0.0, false, false).await?;
+        crate::core::envelope::update_envelope_balance_atomic(&db, food.id, -130.0).await?;
+        crate::core::envelope::update_envelope_balance_atomic(&db, fun.id, -60.0).await?;
+        crate::core::envelope::update_envelope_balance_atomic(&db, debt.id, -20.0).await?;
+
+        // Food's and Fun's deficits move to the debt envelope, which carries its own over
+        monthly::set_deficit_policy(&db, monthly::DeficitPolicy::Debt).await?;
+        monthly::set_debt_envelope(&db, debt.id).await?;
+        let result = monthly::process_monthly_updates(&db).await?;
+        assert_eq!(
+            result.map(|result| (result.total_debt_transfer, result.total_carried_deficit)),
+            Some((190.0, 20.0))
+        );
+        let debt = Envelope::find_by_id(debt.id).one(&db).await?;
+        assert_eq!(debt.map(|env| env.balance), Some(-210.0));
+
+        let report = check_balances(&db).await?;
+        assert_eq!(report.checked, 3);
+        assert!(
+            report.discrepancies.is_empty(),
+            "{:?}",
+            report.discrepancies
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_no_drift_after_allocation_changes() -> Result<()> {
+        let db = setup_test_db().await?;
+        let food = create_custom_envelope(&db, "Food", None, "food", 100.0, false, false).await?;
+        crate::core::envelope::update_envelope_balance_atomic(&db, food.id, 30.0).await?;
+        monthly::process_monthly_updates(&db).await?;
+
+        // Raising the allocation now takes effect next month, not in the opening applied
+        crate::core::envelope::set_allocation(&db, food.id, 150.0).await?;
+        let report = check_balances(&db).await?;
+        assert_eq!(report.checked, 1);
+        assert!(
+            report.discrepancies.is_empty(),
+            "{:?}",
+            report.discrepancies
+        );
+
+        // Statements that didn't archive their opening replay the allocation they had
+        MonthlyStatement::update_many()
+            .col_expr(
+                monthly_statement::Column::AppliedOpening,
+                sea_orm::sea_query::Expr::value(Option::<f64>::None),
+            )
+            .exec(&db)
+            .await?;
+        assert!(check_balances(&db).await?.discrepancies.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_no_drift_after_funding_rules() -> Result<()> {
+        let db = setup_test_db().await?;
+        let emergency =
+            create_custom_envelope(&db, "Emergency", None, "save", 50.0, false, true).await?;
+        crate::core::envelope::set_sinking_fund(&db, emergency.id, 0.0, Some(250.0)).await?;
+        let vacation =
+            create_custom_envelope(&db, "Vacation", None, "save", 200.0, false, true).await?;
+        allocation::set_funding_rule(&db, vacation.id, Some(emergency.id)).await?;
+
+        // Vacation's whole allocation tops up Emergency
+        let result = monthly::process_monthly_updates(&db).await?;
+        assert_eq!(result.map(|result| result.held_funding.len()), Some(1));
+        let balances: Vec<f64> = Envelope::find()
+            .order_by_asc(envelope::Column::Name)
+            .all(&db)
+            .await?
+            .iter()
+            .map(|env| env.balance)
+            .collect();
+        assert_eq!(balances, vec![250.0, 0.0]);
+
+        let report = check_balances(&db).await?;
+        assert_eq!(report.checked, 2);
+        assert!(
+            report.discrepancies.is_empty(),
+            "{:?}",
+            report.discrepancies
+        );
+        Ok(())
+    }
+}
@@ -3,12 +3,136 @@
 //! This module provides functions for generating spending reports, progress calculations,
 //! and transaction summaries. All functions are framework-agnostic and return structured
 //! data that can be formatted by the bot layer.
+//!
+//! Envelope visibility is enforced here: private individual envelopes are only shown to
+//! their owner, and everyone else receives an [`EnvelopeView::Hidden`] placeholder.
+//!
+//! Reports default to the current month, computed from envelope balances. Reports over
+//! another [`ReportRange`] aggregate spending from the transactions in the window instead
+//! and compare it to the allocation prorated over the same days. "Month" always means a
+//! budget cycle, which starts on the configured cycle start day (see
+//! [`crate::core::calendar`]).
+//!
+//! Past balances are reconstructed by [`reconstruct_balance`] from the monthly statements
+//! and the transactions recorded since.
+//!
+//! [`personal_report`] covers only the caller's own individual envelopes (allowances),
+//! with how much of each allocation they saved this month compared to last month.
+//!
+//! Each envelope's status compares its spending to the expected pace for the days elapsed.
+//! The thresholds for 🟢/🟡/🔴 are saved for the household, optionally overridden per
+//! envelope ([`PaceSettings`]). Envelopes in weekly mode are also broken down by week of
+//! the budget month ([`get_weekly_spending`]), comparing each week's spending to its
+//! share of the allocation.
+//!
+//! [`variance_report`] compares each envelope's allocation to its actual spending for a
+//! budget month, live for the current month and from the monthly statements for closed
+//! ones.
+//!
+//! [`rollover_accumulation`] splits each rollover envelope's balance into the surplus
+//! carried over from earlier months and this month's funding, with the surplus each
+//! closed month ended with taken from its statement.
+//!
+//! [`get_stale_envelopes`] finds envelopes nobody has used in a number of months, as
+//! candidates for archiving.
+//!
+//! [`build_report_model`] computes everything the current month's report shows as a
+//! [`ReportModel`], leaving the formatting to the frontend (`bot::render` for Discord).
+//!
+//! The household's [`Theme`] decides how progress bars, status indicators and embed
+//! colors look, so every report and confirmation shares the same style.
 
 use crate::{
-    entities::{envelope, transaction},
-    errors::Result,
+    core::{
+        cache::{self, CachedTable},
+        calendar::{self, BudgetCycle},
+        setup::{self, Currency},
+        state, statement,
+        table::{self, Table},
+    },
+    entities::{
+        MonthlyStatement, Transaction, TransactionType, envelope, monthly_statement, transaction,
+    },
+    errors::{Error, Result},
 };
-use sea_orm::DatabaseConnection;
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Utc};
+use sea_orm::{Condition, DatabaseConnection, QueryOrder, QuerySelect, prelude::*};
+use std::collections::HashMap;
+
+/// Text shown in place of a private envelope's figures.
+pub const HIDDEN_LABEL: &str = "hidden";
+
+/// An envelope as seen by a particular household member.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnvelopeView {
+    /// The viewer may see the full envelope
+    Visible(Box<envelope::Model>),
+    /// The envelope is private to another member; only identifying details are exposed
+    Hidden(HiddenEnvelope),
+}
+
+/// Identifying details of a private envelope that the viewer is not allowed to see.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HiddenEnvelope {
+    /// Envelope ID
+    pub id: i64,
+    /// Envelope name
+    pub name: String,
+    /// Budget category
+    pub category: String,
+    /// Discord user ID of the owner
+    pub user_id: Option<String>,
+}
+
+impl EnvelopeView {
+    /// Returns the envelope name, which is visible either way.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Visible(env) => &env.name,
+            Self::Hidden(hidden) => &hidden.name,
+        }
+    }
+}
+
+/// Checks whether a user may see an envelope's balance and transactions.
+///
+/// Shared and non-private envelopes are visible to everyone; private envelopes only to
+/// their owner.
+#[must_use]
+pub fn can_view(envelope: &envelope::Model, viewer_id: &str) -> bool {
+    !envelope.is_private || envelope.user_id.as_deref() == Some(viewer_id)
+}
+
+/// Wraps an envelope in the view appropriate for the given viewer.
+#[must_use]
+pub fn view_envelope(envelope: envelope::Model, viewer_id: &str) -> EnvelopeView {
+    if can_view(&envelope, viewer_id) {
+        EnvelopeView::Visible(Box::new(envelope))
+    } else {
+        EnvelopeView::Hidden(HiddenEnvelope {
+            id: envelope.id,
+            name: envelope.name,
+            category: envelope.category,
+            user_id: envelope.user_id,
+        })
+    }
+}
+
+/// Retrieves all active envelopes as seen by the given viewer.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_envelope_views(
+    db: &DatabaseConnection,
+    viewer_id: &str,
+) -> Result<Vec<EnvelopeView>> {
+    let envelopes = crate::core::envelope::get_all_active_envelopes(db).await?;
+    Ok(envelopes
+        .into_iter()
+        .map(|env| view_envelope(env, viewer_id))
+        .collect())
+}
 
 /// Represents a comprehensive envelope report with spending analysis.
 #[derive(Debug, Clone)]
@@ -55,7 +179,7 @@ pub async fn generate_envelope_report(
     // Get envelope
     let envelope = crate::core::envelope::get_envelope_by_id(db, envelope_id)
         .await?
-        .ok_or_else(|| crate::errors::Error::EnvelopeNotFound {
+        .ok_or_else(|| Error::EnvelopeNotFound {
             name: envelope_id.to_string(),
         })?;
 
@@ -86,6 +210,37 @@ pub async fn generate_envelope_report(
     })
 }
 
+/// Generates an envelope report on behalf of a specific user.
+///
+/// Same as [`generate_envelope_report`], but refuses to expose private envelopes to
+/// anyone other than their owner.
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope does not exist
+/// - The envelope is private to another user (`Error::EnvelopeHidden`)
+/// - Database queries fail
+pub async fn generate_envelope_report_for_viewer(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    transaction_limit: Option<u64>,
+    viewer_id: &str,
+) -> Result<EnvelopeReport> {
+    let envelope = crate::core::envelope::get_envelope_by_id(db, envelope_id)
+        .await?
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    if !can_view(&envelope, viewer_id) {
+        return Err(Error::EnvelopeHidden {
+            name: envelope.name,
+        });
+    }
+
+    generate_envelope_report(db, envelope_id, transaction_limit).await
+}
+
 /// Calculates progress percentage based on current balance and allocation.
 ///
 /// Progress represents how much of the allocation has been used:
@@ -109,9 +264,29 @@ pub fn calculate_progress(balance: f64, allocation: f64) -> f64 {
     (balance / allocation) * 100.0
 }
 
-/// Generates a progress bar string for visual representation.
+/// Formats progress toward a sinking-fund savings goal.
+///
+/// # Arguments
+/// * `balance` - Current envelope balance
+/// * `goal` - Savings target for the envelope
+/// * `currency` - How the household currency is written
+///
+/// # Returns
+/// Formatted string like "$150.00 / $600.00 (25.0%)"
+#[must_use]
+pub fn format_goal_progress(balance: f64, goal: f64, currency: Currency) -> String {
+    let percent = calculate_progress(balance, goal);
+    format!(
+        "{} / {} ({percent:.1}%)",
+        currency.format(balance),
+        currency.format(goal)
+    )
+}
+
+/// Generates a progress bar string for visual representation, in the classic theme.
 ///
-/// Creates a text-based progress bar like: `[████████░░] 80%`
+/// Creates a text-based progress bar like: `[████████░░]`. Use [`Theme::progress_bar`]
+/// for the household's theme.
 ///
 /// # Arguments
 /// * `progress_percent` - Progress percentage (0-100)
@@ -121,199 +296,2259 @@ pub fn calculate_progress(balance: f64, allocation: f64) -> f64 {
 /// Formatted progress bar string
 #[must_use]
 pub fn format_progress_bar(progress_percent: f64, bar_length: Option<usize>) -> String {
-    let length = bar_length.unwrap_or(10);
-    let clamped_progress = progress_percent.clamp(0.0, 100.0);
+    Theme::Classic.progress_bar(progress_percent, bar_length)
+}
+
+/// `system_state` key holding the household's theme.
+const THEME_KEY: &str = "theme";
+
+/// Role of an embed, which picks its color from the [`Theme`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbedColor {
+    /// Confirmations and lists
+    Accent,
+    /// Reports and statements
+    Report,
+    /// Savings and allowances
+    Positive,
+}
+
+/// Style of the bot's progress bars, status indicators and embed colors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    /// Block bars and colored circles
+    #[default]
+    Classic,
+    /// Emoji square bars and colored squares
+    Squares,
+    /// Heart bars and colored hearts
+    Hearts,
+    /// Plain shapes and muted colors
+    Minimal,
+}
+
+impl Theme {
+    /// Every theme, in the order they're listed.
+    pub const ALL: [Self; 4] = [Self::Classic, Self::Squares, Self::Hearts, Self::Minimal];
+
+    /// Name of the theme as typed in `/config set theme`.
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Squares => "squares",
+            Self::Hearts => "hearts",
+            Self::Minimal => "minimal",
+        }
+    }
+
+    /// Looks up a theme by its [`key`](Self::key), ignoring case.
+    #[must_use]
+    pub fn from_key(key: &str) -> Option<Self> {
+        let key = key.trim();
+        Self::ALL
+            .into_iter()
+            .find(|theme| theme.key().eq_ignore_ascii_case(key))
+    }
+
+    /// Filled and empty segments of the theme's progress bars.
+    const fn bar_segments(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Classic => ("█", "░"),
+            Self::Squares => ("🟩", "⬜"),
+            Self::Hearts => ("❤️", "🤍"),
+            Self::Minimal => ("■", "□"),
+        }
+    }
+
+    /// Status indicator for a pace status.
+    #[must_use]
+    pub const fn status(self, status: PaceStatus) -> &'static str {
+        match (self, status) {
+            (Self::Classic, _) => status.emoji(),
+            (Self::Squares, PaceStatus::OnTrack) => "🟩",
+            (Self::Squares, PaceStatus::SlightlyOver) => "🟨",
+            (Self::Squares, PaceStatus::WellOver) => "🟥",
+            (Self::Hearts, PaceStatus::OnTrack) => "💚",
+            (Self::Hearts, PaceStatus::SlightlyOver) => "💛",
+            (Self::Hearts, PaceStatus::WellOver) => "❤️",
+            (Self::Minimal, PaceStatus::OnTrack) => "✓",
+            (Self::Minimal, PaceStatus::SlightlyOver) => "~",
+            (Self::Minimal, PaceStatus::WellOver) => "✗",
+        }
+    }
+
+    /// Embed color for an embed role.
+    #[must_use]
+    pub const fn color(self, role: EmbedColor) -> u32 {
+        match (self, role) {
+            (Self::Classic, EmbedColor::Accent) => 0x0058_65F2, // Discord purple
+            (Self::Classic, EmbedColor::Report) => 0x0034_98DB, // Blue
+            (Self::Classic | Self::Squares, EmbedColor::Positive) => 0x002E_CC71, // Green
+            (Self::Squares, EmbedColor::Accent) => 0x009B_59B6, // Purple
+            (Self::Squares, EmbedColor::Report) => 0x001A_BC9C, // Teal
+            (Self::Hearts, EmbedColor::Accent) => 0x00E9_1E63,  // Pink
+            (Self::Hearts, EmbedColor::Report) => 0x00E7_4C3C,  // Red
+            (Self::Hearts, EmbedColor::Positive) => 0x00F1_C40F, // Gold
+            (Self::Minimal, EmbedColor::Accent) => 0x0095_A5A6, // Grey
+            (Self::Minimal, EmbedColor::Report) => 0x007F_8C8D, // Dark grey
+            (Self::Minimal, EmbedColor::Positive) => 0x00BD_C3C7, // Light grey
+        }
+    }
+
+    /// Generates a progress bar like `[████████░░]` in the theme's segments.
+    ///
+    /// # Arguments
+    /// * `progress_percent` - Progress percentage (0-100)
+    /// * `bar_length` - Length of the progress bar in segments (default 10)
+    #[must_use]
+    pub fn progress_bar(self, progress_percent: f64, bar_length: Option<usize>) -> String {
+        let length = bar_length.unwrap_or(10);
+        let clamped_progress = progress_percent.clamp(0.0, 100.0);
+
+        // Cast safety: clamped_progress ∈ [0, 100], length is small (10-20).
+        // Result is mathematically in [0, length], truncation/sign loss intentional for display.
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let filled = ((clamped_progress / 100.0) * length as f64).round() as usize;
+        let empty = length.saturating_sub(filled);
 
-    // Cast safety: clamped_progress ∈ [0, 100], length is small (10-20).
-    // Result is mathematically in [0, length], truncation/sign loss intentional for display.
-    #[allow(
-        clippy::cast_possible_truncation,
-        clippy::cast_sign_loss,
-        clippy::cast_precision_loss
-    )]
-    let filled = ((clamped_progress / 100.0) * length as f64).round() as usize;
-    let empty = length.saturating_sub(filled);
+        let (filled_segment, empty_segment) = self.bar_segments();
+        let filled_str = filled_segment.repeat(filled);
+        let empty_str = empty_segment.repeat(empty);
 
-    let filled_str = "█".repeat(filled);
-    let empty_str = "░".repeat(empty);
+        format!("[{filled_str}{empty_str}]")
+    }
+}
+
+/// Loads the household's theme, or the classic theme if none is saved.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_theme<C>(db: &C) -> Result<Theme>
+where
+    C: ConnectionTrait,
+{
+    Ok(state::get_value(db, THEME_KEY)
+        .await?
+        .and_then(|value| Theme::from_key(&value))
+        .unwrap_or_default())
+}
 
-    format!("[{filled_str}{empty_str}]")
+/// Saves the household's theme.
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_theme<C>(db: &C, theme: Theme) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    state::set_value(db, THEME_KEY, theme.key()).await
 }
 
 /// Formats a transaction amount with appropriate sign and currency.
 ///
 /// # Arguments
 /// * `amount` - Transaction amount (positive for income, negative for expenses)
+/// * `currency` - How the household currency is written
 ///
 /// # Returns
 /// Formatted string like "+$50.00" or "-$25.50"
 #[must_use]
-pub fn format_transaction_amount(amount: f64) -> String {
-    if amount >= 0.0 {
-        format!("+${amount:.2}")
-    } else {
-        format!("-${:.2}", amount.abs())
-    }
+pub fn format_transaction_amount(amount: f64, currency: Currency) -> String {
+    currency.format_signed(amount)
 }
 
 /// Generates a summary line for a transaction.
 ///
 /// # Arguments
 /// * `transaction` - The transaction to summarize
+/// * `currency` - How the household currency is written
 ///
 /// # Returns
 /// Formatted summary string
 #[must_use]
-pub fn format_transaction_summary(transaction: &transaction::Model) -> String {
-    let amount_str = format_transaction_amount(transaction.amount);
+pub fn format_transaction_summary(transaction: &transaction::Model, currency: Currency) -> String {
+    let mut amount_str = format_transaction_amount(transaction.amount, currency);
+    if let (Some(original), Some(currency)) =
+        (transaction.original_amount, &transaction.original_currency)
+    {
+        amount_str = format!("{amount_str} ({currency} {:.2})", original.abs());
+    }
+    if let Some(quantity) = format_quantity(transaction, currency) {
+        amount_str = format!("{amount_str} ({quantity})");
+    }
     let desc = &transaction.description;
-    let tx_type = &transaction.transaction_type;
+    let mut tx_type = transaction.refund_of.map_or_else(
+        || transaction.transaction_type.to_string(),
+        |original_id| format!("{} of #{original_id}", transaction.transaction_type),
+    );
+    if transaction.pending {
+        tx_type.push_str(" (pending)");
+    }
 
     format!("{amount_str} | {tx_type} | {desc}")
 }
 
-#[cfg(test)]
-mod tests {
-    #![allow(clippy::float_cmp)]
-    use super::*;
-    use crate::test_utils::*;
+/// Formats a spend's quantity with its unit price, e.g. `3.2 gal @ $4.00/gal`.
+///
+/// Returns `None` if the transaction didn't record a quantity.
+#[must_use]
+pub fn format_quantity(transaction: &transaction::Model, currency: Currency) -> Option<String> {
+    let quantity = transaction.quantity?;
+    let price = currency.format(crate::core::transaction::unit_price(transaction)?);
+    Some(transaction.unit.as_deref().map_or_else(
+        || format!("{quantity} @ {price} each"),
+        |unit| format!("{quantity} {unit} @ {price}/{unit}"),
+    ))
+}
 
-    #[test]
-    fn test_calculate_progress_full_allocation() {
-        // Full allocation remaining = 100%
-        let progress = calculate_progress(100.0, 100.0);
-        assert_eq!(progress, 100.0);
-    }
+/// Named report windows offered as shortcuts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportPeriod {
+    /// From the start of the current budget month to today
+    ThisMonth,
+    /// The whole previous budget month
+    LastMonth,
+    /// From January 1st to today
+    YearToDate,
+}
 
-    #[test]
-    fn test_calculate_progress_half_spent() {
-        // Half spent = 50% remaining
-        let progress = calculate_progress(50.0, 100.0);
-        assert_eq!(progress, 50.0);
-    }
+/// An inclusive date window covered by a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportRange {
+    /// First day included
+    pub from: NaiveDate,
+    /// Last day included
+    pub to: NaiveDate,
+}
 
-    #[test]
-    fn test_calculate_progress_fully_spent() {
-        // All spent = 0% remaining
-        let progress = calculate_progress(0.0, 100.0);
-        assert_eq!(progress, 0.0);
+impl ReportRange {
+    /// Creates a range from `from` to `to`, both inclusive.
+    ///
+    /// # Errors
+    /// Returns `Error::Config` if `from` is after `to`.
+    pub fn new(from: NaiveDate, to: NaiveDate) -> Result<Self> {
+        if from > to {
+            return Err(Error::Config {
+                message: format!("Report start date {from} is after end date {to}"),
+            });
+        }
+        Ok(Self { from, to })
     }
 
-    #[test]
-    fn test_calculate_progress_overspent() {
-        // Overspent = negative percentage
-        let progress = calculate_progress(-25.0, 100.0);
-        assert_eq!(progress, -25.0);
+    /// Returns the range for a named period, relative to `today`, for budget months
+    /// starting on `cycle_start_day`.
+    #[must_use]
+    pub fn for_period(period: ReportPeriod, today: NaiveDate, cycle_start_day: u32) -> Self {
+        let cycle = BudgetCycle::containing(today, cycle_start_day);
+        match period {
+            ReportPeriod::ThisMonth => Self {
+                from: cycle.start,
+                to: today,
+            },
+            ReportPeriod::LastMonth => {
+                let previous = cycle.previous();
+                Self {
+                    from: previous.start,
+                    to: previous.end,
+                }
+            }
+            ReportPeriod::YearToDate => Self {
+                from: today.with_ordinal(1).unwrap_or(today),
+                to: today,
+            },
+        }
     }
 
-    #[test]
-    fn test_calculate_progress_zero_allocation() {
-        // Zero allocation edge case
-        let progress = calculate_progress(50.0, 0.0);
-        assert_eq!(progress, 0.0);
+    /// Parses `YYYY-MM-DD` bounds. A missing start defaults to the start of the current
+    /// budget month and a missing end to `today`.
+    ///
+    /// # Errors
+    /// Returns `Error::Config` if a date is malformed or the start is after the end.
+    pub fn parse(
+        from: Option<&str>,
+        to: Option<&str>,
+        today: NaiveDate,
+        cycle_start_day: u32,
+    ) -> Result<Self> {
+        let from = from.map(parse_date).transpose()?.unwrap_or_else(|| {
+            Self::for_period(ReportPeriod::ThisMonth, today, cycle_start_day).from
+        });
+        let to = to.map(parse_date).transpose()?.unwrap_or(today);
+        Self::new(from, to)
     }
 
-    #[test]
-    fn test_format_progress_bar_full() {
-        let bar = format_progress_bar(100.0, Some(10));
-        assert_eq!(bar, "[██████████]");
+    /// Number of days in the range.
+    #[must_use]
+    pub fn days(&self) -> i64 {
+        (self.to - self.from).num_days() + 1
     }
 
-    #[test]
-    fn test_format_progress_bar_half() {
-        let bar = format_progress_bar(50.0, Some(10));
-        assert_eq!(bar, "[█████░░░░░]");
+    /// Number of days of the range that have passed as of `today` (including today).
+    #[must_use]
+    pub fn elapsed_days(&self, today: NaiveDate) -> i64 {
+        ((today - self.from).num_days() + 1).clamp(0, self.days())
     }
+}
 
-    #[test]
-    fn test_format_progress_bar_zero() {
-        let bar = format_progress_bar(0.0, Some(10));
-        assert_eq!(bar, "[░░░░░░░░░░]");
-    }
+/// Parses a `YYYY-MM-DD` date.
+///
+/// # Errors
+/// Returns `Error::Config` if the value isn't a valid date in that format.
+pub fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").map_err(|_| Error::Config {
+        message: format!("Invalid date '{value}', expected YYYY-MM-DD"),
+    })
+}
 
-    #[test]
-    fn test_format_progress_bar_overspent() {
-        // Overspending is clamped to 0% in the bar (percentage display handled separately)
-        let bar = format_progress_bar(-25.0, Some(10));
-        assert_eq!(bar, "[░░░░░░░░░░]");
-    }
+/// Prorates a monthly allocation over a date range.
+///
+/// Each budget month (starting on `cycle_start_day`) in the range contributes the share
+/// of its days that fall inside the range, so a full month counts as one allocation.
+#[must_use]
+pub fn prorated_allocation(allocation: f64, range: &ReportRange, cycle_start_day: u32) -> f64 {
+    let mut total = 0.0;
+    let mut day = range.from;
 
-    #[test]
-    fn test_format_transaction_amount_positive() {
-        assert_eq!(format_transaction_amount(50.0), "+$50.00");
-        assert_eq!(format_transaction_amount(123.45), "+$123.45");
-    }
+    while day <= range.to {
+        let cycle = BudgetCycle::containing(day, cycle_start_day);
+        let segment_end = cycle.end.min(range.to);
+        let covered = (segment_end - day).num_days() + 1;
 
-    #[test]
-    fn test_format_transaction_amount_negative() {
-        assert_eq!(format_transaction_amount(-50.0), "-$50.00");
-        assert_eq!(format_transaction_amount(-123.45), "-$123.45");
+        // Day counts are small, precision loss negligible
+        #[allow(clippy::cast_precision_loss)]
+        let share = covered as f64 / cycle.days() as f64;
+        total += allocation * share;
+        let Some(next_cycle) = cycle.end.succ_opt() else {
+            break;
+        };
+        day = next_cycle;
     }
 
-    #[test]
-    fn test_format_transaction_amount_zero() {
-        assert_eq!(format_transaction_amount(0.0), "+$0.00");
-    }
+    total
+}
 
-    #[tokio::test]
-    async fn test_generate_envelope_report_integration() -> Result<()> {
-        let (db, envelope) = setup_with_envelope().await?;
+/// Sums the spending of each envelope within a date range.
+///
+/// Spending is the total of expenses (negative transactions) less refunds, by
+/// transaction date in the household timezone. Deposits such as `addfunds` are not
+/// counted. Envelopes without spending in the range are absent from the map.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_spending_in_range(
+    db: &DatabaseConnection,
+    range: &ReportRange,
+) -> Result<HashMap<i64, f64>> {
+    let (start, end) = calendar::utc_bounds(db, range.from, range.to).await?;
 
-        // Create some transactions (they automatically update balance)
-        create_test_transaction(&db, envelope.id, 100.0).await?; // Added funds (+100)
-        create_test_transaction(&db, envelope.id, -25.0).await?; // Spent (-25)
-        // Final balance should be: 0 + 100 - 25 = 75
+    let transactions = Transaction::find_active()
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .filter(
+            Condition::any()
+                .add(transaction::Column::Amount.lt(0.0))
+                .add(transaction::Column::TransactionType.is_in(TransactionType::REFUNDS)),
+        )
+        .all(db)
+        .await?;
 
-        // Generate report
-        let report = generate_envelope_report(&db, envelope.id, Some(5)).await?;
+    let mut spending = HashMap::new();
+    for tx in transactions {
+        *spending.entry(tx.envelope_id).or_insert(0.0) -= tx.amount;
+    }
+    Ok(spending)
+}
 
-        assert_eq!(report.envelope.id, envelope.id);
-        assert_eq!(report.balance, 75.0);
-        assert_eq!(report.allocation, 100.0);
-        assert_eq!(report.progress_percent, 75.0);
-        assert_eq!(report.amount_remaining, 75.0);
-        assert_eq!(report.amount_spent, 25.0);
-        assert_eq!(report.recent_transactions.len(), 2);
+/// Sums the interest paid to each envelope within a date range.
+///
+/// Interest is paid by the monthly update (see
+/// [`crate::core::monthly::monthly_interest`]). Envelopes without interest in the range
+/// are absent from the map.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_interest_in_range(
+    db: &DatabaseConnection,
+    range: &ReportRange,
+) -> Result<HashMap<i64, f64>> {
+    let (start, end) = calendar::utc_bounds(db, range.from, range.to).await?;
 
-        Ok(())
+    let transactions = Transaction::find_active()
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .filter(transaction::Column::TransactionType.eq(TransactionType::Interest))
+        .all(db)
+        .await?;
+
+    let mut interest = HashMap::new();
+    for tx in transactions {
+        *interest.entry(tx.envelope_id).or_insert(0.0) += tx.amount;
     }
+    Ok(interest)
+}
 
-    #[tokio::test]
-    async fn test_generate_envelope_report_no_transactions() -> Result<()> {
-        let (db, envelope) = setup_with_envelope().await?;
+/// An envelope without transactions since a cutoff, from [`get_stale_envelopes`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleEnvelope {
+    /// The envelope
+    pub envelope: envelope::Model,
+    /// When its latest transaction was recorded, or `None` if it has none
+    pub last_activity: Option<DateTime<Utc>>,
+}
 
-        let report = generate_envelope_report(&db, envelope.id, None).await?;
+/// Finds active envelopes with no transactions in the `months` months before `today`,
+/// least recently used first.
+///
+/// Interest paid by the monthly update doesn't count as activity. Envelopes that never
+/// had a transaction are listed first.
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn get_stale_envelopes(
+    db: &DatabaseConnection,
+    months: u32,
+    today: NaiveDate,
+) -> Result<Vec<StaleEnvelope>> {
+    let cutoff_day = today
+        .checked_sub_months(Months::new(months))
+        .unwrap_or(NaiveDate::MIN);
+    let cutoff = calendar::start_of_day(calendar::get_timezone(db).await?, cutoff_day);
 
-        assert_eq!(report.envelope.id, envelope.id);
-        assert_eq!(report.recent_transactions.len(), 0);
-        assert_eq!(report.balance, 0.0);
+    // Latest transaction per envelope, aggregated in the database
+    let last_activity: HashMap<i64, DateTime<Utc>> = Transaction::find_active()
+        .select_only()
+        .column(transaction::Column::EnvelopeId)
+        .column_as(transaction::Column::Timestamp.max(), "last_activity")
+        .filter(transaction::Column::TransactionType.ne(TransactionType::Interest))
+        .group_by(transaction::Column::EnvelopeId)
+        .into_tuple::<(i64, Option<DateTime<Utc>>)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|(envelope_id, last)| Some((envelope_id, last?)))
+        .collect();
 
-        Ok(())
-    }
+    let mut stale: Vec<StaleEnvelope> = crate::core::envelope::get_all_active_envelopes(db)
+        .await?
+        .into_iter()
+        .map(|envelope| StaleEnvelope {
+            last_activity: last_activity.get(&envelope.id).copied(),
+            envelope,
+        })
+        .filter(|stale| stale.last_activity.is_none_or(|last| last < cutoff))
+        .collect();
+    stale.sort_by_key(|stale| stale.last_activity);
+    Ok(stale)
+}
 
-    #[tokio::test]
-    async fn test_generate_envelope_report_transaction_limit() -> Result<()> {
-        let (db, envelope) = setup_with_envelope().await?;
+/// Retrieves the largest expenses from the given envelopes within a date range, biggest
+/// first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_largest_spends(
+    db: &DatabaseConnection,
+    range: &ReportRange,
+    envelope_ids: &[i64],
+    limit: u64,
+) -> Result<Vec<transaction::Model>> {
+    let (start, end) = calendar::utc_bounds(db, range.from, range.to).await?;
 
-        // Create 15 transactions
-        for i in 0..15 {
-            create_custom_transaction(
-                &db,
-                envelope.id,
-                10.0,
-                &format!("Transaction {i}"),
-                "user1",
-                None,
-                "addfunds",
-            )
-            .await?;
+    Transaction::find_active()
+        .filter(transaction::Column::EnvelopeId.is_in(envelope_ids.iter().copied()))
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .filter(transaction::Column::Amount.lt(0.0))
+        .order_by_asc(transaction::Column::Amount)
+        .limit(limit)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Length of a budget week in days.
+const WEEK_DAYS: i64 = 7;
+
+/// One envelope's spending in one week of the budget month, for envelopes shown by week.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BudgetWeek {
+    /// Days in the week (see [`cycle_weeks`])
+    pub range: ReportRange,
+    /// Spending during the week, less refunds
+    pub spent: f64,
+    /// The monthly allocation prorated over the week's days
+    pub share: f64,
+}
+
+impl BudgetWeek {
+    /// Percentage of the weekly share spent, or 0 with no share.
+    #[must_use]
+    pub fn spent_percent(&self) -> f64 {
+        if self.share > 0.0 {
+            (self.spent / self.share) * 100.0
+        } else {
+            0.0
         }
+    }
 
-        // Request only 5 recent
-        let report = generate_envelope_report(&db, envelope.id, Some(5)).await?;
+    /// Percentage of the weekly share expected to be spent by the end of `today`.
+    #[must_use]
+    pub fn expected_percent(&self, today: NaiveDate) -> f64 {
+        // Day counts are small, precision loss negligible
+        #[allow(clippy::cast_precision_loss)]
+        let elapsed = self.range.elapsed_days(today) as f64 / self.range.days() as f64;
+        elapsed * 100.0
+    }
+}
 
-        assert_eq!(report.recent_transactions.len(), 5);
+/// Splits a budget month into weeks starting on its first day; the last week is cut
+/// short at the end of the month.
+#[must_use]
+pub fn cycle_weeks(cycle: &BudgetCycle) -> Vec<ReportRange> {
+    let mut weeks = Vec::new();
+    let mut from = cycle.start;
+    while from <= cycle.end {
+        let to = from
+            .checked_add_days(Days::new(6))
+            .map_or(cycle.end, |end| end.min(cycle.end));
+        weeks.push(ReportRange { from, to });
+        let Some(next) = to.succ_opt() else {
+            break;
+        };
+        from = next;
+    }
+    weeks
+}
+
+/// Breaks the current budget month's spending down by week for the given envelopes.
+///
+/// Each envelope gets the weeks from the start of the month up to the week containing
+/// `today`, with the spending in each week (by transaction timestamp in UTC, as in
+/// [`get_spending_in_range`]) and its share of the allocation.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_weekly_spending(
+    db: &DatabaseConnection,
+    envelopes: &[envelope::Model],
+    today: NaiveDate,
+    cycle_start_day: u32,
+) -> Result<HashMap<i64, Vec<BudgetWeek>>> {
+    if envelopes.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let cycle = BudgetCycle::containing(today, cycle_start_day);
+    let weeks: Vec<ReportRange> = cycle_weeks(&cycle)
+        .into_iter()
+        .filter(|week| week.from <= today)
+        .collect();
+    let Some(last_week) = weeks.last() else {
+        return Ok(HashMap::new());
+    };
+
+    let tz = calendar::get_timezone(db).await?;
+    let (start, end) = calendar::utc_bounds(db, cycle.start, last_week.to).await?;
+    let transactions = Transaction::find_active()
+        .filter(transaction::Column::EnvelopeId.is_in(envelopes.iter().map(|env| env.id)))
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .filter(
+            Condition::any()
+                .add(transaction::Column::Amount.lt(0.0))
+                .add(transaction::Column::TransactionType.is_in(TransactionType::REFUNDS)),
+        )
+        .all(db)
+        .await?;
+
+    let mut spending: HashMap<i64, Vec<f64>> = HashMap::new();
+    for tx in transactions {
+        let days_in = (tx.timestamp.with_timezone(&tz).date_naive() - cycle.start).num_days();
+        let Ok(index) = usize::try_from(days_in / WEEK_DAYS) else {
+            continue;
+        };
+        if let Some(spent) = spending
+            .entry(tx.envelope_id)
+            .or_insert_with(|| vec![0.0; weeks.len()])
+            .get_mut(index)
+        {
+            *spent -= tx.amount;
+        }
+    }
+
+    Ok(envelopes
+        .iter()
+        .map(|env| {
+            let spent = spending.remove(&env.id);
+            let breakdown = weeks
+                .iter()
+                .enumerate()
+                .map(|(index, week)| BudgetWeek {
+                    range: *week,
+                    spent: spent.as_ref().map_or(0.0, |spent| spent[index]),
+                    share: prorated_allocation(env.allocation, week, cycle_start_day),
+                })
+                .collect();
+            (env.id, breakdown)
+        })
+        .collect())
+}
+
+/// Household-wide totals shown at the top of `/report`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HouseholdSummary {
+    /// Sum of the envelopes' monthly allocations
+    pub total_allocated: f64,
+    /// Allocation spent so far this month (allocated minus remaining)
+    pub total_spent: f64,
+    /// Sum of the envelopes' current balances
+    pub total_remaining: f64,
+}
+
+impl HouseholdSummary {
+    /// Percentage of the total allocation spent so far, or 0 with no allocation.
+    #[must_use]
+    pub fn spent_percent(&self) -> f64 {
+        if self.total_allocated > 0.0 {
+            (self.total_spent / self.total_allocated) * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Totals allocations and balances across the active envelopes a viewer can see.
+///
+/// Computed with a single aggregate query. Private envelopes of other members are left
+/// out, so the totals can't be used to work out their balances.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn household_summary(
+    db: &DatabaseConnection,
+    viewer_id: &str,
+) -> Result<HouseholdSummary> {
+    let totals: Option<(Option<f64>, Option<f64>)> = crate::entities::Envelope::find()
+        .select_only()
+        .column_as(envelope::Column::Allocation.sum(), "total_allocated")
+        .column_as(envelope::Column::Balance.sum(), "total_remaining")
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(crate::core::envelope::not_template())
+        .filter(
+            Condition::any()
+                .add(envelope::Column::IsPrivate.eq(false))
+                .add(envelope::Column::UserId.eq(viewer_id)),
+        )
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    let (total_allocated, total_remaining) = totals.unwrap_or_default();
+    let total_allocated = total_allocated.unwrap_or(0.0);
+    let total_remaining = total_remaining.unwrap_or(0.0);
+    Ok(HouseholdSummary {
+        total_allocated,
+        total_spent: total_allocated - total_remaining,
+        total_remaining,
+    })
+}
+
+/// One envelope's row of a budget vs actual variance report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarianceLine {
+    /// Envelope name (as of the month's statement for closed months)
+    pub envelope_name: String,
+    /// Monthly allocation
+    pub allocation: f64,
+    /// Spent during the month, less refunds
+    pub actual: f64,
+}
+
+impl VarianceLine {
+    /// Allocation left unspent; negative when the envelope overspent.
+    #[must_use]
+    pub fn variance(&self) -> f64 {
+        self.allocation - self.actual
+    }
+
+    /// Variance as a percentage of the allocation, or `None` with no allocation.
+    #[must_use]
+    pub fn variance_percent(&self) -> Option<f64> {
+        (self.allocation > 0.0).then(|| self.variance() / self.allocation * 100.0)
+    }
+}
+
+/// Budget vs actual spending per envelope for one budget month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarianceReport {
+    /// Budget month as `YYYY-MM`
+    pub month: String,
+    /// First and last day of the month, if it has any data
+    pub period: Option<(NaiveDate, NaiveDate)>,
+    /// Whether the month is still running, so its spending is live rather than archived
+    pub in_progress: bool,
+    /// Envelopes the viewer can see
+    pub lines: Vec<VarianceLine>,
+    /// Other members' private envelopes left out of the report
+    pub hidden: usize,
+}
+
+impl VarianceReport {
+    /// Totals across every line.
+    #[must_use]
+    pub fn totals(&self) -> VarianceLine {
+        VarianceLine {
+            envelope_name: "Total".to_string(),
+            allocation: self.lines.iter().map(|line| line.allocation).sum(),
+            actual: self.lines.iter().map(|line| line.actual).sum(),
+        }
+    }
+}
+
+/// Compares each envelope's allocation to what it actually spent in a budget month.
+///
+/// Without `month`, or for the current month, spending is aggregated live from this
+/// month's transactions. Closed months are read from their monthly statements, using the
+/// allocation each statement recorded (statements from before allocations were recorded
+/// fall back to the envelope's current allocation). Other members' private envelopes are
+/// counted in `hidden` instead of being listed.
+///
+/// # Errors
+/// Returns an error if:
+/// - `month` isn't a valid `YYYY-MM` month or is after the current month (`Error::Config`)
+/// - A database query fails
+pub async fn variance_report(
+    db: &DatabaseConnection,
+    month: Option<&str>,
+    viewer_id: &str,
+    today: NaiveDate,
+) -> Result<VarianceReport> {
+    let current = calendar::current_cycle(db, today).await?;
+    let current_month = statement::month_label(&current);
+    let month = month.map(statement::parse_month).transpose()?;
+    match month {
+        Some(month) if month > current_month => Err(Error::Config {
+            message: format!("{month} hasn't started yet"),
+        }),
+        Some(month) if month != current_month => closed_month_variance(db, month, viewer_id).await,
+        _ => current_month_variance(db, &current, current_month, viewer_id).await,
+    }
+}
+
+/// Variance of the running month, from envelope allocations and this month's spending.
+async fn current_month_variance(
+    db: &DatabaseConnection,
+    cycle: &BudgetCycle,
+    month: String,
+    viewer_id: &str,
+) -> Result<VarianceReport> {
+    let range = ReportRange {
+        from: cycle.start,
+        to: cycle.end,
+    };
+    let spending = get_spending_in_range(db, &range).await?;
+    let mut report = VarianceReport {
+        month,
+        period: Some((cycle.start, cycle.end)),
+        in_progress: true,
+        lines: Vec::new(),
+        hidden: 0,
+    };
+    for view in get_envelope_views(db, viewer_id).await? {
+        match view {
+            EnvelopeView::Visible(env) => report.lines.push(VarianceLine {
+                actual: spending.get(&env.id).copied().unwrap_or(0.0),
+                envelope_name: env.name,
+                allocation: env.allocation,
+            }),
+            EnvelopeView::Hidden(_) => report.hidden += 1,
+        }
+    }
+    Ok(report)
+}
+
+/// Variance of a closed month, from its monthly statements.
+async fn closed_month_variance(
+    db: &DatabaseConnection,
+    month: String,
+    viewer_id: &str,
+) -> Result<VarianceReport> {
+    let views = statement::get_statements(db, &month, viewer_id).await?;
+    let envelope_ids: Vec<i64> = views
+        .iter()
+        .filter_map(|view| match view {
+            statement::StatementView::Visible(s) if s.allocation.is_none() => Some(s.envelope_id),
+            _ => None,
+        })
+        .collect();
+    let current_allocations: HashMap<i64, f64> = crate::entities::Envelope::find()
+        .filter(envelope::Column::Id.is_in(envelope_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|env| (env.id, env.allocation))
+        .collect();
+
+    let mut report = VarianceReport {
+        month,
+        period: statement::StatementTotals::from_views(&views).period,
+        in_progress: false,
+        lines: Vec::new(),
+        hidden: 0,
+    };
+    for view in views {
+        match view {
+            statement::StatementView::Visible(s) => report.lines.push(VarianceLine {
+                allocation: s
+                    .allocation
+                    .or_else(|| current_allocations.get(&s.envelope_id).copied())
+                    .unwrap_or(0.0),
+                actual: s.total_spent,
+                envelope_name: s.envelope_name,
+            }),
+            statement::StatementView::Hidden { .. } => report.hidden += 1,
+        }
+    }
+    Ok(report)
+}
+
+/// Renders a variance report as a fixed-width table for a code block, with a totals row.
+#[must_use]
+pub fn format_variance_table(report: &VarianceReport) -> String {
+    let row = |line: &VarianceLine| {
+        vec![
+            line.envelope_name.clone(),
+            format!("{:.2}", line.allocation),
+            format!("{:.2}", line.actual),
+            format!("{:+.2}", line.variance()),
+            line.variance_percent()
+                .map_or_else(|| "—".to_string(), |percent| format!("{percent:+.0}%")),
+        ]
+    };
+
+    let mut table = Table::new(vec![
+        table::Column::left("Envelope").max_width(16),
+        table::Column::right("Budget"),
+        table::Column::right("Actual"),
+        table::Column::right("Var"),
+        table::Column::right("Var%"),
+    ]);
+    for line in &report.lines {
+        table.row(row(line));
+    }
+    table.separator();
+    table.row(row(&report.totals()));
+    table.render()
+}
+
+/// An envelope's balance as of the end of a day, and how it was worked out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceReconstruction {
+    /// Balance in dollars at the end of the day (household timezone)
+    pub balance: f64,
+    /// Statement month the balance was worked forward from, or `None` if it was worked
+    /// back from the current balance
+    pub statement_month: Option<String>,
+    /// Transactions applied between that starting point and the day
+    pub transaction_count: usize,
+}
+
+/// Recomputes an envelope's balance as of the end of `date` (household timezone).
+///
+/// Monthly updates reset or top up balances without recording a transaction, so a
+/// closed budget month is worked forward from the opening balance in its statement.
+/// A month that hasn't been closed yet is worked back from the current balance instead.
+///
+/// # Errors
+/// Returns an error if:
+/// - The envelope does not exist (`Error::EnvelopeNotFound`)
+/// - The date is in the future, or the month closed without a statement with balances,
+///   or its transactions were pruned by the retention policy (`Error::Config`)
+/// - Database queries fail
+pub async fn reconstruct_balance(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    date: NaiveDate,
+) -> Result<BalanceReconstruction> {
+    if date > calendar::today(db).await? {
+        return Err(Error::Config {
+            message: format!("{date} is in the future"),
+        });
+    }
+    let envelope = crate::core::envelope::get_envelope_by_id(db, envelope_id)
+        .await?
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    let cycle = BudgetCycle::containing(date, calendar::get_cycle_start_day(db).await?);
+    let month = statement::month_label(&cycle);
+    let (_, day_end) = calendar::utc_bounds(db, date, date).await?;
+    let (month_start, month_end) = calendar::utc_bounds(db, cycle.start, cycle.end).await?;
+
+    // The first statement from the month on tells whether the month has been closed
+    let first_statement = MonthlyStatement::find()
+        .filter(monthly_statement::Column::EnvelopeId.eq(envelope_id))
+        .filter(monthly_statement::Column::Month.gte(&month))
+        .order_by_asc(monthly_statement::Column::Month)
+        .one(db)
+        .await?;
+
+    let Some(statement) = first_statement else {
+        // Not closed yet: back out everything recorded since
+        let later = Transaction::find_active()
+            .filter(transaction::Column::EnvelopeId.eq(envelope_id))
+            .filter(transaction::Column::Timestamp.gte(day_end))
+            .all(db)
+            .await?;
+        return Ok(BalanceReconstruction {
+            balance: envelope.balance - later.iter().map(|tx| tx.amount).sum::<f64>(),
+            statement_month: None,
+            transaction_count: later.len(),
+        });
+    };
+
+    let open_balance = statement
+        .open_balance
+        .filter(|_| statement.month == month)
+        .ok_or_else(|| Error::Config {
+            message: format!(
+                "No balances were kept for '{}' in {month}, so its balance can't be reconstructed",
+                envelope.name
+            ),
+        })?;
+    let month_transactions = Transaction::find_active()
+        .filter(transaction::Column::EnvelopeId.eq(envelope_id))
+        .filter(transaction::Column::Timestamp.gte(month_start))
+        .filter(transaction::Column::Timestamp.lt(month_end))
+        .all(db)
+        .await?;
+    if i64::try_from(month_transactions.len()).unwrap_or(i64::MAX) < statement.transaction_count {
+        return Err(Error::Config {
+            message: format!(
+                "Transactions for {month} have been pruned; only the month's statement is left"
+            ),
+        });
+    }
+
+    let applied: Vec<&transaction::Model> = month_transactions
+        .iter()
+        .filter(|tx| tx.timestamp < day_end)
+        .collect();
+    Ok(BalanceReconstruction {
+        balance: open_balance + applied.iter().map(|tx| tx.amount).sum::<f64>(),
+        statement_month: Some(month),
+        transaction_count: applied.len(),
+    })
+}
+
+/// How a rollover envelope's balance built up.
+///
+/// `carried_over + funded + net_this_month` is the current balance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RolloverAccumulation {
+    /// The rollover envelope
+    pub envelope: envelope::Model,
+    /// Surplus carried over from earlier months into this one
+    pub carried_over: f64,
+    /// Allocation and contribution the monthly update added this month
+    pub funded: f64,
+    /// Net change from transactions since this month started
+    pub net_this_month: f64,
+    /// Balance each closed month ended with, by statement month, oldest first
+    pub history: Vec<(String, f64)>,
+}
+
+/// Splits the balance of every active rollover envelope into carried-over surplus and
+/// this month's funding.
+///
+/// The surplus comes from last month's statement. Without one (for example before the
+/// first monthly update), the opening balance less the current allocation and
+/// contribution is used instead.
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn rollover_accumulation(
+    db: &DatabaseConnection,
+    today: NaiveDate,
+) -> Result<Vec<RolloverAccumulation>> {
+    let envelopes: Vec<envelope::Model> = crate::core::envelope::get_all_active_envelopes(db)
+        .await?
+        .into_iter()
+        .filter(|env| env.rollover)
+        .collect();
+    if envelopes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids: Vec<i64> = envelopes.iter().map(|env| env.id).collect();
+
+    let cycle = calendar::current_cycle(db, today).await?;
+    let (start, _) = calendar::utc_bounds(db, cycle.start, cycle.end).await?;
+    let mut net: HashMap<i64, f64> = HashMap::new();
+    for tx in Transaction::find_active()
+        .filter(transaction::Column::EnvelopeId.is_in(ids.clone()))
+        .filter(transaction::Column::Timestamp.gte(start))
+        .all(db)
+        .await?
+    {
+        *net.entry(tx.envelope_id).or_default() += tx.amount;
+    }
+
+    let mut history: HashMap<i64, Vec<(String, f64)>> = HashMap::new();
+    for statement in MonthlyStatement::find()
+        .filter(monthly_statement::Column::EnvelopeId.is_in(ids))
+        .order_by_asc(monthly_statement::Column::Month)
+        .all(db)
+        .await?
+    {
+        if let Some(close) = statement.close_balance {
+            history
+                .entry(statement.envelope_id)
+                .or_default()
+                .push((statement.month, close));
+        }
+    }
+
+    let last_month = statement::month_label(&cycle.previous());
+    Ok(envelopes
+        .into_iter()
+        .map(|envelope| {
+            let net_this_month = net.get(&envelope.id).copied().unwrap_or_default();
+            let history = history.remove(&envelope.id).unwrap_or_default();
+            let opening = envelope.balance - net_this_month;
+            let carried_over = history
+                .iter()
+                .find(|(month, _)| *month == last_month)
+                .map_or(
+                    opening - envelope.allocation - envelope.contribution,
+                    |(_, close)| *close,
+                );
+            RolloverAccumulation {
+                carried_over,
+                funded: opening - carried_over,
+                net_this_month,
+                history,
+                envelope,
+            }
+        })
+        .collect())
+}
+
+/// One of a member's individual envelopes in their `/allowance` report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonalEnvelope {
+    /// The member's individual envelope
+    pub envelope: envelope::Model,
+    /// Spending so far in the current budget month, less refunds
+    pub spent_this_month: f64,
+    /// Spending in the previous budget month, less refunds
+    pub spent_last_month: f64,
+}
+
+impl PersonalEnvelope {
+    /// Allocation left unspent so far this month.
+    #[must_use]
+    pub fn saved_this_month(&self) -> f64 {
+        self.envelope.allocation - self.spent_this_month
+    }
+
+    /// Allocation left unspent last month.
+    #[must_use]
+    pub fn saved_last_month(&self) -> f64 {
+        self.envelope.allocation - self.spent_last_month
+    }
+
+    /// Change in savings from last month to this month so far.
+    #[must_use]
+    pub fn savings_change(&self) -> f64 {
+        self.saved_this_month() - self.saved_last_month()
+    }
+}
+
+/// A member's own allowances, shown by `/allowance`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonalReport {
+    /// The current budget month
+    pub this_month: ReportRange,
+    /// The previous budget month
+    pub last_month: ReportRange,
+    /// The member's individual envelopes, by name
+    pub envelopes: Vec<PersonalEnvelope>,
+}
+
+impl PersonalReport {
+    /// Sum of the envelopes' current balances.
+    #[must_use]
+    pub fn total_balance(&self) -> f64 {
+        self.envelopes.iter().map(|env| env.envelope.balance).sum()
+    }
+
+    /// Sum of the envelopes' savings so far this month.
+    #[must_use]
+    pub fn total_saved_this_month(&self) -> f64 {
+        self.envelopes
+            .iter()
+            .map(PersonalEnvelope::saved_this_month)
+            .sum()
+    }
+
+    /// Sum of the envelopes' savings last month.
+    #[must_use]
+    pub fn total_saved_last_month(&self) -> f64 {
+        self.envelopes
+            .iter()
+            .map(PersonalEnvelope::saved_last_month)
+            .sum()
+    }
+}
+
+/// Builds a member's personal allowance report as of `today`.
+///
+/// Only active individual envelopes owned by `user_id` are included; shared envelopes
+/// and other members' envelopes are filtered out in the query itself, so the report can
+/// never show someone else's figures.
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn personal_report(
+    db: &DatabaseConnection,
+    user_id: &str,
+    today: NaiveDate,
+) -> Result<PersonalReport> {
+    let cycle_start_day = calendar::get_cycle_start_day(db).await?;
+    let this_month = ReportRange::for_period(ReportPeriod::ThisMonth, today, cycle_start_day);
+    let last_month = ReportRange::for_period(ReportPeriod::LastMonth, today, cycle_start_day);
+
+    let envelopes = crate::entities::Envelope::find()
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(envelope::Column::IsIndividual.eq(true))
+        .filter(envelope::Column::UserId.eq(user_id))
+        .order_by_asc(envelope::Column::Name)
+        .all(db)
+        .await?;
+
+    let spent_this_month = get_spending_in_range(db, &this_month).await?;
+    let spent_last_month = get_spending_in_range(db, &last_month).await?;
+    let envelopes = envelopes
+        .into_iter()
+        .map(|env| PersonalEnvelope {
+            spent_this_month: spent_this_month.get(&env.id).copied().unwrap_or(0.0),
+            spent_last_month: spent_last_month.get(&env.id).copied().unwrap_or(0.0),
+            envelope: env,
+        })
+        .collect();
+
+    Ok(PersonalReport {
+        this_month,
+        last_month,
+        envelopes,
+    })
+}
+
+/// `system_state` key holding the household's pace thresholds.
+const PACE_THRESHOLDS_KEY: &str = "pace_thresholds";
+
+/// How an envelope's spending compares to the expected pace for the month so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaceStatus {
+    /// On track or under budget
+    OnTrack,
+    /// Slightly over pace
+    SlightlyOver,
+    /// Significantly over pace
+    WellOver,
+}
+
+impl PaceStatus {
+    /// Status indicator shown in reports.
+    #[must_use]
+    pub const fn emoji(self) -> &'static str {
+        match self {
+            Self::OnTrack => "🟢",
+            Self::SlightlyOver => "🟡",
+            Self::WellOver => "🔴",
+        }
+    }
+}
+
+/// Percentage points over the expected pace at which the report status changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaceThresholds {
+    /// Most spending may exceed the expected pace and still show 🟢
+    pub on_track: f64,
+    /// Most spending may exceed the expected pace and still show 🟡; beyond it is 🔴
+    pub slightly_over: f64,
+}
+
+impl Default for PaceThresholds {
+    fn default() -> Self {
+        Self {
+            on_track: 0.0,
+            slightly_over: 20.0,
+        }
+    }
+}
+
+impl PaceThresholds {
+    /// Thresholds that never warn: spending always shows as on track, for envelopes like
+    /// rent that are paid in one go rather than spent through the month. Stored as the
+    /// envelope's `pace_never_warn` flag.
+    pub const NEVER: Self = Self {
+        on_track: f64::INFINITY,
+        slightly_over: f64::INFINITY,
+    };
+
+    /// Whether these are the [`PaceThresholds::NEVER`] thresholds.
+    #[must_use]
+    pub const fn is_never(&self) -> bool {
+        self.on_track.is_infinite()
+    }
+
+    /// Creates thresholds, checking that they're finite and in increasing order.
+    ///
+    /// # Errors
+    /// Returns `Error::Config` if a threshold isn't a finite number or `slightly_over`
+    /// is below `on_track`.
+    pub fn new(on_track: f64, slightly_over: f64) -> Result<Self> {
+        if !on_track.is_finite() || !slightly_over.is_finite() {
+            return Err(Error::Config {
+                message: "Pace thresholds must be valid numbers".to_string(),
+            });
+        }
+        if slightly_over < on_track {
+            return Err(Error::Config {
+                message: format!(
+                    "The 🟡 threshold (+{slightly_over}%) can't be below the 🟢 threshold (+{on_track}%)"
+                ),
+            });
+        }
+        Ok(Self {
+            on_track,
+            slightly_over,
+        })
+    }
+
+    /// Classifies spending against the expected pace, both as percentages of the budget.
+    #[must_use]
+    pub fn classify(&self, spent_percent: f64, expected_percent: f64) -> PaceStatus {
+        if spent_percent <= expected_percent + self.on_track {
+            PaceStatus::OnTrack
+        } else if spent_percent <= expected_percent + self.slightly_over {
+            PaceStatus::SlightlyOver
+        } else {
+            PaceStatus::WellOver
+        }
+    }
+
+    /// Formats the thresholds the way [`PaceThresholds::parse`] reads them.
+    fn format(&self) -> String {
+        format!("{},{}", self.on_track, self.slightly_over)
+    }
+
+    /// Parses thresholds stored as `on_track,slightly_over`.
+    fn parse(value: &str) -> Option<Self> {
+        let (on_track, slightly_over) = value.split_once(',')?;
+        Self::new(
+            on_track.trim().parse().ok()?,
+            slightly_over.trim().parse().ok()?,
+        )
+        .ok()
+    }
+}
+
+/// The household's pace thresholds and any per-envelope overrides.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaceSettings {
+    /// Thresholds for envelopes without an override
+    pub household: PaceThresholds,
+    /// Overrides by envelope ID, from the envelopes' own pace columns
+    pub envelopes: HashMap<i64, PaceThresholds>,
+}
+
+impl PaceSettings {
+    /// Returns the thresholds that apply to an envelope.
+    #[must_use]
+    pub fn for_envelope(&self, envelope_id: i64) -> PaceThresholds {
+        self.envelopes
+            .get(&envelope_id)
+            .copied()
+            .unwrap_or(self.household)
+    }
+}
+
+/// Loads the pace thresholds, using the defaults (🟢 up to the expected pace, 🟡 up to 20
+/// points over) where none are saved.
+///
+/// The household's thresholds live in `system_state`; overrides are read from the
+/// active envelopes' `pace_on_track`, `pace_slightly_over`, and `pace_never_warn` columns.
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn get_pace_settings<C>(db: &C) -> Result<PaceSettings>
+where
+    C: ConnectionTrait,
+{
+    let household = state::get_value(db, PACE_THRESHOLDS_KEY)
+        .await?
+        .and_then(|value| PaceThresholds::parse(&value))
+        .unwrap_or_default();
+    let envelopes = envelope::Entity::find()
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .filter(
+            Condition::any()
+                .add(envelope::Column::PaceNeverWarn.eq(true))
+                .add(envelope::Column::PaceOnTrack.is_not_null()),
+        )
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|env| Some((env.id, envelope_pace(&env)?)))
+        .collect();
+    Ok(PaceSettings {
+        household,
+        envelopes,
+    })
+}
+
+/// An envelope's own pace thresholds, if it overrides the household's.
+#[must_use]
+pub fn envelope_pace(env: &envelope::Model) -> Option<PaceThresholds> {
+    if env.pace_never_warn {
+        return Some(PaceThresholds::NEVER);
+    }
+    PaceThresholds::new(env.pace_on_track?, env.pace_slightly_over?).ok()
+}
+
+/// Saves pace thresholds for the household, or for one envelope if `envelope_id` is
+/// given. `None` goes back to the defaults (or the household thresholds, for an
+/// envelope).
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_pace_thresholds<C>(
+    db: &C,
+    envelope_id: Option<i64>,
+    thresholds: Option<PaceThresholds>,
+) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let Some(envelope_id) = envelope_id else {
+        return match thresholds {
+            Some(thresholds) => {
+                state::set_value(db, PACE_THRESHOLDS_KEY, &thresholds.format()).await
+            }
+            None => state::delete_value(db, PACE_THRESHOLDS_KEY).await,
+        };
+    };
+
+    let never_warn = thresholds.is_some_and(|thresholds| thresholds.is_never());
+    let (on_track, slightly_over) = match thresholds {
+        Some(thresholds) if !never_warn => {
+            (Some(thresholds.on_track), Some(thresholds.slightly_over))
+        }
+        _ => (None, None),
+    };
+    envelope::Entity::update_many()
+        .col_expr(envelope::Column::PaceOnTrack, Expr::value(on_track))
+        .col_expr(
+            envelope::Column::PaceSlightlyOver,
+            Expr::value(slightly_over),
+        )
+        .col_expr(envelope::Column::PaceNeverWarn, Expr::value(never_warn))
+        .filter(envelope::Column::Id.eq(envelope_id))
+        .exec(db)
+        .await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(())
+}
+
+/// Spending locations listed in a [`ReportModel`].
+const REPORT_TOP_LOCATIONS: usize = 5;
+
+/// Every figure in the current month's report, as seen by one member.
+///
+/// [`build_report_model`] does all the computing; frontends only format the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportModel {
+    /// Date the report is for, in the household's timezone
+    pub today: NaiveDate,
+    /// The current budget month
+    pub cycle: BudgetCycle,
+    /// Percentage of the budget month elapsed by the end of `today`
+    pub expected_percent: f64,
+    /// Household totals
+    pub summary: HouseholdSummary,
+    /// The household's spending against the expected pace
+    pub status: PaceStatus,
+    /// One line per active envelope, in report order
+    pub envelopes: Vec<EnvelopeLine>,
+    /// Who owes whom
+    pub ious: Vec<crate::core::iou::NetBalance>,
+    /// The budget month's top spending locations
+    pub locations: Vec<crate::core::location::LocationSpending>,
+    /// How the household currency is written
+    pub currency: Currency,
+}
+
+/// One envelope's line in a [`ReportModel`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnvelopeLine {
+    /// The viewer may see the envelope's figures
+    Visible(Box<EnvelopeFigures>),
+    /// The envelope is private to another member
+    Hidden(HiddenEnvelope),
+}
+
+/// An envelope's figures for the current month's report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvelopeFigures {
+    /// The envelope
+    pub envelope: envelope::Model,
+    /// Balance as a percentage of the allocation
+    pub progress: f64,
+    /// Allocation spent so far (allocation minus balance)
+    pub spent: f64,
+    /// `spent` as a percentage of the allocation, or 0 with no allocation
+    pub spent_percent: f64,
+    /// Allocation expected to be spent by now
+    pub expected_spent: f64,
+    /// Spending against the expected pace, with the envelope's thresholds
+    pub status: PaceStatus,
+    /// Pending spends already taken out of the balance
+    pub pending: Option<f64>,
+    /// Interest paid by this month's update
+    pub interest: Option<f64>,
+    /// Week-by-week spending, for envelopes in weekly mode
+    pub weeks: Vec<(BudgetWeek, PaceStatus)>,
+}
+
+/// Computes the current month's report for `viewer_id`, hiding other members' private
+/// envelopes.
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn build_report_model(
+    db: &DatabaseConnection,
+    viewer_id: &str,
+    today: NaiveDate,
+) -> Result<ReportModel> {
+    let cycle_start_day = calendar::get_cycle_start_day(db).await?;
+    let cycle = BudgetCycle::containing(today, cycle_start_day);
+    // Days in month is small, precision loss negligible
+    #[allow(clippy::cast_precision_loss)]
+    let elapsed = cycle.day_number(today) as f64 / cycle.days() as f64;
+    let expected_percent = elapsed * 100.0;
+
+    let views = get_envelope_views(db, viewer_id).await?;
+    let pace = get_pace_settings(db).await?;
+    let summary = household_summary(db, viewer_id).await?;
+    let pending = crate::core::transaction::get_pending_totals(db).await?;
+    let interest = get_interest_in_range(db, &ReportRange::new(cycle.start, today)?).await?;
+    let weekly_envelopes: Vec<_> = views
+        .iter()
+        .filter_map(|view| match view {
+            EnvelopeView::Visible(env) if env.weekly => Some(env.as_ref().clone()),
+            _ => None,
+        })
+        .collect();
+    let mut weekly = get_weekly_spending(db, &weekly_envelopes, today, cycle_start_day).await?;
+
+    let envelopes = views
+        .into_iter()
+        .map(|view| match view {
+            EnvelopeView::Hidden(hidden) => EnvelopeLine::Hidden(hidden),
+            EnvelopeView::Visible(env) => {
+                let thresholds = pace.for_envelope(env.id);
+                let spent = env.allocation - env.balance;
+                let spent_percent = if env.allocation > 0.0 {
+                    (spent / env.allocation) * 100.0
+                } else {
+                    0.0
+                };
+                let weeks = weekly
+                    .remove(&env.id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|week| {
+                        let status =
+                            thresholds.classify(week.spent_percent(), week.expected_percent(today));
+                        (week, status)
+                    })
+                    .collect();
+                EnvelopeLine::Visible(Box::new(EnvelopeFigures {
+                    progress: calculate_progress(env.balance, env.allocation),
+                    spent,
+                    spent_percent,
+                    expected_spent: env.allocation * elapsed,
+                    status: thresholds.classify(spent_percent, expected_percent),
+                    pending: pending.get(&env.id).copied(),
+                    interest: interest.get(&env.id).copied(),
+                    weeks,
+                    envelope: *env,
+                }))
+            }
+        })
+        .collect();
+
+    let (start, end) = calendar::utc_bounds(db, cycle.start, cycle.end).await?;
+    Ok(ReportModel {
+        today,
+        cycle,
+        expected_percent,
+        summary,
+        status: pace
+            .household
+            .classify(summary.spent_percent(), expected_percent),
+        envelopes,
+        ious: crate::core::iou::get_net_balances(db).await?,
+        locations: crate::core::location::get_top_locations(
+            db,
+            start,
+            end,
+            viewer_id,
+            REPORT_TOP_LOCATIONS,
+        )
+        .await?,
+        currency: setup::get_currency_format(db).await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::test_utils::*;
+    use chrono::Utc;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_report_range_periods() -> Result<()> {
+        let today = date(2025, 1, 15);
+        assert_eq!(
+            ReportRange::for_period(ReportPeriod::ThisMonth, today, 1),
+            ReportRange::new(date(2025, 1, 1), today)?
+        );
+        assert_eq!(
+            ReportRange::for_period(ReportPeriod::LastMonth, today, 1),
+            ReportRange::new(date(2024, 12, 1), date(2024, 12, 31))?
+        );
+        let ytd = ReportRange::for_period(ReportPeriod::YearToDate, date(2025, 3, 10), 1);
+        assert_eq!(ytd.from, date(2025, 1, 1));
+        assert_eq!(ytd.days(), 69);
+
+        // Budget months running payday to payday
+        assert_eq!(
+            ReportRange::for_period(ReportPeriod::ThisMonth, today, 25),
+            ReportRange::new(date(2024, 12, 25), today)?
+        );
+        assert_eq!(
+            ReportRange::for_period(ReportPeriod::LastMonth, today, 25),
+            ReportRange::new(date(2024, 11, 25), date(2024, 12, 24))?
+        );
+
+        let parsed = ReportRange::parse(Some("2024-12-20"), None, today, 1)?;
+        assert_eq!(parsed, ReportRange::new(date(2024, 12, 20), today)?);
+        assert_eq!(parsed.elapsed_days(date(2024, 12, 25)), 6);
+        assert_eq!(parsed.elapsed_days(date(2025, 6, 1)), parsed.days());
+
+        assert!(ReportRange::parse(Some("12/20/2024"), None, today, 1).is_err());
+        assert!(ReportRange::parse(Some("2025-02-01"), Some("2025-01-01"), today, 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prorated_allocation() -> Result<()> {
+        let january = ReportRange::new(date(2025, 1, 1), date(2025, 1, 31))?;
+        assert!((prorated_allocation(310.0, &january, 1) - 310.0).abs() < 1e-9);
+
+        // Half of January and half of February
+        let span = ReportRange::new(date(2025, 1, 17), date(2025, 2, 14))?;
+        let expected = 310.0 * (15.0 / 31.0 + 14.0 / 28.0);
+        assert!((prorated_allocation(310.0, &span, 1) - expected).abs() < 1e-9);
+
+        // One whole budget month from payday to payday
+        let cycle = ReportRange::new(date(2025, 1, 25), date(2025, 2, 24))?;
+        assert!((prorated_allocation(310.0, &cycle, 25) - 310.0).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spending_in_range() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 100.0).await?;
+        let spend = create_test_transaction(&db, env.id, -30.0).await?;
+        crate::core::transaction::create_refund(
+            &db,
+            spend.id,
+            Some(10.0),
+            "test_user".to_string(),
+            None,
+        )
+        .await?;
+
+        let today = chrono::Utc::now().date_naive();
+        let this_month = ReportRange::for_period(ReportPeriod::ThisMonth, today, 1);
+        let spending = get_spending_in_range(&db, &this_month).await?;
+        assert_eq!(spending.get(&env.id).copied(), Some(20.0));
+
+        let last_month = ReportRange::for_period(ReportPeriod::LastMonth, today, 1);
+        assert!(get_spending_in_range(&db, &last_month).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_weekly_spending() -> Result<()> {
+        use sea_orm::Set;
+
+        let january = BudgetCycle::containing(date(2025, 1, 15), 1);
+        let weeks = cycle_weeks(&january);
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(
+            weeks[1],
+            ReportRange::new(date(2025, 1, 8), date(2025, 1, 14))?
+        );
+        assert_eq!(
+            weeks[4],
+            ReportRange::new(date(2025, 1, 29), date(2025, 1, 31))?
+        );
+
+        let (db, env) = setup_with_envelope().await?;
+        for (amount, day) in [(100.0, 1), (-20.0, 3), (-15.0, 9), (-5.0, 10), (50.0, 10)] {
+            let tx = create_test_transaction(&db, env.id, amount).await?;
+            let mut active_model: transaction::ActiveModel = tx.into();
+            active_model.timestamp = Set(date(2025, 1, day)
+                .and_hms_opt(12, 0, 0)
+                .unwrap_or_default()
+                .and_utc());
+            active_model.update(&db).await?;
+        }
+
+        let today = date(2025, 1, 10);
+        let spending = get_weekly_spending(&db, std::slice::from_ref(&env), today, 1).await?;
+        let breakdown = &spending[&env.id];
+        assert_eq!(breakdown.len(), 2);
+        assert_eq!(breakdown[0].spent, 20.0);
+        assert_eq!(breakdown[1].spent, 20.0);
+        assert!((breakdown[1].share - env.allocation * 7.0 / 31.0).abs() < 1e-9);
+        // Three of the second week's seven days have passed
+        assert!((breakdown[1].expected_percent(today) - 300.0 / 7.0).abs() < 1e-9);
+        assert_eq!(breakdown[0].expected_percent(today), 100.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_calculate_progress_full_allocation() {
+        // Full allocation remaining = 100%
+        let progress = calculate_progress(100.0, 100.0);
+        assert_eq!(progress, 100.0);
+    }
+
+    #[test]
+    fn test_calculate_progress_half_spent() {
+        // Half spent = 50% remaining
+        let progress = calculate_progress(50.0, 100.0);
+        assert_eq!(progress, 50.0);
+    }
+
+    #[test]
+    fn test_calculate_progress_fully_spent() {
+        // All spent = 0% remaining
+        let progress = calculate_progress(0.0, 100.0);
+        assert_eq!(progress, 0.0);
+    }
+
+    #[test]
+    fn test_calculate_progress_overspent() {
+        // Overspent = negative percentage
+        let progress = calculate_progress(-25.0, 100.0);
+        assert_eq!(progress, -25.0);
+    }
+
+    #[test]
+    fn test_calculate_progress_zero_allocation() {
+        // Zero allocation edge case
+        let progress = calculate_progress(50.0, 0.0);
+        assert_eq!(progress, 0.0);
+    }
+
+    #[test]
+    fn test_format_progress_bar_full() {
+        let bar = format_progress_bar(100.0, Some(10));
+        assert_eq!(bar, "[██████████]");
+    }
+
+    #[test]
+    fn test_format_progress_bar_half() {
+        let bar = format_progress_bar(50.0, Some(10));
+        assert_eq!(bar, "[█████░░░░░]");
+    }
+
+    #[test]
+    fn test_format_progress_bar_zero() {
+        let bar = format_progress_bar(0.0, Some(10));
+        assert_eq!(bar, "[░░░░░░░░░░]");
+    }
+
+    #[test]
+    fn test_format_progress_bar_overspent() {
+        // Overspending is clamped to 0% in the bar (percentage display handled separately)
+        let bar = format_progress_bar(-25.0, Some(10));
+        assert_eq!(bar, "[░░░░░░░░░░]");
+    }
+
+    #[test]
+    fn test_format_goal_progress() {
+        let dollars = Currency::default();
+        assert_eq!(
+            format_goal_progress(150.0, 600.0, dollars),
+            "$150.00 / $600.00 (25.0%)"
+        );
+        assert_eq!(
+            format_goal_progress(0.0, 100.0, dollars),
+            "$0.00 / $100.00 (0.0%)"
+        );
+        assert_eq!(
+            format_goal_progress(50.0, 100.0, Currency::from_code("EUR")),
+            "€50.00 / €100.00 (50.0%)"
+        );
+    }
+
+    #[test]
+    fn test_format_transaction_amount_positive() {
+        let dollars = Currency::default();
+        assert_eq!(format_transaction_amount(50.0, dollars), "+$50.00");
+        assert_eq!(format_transaction_amount(123.45, dollars), "+$123.45");
+    }
+
+    #[test]
+    fn test_format_transaction_amount_negative() {
+        let dollars = Currency::default();
+        assert_eq!(format_transaction_amount(-50.0, dollars), "-$50.00");
+        assert_eq!(format_transaction_amount(-123.45, dollars), "-$123.45");
+    }
+
+    #[test]
+    fn test_format_transaction_amount_zero() {
+        assert_eq!(
+            format_transaction_amount(0.0, Currency::default()),
+            "+$0.00"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_envelope_report_integration() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+
+        // Create some transactions (they automatically update balance)
+        create_test_transaction(&db, envelope.id, 100.0).await?; // Added funds (+100)
+        create_test_transaction(&db, envelope.id, -25.0).await?; // Spent (-25)
+        // Final balance should be: 0 + 100 - 25 = 75
+
+        // Generate report
+        let report = generate_envelope_report(&db, envelope.id, Some(5)).await?;
+
+        assert_eq!(report.envelope.id, envelope.id);
+        assert_eq!(report.balance, 75.0);
+        assert_eq!(report.allocation, 100.0);
+        assert_eq!(report.progress_percent, 75.0);
+        assert_eq!(report.amount_remaining, 75.0);
+        assert_eq!(report.amount_spent, 25.0);
+        assert_eq!(report.recent_transactions.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_envelope_report_no_transactions() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+
+        let report = generate_envelope_report(&db, envelope.id, None).await?;
+
+        assert_eq!(report.envelope.id, envelope.id);
+        assert_eq!(report.recent_transactions.len(), 0);
+        assert_eq!(report.balance, 0.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_envelope_report_transaction_limit() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+
+        // Create 15 transactions
+        for i in 0..15 {
+            create_custom_transaction(
+                &db,
+                envelope.id,
+                10.0,
+                &format!("Transaction {i}"),
+                "user1",
+                None,
+                TransactionType::AddFunds,
+            )
+            .await?;
+        }
+
+        // Request only 5 recent
+        let report = generate_envelope_report(&db, envelope.id, Some(5)).await?;
+
+        assert_eq!(report.recent_transactions.len(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_private_envelope_visibility() -> Result<()> {
+        let db = setup_test_db().await?;
+        let private = create_custom_envelope(
+            &db,
+            "game",
+            Some("owner".to_string()),
+            "quality_of_life",
+            80.0,
+            true,
+            false,
+        )
+        .await?;
+        let private = crate::core::envelope::set_private(&db, private.id, true).await?;
+        create_test_envelope(&db, "groceries").await?;
+
+        assert!(can_view(&private, "owner"));
+        assert!(!can_view(&private, "someone_else"));
+
+        // The owner sees everything
+        let views = get_envelope_views(&db, "owner").await?;
+        assert!(views.iter().all(|v| matches!(v, EnvelopeView::Visible(_))));
+
+        // Others see the private envelope as hidden, but shared envelopes normally
+        let views = get_envelope_views(&db, "someone_else").await?;
+        let hidden: Vec<_> = views
+            .iter()
+            .filter(|v| matches!(v, EnvelopeView::Hidden(_)))
+            .collect();
+        assert_eq!(hidden.len(), 1);
+        assert_eq!(hidden[0].name(), "game");
+
+        // Reports are refused for non-owners
+        assert!(
+            generate_envelope_report_for_viewer(&db, private.id, None, "owner")
+                .await
+                .is_ok()
+        );
+        let result =
+            generate_envelope_report_for_viewer(&db, private.id, None, "someone_else").await;
+        assert!(matches!(result, Err(Error::EnvelopeHidden { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_household_summary() -> Result<()> {
+        let db = setup_test_db().await?;
+        assert_eq!(
+            household_summary(&db, "owner").await?,
+            HouseholdSummary {
+                total_allocated: 0.0,
+                total_spent: 0.0,
+                total_remaining: 0.0,
+            }
+        );
+
+        let groceries = create_test_envelope(&db, "groceries").await?;
+        create_test_transaction(&db, groceries.id, 100.0).await?;
+        create_test_transaction(&db, groceries.id, -30.0).await?;
+        let private = create_custom_envelope(
+            &db,
+            "game",
+            Some("owner".to_string()),
+            "quality_of_life",
+            80.0,
+            true,
+            false,
+        )
+        .await?;
+        crate::core::envelope::set_private(&db, private.id, true).await?;
+        create_test_transaction(&db, private.id, 50.0).await?;
+
+        let summary = household_summary(&db, "owner").await?;
+        assert_eq!(summary.total_allocated, 180.0);
+        assert_eq!(summary.total_remaining, 120.0);
+        assert_eq!(summary.total_spent, 60.0);
+
+        // Other members' private envelopes are left out
+        let summary = household_summary(&db, "someone_else").await?;
+        assert_eq!(summary.total_allocated, 100.0);
+        assert_eq!(summary.total_spent, 30.0);
+        assert_eq!(summary.spent_percent(), 30.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_build_report_model() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let owner = Some("user1".to_string());
+        let private =
+            create_custom_envelope(&db, "Private", owner, "personal", 50.0, true, false).await?;
+        crate::core::envelope::set_private(&db, private.id, true).await?;
+        create_test_transaction(&db, env.id, 40.0).await?;
+        let today = calendar::today(&db).await?;
+
+        let model = build_report_model(&db, "someone_else", today).await?;
+        assert_eq!(model.envelopes.len(), 2);
+        let Some(EnvelopeLine::Visible(figures)) = model
+            .envelopes
+            .iter()
+            .find(|line| matches!(line, EnvelopeLine::Visible(f) if f.envelope.id == env.id))
+        else {
+            return Err(Error::Config {
+                message: "the shared envelope should be visible".to_string(),
+            });
+        };
+        assert_eq!(figures.spent, env.allocation - (env.balance + 40.0));
+        let expected =
+            PaceThresholds::default().classify(figures.spent_percent, model.expected_percent);
+        assert_eq!(figures.status, expected);
+        assert!(figures.weeks.is_empty());
+        assert!(
+            model
+                .envelopes
+                .iter()
+                .any(|line| matches!(line, EnvelopeLine::Hidden(h) if h.name == "Private"))
+        );
+        assert!(model.ious.is_empty());
+        assert!(model.expected_percent > 0.0 && model.expected_percent <= 100.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pace_classification() -> Result<()> {
+        let defaults = PaceThresholds::default();
+        assert_eq!(defaults.classify(40.0, 50.0), PaceStatus::OnTrack);
+        assert_eq!(defaults.classify(50.0, 50.0), PaceStatus::OnTrack);
+        assert_eq!(defaults.classify(70.0, 50.0), PaceStatus::SlightlyOver);
+        assert_eq!(defaults.classify(70.1, 50.0), PaceStatus::WellOver);
+
+        let lenient = PaceThresholds::new(10.0, 30.0)?;
+        assert_eq!(lenient.classify(60.0, 50.0), PaceStatus::OnTrack);
+        assert_eq!(lenient.classify(80.0, 50.0), PaceStatus::SlightlyOver);
+        assert_eq!(lenient.classify(81.0, 50.0), PaceStatus::WellOver);
+        assert_eq!(PaceStatus::SlightlyOver.emoji(), "🟡");
+
+        assert!(PaceThresholds::new(20.0, 10.0).is_err());
+        assert!(PaceThresholds::new(f64::NAN, 10.0).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_stale_envelopes() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let unused = create_test_envelope(&db, "Unused").await?;
+        let old = create_test_envelope(&db, "Old").await?;
+        let recent = create_test_transaction(&db, env.id, 10.0).await?;
+        let stale_tx = create_test_transaction(&db, old.id, 10.0).await?;
+        let mut backdated: transaction::ActiveModel = stale_tx.into();
+        backdated.timestamp = sea_orm::Set(recent.timestamp - chrono::Duration::days(120));
+        backdated.update(&db).await?;
+
+        let today = recent.timestamp.date_naive();
+        let stale = get_stale_envelopes(&db, 3, today).await?;
+        let names: Vec<&str> = stale.iter().map(|s| s.envelope.name.as_str()).collect();
+        assert_eq!(names, ["Unused", "Old"]);
+        assert_eq!(stale[0].envelope.id, unused.id);
+        assert!(stale[0].last_activity.is_none());
+        assert!(stale[1].last_activity.is_some());
+
+        // A longer window keeps the old envelope's spend in range
+        let stale = get_stale_envelopes(&db, 6, today).await?;
+        assert_eq!(stale.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_theme() -> Result<()> {
+        let db = setup_test_db().await?;
+        assert_eq!(get_theme(&db).await?, Theme::Classic);
+        set_theme(&db, Theme::Squares).await?;
+        assert_eq!(get_theme(&db).await?, Theme::Squares);
+
+        assert_eq!(Theme::from_key("Hearts"), Some(Theme::Hearts));
+        assert_eq!(Theme::from_key("neon"), None);
+        assert_eq!(Theme::Squares.progress_bar(50.0, Some(4)), "[🟩🟩⬜⬜]");
+        assert_eq!(
+            Theme::Classic.progress_bar(80.0, None),
+            format_progress_bar(80.0, None)
+        );
+        assert_eq!(Theme::Minimal.status(PaceStatus::WellOver), "✗");
+        assert_eq!(
+            Theme::Classic.status(PaceStatus::OnTrack),
+            PaceStatus::OnTrack.emoji()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pace_settings() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        assert_eq!(get_pace_settings(&db).await?, PaceSettings::default());
+
+        let household = PaceThresholds::new(5.0, 15.0)?;
+        let strict = PaceThresholds::new(0.0, 5.0)?;
+        set_pace_thresholds(&db, None, Some(household)).await?;
+        set_pace_thresholds(&db, Some(env.id), Some(strict)).await?;
+        let settings = get_pace_settings(&db).await?;
+        assert_eq!(settings.household, household);
+        assert_eq!(settings.for_envelope(env.id), strict);
+        assert_eq!(settings.for_envelope(env.id + 1), household);
+
+        // Rent-like envelopes can opt out of warnings entirely
+        set_pace_thresholds(&db, Some(env.id), Some(PaceThresholds::NEVER)).await?;
+        let never = get_pace_settings(&db).await?.for_envelope(env.id);
+        assert!(never.is_never());
+        assert_eq!(never.classify(100.0, 1.0), PaceStatus::OnTrack);
+
+        set_pace_thresholds(&db, Some(env.id), None).await?;
+        set_pace_thresholds(&db, None, None).await?;
+        assert_eq!(get_pace_settings(&db).await?, PaceSettings::default());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reconstruct_balance() -> Result<()> {
+        use sea_orm::Set;
+
+        let (db, env) = setup_with_envelope().await?;
+        let date = |m: u32, d: u32| NaiveDate::from_ymd_opt(2025, m, d).unwrap_or_default();
+        let record = |amount: f64, day: NaiveDate| {
+            let db = &db;
+            async move {
+                let tx = create_test_transaction(db, env.id, amount).await?;
+                let mut active_model: transaction::ActiveModel = tx.into();
+                active_model.timestamp =
+                    Set(day.and_hms_opt(12, 0, 0).unwrap_or_default().and_utc());
+                active_model.update(db).await?;
+                Ok::<_, Error>(())
+            }
+        };
+        record(100.0, date(1, 3)).await?;
+        record(-30.0, date(1, 10)).await?;
+
+        // Close January, then reset the balance the way the monthly update does
+        let january = BudgetCycle::containing(date(1, 10), 1);
+        let envelopes = crate::core::envelope::get_all_active_envelopes(&db).await?;
+        statement::record_statements(&db, &envelopes, &january, Utc::now()).await?;
+        let mut active_model: envelope::ActiveModel = env.clone().into();
+        active_model.balance = Set(100.0);
+        active_model.update(&db).await?;
+        record(-20.0, date(2, 5)).await?;
+
+        let mid_january = reconstruct_balance(&db, env.id, date(1, 5)).await?;
+        assert_eq!(mid_january.balance, 100.0);
+        assert_eq!(mid_january.statement_month.as_deref(), Some("2025-01"));
+        assert_eq!(mid_january.transaction_count, 1);
+        assert_eq!(
+            reconstruct_balance(&db, env.id, date(1, 31)).await?.balance,
+            70.0
+        );
+
+        // February hasn't been closed, so it is worked back from the current balance
+        let february = reconstruct_balance(&db, env.id, date(2, 4)).await?;
+        assert_eq!(february.balance, 100.0);
+        assert_eq!(february.statement_month, None);
+        assert_eq!(
+            reconstruct_balance(&db, env.id, date(2, 5)).await?.balance,
+            80.0
+        );
+
+        // December closed before statements were kept
+        let december = NaiveDate::from_ymd_opt(2024, 12, 15).unwrap_or_default();
+        assert!(matches!(
+            reconstruct_balance(&db, env.id, december).await,
+            Err(Error::Config { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_personal_report_only_shows_own_envelopes() -> Result<()> {
+        use sea_orm::Set;
+
+        let db = setup_test_db().await?;
+        let own = create_custom_envelope(
+            &db,
+            "Fun Money",
+            Some("user1".to_string()),
+            "quality_of_life",
+            100.0,
+            true,
+            false,
+        )
+        .await?;
+        let partner = create_custom_envelope(
+            &db,
+            "Fun Money",
+            Some("user2".to_string()),
+            "quality_of_life",
+            100.0,
+            true,
+            false,
+        )
+        .await?;
+        create_custom_envelope(&db, "Groceries", None, "necessary", 500.0, false, false).await?;
+
+        let date = |m: u32, d: u32| NaiveDate::from_ymd_opt(2025, m, d).unwrap_or_default();
+        let record = |env_id: i64, amount: f64, day: NaiveDate| {
+            let db = &db;
+            async move {
+                let tx = create_test_transaction(db, env_id, amount).await?;
+                let mut active_model: transaction::ActiveModel = tx.into();
+                active_model.timestamp =
+                    Set(day.and_hms_opt(12, 0, 0).unwrap_or_default().and_utc());
+                active_model.update(db).await?;
+                Ok::<_, Error>(())
+            }
+        };
+        record(own.id, 100.0, date(1, 2)).await?;
+        record(partner.id, 100.0, date(1, 2)).await?;
+        record(own.id, -60.0, date(1, 10)).await?;
+        record(own.id, -20.0, date(2, 5)).await?;
+        record(partner.id, -90.0, date(2, 6)).await?;
+
+        let report = personal_report(&db, "user1", date(2, 15)).await?;
+        assert_eq!(report.this_month.from, date(2, 1));
+        assert_eq!(report.envelopes.len(), 1);
+        let fun = &report.envelopes[0];
+        assert_eq!(fun.envelope.id, own.id);
+        assert_eq!(fun.spent_this_month, 20.0);
+        assert_eq!(fun.spent_last_month, 60.0);
+        assert_eq!(fun.savings_change(), 40.0);
+        assert_eq!(report.total_saved_this_month(), 80.0);
+
+        assert!(
+            personal_report(&db, "user3", date(2, 15))
+                .await?
+                .envelopes
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rollover_accumulation() -> Result<()> {
+        use sea_orm::Set;
+
+        let db = setup_test_db().await?;
+        let set_balance = |env: envelope::Model, balance: f64| {
+            let db = &db;
+            async move {
+                let mut active_model: envelope::ActiveModel = env.into();
+                active_model.balance = Set(balance);
+                active_model.update(db).await
+            }
+        };
+        let savings =
+            create_custom_envelope(&db, "savings", None, "necessary", 100.0, false, true).await?;
+        create_custom_envelope(&db, "groceries", None, "necessary", 100.0, false, false).await?;
+
+        // Close last month with $250 left, then roll it over the way the monthly update does
+        let today = calendar::today(&db).await?;
+        let last_month = calendar::current_cycle(&db, today).await?.previous();
+        let savings = set_balance(savings, 250.0).await?;
+        let envelopes = crate::core::envelope::get_all_active_envelopes(&db).await?;
+        statement::record_statements(&db, &envelopes, &last_month, Utc::now()).await?;
+        let savings = set_balance(savings, 350.0).await?;
+        create_test_transaction(&db, savings.id, -30.0).await?;
+
+        // Without a statement, the opening balance less the allocation is the surplus
+        let fund =
+            create_custom_envelope(&db, "fund", None, "necessary", 50.0, false, true).await?;
+        set_balance(fund, 80.0).await?;
+
+        let accumulation = rollover_accumulation(&db, today).await?;
+        // Sorted by name, without the non-rollover envelope
+        assert_eq!(accumulation.len(), 2);
+        let fund = &accumulation[0];
+        assert_eq!(fund.carried_over, 30.0);
+        assert_eq!(fund.funded, 50.0);
+        assert!(fund.history.is_empty());
+        let savings = &accumulation[1];
+        assert_eq!(savings.envelope.name, "savings");
+        assert_eq!(savings.carried_over, 250.0);
+        assert_eq!(savings.funded, 100.0);
+        assert_eq!(savings.net_this_month, -30.0);
+        assert_eq!(
+            savings.history,
+            vec![(statement::month_label(&last_month), 250.0)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_variance_report() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries =
+            create_custom_envelope(&db, "groceries", None, "necessary", 100.0, false, false)
+                .await?;
+        let private = create_custom_envelope(
+            &db,
+            "hobby",
+            Some("user2".to_string()),
+            "quality_of_life",
+            50.0,
+            true,
+            false,
+        )
+        .await?;
+        crate::core::envelope::set_private(&db, private.id, true).await?;
+        create_test_transaction(&db, groceries.id, 100.0).await?;
+        create_test_transaction(&db, groceries.id, -30.0).await?;
+
+        // The current month is aggregated live, without other members' private envelopes
+        let today = calendar::today(&db).await?;
+        let current = variance_report(&db, None, "user1", today).await?;
+        assert!(current.in_progress);
+        assert_eq!(current.hidden, 1);
+        assert_eq!(current.lines.len(), 1);
+        assert_eq!(current.lines[0].actual, 30.0);
+        assert_eq!(current.lines[0].variance(), 70.0);
+        assert_eq!(current.lines[0].variance_percent(), Some(70.0));
+        let table = format_variance_table(&current);
+        assert!(table.contains("+70.00"));
+        assert!(
+            table
+                .lines()
+                .last()
+                .unwrap_or_default()
+                .starts_with("Total")
+        );
+
+        // Closed months come from their statements, with the allocation they recorded
+        let last_month = calendar::current_cycle(&db, today).await?.previous();
+        let envelopes = crate::core::envelope::get_all_active_envelopes(&db).await?;
+        statement::record_statements(&db, &envelopes, &last_month, Utc::now()).await?;
+        let label = statement::month_label(&last_month);
+        let closed = variance_report(&db, Some(&label), "user2", today).await?;
+        assert!(!closed.in_progress);
+        assert_eq!(closed.hidden, 0);
+        assert_eq!(closed.lines.len(), 2);
+        assert_eq!(closed.totals().allocation, 150.0);
+        assert_eq!(closed.totals().actual, 0.0);
 
+        // Months that haven't started are rejected
+        let next_month = statement::month_label(&calendar::current_cycle(&db, today).await?.next());
+        assert!(matches!(
+            variance_report(&db, Some(&next_month), "user1", today).await,
+            Err(Error::Config { .. })
+        ));
         Ok(())
     }
 }
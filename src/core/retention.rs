@@ -0,0 +1,315 @@
+//! Transaction retention policy - Prunes old raw history after archiving it.
+//!
+//! Admins can choose to keep only the last N budget months of raw transactions (see
+//! [`set_retention_months`]); by default history is kept forever. The monthly update calls
+//! [`apply_retention`], which first aggregates every month about to be pruned into the
+//! `monthly_statements` table (months closed by a monthly update already have a statement)
+//! and then deletes the transactions. Envelope balances are unaffected, and links from
+//! IOUs, wishlist proposals, and scheduled spends to pruned transactions are cleared.
+
+use crate::{
+    core::{
+        calendar::{self, BudgetCycle},
+        state, statement,
+    },
+    entities::{
+        Envelope, Iou, MonthlyStatement, PendingPurchase, ScheduledTransaction, Transaction,
+        envelope, iou, monthly_statement, pending_purchase, scheduled_transaction, transaction,
+    },
+    errors::{Error, Result},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::{
+    QuerySelect, Set,
+    prelude::*,
+    sea_query::{Expr, Query},
+};
+use std::collections::{HashMap, HashSet};
+
+/// `system_state` key holding the number of months of raw transactions to keep.
+const RETENTION_MONTHS_KEY: &str = "transaction_retention_months";
+
+/// Longest retention that can be configured (ten years).
+pub const MAX_RETENTION_MONTHS: u32 = 120;
+
+/// What a [`apply_retention`] pass did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionResult {
+    /// Transactions before this day were pruned
+    pub cutoff: NaiveDate,
+    /// Statements created for pruned months that had none
+    pub aggregated_statements: usize,
+    /// Transactions deleted
+    pub pruned_transactions: u64,
+}
+
+/// Gets the number of closed budget months of raw transactions to keep, or `None` to
+/// keep them forever.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_retention_months<C>(db: &C) -> Result<Option<u32>>
+where
+    C: ConnectionTrait,
+{
+    Ok(state::get_value(db, RETENTION_MONTHS_KEY)
+        .await?
+        .and_then(|value| value.parse().ok())
+        .filter(|months| (1..=MAX_RETENTION_MONTHS).contains(months)))
+}
+
+/// Sets how many closed budget months of raw transactions to keep; `None` keeps them
+/// forever.
+///
+/// # Errors
+/// Returns an error if `months` is outside `1..=MAX_RETENTION_MONTHS` (`Error::Config`)
+/// or the database write fails.
+pub async fn set_retention_months<C>(db: &C, months: Option<u32>) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let Some(months) = months else {
+        return state::delete_value(db, RETENTION_MONTHS_KEY).await;
+    };
+    if !(1..=MAX_RETENTION_MONTHS).contains(&months) {
+        return Err(Error::Config {
+            message: format!("Retention must be between 1 and {MAX_RETENTION_MONTHS} months"),
+        });
+    }
+    state::set_value(db, RETENTION_MONTHS_KEY, &months.to_string()).await
+}
+
+/// Returns the first day whose transactions are kept: the start of the budget month
+/// `months` months before `current`.
+#[must_use]
+pub fn retention_cutoff(current: &BudgetCycle, months: u32) -> NaiveDate {
+    (0..months)
+        .fold(*current, |cycle, _| cycle.previous())
+        .start
+}
+
+/// Applies the configured retention policy, keeping the current budget month and the
+/// configured number of months before it.
+///
+/// Older months without a statement are aggregated into one first; their balances
+/// can't be reconstructed after monthly resets, so only spending and counts are stored.
+///
+/// # Returns
+/// `None` if history is kept forever
+///
+/// # Errors
+/// Returns an error if a database query, insert, update, or delete fails.
+pub async fn apply_retention<C>(
+    db: &C,
+    current: &BudgetCycle,
+    now: DateTime<Utc>,
+) -> Result<Option<RetentionResult>>
+where
+    C: ConnectionTrait,
+{
+    let Some(months) = get_retention_months(db).await? else {
+        return Ok(None);
+    };
+    let cutoff = retention_cutoff(current, months);
+    let cutoff_time = calendar::start_of_day(calendar::get_timezone(db).await?, cutoff);
+
+    let aggregated_statements = aggregate_before(db, cutoff_time, now).await?;
+
+    // Clear links to the transactions being pruned so foreign keys stay valid
+    let pruned_ids = Query::select()
+        .column(transaction::Column::Id)
+        .from(Transaction)
+        .and_where(Expr::col(transaction::Column::Timestamp).lt(cutoff_time))
+        .to_owned();
+    Iou::update_many()
+        .col_expr(iou::Column::TransactionId, Expr::value(Option::<i64>::None))
+        .filter(iou::Column::TransactionId.in_subquery(pruned_ids.clone()))
+        .exec(db)
+        .await?;
+    PendingPurchase::update_many()
+        .col_expr(
+            pending_purchase::Column::TransactionId,
+            Expr::value(Option::<i64>::None),
+        )
+        .filter(pending_purchase::Column::TransactionId.in_subquery(pruned_ids.clone()))
+        .exec(db)
+        .await?;
+    ScheduledTransaction::update_many()
+        .col_expr(
+            scheduled_transaction::Column::TransactionId,
+            Expr::value(Option::<i64>::None),
+        )
+        .filter(scheduled_transaction::Column::TransactionId.in_subquery(pruned_ids))
+        .exec(db)
+        .await?;
+
+    let pruned = Transaction::delete_many()
+        .filter(transaction::Column::Timestamp.lt(cutoff_time))
+        .exec(db)
+        .await?;
+
+    Ok(Some(RetentionResult {
+        cutoff,
+        aggregated_statements,
+        pruned_transactions: pruned.rows_affected,
+    }))
+}
+
+/// Creates statements for the envelope-months before `cutoff` that don't have one.
+async fn aggregate_before<C>(db: &C, cutoff: DateTime<Utc>, now: DateTime<Utc>) -> Result<usize>
+where
+    C: ConnectionTrait,
+{
+    let transactions = Transaction::find_active()
+        .filter(transaction::Column::Timestamp.lt(cutoff))
+        .all(db)
+        .await?;
+    if transactions.is_empty() {
+        return Ok(0);
+    }
+
+    let start_day = calendar::get_cycle_start_day(db).await?;
+    let tz = calendar::get_timezone(db).await?;
+    let existing: HashSet<(i64, String)> = MonthlyStatement::find()
+        .select_only()
+        .column(monthly_statement::Column::EnvelopeId)
+        .column(monthly_statement::Column::Month)
+        .into_tuple::<(i64, String)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+
+    // Spending and counts per envelope and budget month
+    let mut months: HashMap<(i64, String), (BudgetCycle, f64, i64)> = HashMap::new();
+    for tx in &transactions {
+        let cycle =
+            BudgetCycle::containing(tx.timestamp.with_timezone(&tz).date_naive(), start_day);
+        let key = (tx.envelope_id, statement::month_label(&cycle));
+        if existing.contains(&key) {
+            continue;
+        }
+        let entry = months.entry(key).or_insert((cycle, 0.0, 0));
+        if tx.amount < 0.0 || tx.transaction_type.is_refund() {
+            entry.1 -= tx.amount;
+        }
+        entry.2 += 1;
+    }
+
+    let envelope_ids: Vec<i64> = months.keys().map(|(id, _)| *id).collect();
+    let names: HashMap<i64, String> = Envelope::find()
+        .filter(envelope::Column::Id.is_in(envelope_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|env| (env.id, env.name))
+        .collect();
+
+    let created = months.len();
+    for ((envelope_id, month), (cycle, spent, count)) in months {
+        monthly_statement::ActiveModel {
+            envelope_id: Set(envelope_id),
+            envelope_name: Set(names
+                .get(&envelope_id)
+                .cloned()
+                .unwrap_or_else(|| format!("Envelope {envelope_id}"))),
+            month: Set(month),
+            period_start: Set(cycle.start),
+            period_end: Set(cycle.end),
+            open_balance: Set(None),
+            close_balance: Set(None),
+            total_spent: Set(spent),
+            transaction_count: Set(count),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{core::iou as iou_core, test_utils::*};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// Backdates a transaction to noon UTC on `day`.
+    async fn backdate(db: &DatabaseConnection, tx: transaction::Model, day: NaiveDate) {
+        let mut active_model: transaction::ActiveModel = tx.into();
+        active_model.timestamp = Set(day.and_hms_opt(12, 0, 0).unwrap().and_utc());
+        active_model.update(db).await.unwrap();
+    }
+
+    #[test]
+    fn test_retention_cutoff() {
+        let current = BudgetCycle::containing(date(2025, 3, 10), 1);
+        assert_eq!(retention_cutoff(&current, 1), date(2025, 2, 1));
+        assert_eq!(retention_cutoff(&current, 13), date(2024, 2, 1));
+
+        let payday = BudgetCycle::containing(date(2025, 3, 10), 25);
+        assert_eq!(retention_cutoff(&payday, 2), date(2024, 12, 25));
+    }
+
+    #[tokio::test]
+    async fn test_apply_retention() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let current = BudgetCycle::containing(date(2025, 3, 10), 1);
+
+        let old_deposit = create_test_transaction(&db, env.id, 100.0).await?;
+        backdate(&db, old_deposit, date(2024, 12, 3)).await;
+        let old_spend = create_test_transaction(&db, env.id, -40.0).await?;
+        let old_spend_id = old_spend.id;
+        backdate(&db, old_spend, date(2024, 12, 20)).await;
+        let kept = create_test_transaction(&db, env.id, -10.0).await?;
+        backdate(&db, kept, date(2025, 2, 5)).await;
+        iou_core::add_iou(
+            &db,
+            "creditor",
+            "debtor",
+            40.0,
+            "Dinner".to_string(),
+            Some(old_spend_id),
+        )
+        .await?;
+
+        // Kept forever by default
+        assert_eq!(apply_retention(&db, &current, Utc::now()).await?, None);
+
+        set_retention_months(&db, Some(1)).await?;
+        let result = apply_retention(&db, &current, Utc::now()).await?.unwrap();
+        assert_eq!(result.cutoff, date(2025, 2, 1));
+        assert_eq!(result.aggregated_statements, 1);
+        assert_eq!(result.pruned_transactions, 2);
+
+        let remaining = Transaction::find().all(&db).await?;
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].amount, -10.0);
+        assert!(
+            Iou::find()
+                .all(&db)
+                .await?
+                .iter()
+                .all(|iou| iou.transaction_id.is_none())
+        );
+
+        let statements = MonthlyStatement::find().all(&db).await?;
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].month, "2024-12");
+        assert_eq!(statements[0].total_spent, 40.0);
+        assert_eq!(statements[0].transaction_count, 2);
+        assert_eq!(statements[0].open_balance, None);
+
+        assert!(set_retention_months(&db, Some(0)).await.is_err());
+        set_retention_months(&db, None).await?;
+        assert_eq!(get_retention_months(&db).await?, None);
+        Ok(())
+    }
+}
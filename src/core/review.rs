@@ -0,0 +1,196 @@
+//! Monthly review business logic - Suggested allocation changes after a month closes.
+//!
+//! Once the monthly update has archived the closed month's statements, [`suggest_adjustments`]
+//! looks for envelopes whose allocation didn't fit the month: ones that ended negative
+//! and ones that spent less than [`UNDERSPENT_RATIO`] of their allocation. Each gets a
+//! suggested allocation for the coming month, which [`apply_suggestion`] writes through
+//! [`envelope::set_allocation`].
+
+use crate::{
+    core::envelope,
+    entities::{Envelope, MonthlyStatement, envelope as envelope_entity, monthly_statement},
+    errors::Result,
+};
+use sea_orm::{QueryOrder, prelude::*};
+use std::collections::HashMap;
+
+/// Envelopes that spent less than this share of their allocation are under-spent.
+pub const UNDERSPENT_RATIO: f64 = 0.75;
+
+/// Why an envelope's allocation is up for review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewReason {
+    /// The month closed with a negative balance
+    Overspent,
+    /// Less than [`UNDERSPENT_RATIO`] of the allocation was spent
+    Underspent,
+}
+
+/// A suggested allocation change for one envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllocationSuggestion {
+    /// Envelope to adjust
+    pub envelope_id: i64,
+    /// Envelope name
+    pub envelope_name: String,
+    /// Why the envelope is up for review
+    pub reason: ReviewReason,
+    /// Current monthly allocation
+    pub allocation: f64,
+    /// Spending during the closed month, less refunds
+    pub total_spent: f64,
+    /// Balance the closed month ended with
+    pub close_balance: f64,
+    /// Suggested allocation for the coming month, in whole dollars
+    pub suggested: f64,
+}
+
+impl AllocationSuggestion {
+    /// Returns the suggested change (positive to raise the allocation).
+    #[must_use]
+    pub fn delta(&self) -> f64 {
+        self.suggested - self.allocation
+    }
+}
+
+/// Suggests an allocation change for an envelope from its closed month's statement.
+///
+/// Overspent envelopes are raised by the overspend; under-spent ones are lowered to what
+/// was actually spent. Suggestions are rounded outward to whole dollars. Returns `None`
+/// if the allocation fit the month, or the statement has no closing balance.
+#[must_use]
+pub fn suggest(
+    env: &envelope_entity::Model,
+    statement: &monthly_statement::Model,
+) -> Option<AllocationSuggestion> {
+    let close_balance = statement.close_balance?;
+    let (reason, suggested) = if close_balance < 0.0 {
+        (
+            ReviewReason::Overspent,
+            (env.allocation - close_balance).ceil(),
+        )
+    } else if env.allocation > 0.0 && statement.total_spent < env.allocation * UNDERSPENT_RATIO {
+        (
+            ReviewReason::Underspent,
+            statement.total_spent.max(0.0).ceil(),
+        )
+    } else {
+        return None;
+    };
+
+    Some(AllocationSuggestion {
+        envelope_id: env.id,
+        envelope_name: env.name.clone(),
+        reason,
+        allocation: env.allocation,
+        total_spent: statement.total_spent,
+        close_balance,
+        suggested,
+    })
+}
+
+/// Suggests allocation changes from the statements of `month` (`YYYY-MM`), by
+/// envelope name.
+///
+/// Envelopes deleted since the month closed are left out.
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn suggest_adjustments(
+    db: &DatabaseConnection,
+    month: &str,
+) -> Result<Vec<AllocationSuggestion>> {
+    let statements = MonthlyStatement::find()
+        .filter(monthly_statement::Column::Month.eq(month))
+        .order_by_asc(monthly_statement::Column::EnvelopeName)
+        .all(db)
+        .await?;
+
+    let envelope_ids: Vec<i64> = statements.iter().map(|s| s.envelope_id).collect();
+    let envelopes: HashMap<i64, envelope_entity::Model> = Envelope::find()
+        .filter(envelope_entity::Column::Id.is_in(envelope_ids))
+        .filter(envelope_entity::Column::IsDeleted.eq(false))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|env| (env.id, env))
+        .collect();
+
+    Ok(statements
+        .iter()
+        .filter_map(|statement| suggest(envelopes.get(&statement.envelope_id)?, statement))
+        .collect())
+}
+
+/// Sets the envelope's allocation to the suggested amount.
+///
+/// # Errors
+/// Returns an error if the envelope no longer exists or the update fails.
+pub async fn apply_suggestion(
+    db: &DatabaseConnection,
+    suggestion: &AllocationSuggestion,
+) -> Result<envelope_entity::Model> {
+    envelope::set_allocation(db, suggestion.envelope_id, suggestion.suggested).await
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{
+        core::{calendar::BudgetCycle, statement},
+        test_utils::*,
+    };
+    use chrono::Utc;
+    use sea_orm::Set;
+
+    #[tokio::test]
+    async fn test_suggest_adjustments() -> Result<()> {
+        let db = setup_test_db().await?;
+        let groceries =
+            create_custom_envelope(&db, "groceries", None, "food", 300.0, false, false).await?;
+        let fun = create_custom_envelope(&db, "fun", None, "misc", 100.0, false, false).await?;
+        let gas = create_custom_envelope(&db, "gas", None, "car", 80.0, false, false).await?;
+
+        let last_month = BudgetCycle::containing(Utc::now().date_naive(), 1).previous();
+        let envelopes = Envelope::find().all(&db).await?;
+        statement::record_statements(&db, &envelopes, &last_month, Utc::now()).await?;
+
+        // Groceries overspent by $42.50, fun spent $20 of $100, gas spent about its allocation
+        for (envelope_id, close, spent) in [
+            (groceries.id, -42.5, 342.5),
+            (fun.id, 80.0, 20.0),
+            (gas.id, 5.0, 75.0),
+        ] {
+            let statement = MonthlyStatement::find()
+                .filter(monthly_statement::Column::EnvelopeId.eq(envelope_id))
+                .one(&db)
+                .await?
+                .ok_or_else(|| crate::errors::Error::Config {
+                    message: "missing statement".to_string(),
+                })?;
+            let mut active: monthly_statement::ActiveModel = statement.into();
+            active.close_balance = Set(Some(close));
+            active.total_spent = Set(spent);
+            active.update(&db).await?;
+        }
+
+        let suggestions = suggest_adjustments(&db, &statement::month_label(&last_month)).await?;
+        assert_eq!(suggestions.len(), 2);
+
+        let fun_suggestion = &suggestions[0];
+        assert_eq!(fun_suggestion.envelope_name, "fun");
+        assert_eq!(fun_suggestion.reason, ReviewReason::Underspent);
+        assert_eq!(fun_suggestion.suggested, 20.0);
+        assert_eq!(fun_suggestion.delta(), -80.0);
+
+        let groceries_suggestion = &suggestions[1];
+        assert_eq!(groceries_suggestion.reason, ReviewReason::Overspent);
+        assert_eq!(groceries_suggestion.suggested, 343.0);
+
+        let updated = apply_suggestion(&db, groceries_suggestion).await?;
+        assert_eq!(updated.allocation, 343.0);
+
+        Ok(())
+    }
+}
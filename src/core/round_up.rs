@@ -0,0 +1,351 @@
+//! Round-ups - Saving the change from every spend.
+//!
+//! Round-ups are opt-in: once a savings envelope is chosen with
+//! [`set_round_up_envelope`], every spend is rounded up to the next whole dollar and the
+//! difference moves from the spend's envelope to the savings envelope, in the same
+//! database transaction that records the spend. The move is a pair of `round_up`
+//! transactions linked to the spend through `round_up_of`, deleted with the spend and
+//! redone when a pending spend clears at a different amount. The change is saved rather
+//! than spent, so daily limits and category caps don't count it. The savings envelope is
+//! household-wide, so the change from a private envelope is saved without naming it or
+//! the spend.
+//!
+//! A round-up is skipped, without failing the spend, when the change would overdraw the
+//! spend's envelope or take it below its minimum balance. The monthly update reports the
+//! total saved over the month it closes (see [`get_round_up_total`]).
+
+use crate::{
+    core::{calendar, envelope::update_envelope_balance_atomic, state},
+    entities::{Envelope, Transaction, TransactionType, envelope, transaction},
+    errors::Result,
+};
+use chrono::NaiveDate;
+use sea_orm::{QuerySelect, Set, prelude::*};
+
+/// `system_state` key holding the ID of the envelope round-ups are saved to.
+const ROUND_UP_ENVELOPE_KEY: &str = "round_up_envelope";
+
+/// Loads the envelope round-ups are saved to, or `None` if round-ups are off or the
+/// envelope was deleted.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_round_up_envelope<C>(db: &C) -> Result<Option<envelope::Model>>
+where
+    C: ConnectionTrait,
+{
+    let Some(id) = state::get_value(db, ROUND_UP_ENVELOPE_KEY)
+        .await?
+        .and_then(|value| value.parse::<i64>().ok())
+    else {
+        return Ok(None);
+    };
+    Ok(Envelope::find_by_id(id)
+        .one(db)
+        .await?
+        .filter(|env| !env.is_deleted))
+}
+
+/// Turns round-ups on, saving to the given envelope, or off with `None`.
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_round_up_envelope<C>(db: &C, envelope_id: Option<i64>) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    match envelope_id {
+        Some(id) => state::set_value(db, ROUND_UP_ENVELOPE_KEY, &id.to_string()).await,
+        None => state::delete_value(db, ROUND_UP_ENVELOPE_KEY).await,
+    }
+}
+
+/// Change left when `amount` is rounded up to the next whole dollar, e.g. `0.53` for a
+/// `4.47` spend and `0.0` for an even `5.00`.
+#[must_use]
+pub fn round_up_amount(amount: f64) -> f64 {
+    // Whole cents, so 4.10 isn't rounded up over a floating-point error
+    #[allow(clippy::cast_possible_truncation)] // Spends are far below i64::MAX cents
+    let cents = (amount.abs() * 100.0).round() as i64;
+    let change = (100 - cents % 100) % 100;
+    // Cast is safe: change is below 100
+    #[allow(clippy::cast_precision_loss)]
+    let change = change as f64 / 100.0;
+    change
+}
+
+/// Rounds up a spend that was just recorded, if round-ups are on.
+///
+/// Called by [`crate::core::transaction`] inside the database transaction that inserted
+/// `spend`. Returns the two round-up transactions (out of the spend's envelope, then into
+/// savings), or nothing if the spend isn't rounded up; journaling them is left to the
+/// caller.
+///
+/// # Errors
+/// Returns an error if a database query or update fails.
+pub(crate) async fn record_round_up<C>(
+    db: &C,
+    spend: &transaction::Model,
+) -> Result<Vec<transaction::Model>>
+where
+    C: ConnectionTrait,
+{
+    if spend.transaction_type != TransactionType::Spend || spend.amount >= 0.0 {
+        return Ok(Vec::new());
+    }
+    let change = round_up_amount(spend.amount);
+    if change <= 0.0 {
+        return Ok(Vec::new());
+    }
+    let Some(savings) = get_round_up_envelope(db).await? else {
+        return Ok(Vec::new());
+    };
+    if savings.id == spend.envelope_id {
+        return Ok(Vec::new());
+    }
+    let Some(source) = Envelope::find_by_id(spend.envelope_id).one(db).await? else {
+        return Ok(Vec::new());
+    };
+    let floor = source.min_balance.unwrap_or(0.0).max(0.0);
+    if source.balance - change < floor {
+        return Ok(Vec::new());
+    }
+
+    let saved = if source.is_private {
+        "Round-up from a private envelope".to_string()
+    } else {
+        format!("Round-up from {}: {}", source.name, spend.description)
+    };
+    let legs = [
+        (source.id, -change, format!("Round-up to {}", savings.name)),
+        (savings.id, change, saved),
+    ];
+    let mut created = Vec::with_capacity(legs.len());
+    for (envelope_id, amount, description) in legs {
+        let leg = transaction::ActiveModel {
+            envelope_id: Set(envelope_id),
+            amount: Set(amount),
+            description: Set(description),
+            timestamp: Set(spend.timestamp),
+            user_id: Set(spend.user_id.clone()),
+            transaction_type: Set(TransactionType::RoundUp),
+            round_up_of: Set(Some(spend.id)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        update_envelope_balance_atomic(db, envelope_id, amount).await?;
+        created.push(leg);
+    }
+    Ok(created)
+}
+
+/// Deletes a spend's round-up legs and reverses them.
+///
+/// Called inside the database transaction that deletes or changes the spend. Returns the
+/// deleted legs; journaling them is left to the caller.
+///
+/// # Errors
+/// Returns an error if a database query or update fails.
+pub(crate) async fn remove_round_up<C>(db: &C, spend_id: i64) -> Result<Vec<transaction::Model>>
+where
+    C: ConnectionTrait,
+{
+    let legs = Transaction::find_active()
+        .filter(transaction::Column::RoundUpOf.eq(spend_id))
+        .all(db)
+        .await?;
+    for leg in &legs {
+        leg.clone().delete(db).await?;
+        update_envelope_balance_atomic(db, leg.envelope_id, -leg.amount).await?;
+    }
+    Ok(legs)
+}
+
+/// Returns the change a spend was rounded up by, or `None` if it wasn't.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_round_up<C>(db: &C, spend_id: i64) -> Result<Option<f64>>
+where
+    C: ConnectionTrait,
+{
+    Ok(Transaction::find_active()
+        .filter(transaction::Column::RoundUpOf.eq(spend_id))
+        .filter(transaction::Column::Amount.gt(0.0))
+        .one(db)
+        .await?
+        .map(|leg| leg.amount))
+}
+
+/// Totals the change saved by round-ups on the household days `from..=to`.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_round_up_total<C>(db: &C, from: NaiveDate, to: NaiveDate) -> Result<f64>
+where
+    C: ConnectionTrait,
+{
+    let (start, end) = calendar::utc_bounds(db, from, to).await?;
+    let total: Option<Option<f64>> = Transaction::find_active()
+        .select_only()
+        .column_as(transaction::Column::Amount.sum(), "total")
+        .filter(transaction::Column::TransactionType.eq(TransactionType::RoundUp))
+        .filter(transaction::Column::Amount.gt(0.0))
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .into_tuple()
+        .one(db)
+        .await?;
+    Ok(total.flatten().unwrap_or(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{envelope as envelope_core, transaction as transaction_core},
+        test_utils::*,
+    };
+
+    #[test]
+    fn test_round_up_amount() {
+        assert!((round_up_amount(-4.47) - 0.53).abs() < 1e-9);
+        assert!((round_up_amount(-4.10) - 0.90).abs() < 1e-9);
+        assert!((round_up_amount(-0.01) - 0.99).abs() < 1e-9);
+        assert!(round_up_amount(-5.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_spends_round_up_to_savings() -> Result<()> {
+        let (db, groceries) = setup_with_envelope().await?;
+        let savings = create_test_envelope(&db, "savings").await?;
+        create_test_transaction(&db, groceries.id, 100.0).await?;
+        create_test_transaction(&db, savings.id, 100.0).await?;
+
+        // Off by default
+        let spend = create_test_transaction(&db, groceries.id, -4.47).await?;
+        assert_eq!(get_round_up(&db, spend.id).await?, None);
+
+        set_round_up_envelope(&db, Some(savings.id)).await?;
+        let spend = create_test_transaction(&db, groceries.id, -10.25).await?;
+        assert_eq!(get_round_up(&db, spend.id).await?, Some(0.75));
+        // Even amounts and spends from savings itself aren't rounded up
+        let even = create_test_transaction(&db, groceries.id, -5.0).await?;
+        assert_eq!(get_round_up(&db, even.id).await?, None);
+        let own = create_test_transaction(&db, savings.id, -1.5).await?;
+        assert_eq!(get_round_up(&db, own.id).await?, None);
+
+        let groceries = envelope_core::get_envelope_by_id(&db, groceries.id)
+            .await?
+            .unwrap_or(groceries);
+        let savings = envelope_core::get_envelope_by_id(&db, savings.id)
+            .await?
+            .unwrap_or(savings);
+        assert!((groceries.balance - (100.0 - 4.47 - 10.25 - 0.75 - 5.0)).abs() < 1e-9);
+        assert!((savings.balance - (100.0 + 0.75 - 1.5)).abs() < 1e-9);
+
+        let legs = transaction_core::get_transactions_for_envelope(&db, groceries.id).await?;
+        assert!(
+            legs.iter()
+                .any(|tx| tx.transaction_type == TransactionType::RoundUp
+                    && tx.round_up_of == Some(spend.id))
+        );
+
+        let today = calendar::today(&db).await?;
+        assert!((get_round_up_total(&db, today, today).await? - 0.75).abs() < 1e-9);
+
+        // Change that would overdraw the envelope is skipped, not the spend
+        let last = create_test_transaction(&db, groceries.id, -79.3).await?;
+        assert_eq!(get_round_up(&db, last.id).await?, None);
+
+        set_round_up_envelope(&db, None).await?;
+        assert!(get_round_up_envelope(&db).await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_up_legs_follow_their_spend() -> Result<()> {
+        let (db, groceries) = setup_with_envelope().await?;
+        let savings = create_test_envelope(&db, "savings").await?;
+        create_test_transaction(&db, groceries.id, 100.0).await?;
+        set_round_up_envelope(&db, Some(savings.id)).await?;
+        let spend = create_test_transaction(&db, groceries.id, -10.25).await?;
+
+        // The change isn't spending: a daily limit sees only the spend itself
+        let limited = envelope_core::set_daily_limit(&db, groceries.id, Some(10.25)).await?;
+        let now = chrono::Utc::now();
+        transaction_core::check_daily_limits(&db, &limited, "test_user", 0.0, now).await?;
+
+        // Deleting the spend deletes and reverses both legs
+        transaction_core::delete_transaction(&db, spend.id).await?;
+        assert_eq!(get_round_up(&db, spend.id).await?, None);
+        let groceries = envelope_core::get_envelope_by_id(&db, groceries.id)
+            .await?
+            .unwrap_or(groceries);
+        let savings = envelope_core::get_envelope_by_id(&db, savings.id)
+            .await?
+            .unwrap_or(savings);
+        assert!((groceries.balance - 100.0).abs() < 1e-9);
+        assert!(savings.balance.abs() < 1e-9);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_up_follows_the_posted_amount() -> Result<()> {
+        let (db, groceries) = setup_with_envelope().await?;
+        let savings = create_test_envelope(&db, "savings").await?;
+        create_test_transaction(&db, groceries.id, 100.0).await?;
+        set_round_up_envelope(&db, Some(savings.id)).await?;
+        let pending = transaction_core::create_pending_spend(
+            &db,
+            groceries.id,
+            -20.25,
+            None,
+            "Dinner".to_string(),
+            "test_user".to_string(),
+            None,
+            transaction_core::Clearance::default(),
+        )
+        .await?;
+        assert_eq!(get_round_up(&db, pending.id).await?, Some(0.75));
+
+        // A tip changes the change saved
+        transaction_core::clear_transaction(&db, pending.id, Some(24.6)).await?;
+        assert_eq!(get_round_up(&db, pending.id).await?, Some(0.4));
+        let groceries = envelope_core::get_envelope_by_id(&db, groceries.id)
+            .await?
+            .unwrap_or(groceries);
+        let savings = envelope_core::get_envelope_by_id(&db, savings.id)
+            .await?
+            .unwrap_or(savings);
+        assert!((groceries.balance - (100.0 - 24.6 - 0.4)).abs() < 1e-9);
+        assert!((savings.balance - 0.4).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_private_round_ups_stay_private() -> Result<()> {
+        let db = setup_test_db().await?;
+        let savings = create_test_envelope(&db, "savings").await?;
+        let diary = create_custom_envelope(
+            &db,
+            "diary",
+            Some("alice".to_string()),
+            "personal",
+            50.0,
+            true,
+            false,
+        )
+        .await?;
+        envelope_core::set_private(&db, diary.id, true).await?;
+        create_test_transaction(&db, diary.id, 50.0).await?;
+        set_round_up_envelope(&db, Some(savings.id)).await?;
+        create_test_transaction(&db, diary.id, -4.47).await?;
+
+        let legs = transaction_core::get_transactions_for_envelope(&db, savings.id).await?;
+        assert_eq!(legs.len(), 1);
+        assert_eq!(legs[0].description, "Round-up from a private envelope");
+        Ok(())
+    }
+}
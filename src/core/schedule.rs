@@ -0,0 +1,338 @@
+//! Scheduled transaction business logic - One-off spends that run on a future date.
+//!
+//! A member registers a spend for a date with [`schedule_spend`] (e.g. rent on the 1st).
+//! The background scheduler calls [`run_due`] regularly; every pending spend whose date
+//! has arrived is executed as the member who scheduled it. A failed attempt (e.g.
+//! insufficient funds) is retried on later runs, and after [`MAX_ATTEMPTS`] failures the
+//! spend is marked failed. Each spend uses its schedule entry as the idempotency key, so
+//! a retry never spends twice.
+
+use crate::{
+    core::{setup, transaction},
+    entities::{
+        ScheduledTransaction, TransactionType, scheduled_transaction,
+        transaction as transaction_entity,
+    },
+    errors::{Error, Result},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::{QueryOrder, Set, prelude::*};
+
+/// Status of a spend waiting for its date.
+pub const STATUS_PENDING: &str = "pending";
+/// Status of a spend that ran.
+pub const STATUS_EXECUTED: &str = "executed";
+/// Status of a spend that kept failing and was given up on.
+pub const STATUS_FAILED: &str = "failed";
+/// Status of a spend cancelled before it ran.
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+/// Failed attempts after which a scheduled spend is marked failed.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// What happened to a scheduled spend during a [`run_due`] pass.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunOutcome {
+    /// The spend was executed
+    Executed(Box<transaction_entity::Model>),
+    /// The attempt failed and will be retried on a later run
+    Retrying {
+        /// Why the attempt failed
+        error: String,
+    },
+    /// The attempt failed and no retries are left
+    Failed {
+        /// Why the last attempt failed
+        error: String,
+    },
+}
+
+/// A scheduled spend processed by [`run_due`] and its outcome.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledRun {
+    /// The scheduled spend, as updated by the run
+    pub scheduled: scheduled_transaction::Model,
+    /// What happened
+    pub outcome: RunOutcome,
+}
+
+/// Schedules a spend from an envelope to run on `run_on`.
+///
+/// `channel_id` is where the confirmation is posted when the spend runs.
+///
+/// # Errors
+/// Returns an error if:
+/// - The amount is not positive and finite (`Error::InvalidAmount`)
+/// - `run_on` is before `today` (`Error::Config`)
+/// - The database insert fails
+#[allow(clippy::too_many_arguments)] // Mirrors the /schedule spend options plus the current date
+pub async fn schedule_spend(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    user_id: &str,
+    amount: f64,
+    description: String,
+    run_on: NaiveDate,
+    channel_id: Option<String>,
+    today: NaiveDate,
+) -> Result<scheduled_transaction::Model> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(Error::InvalidAmount { amount });
+    }
+    if run_on < today {
+        return Err(Error::Config {
+            message: format!("Can't schedule a spend in the past ({run_on})"),
+        });
+    }
+
+    let record = scheduled_transaction::ActiveModel {
+        envelope_id: Set(envelope_id),
+        user_id: Set(user_id.to_string()),
+        amount: Set(amount),
+        description: Set(description),
+        run_on: Set(run_on),
+        channel_id: Set(channel_id),
+        status: Set(STATUS_PENDING.to_string()),
+        attempts: Set(0),
+        last_error: Set(None),
+        transaction_id: Set(None),
+        created_at: Set(Utc::now()),
+        finished_at: Set(None),
+        ..Default::default()
+    };
+
+    record.insert(db).await.map_err(Into::into)
+}
+
+/// Retrieves a member's pending scheduled spends, soonest first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_pending_for_user(
+    db: &DatabaseConnection,
+    user_id: &str,
+) -> Result<Vec<scheduled_transaction::Model>> {
+    ScheduledTransaction::find()
+        .filter(scheduled_transaction::Column::UserId.eq(user_id))
+        .filter(scheduled_transaction::Column::Status.eq(STATUS_PENDING))
+        .order_by_asc(scheduled_transaction::Column::RunOn)
+        .order_by_asc(scheduled_transaction::Column::Id)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Cancels one of a member's pending scheduled spends.
+///
+/// # Errors
+/// Returns an error if:
+/// - No pending spend with that ID belongs to the member
+///   (`Error::ScheduledTransactionNotFound`)
+/// - The database query or update fails
+pub async fn cancel_scheduled(
+    db: &DatabaseConnection,
+    id: i64,
+    user_id: &str,
+    now: DateTime<Utc>,
+) -> Result<scheduled_transaction::Model> {
+    let scheduled = ScheduledTransaction::find_by_id(id)
+        .one(db)
+        .await?
+        .filter(|s| s.user_id == user_id && s.status == STATUS_PENDING)
+        .ok_or(Error::ScheduledTransactionNotFound { id })?;
+
+    let mut active_model: scheduled_transaction::ActiveModel = scheduled.into();
+    active_model.status = Set(STATUS_CANCELLED.to_string());
+    active_model.finished_at = Set(Some(now));
+    active_model.update(db).await.map_err(Into::into)
+}
+
+/// Executes every pending spend scheduled on or before `today`.
+///
+/// A failing spend doesn't stop the others; its error is recorded and it is retried on
+/// the next run until it has failed [`MAX_ATTEMPTS`] times.
+///
+/// # Errors
+/// Returns an error if loading or updating the schedule fails.
+pub async fn run_due(
+    db: &DatabaseConnection,
+    today: NaiveDate,
+    now: DateTime<Utc>,
+) -> Result<Vec<ScheduledRun>> {
+    let due = ScheduledTransaction::find()
+        .filter(scheduled_transaction::Column::Status.eq(STATUS_PENDING))
+        .filter(scheduled_transaction::Column::RunOn.lte(today))
+        .order_by_asc(scheduled_transaction::Column::RunOn)
+        .order_by_asc(scheduled_transaction::Column::Id)
+        .all(db)
+        .await?;
+    let currency = setup::get_currency_format(db).await?;
+
+    let mut runs = Vec::with_capacity(due.len());
+    for scheduled in due {
+        // Nobody is there to confirm an over-limit spend, so daily limits don't apply
+        let spend = transaction::create_cleared_transaction(
+            db,
+            scheduled.envelope_id,
+            -scheduled.amount,
+            scheduled.description.clone(),
+            scheduled.user_id.clone(),
+            Some(format!("scheduled:{}", scheduled.id)),
+            TransactionType::Spend,
+            transaction::Clearance {
+                over_daily_limit: true,
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let attempts = scheduled.attempts + 1;
+        let mut active_model: scheduled_transaction::ActiveModel = scheduled.into();
+        let outcome = match spend {
+            Ok(spend) => {
+                active_model.status = Set(STATUS_EXECUTED.to_string());
+                active_model.transaction_id = Set(Some(spend.id));
+                active_model.finished_at = Set(Some(now));
+                RunOutcome::Executed(Box::new(spend))
+            }
+            Err(e) => {
+                let error = e.user_message(currency);
+                active_model.attempts = Set(attempts);
+                active_model.last_error = Set(Some(error.clone()));
+                if attempts >= MAX_ATTEMPTS {
+                    active_model.status = Set(STATUS_FAILED.to_string());
+                    active_model.finished_at = Set(Some(now));
+                    RunOutcome::Failed { error }
+                } else {
+                    RunOutcome::Retrying { error }
+                }
+            }
+        };
+        let scheduled = active_model.update(db).await?;
+        runs.push(ScheduledRun { scheduled, outcome });
+    }
+
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{core::envelope, test_utils::*};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_spend_runs_on_its_date() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 1000.0).await?;
+
+        let today = date(2025, 3, 20);
+        let rent = schedule_spend(
+            &db,
+            env.id,
+            "user1",
+            800.0,
+            "Rent".to_string(),
+            date(2025, 4, 1),
+            Some("123".to_string()),
+            today,
+        )
+        .await?;
+        assert_eq!(get_pending_for_user(&db, "user1").await?.len(), 1);
+
+        // Nothing runs before the date
+        assert!(run_due(&db, today, Utc::now()).await?.is_empty());
+
+        let runs = run_due(&db, date(2025, 4, 1), Utc::now()).await?;
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].scheduled.id, rent.id);
+        assert_eq!(runs[0].scheduled.status, STATUS_EXECUTED);
+        let RunOutcome::Executed(spend) = &runs[0].outcome else {
+            return Err(Error::Config {
+                message: "expected the spend to execute".to_string(),
+            });
+        };
+        assert_eq!(spend.amount, -800.0);
+        assert_eq!(spend.user_id, "user1");
+
+        // Executed spends don't run again
+        assert!(run_due(&db, date(2025, 4, 2), Utc::now()).await?.is_empty());
+        let env = envelope::get_envelope_by_id(&db, env.id).await?.unwrap();
+        assert_eq!(env.balance, 200.0);
+
+        assert!(matches!(
+            schedule_spend(
+                &db,
+                env.id,
+                "user1",
+                5.0,
+                String::new(),
+                date(2025, 1, 1),
+                None,
+                today
+            )
+            .await,
+            Err(Error::Config { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_spend_retries_then_fails() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let today = date(2025, 3, 20);
+        let scheduled = schedule_spend(
+            &db,
+            env.id,
+            "user1",
+            50.0,
+            "Gym".to_string(),
+            today,
+            None,
+            today,
+        )
+        .await?;
+
+        // No funds: retried until the attempts run out
+        for _ in 1..MAX_ATTEMPTS {
+            let runs = run_due(&db, today, Utc::now()).await?;
+            assert!(matches!(runs[0].outcome, RunOutcome::Retrying { .. }));
+        }
+        let runs = run_due(&db, today, Utc::now()).await?;
+        assert!(matches!(runs[0].outcome, RunOutcome::Failed { .. }));
+        assert_eq!(runs[0].scheduled.status, STATUS_FAILED);
+        assert_eq!(runs[0].scheduled.attempts, MAX_ATTEMPTS);
+        assert!(run_due(&db, today, Utc::now()).await?.is_empty());
+
+        // Only pending spends owned by the member can be cancelled
+        assert!(matches!(
+            cancel_scheduled(&db, scheduled.id, "user1", Utc::now()).await,
+            Err(Error::ScheduledTransactionNotFound { .. })
+        ));
+        let other = schedule_spend(
+            &db,
+            env.id,
+            "user1",
+            5.0,
+            "Later".to_string(),
+            date(2025, 5, 1),
+            None,
+            today,
+        )
+        .await?;
+        assert!(
+            cancel_scheduled(&db, other.id, "user2", Utc::now())
+                .await
+                .is_err()
+        );
+        let cancelled = cancel_scheduled(&db, other.id, "user1", Utc::now()).await?;
+        assert_eq!(cancelled.status, STATUS_CANCELLED);
+        assert!(get_pending_for_user(&db, "user1").await?.is_empty());
+        Ok(())
+    }
+}
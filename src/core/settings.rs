@@ -0,0 +1,457 @@
+//! Runtime settings - The bot settings admins may read and change with `/config`.
+//!
+//! Each [`Setting`] is stored in the `system_state` table by the module that owns it
+//! (setup, calendar, retention, report, monthly, round-up, approval, amount parsing); this
+//! module gives them a common key, parses and validates values typed by an admin, and
+//! passes them on to the owning module's setter so the same rules apply however a setting
+//! is changed.
+
+use crate::{
+    core::{
+        amount_parser, approval, calendar, envelope, monthly, report, retention, round_up, setup,
+    },
+    errors::{Error, Result},
+};
+use sea_orm::DatabaseConnection;
+
+/// A runtime setting exposed through `/config`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    /// Channel for monthly updates and alerts
+    AnnouncementChannel,
+    /// Channel for confirmations that couldn't be delivered
+    AuditChannel,
+    /// Household currency code
+    Currency,
+    /// Day of the month budget cycles start on
+    CycleStartDay,
+    /// Household timezone that days and months follow
+    Timezone,
+    /// Closed budget months of raw transactions to keep
+    RetentionMonths,
+    /// Points over the expected pace still shown as 🟢 in `/report`
+    PaceOnTrack,
+    /// Points over the expected pace still shown as 🟡 in `/report`
+    PaceSlightlyOver,
+    /// Style of progress bars, status indicators and embed colors
+    Theme,
+    /// What the monthly update does with a non-rollover envelope's deficit
+    DeficitPolicy,
+    /// Shared envelope deficits are moved to under the `debt` policy
+    DebtEnvelope,
+    /// Shared envelope spends are rounded up into, or `off`
+    RoundUpEnvelope,
+    /// Spends above this amount need another member's approval, or `off`
+    LargeTransactionThreshold,
+    /// Whether typed amounts put cents after a point or a comma
+    DecimalSeparator,
+    /// Role that enrolls a server member in the household when given, or `off`
+    BudgetRole,
+}
+
+impl Setting {
+    /// Every setting, in the order `/config get` lists them.
+    pub const ALL: [Self; 15] = [
+        Self::AnnouncementChannel,
+        Self::AuditChannel,
+        Self::Currency,
+        Self::CycleStartDay,
+        Self::Timezone,
+        Self::RetentionMonths,
+        Self::PaceOnTrack,
+        Self::PaceSlightlyOver,
+        Self::Theme,
+        Self::DeficitPolicy,
+        Self::DebtEnvelope,
+        Self::RoundUpEnvelope,
+        Self::LargeTransactionThreshold,
+        Self::DecimalSeparator,
+        Self::BudgetRole,
+    ];
+
+    /// Name of the setting as typed in `/config`.
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::AnnouncementChannel => "announcement_channel",
+            Self::AuditChannel => "audit_channel",
+            Self::Currency => "currency",
+            Self::CycleStartDay => "cycle_start_day",
+            Self::Timezone => "timezone",
+            Self::RetentionMonths => "retention_months",
+            Self::PaceOnTrack => "pace_on_track",
+            Self::PaceSlightlyOver => "pace_slightly_over",
+            Self::Theme => "theme",
+            Self::DeficitPolicy => "deficit_policy",
+            Self::DebtEnvelope => "debt_envelope",
+            Self::RoundUpEnvelope => "round_up_envelope",
+            Self::LargeTransactionThreshold => "large_transaction_threshold",
+            Self::DecimalSeparator => "decimal_separator",
+            Self::BudgetRole => "budget_role",
+        }
+    }
+
+    /// Looks up a setting by its [`key`](Self::key), ignoring case.
+    #[must_use]
+    pub fn from_key(key: &str) -> Option<Self> {
+        let key = key.trim();
+        Self::ALL
+            .into_iter()
+            .find(|setting| setting.key().eq_ignore_ascii_case(key))
+    }
+
+    /// Values the setting accepts, shown next to it in `/config get`.
+    #[must_use]
+    pub const fn expected(self) -> &'static str {
+        match self {
+            Self::AnnouncementChannel | Self::AuditChannel => "channel mention or ID",
+            Self::Currency => "currency code",
+            Self::CycleStartDay => "day 1-28",
+            Self::Timezone => "IANA name, e.g. America/New_York",
+            Self::RetentionMonths => "months 1-120, 0 keeps everything",
+            Self::PaceOnTrack | Self::PaceSlightlyOver => "percentage points",
+            Self::Theme => "classic, squares, hearts or minimal",
+            Self::DeficitPolicy => "forgive, carryover or debt",
+            Self::DebtEnvelope => "shared envelope name",
+            Self::RoundUpEnvelope => "shared envelope name, or off",
+            Self::LargeTransactionThreshold => "amount, or off",
+            Self::DecimalSeparator => "point (12.50) or comma (12,50)",
+            Self::BudgetRole => "role mention or ID, or off",
+        }
+    }
+}
+
+/// Gets a setting's current value as text, or `None` if it isn't set and has no default.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_setting(db: &DatabaseConnection, setting: Setting) -> Result<Option<String>> {
+    Ok(match setting {
+        Setting::AnnouncementChannel => setup::get_announcement_channel(db).await?,
+        Setting::AuditChannel => setup::get_audit_channel(db).await?,
+        Setting::Currency => Some(setup::get_currency(db).await?),
+        Setting::CycleStartDay => Some(calendar::get_cycle_start_day(db).await?.to_string()),
+        Setting::Timezone => Some(calendar::get_timezone(db).await?.name().to_string()),
+        Setting::RetentionMonths => retention::get_retention_months(db)
+            .await?
+            .map(|months| months.to_string()),
+        Setting::PaceOnTrack => Some(
+            report::get_pace_settings(db)
+                .await?
+                .household
+                .on_track
+                .to_string(),
+        ),
+        Setting::PaceSlightlyOver => Some(
+            report::get_pace_settings(db)
+                .await?
+                .household
+                .slightly_over
+                .to_string(),
+        ),
+        Setting::Theme => Some(report::get_theme(db).await?.key().to_string()),
+        Setting::DeficitPolicy => Some(monthly::get_deficit_policy(db).await?.key().to_string()),
+        Setting::DebtEnvelope => monthly::get_debt_envelope(db).await?.map(|env| env.name),
+        Setting::RoundUpEnvelope => round_up::get_round_up_envelope(db)
+            .await?
+            .map(|env| env.name),
+        Setting::LargeTransactionThreshold => approval::get_threshold(db)
+            .await?
+            .map(|threshold| format!("{threshold:.2}")),
+        Setting::DecimalSeparator => Some(
+            amount_parser::get_decimal_separator(db)
+                .await?
+                .key()
+                .to_string(),
+        ),
+        Setting::BudgetRole => setup::get_budget_role(db).await?,
+    })
+}
+
+/// Parses `value` for a setting and saves it.
+///
+/// # Errors
+/// Returns `Error::Config` if the value has the wrong type or fails the setting's
+/// validation, or an error if the database write fails.
+#[allow(clippy::too_many_lines)] // One parse-and-save arm per setting
+pub async fn set_setting(db: &DatabaseConnection, setting: Setting, value: &str) -> Result<()> {
+    let value = value.trim();
+    match setting {
+        Setting::AnnouncementChannel => {
+            let channel_id = parse_channel_id(value)?;
+            setup::set_announcement_channel(db, &channel_id.to_string()).await
+        }
+        Setting::AuditChannel => {
+            let channel_id = parse_channel_id(value)?;
+            setup::set_audit_channel(db, &channel_id.to_string()).await
+        }
+        Setting::Currency => setup::set_currency(db, value).await,
+        Setting::CycleStartDay => {
+            calendar::set_cycle_start_day(db, parse_number(setting, value)?).await
+        }
+        Setting::Timezone => calendar::set_timezone(db, value).await,
+        Setting::RetentionMonths => {
+            let months: u32 = parse_number(setting, value)?;
+            retention::set_retention_months(db, (months > 0).then_some(months)).await
+        }
+        Setting::PaceOnTrack | Setting::PaceSlightlyOver => {
+            let points: f64 = parse_number(setting, value)?;
+            let current = report::get_pace_settings(db).await?.household;
+            let thresholds = if setting == Setting::PaceOnTrack {
+                report::PaceThresholds::new(points, current.slightly_over)?
+            } else {
+                report::PaceThresholds::new(current.on_track, points)?
+            };
+            report::set_pace_thresholds(db, None, Some(thresholds)).await
+        }
+        Setting::Theme => {
+            let theme = report::Theme::from_key(value).ok_or_else(|| Error::Config {
+                message: format!("'{value}' is not a theme (expected {})", setting.expected()),
+            })?;
+            report::set_theme(db, theme).await
+        }
+        Setting::DeficitPolicy => {
+            let policy = monthly::DeficitPolicy::from_key(value).ok_or_else(|| Error::Config {
+                message: format!(
+                    "'{value}' is not a deficit policy (expected {})",
+                    setting.expected()
+                ),
+            })?;
+            monthly::set_deficit_policy(db, policy).await
+        }
+        Setting::DebtEnvelope => {
+            let debt = envelope::get_shared_envelope_by_name(db, value)
+                .await?
+                .ok_or_else(|| Error::Config {
+                    message: format!("There is no shared envelope named '{value}'"),
+                })?;
+            monthly::set_debt_envelope(db, debt.id).await
+        }
+        Setting::RoundUpEnvelope => {
+            if value.eq_ignore_ascii_case("off") {
+                return round_up::set_round_up_envelope(db, None).await;
+            }
+            let savings = envelope::get_shared_envelope_by_name(db, value)
+                .await?
+                .ok_or_else(|| Error::Config {
+                    message: format!("There is no shared envelope named '{value}'"),
+                })?;
+            round_up::set_round_up_envelope(db, Some(savings.id)).await
+        }
+        Setting::LargeTransactionThreshold => {
+            if value.eq_ignore_ascii_case("off") {
+                return approval::set_threshold(db, None).await;
+            }
+            let threshold: f64 = parse_number(setting, value.trim_start_matches('$'))?;
+            match approval::set_threshold(db, Some(threshold)).await {
+                Err(Error::InvalidAmount { .. }) => Err(Error::Config {
+                    message: format!("The {} must be greater than zero (or off)", setting.key()),
+                }),
+                result => result,
+            }
+        }
+        Setting::DecimalSeparator => {
+            let separator =
+                amount_parser::DecimalSeparator::from_key(value).ok_or_else(|| Error::Config {
+                    message: format!(
+                        "'{value}' is not a decimal separator (expected {})",
+                        setting.expected()
+                    ),
+                })?;
+            amount_parser::set_decimal_separator(db, separator).await
+        }
+        Setting::BudgetRole => {
+            if value.eq_ignore_ascii_case("off") {
+                return setup::set_budget_role(db, None).await;
+            }
+            let role_id = parse_role_id(value)?;
+            setup::set_budget_role(db, Some(&role_id.to_string())).await
+        }
+    }
+}
+
+/// Parses a number for a setting, naming the setting if it isn't one.
+fn parse_number<T: std::str::FromStr>(setting: Setting, value: &str) -> Result<T> {
+    value.parse().map_err(|_| Error::Config {
+        message: format!(
+            "'{value}' is not a valid value for {} (expected {})",
+            setting.key(),
+            setting.expected()
+        ),
+    })
+}
+
+/// Parses a channel given as a mention (`<#123>`) or a bare ID.
+fn parse_channel_id(value: &str) -> Result<u64> {
+    value
+        .strip_prefix("<#")
+        .and_then(|rest| rest.strip_suffix('>'))
+        .unwrap_or(value)
+        .parse()
+        .ok()
+        .filter(|id| *id > 0)
+        .ok_or_else(|| Error::Config {
+            message: format!("'{value}' is not a channel mention or ID"),
+        })
+}
+
+/// Parses a role given as a mention (`<@&123>`) or a bare ID.
+fn parse_role_id(value: &str) -> Result<u64> {
+    value
+        .strip_prefix("<@&")
+        .and_then(|rest| rest.strip_suffix('>'))
+        .unwrap_or(value)
+        .parse()
+        .ok()
+        .filter(|id| *id > 0)
+        .ok_or_else(|| Error::Config {
+            message: format!("'{value}' is not a role mention or ID"),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_from_key() {
+        assert_eq!(
+            Setting::from_key("Cycle_Start_Day"),
+            Some(Setting::CycleStartDay)
+        );
+        assert_eq!(Setting::from_key("token"), None);
+        assert!(
+            Setting::ALL
+                .iter()
+                .all(|setting| Setting::from_key(setting.key()) == Some(*setting))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_and_set_settings() -> Result<()> {
+        let db = setup_test_db().await?;
+        assert_eq!(get_setting(&db, Setting::AnnouncementChannel).await?, None);
+        assert_eq!(
+            get_setting(&db, Setting::Currency).await?,
+            Some("USD".to_string())
+        );
+
+        set_setting(&db, Setting::AnnouncementChannel, "<#1234>").await?;
+        set_setting(&db, Setting::AuditChannel, "5678").await?;
+        set_setting(&db, Setting::Currency, "eur").await?;
+        set_setting(&db, Setting::CycleStartDay, "25").await?;
+        set_setting(&db, Setting::Timezone, "Europe/Berlin").await?;
+        set_setting(&db, Setting::RetentionMonths, "12").await?;
+        set_setting(&db, Setting::PaceSlightlyOver, "30").await?;
+        set_setting(&db, Setting::Theme, "Hearts").await?;
+        set_setting(&db, Setting::DeficitPolicy, "debt").await?;
+        create_test_envelope(&db, "Debt").await?;
+        set_setting(&db, Setting::DebtEnvelope, "Debt").await?;
+        create_test_envelope(&db, "Savings").await?;
+        set_setting(&db, Setting::RoundUpEnvelope, "Savings").await?;
+        set_setting(&db, Setting::DecimalSeparator, "comma").await?;
+        assert_eq!(
+            get_setting(&db, Setting::AnnouncementChannel).await?,
+            Some("1234".to_string())
+        );
+        assert_eq!(
+            get_setting(&db, Setting::AuditChannel).await?,
+            Some("5678".to_string())
+        );
+        assert_eq!(
+            get_setting(&db, Setting::Currency).await?,
+            Some("EUR".to_string())
+        );
+        assert_eq!(
+            get_setting(&db, Setting::CycleStartDay).await?,
+            Some("25".to_string())
+        );
+        assert_eq!(
+            get_setting(&db, Setting::Timezone).await?,
+            Some("Europe/Berlin".to_string())
+        );
+        assert_eq!(
+            get_setting(&db, Setting::PaceSlightlyOver).await?,
+            Some("30".to_string())
+        );
+
+        assert_eq!(
+            get_setting(&db, Setting::Theme).await?,
+            Some("hearts".to_string())
+        );
+        assert_eq!(
+            get_setting(&db, Setting::DeficitPolicy).await?,
+            Some("debt".to_string())
+        );
+        assert_eq!(
+            get_setting(&db, Setting::DebtEnvelope).await?,
+            Some("Debt".to_string())
+        );
+
+        assert_eq!(
+            get_setting(&db, Setting::RoundUpEnvelope).await?,
+            Some("Savings".to_string())
+        );
+        assert_eq!(
+            get_setting(&db, Setting::DecimalSeparator).await?,
+            Some("comma".to_string())
+        );
+        set_setting(&db, Setting::LargeTransactionThreshold, "$500").await?;
+        assert_eq!(
+            get_setting(&db, Setting::LargeTransactionThreshold).await?,
+            Some("500.00".to_string())
+        );
+
+        set_setting(&db, Setting::RetentionMonths, "0").await?;
+        assert_eq!(get_setting(&db, Setting::RetentionMonths).await?, None);
+        set_setting(&db, Setting::RoundUpEnvelope, "OFF").await?;
+        assert_eq!(get_setting(&db, Setting::RoundUpEnvelope).await?, None);
+        set_setting(&db, Setting::LargeTransactionThreshold, "off").await?;
+        assert_eq!(
+            get_setting(&db, Setting::LargeTransactionThreshold).await?,
+            None
+        );
+
+        // Values of the wrong type or failing validation are rejected
+        for (setting, value) in [
+            (Setting::AnnouncementChannel, "general"),
+            (Setting::Currency, "BTC"),
+            (Setting::CycleStartDay, "thirty"),
+            (Setting::CycleStartDay, "30"),
+            (Setting::Timezone, "Mars/Olympus_Mons"),
+            (Setting::RetentionMonths, "-1"),
+            (Setting::PaceOnTrack, "40"),
+            (Setting::Theme, "neon"),
+            (Setting::DeficitPolicy, "ignore"),
+            (Setting::DebtEnvelope, "Nowhere"),
+            (Setting::RoundUpEnvelope, "Nowhere"),
+            (Setting::LargeTransactionThreshold, "0"),
+            (Setting::LargeTransactionThreshold, "lots"),
+            (Setting::DecimalSeparator, "dot"),
+        ] {
+            assert!(matches!(
+                set_setting(&db, setting, value).await,
+                Err(Error::Config { .. })
+            ));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_budget_role_setting() -> Result<()> {
+        let db = setup_test_db().await?;
+        set_setting(&db, Setting::BudgetRole, "<@&4321>").await?;
+        assert_eq!(
+            get_setting(&db, Setting::BudgetRole).await?,
+            Some("4321".to_string())
+        );
+        assert!(matches!(
+            set_setting(&db, Setting::BudgetRole, "<#1234>").await,
+            Err(Error::Config { .. })
+        ));
+        set_setting(&db, Setting::BudgetRole, "off").await?;
+        assert_eq!(get_setting(&db, Setting::BudgetRole).await?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,562 @@
+//! Onboarding business logic - Initial household configuration.
+//!
+//! Backs the `/setup` command: member nicknames, household currency, starter envelopes
+//! chosen from built-in templates, and the announcement channel. Everything is stored in
+//! the `system_state` table (or as envelopes) so a new server can be configured without
+//! hand-editing `config.toml`.
+//!
+//! Members who join later are enrolled with [`enroll_member`] when they are given the
+//! household's budget role, which creates their copy of every individual template.
+
+use crate::{
+    core::{envelope, state},
+    entities::envelope as envelope_entity,
+    errors::{Error, Result},
+};
+use chrono::Utc;
+use sea_orm::DatabaseConnection;
+use std::collections::HashMap;
+
+const NICKNAME_KEY_PREFIX: &str = "nickname:";
+const CURRENCY_KEY: &str = "currency";
+const ANNOUNCEMENT_CHANNEL_KEY: &str = "announcement_channel_id";
+const AUDIT_CHANNEL_KEY: &str = "audit_channel_id";
+const SETUP_COMPLETED_KEY: &str = "setup_completed_at";
+const BUDGET_ROLE_KEY: &str = "budget_role_id";
+const ENROLLED_KEY_PREFIX: &str = "enrolled:";
+
+/// Currency used when none has been configured.
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+/// Currency codes offered during setup.
+pub const SUPPORTED_CURRENCIES: &[&str] = &["USD", "CAD", "EUR", "GBP", "AUD", "NZD", "JPY"];
+
+/// How amounts in the household currency are written in balances, reports, and
+/// statements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Currency {
+    /// Symbol written before amounts, e.g. `€`
+    pub symbol: &'static str,
+    /// Digits after the decimal point
+    pub decimals: usize,
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self::from_code(DEFAULT_CURRENCY)
+    }
+}
+
+impl Currency {
+    /// Looks up a currency code; codes that aren't supported are written like US dollars.
+    #[must_use]
+    pub fn from_code(code: &str) -> Self {
+        let (symbol, decimals) = match code.trim().to_uppercase().as_str() {
+            "CAD" => ("CA$", 2),
+            "EUR" => ("€", 2),
+            "GBP" => ("£", 2),
+            "AUD" => ("A$", 2),
+            "NZD" => ("NZ$", 2),
+            "JPY" => ("¥", 0),
+            // US dollars, and anything else
+            _ => ("$", 2),
+        };
+        Self { symbol, decimals }
+    }
+
+    /// Writes an amount, e.g. `€12.50`.
+    #[must_use]
+    pub fn format(self, amount: f64) -> String {
+        format!("{}{amount:.*}", self.symbol, self.decimals)
+    }
+
+    /// Writes an amount with an explicit sign, e.g. `+€12.50` or `-€3.00`.
+    #[must_use]
+    pub fn format_signed(self, amount: f64) -> String {
+        let sign = if amount >= 0.0 { '+' } else { '-' };
+        format!("{sign}{}", self.format(amount.abs()))
+    }
+}
+
+/// A starter envelope offered during setup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvelopeTemplate {
+    /// Envelope name
+    pub name: &'static str,
+    /// Budget category
+    pub category: &'static str,
+    /// Suggested monthly allocation
+    pub allocation: f64,
+    /// Whether one envelope is created per household member
+    pub is_individual: bool,
+    /// Whether unused funds roll over
+    pub rollover: bool,
+}
+
+/// Built-in starter envelopes, mirroring the example `config.toml`.
+pub const STARTER_TEMPLATES: &[EnvelopeTemplate] = &[
+    EnvelopeTemplate {
+        name: "groceries",
+        category: "necessary",
+        allocation: 500.0,
+        is_individual: false,
+        rollover: false,
+    },
+    EnvelopeTemplate {
+        name: "utilities",
+        category: "necessary",
+        allocation: 200.0,
+        is_individual: false,
+        rollover: false,
+    },
+    EnvelopeTemplate {
+        name: "eating-out",
+        category: "quality_of_life",
+        allocation: 150.0,
+        is_individual: false,
+        rollover: false,
+    },
+    EnvelopeTemplate {
+        name: "emergency-fund",
+        category: "savings",
+        allocation: 100.0,
+        is_individual: false,
+        rollover: true,
+    },
+    EnvelopeTemplate {
+        name: "game",
+        category: "quality_of_life",
+        allocation: 80.0,
+        is_individual: true,
+        rollover: true,
+    },
+    EnvelopeTemplate {
+        name: "drink",
+        category: "quality_of_life",
+        allocation: 60.0,
+        is_individual: true,
+        rollover: false,
+    },
+];
+
+/// Looks up a starter template by name (case-insensitive).
+#[must_use]
+pub fn find_template(name: &str) -> Option<&'static EnvelopeTemplate> {
+    STARTER_TEMPLATES
+        .iter()
+        .find(|template| template.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// Stores a nickname for a household member.
+///
+/// # Errors
+/// Returns an error if the nickname is empty or the database write fails.
+pub async fn save_nickname(db: &DatabaseConnection, user_id: &str, nickname: &str) -> Result<()> {
+    let nickname = nickname.trim();
+    if nickname.is_empty() {
+        return Err(Error::Config {
+            message: "Nickname cannot be empty".to_string(),
+        });
+    }
+
+    state::set_value(db, &format!("{NICKNAME_KEY_PREFIX}{user_id}"), nickname).await
+}
+
+/// Retrieves all nicknames configured through setup, keyed by Discord user ID.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_saved_nicknames(db: &DatabaseConnection) -> Result<HashMap<String, String>> {
+    let entries = state::get_values_with_prefix(db, NICKNAME_KEY_PREFIX).await?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|(key, value)| {
+            key.strip_prefix(NICKNAME_KEY_PREFIX)
+                .map(|user_id| (user_id.to_string(), value))
+        })
+        .collect())
+}
+
+/// Sets the household currency code.
+///
+/// # Errors
+/// Returns an error if the code is not one of [`SUPPORTED_CURRENCIES`] or the write fails.
+pub async fn set_currency(db: &DatabaseConnection, code: &str) -> Result<()> {
+    let code = code.trim().to_uppercase();
+    if !SUPPORTED_CURRENCIES.contains(&code.as_str()) {
+        return Err(Error::Config {
+            message: format!(
+                "Unsupported currency '{code}'. Supported: {}",
+                SUPPORTED_CURRENCIES.join(", ")
+            ),
+        });
+    }
+
+    state::set_value(db, CURRENCY_KEY, &code).await
+}
+
+/// Gets the household currency code, falling back to [`DEFAULT_CURRENCY`].
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_currency(db: &DatabaseConnection) -> Result<String> {
+    Ok(state::get_value(db, CURRENCY_KEY)
+        .await?
+        .unwrap_or_else(|| DEFAULT_CURRENCY.to_string()))
+}
+
+/// Gets how amounts in the household currency are written.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_currency_format(db: &DatabaseConnection) -> Result<Currency> {
+    Ok(Currency::from_code(&get_currency(db).await?))
+}
+
+/// Sets the Discord channel used for bot announcements (monthly updates, alerts).
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_announcement_channel(db: &DatabaseConnection, channel_id: &str) -> Result<()> {
+    state::set_value(db, ANNOUNCEMENT_CHANNEL_KEY, channel_id).await
+}
+
+/// Gets the configured announcement channel ID, if any.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_announcement_channel(db: &DatabaseConnection) -> Result<Option<String>> {
+    state::get_value(db, ANNOUNCEMENT_CHANNEL_KEY).await
+}
+
+/// Sets the Discord channel that receives confirmations the bot couldn't deliver.
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_audit_channel(db: &DatabaseConnection, channel_id: &str) -> Result<()> {
+    state::set_value(db, AUDIT_CHANNEL_KEY, channel_id).await
+}
+
+/// Gets the configured audit channel ID, if any.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_audit_channel(db: &DatabaseConnection) -> Result<Option<String>> {
+    state::get_value(db, AUDIT_CHANNEL_KEY).await
+}
+
+/// Sets the Discord role that makes a server member part of the household, or clears it.
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn set_budget_role(db: &DatabaseConnection, role_id: Option<&str>) -> Result<()> {
+    match role_id {
+        Some(role_id) => state::set_value(db, BUDGET_ROLE_KEY, role_id).await,
+        None => state::delete_value(db, BUDGET_ROLE_KEY).await,
+    }
+}
+
+/// Gets the household's budget role ID, if one has been configured.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_budget_role(db: &DatabaseConnection) -> Result<Option<String>> {
+    state::get_value(db, BUDGET_ROLE_KEY).await
+}
+
+/// Enrolls a member who was given the budget role, creating their copy of every individual
+/// template they don't have yet (see [`envelope::instantiate_template`]).
+///
+/// # Returns
+/// The envelopes created for the member, or `None` if they were enrolled before or already
+/// had envelopes of their own, so a role granted again (or to an existing member) doesn't
+/// welcome them twice.
+///
+/// # Errors
+/// Returns an error if a database query or insert fails.
+pub async fn enroll_member(
+    db: &DatabaseConnection,
+    user_id: &str,
+) -> Result<Option<Vec<envelope_entity::Model>>> {
+    let enrolled_key = format!("{ENROLLED_KEY_PREFIX}{user_id}");
+    if state::get_value(db, &enrolled_key).await?.is_some() {
+        return Ok(None);
+    }
+
+    let mut created = Vec::new();
+    let mut existing_member = false;
+    for template in envelope::get_templates(db).await? {
+        if envelope::get_envelope_by_name_and_user(db, &template.name, user_id)
+            .await?
+            .is_some()
+        {
+            existing_member = true;
+            continue;
+        }
+        created.push(envelope::instantiate_template(db, &template, user_id).await?);
+    }
+    state::set_value(db, &enrolled_key, &Utc::now().to_rfc3339()).await?;
+
+    Ok((!existing_member).then_some(created))
+}
+
+/// Creates envelopes for the selected starter templates.
+///
+/// Shared templates become a single shared envelope; individual templates become one
+/// envelope per household member. Envelopes that already exist are skipped, so running
+/// setup twice is safe.
+///
+/// # Errors
+/// Returns an error if:
+/// - A template name is unknown
+/// - An individual template is selected but no members are given
+/// - A database query or insert fails
+pub async fn apply_starter_templates(
+    db: &DatabaseConnection,
+    template_names: &[String],
+    member_ids: &[String],
+) -> Result<Vec<envelope_entity::Model>> {
+    let mut created = Vec::new();
+
+    for template_name in template_names {
+        let template = find_template(template_name).ok_or_else(|| Error::Config {
+            message: format!("Unknown starter envelope template '{template_name}'"),
+        })?;
+
+        if template.is_individual {
+            if member_ids.is_empty() {
+                return Err(Error::IndividualEnvelopeWithoutUser {
+                    name: template.name.to_string(),
+                });
+            }
+
+            for member_id in member_ids {
+                if envelope::get_envelope_by_name_and_user(db, template.name, member_id)
+                    .await?
+                    .is_some()
+                {
+                    continue;
+                }
+                created.push(create_from_template(db, template, Some(member_id.clone())).await?);
+            }
+        } else {
+            if envelope::get_shared_envelope_by_name(db, template.name)
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+            created.push(create_from_template(db, template, None).await?);
+        }
+    }
+
+    Ok(created)
+}
+
+/// Creates a single envelope from a template.
+async fn create_from_template(
+    db: &DatabaseConnection,
+    template: &EnvelopeTemplate,
+    user_id: Option<String>,
+) -> Result<envelope_entity::Model> {
+    envelope::create_envelope(
+        db,
+        template.name.to_string(),
+        user_id,
+        template.category.to_string(),
+        template.allocation,
+        template.is_individual,
+        template.rollover,
+    )
+    .await
+}
+
+/// Records that setup has been completed.
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn mark_setup_complete(db: &DatabaseConnection) -> Result<()> {
+    state::set_value(db, SETUP_COMPLETED_KEY, &Utc::now().to_rfc3339()).await
+}
+
+/// Checks whether `/setup` has been completed at least once.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn is_setup_complete(db: &DatabaseConnection) -> Result<bool> {
+    Ok(state::get_value(db, SETUP_COMPLETED_KEY).await?.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_currency_format() {
+        assert_eq!(Currency::default().format(12.5), "$12.50");
+        assert_eq!(Currency::from_code("eur").format_signed(-3.0), "-€3.00");
+        assert_eq!(Currency::from_code("GBP").format_signed(0.0), "+£0.00");
+        assert_eq!(Currency::from_code("JPY").format(1200.4), "¥1200");
+        assert_eq!(Currency::from_code(" cad ").format(4.5), "CA$4.50");
+        assert_eq!(Currency::from_code("JPY").format_signed(-15.6), "-¥16");
+
+        // Unsupported codes are written like US dollars
+        assert_eq!(Currency::from_code("XYZ"), Currency::default());
+    }
+
+    #[tokio::test]
+    async fn test_currency_format_follows_setting() -> Result<()> {
+        let db = setup_test_db().await?;
+        assert_eq!(get_currency_format(&db).await?.format(7.0), "$7.00");
+
+        set_currency(&db, "NZD").await?;
+        assert_eq!(get_currency_format(&db).await?.format(7.0), "NZ$7.00");
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_template() {
+        assert!(find_template("groceries").is_some());
+        assert!(find_template(" Groceries ").is_some());
+        assert!(find_template("yachts").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_nicknames_round_trip() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        save_nickname(&db, "111", "Alice").await?;
+        save_nickname(&db, "222", " Bob ").await?;
+
+        let nicknames = get_saved_nicknames(&db).await?;
+        assert_eq!(nicknames.get("111"), Some(&"Alice".to_string()));
+        assert_eq!(nicknames.get("222"), Some(&"Bob".to_string()));
+
+        assert!(save_nickname(&db, "333", "   ").await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_currency_validation_and_default() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        assert_eq!(get_currency(&db).await?, DEFAULT_CURRENCY);
+
+        set_currency(&db, "cad").await?;
+        assert_eq!(get_currency(&db).await?, "CAD");
+        assert_eq!(get_currency_format(&db).await?.format(-4.5), "CA$-4.50");
+
+        assert!(matches!(
+            set_currency(&db, "XYZ").await,
+            Err(Error::Config { .. })
+        ));
+        assert_eq!(get_currency(&db).await?, "CAD");
+        assert_eq!(get_currency_format(&db).await?.format(-4.5), "CA$-4.50");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_enroll_member() -> Result<()> {
+        let db = setup_test_db().await?;
+        for name in ["game", "drink"] {
+            envelope::create_template(&db, name.to_string(), "fun".to_string(), 50.0, false)
+                .await?;
+        }
+        create_custom_envelope(
+            &db,
+            "game",
+            Some("111".to_string()),
+            "fun",
+            50.0,
+            true,
+            false,
+        )
+        .await?;
+
+        let created = enroll_member(&db, "222").await?.unwrap_or_default();
+        let names: Vec<&str> = created.iter().map(|env| env.name.as_str()).collect();
+        assert_eq!(names, vec!["drink", "game"]);
+        assert!(
+            created
+                .iter()
+                .all(|env| env.user_id.as_deref() == Some("222"))
+        );
+        assert!(enroll_member(&db, "222").await?.is_none());
+
+        // Existing members get their missing envelopes without being welcomed
+        assert!(enroll_member(&db, "111").await?.is_none());
+        assert!(
+            envelope::get_envelope_by_name_and_user(&db, "drink", "111")
+                .await?
+                .is_some()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_apply_starter_templates() -> Result<()> {
+        let db = setup_test_db().await?;
+        let members = vec!["111".to_string(), "222".to_string()];
+
+        let created = apply_starter_templates(
+            &db,
+            &["groceries".to_string(), "game".to_string()],
+            &members,
+        )
+        .await?;
+
+        // One shared envelope plus one individual envelope per member
+        assert_eq!(created.len(), 3);
+        assert!(
+            envelope::get_shared_envelope_by_name(&db, "groceries")
+                .await?
+                .is_some()
+        );
+        assert!(
+            envelope::get_envelope_by_name_and_user(&db, "game", "222")
+                .await?
+                .is_some()
+        );
+
+        // Re-running skips existing envelopes
+        let created_again =
+            apply_starter_templates(&db, &["groceries".to_string()], &members).await?;
+        assert!(created_again.is_empty());
+
+        // Unknown templates are rejected
+        assert!(
+            apply_starter_templates(&db, &["yachts".to_string()], &members)
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_setup_completion_and_channel() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        assert!(!is_setup_complete(&db).await?);
+        assert_eq!(get_announcement_channel(&db).await?, None);
+
+        set_announcement_channel(&db, "987").await?;
+        mark_setup_complete(&db).await?;
+
+        assert!(is_setup_complete(&db).await?);
+        assert_eq!(
+            get_announcement_channel(&db).await?,
+            Some("987".to_string())
+        );
+
+        assert_eq!(get_audit_channel(&db).await?, None);
+        set_audit_channel(&db, "654").await?;
+        assert_eq!(get_audit_channel(&db).await?, Some("654".to_string()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! Share link business logic - Time-limited read-only access for outside viewers.
+//!
+//! An admin creates a share link with [`create_share_link`] and hands its token to a
+//! family member who isn't in the Discord server. Anyone holding an unexpired token can
+//! view the balances returned by [`shared_balances`], but never change anything. Links
+//! are stored in the `system_state` table under `share:<token>` as
+//! `<expires_at>;<created_by>` and can be revoked early with [`revoke_share_link`].
+
+use crate::{
+    core::{envelope, state},
+    entities::envelope as envelope_entity,
+    errors::{Error, Result},
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::DatabaseConnection;
+
+/// Prefix of the `system_state` keys holding share links.
+const SHARE_KEY_PREFIX: &str = "share:";
+
+/// How long a share link stays valid when no duration is given.
+pub const DEFAULT_SHARE_HOURS: i64 = 24 * 7;
+
+/// Longest a share link may stay valid.
+pub const MAX_SHARE_HOURS: i64 = 24 * 30;
+
+/// A read-only share link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareLink {
+    /// Secret token given to the viewer
+    pub token: String,
+    /// Discord user ID of the admin who created the link
+    pub created_by: String,
+    /// When the link stops working
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Generates a random 32-character hex token from 128 bits of OS randomness.
+///
+/// # Errors
+/// Returns `Error::Config` if the operating system's random source is unavailable.
+fn generate_token() -> Result<String> {
+    let mut bytes = [0_u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| Error::Config {
+        message: format!("Could not generate a share token: {e}"),
+    })?;
+    Ok(format!("{:032x}", u128::from_be_bytes(bytes)))
+}
+
+/// Creates a share link that expires `valid_for` after `now`.
+///
+/// # Errors
+/// Returns an error if the duration is not positive or exceeds [`MAX_SHARE_HOURS`]
+/// (`Error::Config`), no random token can be generated, or the database write fails.
+pub async fn create_share_link(
+    db: &DatabaseConnection,
+    created_by: &str,
+    valid_for: Duration,
+    now: DateTime<Utc>,
+) -> Result<ShareLink> {
+    if valid_for <= Duration::zero() || valid_for > Duration::hours(MAX_SHARE_HOURS) {
+        return Err(Error::Config {
+            message: format!("Share links must last between 1 and {MAX_SHARE_HOURS} hours"),
+        });
+    }
+
+    let link = ShareLink {
+        token: generate_token()?,
+        created_by: created_by.to_string(),
+        expires_at: now + valid_for,
+    };
+    state::set_value(
+        db,
+        &format!("{SHARE_KEY_PREFIX}{}", link.token),
+        &format!("{};{}", link.expires_at.to_rfc3339(), link.created_by),
+    )
+    .await?;
+    Ok(link)
+}
+
+/// Parses a stored share link; malformed values are ignored.
+fn parse_share_link(token: &str, value: &str) -> Option<ShareLink> {
+    let (expires_at, created_by) = value.split_once(';')?;
+    Some(ShareLink {
+        token: token.to_string(),
+        created_by: created_by.to_string(),
+        expires_at: DateTime::parse_from_rfc3339(expires_at)
+            .ok()?
+            .with_timezone(&Utc),
+    })
+}
+
+/// Looks up a share link, returning `None` if it doesn't exist or has expired.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_share_link(
+    db: &DatabaseConnection,
+    token: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<ShareLink>> {
+    let token = token.trim();
+    Ok(state::get_value(db, &format!("{SHARE_KEY_PREFIX}{token}"))
+        .await?
+        .and_then(|value| parse_share_link(token, &value))
+        .filter(|link| link.expires_at > now))
+}
+
+/// Retrieves all unexpired share links, soonest to expire first.
+///
+/// Expired links found along the way are deleted.
+///
+/// # Errors
+/// Returns an error if the database query or delete fails.
+pub async fn get_active_share_links(
+    db: &DatabaseConnection,
+    now: DateTime<Utc>,
+) -> Result<Vec<ShareLink>> {
+    let mut links = Vec::new();
+    for (key, value) in state::get_values_with_prefix(db, SHARE_KEY_PREFIX).await? {
+        let token = key.strip_prefix(SHARE_KEY_PREFIX).unwrap_or_default();
+        match parse_share_link(token, &value) {
+            Some(link) if link.expires_at > now => links.push(link),
+            _ => state::delete_value(db, &key).await?,
+        }
+    }
+    links.sort_by_key(|link| link.expires_at);
+    Ok(links)
+}
+
+/// Revokes a share link before it expires.
+///
+/// # Returns
+/// `true` if the link existed
+///
+/// # Errors
+/// Returns an error if the database query or delete fails.
+pub async fn revoke_share_link(db: &DatabaseConnection, token: &str) -> Result<bool> {
+    let key = format!("{SHARE_KEY_PREFIX}{}", token.trim());
+    if state::get_value(db, &key).await?.is_none() {
+        return Ok(false);
+    }
+    state::delete_value(db, &key).await?;
+    Ok(true)
+}
+
+/// Envelopes an outside viewer may see: every active envelope that isn't private.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn shared_balances(db: &DatabaseConnection) -> Result<Vec<envelope_entity::Model>> {
+    Ok(envelope::get_all_active_envelopes(db)
+        .await?
+        .into_iter()
+        .filter(|env| !env.is_private)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_share_link_lifecycle() -> Result<()> {
+        let db = setup_test_db().await?;
+        let now = Utc::now();
+
+        let link = create_share_link(&db, "admin", Duration::hours(24), now).await?;
+        assert_eq!(link.token.len(), 32);
+        let other = create_share_link(&db, "admin", Duration::hours(1), now).await?;
+        assert_ne!(link.token, other.token);
+
+        assert_eq!(
+            get_share_link(&db, &link.token, now).await?,
+            Some(link.clone())
+        );
+        // Expired links stop working and are cleaned up when listed
+        let later = now + Duration::hours(2);
+        assert!(get_share_link(&db, &other.token, later).await?.is_none());
+        assert_eq!(
+            get_active_share_links(&db, later).await?,
+            vec![link.clone()]
+        );
+        assert!(!revoke_share_link(&db, &other.token).await?);
+
+        assert!(revoke_share_link(&db, &link.token).await?);
+        assert!(get_share_link(&db, &link.token, now).await?.is_none());
+
+        assert!(matches!(
+            create_share_link(&db, "admin", Duration::hours(MAX_SHARE_HOURS + 1), now).await,
+            Err(Error::Config { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shared_balances_hide_private_envelopes() -> Result<()> {
+        let (db, shared) = setup_with_envelope().await?;
+        let private = create_custom_envelope(
+            &db,
+            "Hobby",
+            Some("alice".to_string()),
+            "personal",
+            50.0,
+            true,
+            false,
+        )
+        .await?;
+        envelope::set_private(&db, private.id, true).await?;
+
+        let visible = shared_balances(&db).await?;
+        assert!(visible.iter().any(|env| env.id == shared.id));
+        assert!(visible.iter().all(|env| env.id != private.id));
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+//! Shopping list business logic - Planned purchases on each envelope's list.
+//!
+//! Members add what they mean to buy to an envelope's list with [`add_item`], or from a
+//! product with [`add_product`]. Checking an item off with [`check_off`] can also log the
+//! spend: items from a product are logged at the product's current price, so planning
+//! and tracking meet in one step. Checked-off items stay visible until [`clear_checked`].
+
+use crate::{
+    core::{product, setup, transaction},
+    entities::{
+        ShoppingListItem, TransactionType, envelope, product as product_entity, shopping_list_item,
+        transaction as transaction_entity,
+    },
+    errors::{Error, Result},
+};
+use chrono::NaiveDate;
+use sea_orm::{QueryOrder, Set, prelude::*};
+
+/// Longest item name accepted, in characters.
+pub const MAX_NAME_LENGTH: usize = 100;
+
+/// An item that was checked off, with the spend logged for it if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedItem {
+    /// The item, now checked off
+    pub item: shopping_list_item::Model,
+    /// The spend logged for it
+    pub spend: Option<transaction_entity::Model>,
+}
+
+/// Adds a free-text item to an envelope's list.
+///
+/// # Errors
+/// Returns `Error::Config` if the name is empty or too long, or the quantity isn't
+/// positive. Returns an error if the database write fails.
+pub async fn add_item(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    name: &str,
+    quantity: i64,
+    added_by: &str,
+) -> Result<shopping_list_item::Model> {
+    insert_item(db, envelope_id, name, None, quantity, added_by).await
+}
+
+/// Adds a product to its envelope's list, so checking it off can log it at its price.
+///
+/// # Errors
+/// Returns `Error::Config` if the quantity isn't positive. Returns an error if the
+/// database write fails.
+pub async fn add_product(
+    db: &DatabaseConnection,
+    product: &product_entity::Model,
+    quantity: i64,
+    added_by: &str,
+) -> Result<shopping_list_item::Model> {
+    insert_item(
+        db,
+        product.envelope_id,
+        &product.name,
+        Some(product.id),
+        quantity,
+        added_by,
+    )
+    .await
+}
+
+/// Validates and inserts a list item.
+async fn insert_item(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    name: &str,
+    product_id: Option<i64>,
+    quantity: i64,
+    added_by: &str,
+) -> Result<shopping_list_item::Model> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(Error::Config {
+            message: format!("Item names must be 1 to {MAX_NAME_LENGTH} characters"),
+        });
+    }
+    if quantity <= 0 {
+        return Err(Error::Config {
+            message: "Quantity must be a positive number".to_string(),
+        });
+    }
+
+    shopping_list_item::ActiveModel {
+        envelope_id: Set(envelope_id),
+        name: Set(name.to_string()),
+        product_id: Set(product_id),
+        quantity: Set(quantity),
+        added_by: Set(added_by.to_string()),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(Into::into)
+}
+
+/// Retrieves an envelope's list: items still to buy first, oldest first within each.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_items(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+) -> Result<Vec<shopping_list_item::Model>> {
+    let mut items = ShoppingListItem::find()
+        .filter(shopping_list_item::Column::EnvelopeId.eq(envelope_id))
+        .order_by_asc(shopping_list_item::Column::CreatedAt)
+        .order_by_asc(shopping_list_item::Column::Id)
+        .all(db)
+        .await?;
+    items.sort_by_key(|item| item.checked_at.is_some());
+    Ok(items)
+}
+
+/// Checks an item off `envelope`'s list, logging it as a spend if `log_spend` is set.
+///
+/// The spend costs the product's current price times the quantity and is recorded for
+/// `user_id` like a `/use_product`, so spend permissions and balance rules apply. The
+/// item ID makes the spend idempotent, so a repeated click never logs it twice.
+///
+/// # Errors
+/// Returns `Error::Config` if the item isn't on the envelope's list, is already checked
+/// off, or should be logged but wasn't added from a product. Returns
+/// `Error::ProductNotFound` if its product was deleted. Returns an error if the spend is
+/// refused or a database write fails; the item stays unchecked in that case.
+pub async fn check_off(
+    db: &DatabaseConnection,
+    envelope: &envelope::Model,
+    item_id: i64,
+    user_id: &str,
+    log_spend: bool,
+    today: NaiveDate,
+) -> Result<CheckedItem> {
+    let item = ShoppingListItem::find_by_id(item_id)
+        .filter(shopping_list_item::Column::EnvelopeId.eq(envelope.id))
+        .one(db)
+        .await?
+        .ok_or_else(|| Error::Config {
+            message: format!("Item #{item_id} isn't on the '{}' list", envelope.name),
+        })?;
+    if item.checked_at.is_some() {
+        return Err(Error::Config {
+            message: format!("'{}' is already checked off", item.name),
+        });
+    }
+
+    let spend = if log_spend {
+        let Some(product_id) = item.product_id else {
+            return Err(Error::Config {
+                message: format!(
+                    "'{}' wasn't added from a product, so it has no price",
+                    item.name
+                ),
+            });
+        };
+        let prod = product::get_product_by_id(db, product_id)
+            .await?
+            .filter(|prod| !prod.is_deleted)
+            .ok_or_else(|| Error::ProductNotFound {
+                name: item.name.clone(),
+            })?;
+        // Cast is safe: for quantities < 2^53, no precision loss occurs in f64
+        #[allow(clippy::cast_precision_loss)]
+        let cost = prod.price * (item.quantity as f64);
+        let currency = setup::get_currency_format(db).await?;
+        let description =
+            product::use_description(&prod, envelope, item.quantity, today, currency, None);
+        Some(
+            transaction::create_transaction(
+                db,
+                envelope.id,
+                -cost,
+                description,
+                user_id.to_string(),
+                Some(format!("shopping_list:{}", item.id)),
+                TransactionType::UseProduct,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    let mut active_model: shopping_list_item::ActiveModel = item.into();
+    active_model.checked_at = Set(Some(chrono::Utc::now()));
+    active_model.checked_by = Set(Some(user_id.to_string()));
+    active_model.transaction_id = Set(spend.as_ref().map(|spend| spend.id));
+    let item = active_model.update(db).await?;
+
+    Ok(CheckedItem { item, spend })
+}
+
+/// Removes the checked-off items from an envelope's list.
+///
+/// # Returns
+/// The number of items removed
+///
+/// # Errors
+/// Returns an error if the database write fails.
+pub async fn clear_checked(db: &DatabaseConnection, envelope_id: i64) -> Result<u64> {
+    let result = ShoppingListItem::delete_many()
+        .filter(shopping_list_item::Column::EnvelopeId.eq(envelope_id))
+        .filter(shopping_list_item::Column::CheckedAt.is_not_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{core::envelope as envelope_core, test_utils::*};
+
+    #[tokio::test]
+    async fn test_shopping_list() -> Result<()> {
+        let (db, env, prod) = setup_with_product().await?;
+        create_test_transaction(&db, env.id, 100.0).await?;
+        let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap_or_default();
+
+        let milk = add_item(&db, env.id, " Milk ", 1, "user1").await?;
+        assert_eq!(milk.name, "Milk");
+        let bought = add_product(&db, &prod, 2, "user1").await?;
+        assert!(add_item(&db, env.id, "  ", 1, "user1").await.is_err());
+        assert!(add_item(&db, env.id, "Eggs", 0, "user1").await.is_err());
+
+        // Free-text items can be checked off but not logged
+        assert!(
+            check_off(&db, &env, milk.id, "user2", true, today)
+                .await
+                .is_err()
+        );
+        let checked = check_off(&db, &env, milk.id, "user2", false, today).await?;
+        assert_eq!(checked.item.checked_by.as_deref(), Some("user2"));
+        assert!(checked.spend.is_none());
+        assert!(
+            check_off(&db, &env, milk.id, "user2", false, today)
+                .await
+                .is_err()
+        );
+
+        // Product items log a spend at the product's price
+        let checked = check_off(&db, &env, bought.id, "user2", true, today).await?;
+        let cost = prod.price * 2.0;
+        assert_eq!(
+            checked.spend.as_ref().map(|spend| spend.amount),
+            Some(-cost)
+        );
+        assert_eq!(
+            checked.item.transaction_id,
+            checked.spend.as_ref().map(|spend| spend.id)
+        );
+        let after = envelope_core::get_envelope_by_id(&db, env.id).await?;
+        assert_eq!(after.map(|e| e.balance), Some(env.balance + 100.0 - cost));
+
+        let eggs = add_item(&db, env.id, "Eggs", 12, "user1").await?;
+        let items = get_items(&db, env.id).await?;
+        assert_eq!(items[0].id, eggs.id);
+        assert_eq!(clear_checked(&db, env.id).await?, 2);
+        assert_eq!(get_items(&db, env.id).await?.len(), 1);
+
+        Ok(())
+    }
+}
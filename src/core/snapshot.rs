@@ -0,0 +1,241 @@
+//! Balance snapshot business logic - Daily balances and trend lines.
+//!
+//! A nightly job calls [`record_snapshots`] to store every active envelope's balance for
+//! the day, then [`prune_snapshots`] to drop snapshots older than the retention period.
+//! Trends are read back with [`get_trend`] and rendered as a text sparkline.
+
+use crate::{
+    core::{envelope, setup::Currency},
+    entities::{BalanceSnapshot, balance_snapshot},
+    errors::Result,
+};
+use chrono::{Days, NaiveDate, Utc};
+use sea_orm::{QueryOrder, Set, prelude::*};
+use std::fmt::Write;
+
+/// Number of days shown by a balance trend.
+pub const TREND_DAYS: u64 = 90;
+
+/// Default number of days snapshots are kept before pruning.
+pub const DEFAULT_RETENTION_DAYS: u64 = 365;
+
+/// Characters used for sparkline bars, lowest to highest.
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Records the current balance of every active envelope for `date`.
+///
+/// Envelopes that already have a snapshot for the day are skipped, so the job can safely
+/// run more than once per day (for example after a restart).
+///
+/// # Returns
+/// The number of snapshots created
+///
+/// # Errors
+/// Returns an error if a database query or insert fails.
+pub async fn record_snapshots(db: &DatabaseConnection, date: NaiveDate) -> Result<usize> {
+    let envelopes = envelope::get_all_active_envelopes(db).await?;
+    let now = Utc::now();
+    let mut created = 0;
+
+    for env in envelopes {
+        let existing = BalanceSnapshot::find()
+            .filter(balance_snapshot::Column::EnvelopeId.eq(env.id))
+            .filter(balance_snapshot::Column::SnapshotDate.eq(date))
+            .one(db)
+            .await?;
+        if existing.is_some() {
+            continue;
+        }
+
+        balance_snapshot::ActiveModel {
+            envelope_id: Set(env.id),
+            balance: Set(env.balance),
+            snapshot_date: Set(date),
+            recorded_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        created += 1;
+    }
+
+    Ok(created)
+}
+
+/// Deletes snapshots older than `retention_days` before `today`.
+///
+/// # Returns
+/// The number of snapshots deleted
+///
+/// # Errors
+/// Returns an error if the database delete fails.
+pub async fn prune_snapshots(
+    db: &DatabaseConnection,
+    today: NaiveDate,
+    retention_days: u64,
+) -> Result<u64> {
+    let Some(cutoff) = today.checked_sub_days(Days::new(retention_days)) else {
+        return Ok(0);
+    };
+
+    let result = BalanceSnapshot::delete_many()
+        .filter(balance_snapshot::Column::SnapshotDate.lt(cutoff))
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected)
+}
+
+/// Retrieves an envelope's snapshots for the last `days` days up to `today`, oldest first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_trend(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    today: NaiveDate,
+    days: u64,
+) -> Result<Vec<balance_snapshot::Model>> {
+    let start = today
+        .checked_sub_days(Days::new(days))
+        .unwrap_or(NaiveDate::MIN);
+
+    BalanceSnapshot::find()
+        .filter(balance_snapshot::Column::EnvelopeId.eq(envelope_id))
+        .filter(balance_snapshot::Column::SnapshotDate.gt(start))
+        .filter(balance_snapshot::Column::SnapshotDate.lte(today))
+        .order_by_asc(balance_snapshot::Column::SnapshotDate)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Renders values as a text sparkline, scaled between their minimum and maximum.
+///
+/// A flat series is drawn at mid height.
+#[must_use]
+pub fn sparkline(values: &[f64]) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+
+    values
+        .iter()
+        .map(|value| {
+            if range <= f64::EPSILON {
+                return SPARK_LEVELS[SPARK_LEVELS.len() / 2];
+            }
+            // Scaled into 0..=7, so the cast can't truncate or wrap
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let level = (((value - min) / range) * 7.0).round() as usize;
+            SPARK_LEVELS[level.min(SPARK_LEVELS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Formats an envelope's snapshots as a sparkline with start, end, low, and high values.
+///
+/// # Errors
+/// Returns an error if string formatting fails.
+pub fn format_trend(
+    envelope_name: &str,
+    snapshots: &[balance_snapshot::Model],
+    currency: Currency,
+) -> Result<String> {
+    let mut output = String::new();
+    let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+        writeln!(
+            &mut output,
+            "No balance snapshots for '{envelope_name}' yet. Snapshots are recorded nightly."
+        )?;
+        return Ok(output);
+    };
+
+    let balances: Vec<f64> = snapshots.iter().map(|s| s.balance).collect();
+    let low = balances.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = balances.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    writeln!(
+        &mut output,
+        "📈 **{envelope_name}** balance, last {TREND_DAYS} days"
+    )?;
+    writeln!(&mut output, "`{}`", sparkline(&balances))?;
+    writeln!(
+        &mut output,
+        "{}: {} → {}: {}",
+        first.snapshot_date,
+        currency.format(first.balance),
+        last.snapshot_date,
+        currency.format(last.balance)
+    )?;
+    writeln!(
+        &mut output,
+        "Low {} · High {}",
+        currency.format(low),
+        currency.format(high)
+    )?;
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::test_utils::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_snapshots_once_per_day() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+
+        assert_eq!(record_snapshots(&db, day(1)).await?, 1);
+        assert_eq!(record_snapshots(&db, day(1)).await?, 0);
+
+        create_test_transaction(&db, env.id, 40.0).await?;
+        assert_eq!(record_snapshots(&db, day(2)).await?, 1);
+
+        let trend = get_trend(&db, env.id, day(2), TREND_DAYS).await?;
+        let balances: Vec<f64> = trend.iter().map(|s| s.balance).collect();
+        assert_eq!(balances, vec![0.0, 40.0]);
+
+        // The window excludes snapshots older than the requested number of days
+        let recent = get_trend(&db, env.id, day(2), 1).await?;
+        assert_eq!(recent.len(), 1);
+
+        let formatted = format_trend("groceries", &trend, Currency::default())?;
+        assert!(formatted.contains("2025-03-01: $0.00 → 2025-03-02: $40.00"));
+        let euros = format_trend("groceries", &trend, Currency::from_code("EUR"))?;
+        assert!(euros.contains("Low €0.00 · High €40.00"));
+        assert!(
+            format_trend("groceries", &[], Currency::default())?.contains("No balance snapshots")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prune_snapshots() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        for d in 1..=5 {
+            record_snapshots(&db, day(d)).await?;
+        }
+
+        assert_eq!(prune_snapshots(&db, day(5), 2).await?, 2);
+        let remaining = get_trend(&db, env.id, day(5), TREND_DAYS).await?;
+        assert_eq!(remaining.first().unwrap().snapshot_date, day(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0.0, 50.0, 100.0]), "▁▅█");
+        assert_eq!(sparkline(&[20.0, 20.0]), "▅▅");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
@@ -0,0 +1,155 @@
+//! System state business logic - Key-value access to the `system_state` table.
+//!
+//! Provides small helpers for reading and writing string values by key. Higher-level
+//! modules (monthly updates, setup, settings) build typed accessors on top of these
+//! so that the upsert logic lives in one place.
+
+use crate::{
+    entities::{SystemState, system_state},
+    errors::Result,
+};
+use chrono::Utc;
+use sea_orm::{Set, prelude::*};
+
+/// Retrieves the raw string value stored under a key, if any.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_value<C>(db: &C, key: &str) -> Result<Option<String>>
+where
+    C: ConnectionTrait,
+{
+    let state = SystemState::find()
+        .filter(system_state::Column::Key.eq(key))
+        .one(db)
+        .await?;
+
+    Ok(state.map(|s| s.value))
+}
+
+/// Stores a string value under a key, inserting or updating as needed.
+///
+/// # Errors
+/// Returns an error if the database query or write fails.
+pub async fn set_value<C>(db: &C, key: &str, value: &str) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let now = Utc::now().naive_utc();
+
+    // Check if the key exists
+    let existing = SystemState::find()
+        .filter(system_state::Column::Key.eq(key))
+        .one(db)
+        .await?;
+
+    if let Some(state) = existing {
+        // Update existing record
+        let mut active_model: system_state::ActiveModel = state.into();
+        active_model.value = Set(value.to_string());
+        active_model.updated_at = Set(now);
+        active_model.update(db).await?;
+    } else {
+        // Insert new record
+        let new_state = system_state::ActiveModel {
+            key: Set(key.to_string()),
+            value: Set(value.to_string()),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        new_state.insert(db).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes a key from the `system_state` table. Missing keys are ignored.
+///
+/// # Errors
+/// Returns an error if the database delete fails.
+pub async fn delete_value<C>(db: &C, key: &str) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    SystemState::delete_many()
+        .filter(system_state::Column::Key.eq(key))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Retrieves all key-value pairs whose key starts with the given prefix.
+///
+/// Used for namespaced entries such as `nickname:<user_id>`.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_values_with_prefix<C>(db: &C, prefix: &str) -> Result<Vec<(String, String)>>
+where
+    C: ConnectionTrait,
+{
+    let states = SystemState::find()
+        .filter(system_state::Column::Key.starts_with(prefix))
+        .all(db)
+        .await?;
+
+    Ok(states.into_iter().map(|s| (s.key, s.value)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_get_value_missing() -> Result<()> {
+        let db = setup_test_db().await?;
+        assert_eq!(get_value(&db, "missing").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_value_inserts_and_updates() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        set_value(&db, "currency", "USD").await?;
+        assert_eq!(get_value(&db, "currency").await?, Some("USD".to_string()));
+
+        set_value(&db, "currency", "CAD").await?;
+        assert_eq!(get_value(&db, "currency").await?, Some("CAD".to_string()));
+
+        // Only one row per key
+        let count = SystemState::find()
+            .filter(system_state::Column::Key.eq("currency"))
+            .count(&db)
+            .await?;
+        assert_eq!(count, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_value_and_prefix_lookup() -> Result<()> {
+        let db = setup_test_db().await?;
+
+        set_value(&db, "nickname:1", "Alice").await?;
+        set_value(&db, "nickname:2", "Bob").await?;
+        set_value(&db, "currency", "USD").await?;
+
+        let mut nicknames = get_values_with_prefix(&db, "nickname:").await?;
+        nicknames.sort();
+        assert_eq!(
+            nicknames,
+            vec![
+                ("nickname:1".to_string(), "Alice".to_string()),
+                ("nickname:2".to_string(), "Bob".to_string()),
+            ]
+        );
+
+        delete_value(&db, "nickname:1").await?;
+        assert_eq!(get_value(&db, "nickname:1").await?, None);
+        assert_eq!(get_values_with_prefix(&db, "nickname:").await?.len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+//! Monthly statement business logic - Archived summaries of closed budget months.
+//!
+//! When the monthly update closes a budget month, [`record_statements`] stores one
+//! statement per envelope: its opening and closing balance, total spending, transaction
+//! count, and allocation. Balances are worked back from the envelope's current balance and
+//! the transactions since the month started, so no extra bookkeeping is needed while the
+//! month runs. Statements are read back with [`get_statements`] and outlive the
+//! transactions they summarize.
+
+use crate::{
+    core::{
+        calendar::{self, BudgetCycle},
+        report,
+    },
+    entities::{Envelope, MonthlyStatement, Transaction, envelope, monthly_statement, transaction},
+    errors::{Error, Result},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::{QueryOrder, QuerySelect, Set, prelude::*};
+use std::collections::{HashMap, HashSet};
+
+/// Format of a statement month, e.g. `2025-01`.
+const MONTH_FORMAT: &str = "%Y-%m";
+
+/// A statement as seen by a particular household member.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementView {
+    /// The viewer may see the statement's figures
+    Visible(monthly_statement::Model),
+    /// The statement belongs to another member's private envelope; only its name is shown
+    Hidden {
+        /// Envelope name when the statement was generated
+        envelope_name: String,
+    },
+}
+
+/// Household totals across the statements of a month a viewer can see.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StatementTotals {
+    /// First and last day of the budget month, if any statement is visible
+    pub period: Option<(NaiveDate, NaiveDate)>,
+    /// Total opening balance; `None` if any statement lacks its balances
+    pub open_balance: Option<f64>,
+    /// Total closing balance; `None` if any statement lacks its balances
+    pub close_balance: Option<f64>,
+    /// Total spending, less refunds
+    pub total_spent: f64,
+    /// Total number of transactions
+    pub transaction_count: i64,
+}
+
+impl StatementTotals {
+    /// Totals the visible statements among `views`; hidden ones are left out.
+    #[must_use]
+    pub fn from_views(views: &[StatementView]) -> Self {
+        let mut totals = Self {
+            open_balance: Some(0.0),
+            close_balance: Some(0.0),
+            ..Self::default()
+        };
+        for view in views {
+            let StatementView::Visible(s) = view else {
+                continue;
+            };
+            totals.period.get_or_insert((s.period_start, s.period_end));
+            totals.open_balance = totals.open_balance.zip(s.open_balance).map(|(a, b)| a + b);
+            totals.close_balance = totals
+                .close_balance
+                .zip(s.close_balance)
+                .map(|(a, b)| a + b);
+            totals.total_spent += s.total_spent;
+            totals.transaction_count += s.transaction_count;
+        }
+        totals
+    }
+}
+
+/// Returns the statement month label (`YYYY-MM`) for a budget cycle.
+///
+/// Cycles are named after the month they start in, so with a cycle start day of 25 the
+/// month running from January 25 to February 24 is `2025-01`.
+#[must_use]
+pub fn month_label(cycle: &BudgetCycle) -> String {
+    cycle.start.format(MONTH_FORMAT).to_string()
+}
+
+/// Validates a month entered as `YYYY-MM` and returns it in canonical form.
+///
+/// # Errors
+/// Returns `Error::Config` if the value isn't a valid `YYYY-MM` month.
+pub fn parse_month(value: &str) -> Result<String> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(&format!("{value}-01"), "%Y-%m-%d")
+        .map(|date| date.format(MONTH_FORMAT).to_string())
+        .map_err(|_| Error::Config {
+            message: format!("Invalid month '{value}'. Use YYYY-MM, e.g. 2025-01."),
+        })
+}
+
+/// Stores a statement for each envelope covering the budget month `closing`.
+///
+/// Must run before the monthly update changes balances. Envelopes that already have a
+/// statement for the month are skipped, so a repeated call doesn't duplicate them.
+///
+/// # Returns
+/// The number of statements created
+///
+/// # Errors
+/// Returns an error if a database query or insert fails.
+pub async fn record_statements<C>(
+    db: &C,
+    envelopes: &[envelope::Model],
+    closing: &BudgetCycle,
+    now: DateTime<Utc>,
+) -> Result<usize>
+where
+    C: ConnectionTrait,
+{
+    let month = month_label(closing);
+    let existing: HashSet<i64> = MonthlyStatement::find()
+        .select_only()
+        .column(monthly_statement::Column::EnvelopeId)
+        .filter(monthly_statement::Column::Month.eq(&month))
+        .into_tuple::<i64>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+
+    let totals = month_totals(db, closing).await?;
+    let mut created = 0;
+    for env in envelopes.iter().filter(|env| !existing.contains(&env.id)) {
+        let totals = totals.get(&env.id).copied().unwrap_or_default();
+        let close_balance = env.balance - totals.net_after;
+        monthly_statement::ActiveModel {
+            envelope_id: Set(env.id),
+            envelope_name: Set(env.name.clone()),
+            month: Set(month.clone()),
+            period_start: Set(closing.start),
+            period_end: Set(closing.end),
+            open_balance: Set(Some(close_balance - totals.net)),
+            close_balance: Set(Some(close_balance)),
+            total_spent: Set(totals.spent),
+            transaction_count: Set(totals.count),
+            allocation: Set(Some(env.allocation)),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        created += 1;
+    }
+
+    Ok(created)
+}
+
+/// Records the balance the monthly update opened the month after `month` with on an
+/// envelope's statement for `month`.
+///
+/// # Errors
+/// Returns an error if the database update fails.
+pub async fn record_opening<C>(db: &C, envelope_id: i64, month: &str, balance: f64) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    MonthlyStatement::update_many()
+        .col_expr(
+            monthly_statement::Column::AppliedOpening,
+            Expr::value(Some(balance)),
+        )
+        .filter(monthly_statement::Column::EnvelopeId.eq(envelope_id))
+        .filter(monthly_statement::Column::Month.eq(month))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Net change each envelope's transactions made after the budget month `closing` ended.
+///
+/// Backing it out of an envelope's current balance gives the balance it closed the month
+/// with, as its statement records.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn net_after_close<C>(db: &C, closing: &BudgetCycle) -> Result<HashMap<i64, f64>>
+where
+    C: ConnectionTrait,
+{
+    Ok(month_totals(db, closing)
+        .await?
+        .into_iter()
+        .map(|(envelope_id, totals)| (envelope_id, totals.net_after))
+        .collect())
+}
+
+/// Totals each envelope's transactions from the start of the budget month `closing` on.
+async fn month_totals<C>(db: &C, closing: &BudgetCycle) -> Result<HashMap<i64, MonthTotals>>
+where
+    C: ConnectionTrait,
+{
+    let (start, end) = calendar::utc_bounds(db, closing.start, closing.end).await?;
+
+    // Everything since the month started: transactions after it closed are backed out of
+    // the current balance to find the closing balance
+    let transactions = Transaction::find_active()
+        .filter(transaction::Column::Timestamp.gte(start))
+        .all(db)
+        .await?;
+    let mut totals: HashMap<i64, MonthTotals> = HashMap::new();
+    for tx in &transactions {
+        totals.entry(tx.envelope_id).or_default().add(tx, end);
+    }
+    Ok(totals)
+}
+
+/// Running totals of one envelope's transactions while building a statement.
+#[derive(Debug, Clone, Copy, Default)]
+struct MonthTotals {
+    /// Net change during the month
+    net: f64,
+    /// Spending during the month, less refunds
+    spent: f64,
+    /// Transactions during the month
+    count: i64,
+    /// Net change after the month closed
+    net_after: f64,
+}
+
+impl MonthTotals {
+    /// Adds a transaction recorded on or after the month's start; `end` is when the
+    /// month closed.
+    fn add(&mut self, tx: &transaction::Model, end: DateTime<Utc>) {
+        if tx.timestamp >= end {
+            self.net_after += tx.amount;
+            return;
+        }
+        self.net += tx.amount;
+        self.count += 1;
+        if tx.amount < 0.0 || tx.transaction_type.is_refund() {
+            self.spent -= tx.amount;
+        }
+    }
+}
+
+/// Retrieves the statements for a month as seen by the given viewer, by envelope name.
+///
+/// Statements of other members' private envelopes are hidden (see
+/// [`report::can_view`]).
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_statements(
+    db: &DatabaseConnection,
+    month: &str,
+    viewer_id: &str,
+) -> Result<Vec<StatementView>> {
+    let statements = MonthlyStatement::find()
+        .filter(monthly_statement::Column::Month.eq(month))
+        .order_by_asc(monthly_statement::Column::EnvelopeName)
+        .all(db)
+        .await?;
+
+    let envelope_ids: Vec<i64> = statements.iter().map(|s| s.envelope_id).collect();
+    let envelopes: HashMap<i64, envelope::Model> = Envelope::find()
+        .filter(envelope::Column::Id.is_in(envelope_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|env| (env.id, env))
+        .collect();
+
+    Ok(statements
+        .into_iter()
+        .map(|statement| {
+            let visible = envelopes
+                .get(&statement.envelope_id)
+                .is_none_or(|env| report::can_view(env, viewer_id));
+            if visible {
+                StatementView::Visible(statement)
+            } else {
+                StatementView::Hidden {
+                    envelope_name: statement.envelope_name,
+                }
+            }
+        })
+        .collect())
+}
+
+/// Lists the months that have statements, newest first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_statement_months(db: &DatabaseConnection) -> Result<Vec<String>> {
+    MonthlyStatement::find()
+        .select_only()
+        .column(monthly_statement::Column::Month)
+        .distinct()
+        .order_by_desc(monthly_statement::Column::Month)
+        .into_tuple::<String>()
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{
+        core::envelope as envelope_core, entities::transaction::ActiveModel, test_utils::*,
+    };
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// Backdates a transaction to noon UTC on `day`.
+    async fn backdate(db: &DatabaseConnection, tx: transaction::Model, day: NaiveDate) {
+        let mut active_model: ActiveModel = tx.into();
+        active_model.timestamp = Set(day.and_hms_opt(12, 0, 0).unwrap().and_utc());
+        active_model.update(db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_record_statements() -> Result<()> {
+        let db = setup_test_db().await?;
+        let env = create_custom_envelope(
+            &db,
+            "game",
+            Some("owner".to_string()),
+            "quality_of_life",
+            80.0,
+            true,
+            false,
+        )
+        .await?;
+        let before = create_test_transaction(&db, env.id, 100.0).await?;
+        backdate(&db, before, date(2024, 12, 20)).await;
+        let spend = create_test_transaction(&db, env.id, -30.0).await?;
+        backdate(&db, spend, date(2025, 1, 10)).await;
+        let deposit = create_test_transaction(&db, env.id, 20.0).await?;
+        backdate(&db, deposit, date(2025, 1, 15)).await;
+        // After the month closed
+        let later = create_test_transaction(&db, env.id, -5.0).await?;
+        backdate(&db, later, date(2025, 2, 1)).await;
+
+        let january = BudgetCycle::containing(date(2025, 1, 10), 1);
+        let envelopes = envelope_core::get_all_active_envelopes(&db).await?;
+        assert_eq!(
+            record_statements(&db, &envelopes, &january, Utc::now()).await?,
+            1
+        );
+        // Repeating the close doesn't duplicate statements
+        assert_eq!(
+            record_statements(&db, &envelopes, &january, Utc::now()).await?,
+            0
+        );
+
+        let views = get_statements(&db, "2025-01", "someone").await?;
+        let [StatementView::Visible(statement)] = views.as_slice() else {
+            return Err(Error::Config {
+                message: format!("expected one visible statement, got {views:?}"),
+            });
+        };
+        assert_eq!(statement.open_balance, Some(100.0));
+        assert_eq!(statement.close_balance, Some(90.0));
+        assert_eq!(statement.total_spent, 30.0);
+        assert_eq!(statement.transaction_count, 2);
+        assert_eq!(statement.period_end, date(2025, 1, 31));
+        assert_eq!(
+            get_statement_months(&db).await?,
+            vec!["2025-01".to_string()]
+        );
+
+        // Private envelopes are hidden from other members
+        envelope_core::set_private(&db, env.id, true).await?;
+        let views = get_statements(&db, "2025-01", "someone").await?;
+        assert!(matches!(views[0], StatementView::Hidden { .. }));
+        let views = get_statements(&db, "2025-01", "owner").await?;
+        assert!(matches!(views[0], StatementView::Visible(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_month() {
+        assert_eq!(parse_month(" 2025-01 ").unwrap(), "2025-01");
+        assert_eq!(parse_month("2025-1").unwrap(), "2025-01");
+        assert!(parse_month("2025-13").is_err());
+        assert!(parse_month("January").is_err());
+    }
+}
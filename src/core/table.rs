@@ -0,0 +1,241 @@
+//! Fixed-width tables for Discord code blocks.
+//!
+//! A [`Table`] lines its columns up by padding every cell to the widest one, so it reads
+//! as a grid inside a code block. Long cells are cut to the column's maximum width with
+//! an ellipsis. [`Table::preview`] renders only the first rows, for showing what a file
+//! holds before it is sent or applied.
+
+/// Rows shown by a preview of an exported or imported file.
+pub const PREVIEW_ROWS: usize = 10;
+
+/// Widest a cell may be unless its column says otherwise.
+const DEFAULT_MAX_WIDTH: usize = 24;
+
+/// Which side of its column a cell is padded against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// Text, padded on the right
+    Left,
+    /// Numbers, padded on the left
+    Right,
+}
+
+/// One column's header, alignment, and maximum width.
+#[derive(Debug, Clone)]
+pub struct Column {
+    header: String,
+    align: Align,
+    max_width: usize,
+}
+
+impl Column {
+    /// A left-aligned column, for text.
+    #[must_use]
+    pub fn left(header: &str) -> Self {
+        Self {
+            header: header.to_string(),
+            align: Align::Left,
+            max_width: DEFAULT_MAX_WIDTH,
+        }
+    }
+
+    /// A right-aligned column, for numbers.
+    #[must_use]
+    pub fn right(header: &str) -> Self {
+        Self {
+            align: Align::Right,
+            ..Self::left(header)
+        }
+    }
+
+    /// Cuts cells longer than `max_width` characters.
+    #[must_use]
+    pub const fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width;
+        self
+    }
+}
+
+/// A table of text cells under a header row.
+#[derive(Debug, Clone)]
+pub struct Table {
+    columns: Vec<Column>,
+    /// Rows of cells; `None` is a separator line
+    rows: Vec<Option<Vec<String>>>,
+}
+
+impl Table {
+    /// An empty table with the given columns.
+    #[must_use]
+    pub const fn new(columns: Vec<Column>) -> Self {
+        Self {
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a row. Missing cells are left blank and extra cells are dropped.
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(Some(cells));
+    }
+
+    /// Adds a separator line, e.g. above a totals row.
+    pub fn separator(&mut self) {
+        self.rows.push(None);
+    }
+
+    /// Number of rows, not counting the header and separators.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.rows.iter().flatten().count()
+    }
+
+    /// Whether the table has no rows.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Renders the whole table, one line per row, without a trailing newline.
+    #[must_use]
+    pub fn render(&self) -> String {
+        self.render_rows(&self.rows)
+    }
+
+    /// Renders the header and the first `limit` rows, followed by a line counting the
+    /// rows left out, if any.
+    #[must_use]
+    pub fn preview(&self, limit: usize) -> String {
+        let mut shown = 0;
+        let rows: Vec<Option<Vec<String>>> = self
+            .rows
+            .iter()
+            .take_while(|row| {
+                if row.is_some() {
+                    shown += 1;
+                }
+                shown <= limit
+            })
+            .cloned()
+            .collect();
+        let mut text = self.render_rows(&rows);
+        let hidden = self.len().saturating_sub(limit);
+        if hidden > 0 {
+            text = format!("{text}\n… and {hidden} more row(s)");
+        }
+        text
+    }
+
+    fn render_rows(&self, rows: &[Option<Vec<String>>]) -> String {
+        let cell = |row: &[String], index: usize, column: &Column| {
+            let text = row.get(index).map_or("", String::as_str);
+            if text.chars().count() > column.max_width {
+                let mut cut: String = text
+                    .chars()
+                    .take(column.max_width.saturating_sub(1))
+                    .collect();
+                cut.push('…');
+                cut
+            } else {
+                text.to_string()
+            }
+        };
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| column.header.clone())
+            .collect();
+        let cells: Vec<Vec<String>> = std::iter::once(&header)
+            .chain(rows.iter().flatten())
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .enumerate()
+                    .map(|(index, column)| cell(row, index, column))
+                    .collect()
+            })
+            .collect();
+        let widths: Vec<usize> = (0..self.columns.len())
+            .map(|index| {
+                cells
+                    .iter()
+                    .map(|row| row[index].chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let line = |row: &[String]| {
+            let padded: Vec<String> = row
+                .iter()
+                .zip(&self.columns)
+                .zip(&widths)
+                .map(|((text, column), &width)| match column.align {
+                    Align::Left => format!("{text:<width$}"),
+                    Align::Right => format!("{text:>width$}"),
+                })
+                .collect();
+            padded.join(" ").trim_end().to_string()
+        };
+        let total_width = widths.iter().sum::<usize>() + widths.len().saturating_sub(1);
+
+        let mut lines = vec![line(&cells[0])];
+        let mut data = cells[1..].iter();
+        for row in rows {
+            match row {
+                Some(_) => lines.extend(data.next().map(|cells| line(cells))),
+                None => lines.push("-".repeat(total_width)),
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(rows: u32) -> Table {
+        let mut table = Table::new(vec![
+            Column::left("Name").max_width(8),
+            Column::right("Amount"),
+        ]);
+        for index in 0..rows {
+            table.row(vec![
+                format!("Row {index}"),
+                format!("{:.2}", f64::from(index) * 10.5),
+            ]);
+        }
+        table
+    }
+
+    #[test]
+    fn test_render_aligns_columns() {
+        let mut table = sample(2);
+        table.row(vec![
+            "Groceries and more".to_string(),
+            "1000.00".to_string(),
+        ]);
+        table.separator();
+        table.row(vec!["Total".to_string()]);
+        assert_eq!(
+            table.render(),
+            "Name      Amount\n\
+             Row 0       0.00\n\
+             Row 1      10.50\n\
+             Groceri… 1000.00\n\
+             ----------------\n\
+             Total"
+        );
+        assert_eq!(table.len(), 4);
+    }
+
+    #[test]
+    fn test_preview_counts_hidden_rows() {
+        let table = sample(12);
+        let preview = table.preview(PREVIEW_ROWS);
+        assert_eq!(preview.lines().count(), PREVIEW_ROWS + 2);
+        assert!(preview.ends_with("… and 2 more row(s)"));
+        assert_eq!(sample(3).preview(PREVIEW_ROWS), sample(3).render());
+    }
+}
@@ -5,12 +5,54 @@
 //! to maintain data consistency. The module includes comprehensive validation to prevent invalid
 //! transactions such as zero amounts or transactions that would result in negative envelope balances.
 //! All functions are async and return Result types for proper error handling throughout the system.
+//!
+//! Spends can be recorded as pending (e.g. a card authorization) with
+//! [`create_pending_spend`]. A pending spend reduces the envelope balance right away, so
+//! `balance` is always the *available* balance; the *cleared* balance leaves pending spends
+//! out (see [`get_pending_totals`]). [`clear_transaction`] later confirms the posted amount,
+//! adjusting the balance by any difference.
+//!
+//! Spends record a merchant, taken from the first word of the description unless one is
+//! set with [`set_merchant`]; refunds share their spend's. [`normalize_merchant`] keeps
+//! spellings of the same merchant together for [`get_top_merchants`].
+//!
+//! Spends on measurable goods can record the quantity bought with [`set_quantity`], so
+//! [`unit_price`] shows what each gallon or pound cost.
+//!
+//! With round-ups on, spends are rounded up to the next dollar as they are recorded (see
+//! [`round_up`]).
+//!
+//! Admins can remove many transactions at once: a [`TransactionCriteria`] selects them,
+//! [`preview_purge`] counts and totals the selection, and [`purge_transactions`]
+//! soft-deletes it, reversing each transaction's effect on its envelope like
+//! [`delete_transaction`]. Purged transactions stay in the table but are left out of every
+//! read (see [`crate::entities::transaction::Entity::find_active`]).
 
 use crate::{
-    entities::{Envelope, transaction},
+    config::database::TRANSACTION_SEARCH_TABLE,
+    core::{
+        approval, bill,
+        cache::{self, CachedTable},
+        calendar, category_cap, envelope as envelope_core, envelope_permission,
+        fx::Conversion,
+        journal::{self, JournalEvent},
+        round_up, state,
+    },
+    entities::{
+        Envelope, MonthlyStatement, TransactionType, envelope, monthly_statement, transaction,
+    },
     errors::{Error, Result},
 };
-use sea_orm::{QueryOrder, Set, TransactionTrait, prelude::*};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use sea_orm::{
+    Condition, DatabaseBackend, QueryOrder, QuerySelect, Set, TransactionTrait,
+    prelude::*,
+    sea_query::{Query, SimpleExpr},
+};
+use std::collections::HashMap;
+
+/// `system_state` key prefix for per-user daily spending limits, followed by the user ID.
+const USER_DAILY_LIMIT_PREFIX: &str = "daily_limit:";
 
 /// Creates a new transaction and automatically updates the envelope balance.
 ///
@@ -18,122 +60,1264 @@ use sea_orm::{QueryOrder, Set, TransactionTrait, prelude::*};
 /// and checks that the transaction won't result in a negative envelope balance. Upon successful
 /// creation, the envelope's balance is automatically updated to reflect the new transaction amount.
 ///
+/// The call is idempotent on `message_id`: if a transaction with the same Discord message or
+/// interaction ID already exists (e.g. Discord retried the interaction), that original
+/// transaction is returned and the balance is left untouched.
+///
+/// Newly created transactions are written to the transaction journal, if one is
+/// configured. To add several spends inside one database transaction, use
+/// [`create_unjournaled_transaction`] and journal them after the outer commit.
+///
 /// # Arguments
 /// * `envelope_id` - The envelope to transact against
 /// * `amount` - Transaction amount (positive for income, negative for expenses)
 /// * `description` - Description of the transaction
 /// * `user_id` - Discord user ID who created the transaction
-/// * `message_id` - Optional Discord message ID for reference
-/// * `transaction_type` - Type of transaction ("spend", "addfunds", etc.)
+/// * `message_id` - Optional Discord message or interaction ID (idempotency key)
+/// * `transaction_type` - Kind of transaction (spend, add funds, etc.)
+///
+/// # Errors
+/// Returns an error if:
+/// - The amount is zero or not finite (NaN, infinity)
+/// - The envelope does not exist or is deleted
+/// - The transaction would result in a negative balance
+/// - A spend or product use is made from a frozen envelope (`Error::EnvelopeFrozen`)
+/// - A spend is made by a member who isn't one of the envelope's spenders
+///   (`Error::SpendNotPermitted`)
+/// - A spend is above the large transaction threshold (`Error::ApprovalRequired`)
+/// - A spend would take today's spending past a daily limit (`Error::DailyLimitExceeded`)
+/// - A spend would take the envelope below its minimum balance (`Error::BelowMinimumBalance`)
+/// - A spend would take its category past a hard monthly cap (`Error::CategoryCapExceeded`)
+/// - The database transaction fails
+pub async fn create_transaction<C>(
+    db: &C,
+    envelope_id: i64,
+    amount: f64,
+    description: String,
+    user_id: String,
+    message_id: Option<String>,
+    transaction_type: TransactionType,
+) -> Result<transaction::Model>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    create_cleared_transaction(
+        db,
+        envelope_id,
+        amount,
+        description,
+        user_id,
+        message_id,
+        transaction_type,
+        Clearance::default(),
+    )
+    .await
+}
+
+/// Like [`create_transaction`], for a spend already cleared of some guardrails (see
+/// [`Clearance`]).
+///
+/// # Errors
+/// Returns an error under the same conditions as [`create_transaction`], less the
+/// guardrails in `clearance`.
+#[allow(clippy::too_many_arguments)] // The spend's fields, plus its clearance
+pub async fn create_cleared_transaction<C>(
+    db: &C,
+    envelope_id: i64,
+    amount: f64,
+    description: String,
+    user_id: String,
+    message_id: Option<String>,
+    transaction_type: TransactionType,
+    clearance: Clearance,
+) -> Result<transaction::Model>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    insert_transaction(
+        db,
+        NewTransaction {
+            envelope_id,
+            amount,
+            description,
+            user_id,
+            message_id,
+            transaction_type,
+            original: None,
+            refund_of: None,
+            pending: false,
+            merchant: None,
+            location: None,
+            clearance,
+        },
+    )
+    .await
+    .map(journal_created)
+}
+
+/// Like [`create_transaction`], but leaves journaling to the caller.
+///
+/// Used when `db` is an open database transaction that commits several spends together
+/// (see [`crate::core::bundle::use_bundle`]): journaling inside it could record spends
+/// that are later rolled back. Also returns whether the transaction was newly created
+/// (`false` when an existing one was returned for a retried `message_id`).
+///
+/// # Errors
+/// Returns an error under the same conditions as [`create_transaction`].
+pub(crate) async fn create_unjournaled_transaction<C>(
+    db: &C,
+    envelope_id: i64,
+    amount: f64,
+    description: String,
+    user_id: String,
+    message_id: Option<String>,
+    transaction_type: TransactionType,
+) -> Result<(transaction::Model, bool)>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    insert_transaction(
+        db,
+        NewTransaction {
+            envelope_id,
+            amount,
+            description,
+            user_id,
+            message_id,
+            transaction_type,
+            original: None,
+            refund_of: None,
+            pending: false,
+            merchant: None,
+            location: None,
+            clearance: Clearance::default(),
+        },
+    )
+    .await
+}
+
+/// Journals a transaction returned by [`insert_transaction`] if it was newly created.
+fn journal_created((transaction, created): (transaction::Model, bool)) -> transaction::Model {
+    if created {
+        journal::record(JournalEvent::Created, &transaction);
+    }
+    transaction
+}
+
+/// Creates a transaction entered in a foreign currency.
+///
+/// Behaves like [`create_transaction`], using `conversion.converted_amount` (signed:
+/// negative for expenses) as the transaction amount and recording the original amount
+/// and currency alongside it.
+///
+/// # Errors
+/// Returns an error under the same conditions as [`create_transaction`].
+#[allow(clippy::too_many_arguments)] // The spend's fields, plus its clearance
+pub async fn create_converted_transaction(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    conversion: &Conversion,
+    description: String,
+    user_id: String,
+    message_id: Option<String>,
+    transaction_type: TransactionType,
+    clearance: Clearance,
+) -> Result<transaction::Model> {
+    insert_transaction(
+        db,
+        NewTransaction {
+            envelope_id,
+            amount: conversion.converted_amount,
+            description,
+            user_id,
+            message_id,
+            transaction_type,
+            original: Some((
+                conversion.original_amount,
+                conversion.original_currency.clone(),
+            )),
+            refund_of: None,
+            pending: false,
+            merchant: None,
+            location: None,
+            clearance,
+        },
+    )
+    .await
+    .map(journal_created)
+}
+
+/// Records a pending spend, such as a card authorization whose posted amount may differ.
+///
+/// Behaves like [`create_transaction`] with a negative `amount` of type
+/// [`TransactionType::Spend`], and the balance is reduced right away. `original` is the
+/// amount and currency code as entered for foreign-currency spends. Confirm the posted
+/// amount later with [`clear_transaction`].
+///
+/// # Errors
+/// Returns an error under the same conditions as [`create_transaction`], or
+/// `Error::InvalidAmount` if `amount` is not negative.
+#[allow(clippy::too_many_arguments)] // The spend's fields, plus its clearance
+pub async fn create_pending_spend(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    amount: f64,
+    original: Option<(f64, String)>,
+    description: String,
+    user_id: String,
+    message_id: Option<String>,
+    clearance: Clearance,
+) -> Result<transaction::Model> {
+    if amount >= 0.0 {
+        return Err(Error::InvalidAmount { amount });
+    }
+    insert_transaction(
+        db,
+        NewTransaction {
+            envelope_id,
+            amount,
+            description,
+            user_id,
+            message_id,
+            transaction_type: TransactionType::Spend,
+            original,
+            refund_of: None,
+            pending: true,
+            merchant: None,
+            location: None,
+            clearance,
+        },
+    )
+    .await
+    .map(journal_created)
+}
+
+/// Clears a pending spend, optionally with a posted amount that differs from the
+/// authorized one (e.g. a tip added to a restaurant bill).
+///
+/// `posted_amount` is the positive amount spent; the envelope balance is adjusted by the
+/// difference from the pending amount, and the spend's round-up is worked out again.
+/// Without it, the pending amount is kept as is.
+///
+/// # Errors
+/// Returns an error if:
+/// - The transaction does not exist (`Error::TransactionNotFound`)
+/// - The transaction is not pending (`Error::Config`)
+/// - The posted amount is not positive and finite (`Error::InvalidAmount`)
+/// - A posted amount above the pending one fails a spend guardrail for the difference:
+///   the envelope is frozen, the difference would pass a daily limit, the approval
+///   threshold, the minimum balance, zero, or a hard category cap
+/// - The database transaction fails
+pub async fn clear_transaction(
+    db: &DatabaseConnection,
+    transaction_id: i64,
+    posted_amount: Option<f64>,
+) -> Result<transaction::Model> {
+    if let Some(amount) = posted_amount
+        && (!amount.is_finite() || amount <= 0.0)
+    {
+        return Err(Error::InvalidAmount { amount });
+    }
+
+    let txn = db.begin().await?;
+    let pending = crate::entities::Transaction::find_active_by_id(transaction_id)
+        .one(&txn)
+        .await?
+        .ok_or(Error::TransactionNotFound { id: transaction_id })?;
+    if !pending.pending {
+        return Err(Error::Config {
+            message: format!("Transaction #{transaction_id} is not pending"),
+        });
+    }
+
+    let amount = posted_amount.map_or(pending.amount, |posted| -posted);
+    let delta = amount - pending.amount;
+    // The round-up of the pending amount no longer applies; it is redone once cleared
+    let removed = if delta == 0.0 {
+        Vec::new()
+    } else {
+        round_up::remove_round_up(&txn, transaction_id).await?
+    };
+    if delta != 0.0 {
+        let envelope = Envelope::find_by_id(pending.envelope_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| Error::EnvelopeNotFound {
+                name: pending.envelope_id.to_string(),
+            })?;
+        // Posting more than was pending is spending the difference
+        if delta < 0.0 {
+            check_guardrails(
+                &txn,
+                &envelope,
+                &pending.user_id,
+                pending.transaction_type,
+                delta,
+                Clearance::default(),
+            )
+            .await?;
+        }
+        crate::core::envelope::update_envelope_balance_atomic(&txn, pending.envelope_id, delta)
+            .await?;
+    }
+
+    let mut active_model: transaction::ActiveModel = pending.into();
+    active_model.amount = Set(amount);
+    active_model.pending = Set(false);
+    let cleared = active_model.update(&txn).await?;
+    let redone = if delta == 0.0 {
+        Vec::new()
+    } else {
+        round_up::record_round_up(&txn, &cleared).await?
+    };
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    journal::record(JournalEvent::Cleared, &cleared);
+    for leg in &removed {
+        journal::record(JournalEvent::Deleted, leg);
+    }
+    for leg in &redone {
+        journal::record(JournalEvent::Created, leg);
+    }
+    Ok(cleared)
+}
+
+/// Totals the pending spends of each envelope that has any, as negative amounts.
+///
+/// An envelope's cleared balance is its `balance` minus its pending total.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_pending_totals<C>(db: &C) -> Result<HashMap<i64, f64>>
+where
+    C: ConnectionTrait,
+{
+    let totals: Vec<(i64, Option<f64>)> = crate::entities::Transaction::find_active()
+        .select_only()
+        .column(transaction::Column::EnvelopeId)
+        .column_as(transaction::Column::Amount.sum(), "pending_total")
+        .filter(transaction::Column::Pending.eq(true))
+        .group_by(transaction::Column::EnvelopeId)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    Ok(totals
+        .into_iter()
+        .map(|(envelope_id, total)| (envelope_id, total.unwrap_or(0.0)))
+        .collect())
+}
+
+/// Lists pending spends, oldest first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_pending_transactions(db: &DatabaseConnection) -> Result<Vec<transaction::Model>> {
+    crate::entities::Transaction::find_active()
+        .filter(transaction::Column::Pending.eq(true))
+        .order_by_asc(transaction::Column::Timestamp)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Guardrails a spend has already been cleared of, so they don't stop it again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clearance {
+    /// Another member approved the spend (see
+    /// [`crate::core::wishlist::respond_to_purchase`])
+    pub approved: bool,
+    /// The spend may go past daily limits: the member confirmed it, or it runs on a
+    /// schedule (see [`check_daily_limits`])
+    pub over_daily_limit: bool,
+}
+
+/// Fields for a transaction about to be inserted.
+struct NewTransaction {
+    envelope_id: i64,
+    amount: f64,
+    description: String,
+    user_id: String,
+    message_id: Option<String>,
+    transaction_type: TransactionType,
+    /// Original amount and currency code for foreign-currency entries
+    original: Option<(f64, String)>,
+    /// Spend reversed by this transaction, for refunds
+    refund_of: Option<i64>,
+    /// Whether the spend is pending (see [`create_pending_spend`])
+    pending: bool,
+    /// Merchant to record; spends without one take it from their description
+    merchant: Option<String>,
+    /// Where the money was spent, already normalized
+    location: Option<String>,
+    /// Guardrails the spend doesn't need to pass again
+    clearance: Clearance,
+}
+
+/// Fails with [`Error::EnvelopeFrozen`] if `envelope` is frozen today.
+async fn check_not_frozen<C>(db: &C, envelope: &envelope::Model) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    if !envelope.frozen {
+        return Ok(());
+    }
+    let today = calendar::local_date(db, Utc::now()).await?;
+    if envelope_core::is_frozen_on(envelope, today) {
+        return Err(Error::EnvelopeFrozen {
+            envelope: envelope.name.clone(),
+            until: envelope.frozen_until,
+        });
+    }
+    Ok(())
+}
+
+/// Checks a change of `amount` to `envelope` against the guardrails every spend passes.
+///
+/// Spends (including product uses) must not be from a frozen envelope, take the day's
+/// spending past a daily limit, or exceed the large transaction threshold, unless their
+/// [`Clearance`] says otherwise. Any withdrawal must keep the envelope at or above its
+/// minimum balance and zero, and within its category's hard cap. Used both when a
+/// transaction is inserted and when [`clear_transaction`] posts more than was pending.
+async fn check_guardrails<C>(
+    db: &C,
+    envelope: &envelope::Model,
+    user_id: &str,
+    transaction_type: TransactionType,
+    amount: f64,
+    clearance: Clearance,
+) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    if matches!(
+        transaction_type,
+        TransactionType::Spend | TransactionType::UseProduct
+    ) {
+        // Frozen envelopes take no new spends until the freeze ends
+        check_not_frozen(db, envelope).await?;
+        // Daily limits hold unless the spend was confirmed past them
+        if !clearance.over_daily_limit {
+            check_daily_limits(db, envelope, user_id, -amount, Utc::now()).await?;
+        }
+        // Large spends wait for another member's approval instead
+        if !clearance.approved
+            && let Some(threshold) = approval::get_threshold(db).await?
+            && -amount > threshold
+        {
+            return Err(Error::ApprovalRequired {
+                amount: -amount,
+                threshold,
+            });
+        }
+    }
+    if amount >= 0.0 {
+        return Ok(());
+    }
+
+    // Protected envelopes keep a floor that spends can't dip into
+    let new_balance = envelope.balance + amount;
+    if let Some(floor) = envelope.min_balance
+        && new_balance < floor
+    {
+        return Err(Error::BelowMinimumBalance {
+            envelope: envelope.name.clone(),
+            floor,
+            available: (envelope.balance - floor).max(0.0),
+        });
+    }
+
+    // Check if the resulting balance would be negative (for spending)
+    // This is a preliminary check - the atomic update will ensure consistency
+    if new_balance < 0.0 {
+        return Err(Error::InsufficientFunds {
+            current: envelope.balance,
+            required: -amount,
+        });
+    }
+
+    // Spends also count toward their category's monthly cap
+    let today = calendar::local_date(db, Utc::now()).await?;
+    category_cap::check_cap(db, &envelope.category, -amount, today).await
+}
+
+/// Validates and inserts a transaction, updating the envelope balance atomically.
+///
+/// Returns the transaction and whether it was newly created, rather than an existing one
+/// returned for a retried `message_id`.
+async fn insert_transaction<C>(db: &C, new: NewTransaction) -> Result<(transaction::Model, bool)>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let NewTransaction {
+        envelope_id,
+        amount,
+        description,
+        user_id,
+        message_id,
+        transaction_type,
+        original,
+        refund_of,
+        pending,
+        merchant,
+        location,
+        clearance,
+    } = new;
+    let (original_amount, original_currency) = original.unzip();
+
+    if amount == 0.0 || !amount.is_finite() {
+        return Err(Error::InvalidAmount { amount });
+    }
+
+    // Use a transaction to ensure atomicity
+    let txn = db.begin().await?;
+
+    // A retried command returns the original transaction instead of posting again
+    if let Some(ref id) = message_id
+        && let Some(existing) = get_transaction_by_message_id(&txn, id).await?
+    {
+        return Ok((existing, false));
+    }
+
+    let envelope = Envelope::find_by_id(envelope_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(|| Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        })?;
+
+    if envelope.is_deleted {
+        return Err(Error::EnvelopeNotFound {
+            name: envelope_id.to_string(),
+        });
+    }
+
+    // Restricted shared envelopes only take spends from their spenders
+    if matches!(
+        transaction_type,
+        TransactionType::Spend | TransactionType::UseProduct
+    ) {
+        envelope_permission::check_can_spend(&txn, &envelope, &user_id).await?;
+    }
+    check_guardrails(
+        &txn,
+        &envelope,
+        &user_id,
+        transaction_type,
+        amount,
+        clearance,
+    )
+    .await?;
+
+    let now = chrono::Utc::now();
+    let merchant = if merchant.is_none() && amount < 0.0 {
+        extract_merchant(&description)
+    } else {
+        merchant
+    };
+
+    let transaction_model = transaction::ActiveModel {
+        envelope_id: Set(envelope_id),
+        amount: Set(amount),
+        description: Set(description),
+        timestamp: Set(now),
+        user_id: Set(user_id),
+        message_id: Set(message_id.clone()),
+        transaction_type: Set(transaction_type),
+        original_amount: Set(original_amount),
+        original_currency: Set(original_currency),
+        refund_of: Set(refund_of),
+        pending: Set(pending),
+        merchant: Set(merchant),
+        location: Set(location),
+        ..Default::default()
+    };
+
+    let result = match transaction_model.insert(&txn).await {
+        Ok(result) => result,
+        Err(e) => {
+            // A concurrent retry may have inserted the same message ID first
+            txn.rollback().await?;
+            if let Some(ref id) = message_id
+                && let Some(existing) = get_transaction_by_message_id(db, id).await?
+            {
+                return Ok((existing, false));
+            }
+            return Err(e.into());
+        }
+    };
+
+    // Atomically update the balance
+    crate::core::envelope::update_envelope_balance_atomic(&txn, envelope_id, amount).await?;
+
+    // A spend matching a recurring bill marks it paid
+    bill::record_payment(&txn, &result).await?;
+
+    // Save the change to the round-up envelope, if round-ups are on
+    let round_ups = round_up::record_round_up(&txn, &result).await?;
+
+    // Commit the transaction
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    for leg in &round_ups {
+        journal::record(JournalEvent::Created, leg);
+    }
+
+    Ok((result, true))
+}
+
+/// Refunds part or all of a previous spend by crediting its envelope.
+///
+/// The refund is recorded as a [`TransactionType::Refund`] transaction linked to the original through
+/// `refund_of`. Multiple partial refunds are allowed until the full spend has been
+/// returned. Like [`create_transaction`], the call is idempotent on `message_id`.
+///
+/// # Arguments
+/// * `original_id` - The spend to refund
+/// * `amount` - Amount to return (positive); defaults to everything not yet refunded
+///
+/// # Errors
+/// Returns an error if:
+/// - The original transaction does not exist (`Error::TransactionNotFound`)
+/// - The original is not a spend, is still pending, or is already fully refunded
+///   (`Error::Config`)
+/// - The amount is not positive or exceeds what is left to refund (`Error::InvalidAmount`)
+/// - The envelope does not exist or is deleted
+/// - The database transaction fails
+pub async fn create_refund(
+    db: &DatabaseConnection,
+    original_id: i64,
+    amount: Option<f64>,
+    user_id: String,
+    message_id: Option<String>,
+) -> Result<transaction::Model> {
+    create_linked_refund(
+        db,
+        original_id,
+        amount,
+        user_id,
+        message_id,
+        TransactionType::Refund,
+    )
+    .await
+}
+
+/// Records a refund of `kind` (one of [`TransactionType::REFUNDS`]) against a spend,
+/// with the validation described on [`create_refund`].
+///
+/// # Errors
+/// Returns the errors listed on [`create_refund`].
+pub(crate) async fn create_linked_refund(
+    db: &DatabaseConnection,
+    original_id: i64,
+    amount: Option<f64>,
+    user_id: String,
+    message_id: Option<String>,
+    kind: TransactionType,
+) -> Result<transaction::Model> {
+    // A retried command returns the original refund before the remaining amount shrinks
+    if let Some(ref id) = message_id
+        && let Some(existing) = get_transaction_by_message_id(db, id).await?
+    {
+        return Ok(existing);
+    }
+
+    let original = get_transaction_by_id(db, original_id)
+        .await?
+        .ok_or(Error::TransactionNotFound { id: original_id })?;
+    if original.amount >= 0.0 || original.transaction_type == TransactionType::RoundUp {
+        return Err(Error::Config {
+            message: format!("Transaction #{original_id} is not a spend and can't be refunded"),
+        });
+    }
+    if original.pending {
+        return Err(Error::Config {
+            message: format!(
+                "Transaction #{original_id} is still pending; clear it with its posted amount instead"
+            ),
+        });
+    }
+
+    let remaining = -original.amount - get_refunded_amount(db, original_id).await?;
+    if remaining < 0.005 {
+        return Err(Error::Config {
+            message: format!("Transaction #{original_id} has already been fully refunded"),
+        });
+    }
+
+    let amount = amount.unwrap_or(remaining);
+    if !amount.is_finite() || amount <= 0.0 || amount > remaining + 0.005 {
+        return Err(Error::InvalidAmount { amount });
+    }
+
+    insert_transaction(
+        db,
+        NewTransaction {
+            envelope_id: original.envelope_id,
+            amount: amount.min(remaining),
+            description: if kind == TransactionType::ProductReturn {
+                format!("Return: {}", original.description)
+            } else {
+                format!("Refund: {}", original.description)
+            },
+            user_id,
+            message_id,
+            transaction_type: kind,
+            original: None,
+            refund_of: Some(original_id),
+            pending: false,
+            merchant: original.merchant.clone(),
+            location: original.location.clone(),
+            clearance: Clearance::default(),
+        },
+    )
+    .await
+    .map(journal_created)
+}
+
+/// Returns the total already refunded against a spend.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_refunded_amount<C>(db: &C, original_id: i64) -> Result<f64>
+where
+    C: ConnectionTrait,
+{
+    let refunds = crate::entities::Transaction::find_active()
+        .filter(transaction::Column::RefundOf.eq(original_id))
+        .all(db)
+        .await?;
+
+    Ok(refunds.iter().map(|refund| refund.amount).sum())
+}
+
+/// Retrieves a user's most recent spends that still have something left to refund.
+///
+/// Used by the `/refund` transaction picker. When `search` is given, only spends whose
+/// description matches it are returned (see [`search_transactions`]).
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn get_recent_refundable_transactions(
+    db: &DatabaseConnection,
+    user_id: &str,
+    search: Option<&str>,
+    limit: u64,
+) -> Result<Vec<transaction::Model>> {
+    let mut query = crate::entities::Transaction::find_active()
+        .filter(transaction::Column::UserId.eq(user_id))
+        .filter(transaction::Column::Amount.lt(0.0))
+        .filter(transaction::Column::TransactionType.ne(TransactionType::RoundUp));
+    if let Some(matches) =
+        search.and_then(|search| description_search(db.get_database_backend(), search))
+    {
+        query = query.filter(matches);
+    }
+    let recent = query
+        .order_by_desc(transaction::Column::Timestamp)
+        .limit(limit)
+        .all(db)
+        .await?;
+
+    let mut refundable = Vec::with_capacity(recent.len());
+    for spend in recent {
+        if -spend.amount - get_refunded_amount(db, spend.id).await? >= 0.005 {
+            refundable.push(spend);
+        }
+    }
+    Ok(refundable)
+}
+
+/// Retrieves the most recent `/spend` a user recorded, for `/again` to repeat.
+///
+/// Only plain spends count: product uses, refunds, and round-ups are left out.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_last_spend(
+    db: &DatabaseConnection,
+    user_id: &str,
+) -> Result<Option<transaction::Model>> {
+    crate::entities::Transaction::find_active()
+        .filter(transaction::Column::UserId.eq(user_id))
+        .filter(transaction::Column::TransactionType.eq(TransactionType::Spend))
+        .filter(transaction::Column::Amount.lt(0.0))
+        .order_by_desc(transaction::Column::Timestamp)
+        .order_by_desc(transaction::Column::Id)
+        .one(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Finds transactions whose description matches `query`, newest first.
+///
+/// On `SQLite` the search uses the FTS5 index maintained alongside the transactions table,
+/// matching every word of the query as a prefix (so "groc" finds "Groceries"). Other
+/// backends fall back to a substring match on the description. A blank query matches
+/// nothing.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn search_transactions(
+    db: &DatabaseConnection,
+    query: &str,
+    limit: u64,
+) -> Result<Vec<transaction::Model>> {
+    let Some(matches) = description_search(db.get_database_backend(), query) else {
+        return Ok(Vec::new());
+    };
+
+    crate::entities::Transaction::find_active()
+        .filter(matches)
+        .order_by_desc(transaction::Column::Timestamp)
+        .limit(limit)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Optional filters for [`get_recent_activity`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActivityFilter {
+    /// Only transactions recorded by this user
+    pub user_id: Option<String>,
+    /// Only transactions of this kind
+    pub transaction_type: Option<TransactionType>,
+    /// Member viewing the feed; other members' private envelopes are left out
+    pub viewer_id: Option<String>,
+}
+
+/// Retrieves the most recent transactions across all envelopes, newest first.
+///
+/// Used by the `/activity` feed.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_recent_activity(
+    db: &DatabaseConnection,
+    filter: &ActivityFilter,
+    limit: u64,
+) -> Result<Vec<transaction::Model>> {
+    let mut query = crate::entities::Transaction::find_active();
+    if let Some(user_id) = &filter.user_id {
+        query = query.filter(transaction::Column::UserId.eq(user_id.as_str()));
+    }
+    if let Some(transaction_type) = filter.transaction_type {
+        query = query.filter(transaction::Column::TransactionType.eq(transaction_type));
+    }
+    if let Some(viewer_id) = &filter.viewer_id {
+        let hidden = Query::select()
+            .column(envelope::Column::Id)
+            .from(Envelope)
+            .and_where(Expr::col(envelope::Column::IsPrivate).eq(true))
+            .cond_where(
+                Condition::any()
+                    .add(envelope::Column::UserId.is_null())
+                    .add(envelope::Column::UserId.ne(viewer_id.as_str())),
+            )
+            .to_owned();
+        query = query.filter(transaction::Column::EnvelopeId.not_in_subquery(hidden));
+    }
+
+    query
+        .order_by_desc(transaction::Column::Timestamp)
+        .order_by_desc(transaction::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Builds the filter matching transaction descriptions against a search query.
+fn description_search(backend: DatabaseBackend, query: &str) -> Option<SimpleExpr> {
+    if backend == DatabaseBackend::Sqlite {
+        let terms = fts_query(query)?;
+        Some(Expr::cust_with_values(
+            format!(
+                "\"transactions\".\"id\" IN \
+                (SELECT rowid FROM {TRANSACTION_SEARCH_TABLE} WHERE {TRANSACTION_SEARCH_TABLE} MATCH ?)"
+            ),
+            [terms],
+        ))
+    } else {
+        let query = query.trim();
+        (!query.is_empty()).then(|| transaction::Column::Description.contains(query))
+    }
+}
+
+/// Converts free text into an FTS5 query: every word quoted and matched as a prefix.
+///
+/// Quoting keeps FTS5 operators and punctuation in user input from being interpreted.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Retrieves all transactions for a specific envelope, ordered by timestamp (newest first).
+///
+/// This function is commonly used to display transaction history for an envelope, allowing users
+/// to see all financial activity associated with a particular envelope. The results are ordered
+/// chronologically with the most recent transactions appearing first for better user experience.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_transactions_for_envelope(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+) -> Result<Vec<transaction::Model>> {
+    crate::entities::Transaction::find_active()
+        .filter(transaction::Column::EnvelopeId.eq(envelope_id))
+        .order_by_desc(transaction::Column::Timestamp)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// One page of an envelope's transactions, from [`get_transaction_page`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionPage {
+    /// Transactions on this page, newest first
+    pub transactions: Vec<transaction::Model>,
+    /// Zero-based page number, clamped to the last page
+    pub page: u64,
+    /// Number of pages (at least 1, even with no transactions)
+    pub pages: u64,
+}
+
+/// Retrieves one page of an envelope's transactions, newest first.
+///
+/// With `month` (any date in it), only transactions from that local calendar month are
+/// paged. A `page` past the end returns the last page.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_transaction_page(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    month: Option<NaiveDate>,
+    page: u64,
+    page_size: u64,
+) -> Result<TransactionPage> {
+    let mut query = crate::entities::Transaction::find_active()
+        .filter(transaction::Column::EnvelopeId.eq(envelope_id))
+        .order_by_desc(transaction::Column::Timestamp)
+        .order_by_desc(transaction::Column::Id);
+    if let Some(month) = month {
+        let first = month.with_day(1).unwrap_or(month);
+        let last = first
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(first);
+        let (start, end) = calendar::utc_bounds(db, first, last).await?;
+        query = query
+            .filter(transaction::Column::Timestamp.gte(start))
+            .filter(transaction::Column::Timestamp.lt(end));
+    }
+
+    let paginator = query.paginate(db, page_size.max(1));
+    let pages = paginator.num_pages().await?.max(1);
+    let page = page.min(pages - 1);
+    let transactions = paginator.fetch_page(page).await?;
+    Ok(TransactionPage {
+        transactions,
+        page,
+        pages,
+    })
+}
+
+/// Lists the local calendar months an envelope has transactions in, newest first.
+///
+/// Each month is returned as its first day.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_transaction_months(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+) -> Result<Vec<NaiveDate>> {
+    let timestamps: Vec<DateTime<Utc>> = crate::entities::Transaction::find_active()
+        .select_only()
+        .column(transaction::Column::Timestamp)
+        .filter(transaction::Column::EnvelopeId.eq(envelope_id))
+        .order_by_desc(transaction::Column::Timestamp)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let tz = calendar::get_timezone(db).await?;
+    let mut months: Vec<NaiveDate> = timestamps
+        .iter()
+        .filter_map(|timestamp| timestamp.with_timezone(&tz).date_naive().with_day(1))
+        .collect();
+    months.dedup();
+    Ok(months)
+}
+
+/// Retrieves a specific transaction by its unique ID.
+///
+/// This function is used for transaction lookups when users need to view, update, or delete
+/// a particular transaction. It returns None if the transaction doesn't exist, allowing callers
+/// to handle missing transactions gracefully without throwing errors.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_transaction_by_id(
+    db: &DatabaseConnection,
+    transaction_id: i64,
+) -> Result<Option<transaction::Model>> {
+    crate::entities::Transaction::find_active_by_id(transaction_id)
+        .one(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Retrieves the transaction recorded for a Discord message or interaction ID, if any.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_transaction_by_message_id<C>(
+    db: &C,
+    message_id: &str,
+) -> Result<Option<transaction::Model>>
+where
+    C: ConnectionTrait,
+{
+    crate::entities::Transaction::find()
+        .filter(transaction::Column::MessageId.eq(message_id))
+        .one(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Longest merchant name kept, in characters.
+const MAX_MERCHANT_LEN: usize = 40;
+
+/// Descriptions whose first word says nothing about where the money went.
+const GENERIC_DESCRIPTION_WORDS: &[&str] = &["transaction", "spend", "purchase", "payment"];
+
+/// Normalizes a merchant name so spellings of the same merchant group together.
+///
+/// Lowercases, turns punctuation into spaces, collapses whitespace, and keeps at most
+/// [`MAX_MERCHANT_LEN`] characters, so `"Trader Joe's!"` becomes `"trader joe s"`.
+/// Returns `None` if nothing but digits and punctuation is left.
+#[must_use]
+pub fn normalize_merchant(raw: &str) -> Option<String> {
+    let cleaned: String = raw
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                ' '
+            }
+        })
+        .collect();
+    let merchant: String = cleaned
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_MERCHANT_LEN)
+        .collect();
+    let merchant = merchant.trim_end().to_string();
+    merchant
+        .chars()
+        .any(char::is_alphabetic)
+        .then_some(merchant)
+}
+
+/// Guesses the merchant from the first word of a spend's description.
+///
+/// Generic words such as the default "Transaction" description are ignored.
+#[must_use]
+pub fn extract_merchant(description: &str) -> Option<String> {
+    let first = description.split_whitespace().next()?;
+    normalize_merchant(first)
+        .filter(|merchant| !GENERIC_DESCRIPTION_WORDS.contains(&merchant.as_str()))
+}
+
+/// Sets the merchant of a transaction, replacing the one taken from its description.
+///
+/// # Errors
+/// Returns an error if:
+/// - The merchant has no letters (`Error::Config`)
+/// - The transaction does not exist (`Error::Config`)
+/// - The database update fails
+pub async fn set_merchant(
+    db: &DatabaseConnection,
+    transaction_id: i64,
+    merchant: &str,
+) -> Result<transaction::Model> {
+    let Some(merchant) = normalize_merchant(merchant) else {
+        return Err(Error::Config {
+            message: format!("'{merchant}' isn't a valid merchant name"),
+        });
+    };
+    let transaction = crate::entities::Transaction::find_active_by_id(transaction_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| Error::Config {
+            message: "Transaction not found".to_string(),
+        })?;
+
+    let mut active_model: transaction::ActiveModel = transaction.into();
+    active_model.merchant = Set(Some(merchant));
+    active_model.update(db).await.map_err(Into::into)
+}
+
+/// Longest unit [`set_quantity`] accepts, e.g. `"gal"` or `"lb"`.
+const MAX_UNIT_LENGTH: usize = 16;
+
+/// Records the quantity bought with a spend, e.g. 3.2 gallons, for tracking unit prices.
+///
+/// A blank `unit` is stored as no unit.
 ///
 /// # Errors
 /// Returns an error if:
-/// - The amount is zero or not finite (NaN, infinity)
-/// - The envelope does not exist or is deleted
-/// - The transaction would result in a negative balance
-/// - The database transaction fails
-pub async fn create_transaction(
+/// - The quantity is not positive and finite (`Error::InvalidAmount`)
+/// - The unit is too long, or the transaction does not exist (`Error::Config`)
+/// - The database update fails
+pub async fn set_quantity(
     db: &DatabaseConnection,
-    envelope_id: i64,
-    amount: f64,
-    description: String,
-    user_id: String,
-    message_id: Option<String>,
-    transaction_type: String,
+    transaction_id: i64,
+    quantity: f64,
+    unit: Option<&str>,
 ) -> Result<transaction::Model> {
-    if amount == 0.0 {
-        return Err(Error::InvalidAmount { amount });
-    }
-
-    if !amount.is_finite() {
-        return Err(Error::InvalidAmount { amount });
-    }
-
-    // Use a transaction to ensure atomicity
-    let txn = db.begin().await?;
-
-    let envelope = Envelope::find_by_id(envelope_id)
-        .one(&txn)
+    let unit = validate_quantity(quantity, unit)?;
+    let transaction = crate::entities::Transaction::find_active_by_id(transaction_id)
+        .one(db)
         .await?
-        .ok_or_else(|| Error::EnvelopeNotFound {
-            name: envelope_id.to_string(),
+        .ok_or_else(|| Error::Config {
+            message: "Transaction not found".to_string(),
         })?;
 
-    if envelope.is_deleted {
-        return Err(Error::EnvelopeNotFound {
-            name: envelope_id.to_string(),
-        });
-    }
+    let mut active_model: transaction::ActiveModel = transaction.into();
+    active_model.quantity = Set(Some(quantity));
+    active_model.unit = Set(unit);
+    active_model.update(db).await.map_err(Into::into)
+}
 
-    // Check if the resulting balance would be negative (for spending)
-    // This is a preliminary check - the atomic update will ensure consistency
-    let new_balance = envelope.balance + amount;
-    if new_balance < 0.0 {
-        return Err(Error::InsufficientFunds {
-            current: envelope.balance,
-            required: -amount,
+/// Checks a quantity and unit for [`set_quantity`], returning the trimmed unit.
+///
+/// Commands check before recording a spend, so a bad quantity doesn't leave a spend
+/// recorded without one.
+///
+/// # Errors
+/// Returns `Error::InvalidAmount` for a quantity that isn't positive and finite, or
+/// `Error::Config` for a unit longer than 16 characters.
+pub fn validate_quantity(quantity: f64, unit: Option<&str>) -> Result<Option<String>> {
+    if !quantity.is_finite() || quantity <= 0.0 {
+        return Err(Error::InvalidAmount { amount: quantity });
+    }
+    let unit = unit.map(str::trim).filter(|unit| !unit.is_empty());
+    if unit.is_some_and(|unit| unit.chars().count() > MAX_UNIT_LENGTH) {
+        return Err(Error::Config {
+            message: format!("Units can be at most {MAX_UNIT_LENGTH} characters"),
         });
     }
+    Ok(unit.map(str::to_string))
+}
 
-    let now = chrono::Utc::now();
-    let transaction_model = transaction::ActiveModel {
-        envelope_id: Set(envelope_id),
-        amount: Set(amount),
-        description: Set(description),
-        timestamp: Set(now),
-        user_id: Set(user_id),
-        message_id: Set(message_id),
-        transaction_type: Set(transaction_type),
-        ..Default::default()
-    };
-
-    let result = transaction_model.insert(&txn).await?;
-
-    // Atomically update the balance
-    crate::core::envelope::update_envelope_balance_atomic(&txn, envelope_id, amount).await?;
-
-    // Commit the transaction
-    txn.commit().await?;
-
-    Ok(result)
+/// Price paid per unit of a spend's quantity, if it recorded one.
+#[must_use]
+pub fn unit_price(transaction: &transaction::Model) -> Option<f64> {
+    transaction
+        .quantity
+        .filter(|quantity| *quantity > 0.0)
+        .map(|quantity| transaction.amount.abs() / quantity)
 }
 
-/// Retrieves all transactions for a specific envelope, ordered by timestamp (newest first).
-///
-/// This function is commonly used to display transaction history for an envelope, allowing users
-/// to see all financial activity associated with a particular envelope. The results are ordered
-/// chronologically with the most recent transactions appearing first for better user experience.
+/// IDs of the private envelopes whose spending `viewer_id` may not see.
 ///
 /// # Errors
 /// Returns an error if the database query fails.
-pub async fn get_transactions_for_envelope(
-    db: &DatabaseConnection,
-    envelope_id: i64,
-) -> Result<Vec<transaction::Model>> {
-    crate::entities::Transaction::find()
-        .filter(transaction::Column::EnvelopeId.eq(envelope_id))
-        .order_by_desc(transaction::Column::Timestamp)
+pub(crate) async fn hidden_envelope_ids<C>(db: &C, viewer_id: &str) -> Result<Vec<i64>>
+where
+    C: ConnectionTrait,
+{
+    Envelope::find()
+        .select_only()
+        .column(envelope::Column::Id)
+        .filter(envelope::Column::IsPrivate.eq(true))
+        .filter(
+            Condition::any()
+                .add(envelope::Column::UserId.is_null())
+                .add(envelope::Column::UserId.ne(viewer_id)),
+        )
+        .into_tuple()
         .all(db)
         .await
         .map_err(Into::into)
 }
 
-/// Retrieves a specific transaction by its unique ID.
+/// A merchant's spending over a period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerchantSpending {
+    /// Normalized merchant name
+    pub merchant: String,
+    /// Spending in dollars, less refunds
+    pub spent: f64,
+    /// Number of spends and refunds
+    pub transaction_count: usize,
+}
+
+/// Totals spending per merchant between `start` (inclusive) and `end` (exclusive),
+/// largest first, keeping the top `limit`.
 ///
-/// This function is used for transaction lookups when users need to view, update, or delete
-/// a particular transaction. It returns None if the transaction doesn't exist, allowing callers
-/// to handle missing transactions gracefully without throwing errors.
+/// Spends from other members' private envelopes are left out, and transactions without
+/// a merchant aren't counted.
 ///
 /// # Errors
 /// Returns an error if the database query fails.
-pub async fn get_transaction_by_id(
+pub async fn get_top_merchants(
     db: &DatabaseConnection,
-    transaction_id: i64,
-) -> Result<Option<transaction::Model>> {
-    crate::entities::Transaction::find_by_id(transaction_id)
-        .one(db)
-        .await
-        .map_err(Into::into)
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    viewer_id: &str,
+    limit: usize,
+) -> Result<Vec<MerchantSpending>> {
+    let hidden = hidden_envelope_ids(db, viewer_id).await?;
+
+    let transactions = crate::entities::Transaction::find_active()
+        .filter(transaction::Column::Merchant.is_not_null())
+        .filter(transaction::Column::EnvelopeId.is_not_in(hidden))
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .filter(
+            Condition::any()
+                .add(transaction::Column::Amount.lt(0.0))
+                .add(transaction::Column::TransactionType.is_in(TransactionType::REFUNDS)),
+        )
+        .all(db)
+        .await?;
+
+    let mut totals: HashMap<String, MerchantSpending> = HashMap::new();
+    for tx in transactions {
+        let Some(merchant) = tx.merchant else {
+            continue;
+        };
+        let entry = totals
+            .entry(merchant.clone())
+            .or_insert_with(|| MerchantSpending {
+                merchant,
+                spent: 0.0,
+                transaction_count: 0,
+            });
+        entry.spent -= tx.amount;
+        entry.transaction_count += 1;
+    }
+
+    let mut merchants: Vec<MerchantSpending> = totals.into_values().collect();
+    merchants.sort_by(|a, b| {
+        b.spent
+            .total_cmp(&a.spent)
+            .then_with(|| a.merchant.cmp(&b.merchant))
+    });
+    merchants.truncate(limit);
+    Ok(merchants)
 }
 
 /// Deletes a transaction and automatically reverses its effect on the envelope balance.
@@ -141,6 +1325,7 @@ pub async fn get_transaction_by_id(
 /// This function is used for transaction corrections and cancellations. When a transaction is
 /// deleted, the envelope's balance is automatically adjusted by subtracting the transaction amount,
 /// ensuring that the envelope balance remains accurate and consistent with the remaining transactions.
+/// A spend's round-up legs are deleted and reversed with it.
 ///
 /// # Errors
 /// Returns an error if:
@@ -151,7 +1336,7 @@ pub async fn delete_transaction(db: &DatabaseConnection, transaction_id: i64) ->
     // Use a transaction to ensure atomicity
     let txn = db.begin().await?;
 
-    let transaction = crate::entities::Transaction::find_by_id(transaction_id)
+    let transaction = crate::entities::Transaction::find_active_by_id(transaction_id)
         .one(&txn)
         .await?
         .ok_or_else(|| Error::Config {
@@ -169,8 +1354,11 @@ pub async fn delete_transaction(db: &DatabaseConnection, transaction_id: i64) ->
     let envelope_id = transaction.envelope_id;
     let amount_to_reverse = -transaction.amount; // Negate to reverse the transaction
 
+    // Round-up legs were recorded with the spend, so they go with it
+    let round_ups = round_up::remove_round_up(&txn, transaction_id).await?;
+
     // Delete the transaction
-    transaction.delete(&txn).await?;
+    transaction.clone().delete(&txn).await?;
 
     // Atomically update the balance by reversing the transaction amount
     crate::core::envelope::update_envelope_balance_atomic(&txn, envelope_id, amount_to_reverse)
@@ -178,6 +1366,406 @@ pub async fn delete_transaction(db: &DatabaseConnection, transaction_id: i64) ->
 
     // Commit the transaction
     txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    journal::record(JournalEvent::Deleted, &transaction);
+    for leg in &round_ups {
+        journal::record(JournalEvent::Deleted, leg);
+    }
+    Ok(())
+}
+
+/// Filters selecting transactions for [`preview_purge`] and [`purge_transactions`].
+///
+/// Built up with the chained setters; every filter that is set must match. Dates are
+/// household days, both ends included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionCriteria {
+    envelope_id: Option<i64>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    transaction_type: Option<TransactionType>,
+    user_id: Option<String>,
+}
+
+impl TransactionCriteria {
+    /// Starts with no filters.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only transactions in this envelope.
+    #[must_use]
+    pub const fn envelope(mut self, envelope_id: i64) -> Self {
+        self.envelope_id = Some(envelope_id);
+        self
+    }
+
+    /// Only transactions on or after this day.
+    #[must_use]
+    pub const fn from(mut self, date: NaiveDate) -> Self {
+        self.from = Some(date);
+        self
+    }
+
+    /// Only transactions on or before this day.
+    #[must_use]
+    pub const fn to(mut self, date: NaiveDate) -> Self {
+        self.to = Some(date);
+        self
+    }
+
+    /// Only transactions of this kind.
+    #[must_use]
+    pub const fn transaction_type(mut self, transaction_type: TransactionType) -> Self {
+        self.transaction_type = Some(transaction_type);
+        self
+    }
+
+    /// Only transactions recorded by this user.
+    #[must_use]
+    pub fn user(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Whether no filter is set, which would select every transaction.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.envelope_id.is_none()
+            && self.from.is_none()
+            && self.to.is_none()
+            && self.transaction_type.is_none()
+            && self.user_id.is_none()
+    }
+
+    /// Builds the query condition, reading days in the household timezone.
+    ///
+    /// # Errors
+    /// Returns `Error::Config` if no filter is set or the range ends before it starts,
+    /// or an error if the timezone can't be read.
+    async fn condition<C>(&self, db: &C) -> Result<Condition>
+    where
+        C: ConnectionTrait,
+    {
+        if self.is_empty() {
+            return Err(Error::Config {
+                message: "Choose at least one filter".to_string(),
+            });
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to)
+            && to < from
+        {
+            return Err(Error::Config {
+                message: format!("The range ends ({to}) before it starts ({from})"),
+            });
+        }
+
+        let tz = calendar::get_timezone(db).await?;
+        let mut condition = Condition::all();
+        if let Some(envelope_id) = self.envelope_id {
+            condition = condition.add(transaction::Column::EnvelopeId.eq(envelope_id));
+        }
+        if let Some(from) = self.from {
+            condition =
+                condition.add(transaction::Column::Timestamp.gte(calendar::start_of_day(tz, from)));
+        }
+        if let Some(next_day) = self.to.and_then(|to| to.succ_opt()) {
+            condition = condition
+                .add(transaction::Column::Timestamp.lt(calendar::start_of_day(tz, next_day)));
+        }
+        if let Some(transaction_type) = self.transaction_type {
+            condition = condition.add(transaction::Column::TransactionType.eq(transaction_type));
+        }
+        if let Some(user_id) = &self.user_id {
+            condition = condition.add(transaction::Column::UserId.eq(user_id.as_str()));
+        }
+        Ok(condition)
+    }
+}
+
+/// Transactions selected by a [`TransactionCriteria`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PurgeSummary {
+    /// Number of transactions
+    pub count: usize,
+    /// Sum of their amounts (negative when spends dominate)
+    pub total: f64,
+    /// Envelopes that reversing the transactions would take below zero, by name;
+    /// [`purge_transactions`] refuses while there are any
+    pub overdrawn: Vec<String>,
+    /// Envelopes with transactions in a budget month that already has a statement, by
+    /// name; [`purge_transactions`] refuses while there are any, since reconciliation
+    /// works balances forward from the statement and would revert the purge
+    pub closed: Vec<String>,
+}
+
+/// Selects the transactions matching `criteria` and works out each envelope's reversal.
+async fn select_for_purge<C>(
+    db: &C,
+    criteria: &TransactionCriteria,
+) -> Result<(Vec<transaction::Model>, HashMap<i64, f64>, PurgeSummary)>
+where
+    C: ConnectionTrait,
+{
+    let mut matching = crate::entities::Transaction::find_active()
+        .filter(criteria.condition(db).await?)
+        .all(db)
+        .await?;
+    // Round-up legs go with their spends, as in delete_transaction
+    let ids: Vec<i64> = matching.iter().map(|tx| tx.id).collect();
+    let round_ups = crate::entities::Transaction::find_active()
+        .filter(transaction::Column::RoundUpOf.is_in(ids.clone()))
+        .filter(transaction::Column::Id.is_not_in(ids))
+        .all(db)
+        .await?;
+    matching.extend(round_ups);
+
+    let mut reversals: HashMap<i64, f64> = HashMap::new();
+    for tx in &matching {
+        *reversals.entry(tx.envelope_id).or_default() -= tx.amount;
+    }
+
+    let envelopes = Envelope::find()
+        .filter(envelope::Column::Id.is_in(reversals.keys().copied()))
+        .all(db)
+        .await?;
+    let mut overdrawn: Vec<String> = envelopes
+        .iter()
+        .filter(|env| env.balance + reversals.get(&env.id).copied().unwrap_or(0.0) < -0.005)
+        .map(|env| env.name.clone())
+        .collect();
+    overdrawn.sort();
+
+    let mut closed = Vec::new();
+    for env in &envelopes {
+        let Some(statement) = MonthlyStatement::find()
+            .filter(monthly_statement::Column::EnvelopeId.eq(env.id))
+            .order_by_desc(monthly_statement::Column::PeriodEnd)
+            .one(db)
+            .await?
+        else {
+            continue;
+        };
+        let (_, open_from) =
+            calendar::utc_bounds(db, statement.period_end, statement.period_end).await?;
+        if matching
+            .iter()
+            .any(|tx| tx.envelope_id == env.id && tx.timestamp < open_from)
+        {
+            closed.push(env.name.clone());
+        }
+    }
+    closed.sort();
+
+    let summary = PurgeSummary {
+        count: matching.len(),
+        total: matching.iter().map(|tx| tx.amount).sum(),
+        overdrawn,
+        closed,
+    };
+    Ok((matching, reversals, summary))
+}
+
+/// Counts and totals the transactions [`purge_transactions`] would delete, and names the
+/// envelopes it would leave below zero or whose closed months it would change.
+///
+/// # Errors
+/// Returns `Error::Config` if the criteria are empty or invalid, or an error if the
+/// database query fails.
+pub async fn preview_purge(
+    db: &DatabaseConnection,
+    criteria: &TransactionCriteria,
+) -> Result<PurgeSummary> {
+    Ok(select_for_purge(db, criteria).await?.2)
+}
+
+/// Soft-deletes every transaction matching `criteria` in one database transaction.
+///
+/// The transactions are kept, marked with `deleted_at`, but no longer count in reports,
+/// statements, limits, caps or search. Like [`delete_transaction`], each envelope's
+/// balance is adjusted to reverse them, the round-up legs of purged spends are purged
+/// with them, and each is journaled as deleted. Links to them from IOUs, wishlist
+/// proposals, scheduled spends and refunds are left alone.
+///
+/// # Errors
+/// Returns `Error::Config` if the criteria are empty or invalid, if the reversal would
+/// take an envelope below zero (see [`PurgeSummary::overdrawn`]), or if a transaction is
+/// in a month that already has a statement (see [`PurgeSummary::closed`]). Returns an
+/// error if a database operation fails.
+pub async fn purge_transactions(
+    db: &DatabaseConnection,
+    criteria: &TransactionCriteria,
+) -> Result<PurgeSummary> {
+    let txn = db.begin().await?;
+    let (matching, reversals, summary) = select_for_purge(&txn, criteria).await?;
+    if matching.is_empty() {
+        return Ok(summary);
+    }
+    if !summary.overdrawn.is_empty() {
+        return Err(Error::Config {
+            message: format!(
+                "Purging these would leave {} below zero; narrow the filters or add funds first",
+                summary
+                    .overdrawn
+                    .iter()
+                    .map(|name| format!("'{name}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        });
+    }
+    if !summary.closed.is_empty() {
+        return Err(Error::Config {
+            message: format!(
+                "Some of these are in months that already have statements ({}); narrow the \
+                 filters to after the last monthly update",
+                summary
+                    .closed
+                    .iter()
+                    .map(|name| format!("'{name}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        });
+    }
+
+    let now = Utc::now();
+    crate::entities::Transaction::update_many()
+        .col_expr(transaction::Column::DeletedAt, Expr::value(Some(now)))
+        .filter(transaction::Column::Id.is_in(matching.iter().map(|tx| tx.id)))
+        .exec(&txn)
+        .await?;
+    for (envelope_id, amount) in reversals {
+        crate::core::envelope::update_envelope_balance_atomic(&txn, envelope_id, amount).await?;
+    }
+
+    txn.commit().await?;
+    cache::invalidate(CachedTable::Envelopes);
+    for tx in &matching {
+        journal::record(JournalEvent::Deleted, tx);
+    }
+    Ok(summary)
+}
+
+/// Returns a member's daily spending limit across all envelopes, if set.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_user_daily_limit<C>(db: &C, user_id: &str) -> Result<Option<f64>>
+where
+    C: ConnectionTrait,
+{
+    Ok(
+        state::get_value(db, &format!("{USER_DAILY_LIMIT_PREFIX}{user_id}"))
+            .await?
+            .and_then(|value| value.parse().ok()),
+    )
+}
+
+/// Sets (or clears) a member's daily spending limit across all envelopes.
+///
+/// # Errors
+/// Returns an error if:
+/// - The limit is not positive and finite (`Error::InvalidAmount`)
+/// - The database update fails
+pub async fn set_user_daily_limit(
+    db: &DatabaseConnection,
+    user_id: &str,
+    daily_limit: Option<f64>,
+) -> Result<()> {
+    let key = format!("{USER_DAILY_LIMIT_PREFIX}{user_id}");
+    match daily_limit {
+        Some(limit) if !limit.is_finite() || limit <= 0.0 => {
+            Err(Error::InvalidAmount { amount: limit })
+        }
+        Some(limit) => state::set_value(db, &key, &limit.to_string()).await,
+        None => state::delete_value(db, &key).await,
+    }
+}
+
+/// Totals a member's spending across all envelopes on the local day containing `now`.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_user_spent_on_day<C>(db: &C, user_id: &str, now: DateTime<Utc>) -> Result<f64>
+where
+    C: ConnectionTrait,
+{
+    let filter = Condition::all().add(transaction::Column::UserId.eq(user_id));
+    spent_on_day(db, filter, now).await
+}
+
+/// Totals the spending matching `filter` on the local day containing `now`.
+///
+/// Spending is every negative transaction except round-ups, which move change to savings;
+/// refunds and added funds don't reduce it.
+async fn spent_on_day<C>(db: &C, filter: Condition, now: DateTime<Utc>) -> Result<f64>
+where
+    C: ConnectionTrait,
+{
+    let today = calendar::local_date(db, now).await?;
+    let (start, end) = calendar::utc_bounds(db, today, today).await?;
+
+    let total: Option<Option<f64>> = crate::entities::Transaction::find_active()
+        .select_only()
+        .column_as(transaction::Column::Amount.sum(), "total")
+        .filter(transaction::Column::Amount.lt(0.0))
+        .filter(transaction::Column::TransactionType.ne(TransactionType::RoundUp))
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .filter(filter)
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    Ok(-total.flatten().unwrap_or(0.0))
+}
+
+/// Checks a spend of `amount` (positive) against the envelope's and the member's daily
+/// limits.
+///
+/// Every spend is checked as it is recorded, unless its [`Clearance`] lets it past the
+/// limits; interactive commands also run it first so the member can confirm an
+/// over-limit spend explicitly. Scheduled and approved spends aren't limited.
+///
+/// # Errors
+/// Returns an error if:
+/// - The spend would take today's spending past either limit (`Error::DailyLimitExceeded`)
+/// - The database query fails
+pub async fn check_daily_limits<C>(
+    db: &C,
+    envelope: &envelope::Model,
+    user_id: &str,
+    amount: f64,
+    now: DateTime<Utc>,
+) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    if let Some(limit) = envelope.daily_limit {
+        let filter = Condition::all().add(transaction::Column::EnvelopeId.eq(envelope.id));
+        let spent = spent_on_day(db, filter, now).await? + amount;
+        if spent > limit + 0.005 {
+            return Err(Error::DailyLimitExceeded {
+                scope: format!("envelope '{}'", envelope.name),
+                limit,
+                spent,
+            });
+        }
+    }
+
+    if let Some(limit) = get_user_daily_limit(db, user_id).await? {
+        let spent = get_user_spent_on_day(db, user_id, now).await? + amount;
+        if spent > limit + 0.005 {
+            return Err(Error::DailyLimitExceeded {
+                scope: "your spending".to_string(),
+                limit,
+                spent,
+            });
+        }
+    }
+
     Ok(())
 }
 #[cfg(test)]
@@ -187,6 +1775,7 @@ mod tests {
     use super::*;
     use crate::entities::envelope;
     use crate::test_utils::*;
+    use chrono::{Days, NaiveTime};
     use sea_orm::{DatabaseBackend, MockDatabase};
 
     #[tokio::test]
@@ -201,7 +1790,7 @@ mod tests {
             "test".to_string(),
             "user1".to_string(),
             None,
-            "spend".to_string(),
+            TransactionType::Spend,
         )
         .await;
         assert!(result.is_err());
@@ -218,7 +1807,7 @@ mod tests {
             "test".to_string(),
             "user1".to_string(),
             None,
-            "spend".to_string(),
+            TransactionType::Spend,
         )
         .await;
         assert!(result.is_err());
@@ -235,7 +1824,7 @@ mod tests {
             "test".to_string(),
             "user1".to_string(),
             None,
-            "spend".to_string(),
+            TransactionType::Spend,
         )
         .await;
         assert!(result.is_err());
@@ -252,7 +1841,7 @@ mod tests {
             "test".to_string(),
             "user1".to_string(),
             None,
-            "spend".to_string(),
+            TransactionType::Spend,
         )
         .await;
         assert!(result.is_err());
@@ -278,7 +1867,7 @@ mod tests {
             "test".to_string(),
             "user1".to_string(),
             None,
-            "spend".to_string(),
+            TransactionType::Spend,
         )
         .await;
         assert!(result.is_err());
@@ -291,44 +1880,202 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_create_transaction_insufficient_funds() -> Result<()> {
-        let envelope_with_low_balance = envelope::Model {
-            id: 1,
-            name: "Low Balance Envelope".to_string(),
-            category: "necessary".to_string(),
-            allocation: 100.0,
-            balance: 10.0, // Low balance
-            is_individual: false,
-            user_id: None,
-            rollover: false,
-            is_deleted: false,
+    async fn test_create_transaction_insufficient_funds() -> Result<()> {
+        let envelope_with_low_balance = envelope::Model {
+            id: 1,
+            name: "Low Balance Envelope".to_string(),
+            category: "necessary".to_string(),
+            allocation: 100.0,
+            balance: 10.0, // Low balance
+            is_individual: false,
+            user_id: None,
+            rollover: false,
+            is_deleted: false,
+            contribution: 0.0,
+            goal: None,
+            is_private: false,
+            alert_below: None,
+            daily_limit: None,
+            min_balance: None,
+            thread_id: None,
+            weekly: false,
+            ping_mention: None,
+            interest_rate: None,
+            pinned: false,
+            sort_order: None,
+            default_description: None,
+            frozen: false,
+            frozen_until: None,
+            pace_on_track: None,
+            pace_slightly_over: None,
+            pace_never_warn: false,
+        };
+
+        // Configure MockDatabase to return envelope with low balance, open to every spender,
+        // with no daily limit or approval threshold
+        let db = MockDatabase::new(DatabaseBackend::Sqlite)
+            .append_query_results([vec![envelope_with_low_balance]])
+            .append_query_results([Vec::<crate::entities::envelope_permission::Model>::new()])
+            .append_query_results([Vec::<crate::entities::system_state::Model>::new()])
+            .append_query_results([Vec::<crate::entities::system_state::Model>::new()])
+            .into_connection();
+
+        // Try to spend more than available balance
+        let result = create_transaction(
+            &db,
+            1,
+            -20.0,
+            "test".to_string(),
+            "user1".to_string(),
+            None,
+            TransactionType::Spend,
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            Error::InsufficientFunds {
+                current: 10.0,
+                required: 20.0
+            }
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_min_balance_blocks_spends_below_floor() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 150.0).await?;
+        crate::core::envelope::set_min_balance(&db, env.id, Some(100.0)).await?;
+
+        let spend = |amount: f64| {
+            create_transaction(
+                &db,
+                env.id,
+                amount,
+                "test".to_string(),
+                "user1".to_string(),
+                None,
+                TransactionType::Spend,
+            )
+        };
+        let result = spend(-60.0).await;
+        assert!(matches!(
+            result,
+            Err(Error::BelowMinimumBalance { floor, available, .. })
+                if floor == 100.0 && available == 50.0
+        ));
+
+        // Spending down to the floor is allowed, and deposits below it still go through
+        spend(-50.0).await?;
+        crate::core::envelope::set_min_balance(&db, env.id, Some(200.0)).await?;
+        create_test_transaction(&db, env.id, 10.0).await?;
+        assert!(matches!(
+            spend(-1.0).await,
+            Err(Error::BelowMinimumBalance { available: 0.0, .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_frozen_envelope_blocks_spends() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 100.0).await?;
+        crate::core::envelope::set_frozen(&db, env.id, true, None).await?;
+
+        let spend = || {
+            create_transaction(
+                &db,
+                env.id,
+                -10.0,
+                "test".to_string(),
+                "user1".to_string(),
+                None,
+                TransactionType::Spend,
+            )
+        };
+        assert!(matches!(
+            spend().await,
+            Err(Error::EnvelopeFrozen { until: None, .. })
+        ));
+
+        // Using a product is a spend too
+        let product_use = create_transaction(
+            &db,
+            env.id,
+            -10.0,
+            "Product: Coffee".to_string(),
+            "user1".to_string(),
+            None,
+            TransactionType::UseProduct,
+        )
+        .await;
+        assert!(matches!(product_use, Err(Error::EnvelopeFrozen { .. })));
+
+        // Funds can still come in while frozen
+        create_test_transaction(&db, env.id, 10.0).await?;
+
+        // A freeze that has already ended no longer blocks
+        let ended = Utc::now().date_naive() - Days::new(2);
+        let thawed = crate::core::envelope::set_frozen(&db, env.id, true, Some(ended)).await?;
+        assert_eq!(thawed.frozen_until, Some(ended));
+        spend().await?;
+
+        let unfrozen = crate::core::envelope::set_frozen(&db, env.id, false, Some(ended)).await?;
+        assert!(!unfrozen.frozen);
+        assert_eq!(unfrozen.frozen_until, None);
+        spend().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_large_spends_need_approval() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 1000.0).await?;
+        approval::set_threshold(&db, Some(500.0)).await?;
+
+        let spend = |amount: f64, transaction_type: TransactionType| {
+            create_transaction(
+                &db,
+                env.id,
+                -amount,
+                "Laptop".to_string(),
+                "user1".to_string(),
+                None,
+                transaction_type,
+            )
         };
-
-        // Configure MockDatabase to return envelope with low balance
-        let db = MockDatabase::new(DatabaseBackend::Sqlite)
-            .append_query_results([vec![envelope_with_low_balance]])
-            .into_connection();
-
-        // Try to spend more than available balance
-        let result = create_transaction(
+        for transaction_type in [TransactionType::Spend, TransactionType::UseProduct] {
+            assert!(matches!(
+                spend(600.0, transaction_type).await,
+                Err(Error::ApprovalRequired { threshold, .. }) if threshold == 500.0
+            ));
+        }
+        spend(500.0, TransactionType::Spend).await?;
+
+        // An approved spend goes through
+        create_cleared_transaction(
             &db,
-            1,
-            -20.0,
-            "test".to_string(),
+            env.id,
+            -450.0,
+            "Laptop".to_string(),
             "user1".to_string(),
             None,
-            "spend".to_string(),
+            TransactionType::Spend,
+            Clearance {
+                approved: true,
+                ..Default::default()
+            },
         )
-        .await;
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            Error::InsufficientFunds {
-                current: 10.0,
-                required: 20.0
-            }
-        ));
-
+        .await?;
+        let balance = envelope_core::get_envelope_by_id(&db, env.id)
+            .await?
+            .unwrap()
+            .balance;
+        assert_eq!(balance, 50.0);
         Ok(())
     }
 
@@ -430,7 +2177,7 @@ mod tests {
         assert_eq!(transaction.amount, 50.0);
         assert_eq!(transaction.description, "Test transaction");
         assert_eq!(transaction.user_id, "test_user");
-        assert_eq!(transaction.transaction_type, "addfunds");
+        assert_eq!(transaction.transaction_type, TransactionType::AddFunds);
 
         // Verify envelope balance was updated
         let updated_envelope = Envelope::find_by_id(envelope.id).one(&db).await?.unwrap();
@@ -451,7 +2198,7 @@ mod tests {
             "Test transaction",
             "user456",
             None,
-            "addfunds",
+            TransactionType::AddFunds,
         )
         .await?;
 
@@ -479,7 +2226,7 @@ mod tests {
             "With message",
             "user1",
             Some("msg_12345".to_string()),
-            "spend",
+            TransactionType::Spend,
         )
         .await?;
 
@@ -493,7 +2240,7 @@ mod tests {
             "Without message",
             "user1",
             None,
-            "spend",
+            TransactionType::Spend,
         )
         .await?;
 
@@ -530,11 +2277,11 @@ mod tests {
             "Spend transaction",
             "user1",
             None,
-            "spend",
+            TransactionType::Spend,
         )
         .await?;
 
-        assert_eq!(spend.transaction_type, "spend");
+        assert_eq!(spend.transaction_type, TransactionType::Spend);
 
         // Create addfunds transaction
         let addfunds = create_custom_transaction(
@@ -544,11 +2291,11 @@ mod tests {
             "Add funds transaction",
             "user1",
             None,
-            "addfunds",
+            TransactionType::AddFunds,
         )
         .await?;
 
-        assert_eq!(addfunds.transaction_type, "addfunds");
+        assert_eq!(addfunds.transaction_type, TransactionType::AddFunds);
 
         // Create use_product transaction
         let use_product = create_custom_transaction(
@@ -558,18 +2305,18 @@ mod tests {
             "2x Coffee",
             "user1",
             None,
-            "use_product",
+            TransactionType::UseProduct,
         )
         .await?;
 
-        assert_eq!(use_product.transaction_type, "use_product");
+        assert_eq!(use_product.transaction_type, TransactionType::UseProduct);
 
         // Verify persistence
         let retrieved_spend = crate::entities::Transaction::find_by_id(spend.id)
             .one(&db)
             .await?
             .unwrap();
-        assert_eq!(retrieved_spend.transaction_type, "spend");
+        assert_eq!(retrieved_spend.transaction_type, TransactionType::Spend);
 
         Ok(())
     }
@@ -586,7 +2333,7 @@ mod tests {
             "This is a required description",
             "user1",
             None,
-            "addfunds",
+            TransactionType::AddFunds,
         )
         .await?;
 
@@ -616,4 +2363,703 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_create_transaction_idempotent_on_message_id() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+
+        let first = create_transaction(
+            &db,
+            envelope.id,
+            50.0,
+            "Paycheck".to_string(),
+            "user1".to_string(),
+            Some("interaction_1".to_string()),
+            TransactionType::AddFunds,
+        )
+        .await?;
+
+        // A retry with the same interaction ID returns the original transaction
+        let retry = create_transaction(
+            &db,
+            envelope.id,
+            50.0,
+            "Paycheck".to_string(),
+            "user1".to_string(),
+            Some("interaction_1".to_string()),
+            TransactionType::AddFunds,
+        )
+        .await?;
+        assert_eq!(retry.id, first.id);
+
+        let updated = crate::core::envelope::get_envelope_by_id(&db, envelope.id)
+            .await?
+            .unwrap();
+        assert_eq!(updated.balance, 50.0);
+        assert_eq!(
+            get_transactions_for_envelope(&db, envelope.id).await?.len(),
+            1
+        );
+
+        // The unique index rejects duplicates written outside create_transaction
+        let duplicate = transaction::ActiveModel {
+            envelope_id: Set(envelope.id),
+            amount: Set(1.0),
+            description: Set("dup".to_string()),
+            timestamp: Set(chrono::Utc::now()),
+            user_id: Set("user1".to_string()),
+            message_id: Set(Some("interaction_1".to_string())),
+            transaction_type: Set(TransactionType::AddFunds),
+            ..Default::default()
+        };
+        assert!(duplicate.insert(&db).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_converted_transaction_records_original() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        create_test_transaction(&db, envelope.id, 100.0).await?;
+
+        let provider = crate::core::fx::StaticRateProvider::new([("USD", 1.0), ("CAD", 0.75)]);
+        let conversion = crate::core::fx::convert(&provider, -40.0, "CAD", "USD")?;
+
+        let txn = create_converted_transaction(
+            &db,
+            envelope.id,
+            &conversion,
+            "Poutine".to_string(),
+            "user1".to_string(),
+            None,
+            TransactionType::Spend,
+            Clearance::default(),
+        )
+        .await?;
+        assert_eq!(txn.amount, -30.0);
+        assert_eq!(txn.original_amount, Some(-40.0));
+        assert_eq!(txn.original_currency.as_deref(), Some("CAD"));
+
+        let updated = crate::core::envelope::get_envelope_by_id(&db, envelope.id)
+            .await?
+            .unwrap();
+        assert_eq!(updated.balance, 70.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_refund_partial_and_full() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        create_test_transaction(&db, envelope.id, 100.0).await?;
+        let spend = create_test_transaction(&db, envelope.id, -40.0).await?;
+
+        let partial = create_refund(&db, spend.id, Some(15.0), "user1".to_string(), None).await?;
+        assert_eq!(partial.amount, 15.0);
+        assert_eq!(partial.refund_of, Some(spend.id));
+        assert_eq!(partial.transaction_type, TransactionType::Refund);
+
+        // Can't refund more than what's left
+        let too_much = create_refund(&db, spend.id, Some(30.0), "user1".to_string(), None).await;
+        assert!(matches!(too_much, Err(Error::InvalidAmount { .. })));
+
+        // Defaults to the remainder
+        let rest = create_refund(&db, spend.id, None, "user1".to_string(), None).await?;
+        assert_eq!(rest.amount, 25.0);
+        assert_eq!(get_refunded_amount(&db, spend.id).await?, 40.0);
+
+        let updated = crate::core::envelope::get_envelope_by_id(&db, envelope.id)
+            .await?
+            .unwrap();
+        assert_eq!(updated.balance, 100.0);
+
+        let again = create_refund(&db, spend.id, None, "user1".to_string(), None).await;
+        assert!(matches!(again, Err(Error::Config { .. })));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_refund_rejects_non_spends() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        let deposit = create_test_transaction(&db, envelope.id, 100.0).await?;
+
+        let result = create_refund(&db, deposit.id, None, "user1".to_string(), None).await;
+        assert!(matches!(result, Err(Error::Config { .. })));
+        let result = create_refund(&db, 999, None, "user1".to_string(), None).await;
+        assert!(matches!(
+            result,
+            Err(Error::TransactionNotFound { id: 999 })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pending_spend_cleared_with_posted_amount() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        create_test_transaction(&db, envelope.id, 100.0).await?;
+        let pending = create_pending_spend(
+            &db,
+            envelope.id,
+            -40.0,
+            None,
+            "Dinner".to_string(),
+            "user1".to_string(),
+            None,
+            Clearance::default(),
+        )
+        .await?;
+        assert!(pending.pending);
+
+        // Pending spends already reduce the available balance
+        let balance = |db| async move {
+            crate::core::envelope::get_envelope_by_id(db, envelope.id)
+                .await
+                .map(|env| env.unwrap().balance)
+        };
+        assert_eq!(balance(&db).await?, 60.0);
+        assert_eq!(
+            get_pending_totals(&db).await?.get(&envelope.id),
+            Some(&-40.0)
+        );
+        assert!(matches!(
+            create_refund(&db, pending.id, None, "user1".to_string(), None).await,
+            Err(Error::Config { .. })
+        ));
+
+        // The posted amount includes a tip
+        let cleared = clear_transaction(&db, pending.id, Some(48.0)).await?;
+        assert!(!cleared.pending);
+        assert_eq!(cleared.amount, -48.0);
+        assert_eq!(balance(&db).await?, 52.0);
+        assert!(get_pending_totals(&db).await?.is_empty());
+
+        assert!(matches!(
+            clear_transaction(&db, pending.id, None).await,
+            Err(Error::Config { .. })
+        ));
+        assert!(matches!(
+            clear_transaction(&db, 999, None).await,
+            Err(Error::TransactionNotFound { id: 999 })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_clearing_more_than_pending_passes_spend_guardrails() -> Result<()> {
+        use crate::core::{category_cap, envelope as envelope_core};
+
+        let (db, envelope) = setup_with_envelope().await?;
+        create_test_transaction(&db, envelope.id, 100.0).await?;
+        let pending = create_pending_spend(
+            &db,
+            envelope.id,
+            -1.0,
+            None,
+            "Dinner".to_string(),
+            "user1".to_string(),
+            None,
+            Clearance::default(),
+        )
+        .await?;
+        let clear = || clear_transaction(&db, pending.id, Some(50.0));
+
+        // The $49 posted on top of the pending $1 is checked like a new spend
+        envelope_core::set_min_balance(&db, envelope.id, Some(80.0)).await?;
+        assert!(matches!(
+            clear().await,
+            Err(Error::BelowMinimumBalance { floor, .. }) if floor == 80.0
+        ));
+        envelope_core::set_min_balance(&db, envelope.id, None).await?;
+
+        approval::set_threshold(&db, Some(20.0)).await?;
+        assert!(matches!(
+            clear().await,
+            Err(Error::ApprovalRequired { amount, .. }) if amount == 49.0
+        ));
+        approval::set_threshold(&db, None).await?;
+
+        envelope_core::set_daily_limit(&db, envelope.id, Some(30.0)).await?;
+        assert!(matches!(
+            clear().await,
+            Err(Error::DailyLimitExceeded { spent, .. }) if spent == 50.0
+        ));
+        envelope_core::set_daily_limit(&db, envelope.id, None).await?;
+
+        envelope_core::set_frozen(&db, envelope.id, true, None).await?;
+        assert!(matches!(clear().await, Err(Error::EnvelopeFrozen { .. })));
+        envelope_core::set_frozen(&db, envelope.id, false, None).await?;
+
+        category_cap::set_cap(&db, &envelope.category, 30.0, true).await?;
+        assert!(matches!(
+            clear().await,
+            Err(Error::CategoryCapExceeded { spent, .. }) if spent == 50.0
+        ));
+        category_cap::remove_cap(&db, &envelope.category).await?;
+
+        // Nothing was changed by the refused attempts
+        let envelope = envelope_core::get_envelope_by_id(&db, envelope.id)
+            .await?
+            .unwrap();
+        assert_eq!(envelope.balance, 99.0);
+        assert_eq!(clear().await?.amount, -50.0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_recent_refundable_transactions() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        create_test_transaction(&db, envelope.id, 100.0).await?;
+        let refunded = create_test_transaction(&db, envelope.id, -10.0).await?;
+        let open = create_test_transaction(&db, envelope.id, -20.0).await?;
+        create_refund(&db, refunded.id, None, "test_user".to_string(), None).await?;
+
+        let refundable = get_recent_refundable_transactions(&db, "test_user", None, 10).await?;
+        let ids: Vec<i64> = refundable.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![open.id]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_last_spend() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        assert!(get_last_spend(&db, "test_user").await?.is_none());
+        create_test_transaction(&db, envelope.id, 100.0).await?;
+        create_test_transaction(&db, envelope.id, -10.0).await?;
+        let last = create_test_transaction(&db, envelope.id, -20.0).await?;
+        create_refund(&db, last.id, Some(5.0), "test_user".to_string(), None).await?;
+
+        let found = get_last_spend(&db, "test_user").await?;
+        assert_eq!(found.map(|spend| spend.id), Some(last.id));
+        assert!(get_last_spend(&db, "someone_else").await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_recent_activity() -> Result<()> {
+        let (db, shared) = setup_with_envelope().await?;
+        let private = create_custom_envelope(
+            &db,
+            "fun",
+            Some("user2".to_string()),
+            "quality_of_life",
+            50.0,
+            true,
+            false,
+        )
+        .await?;
+        crate::core::envelope::set_private(&db, private.id, true).await?;
+        create_test_transaction(&db, shared.id, 100.0).await?;
+        create_test_transaction(&db, private.id, 20.0).await?;
+        for (envelope_id, user) in [(shared.id, "user1"), (private.id, "user2")] {
+            create_transaction(
+                &db,
+                envelope_id,
+                -5.0,
+                "Coffee".to_string(),
+                user.to_string(),
+                None,
+                TransactionType::Spend,
+            )
+            .await?;
+        }
+
+        // Newest first, across envelopes
+        let all = get_recent_activity(&db, &ActivityFilter::default(), 10).await?;
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].envelope_id, private.id);
+        assert_eq!(
+            get_recent_activity(&db, &ActivityFilter::default(), 2)
+                .await?
+                .len(),
+            2
+        );
+
+        let spends = ActivityFilter {
+            transaction_type: Some(TransactionType::Spend),
+            ..Default::default()
+        };
+        assert_eq!(get_recent_activity(&db, &spends, 10).await?.len(), 2);
+
+        let by_user = ActivityFilter {
+            user_id: Some("user1".to_string()),
+            ..Default::default()
+        };
+        let found = get_recent_activity(&db, &by_user, 10).await?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].envelope_id, shared.id);
+
+        // Other members' private envelopes stay hidden
+        let viewed_by = |viewer: &str| ActivityFilter {
+            viewer_id: Some(viewer.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_recent_activity(&db, &viewed_by("user1"), 10)
+                .await?
+                .len(),
+            2
+        );
+        assert_eq!(
+            get_recent_activity(&db, &viewed_by("user2"), 10)
+                .await?
+                .len(),
+            4
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_transactions() -> Result<()> {
+        let (db, envelope) = setup_with_envelope().await?;
+        create_test_transaction(&db, envelope.id, 100.0).await?;
+        for description in ["Weekly groceries", "Gas station", "Grocery run"] {
+            create_transaction(
+                &db,
+                envelope.id,
+                -5.0,
+                description.to_string(),
+                "user1".to_string(),
+                None,
+                TransactionType::Spend,
+            )
+            .await?;
+        }
+
+        let found = search_transactions(&db, "groc", 10).await?;
+        let descriptions: Vec<&str> = found.iter().map(|t| t.description.as_str()).collect();
+        assert_eq!(descriptions.len(), 2);
+        assert!(descriptions.contains(&"Weekly groceries"));
+        assert!(descriptions.contains(&"Grocery run"));
+
+        // Every word must match
+        assert_eq!(search_transactions(&db, "grocery RUN", 10).await?.len(), 1);
+
+        // Blank queries and FTS5 syntax in user input are handled
+        assert!(search_transactions(&db, "  ", 10).await?.is_empty());
+        assert!(
+            search_transactions(&db, "\"gas AND (", 10)
+                .await?
+                .is_empty()
+        );
+
+        // Deleted transactions drop out of the index
+        let gas = search_transactions(&db, "gas", 10).await?;
+        assert_eq!(gas.len(), 1);
+        delete_transaction(&db, gas[0].id).await?;
+        assert!(search_transactions(&db, "gas", 10).await?.is_empty());
+
+        let refundable =
+            get_recent_refundable_transactions(&db, "user1", Some("weekly"), 10).await?;
+        assert_eq!(refundable.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction_pages_and_months() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let january = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        for i in 0..12 {
+            let created = create_test_transaction(&db, env.id, 10.0).await?;
+            if i < 4 {
+                let mut active_model: transaction::ActiveModel = created.into();
+                active_model.timestamp = Set(january.and_time(NaiveTime::MIN).and_utc());
+                active_model.update(&db).await?;
+            }
+        }
+
+        let first = get_transaction_page(&db, env.id, None, 0, 5).await?;
+        assert_eq!(first.transactions.len(), 5);
+        assert_eq!((first.page, first.pages), (0, 3));
+        // Pages past the end are clamped to the last page
+        let last = get_transaction_page(&db, env.id, None, 10, 5).await?;
+        assert_eq!(last.page, 2);
+        assert_eq!(last.transactions.len(), 2);
+        assert!(last.transactions.iter().all(|t| t.timestamp.month() == 1));
+
+        let months = get_transaction_months(&db, env.id).await?;
+        assert_eq!(months.len(), 2);
+        assert_eq!(months[1], NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+
+        let in_january = get_transaction_page(&db, env.id, Some(january), 0, 5).await?;
+        assert_eq!(in_january.transactions.len(), 4);
+        assert_eq!(in_january.pages, 1);
+
+        let empty = get_transaction_page(&db, 999, None, 0, 5).await?;
+        assert!(empty.transactions.is_empty());
+        assert_eq!(empty.pages, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_daily_limits() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 500.0).await?;
+        let now = Utc::now();
+
+        // No limits set
+        check_daily_limits(&db, &env, "test_user", 400.0, now).await?;
+
+        let env = crate::core::envelope::set_daily_limit(&db, env.id, Some(100.0)).await?;
+        create_test_transaction(&db, env.id, -60.0).await?;
+        check_daily_limits(&db, &env, "test_user", 40.0, now).await?;
+        assert!(matches!(
+            check_daily_limits(&db, &env, "test_user", 40.01, now).await,
+            Err(Error::DailyLimitExceeded { limit: 100.0, .. })
+        ));
+        // Tomorrow starts a fresh day
+        let tomorrow = now.checked_add_days(Days::new(1)).unwrap();
+        check_daily_limits(&db, &env, "test_user", 100.0, tomorrow).await?;
+
+        // The member limit counts spending across envelopes
+        let env = crate::core::envelope::set_daily_limit(&db, env.id, None).await?;
+        set_user_daily_limit(&db, "test_user", Some(50.0)).await?;
+        assert_eq!(get_user_daily_limit(&db, "test_user").await?, Some(50.0));
+        assert_eq!(get_user_spent_on_day(&db, "test_user", now).await?, 60.0);
+        assert!(matches!(
+            check_daily_limits(&db, &env, "test_user", 1.0, now).await,
+            Err(Error::DailyLimitExceeded { limit: 50.0, .. })
+        ));
+        check_daily_limits(&db, &env, "other_user", 1.0, now).await?;
+
+        assert!(matches!(
+            set_user_daily_limit(&db, "test_user", Some(-5.0)).await,
+            Err(Error::InvalidAmount { .. })
+        ));
+        set_user_daily_limit(&db, "test_user", None).await?;
+        assert_eq!(get_user_daily_limit(&db, "test_user").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spends_enforce_daily_limits() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 500.0).await?;
+        crate::core::envelope::set_daily_limit(&db, env.id, Some(100.0)).await?;
+
+        let spend = |amount: f64, transaction_type: TransactionType, clearance: Clearance| {
+            create_cleared_transaction(
+                &db,
+                env.id,
+                -amount,
+                "Lunch".to_string(),
+                "test_user".to_string(),
+                None,
+                transaction_type,
+                clearance,
+            )
+        };
+        spend(60.0, TransactionType::Spend, Clearance::default()).await?;
+        for transaction_type in [TransactionType::Spend, TransactionType::UseProduct] {
+            assert!(matches!(
+                spend(50.0, transaction_type, Clearance::default()).await,
+                Err(Error::DailyLimitExceeded { limit: 100.0, .. })
+            ));
+        }
+
+        // A confirmed spend goes past the limit, and funds still come in
+        let confirmed = Clearance {
+            over_daily_limit: true,
+            ..Default::default()
+        };
+        spend(50.0, TransactionType::Spend, confirmed).await?;
+        create_test_transaction(&db, env.id, 20.0).await?;
+        let balance = crate::core::envelope::get_envelope_by_id(&db, env.id)
+            .await?
+            .unwrap()
+            .balance;
+        assert_eq!(balance, 410.0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_set_quantity() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 100.0).await?;
+        let spend = create_test_transaction(&db, env.id, -12.8).await?;
+        assert_eq!(unit_price(&spend), None);
+
+        let spend = set_quantity(&db, spend.id, 3.2, Some(" gal ")).await?;
+        assert_eq!(spend.quantity, Some(3.2));
+        assert_eq!(spend.unit.as_deref(), Some("gal"));
+        assert!((unit_price(&spend).unwrap() - 4.0).abs() < 1e-9);
+        assert_eq!(
+            crate::core::report::format_quantity(&spend, crate::core::setup::Currency::default())
+                .as_deref(),
+            Some("3.2 gal @ $4.00/gal")
+        );
+
+        assert!(matches!(
+            set_quantity(&db, spend.id, 0.0, None).await,
+            Err(Error::InvalidAmount { .. })
+        ));
+        assert!(matches!(
+            validate_quantity(1.0, Some(&"x".repeat(17))),
+            Err(Error::Config { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_purge_takes_round_ups_along() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let savings = create_test_envelope(&db, "savings").await?;
+        create_test_transaction(&db, env.id, 100.0).await?;
+        round_up::set_round_up_envelope(&db, Some(savings.id)).await?;
+        create_test_transaction(&db, env.id, -10.25).await?;
+
+        // The spend and both legs go, and the change returns to the envelope
+        let spends = TransactionCriteria::new()
+            .envelope(env.id)
+            .transaction_type(TransactionType::Spend);
+        let purged = purge_transactions(&db, &spends).await?;
+        assert_eq!(purged.count, 3);
+        let savings = crate::core::envelope::get_envelope_by_id(&db, savings.id)
+            .await?
+            .unwrap();
+        assert!(savings.balance.abs() < 1e-9);
+        let env = crate::core::envelope::get_envelope_by_id(&db, env.id)
+            .await?
+            .unwrap();
+        assert!((env.balance - 100.0).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_purge_transactions() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 100.0).await?;
+        let first = create_test_transaction(&db, env.id, -10.0).await?;
+        create_test_transaction(&db, env.id, -20.0).await?;
+        create_refund(&db, first.id, Some(4.0), "test_user".to_string(), None).await?;
+
+        let spends = TransactionCriteria::new()
+            .envelope(env.id)
+            .transaction_type(TransactionType::Spend)
+            .user("test_user");
+        let preview = preview_purge(&db, &spends).await?;
+        assert_eq!(preview.count, 2);
+        assert!((preview.total + 30.0).abs() < 1e-9);
+
+        // The spends are hidden but kept, the refund stays linked, and the balance gets
+        // the spends back
+        let purged = purge_transactions(&db, &spends).await?;
+        assert_eq!(purged, preview);
+        assert_eq!(preview_purge(&db, &spends).await?.count, 0);
+        let remaining = get_transactions_for_envelope(&db, env.id).await?;
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.iter().any(|tx| tx.refund_of == Some(first.id)));
+        assert!(get_transaction_by_id(&db, first.id).await?.is_none());
+        let kept = crate::entities::Transaction::find_by_id(first.id)
+            .one(&db)
+            .await?;
+        assert!(kept.is_some_and(|tx| tx.deleted_at.is_some()));
+        let env = crate::core::envelope::get_envelope_by_id(&db, env.id)
+            .await?
+            .unwrap();
+        assert!((env.balance - 104.0).abs() < 1e-9);
+
+        // Purging the income would leave the envelope below zero, so it's refused
+        create_test_transaction(&db, env.id, -50.0).await?;
+        let income = TransactionCriteria::new()
+            .envelope(env.id)
+            .transaction_type(TransactionType::AddFunds);
+        let preview = preview_purge(&db, &income).await?;
+        assert_eq!(preview.overdrawn, vec![env.name.clone()]);
+        assert!(matches!(
+            purge_transactions(&db, &income).await,
+            Err(Error::Config { .. })
+        ));
+        assert_eq!(get_transactions_for_envelope(&db, env.id).await?.len(), 3);
+
+        // Transactions in a month that already has a statement are refused
+        let today = chrono::Utc::now().date_naive();
+        let this_month = calendar::BudgetCycle::containing(today, 1);
+        crate::core::statement::record_statements(
+            &db,
+            std::slice::from_ref(&env),
+            &this_month,
+            chrono::Utc::now(),
+        )
+        .await?;
+        let preview = preview_purge(&db, &spends).await?;
+        assert_eq!(preview.closed, vec![env.name.clone()]);
+        assert!(matches!(
+            purge_transactions(&db, &spends).await,
+            Err(Error::Config { .. })
+        ));
+
+        // Every filter must be narrowed by something, and ranges must be in order
+        assert!(matches!(
+            preview_purge(&db, &TransactionCriteria::new()).await,
+            Err(Error::Config { .. })
+        ));
+        let backwards = TransactionCriteria::new()
+            .from(today)
+            .to(today - chrono::Days::new(1));
+        assert!(matches!(
+            purge_transactions(&db, &backwards).await,
+            Err(Error::Config { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merchant_normalization() {
+        assert_eq!(
+            normalize_merchant("  Trader Joe's! "),
+            Some("trader joe s".to_string())
+        );
+        assert_eq!(normalize_merchant("#42"), None);
+        assert_eq!(
+            extract_merchant("Costco gas and snacks"),
+            Some("costco".to_string())
+        );
+        assert_eq!(extract_merchant("Transaction"), None);
+        assert_eq!(extract_merchant("   "), None);
+    }
+
+    #[tokio::test]
+    async fn test_top_merchants() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 500.0).await?;
+        let spend = |amount: f64, description: &str| {
+            create_transaction(
+                &db,
+                env.id,
+                amount,
+                description.to_string(),
+                "test_user".to_string(),
+                None,
+                TransactionType::Spend,
+            )
+        };
+        let costco = spend(-80.0, "Costco run").await?;
+        assert_eq!(costco.merchant.as_deref(), Some("costco"));
+        spend(-40.0, "COSTCO, gas").await?;
+        spend(-100.0, "Target").await?;
+        let other = spend(-10.0, "Transaction").await?;
+        assert_eq!(other.merchant, None);
+        let other = set_merchant(&db, other.id, "Corner Shop").await?;
+        assert_eq!(other.merchant.as_deref(), Some("corner shop"));
+        // Refunds keep the merchant of the spend they reverse
+        create_refund(&db, costco.id, Some(30.0), "test_user".to_string(), None).await?;
+
+        let now = Utc::now();
+        let start = now.checked_sub_days(Days::new(1)).unwrap();
+        let end = now.checked_add_days(Days::new(1)).unwrap();
+        let merchants = get_top_merchants(&db, start, end, "test_user", 2).await?;
+        let totals: Vec<(&str, f64, usize)> = merchants
+            .iter()
+            .map(|m| (m.merchant.as_str(), m.spent, m.transaction_count))
+            .collect();
+        assert_eq!(totals, vec![("target", 100.0, 1), ("costco", 90.0, 3)]);
+        Ok(())
+    }
 }
@@ -0,0 +1,555 @@
+//! Undo history - Reversing a member's recent changes.
+//!
+//! Commands that change data call [`record`] with an [`UndoAction`] describing how to
+//! reverse the change; the action is stored as JSON in the command journal. Each member
+//! keeps their last [`HISTORY_LENGTH`] changes, listed newest first by [`history`], and
+//! [`undo`] reverses any of them, not just the latest. Each kind of action has its own
+//! reversal: a transaction is deleted with its amount reversed (unless it has been
+//! refunded or reversing it would overdraw an envelope), an envelope edit puts back the
+//! settings it changed (unless they have been changed again since), and a deleted
+//! envelope is brought back (unless its name has been taken since).
+
+use crate::{
+    core::{
+        cache::{self, CachedTable},
+        envelope as envelope_core, transaction,
+    },
+    entities::{
+        CommandJournal, Envelope, Transaction, command_journal, envelope,
+        transaction as transaction_entity,
+    },
+    errors::{Error, Result},
+};
+use sea_orm::{ActiveModelTrait, Condition, QueryOrder, QuerySelect, Set, prelude::*};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::str::FromStr;
+
+/// Changes kept per member; older entries are dropped as new ones are recorded.
+pub const HISTORY_LENGTH: u64 = 10;
+
+/// Envelope fields an edit never changes: the balance moves with transactions, and
+/// deletion has its own action.
+const UNEDITED_FIELDS: [&str; 3] = ["id", "balance", "is_deleted"];
+
+/// One envelope setting changed by an edit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// Envelope column, e.g. `"allocation"`
+    pub field: String,
+    /// Value before the edit
+    pub before: Value,
+    /// Value the edit left
+    pub after: Value,
+}
+
+/// How to reverse one change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UndoAction {
+    /// A transaction was recorded; undoing deletes it and reverses its amount
+    Transaction {
+        /// ID of the recorded transaction
+        transaction_id: i64,
+    },
+    /// An envelope's settings were edited; undoing restores the ones that changed
+    EnvelopeEdit {
+        /// ID of the edited envelope
+        envelope_id: i64,
+        /// The settings the edit changed
+        changes: Vec<FieldChange>,
+    },
+    /// An envelope was deleted; undoing restores it
+    EnvelopeDelete {
+        /// ID of the deleted envelope
+        envelope_id: i64,
+    },
+}
+
+impl UndoAction {
+    /// Name stored in the journal's `kind` column.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Transaction { .. } => "transaction",
+            Self::EnvelopeEdit { .. } => "envelope_edit",
+            Self::EnvelopeDelete { .. } => "envelope_delete",
+        }
+    }
+
+    /// Describes an edit that turned `before` into `after`, or `None` if no setting
+    /// changed.
+    ///
+    /// # Errors
+    /// Returns `Error::Config` if an envelope can't be serialized.
+    pub fn envelope_edit(
+        before: &envelope::Model,
+        after: &envelope::Model,
+    ) -> Result<Option<Self>> {
+        let before = envelope_fields(before)?;
+        let changes: Vec<FieldChange> = envelope_fields(after)?
+            .into_iter()
+            .filter(|(field, _)| !UNEDITED_FIELDS.contains(&field.as_str()))
+            .filter_map(|(field, after)| {
+                let before = before.get(&field)?.clone();
+                (before != after).then_some(FieldChange {
+                    field,
+                    before,
+                    after,
+                })
+            })
+            .collect();
+        Ok((!changes.is_empty()).then_some(Self::EnvelopeEdit {
+            envelope_id: after.id,
+            changes,
+        }))
+    }
+}
+
+/// Adds a change to `user_id`'s undo history, dropping entries beyond [`HISTORY_LENGTH`].
+///
+/// A transaction already in the journal isn't added again: the command recording it was
+/// retried and got the existing transaction back instead of a new one, so the existing
+/// entry is returned.
+///
+/// # Errors
+/// Returns an error if the action can't be serialized or a database write fails.
+pub async fn record(
+    db: &DatabaseConnection,
+    user_id: &str,
+    action: &UndoAction,
+    summary: &str,
+) -> Result<command_journal::Model> {
+    let payload = serde_json::to_string(action).map_err(|e| Error::Config {
+        message: format!("Failed to save the undo entry: {e}"),
+    })?;
+    if matches!(action, UndoAction::Transaction { .. })
+        && let Some(existing) = CommandJournal::find()
+            .filter(command_journal::Column::Kind.eq(action.kind()))
+            .filter(command_journal::Column::Payload.eq(payload.as_str()))
+            .one(db)
+            .await?
+    {
+        return Ok(existing);
+    }
+    let entry = command_journal::ActiveModel {
+        user_id: Set(user_id.to_string()),
+        kind: Set(action.kind().to_string()),
+        payload: Set(payload),
+        summary: Set(summary.to_string()),
+        created_at: Set(chrono::Utc::now()),
+        undone_at: Set(None),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    let kept: Vec<i64> = CommandJournal::find()
+        .select_only()
+        .column(command_journal::Column::Id)
+        .filter(command_journal::Column::UserId.eq(user_id))
+        .order_by_desc(command_journal::Column::Id)
+        .limit(HISTORY_LENGTH)
+        .into_tuple()
+        .all(db)
+        .await?;
+    CommandJournal::delete_many()
+        .filter(command_journal::Column::UserId.eq(user_id))
+        .filter(command_journal::Column::Id.is_not_in(kept))
+        .exec(db)
+        .await?;
+
+    Ok(entry)
+}
+
+/// Lists `user_id`'s changes that can still be undone, newest first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn history(
+    db: &DatabaseConnection,
+    user_id: &str,
+) -> Result<Vec<command_journal::Model>> {
+    CommandJournal::find()
+        .filter(command_journal::Column::UserId.eq(user_id))
+        .filter(command_journal::Column::UndoneAt.is_null())
+        .order_by_desc(command_journal::Column::Id)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Reverses one of `user_id`'s changes: entry `entry_id`, or the latest one if `None`.
+///
+/// Returns the entry, now marked as undone.
+///
+/// # Errors
+/// Returns `Error::Config` if there is nothing to undo, the entry isn't in the member's
+/// history or was already undone, or what it changed no longer exists. Returns an error
+/// if the reversal or a database write fails.
+pub async fn undo(
+    db: &DatabaseConnection,
+    user_id: &str,
+    entry_id: Option<i64>,
+) -> Result<command_journal::Model> {
+    let entries = history(db, user_id).await?;
+    let entry = match entry_id {
+        Some(id) => entries.into_iter().find(|entry| entry.id == id),
+        None => entries.into_iter().next(),
+    };
+    let Some(entry) = entry else {
+        return Err(Error::Config {
+            message: entry_id.map_or_else(
+                || "Nothing to undo".to_string(),
+                |id| format!("Entry #{id} isn't in your undo history; see `/undo list`"),
+            ),
+        });
+    };
+
+    let action: UndoAction = serde_json::from_str(&entry.payload).map_err(|e| Error::Config {
+        message: format!("Entry #{} can't be undone: {e}", entry.id),
+    })?;
+    reverse(db, &action).await?;
+
+    let mut active_model: command_journal::ActiveModel = entry.into();
+    active_model.undone_at = Set(Some(chrono::Utc::now()));
+    active_model.update(db).await.map_err(Into::into)
+}
+
+/// Applies the reversal for one kind of action.
+async fn reverse(db: &DatabaseConnection, action: &UndoAction) -> Result<()> {
+    match action {
+        UndoAction::Transaction { transaction_id } => {
+            check_reversible(db, *transaction_id).await?;
+            transaction::delete_transaction(db, *transaction_id).await
+        }
+        UndoAction::EnvelopeEdit {
+            envelope_id,
+            changes,
+        } => restore_fields(db, *envelope_id, changes).await,
+        UndoAction::EnvelopeDelete { envelope_id } => {
+            let Some(deleted) = Envelope::find_by_id(*envelope_id).one(db).await? else {
+                return Err(Error::Config {
+                    message: format!("Envelope #{envelope_id} no longer exists"),
+                });
+            };
+            if !deleted.is_deleted {
+                return Err(Error::Config {
+                    message: format!("Envelope '{}' has already been restored", deleted.name),
+                });
+            }
+            envelope_core::restore_envelope(db, *envelope_id).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Checks that deleting a transaction can reverse it cleanly.
+///
+/// Refused while refunds or product returns of it remain, which would be credited a
+/// second time, and if reversing it or its round-up legs would take an envelope below
+/// zero, as purges are.
+async fn check_reversible(db: &DatabaseConnection, transaction_id: i64) -> Result<()> {
+    if transaction::get_transaction_by_id(db, transaction_id)
+        .await?
+        .is_none()
+    {
+        return Err(Error::Config {
+            message: format!("Transaction #{transaction_id} no longer exists"),
+        });
+    }
+    let refunds = Transaction::find_active()
+        .filter(transaction_entity::Column::RefundOf.eq(transaction_id))
+        .count(db)
+        .await?;
+    if refunds > 0 {
+        return Err(Error::Config {
+            message: format!(
+                "Transaction #{transaction_id} has been refunded or returned; undo that first"
+            ),
+        });
+    }
+
+    let reversed = Transaction::find_active()
+        .filter(
+            Condition::any()
+                .add(transaction_entity::Column::Id.eq(transaction_id))
+                .add(transaction_entity::Column::RoundUpOf.eq(transaction_id)),
+        )
+        .all(db)
+        .await?;
+    for tx in reversed.iter().filter(|tx| tx.amount > 0.0) {
+        let Some(env) = Envelope::find_by_id(tx.envelope_id).one(db).await? else {
+            continue;
+        };
+        if env.balance - tx.amount < -0.005 {
+            return Err(Error::Config {
+                message: format!(
+                    "Undoing this would leave '{}' below zero; add funds first",
+                    env.name
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Puts back the settings an envelope edit changed.
+///
+/// Refused if any of them has been changed again since, so a later edit (possibly by
+/// another member) is never silently reverted. A name goes back through
+/// [`envelope_core::rename_envelope`], so it is normalized and checked for clashes.
+async fn restore_fields(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    changes: &[FieldChange],
+) -> Result<()> {
+    let current = active_envelope(db, envelope_id).await?;
+    let mut fields = envelope_fields(&current)?;
+    let changed_since: Vec<&str> = changes
+        .iter()
+        .filter(|change| fields.get(&change.field) != Some(&change.after))
+        .map(|change| change.field.as_str())
+        .collect();
+    if !changed_since.is_empty() {
+        return Err(Error::Config {
+            message: format!(
+                "Envelope '{}' has been changed again since ({}); undo the later change first",
+                current.name,
+                changed_since.join(", ")
+            ),
+        });
+    }
+
+    let (names, settings): (Vec<_>, Vec<_>) =
+        changes.iter().partition(|change| change.field == "name");
+    if let Some(name) = names.first() {
+        let name = name.before.as_str().ok_or_else(|| Error::Config {
+            message: "The previous name can't be read".to_string(),
+        })?;
+        envelope_core::rename_envelope(db, envelope_id, name).await?;
+    }
+    if settings.is_empty() {
+        return Ok(());
+    }
+
+    for change in &settings {
+        fields.insert(change.field.clone(), change.before.clone());
+    }
+    let restored: envelope::Model =
+        serde_json::from_value(Value::Object(fields)).map_err(|e| Error::Config {
+            message: format!("The previous settings can't be read: {e}"),
+        })?;
+    // Only the restored columns are written
+    let mut active_model: envelope::ActiveModel = restored.into();
+    for change in &settings {
+        let column = envelope::Column::from_str(&change.field).map_err(|_| Error::Config {
+            message: format!("Envelope setting '{}' no longer exists", change.field),
+        })?;
+        active_model.reset(column);
+    }
+    active_model.update(db).await?;
+    cache::invalidate(CachedTable::Envelopes);
+    Ok(())
+}
+
+/// An envelope's fields by column name, as stored in undo entries.
+fn envelope_fields(envelope: &envelope::Model) -> Result<Map<String, Value>> {
+    match serde_json::to_value(envelope) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(_) => Err(Error::Config {
+            message: "Envelope isn't a JSON object".to_string(),
+        }),
+        Err(e) => Err(Error::Config {
+            message: format!("Failed to save the envelope edit: {e}"),
+        }),
+    }
+}
+
+/// Loads an envelope that hasn't been deleted.
+async fn active_envelope(db: &DatabaseConnection, envelope_id: i64) -> Result<envelope::Model> {
+    Envelope::find_by_id(envelope_id)
+        .filter(envelope::Column::IsDeleted.eq(false))
+        .one(db)
+        .await?
+        .ok_or_else(|| Error::Config {
+            message: format!("Envelope #{envelope_id} has been deleted"),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{core::envelope as envelope_core, test_utils::*};
+
+    #[tokio::test]
+    async fn test_undo_history() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+
+        create_test_transaction(&db, env.id, 50.0).await?;
+        let spend = create_test_transaction(&db, env.id, -20.0).await?;
+        record(
+            &db,
+            "user1",
+            &UndoAction::Transaction {
+                transaction_id: spend.id,
+            },
+            "Spent $20.00",
+        )
+        .await?;
+        let edited = envelope_core::set_allocation(&db, env.id, 999.0).await?;
+        let action = UndoAction::envelope_edit(&env, &edited)?.unwrap();
+        let edit = record(&db, "user1", &action, "Edited allocation").await?;
+
+        // Undo the older spend first; the edit stays in the history
+        let entries = history(&db, "user1").await?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, edit.id);
+        undo(&db, "user1", Some(entries[1].id)).await?;
+        let after_spend = envelope_core::get_envelope_by_id(&db, env.id).await?;
+        assert_eq!(after_spend.map(|e| e.balance), Some(env.balance + 50.0));
+        assert!(undo(&db, "user1", Some(entries[1].id)).await.is_err());
+        assert!(undo(&db, "user2", None).await.is_err());
+
+        // The edit is reverted, keeping the balance
+        undo(&db, "user1", None).await?;
+        let restored = envelope_core::get_envelope_by_id(&db, env.id).await?;
+        assert_eq!(restored.map(|e| e.allocation), Some(env.allocation));
+        assert!(history(&db, "user1").await?.is_empty());
+
+        // Only the latest entries are kept
+        for n in 0..=HISTORY_LENGTH {
+            record(
+                &db,
+                "user1",
+                &UndoAction::EnvelopeDelete {
+                    envelope_id: env.id,
+                },
+                &format!("Change {n}"),
+            )
+            .await?;
+        }
+        let entries = history(&db, "user1").await?;
+        assert_eq!(entries.len(), 10);
+        assert_eq!(entries[0].summary, "Change 10");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_edit_restores_only_its_own_changes() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+
+        // Only the changed settings are kept, not the whole envelope
+        let edited = envelope_core::set_allocation(&db, env.id, 250.0).await?;
+        let action = UndoAction::envelope_edit(&env, &edited)?.unwrap();
+        assert!(matches!(
+            &action,
+            UndoAction::EnvelopeEdit { changes, .. }
+                if changes.len() == 1 && changes[0].field == "allocation"
+        ));
+        assert!(UndoAction::envelope_edit(&edited, &edited)?.is_none());
+        record(&db, "user1", &action, "Edited allocation").await?;
+
+        // Another member's later freeze survives the undo
+        envelope_core::set_frozen(&db, env.id, true, None).await?;
+        undo(&db, "user1", None).await?;
+        let restored = envelope_core::get_envelope_by_id(&db, env.id)
+            .await?
+            .unwrap();
+        assert_eq!(restored.allocation, env.allocation);
+        assert!(restored.frozen);
+
+        // An edit whose setting was changed again since is refused
+        let before = restored.clone();
+        let edited = envelope_core::set_allocation(&db, env.id, 300.0).await?;
+        let action = UndoAction::envelope_edit(&before, &edited)?.unwrap();
+        record(&db, "user1", &action, "Edited allocation").await?;
+        envelope_core::set_allocation(&db, env.id, 400.0).await?;
+        assert!(matches!(
+            undo(&db, "user1", None).await,
+            Err(Error::Config { .. })
+        ));
+        let current = envelope_core::get_envelope_by_id(&db, env.id)
+            .await?
+            .unwrap();
+        assert_eq!(current.allocation, 400.0);
+
+        // A name goes back only if no other envelope has taken it since
+        let renamed = envelope_core::rename_envelope(&db, env.id, "Food").await?;
+        let action = UndoAction::envelope_edit(&current, &renamed[0])?.unwrap();
+        record(&db, "user1", &action, "Renamed").await?;
+        create_test_envelope(&db, "test envelope").await?;
+        assert!(matches!(
+            undo(&db, "user1", None).await,
+            Err(Error::EnvelopeAlreadyExists { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_transaction_refusals() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let deposit = create_test_transaction(&db, env.id, 50.0).await?;
+        let spend = create_test_transaction(&db, env.id, -30.0).await?;
+        let action = |tx: &crate::entities::transaction::Model| UndoAction::Transaction {
+            transaction_id: tx.id,
+        };
+
+        // A retried command returns the same transaction, which is remembered once
+        record(&db, "user1", &action(&deposit), "Added $50.00").await?;
+        record(&db, "user1", &action(&deposit), "Added $50.00").await?;
+        assert_eq!(history(&db, "user1").await?.len(), 1);
+
+        // Taking the deposit back would overdraw the envelope
+        assert!(matches!(
+            undo(&db, "user1", None).await,
+            Err(Error::Config { .. })
+        ));
+
+        // A refunded spend can't be undone without crediting it twice
+        transaction::create_refund(&db, spend.id, Some(10.0), "user1".to_string(), None).await?;
+        record(&db, "user1", &action(&spend), "Spent $30.00").await?;
+        assert!(matches!(
+            undo(&db, "user1", None).await,
+            Err(Error::Config { .. })
+        ));
+        let current = envelope_core::get_envelope_by_id(&db, env.id)
+            .await?
+            .unwrap();
+        assert_eq!(current.balance, 30.0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undo_delete_checks_the_name() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let mut active_model: envelope::ActiveModel = env.clone().into();
+        active_model.is_deleted = Set(true);
+        active_model.update(&db).await?;
+        let action = UndoAction::EnvelopeDelete {
+            envelope_id: env.id,
+        };
+        record(&db, "user1", &action, "Deleted").await?;
+
+        // A new envelope has taken the name since
+        let taken = create_test_envelope(&db, "test envelope").await?;
+        assert!(matches!(
+            undo(&db, "user1", None).await,
+            Err(Error::EnvelopeAlreadyExists { .. })
+        ));
+
+        let mut active_model: envelope::ActiveModel = taken.into();
+        active_model.is_deleted = Set(true);
+        active_model.update(&db).await?;
+        undo(&db, "user1", None).await?;
+        let restored = envelope_core::get_envelope_by_id(&db, env.id)
+            .await?
+            .unwrap();
+        assert!(!restored.is_deleted);
+        Ok(())
+    }
+}
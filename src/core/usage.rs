@@ -0,0 +1,266 @@
+//! Command usage analytics - Which commands the household runs, and who runs them.
+//!
+//! Every command invocation is counted by [`record_command`], which the bot calls from
+//! the framework's pre-command hook, in the `command_usage` table: one row per command,
+//! member, and budget month. [`get_usage`] summarizes a month with its most-used
+//! commands, its most active members, and the member who recorded the most spends.
+
+use crate::{
+    core::{
+        calendar::{self, BudgetCycle},
+        statement,
+    },
+    entities::{CommandUsage, Transaction, TransactionType, command_usage, transaction},
+    errors::Result,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{Set, prelude::*, sea_query::Expr};
+use std::collections::HashMap;
+
+/// How often a command was run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandCount {
+    /// Full command name, e.g. `"transaction purge"`
+    pub command: String,
+    /// Number of invocations
+    pub count: i64,
+}
+
+/// How many commands a member ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberCount {
+    /// Discord user ID of the member
+    pub user_id: String,
+    /// Number of invocations
+    pub count: i64,
+}
+
+/// Command usage over one budget month, from [`get_usage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageSummary {
+    /// Budget month, as `YYYY-MM`
+    pub month: String,
+    /// Invocations of every command
+    pub total: i64,
+    /// Most-used commands, most first
+    pub commands: Vec<CommandCount>,
+    /// Most active members, most first
+    pub members: Vec<MemberCount>,
+    /// Member who recorded the most spends, with their number of spends
+    pub top_spender: Option<MemberCount>,
+}
+
+/// Counts one invocation of `command` by `user_id` at `at`, in that day's budget month.
+///
+/// # Errors
+/// Returns an error if a database query or write fails.
+pub async fn record_command<C>(
+    db: &C,
+    command: &str,
+    user_id: &str,
+    at: DateTime<Utc>,
+) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let today = calendar::local_date(db, at).await?;
+    let month = statement::month_label(&calendar::current_cycle(db, today).await?);
+
+    if increment(db, command, user_id, &month, at).await? {
+        return Ok(());
+    }
+    let inserted = command_usage::ActiveModel {
+        command: Set(command.to_string()),
+        user_id: Set(user_id.to_string()),
+        month: Set(month.clone()),
+        count: Set(1),
+        last_used: Set(at),
+        ..Default::default()
+    }
+    .insert(db)
+    .await;
+    if let Err(e) = inserted {
+        // A concurrent first invocation may have inserted the row already
+        if !increment(db, command, user_id, &month, at).await? {
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+/// Adds one to an existing usage row, returning whether there was one.
+async fn increment<C>(
+    db: &C,
+    command: &str,
+    user_id: &str,
+    month: &str,
+    at: DateTime<Utc>,
+) -> Result<bool>
+where
+    C: ConnectionTrait,
+{
+    let updated = CommandUsage::update_many()
+        .col_expr(
+            command_usage::Column::Count,
+            Expr::col(command_usage::Column::Count).add(1),
+        )
+        .col_expr(command_usage::Column::LastUsed, Expr::value(at))
+        .filter(command_usage::Column::Command.eq(command))
+        .filter(command_usage::Column::UserId.eq(user_id))
+        .filter(command_usage::Column::Month.eq(month))
+        .exec(db)
+        .await?
+        .rows_affected;
+    Ok(updated > 0)
+}
+
+/// Summarizes command usage in a budget month, keeping the top `limit` commands and
+/// members.
+///
+/// # Errors
+/// Returns an error if a database query fails.
+pub async fn get_usage<C>(db: &C, cycle: &BudgetCycle, limit: usize) -> Result<UsageSummary>
+where
+    C: ConnectionTrait,
+{
+    let month = statement::month_label(cycle);
+    let rows = CommandUsage::find()
+        .filter(command_usage::Column::Month.eq(month.as_str()))
+        .all(db)
+        .await?;
+
+    let mut commands: HashMap<String, i64> = HashMap::new();
+    let mut members: HashMap<String, i64> = HashMap::new();
+    for row in &rows {
+        *commands.entry(row.command.clone()).or_default() += row.count;
+        *members.entry(row.user_id.clone()).or_default() += row.count;
+    }
+    let total = commands.values().sum();
+    let mut commands: Vec<CommandCount> = commands
+        .into_iter()
+        .map(|(command, count)| CommandCount { command, count })
+        .collect();
+    commands.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    commands.truncate(limit);
+    let mut members = ranked_members(members);
+    members.truncate(limit);
+
+    Ok(UsageSummary {
+        month,
+        total,
+        commands,
+        members,
+        top_spender: get_top_spender(db, cycle).await?,
+    })
+}
+
+/// Finds the member who recorded the most spends and product uses in a budget month.
+async fn get_top_spender<C>(db: &C, cycle: &BudgetCycle) -> Result<Option<MemberCount>>
+where
+    C: ConnectionTrait,
+{
+    let (start, end) = calendar::utc_bounds(db, cycle.start, cycle.end).await?;
+    let spends = Transaction::find_active()
+        .filter(
+            transaction::Column::TransactionType
+                .is_in([TransactionType::Spend, TransactionType::UseProduct]),
+        )
+        .filter(transaction::Column::Amount.lt(0.0))
+        .filter(transaction::Column::Timestamp.gte(start))
+        .filter(transaction::Column::Timestamp.lt(end))
+        .all(db)
+        .await?;
+
+    let mut counts: HashMap<String, i64> = HashMap::new();
+    for spend in spends {
+        *counts.entry(spend.user_id).or_default() += 1;
+    }
+    Ok(ranked_members(counts).into_iter().next())
+}
+
+/// Orders member counts most first, then by user ID so ties are stable.
+fn ranked_members(counts: HashMap<String, i64>) -> Vec<MemberCount> {
+    let mut members: Vec<MemberCount> = counts
+        .into_iter()
+        .map(|(user_id, count)| MemberCount { user_id, count })
+        .collect();
+    members.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.user_id.cmp(&b.user_id))
+    });
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[tokio::test]
+    async fn test_record_and_summarize_usage() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let now = Utc::now();
+        for (command, user_id) in [
+            ("spend", "alice"),
+            ("spend", "alice"),
+            ("spend", "bob"),
+            ("report", "bob"),
+            ("transaction purge", "alice"),
+        ] {
+            record_command(&db, command, user_id, now).await?;
+        }
+        create_test_transaction(&db, env.id, 50.0).await?;
+        create_custom_transaction(&db, env.id, -5.0, "a", "bob", None, TransactionType::Spend)
+            .await?;
+        create_custom_transaction(&db, env.id, -6.0, "b", "bob", None, TransactionType::Spend)
+            .await?;
+        create_custom_transaction(
+            &db,
+            env.id,
+            -7.0,
+            "c",
+            "alice",
+            None,
+            TransactionType::Spend,
+        )
+        .await?;
+
+        let today = calendar::local_date(&db, now).await?;
+        let cycle = calendar::current_cycle(&db, today).await?;
+        let usage = get_usage(&db, &cycle, 2).await?;
+        assert_eq!(usage.total, 5);
+        assert_eq!(
+            usage.commands,
+            vec![
+                CommandCount {
+                    command: "spend".to_string(),
+                    count: 3
+                },
+                CommandCount {
+                    command: "report".to_string(),
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(usage.members[0].user_id, "alice");
+        assert_eq!(usage.members[0].count, 3);
+        assert_eq!(
+            usage.top_spender,
+            Some(MemberCount {
+                user_id: "bob".to_string(),
+                count: 2
+            })
+        );
+
+        // Other months are counted separately
+        let usage = get_usage(&db, &cycle.previous(), 2).await?;
+        assert_eq!(usage.total, 0);
+        assert!(usage.top_spender.is_none());
+        Ok(())
+    }
+}
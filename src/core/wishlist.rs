@@ -0,0 +1,422 @@
+//! Wishlist business logic - Purchases that need a partner's approval.
+//!
+//! A member proposes a purchase against an envelope with [`propose_purchase`]. The
+//! approver answers through [`respond_to_purchase`]: approval executes the spend for the
+//! proposer, declining just closes the proposal. Proposals left unanswered for
+//! [`PROPOSAL_TTL_DAYS`] days expire, either when someone responds late or when
+//! [`expire_stale_purchases`] sweeps them.
+//!
+//! Large spends held by the approval threshold (see
+//! [`crate::core::approval::hold_spend`]) are pending purchases too. They have no
+//! approver, so anyone but the member who made the spend can answer them.
+
+use crate::{
+    core::transaction,
+    entities::{
+        PendingPurchase, TransactionType, pending_purchase, transaction as transaction_entity,
+    },
+    errors::{Error, Result},
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{QueryOrder, Set, prelude::*};
+
+/// Status of a proposal waiting for a response.
+pub const STATUS_PENDING: &str = "pending";
+/// Status of a proposal whose spend was executed.
+pub const STATUS_APPROVED: &str = "approved";
+/// Status of a proposal the approver turned down.
+pub const STATUS_DECLINED: &str = "declined";
+/// Status of a proposal nobody answered in time.
+pub const STATUS_EXPIRED: &str = "expired";
+
+/// Days a proposal stays open before it expires.
+pub const PROPOSAL_TTL_DAYS: i64 = 7;
+
+/// Result of an approver responding to a proposal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PurchaseResponse {
+    /// The spend was executed
+    Approved {
+        /// The updated proposal
+        purchase: pending_purchase::Model,
+        /// The spend transaction
+        transaction: Box<transaction_entity::Model>,
+    },
+    /// The proposal was declined
+    Declined(pending_purchase::Model),
+    /// The proposal had expired before the response
+    Expired(pending_purchase::Model),
+    /// The proposal was already approved, declined, or expired
+    AlreadyDecided(pending_purchase::Model),
+    /// The responding member isn't the proposal's approver
+    NotApprover(pending_purchase::Model),
+}
+
+/// Whether a pending purchase is a held large spend rather than a wishlist proposal.
+#[must_use]
+pub const fn is_hold(purchase: &pending_purchase::Model) -> bool {
+    purchase.approver_id.is_none()
+}
+
+/// Whether `user_id` may approve or decline a pending purchase.
+fn can_respond(purchase: &pending_purchase::Model, user_id: &str) -> bool {
+    purchase.approver_id.as_deref().map_or_else(
+        || purchase.proposer_id != user_id,
+        |approver_id| approver_id == user_id,
+    )
+}
+
+/// Records a purchase proposal waiting for `approver_id` to respond.
+///
+/// # Errors
+/// Returns an error if:
+/// - The amount is not positive and finite (`Error::InvalidAmount`)
+/// - The proposer and approver are the same member (`Error::Config`)
+/// - The database insert fails
+pub async fn propose_purchase(
+    db: &DatabaseConnection,
+    envelope_id: i64,
+    proposer_id: &str,
+    approver_id: &str,
+    amount: f64,
+    description: String,
+) -> Result<pending_purchase::Model> {
+    if !amount.is_finite() || amount <= 0.0 {
+        return Err(Error::InvalidAmount { amount });
+    }
+    if proposer_id == approver_id {
+        return Err(Error::Config {
+            message: "A wishlist purchase must be approved by another member".to_string(),
+        });
+    }
+
+    let now = Utc::now();
+    let record = pending_purchase::ActiveModel {
+        envelope_id: Set(envelope_id),
+        proposer_id: Set(proposer_id.to_string()),
+        approver_id: Set(Some(approver_id.to_string())),
+        amount: Set(amount),
+        description: Set(description),
+        merchant: Set(None),
+        channel_id: Set(None),
+        status: Set(STATUS_PENDING.to_string()),
+        decided_by: Set(None),
+        transaction_id: Set(None),
+        created_at: Set(now),
+        expires_at: Set(now + chrono::Duration::days(PROPOSAL_TTL_DAYS)),
+        decided_at: Set(None),
+        ..Default::default()
+    };
+
+    record.insert(db).await.map_err(Into::into)
+}
+
+/// Approves or declines a proposal on behalf of `user_id`.
+///
+/// Approval spends the amount from the envelope as the proposer, with the held merchant
+/// if any. The spend uses the proposal as its idempotency key, so a repeated approval
+/// never spends twice. Held spends already passed the daily limits when they were made;
+/// wishlist proposals are checked against them now. If the spend fails (e.g. insufficient
+/// funds or a daily limit), the proposal stays pending.
+///
+/// # Errors
+/// Returns an error if:
+/// - The proposal does not exist (`Error::PurchaseNotFound`)
+/// - The spend fails on approval (e.g. `Error::InsufficientFunds` or
+///   `Error::DailyLimitExceeded`)
+/// - A database query or update fails
+pub async fn respond_to_purchase(
+    db: &DatabaseConnection,
+    purchase_id: i64,
+    user_id: &str,
+    approve: bool,
+    now: DateTime<Utc>,
+) -> Result<PurchaseResponse> {
+    let purchase = PendingPurchase::find_by_id(purchase_id)
+        .one(db)
+        .await?
+        .ok_or(Error::PurchaseNotFound { id: purchase_id })?;
+
+    if purchase.status != STATUS_PENDING {
+        return Ok(PurchaseResponse::AlreadyDecided(purchase));
+    }
+    if !can_respond(&purchase, user_id) {
+        return Ok(PurchaseResponse::NotApprover(purchase));
+    }
+    if purchase.expires_at <= now {
+        let expired = close(db, purchase, STATUS_EXPIRED, None, None, now).await?;
+        return Ok(PurchaseResponse::Expired(expired));
+    }
+
+    if !approve {
+        let declined = close(db, purchase, STATUS_DECLINED, Some(user_id), None, now).await?;
+        return Ok(PurchaseResponse::Declined(declined));
+    }
+
+    // The approver is another member, so the spend doesn't need a second approval
+    let over_daily_limit = is_hold(&purchase);
+    let mut spend = transaction::create_cleared_transaction(
+        db,
+        purchase.envelope_id,
+        -purchase.amount,
+        purchase.description.clone(),
+        purchase.proposer_id.clone(),
+        Some(format!("wishlist:{}", purchase.id)),
+        TransactionType::Spend,
+        transaction::Clearance {
+            approved: true,
+            over_daily_limit,
+        },
+    )
+    .await?;
+    // The merchant was validated when the spend was held; failing to set it keeps the
+    // one taken from the description
+    if let Some(merchant) = purchase.merchant.as_deref() {
+        match transaction::set_merchant(db, spend.id, merchant).await {
+            Ok(updated) => spend = updated,
+            Err(e) => tracing::warn!(
+                "Failed to set merchant '{merchant}' on transaction {}: {e}",
+                spend.id
+            ),
+        }
+    }
+    let approved = close(
+        db,
+        purchase,
+        STATUS_APPROVED,
+        Some(user_id),
+        Some(spend.id),
+        now,
+    )
+    .await?;
+
+    Ok(PurchaseResponse::Approved {
+        purchase: approved,
+        transaction: Box::new(spend),
+    })
+}
+
+/// Marks every pending proposal whose expiry has passed as expired.
+///
+/// # Returns
+/// The expired proposals, so the members who made held spends can be told
+///
+/// # Errors
+/// Returns an error if a database query or update fails.
+pub async fn expire_stale_purchases(
+    db: &DatabaseConnection,
+    now: DateTime<Utc>,
+) -> Result<Vec<pending_purchase::Model>> {
+    let stale = PendingPurchase::find()
+        .filter(pending_purchase::Column::Status.eq(STATUS_PENDING))
+        .filter(pending_purchase::Column::ExpiresAt.lte(now))
+        .order_by_asc(pending_purchase::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let mut expired = Vec::with_capacity(stale.len());
+    for purchase in stale {
+        expired.push(close(db, purchase, STATUS_EXPIRED, None, None, now).await?);
+    }
+    Ok(expired)
+}
+
+/// Retrieves all proposals still waiting for a response, oldest first.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_pending_purchases(
+    db: &DatabaseConnection,
+) -> Result<Vec<pending_purchase::Model>> {
+    PendingPurchase::find()
+        .filter(pending_purchase::Column::Status.eq(STATUS_PENDING))
+        .order_by_asc(pending_purchase::Column::CreatedAt)
+        .all(db)
+        .await
+        .map_err(Into::into)
+}
+
+/// Moves a proposal out of the pending state.
+async fn close(
+    db: &DatabaseConnection,
+    purchase: pending_purchase::Model,
+    status: &str,
+    decided_by: Option<&str>,
+    transaction_id: Option<i64>,
+    now: DateTime<Utc>,
+) -> Result<pending_purchase::Model> {
+    let mut active_model: pending_purchase::ActiveModel = purchase.into();
+    active_model.status = Set(status.to_string());
+    active_model.decided_by = Set(decided_by.map(str::to_string));
+    active_model.transaction_id = Set(transaction_id);
+    active_model.decided_at = Set(Some(now));
+    active_model.update(db).await.map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::float_cmp)]
+    use super::*;
+    use crate::{
+        core::{approval, envelope},
+        test_utils::*,
+    };
+
+    #[tokio::test]
+    async fn test_approve_executes_spend_once() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 100.0).await?;
+
+        let purchase =
+            propose_purchase(&db, env.id, "user1", "user2", 30.0, "Lamp".to_string()).await?;
+        assert_eq!(purchase.status, STATUS_PENDING);
+
+        // Only the approver can respond
+        let response = respond_to_purchase(&db, purchase.id, "user1", true, Utc::now()).await?;
+        assert!(matches!(response, PurchaseResponse::NotApprover(_)));
+
+        let response = respond_to_purchase(&db, purchase.id, "user2", true, Utc::now()).await?;
+        assert!(matches!(response, PurchaseResponse::Approved { .. }));
+        let purchase = PendingPurchase::find_by_id(purchase.id)
+            .one(&db)
+            .await?
+            .unwrap();
+        assert_eq!(purchase.status, STATUS_APPROVED);
+        assert_eq!(purchase.decided_by.as_deref(), Some("user2"));
+        let transaction = transaction::get_transaction_by_id(&db, purchase.transaction_id.unwrap())
+            .await?
+            .unwrap();
+        assert_eq!(transaction.amount, -30.0);
+        assert_eq!(transaction.user_id, "user1");
+
+        // A second click doesn't spend again
+        let again = respond_to_purchase(&db, purchase.id, "user2", true, Utc::now()).await?;
+        assert!(matches!(again, PurchaseResponse::AlreadyDecided(_)));
+        let env = envelope::get_envelope_by_id(&db, env.id).await?.unwrap();
+        assert_eq!(env.balance, 70.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_decline_and_insufficient_funds() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+
+        // Approval fails without funds and the proposal stays open
+        let purchase =
+            propose_purchase(&db, env.id, "user1", "user2", 30.0, "Lamp".to_string()).await?;
+        let result = respond_to_purchase(&db, purchase.id, "user2", true, Utc::now()).await;
+        assert!(matches!(result, Err(Error::InsufficientFunds { .. })));
+        assert_eq!(get_pending_purchases(&db).await?.len(), 1);
+
+        let response = respond_to_purchase(&db, purchase.id, "user2", false, Utc::now()).await?;
+        assert!(
+            matches!(response, PurchaseResponse::Declined(ref p) if p.status == STATUS_DECLINED)
+        );
+        assert!(get_pending_purchases(&db).await?.is_empty());
+
+        assert!(matches!(
+            propose_purchase(&db, env.id, "user1", "user1", 5.0, "Self".to_string()).await,
+            Err(Error::Config { .. })
+        ));
+        assert!(matches!(
+            respond_to_purchase(&db, 999, "user2", true, Utc::now()).await,
+            Err(Error::PurchaseNotFound { id: 999 })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_proposal_approval_respects_daily_limit() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 500.0).await?;
+        envelope::set_daily_limit(&db, env.id, Some(50.0)).await?;
+
+        let purchase =
+            propose_purchase(&db, env.id, "user1", "user2", 80.0, "Chair".to_string()).await?;
+        assert!(matches!(
+            respond_to_purchase(&db, purchase.id, "user2", true, Utc::now()).await,
+            Err(Error::DailyLimitExceeded { .. })
+        ));
+        assert_eq!(get_pending_purchases(&db).await?.len(), 1);
+
+        // Held spends were checked against the limit when they were made
+        let held = approval::hold_spend(
+            &db,
+            env.id,
+            "user1",
+            80.0,
+            "Desk".to_string(),
+            None,
+            "42".to_string(),
+        )
+        .await?;
+        let response = respond_to_purchase(&db, held.id, "user2", true, Utc::now()).await?;
+        assert!(matches!(response, PurchaseResponse::Approved { .. }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stale_proposals_expire() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        let first =
+            propose_purchase(&db, env.id, "user1", "user2", 10.0, "Book".to_string()).await?;
+        let second =
+            propose_purchase(&db, env.id, "user1", "user2", 20.0, "Game".to_string()).await?;
+
+        let later = Utc::now() + chrono::Duration::days(PROPOSAL_TTL_DAYS + 1);
+        let response = respond_to_purchase(&db, first.id, "user2", true, later).await?;
+        assert!(matches!(response, PurchaseResponse::Expired(_)));
+
+        assert!(expire_stale_purchases(&db, Utc::now()).await?.is_empty());
+        let expired = expire_stale_purchases(&db, later).await?;
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, second.id);
+        assert_eq!(expired[0].status, STATUS_EXPIRED);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_any_other_member_answers_a_hold() -> Result<()> {
+        let (db, env) = setup_with_envelope().await?;
+        create_test_transaction(&db, env.id, 1000.0).await?;
+        approval::set_threshold(&db, Some(500.0)).await?;
+        let held = approval::hold_spend(
+            &db,
+            env.id,
+            "user1",
+            600.0,
+            "Laptop".to_string(),
+            Some("Bestbuy".to_string()),
+            "42".to_string(),
+        )
+        .await?;
+
+        // The member who made the spend can't approve it, but anyone else can
+        let response = respond_to_purchase(&db, held.id, "user1", true, Utc::now()).await?;
+        assert!(matches!(response, PurchaseResponse::NotApprover(_)));
+        let response = respond_to_purchase(&db, held.id, "user3", true, Utc::now()).await?;
+        let PurchaseResponse::Approved {
+            purchase,
+            transaction,
+        } = response
+        else {
+            return Err(Error::Config {
+                message: format!("Expected an approval, got {response:?}"),
+            });
+        };
+        assert_eq!(purchase.decided_by.as_deref(), Some("user3"));
+        assert_eq!(purchase.transaction_id, Some(transaction.id));
+        assert_eq!(transaction.amount, -600.0);
+        assert_eq!(transaction.user_id, "user1");
+        assert_eq!(transaction.merchant.as_deref(), Some("bestbuy"));
+
+        let env = envelope::get_envelope_by_id(&db, env.id).await?.unwrap();
+        assert_eq!(env.balance, 400.0);
+        Ok(())
+    }
+}
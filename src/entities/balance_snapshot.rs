@@ -0,0 +1,45 @@
+//! Balance snapshot entity - Daily record of each envelope's balance.
+//!
+//! A scheduler job records one snapshot per envelope per day so balance trends can be
+//! shown without replaying the transaction history. Old snapshots are pruned according
+//! to the retention policy.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Balance snapshot database model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "balance_snapshots")]
+pub struct Model {
+    /// Unique identifier for the snapshot
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// ID of the envelope this snapshot belongs to
+    pub envelope_id: i64,
+    /// Envelope balance in dollars when the snapshot was taken
+    pub balance: f64,
+    /// Day the snapshot represents (unique per envelope)
+    pub snapshot_date: Date,
+    /// When the snapshot was recorded
+    pub recorded_at: DateTimeUtc,
+}
+
+/// Defines relationships between `BalanceSnapshot` and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each snapshot belongs to one envelope
+    #[sea_orm(
+        belongs_to = "super::envelope::Entity",
+        from = "Column::EnvelopeId",
+        to = "super::envelope::Column::Id"
+    )]
+    Envelope,
+}
+
+impl Related<super::envelope::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Envelope.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,62 @@
+//! Bill entity - Recurring monthly bills with reminders before each due date.
+//!
+//! A bill is due on the same day every month and is paid from one envelope. The
+//! background scheduler posts a reminder a few days before each due date (optionally
+//! creating a Discord scheduled event), and a matching spend from the envelope marks
+//! that due date paid.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Bill database model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "bills")]
+pub struct Model {
+    /// Unique identifier for the bill
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Bill name (e.g. "Rent"), unique
+    pub name: String,
+    /// Amount due each month in dollars (always positive)
+    pub amount: f64,
+    /// Day of the month the bill is due (later days fall on the month's last day)
+    pub due_day: i32,
+    /// ID of the envelope the bill is paid from
+    pub envelope_id: i64,
+    /// Discord user ID of the member who added the bill
+    pub user_id: String,
+    /// Discord channel reminders fall back to when no announcement channel is set
+    pub channel_id: Option<String>,
+    /// Discord server to create scheduled events in
+    pub guild_id: Option<String>,
+    /// How many days before the due date the reminder is posted
+    pub remind_days: i32,
+    /// Whether each reminder also creates a Discord scheduled event on the due date
+    pub create_event: bool,
+    /// Due date of the most recent reminder, so each due date is reminded once
+    pub reminded_for: Option<Date>,
+    /// Most recent due date marked paid
+    pub paid_for: Option<Date>,
+    /// When the bill was added
+    pub created_at: DateTimeUtc,
+}
+
+/// Defines relationships between bills and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each bill is paid from one envelope
+    #[sea_orm(
+        belongs_to = "super::envelope::Entity",
+        from = "Column::EnvelopeId",
+        to = "super::envelope::Column::Id"
+    )]
+    Envelope,
+}
+
+impl Related<super::envelope::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Envelope.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
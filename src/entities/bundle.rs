@@ -0,0 +1,38 @@
+//! Bundle entity - Represents a named set of products used together.
+//!
+//! A bundle (e.g. "Taco Night") lists products with quantities in `bundle_items`. Using
+//! a bundle logs one spend per component against that product's envelope, all in a
+//! single database transaction.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Bundle database model
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "bundles")]
+pub struct Model {
+    /// Unique identifier for the bundle
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Name of the bundle (e.g., "Taco Night")
+    #[sea_orm(unique)]
+    pub name: String,
+    /// When the bundle was created
+    pub created_at: DateTime,
+}
+
+/// Defines relationships between Bundle and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// A bundle is made up of many items
+    #[sea_orm(has_many = "super::bundle_item::Entity")]
+    BundleItems,
+}
+
+impl Related<super::bundle_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::BundleItems.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,54 @@
+//! Bundle item entity - One product and its quantity within a bundle.
+//!
+//! Each product appears at most once per bundle; adding it again changes the quantity.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Bundle item database model
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "bundle_items")]
+pub struct Model {
+    /// Unique identifier for the item
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// ID of the bundle this item belongs to
+    pub bundle_id: i64,
+    /// ID of the product used
+    pub product_id: i64,
+    /// How many units of the product the bundle uses
+    pub quantity: i64,
+}
+
+/// Defines relationships between bundle items and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each item belongs to one bundle
+    #[sea_orm(
+        belongs_to = "super::bundle::Entity",
+        from = "Column::BundleId",
+        to = "super::bundle::Column::Id"
+    )]
+    Bundle,
+    /// Each item uses one product
+    #[sea_orm(
+        belongs_to = "super::product::Entity",
+        from = "Column::ProductId",
+        to = "super::product::Column::Id"
+    )]
+    Product,
+}
+
+impl Related<super::bundle::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Bundle.def()
+    }
+}
+
+impl Related<super::product::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Product.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
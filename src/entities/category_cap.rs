@@ -0,0 +1,32 @@
+//! Category cap entity - A monthly spending cap shared by every envelope in a category.
+//!
+//! Several envelopes can share one real-world limit (e.g. all "fun" spending up to $300
+//! a month). A cap covers the combined spending of the category's envelopes in the
+//! current budget month. Hard caps deny spends that would exceed them; soft caps only
+//! warn. Categories have at most one cap.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Category cap database model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "category_caps")]
+pub struct Model {
+    /// Unique identifier for the cap
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Envelope category the cap applies to (e.g. "fun")
+    pub category: String,
+    /// Most the category may spend per budget month, in dollars
+    pub cap: f64,
+    /// Whether spends past the cap are denied (`true`) or only warned about
+    pub hard: bool,
+    /// When the cap was created or last changed
+    pub updated_at: DateTime,
+}
+
+/// `CategoryCap` has no relationships with other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
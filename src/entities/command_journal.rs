@@ -0,0 +1,35 @@
+//! Command journal entity - A member's recent change that `/undo` can reverse.
+//!
+//! Each entry stores how to reverse one change (a recorded transaction or an envelope
+//! edit) as JSON in `payload`, tagged with its `kind`. Only a member's most recent
+//! entries are kept.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Command journal database model
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "command_journal")]
+pub struct Model {
+    /// Unique identifier for the entry, shown by `/undo list`
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Discord user ID of the member who made the change
+    pub user_id: String,
+    /// Kind of change, e.g. `transaction` or `envelope_edit`
+    pub kind: String,
+    /// JSON describing how to reverse the change
+    pub payload: String,
+    /// Short description of the change, shown by `/undo list`
+    pub summary: String,
+    /// When the change was made
+    pub created_at: DateTimeUtc,
+    /// When the change was undone, if it has been
+    pub undone_at: Option<DateTimeUtc>,
+}
+
+/// Command journal entries have no relations; payloads may refer to deleted rows
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
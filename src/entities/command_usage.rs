@@ -0,0 +1,33 @@
+//! Command usage entity - How often a member ran a command in one budget month.
+//!
+//! Each row counts one member's invocations of one command (by its full name, such as
+//! `"transaction purge"`) during one budget month. Rows are unique per command, member,
+//! and month, and are incremented as commands run.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Command usage database model
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "command_usage")]
+pub struct Model {
+    /// Unique identifier
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Full command name, with the parent for subcommands (e.g. `"transaction purge"`)
+    pub command: String,
+    /// Discord user ID of the member who ran the command
+    pub user_id: String,
+    /// Budget month of the invocations, as `YYYY-MM`
+    pub month: String,
+    /// Number of invocations
+    pub count: i64,
+    /// When the command was last run
+    pub last_used: DateTimeUtc,
+}
+
+/// `CommandUsage` has no relationships with other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -7,6 +7,7 @@ use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 /// Envelope database model
+#[allow(clippy::struct_excessive_bools)] // Each flag maps to an independent database column
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "envelopes")]
 pub struct Model {
@@ -29,6 +30,43 @@ pub struct Model {
     pub rollover: bool,
     /// Soft delete flag - if true, envelope is hidden but data is preserved
     pub is_deleted: bool,
+    /// Fixed amount added on top of the allocation during each monthly update (sinking funds)
+    pub contribution: f64,
+    /// Optional savings target for sinking-fund envelopes, in dollars
+    pub goal: Option<f64>,
+    /// Whether only the owner can see this individual envelope's balance and transactions
+    pub is_private: bool,
+    /// Balance below which a low-balance warning is posted, if set
+    pub alert_below: Option<f64>,
+    /// Most that may be spent from this envelope in one day without an override, if set
+    pub daily_limit: Option<f64>,
+    /// Balance spends may not take the envelope below, if set
+    pub min_balance: Option<f64>,
+    /// Discord thread (or channel) ID where a note is posted for each spend, if linked
+    pub thread_id: Option<String>,
+    /// Whether reports also break this envelope's spending down by week
+    pub weekly: bool,
+    /// Role or member mention (`<@&id>` or `<@id>`) pinged when the envelope overspends
+    pub ping_mention: Option<String>,
+    /// Monthly interest rate in percent paid on a rollover envelope's balance, if set
+    pub interest_rate: Option<f64>,
+    /// Whether the envelope is listed first in reports and envelope lists
+    pub pinned: bool,
+    /// Position in reports and envelope lists, lowest first, if set; see
+    /// [`crate::core::envelope::get_all_active_envelopes`]
+    pub sort_order: Option<i32>,
+    /// Description given to spends recorded without one, if set
+    pub default_description: Option<String>,
+    /// Whether new spends from the envelope are blocked
+    pub frozen: bool,
+    /// Last day a freeze lasts, in the household timezone; `None` freezes until unfrozen
+    pub frozen_until: Option<Date>,
+    /// Points over the expected pace still shown as 🟢, overriding the household's, if set
+    pub pace_on_track: Option<f64>,
+    /// Points over the expected pace still shown as 🟡, overriding the household's, if set
+    pub pace_slightly_over: Option<f64>,
+    /// Whether pace warnings are off, for bills paid in one go such as rent
+    pub pace_never_warn: bool,
 }
 
 /// Defines relationships between Envelope and other entities
@@ -0,0 +1,45 @@
+//! Envelope permission entity - A member allowed to spend from a shared envelope.
+//!
+//! A shared envelope without permissions can be spent from by every member. Once any
+//! member is granted one, only the granted members may spend from it; everyone can still
+//! see it and add funds to it.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Envelope permission database model
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "envelope_permissions")]
+pub struct Model {
+    /// Unique identifier for the permission
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// ID of the shared envelope the permission applies to
+    pub envelope_id: i64,
+    /// Discord user ID of the member allowed to spend from it
+    pub user_id: String,
+    /// Discord user ID of the admin who granted it
+    pub granted_by: String,
+    /// When the permission was granted
+    pub created_at: DateTimeUtc,
+}
+
+/// Defines relationships between envelope permissions and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each permission applies to one envelope
+    #[sea_orm(
+        belongs_to = "super::envelope::Entity",
+        from = "Column::EnvelopeId",
+        to = "super::envelope::Column::Id"
+    )]
+    Envelope,
+}
+
+impl Related<super::envelope::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Envelope.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
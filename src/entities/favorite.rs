@@ -0,0 +1,49 @@
+//! Favorite entity - A member's saved spend, replayed with `/f <label>`.
+//!
+//! Frequent identical spends (parking, coffee) are stored once with an envelope, amount,
+//! and description. Labels are unique per member; each member only sees and uses their
+//! own favorites.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Favorite database model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "favorites")]
+pub struct Model {
+    /// Unique identifier for the favorite
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Discord user ID of the member who owns the favorite
+    pub user_id: String,
+    /// Short label used to invoke it (e.g. "parking")
+    pub label: String,
+    /// ID of the envelope the spend comes from
+    pub envelope_id: i64,
+    /// Spend amount in dollars (always positive)
+    pub amount: f64,
+    /// Description recorded on the spend
+    pub description: String,
+    /// When the favorite was created or last changed
+    pub updated_at: DateTime,
+}
+
+/// Defines relationships between favorites and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each favorite spends from one envelope
+    #[sea_orm(
+        belongs_to = "super::envelope::Entity",
+        from = "Column::EnvelopeId",
+        to = "super::envelope::Column::Id"
+    )]
+    Envelope,
+}
+
+impl Related<super::envelope::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Envelope.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
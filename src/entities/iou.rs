@@ -0,0 +1,51 @@
+//! IOU entity - Records money one household member owes another.
+//!
+//! IOUs are created when one partner covers an expense for the other, optionally linked
+//! to the transaction that generated the debt. Settled IOUs are kept for history with a
+//! `settled_at` timestamp.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// IOU database model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ious")]
+pub struct Model {
+    /// Unique identifier for the IOU
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Discord user ID of the member who is owed the money
+    pub creditor_id: String,
+    /// Discord user ID of the member who owes the money
+    pub debtor_id: String,
+    /// Amount owed in dollars (always positive)
+    pub amount: f64,
+    /// Human-readable description of what the IOU is for
+    pub description: String,
+    /// Transaction that generated the debt, if any
+    pub transaction_id: Option<i64>,
+    /// When the IOU was recorded
+    pub created_at: DateTimeUtc,
+    /// When the IOU was settled, `None` while it is still outstanding
+    pub settled_at: Option<DateTimeUtc>,
+}
+
+/// Defines relationships between IOU and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// An IOU may be linked to the transaction that generated it
+    #[sea_orm(
+        belongs_to = "super::transaction::Entity",
+        from = "Column::TransactionId",
+        to = "super::transaction::Column::Id"
+    )]
+    Transaction,
+}
+
+impl Related<super::transaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Transaction.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -3,17 +3,73 @@
 //! These entities represent the database tables and their relationships.
 //! Each entity has a Model struct for data and an Entity struct for operations.
 
+pub mod balance_snapshot;
+pub mod bill;
+pub mod bundle;
+pub mod bundle_item;
+pub mod category_cap;
+pub mod command_journal;
+pub mod command_usage;
 pub mod envelope;
+pub mod envelope_permission;
+pub mod favorite;
+pub mod iou;
+pub mod monthly_statement;
+pub mod monthly_update_log;
+pub mod pending_purchase;
 pub mod product;
+pub mod scheduled_transaction;
+pub mod shopping_list_item;
 pub mod system_state;
 pub mod transaction;
+pub mod user_preference;
 
 // Re-export specific types to avoid conflicts
+pub use balance_snapshot::{
+    Column as BalanceSnapshotColumn, Entity as BalanceSnapshot, Model as BalanceSnapshotModel,
+};
+pub use bill::{Column as BillColumn, Entity as Bill, Model as BillModel};
+pub use bundle::{Column as BundleColumn, Entity as Bundle, Model as BundleModel};
+pub use bundle_item::{Column as BundleItemColumn, Entity as BundleItem, Model as BundleItemModel};
+pub use category_cap::{
+    Column as CategoryCapColumn, Entity as CategoryCap, Model as CategoryCapModel,
+};
+pub use command_journal::{
+    Column as CommandJournalColumn, Entity as CommandJournal, Model as CommandJournalModel,
+};
+pub use command_usage::{
+    Column as CommandUsageColumn, Entity as CommandUsage, Model as CommandUsageModel,
+};
 pub use envelope::{Column as EnvelopeColumn, Entity as Envelope, Model as EnvelopeModel};
+pub use envelope_permission::{
+    Column as EnvelopePermissionColumn, Entity as EnvelopePermission,
+    Model as EnvelopePermissionModel,
+};
+pub use favorite::{Column as FavoriteColumn, Entity as Favorite, Model as FavoriteModel};
+pub use iou::{Column as IouColumn, Entity as Iou, Model as IouModel};
+pub use monthly_statement::{
+    Column as MonthlyStatementColumn, Entity as MonthlyStatement, Model as MonthlyStatementModel,
+};
+pub use monthly_update_log::{
+    Column as MonthlyUpdateLogColumn, Entity as MonthlyUpdateLog, Model as MonthlyUpdateLogModel,
+};
+pub use pending_purchase::{
+    Column as PendingPurchaseColumn, Entity as PendingPurchase, Model as PendingPurchaseModel,
+};
 pub use product::{Column as ProductColumn, Entity as Product, Model as ProductModel};
+pub use scheduled_transaction::{
+    Column as ScheduledTransactionColumn, Entity as ScheduledTransaction,
+    Model as ScheduledTransactionModel,
+};
+pub use shopping_list_item::{
+    Column as ShoppingListItemColumn, Entity as ShoppingListItem, Model as ShoppingListItemModel,
+};
 pub use system_state::{
     Column as SystemStateColumn, Entity as SystemState, Model as SystemStateModel,
 };
 pub use transaction::{
-    Column as TransactionColumn, Entity as Transaction, Model as TransactionModel,
+    Column as TransactionColumn, Entity as Transaction, Model as TransactionModel, TransactionType,
+};
+pub use user_preference::{
+    Column as UserPreferenceColumn, Entity as UserPreference, Model as UserPreferenceModel,
 };
@@ -0,0 +1,67 @@
+//! Monthly statement entity - Archived per-envelope summary of a closed budget month.
+//!
+//! The monthly update writes one statement per envelope for the budget month it closes.
+//! Statements hold their own figures, so they stay available after the month's
+//! transactions have been pruned. The retention policy also aggregates older months that
+//! have no statement yet before deleting their transactions.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Monthly statement database model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "monthly_statements")]
+pub struct Model {
+    /// Unique identifier for the statement
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// ID of the envelope the statement covers
+    pub envelope_id: i64,
+    /// Envelope name when the statement was generated
+    pub envelope_name: String,
+    /// Budget month as `YYYY-MM`, named after the month its cycle starts in
+    pub month: String,
+    /// First day of the budget month
+    pub period_start: Date,
+    /// Last day of the budget month (inclusive)
+    pub period_end: Date,
+    /// Balance in dollars at the start of the month; `None` for months aggregated from
+    /// history by the retention policy, whose balances can't be reconstructed
+    pub open_balance: Option<f64>,
+    /// Balance in dollars at the end of the month, before the monthly update; `None` for
+    /// months aggregated from history
+    pub close_balance: Option<f64>,
+    /// Spending in dollars during the month, less refunds
+    pub total_spent: f64,
+    /// Number of transactions recorded during the month
+    pub transaction_count: i64,
+    /// Monthly allocation in dollars during the month; `None` for months aggregated from
+    /// history and statements recorded before allocations were archived
+    pub allocation: Option<f64>,
+    /// Balance in dollars the monthly update opened the next month with, before interest
+    /// and transactions made after the month closed; `None` for months aggregated from
+    /// history and statements recorded before openings were archived
+    pub applied_opening: Option<f64>,
+    /// When the statement was generated
+    pub created_at: DateTimeUtc,
+}
+
+/// Defines relationships between `MonthlyStatement` and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each statement belongs to one envelope
+    #[sea_orm(
+        belongs_to = "super::envelope::Entity",
+        from = "Column::EnvelopeId",
+        to = "super::envelope::Column::Id"
+    )]
+    Envelope,
+}
+
+impl Related<super::envelope::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Envelope.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,30 @@
+//! Monthly update log entity - One row per budget month the monthly update has opened.
+//!
+//! The monthly update inserts its row before changing any balance, in the same database
+//! transaction. `month` is unique, so a second update for a month that already has a row
+//! is refused by the database itself, whatever `system_state` says.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Monthly update log database model
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "monthly_update_log")]
+pub struct Model {
+    /// Unique identifier for the log entry
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Budget month the update opened, as `YYYY-MM`; the update's idempotency token
+    #[sea_orm(unique)]
+    pub month: String,
+    /// Budget month the update closed, as `YYYY-MM`
+    pub closed_month: String,
+    /// When the update ran
+    pub performed_at: DateTimeUtc,
+}
+
+/// `MonthlyUpdateLog` has no relationships with other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
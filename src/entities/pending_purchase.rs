@@ -0,0 +1,79 @@
+//! Pending purchase entity - Purchases waiting for a partner's approval.
+//!
+//! One member proposes a purchase against an envelope and another member approves or
+//! declines it. Wishlist proposals name their approver; large spends held by the
+//! approval threshold can be approved by any other member. Approval executes the spend
+//! and links the resulting transaction; proposals nobody answers expire after a fixed
+//! period. Decided proposals are kept for history.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Pending purchase database model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "pending_purchases")]
+pub struct Model {
+    /// Unique identifier for the proposal
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// ID of the envelope the purchase would be spent from
+    pub envelope_id: i64,
+    /// Discord user ID of the member who proposed the purchase
+    pub proposer_id: String,
+    /// Discord user ID of the member who must approve it, or `None` for a held large
+    /// spend any other member can approve
+    pub approver_id: Option<String>,
+    /// Purchase amount in dollars (always positive)
+    pub amount: f64,
+    /// What the purchase is for
+    pub description: String,
+    /// Proposal status: "pending", "approved", "declined", or "expired"
+    pub status: String,
+    /// Merchant given for a held large spend, applied to its transaction on approval
+    pub merchant: Option<String>,
+    /// Discord channel a large spend was held in
+    pub channel_id: Option<String>,
+    /// Discord user ID of the member who approved or declined it
+    pub decided_by: Option<String>,
+    /// Spend transaction created on approval
+    pub transaction_id: Option<i64>,
+    /// When the purchase was proposed
+    pub created_at: DateTimeUtc,
+    /// When the proposal stops accepting a response
+    pub expires_at: DateTimeUtc,
+    /// When the proposal was approved, declined, or expired
+    pub decided_at: Option<DateTimeUtc>,
+}
+
+/// Defines relationships between pending purchases and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each proposal spends from one envelope
+    #[sea_orm(
+        belongs_to = "super::envelope::Entity",
+        from = "Column::EnvelopeId",
+        to = "super::envelope::Column::Id"
+    )]
+    Envelope,
+    /// An approved proposal is linked to its spend transaction
+    #[sea_orm(
+        belongs_to = "super::transaction::Entity",
+        from = "Column::TransactionId",
+        to = "super::transaction::Column::Id"
+    )]
+    Transaction,
+}
+
+impl Related<super::envelope::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Envelope.def()
+    }
+}
+
+impl Related<super::transaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Transaction.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
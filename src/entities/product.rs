@@ -20,6 +20,11 @@ pub struct Model {
     pub price: f64,
     /// ID of the envelope this product charges to
     pub envelope_id: i64,
+    /// Category tag used to filter product lists (e.g., "snacks"), if set
+    pub category: Option<String>,
+    /// Description prefixed to this product's uses, overriding its envelope's default
+    /// description, if set
+    pub default_description: Option<String>,
     /// Soft delete flag - if true, product is hidden but data is preserved
     pub is_deleted: bool,
     /// When the product was created
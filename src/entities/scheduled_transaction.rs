@@ -0,0 +1,74 @@
+//! Scheduled transaction entity - One-off spends that run on a future date.
+//!
+//! A member schedules a spend (e.g. rent on the 1st) and the background scheduler
+//! executes it once its date arrives. Failed attempts are retried a limited number of
+//! times; executed, failed, and cancelled entries are kept for history.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Scheduled transaction database model
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "scheduled_transactions")]
+pub struct Model {
+    /// Unique identifier for the scheduled transaction
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// ID of the envelope the spend comes from
+    pub envelope_id: i64,
+    /// Discord user ID of the member who scheduled it
+    pub user_id: String,
+    /// Spend amount in dollars (always positive)
+    pub amount: f64,
+    /// Description recorded on the spend
+    pub description: String,
+    /// Date (household timezone) on or after which the spend runs
+    pub run_on: Date,
+    /// Discord channel the confirmation is posted to, if scheduled from a channel
+    pub channel_id: Option<String>,
+    /// Status: "pending", "executed", "failed", or "cancelled"
+    pub status: String,
+    /// Number of failed execution attempts
+    pub attempts: i32,
+    /// Error from the most recent failed attempt
+    pub last_error: Option<String>,
+    /// Spend transaction created on execution
+    pub transaction_id: Option<i64>,
+    /// When the spend was scheduled
+    pub created_at: DateTimeUtc,
+    /// When the spend executed, failed for good, or was cancelled
+    pub finished_at: Option<DateTimeUtc>,
+}
+
+/// Defines relationships between scheduled transactions and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each scheduled spend comes from one envelope
+    #[sea_orm(
+        belongs_to = "super::envelope::Entity",
+        from = "Column::EnvelopeId",
+        to = "super::envelope::Column::Id"
+    )]
+    Envelope,
+    /// An executed scheduled spend is linked to its transaction
+    #[sea_orm(
+        belongs_to = "super::transaction::Entity",
+        from = "Column::TransactionId",
+        to = "super::transaction::Column::Id"
+    )]
+    Transaction,
+}
+
+impl Related<super::envelope::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Envelope.def()
+    }
+}
+
+impl Related<super::transaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Transaction.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
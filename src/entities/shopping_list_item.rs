@@ -0,0 +1,55 @@
+//! Shopping list item entity - Something to buy, on an envelope's shopping list.
+//!
+//! Items are either free text or taken from a product, in which case checking them off
+//! can log the spend at the product's price. Checked-off items stay on the list until
+//! it is cleared.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Shopping list item database model
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "shopping_list_items")]
+pub struct Model {
+    /// Unique identifier for the item
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// ID of the envelope whose list the item is on
+    pub envelope_id: i64,
+    /// What to buy
+    pub name: String,
+    /// ID of the product the item was added from, if any
+    pub product_id: Option<i64>,
+    /// How many to buy
+    pub quantity: i64,
+    /// Discord user ID of the member who added the item
+    pub added_by: String,
+    /// When the item was added
+    pub created_at: DateTimeUtc,
+    /// When the item was checked off, if it has been
+    pub checked_at: Option<DateTimeUtc>,
+    /// Discord user ID of the member who checked the item off
+    pub checked_by: Option<String>,
+    /// ID of the spend logged when the item was checked off, if any
+    pub transaction_id: Option<i64>,
+}
+
+/// Defines relationships between shopping list items and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each item is on one envelope's list
+    #[sea_orm(
+        belongs_to = "super::envelope::Entity",
+        from = "Column::EnvelopeId",
+        to = "super::envelope::Column::Id"
+    )]
+    Envelope,
+}
+
+impl Related<super::envelope::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Envelope.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,10 +1,95 @@
 //! Transaction entity - Represents all financial transactions in the system.
 //!
 //! Each transaction has an `envelope_id`, amount, description, timestamp, `user_id`,
-//! optional `message_id` (Discord reference), and `transaction_type` (spend/addfunds).
+//! optional `message_id` (Discord reference), and `transaction_type` ([`TransactionType`]).
 //! Backticks are used for field names to enable proper documentation linking.
+use crate::errors::Error;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Kind of transaction, stored as text in the `transaction_type` column.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum TransactionType {
+    /// Money spent from an envelope
+    #[sea_orm(string_value = "spend")]
+    #[serde(rename = "spend")]
+    Spend,
+    /// Money added to an envelope
+    #[sea_orm(string_value = "addfunds")]
+    #[serde(rename = "addfunds")]
+    AddFunds,
+    /// Spend logged through a product or bundle
+    #[sea_orm(string_value = "use_product")]
+    #[serde(rename = "use_product")]
+    UseProduct,
+    /// Money returned for an earlier spend
+    #[sea_orm(string_value = "refund")]
+    #[serde(rename = "refund")]
+    Refund,
+    /// Interest paid on a rollover envelope's balance by the monthly update
+    #[sea_orm(string_value = "interest")]
+    #[serde(rename = "interest")]
+    Interest,
+    /// Change from rounding a spend up, moved to the savings envelope (see
+    /// [`crate::core::round_up`])
+    #[sea_orm(string_value = "round_up")]
+    #[serde(rename = "round_up")]
+    RoundUp,
+    /// Money returned for units of a product that were used, linked to the usage (see
+    /// [`crate::core::product::return_product`])
+    #[sea_orm(string_value = "product_return")]
+    #[serde(rename = "product_return")]
+    ProductReturn,
+}
+
+impl TransactionType {
+    /// Types that give back money from an earlier spend, linked to it through `refund_of`.
+    /// Reports net them against spending rather than counting them as income.
+    pub const REFUNDS: [Self; 2] = [Self::Refund, Self::ProductReturn];
+
+    /// Whether this type gives back money from an earlier spend (see [`Self::REFUNDS`]).
+    #[must_use]
+    pub const fn is_refund(self) -> bool {
+        matches!(self, Self::Refund | Self::ProductReturn)
+    }
+}
+
+impl fmt::Display for TransactionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_value())
+    }
+}
+
+impl FromStr for TransactionType {
+    type Err = Error;
+
+    /// Parses a transaction type, ignoring case, surrounding whitespace, and `-`, `_`, or
+    /// space separators, so `"add_funds"` and `"Use Product"` are accepted.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized: String = value
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_' | ' '))
+            .flat_map(char::to_lowercase)
+            .collect();
+        match normalized.as_str() {
+            "spend" => Ok(Self::Spend),
+            "addfunds" => Ok(Self::AddFunds),
+            "useproduct" => Ok(Self::UseProduct),
+            "refund" => Ok(Self::Refund),
+            "interest" => Ok(Self::Interest),
+            "roundup" => Ok(Self::RoundUp),
+            "productreturn" => Ok(Self::ProductReturn),
+            _ => Err(Error::InvalidTransactionType {
+                value: value.to_string(),
+            }),
+        }
+    }
+}
 
 /// Transaction database model
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
@@ -23,10 +108,38 @@ pub struct Model {
     pub timestamp: DateTimeUtc,
     /// Discord user ID who created the transaction
     pub user_id: String,
-    /// Optional Discord message ID for tracking original command
+    /// Optional Discord message or interaction ID for tracking the original command.
+    /// Unique when present, so retried commands can't post the same transaction twice.
     pub message_id: Option<String>,
-    /// Type of transaction: `"spend"`, `"addfunds"`, or `"use_product"`
-    pub transaction_type: String,
+    /// Kind of transaction
+    pub transaction_type: TransactionType,
+    /// Amount as entered when it was in a foreign currency (same sign as `amount`)
+    pub original_amount: Option<f64>,
+    /// Currency code of `original_amount`, e.g. `"CAD"`
+    pub original_currency: Option<String>,
+    /// For refunds and product returns, the ID of the spend this transaction reverses
+    pub refund_of: Option<i64>,
+    /// Whether this is a pending spend (e.g. a card authorization) not yet cleared with
+    /// its posted amount; see [`crate::core::transaction::clear_transaction`]
+    #[sea_orm(default_value = false)]
+    pub pending: bool,
+    /// Normalized merchant the money was spent at (see
+    /// [`crate::core::transaction::normalize_merchant`]); refunds share their spend's
+    pub merchant: Option<String>,
+    /// Quantity bought with a spend, e.g. `3.2` gallons, for tracking unit prices (see
+    /// [`crate::core::transaction::unit_price`])
+    pub quantity: Option<f64>,
+    /// Unit of `quantity`, e.g. `"gal"`
+    pub unit: Option<String>,
+    /// For round-ups, the ID of the spend that was rounded up
+    pub round_up_of: Option<i64>,
+    /// Where a spend was made, as free text or `lat, long` (see
+    /// [`crate::core::location`]); refunds share their spend's
+    pub location: Option<String>,
+    /// When the transaction was purged, if it was (see
+    /// [`crate::core::transaction::purge_transactions`]); purged transactions are kept but
+    /// no longer count anywhere
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 /// Defines relationships between Transaction and other entities
@@ -47,4 +160,20 @@ impl Related<super::envelope::Entity> for Entity {
     }
 }
 
+impl Entity {
+    /// Selects the transactions that haven't been purged.
+    ///
+    /// Every read of the ledger starts here rather than at [`EntityTrait::find`].
+    #[must_use]
+    pub fn find_active() -> Select<Self> {
+        Self::find().filter(Column::DeletedAt.is_null())
+    }
+
+    /// Selects a transaction by ID, unless it has been purged.
+    #[must_use]
+    pub fn find_active_by_id(id: i64) -> Select<Self> {
+        Self::find_active().filter(Column::Id.eq(id))
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
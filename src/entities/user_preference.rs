@@ -0,0 +1,30 @@
+//! User preference entity - A member's personal setting, stored as a key-value pair.
+//!
+//! Each row holds one preference (such as how verbose confirmations are) for one
+//! member. Keys are unique per member; members without a row get the default.
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// User preference database model
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "user_preferences")]
+pub struct Model {
+    /// Unique identifier
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// Discord user ID of the member the preference belongs to
+    pub user_id: String,
+    /// Preference key (e.g., `"verbosity"`)
+    pub key: String,
+    /// Preference value stored as string
+    pub value: String,
+    /// When the preference was last changed
+    pub updated_at: DateTime,
+}
+
+/// `UserPreference` has no relationships with other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -4,6 +4,7 @@
 //! that can occur throughout the application, from database operations to Discord
 //! interactions and business logic validation.
 
+use crate::core::setup::Currency;
 use thiserror::Error;
 
 /// Unified error type for all `EnvelopeBuddy` operations
@@ -32,6 +33,20 @@ pub enum Error {
         name: String,
     },
 
+    /// An envelope (active or soft-deleted) already uses the requested name
+    #[error("Envelope already exists: {name}")]
+    EnvelopeAlreadyExists {
+        /// The conflicting envelope name
+        name: String,
+    },
+
+    /// Envelope is private to another household member
+    #[error("Envelope is hidden: {name}")]
+    EnvelopeHidden {
+        /// Name of the private envelope
+        name: String,
+    },
+
     /// Requested product was not found in the database
     #[error("Product not found: {name}")]
     ProductNotFound {
@@ -39,6 +54,27 @@ pub enum Error {
         name: String,
     },
 
+    /// Requested transaction was not found in the database
+    #[error("Transaction not found: {id}")]
+    TransactionNotFound {
+        /// ID of the transaction that wasn't found
+        id: i64,
+    },
+
+    /// Requested wishlist proposal was not found in the database
+    #[error("Wishlist proposal not found: {id}")]
+    PurchaseNotFound {
+        /// ID of the proposal that wasn't found
+        id: i64,
+    },
+
+    /// Requested scheduled transaction was not found in the database
+    #[error("Scheduled transaction not found: {id}")]
+    ScheduledTransactionNotFound {
+        /// ID of the scheduled transaction that wasn't found
+        id: i64,
+    },
+
     /// Transaction would result in negative balance
     #[error("Insufficient funds: envelope has {current}, need {required}")]
     InsufficientFunds {
@@ -48,6 +84,53 @@ pub enum Error {
         required: f64,
     },
 
+    /// A spend would take an envelope below its minimum balance
+    #[error(
+        "Envelope '{envelope}' keeps a {floor:.2} minimum balance: you can spend at most {available:.2}"
+    )]
+    BelowMinimumBalance {
+        /// Name of the protected envelope
+        envelope: String,
+        /// The envelope's minimum balance
+        floor: f64,
+        /// Most that can still be spent without going below the floor
+        available: f64,
+    },
+
+    /// A spend was made from a frozen envelope
+    #[error(
+        "Envelope '{envelope}' is frozen{}; unfreeze it with `/envelope unfreeze` to spend from it",
+        .until.map_or_else(String::new, |date| format!(" through {date}"))
+    )]
+    EnvelopeFrozen {
+        /// Name of the frozen envelope
+        envelope: String,
+        /// Last day of the freeze, if it ends on its own
+        until: Option<chrono::NaiveDate>,
+    },
+
+    /// A member spent from a shared envelope restricted to other spenders
+    #[error(
+        "Only {spenders} can spend from envelope '{envelope}'; an admin can change this with `/envelope spenders`"
+    )]
+    SpendNotPermitted {
+        /// Name of the restricted envelope
+        envelope: String,
+        /// The members allowed to spend from it, as Discord mentions
+        spenders: String,
+    },
+
+    /// A spend above the large transaction threshold was made without approval
+    #[error(
+        "Spends over {threshold:.2} need another member's approval; use `/spend` to request it"
+    )]
+    ApprovalRequired {
+        /// The attempted spend
+        amount: f64,
+        /// The large transaction threshold
+        threshold: f64,
+    },
+
     /// Transaction amount is invalid (e.g., zero, NaN, infinity)
     #[error("Invalid amount: {amount}")]
     InvalidAmount {
@@ -55,6 +138,37 @@ pub enum Error {
         amount: f64,
     },
 
+    /// A spend would take the day's spending past a daily limit
+    #[error("Daily limit for {scope} exceeded: {spent:.2} spent today, limit {limit:.2}")]
+    DailyLimitExceeded {
+        /// What the limit applies to, e.g. `"envelope 'Groceries'"`
+        scope: String,
+        /// The daily limit
+        limit: f64,
+        /// Amount spent today including the attempted spend
+        spent: f64,
+    },
+
+    /// A spend would take a category past its hard monthly cap
+    #[error(
+        "Monthly cap for category '{category}' exceeded: {spent:.2} spent this month, cap {cap:.2}"
+    )]
+    CategoryCapExceeded {
+        /// The capped category
+        category: String,
+        /// The monthly cap
+        cap: f64,
+        /// Amount spent this budget month including the attempted spend
+        spent: f64,
+    },
+
+    /// Transaction type is not one of the known kinds
+    #[error("Invalid transaction type: {value}")]
+    InvalidTransactionType {
+        /// The unrecognized value
+        value: String,
+    },
+
     /// Referenced user was not found
     #[error("User not found: {user_id}")]
     UserNotFound {
@@ -82,6 +196,27 @@ pub enum Error {
         name: String,
     },
 
+    /// Chart rendering failed
+    #[error("Chart error: {message}")]
+    Chart {
+        /// Description of the rendering failure
+        message: String,
+    },
+
+    /// PDF rendering failed
+    #[error("PDF error: {message}")]
+    Pdf {
+        /// Description of the rendering failure
+        message: String,
+    },
+
+    /// An email or Matrix notification couldn't be delivered
+    #[error("Notification error: {message}")]
+    Notification {
+        /// Description of the delivery failure
+        message: String,
+    },
+
     /// Configuration or system state error
     #[error("Configuration error: {message}")]
     Config {
@@ -90,6 +225,55 @@ pub enum Error {
     },
 }
 
+impl Error {
+    /// The error as shown to members, with amounts written in `currency`.
+    ///
+    /// The `Display` text leaves amounts bare since the currency is a database setting;
+    /// anything shown in Discord should go through this instead.
+    #[must_use]
+    pub fn user_message(&self, currency: Currency) -> String {
+        match self {
+            Self::InsufficientFunds { current, required } => format!(
+                "Insufficient funds: envelope has {}, need {}",
+                currency.format(*current),
+                currency.format(*required)
+            ),
+            Self::BelowMinimumBalance {
+                envelope,
+                floor,
+                available,
+            } => format!(
+                "Envelope '{envelope}' keeps a {} minimum balance: you can spend at most {}",
+                currency.format(*floor),
+                currency.format(*available)
+            ),
+            Self::ApprovalRequired { threshold, .. } => format!(
+                "Spends over {} need another member's approval; use `/spend` to request it",
+                currency.format(*threshold)
+            ),
+            Self::DailyLimitExceeded {
+                scope,
+                limit,
+                spent,
+            } => format!(
+                "Daily limit for {scope} exceeded: {} spent today, limit {}",
+                currency.format(*spent),
+                currency.format(*limit)
+            ),
+            Self::CategoryCapExceeded {
+                category,
+                cap,
+                spent,
+            } => format!(
+                "Monthly cap for category '{category}' exceeded: {} spent this month, cap {}",
+                currency.format(*spent),
+                currency.format(*cap)
+            ),
+            _ => self.to_string(),
+        }
+    }
+}
+
 // Add explicit From implementations for unboxed types
 impl From<sea_orm::DbErr> for Error {
     fn from(err: sea_orm::DbErr) -> Self {
@@ -108,3 +292,26 @@ impl From<serenity::Error> for Error {
 /// This type alias is used throughout the codebase to simplify function signatures
 /// that return results with the unified `Error` type.
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_message_uses_currency() {
+        let error = Error::InsufficientFunds {
+            current: 5.0,
+            required: 12.5,
+        };
+        assert_eq!(
+            error.user_message(Currency::from_code("EUR")),
+            "Insufficient funds: envelope has €5.00, need €12.50"
+        );
+
+        // Errors without amounts are shown as-is
+        let error = Error::Config {
+            message: "Bad config".to_string(),
+        };
+        assert_eq!(error.user_message(Currency::default()), error.to_string());
+    }
+}
@@ -73,8 +73,13 @@
 // 1. Macro-generated code (e.g., `poise::command`) doesn't include docs
 // 2. We want to gradually add documentation rather than block compilation
 
+/// HTTP endpoint for spends from external sources
+#[cfg(feature = "api")]
+pub mod api;
 /// Discord bot interface - commands, handlers, and bot context
 pub mod bot;
+/// Headless administration from the command line (`envelope-buddy admin`)
+pub mod cli;
 /// Configuration management for database and application settings
 pub mod config;
 /// Core business logic - framework-agnostic envelope, transaction, and reporting operations
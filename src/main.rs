@@ -1,27 +1,51 @@
 use dotenvy::dotenv;
-use envelope_buddy::{bot, config, core::envelope, errors::Error};
-use sea_orm::{Database, DatabaseConnection};
+use envelope_buddy::{
+    bot, cli, config,
+    core::{self, envelope},
+    errors::Error,
+};
+use sea_orm::{Database, DatabaseConnection, SqlxSqliteConnector};
 use std::env;
+use std::time::{Duration, SystemTime};
 use tracing::{error, info, warn};
-use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+use tracing_subscriber::{EnvFilter, fmt, fmt::writer::BoxMakeWriter, prelude::*};
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     // Load environment variables from .env file
     dotenv().ok();
 
-    // Initialize tracing/logging
-    init_tracing()?;
+    // `--demo` runs on a disposable in-memory database of sample data
+    let demo = env::args().skip(1).any(|arg| arg == "--demo");
+
+    // `admin <command>` runs one maintenance command without Discord, then exits
+    let admin = match env::args()
+        .skip(1)
+        .filter(|arg| arg != "--demo")
+        .collect::<Vec<_>>()
+    {
+        args if args.first().is_some_and(|arg| arg == "admin") => {
+            Some(cli::AdminCommand::parse(&args[1..])?)
+        }
+        _ => None,
+    };
+
+    // Initialize tracing/logging; admin commands keep standard output for their result
+    init_tracing(admin.is_some())?;
     info!("EnvelopeBuddy v0.2.0 starting...");
 
     // Load database configuration
-    let db_url = config::database::get_database_url()?;
     info!("Connecting to database...");
 
     // Connect to database
-    let db = Database::connect(&db_url)
-        .await
-        .map_err(|e| Error::Database(Box::new(e)))?;
+    let db = if demo {
+        config::database::connect_demo().await?
+    } else {
+        let db_url = config::database::get_database_url()?;
+        Database::connect(&db_url)
+            .await
+            .map_err(|e| Error::Database(Box::new(e)))?
+    };
 
     info!("Database connected successfully");
 
@@ -31,64 +55,272 @@ async fn main() -> Result<(), Error> {
         return Err(e);
     }
 
+    if let Some(command) = admin {
+        // Demo data is seeded first so admin commands have something to work on
+        if demo {
+            let today = core::calendar::today(&db).await?;
+            core::demo::seed(&db, today).await?;
+        }
+        let output = cli::run(&db, command).await?;
+        println!("{output}");
+        return Ok(());
+    }
+
+    // Names that differ only in case or spacing make name lookups ambiguous
+    for group in envelope::find_duplicate_names(&db).await? {
+        let names: Vec<String> = group
+            .iter()
+            .map(|env| format!("'{}' (id {})", env.name, env.id))
+            .collect();
+        warn!(
+            "Envelopes with near-duplicate names: {}; lookups that don't match one of them exactly are ambiguous, so rename or merge them",
+            names.join(", ")
+        );
+    }
+
+    // Records pointing at missing data are logged so they get noticed; /audit lists them
+    match core::audit::run_audit(&db).await {
+        Ok(report) if report.is_clean() => info!("Integrity audit found no problems"),
+        Ok(report) => warn!(
+            "Integrity audit found {} problem(s): {} orphaned transaction(s), {} orphaned product(s), {} funded template(s), {} drifted balance(s); run /audit for details",
+            report.finding_count(),
+            report.orphaned_transactions.len(),
+            report.orphaned_products.len(),
+            report.funded_templates.len(),
+            report.drifted_balances.len()
+        ),
+        Err(e) => warn!("Integrity audit failed: {}", e),
+    }
+
     // Only seed envelopes if database is empty (fresh install)
     let existing_envelopes = envelope::get_all_active_envelopes(&db).await?;
-    if existing_envelopes.is_empty() {
+    if demo {
+        let today = core::calendar::today(&db).await?;
+        let summary = core::demo::seed(&db, today).await?;
+        warn!(
+            "Demo mode: {} envelopes, {} products, and {} transactions of sample data in memory; nothing is saved",
+            summary.envelopes, summary.products, summary.transactions
+        );
+    } else if existing_envelopes.is_empty() {
         info!("Database is empty, seeding initial envelopes from config.toml");
         seed_envelopes(&db).await?;
     } else {
-        info!("Database already has {} envelopes, skipping seeding", existing_envelopes.len());
+        info!(
+            "Database already has {} envelopes, skipping seeding",
+            existing_envelopes.len()
+        );
+    }
+
+    // Optionally append every committed transaction to a journal file
+    if let Some(journal) = config::journal::JournalConfig::from_env() {
+        info!(
+            "Journaling transactions to {} ({:?}, rotating at {} bytes)",
+            journal.path.display(),
+            journal.format,
+            journal.max_bytes
+        );
+        core::journal::start(journal);
+    }
+
+    // Deliver alerts and monthly summaries through the backends in NOTIFIERS
+    let notify = config::notify::NotifyConfig::from_env()?;
+    core::notify::start(&notify)?;
+    if notify.email.is_some() || notify.matrix.is_some() {
+        info!(
+            "Notifications: discord={}, email={}, matrix={}",
+            notify.discord,
+            notify.email.is_some(),
+            notify.matrix.is_some()
+        );
+    }
+
+    // Optionally re-apply config.toml whenever it changes on disk (not to demo data)
+    if let Some(interval) = config_watch_interval().filter(|_| !demo) {
+        info!(
+            "Watching config.toml for changes every {}s",
+            interval.as_secs()
+        );
+        tokio::spawn(watch_config(share_connection(&db), interval));
     }
 
+    // Drop cached rows when `envelope-buddy admin` writes to the database
+    tokio::spawn(watch_external_writes(share_connection(&db)));
+
+    // Record daily balance snapshots for trend lines
+    let retention_days = snapshot_retention_days();
+    tokio::spawn(run_snapshot_job(share_connection(&db), retention_days));
+
     // Get Discord bot token
     let token = env::var("DISCORD_BOT_TOKEN").map_err(|_| Error::Config {
         message: "DISCORD_BOT_TOKEN environment variable not set".to_string(),
     })?;
 
+    // Scheduled spends, bill reminders, and wishlist expiry post messages, so their jobs
+    // start once the bot is connected
+    let schedule_db = share_connection(&db);
+    let bill_db = share_connection(&db);
+    let wishlist_db = share_connection(&db);
+
+    // Optionally accept spends over HTTP; the endpoint relays to Discord, so it also
+    // starts once the bot is connected
+    #[cfg(feature = "api")]
+    let api =
+        config::api::ApiConfig::from_env().map(|api_config| (api_config, share_connection(&db)));
+
     info!("Starting Discord bot...");
-    run_bot(token, db).await?;
+    run_bot(
+        token,
+        db,
+        schedule_db,
+        bill_db,
+        wishlist_db,
+        #[cfg(feature = "api")]
+        api,
+    )
+    .await?;
 
     Ok(())
 }
 
 /// Initializes the tracing subscriber for logging
-fn init_tracing() -> Result<(), Error> {
+///
+/// Logs are human-readable text by default; set `LOG_FORMAT=json` for one JSON object
+/// per line, with the current command's correlation ID in its `span` field. With
+/// `admin`, logs are written to standard error instead of standard output.
+fn init_tracing(admin: bool) -> Result<(), Error> {
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
 
-    tracing_subscriber::registry()
-        .with(env_filter)
-        .with(fmt::layer().with_writer(std::io::stdout))
-        .init();
+    let json = match env::var("LOG_FORMAT") {
+        Ok(format) if format.trim().eq_ignore_ascii_case("json") => true,
+        Ok(format) if format.trim().is_empty() || format.trim().eq_ignore_ascii_case("text") => {
+            false
+        }
+        Ok(format) => {
+            return Err(Error::Config {
+                message: format!("Invalid LOG_FORMAT '{format}', expected 'text' or 'json'"),
+            });
+        }
+        Err(_) => false,
+    };
+
+    // Admin output such as `admin export > envelopes.toml` must stay parseable, so logs
+    // go to standard error, without colors, in admin mode
+    let writer = if admin {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+    let registry = tracing_subscriber::registry().with(env_filter);
+    if json {
+        registry
+            .with(
+                fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(false)
+                    .with_writer(writer),
+            )
+            .init();
+    } else {
+        registry
+            .with(fmt::layer().with_ansi(!admin).with_writer(writer))
+            .init();
+    }
 
     Ok(())
 }
 
 /// Runs the Discord bot with the given token and database connection
-async fn run_bot(token: String, db: DatabaseConnection) -> Result<(), Error> {
+async fn run_bot(
+    token: String,
+    db: DatabaseConnection,
+    schedule_db: DatabaseConnection,
+    bill_db: DatabaseConnection,
+    wishlist_db: DatabaseConnection,
+    #[cfg(feature = "api")] api: Option<(config::api::ApiConfig, DatabaseConnection)>,
+) -> Result<(), Error> {
     use poise::serenity_prelude as serenity;
 
+    let cooldowns = config::cooldowns::CommandCooldowns::from_env();
+    let mut commands = vec![
+        // General commands
+        bot::ping(),
+        bot::help(),
+        bot::setup(),
+        bot::reload_config(),
+        bot::status(),
+        bot::reconcile(),
+        bot::audit(),
+        bot::budget_cycle(),
+        bot::retention(),
+        bot::pace_thresholds(),
+        bot::bot_config(),
+        bot::alias(),
+        bot::share(),
+        bot::share_status(),
+        bot::preferences(),
+        bot::verbosity(),
+        // Transaction commands
+        bot::spend(),
+        bot::again(),
+        bot::log_as_expense(),
+        bot::addfunds(),
+        bot::distribution_plan(),
+        bot::refund(),
+        bot::transaction_manage(),
+        bot::activity(),
+        bot::daily_limit(),
+        bot::category_cap(),
+        bot::undo(),
+        // Envelope commands
+        bot::report(),
+        bot::statement(),
+        bot::balance_at(),
+        bot::allowance(),
+        bot::update(),
+        bot::create_envelope(),
+        bot::delete_envelope(),
+        bot::envelopes(),
+        bot::envelope_info(),
+        bot::update_envelope(),
+        bot::allocations(),
+        bot::envelope_manage(),
+        // Product commands
+        bot::product_manage(),
+        bot::use_product(),
+        bot::return_product(),
+        bot::bundle(),
+        bot::favorite(),
+        bot::favorite_use(),
+        // IOU commands
+        bot::iou(),
+        // Wishlist commands
+        bot::wishlist(),
+        // Shopping list commands
+        bot::shopping_list(),
+        // Scheduled spend commands
+        bot::schedule(),
+        // Recurring bill commands
+        bot::bill(),
+        // Usage statistics commands
+        bot::stats(),
+    ];
+    // Cooldowns are keyed on qualified names, which poise would otherwise fill in later
+    poise::framework::set_qualified_names(&mut commands);
+    apply_cooldowns(&mut commands, &cooldowns);
+    bot::handlers::aliases::register_builtin(&mut commands);
+    let members_intent = core::setup::get_budget_role(&db).await?.is_some();
+
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![
-                // General commands
-                bot::ping(),
-                bot::help(),
-                // Transaction commands
-                bot::spend(),
-                bot::addfunds(),
-                // Envelope commands
-                bot::report(),
-                bot::update(),
-                bot::create_envelope(),
-                bot::delete_envelope(),
-                bot::envelopes(),
-                bot::envelope_info(),
-                bot::update_envelope(),
-                // Product commands
-                bot::product_manage(),
-                bot::use_product(),
-            ],
+            commands,
+            prefix_options: poise::PrefixFrameworkOptions {
+                prefix: Some(command_prefix()),
+                ..Default::default()
+            },
+            pre_command: |ctx| Box::pin(pre_command(ctx)),
             on_error: |error| Box::pin(on_error(error)),
+            event_handler: |ctx, event, _framework, data| Box::pin(event_handler(ctx, event, data)),
             ..Default::default()
         })
         .setup(move |ctx, ready, framework| {
@@ -116,18 +348,46 @@ async fn run_bot(token: String, db: DatabaseConnection) -> Result<(), Error> {
                     info!("Commands registered globally");
                 }
 
-                Ok(bot::BotData::new(db))
+                // Nicknames saved through /setup supplement the .env configuration
+                let saved_nicknames = core::setup::get_saved_nicknames(&db).await?;
+                config::users::register_nicknames(saved_nicknames);
+
+                tokio::spawn(run_bill_reminders(bill_db, ctx.http.clone()));
+                tokio::spawn(run_scheduled_spends(schedule_db, ctx.http.clone()));
+                tokio::spawn(run_wishlist_expiry(wishlist_db, ctx.http.clone()));
+                #[cfg(feature = "api")]
+                if let Some((api_config, api_db)) = api {
+                    let http = ctx.http.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = envelope_buddy::api::serve(api_config, api_db, http).await {
+                            error!("Transaction endpoint stopped: {}", e);
+                        }
+                    });
+                }
+
+                let rates = core::fx::StaticRateProvider::from_env()?;
+                Ok(bot::BotData::new(db)
+                    .with_rate_provider(rates)
+                    .with_cooldowns(cooldowns)
+                    .with_members_intent(members_intent))
             })
         })
         .build();
 
-    let intents = serenity::GatewayIntents::GUILD_MESSAGES
+    // Guilds fills the cache of server roles that admin checks fall back on
+    let mut intents = serenity::GatewayIntents::GUILDS
+        | serenity::GatewayIntents::GUILD_MESSAGES
         | serenity::GatewayIntents::DIRECT_MESSAGES
         | serenity::GatewayIntents::MESSAGE_CONTENT;
+    // Role changes need the privileged Server Members intent, only asked for when used;
+    // `/config set budget_role` says when a restart is needed to pick it up
+    if members_intent {
+        intents |= serenity::GatewayIntents::GUILD_MEMBERS;
+    }
 
     info!("Creating Discord client...");
     let mut client = serenity::Client::builder(&token, intents)
-        .framework(framework)
+        .framework(bot::correlation::CorrelatedFramework::new(framework))
         .await
         .map_err(|e| Error::Config {
             message: format!("Failed to create Discord client: {}", e),
@@ -141,6 +401,76 @@ async fn run_bot(token: String, db: DatabaseConnection) -> Result<(), Error> {
     Ok(())
 }
 
+/// Applies the configured per-user cooldowns to the matching commands and subcommands.
+///
+/// Poise checks the cooldown of the subcommand that actually runs, so subcommands
+/// inherit their parent's cooldown when they have none of their own.
+fn apply_cooldowns(
+    commands: &mut [poise::Command<bot::BotData, Error>],
+    cooldowns: &config::cooldowns::CommandCooldowns,
+) {
+    apply_cooldowns_with_parent(commands, cooldowns, None);
+}
+
+/// Recursive step of [`apply_cooldowns`], carrying the nearest parent's cooldown.
+fn apply_cooldowns_with_parent(
+    commands: &mut [poise::Command<bot::BotData, Error>],
+    cooldowns: &config::cooldowns::CommandCooldowns,
+    inherited: Option<Duration>,
+) {
+    for command in commands {
+        let cooldown = cooldowns.for_command(&command.qualified_name).or(inherited);
+        if let Some(cooldown) = cooldown {
+            if let Ok(mut cooldown_config) = command.cooldown_config.write() {
+                cooldown_config.user = Some(cooldown);
+            }
+            info!(
+                "Cooldown for /{}: {}s per user",
+                command.qualified_name,
+                cooldown.as_secs()
+            );
+        }
+        apply_cooldowns_with_parent(&mut command.subcommands, cooldowns, cooldown);
+    }
+}
+
+/// Handles gateway events that aren't commands, such as wishlist button clicks
+async fn event_handler(
+    ctx: &poise::serenity_prelude::Context,
+    event: &poise::serenity_prelude::FullEvent,
+    data: &bot::BotData,
+) -> Result<(), Error> {
+    use poise::serenity_prelude as serenity;
+
+    if let serenity::FullEvent::InteractionCreate {
+        interaction: serenity::Interaction::Component(component),
+    } = event
+    {
+        bot::handlers::wishlist_buttons::handle_component(ctx, component, data).await?;
+        bot::handlers::envelope_pages::handle_component(ctx, component, data).await?;
+        bot::handlers::product_quick_create::handle_component(ctx, component, data).await?;
+        bot::handlers::shopping_list_menus::handle_component(ctx, component, data).await?;
+    }
+    if let serenity::FullEvent::GuildMemberUpdate { event, .. } = event {
+        bot::handlers::member_onboarding::handle_member_update(ctx, event, data).await?;
+    }
+    Ok(())
+}
+
+/// Counts every command invocation for `/stats usage`
+///
+/// Failures are logged and never stop the command from running.
+async fn pre_command(ctx: poise::Context<'_, bot::BotData, Error>) {
+    let command = &ctx.command().qualified_name;
+    let user_id = ctx.author().id.to_string();
+    if let Err(e) =
+        core::usage::record_command(&ctx.data().database, command, &user_id, chrono::Utc::now())
+            .await
+    {
+        warn!("Failed to record usage of `{}`: {}", command, e);
+    }
+}
+
 /// Error handler for poise framework
 async fn on_error(error: poise::FrameworkError<'_, bot::BotData, Error>) {
     match error {
@@ -149,10 +479,58 @@ async fn on_error(error: poise::FrameworkError<'_, bot::BotData, Error>) {
         }
         poise::FrameworkError::Command { error, ctx, .. } => {
             error!("Error in command `{}`: {:?}", ctx.command().name, error);
-            if let Err(e) = ctx.say(format!("❌ An error occurred: {}", error)).await {
+            let currency = match core::setup::get_currency_format(&ctx.data().database).await {
+                Ok(currency) => currency,
+                Err(e) => {
+                    warn!("Failed to read the currency: {e}");
+                    core::setup::Currency::default()
+                }
+            };
+            let message = format!("❌ An error occurred: {}", error.user_message(currency));
+            if let Err(e) = ctx.say(message).await {
                 error!("Failed to send error message: {}", e);
             }
         }
+        poise::FrameworkError::CooldownHit {
+            remaining_cooldown,
+            ctx,
+            ..
+        } => {
+            let reply = poise::CreateReply::default()
+                .content(bot::cooldown_message(
+                    &ctx.command().name,
+                    remaining_cooldown,
+                ))
+                .ephemeral(true);
+            if let Err(e) = ctx.send(reply).await {
+                error!("Failed to send cooldown message: {}", e);
+            }
+        }
+        poise::FrameworkError::UnknownCommand {
+            ctx,
+            msg,
+            prefix,
+            msg_content,
+            framework,
+            invocation_data,
+            trigger,
+            ..
+        } => {
+            // Server-specific aliases aren't registered commands
+            if !bot::handlers::aliases::dispatch_alias(
+                framework,
+                ctx,
+                msg,
+                prefix,
+                msg_content,
+                trigger,
+                invocation_data,
+            )
+            .await
+            {
+                warn!("Unknown prefix command: {prefix}{msg_content}");
+            }
+        }
         error => {
             if let Err(e) = poise::builtins::on_error(error).await {
                 error!("Error while handling error: {}", e);
@@ -165,8 +543,8 @@ async fn on_error(error: poise::FrameworkError<'_, bot::BotData, Error>) {
 ///
 /// This function loads envelope definitions from config.toml and creates them
 /// in the database if they don't already exist (checked by name). Individual
-/// envelopes are created as templates (no user_id) and will be instantiated
-/// per-user when first accessed.
+/// envelopes are created as templates (no user_id) and are instantiated per-user
+/// when first used (see `envelope::resolve_envelope`).
 async fn seed_envelopes(db: &DatabaseConnection) -> Result<(), Error> {
     // Load config file
     let config = match config::envelopes::load_default_config() {
@@ -184,39 +562,76 @@ async fn seed_envelopes(db: &DatabaseConnection) -> Result<(), Error> {
         "Seeding {} envelopes from config.toml...",
         config.envelopes.len()
     );
+    let currency = core::setup::get_currency_format(db).await?;
 
     for env_config in &config.envelopes {
-        // Skip individual envelopes - they should be created by users dynamically
-        if env_config.is_individual {
-            info!("Skipping individual envelope '{}' - these are created by users", env_config.name);
-            continue;
-        }
-
-        // Check if shared envelope already exists
-        let existing = envelope::get_shared_envelope_by_name(db, &env_config.name).await?;
+        // Check if the envelope (or any member's copy of it) already exists
+        let exists = if env_config.is_individual {
+            !envelope::get_individual_envelopes_by_name(db, &env_config.name)
+                .await?
+                .is_empty()
+        } else {
+            envelope::get_shared_envelope_by_name(db, &env_config.name)
+                .await?
+                .is_some()
+        };
 
-        if existing.is_some() {
+        if exists {
             info!("Envelope '{}' already exists, skipping", env_config.name);
             continue;
         }
 
-        // Create the shared envelope
-        match envelope::create_envelope(
-            db,
-            env_config.name.clone(),
-            None, // No user_id for shared envelopes
-            env_config.category.clone(),
-            env_config.allocation,
-            false, // Always false for shared envelopes
+        // Reject bad sinking-fund settings before anything is written, so the entry is
+        // reported again on the next start instead of being skipped as existing
+        if let Err(e) = envelope::validate_sinking_fund(
+            env_config.contribution,
+            env_config.goal,
             env_config.rollover,
-        )
-        .await
-        {
-            Ok(_) => {
+        ) {
+            error!("Invalid config for envelope '{}': {}", env_config.name, e);
+            return Err(e);
+        }
+
+        // Create the shared envelope, or the template for an individual one
+        let result = if env_config.is_individual {
+            envelope::create_template(
+                db,
+                env_config.name.clone(),
+                env_config.category.clone(),
+                env_config.allocation,
+                env_config.rollover,
+            )
+            .await
+        } else {
+            envelope::create_envelope(
+                db,
+                env_config.name.clone(),
+                None, // No user_id for shared envelopes
+                env_config.category.clone(),
+                env_config.allocation,
+                false, // Always false for shared envelopes
+                env_config.rollover,
+            )
+            .await
+        };
+        match result {
+            Ok(created) => {
                 info!(
-                    "✓ Created envelope '{}' ({}, ${:.2})",
-                    env_config.name, env_config.category, env_config.allocation
+                    "✓ Created envelope '{}' ({}, {})",
+                    env_config.name,
+                    env_config.category,
+                    currency.format(env_config.allocation)
                 );
+
+                if env_config.contribution > 0.0 || env_config.goal.is_some() {
+                    envelope::set_sinking_fund(
+                        db,
+                        created.id,
+                        env_config.contribution,
+                        env_config.goal,
+                    )
+                    .await?;
+                }
             }
             Err(e) => {
                 error!("Failed to create envelope '{}': {}", env_config.name, e);
@@ -228,3 +643,229 @@ async fn seed_envelopes(db: &DatabaseConnection) -> Result<(), Error> {
     info!("Envelope seeding complete");
     Ok(())
 }
+
+/// Reads the prefix for text commands (e.g. `!s`) from `COMMAND_PREFIX`, defaulting to `!`.
+fn command_prefix() -> String {
+    env::var("COMMAND_PREFIX")
+        .ok()
+        .map(|prefix| prefix.trim().to_string())
+        .filter(|prefix| !prefix.is_empty())
+        .unwrap_or_else(|| "!".to_string())
+}
+
+/// Reads the config.toml polling interval from `CONFIG_WATCH_INTERVAL_SECS`.
+///
+/// Returns `None` (watching disabled) when the variable is unset, invalid, or zero.
+fn config_watch_interval() -> Option<Duration> {
+    env::var("CONFIG_WATCH_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// Reads how many days of balance snapshots to keep from `SNAPSHOT_RETENTION_DAYS`.
+///
+/// Returns `None` (keep forever) when the variable is zero, and the default retention
+/// when it is unset or invalid.
+fn snapshot_retention_days() -> Option<u64> {
+    match env::var("SNAPSHOT_RETENTION_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+    {
+        Some(0) => None,
+        Some(days) => Some(days),
+        None => Some(core::snapshot::DEFAULT_RETENTION_DAYS),
+    }
+}
+
+/// Records balance snapshots at startup and then every night at local midnight, pruning
+/// snapshots older than the retention period after each run.
+async fn run_snapshot_job(db: DatabaseConnection, retention_days: Option<u64>) {
+    loop {
+        let now = chrono::Utc::now();
+        let today = local_date(&db, now).await;
+        match core::snapshot::record_snapshots(&db, today).await {
+            Ok(created) => info!("Recorded {} balance snapshot(s) for {}", created, today),
+            Err(e) => error!("Failed to record balance snapshots: {}", e),
+        }
+        if let Some(days) = retention_days {
+            match core::snapshot::prune_snapshots(&db, today, days).await {
+                Ok(0) => {}
+                Ok(pruned) => info!("Pruned {} old balance snapshot(s)", pruned),
+                Err(e) => error!("Failed to prune balance snapshots: {}", e),
+            }
+        }
+        if let Err(e) = core::diagnostics::record_job_run(&db, "balance_snapshots", now).await {
+            warn!("Failed to record balance snapshot run: {}", e);
+        }
+
+        tokio::time::sleep(until_next_midnight(&db).await).await;
+    }
+}
+
+/// Marks stale wishlist proposals and held large spends as expired once an hour,
+/// telling the members who made the spends.
+async fn run_wishlist_expiry(
+    db: DatabaseConnection,
+    http: std::sync::Arc<poise::serenity_prelude::Http>,
+) {
+    let mut ticker = tokio::time::interval(Duration::from_hours(1));
+    loop {
+        ticker.tick().await;
+        let now = chrono::Utc::now();
+        match core::wishlist::expire_stale_purchases(&db, now).await {
+            Ok(expired) if expired.is_empty() => {}
+            Ok(expired) => {
+                info!("Expired {} stale wishlist proposal(s)", expired.len());
+                bot::handlers::wishlist_buttons::announce_expired(&http, &db, &expired).await;
+            }
+            Err(e) => error!("Failed to expire wishlist proposals: {}", e),
+        }
+        if let Err(e) = core::diagnostics::record_job_run(&db, "wishlist_expiry", now).await {
+            warn!("Failed to record wishlist expiry run: {}", e);
+        }
+    }
+}
+
+/// Executes due scheduled spends at startup and then once an hour, posting a
+/// confirmation for each.
+async fn run_scheduled_spends(
+    db: DatabaseConnection,
+    http: std::sync::Arc<poise::serenity_prelude::Http>,
+) {
+    let mut ticker = tokio::time::interval(Duration::from_hours(1));
+    loop {
+        ticker.tick().await;
+        let now = chrono::Utc::now();
+        match core::schedule::run_due(&db, local_date(&db, now).await, now).await {
+            Ok(runs) if runs.is_empty() => {}
+            Ok(runs) => {
+                info!("Processed {} scheduled spend(s)", runs.len());
+                bot::handlers::scheduled::announce_runs(&http, &db, &runs).await;
+            }
+            Err(e) => error!("Failed to run scheduled spends: {}", e),
+        }
+        if let Err(e) = core::diagnostics::record_job_run(&db, "scheduled_spends", now).await {
+            warn!("Failed to record scheduled spend run: {}", e);
+        }
+    }
+}
+
+/// Posts reminders for bills coming due at startup and then once an hour.
+async fn run_bill_reminders(
+    db: DatabaseConnection,
+    http: std::sync::Arc<poise::serenity_prelude::Http>,
+) {
+    let mut ticker = tokio::time::interval(Duration::from_hours(1));
+    loop {
+        ticker.tick().await;
+        let now = chrono::Utc::now();
+        match core::bill::due_reminders(&db, local_date(&db, now).await).await {
+            Ok(reminders) if reminders.is_empty() => {}
+            Ok(reminders) => {
+                info!("Sending {} bill reminder(s)", reminders.len());
+                bot::handlers::bills::announce_reminders(&http, &db, &reminders, now).await;
+            }
+            Err(e) => error!("Failed to check bill reminders: {}", e),
+        }
+        if let Err(e) = core::diagnostics::record_job_run(&db, "bill_reminders", now).await {
+            warn!("Failed to record bill reminder run: {}", e);
+        }
+    }
+}
+
+/// The household's date at `now`, falling back to the UTC date if the timezone can't be read.
+async fn local_date(
+    db: &DatabaseConnection,
+    now: chrono::DateTime<chrono::Utc>,
+) -> chrono::NaiveDate {
+    core::calendar::local_date(db, now)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to read the household timezone: {}", e);
+            now.date_naive()
+        })
+}
+
+/// Time remaining until the next local midnight in the household timezone.
+async fn until_next_midnight(db: &DatabaseConnection) -> Duration {
+    let now = chrono::Utc::now();
+    let tz = core::calendar::get_timezone(db)
+        .await
+        .unwrap_or(core::calendar::DEFAULT_TIMEZONE);
+    let tomorrow = now.with_timezone(&tz).date_naive() + chrono::Days::new(1);
+    (core::calendar::start_of_day(tz, tomorrow) - now)
+        .to_std()
+        .unwrap_or(Duration::from_secs(24 * 60 * 60))
+}
+
+/// Returns the last modification time of config.toml, if it can be read.
+fn config_modified_time() -> Option<SystemTime> {
+    std::fs::metadata("config.toml")
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Hands a background job a handle on the same SQLite pool as `db`.
+///
+/// Every background task shares the bot's pool rather than opening its own, so the
+/// process holds one set of connections to the database file. `DatabaseConnection` is
+/// not `Clone` while sea-orm's `mock` feature is on (the tests need it), but the pool
+/// inside it is.
+fn share_connection(db: &DatabaseConnection) -> DatabaseConnection {
+    SqlxSqliteConnector::from_sqlx_sqlite_pool(db.get_sqlite_connection_pool().clone())
+}
+
+/// Checks every 30 seconds whether another process, such as `envelope-buddy admin`,
+/// wrote to the database, and drops the bot's cached rows if so.
+async fn watch_external_writes(db: DatabaseConnection) {
+    let mut seen = None;
+    let mut ticker = tokio::time::interval(Duration::from_secs(30));
+    loop {
+        ticker.tick().await;
+        match core::cache::check_external_writes(&db, seen.as_deref()).await {
+            Ok(stamp) => seen = stamp,
+            Err(e) => warn!("Failed to check for writes from other processes: {}", e),
+        }
+    }
+}
+
+/// Polls config.toml and applies changes with `core::config_sync` when it is modified.
+///
+/// This is the file-watcher counterpart to `/reload_config`; results are logged rather
+/// than posted to Discord.
+async fn watch_config(db: DatabaseConnection, interval: Duration) {
+    let mut last_modified = config_modified_time();
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let modified = config_modified_time();
+        if modified.is_none() || modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        let config = match config::envelopes::load_default_config() {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                warn!("config.toml changed but could not be loaded: {}", e);
+                continue;
+            }
+        };
+
+        match core::config_sync::sync_envelopes(&db, &config).await {
+            Ok(result) if result.is_empty() => {
+                info!("config.toml changed; envelopes already up to date");
+            }
+            Ok(result) => info!(
+                "Applied config.toml changes: {} created, {} updated",
+                result.created.len(),
+                result.updated.len()
+            ),
+            Err(e) => error!("Failed to apply config.toml changes: {}", e),
+        }
+    }
+}
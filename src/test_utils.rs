@@ -4,8 +4,8 @@
 //! and creating test entities with sensible defaults.
 
 use crate::{
-    core::{envelope, product, transaction},
-    entities,
+    core::{calendar, demo, envelope, product, transaction},
+    entities::{self, TransactionType},
     errors::Result,
 };
 use sea_orm::DatabaseConnection;
@@ -21,6 +21,18 @@ pub async fn setup_test_db() -> Result<DatabaseConnection> {
     Ok(db)
 }
 
+/// Creates an in-memory database holding the `--demo` household (see [`demo::seed`]),
+/// with spending up to today.
+///
+/// # Errors
+/// Returns an error if database setup or seeding fails.
+pub async fn setup_demo_db() -> Result<(DatabaseConnection, demo::DemoSummary)> {
+    let db = setup_test_db().await?;
+    let today = calendar::today(&db).await?;
+    let summary = demo::seed(&db, today).await?;
+    Ok((db, summary))
+}
+
 /// Creates a test envelope with sensible defaults.
 ///
 /// # Arguments
@@ -122,7 +134,7 @@ pub async fn create_custom_product(
 /// * `description`: `"Test transaction"`
 /// * `user_id`: `"test_user"`
 /// * `message_id`: None
-/// * `transaction_type`: "spend" (if negative) or "addfunds" (if positive)
+/// * `transaction_type`: spend (if negative) or add funds (if positive)
 ///
 /// # Errors
 /// Returns an error if transaction creation fails.
@@ -131,7 +143,11 @@ pub async fn create_test_transaction(
     envelope_id: i64,
     amount: f64,
 ) -> Result<entities::transaction::Model> {
-    let transaction_type = if amount < 0.0 { "spend" } else { "addfunds" };
+    let transaction_type = if amount < 0.0 {
+        TransactionType::Spend
+    } else {
+        TransactionType::AddFunds
+    };
 
     transaction::create_transaction(
         db,
@@ -140,7 +156,7 @@ pub async fn create_test_transaction(
         "Test transaction".to_string(),
         "test_user".to_string(),
         None,
-        transaction_type.to_string(),
+        transaction_type,
     )
     .await
 }
@@ -156,7 +172,7 @@ pub async fn create_custom_transaction(
     description: &str,
     user_id: &str,
     message_id: Option<String>,
-    transaction_type: &str,
+    transaction_type: TransactionType,
 ) -> Result<entities::transaction::Model> {
     transaction::create_transaction(
         db,
@@ -165,7 +181,7 @@ pub async fn create_custom_transaction(
         description.to_string(),
         user_id.to_string(),
         message_id,
-        transaction_type.to_string(),
+        transaction_type,
     )
     .await
 }
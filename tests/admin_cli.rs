@@ -0,0 +1,22 @@
+//! End-to-end checks of `envelope-buddy admin`, run against the binary.
+
+use envelope_buddy::config::envelopes::parse_config;
+use std::process::Command;
+
+#[test]
+fn test_admin_export_output_parses() -> Result<(), Box<dyn std::error::Error>> {
+    // Debug logging includes the SQL statements, which must not reach standard output
+    let output = Command::new(env!("CARGO_BIN_EXE_envelope-buddy"))
+        .args(["--demo", "admin", "export"])
+        .env("RUST_LOG", "debug")
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let config = parse_config(&String::from_utf8(output.stdout)?)?;
+    assert!(!config.envelopes.is_empty());
+    Ok(())
+}